@@ -23,7 +23,7 @@ use roc_mono::ir::OptLevel;
 use roc_packaging::cache::RocCacheDir;
 use roc_packaging::tarball::Compression;
 use roc_reporting::report::ANSI_STYLE_CODES;
-use roc_target::{Architecture, Target};
+use roc_target::{Architecture, Target, WasmFeatures};
 use std::env;
 use std::ffi::{CString, OsStr, OsString};
 use std::io;
@@ -51,10 +51,18 @@ pub const CMD_TEST: &str = "test";
 pub const CMD_GLUE: &str = "glue";
 pub const CMD_GEN_STUB_LIB: &str = "gen-stub-lib";
 pub const CMD_PREPROCESS_HOST: &str = "preprocess-host";
+pub const CMD_LINKER: &str = "linker";
+pub const CMD_INSPECT: &str = "inspect";
+pub const CMD_VENDOR: &str = "vendor";
+pub const CMD_CACHE: &str = "cache";
+pub const CMD_LIST: &str = "list";
+pub const CMD_VERIFY: &str = "verify";
+pub const CMD_PRUNE: &str = "prune";
 
 pub const FLAG_EMIT_LLVM_IR: &str = "emit-llvm-ir";
 pub const FLAG_PROFILING: &str = "profiling";
 pub const FLAG_BUNDLE: &str = "bundle";
+pub const FLAG_VERIFY: &str = "verify";
 pub const FLAG_DEV: &str = "dev";
 pub const FLAG_OPTIMIZE: &str = "optimize";
 pub const FLAG_MAX_THREADS: &str = "max-threads";
@@ -70,13 +78,19 @@ pub const FLAG_CHECK: &str = "check";
 pub const FLAG_STDIN: &str = "stdin";
 pub const FLAG_STDOUT: &str = "stdout";
 pub const FLAG_WASM_STACK_SIZE_KB: &str = "wasm-stack-size-kb";
+pub const FLAG_WASM_FEATURES: &str = "wasm-features";
 pub const FLAG_OUTPUT: &str = "output";
 pub const FLAG_FUZZ: &str = "fuzz";
 pub const FLAG_MAIN: &str = "main";
+pub const FLAG_APP: &str = "app";
+pub const FLAG_OLDER_THAN: &str = "older-than";
+pub const FLAG_UNUSED_BY: &str = "unused-by";
+pub const FLAG_LOCKED: &str = "locked";
 pub const ROC_FILE: &str = "ROC_FILE";
 pub const ROC_DIR: &str = "ROC_DIR";
 pub const GLUE_DIR: &str = "GLUE_DIR";
 pub const GLUE_SPEC: &str = "GLUE_SPEC";
+pub const HOST_FILE: &str = "HOST_FILE";
 pub const DIRECTORY_OR_FILES: &str = "DIRECTORY_OR_FILES";
 pub const ARGS_FOR_APP: &str = "ARGS_FOR_APP";
 
@@ -144,6 +158,12 @@ pub fn build_app() -> Command {
         .value_parser(value_parser!(u32))
         .required(false);
 
+    let flag_wasm_features = Arg::new(FLAG_WASM_FEATURES)
+        .long(FLAG_WASM_FEATURES)
+        .help("Comma-separated Wasm proposals the wasm32 backend may use: bulk-memory, multi-value, tail-call, or all\n(This only applies when --dev also provided.)")
+        .value_parser(|s: &str| s.parse::<WasmFeatures>().map_err(|e| e.to_string()))
+        .required(false);
+
     let flag_fuzz = Arg::new(FLAG_FUZZ)
         .long(FLAG_FUZZ)
         .help("Instrument the roc binary for fuzzing with roc-fuzz")
@@ -156,6 +176,12 @@ pub fn build_app() -> Command {
         .value_parser(value_parser!(PathBuf))
         .required(false);
 
+    let flag_locked = Arg::new(FLAG_LOCKED)
+        .long(FLAG_LOCKED)
+        .help("Fail if the packages being used (including the packages they use) don't match roc.lock, instead of updating it")
+        .action(ArgAction::SetTrue)
+        .required(false);
+
     let roc_file_to_run = Arg::new(ROC_FILE)
         .help("The .roc file of an app to run")
         .value_parser(value_parser!(PathBuf))
@@ -193,7 +219,9 @@ pub fn build_app() -> Command {
             .arg(flag_linker.clone())
             .arg(flag_prebuilt.clone())
             .arg(flag_fuzz.clone())
+            .arg(flag_locked.clone())
             .arg(flag_wasm_stack_size_kb)
+            .arg(flag_wasm_features)
             .arg(
                 Arg::new(FLAG_TARGET)
                     .long(FLAG_TARGET)
@@ -217,6 +245,14 @@ pub fn build_app() -> Command {
                     .value_parser([".tar", ".tar.gz", ".tar.br"])
                     .required(false),
             )
+            .arg(
+                Arg::new(FLAG_VERIFY)
+                    .long(FLAG_VERIFY)
+                    .help("Instead of writing an archive, rebuild it and check that it matches a published package URL (or just its hash).")
+                    .requires(FLAG_BUNDLE)
+                    .value_name("URL")
+                    .required(false),
+            )
             .arg(
                 Arg::new(FLAG_NO_LINK)
                     .long(FLAG_NO_LINK)
@@ -332,6 +368,7 @@ pub fn build_app() -> Command {
             .arg(flag_main.clone())
             .arg(flag_time.clone())
             .arg(flag_max_threads.clone())
+            .arg(flag_locked)
             .arg(
                 Arg::new(ROC_FILE)
                     .help("The .roc file to check")
@@ -357,6 +394,52 @@ pub fn build_app() -> Command {
                     .default_value(DEFAULT_ROC_FILENAME),
                 )
         )
+        .subcommand(
+            Command::new(CMD_VENDOR)
+                .about("Copy the tarballs of all the packages an app or package depends on into a directory, for building without network access")
+                .arg(Arg::new(FLAG_OUTPUT)
+                    .long(FLAG_OUTPUT)
+                    .help("Directory to copy the tarballs into\n(Defaults to a `vendor` directory next to the .roc file.)")
+                    .value_parser(value_parser!(PathBuf))
+                    .required(false),
+                )
+                .arg(Arg::new(ROC_FILE)
+                    .help("The .roc file whose dependencies should be vendored")
+                    .value_parser(value_parser!(PathBuf))
+                    .required(false)
+                    .default_value(DEFAULT_ROC_FILENAME),
+                )
+                .after_help("To build using only the vendored tarballs, set the ROC_PACKAGE_MIRROR environment\nvariable to the file:// URL of the directory they were copied into.")
+        )
+        .subcommand(Command::new(CMD_CACHE)
+            .about("Inspect and clean up the cache of downloaded packages")
+            .subcommand_required(true)
+            .subcommand(Command::new(CMD_LIST)
+                .about("List the cached packages, with their URLs, sizes, and when they were last used")
+            )
+            .subcommand(Command::new(CMD_VERIFY)
+                .about("Re-hash each cached package's files to check that they haven't changed since it was downloaded")
+            )
+            .subcommand(Command::new(CMD_PRUNE)
+                .about("Remove cached packages, which will be downloaded again if they're needed later")
+                .arg(
+                    Arg::new(FLAG_OLDER_THAN)
+                        .long(FLAG_OLDER_THAN)
+                        .help("Only remove packages that haven't been used in this many days")
+                        .value_name("DAYS")
+                        .value_parser(value_parser!(u64))
+                        .required_unless_present(FLAG_UNUSED_BY),
+                )
+                .arg(
+                    Arg::new(FLAG_UNUSED_BY)
+                        .long(FLAG_UNUSED_BY)
+                        .help("Only remove packages that this .roc file doesn't use\n(Can be given more than once.)")
+                        .value_parser(value_parser!(PathBuf))
+                        .action(ArgAction::Append)
+                        .required(false),
+                )
+            )
+        )
         .subcommand(Command::new(CMD_GLUE)
             .about("Generate glue code between a platform's Roc API and its host language")
             .arg(&flag_dev)
@@ -410,10 +493,38 @@ pub fn build_app() -> Command {
                     .long(FLAG_TARGET)
                     .help("Choose a different target")
                     .default_value(Into::<&'static str>::into(Target::default()))
-                    .value_parser(build_target_values_parser)
+                    .value_parser(build_target_values_parser.clone())
                     .required(false),
             )
         )
+        .subcommand(Command::new(CMD_LINKER)
+            .about("Diagnose problems with the surgical linker")
+            .subcommand_required(true)
+            .subcommand(Command::new(CMD_INSPECT)
+                .about("Report the Roc symbols a host expects from the app, and where it refers to them.\nAccepts a host executable, or a preprocessed `.rh` host next to its metadata.")
+                .arg(
+                    Arg::new(HOST_FILE)
+                        .help("The host executable or `.rh` file")
+                        .value_parser(value_parser!(PathBuf))
+                        .required(true)
+                )
+                .arg(
+                    Arg::new(FLAG_APP)
+                        .long(FLAG_APP)
+                        .help("A .roc app to check against the symbols the host expects")
+                        .value_parser(value_parser!(PathBuf))
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(FLAG_TARGET)
+                        .long(FLAG_TARGET)
+                        .help("Choose a different target")
+                        .default_value(Into::<&'static str>::into(Target::default()))
+                        .value_parser(build_target_values_parser)
+                        .required(false),
+                )
+            )
+        )
         .arg(flag_optimize)
         .arg(flag_max_threads)
         .arg(flag_opt_size)
@@ -541,7 +652,9 @@ pub fn test(matches: &ArgMatches, target: Target) -> io::Result<i32> {
     let interns = loaded.interns.clone();
     let sources = loaded.sources.clone();
 
-    let (dyn_lib, expects_by_module, layout_interner) =
+    let (dyn_lib, expects_by_module, layout_interner) = if matches.get_flag(FLAG_DEV) {
+        roc_repl_expect::run::expect_mono_module_to_dylib_asm(arena, target, loaded)
+    } else {
         roc_repl_expect::run::expect_mono_module_to_dylib(
             arena,
             target,
@@ -549,7 +662,8 @@ pub fn test(matches: &ArgMatches, target: Target) -> io::Result<i32> {
             opt_level,
             LlvmBackendMode::CliTest,
         )
-        .unwrap();
+    }
+    .unwrap();
 
     // Print warnings before running tests.
     {
@@ -736,6 +850,35 @@ pub fn build(
         }
 
         if config == BuildConfig::BuildOnly && matches.contains_id(FLAG_BUNDLE) {
+            if let Some(published) = matches.get_one::<String>(FLAG_VERIFY) {
+                // The hash is of the uncompressed archive, so compression doesn't matter here.
+                let expected = roc_packaging::tarball::published_hash(published);
+                let actual = match roc_packaging::tarball::rebuilt_hash(path) {
+                    Ok(hash) => hash,
+                    Err(err) => {
+                        eprintln!("\nUnable to bundle {}: {err}\n", path.to_string_lossy());
+
+                        return Ok(1);
+                    }
+                };
+
+                return if actual == expected {
+                    println!(
+                        "\n\x1B[32mVerified:\x1B[39m bundling {} gives the published hash \x1B[33m{actual}\x1B[39m\n",
+                        path.to_string_lossy()
+                    );
+
+                    Ok(0)
+                } else {
+                    eprintln!(
+                        "\n\x1B[31mVerification failed:\x1B[39m bundling {} gives the hash \x1B[33m{actual}\x1B[39m, but the published hash is \x1B[33m{expected}\x1B[39m\n",
+                        path.to_string_lossy()
+                    );
+
+                    Ok(1)
+                };
+            }
+
             let start_time = Instant::now();
 
             let compression =
@@ -760,7 +903,14 @@ pub fn build(
 
             // Rather than building an executable or library, we're building
             // a tarball so this code can be distributed via HTTPS
-            let filename = roc_packaging::tarball::build(path, compression)?;
+            let filename = match roc_packaging::tarball::build(path, compression) {
+                Ok(filename) => filename,
+                Err(err) => {
+                    eprintln!("\nUnable to bundle {}: {err}\n", path.to_string_lossy());
+
+                    return Ok(1);
+                }
+            };
             let total_time_ms = start_time.elapsed().as_millis();
             let total_time = if total_time_ms > 1000 {
                 format!("{}s {}ms", total_time_ms / 1000, total_time_ms % 1000)
@@ -857,6 +1007,13 @@ pub fn build(
         .flatten()
         .map(|x| x * 1024);
 
+    let wasm_features = matches
+        .try_get_one::<WasmFeatures>(FLAG_WASM_FEATURES)
+        .ok()
+        .flatten()
+        .copied()
+        .unwrap_or_default();
+
     let build_ordering = match config {
         BuildAndRunIfNoErrors => BuildOrdering::BuildIfChecks,
         _ => BuildOrdering::AlwaysBuild,
@@ -868,6 +1025,7 @@ pub fn build(
         emit_debug_info,
         emit_llvm_ir,
         fuzz,
+        wasm_features,
     };
 
     let load_config = standard_load_config(target, build_ordering, threading);
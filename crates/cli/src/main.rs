@@ -3,11 +3,12 @@ use bumpalo::Bump;
 use roc_build::link::LinkType;
 use roc_build::program::{check_file, CodeGenBackend};
 use roc_cli::{
-    build_app, format_files, format_src, test, BuildConfig, FormatMode, CMD_BUILD, CMD_CHECK,
-    CMD_DEV, CMD_DOCS, CMD_FORMAT, CMD_GEN_STUB_LIB, CMD_GLUE, CMD_PREPROCESS_HOST, CMD_REPL,
-    CMD_RUN, CMD_TEST, CMD_VERSION, DIRECTORY_OR_FILES, FLAG_CHECK, FLAG_DEV, FLAG_LIB, FLAG_MAIN,
-    FLAG_NO_LINK, FLAG_OUTPUT, FLAG_STDIN, FLAG_STDOUT, FLAG_TARGET, FLAG_TIME, GLUE_DIR,
-    GLUE_SPEC, ROC_FILE,
+    build_app, format_files, format_src, test, BuildConfig, FormatMode, CMD_BUILD, CMD_CACHE,
+    CMD_CHECK, CMD_DEV, CMD_DOCS, CMD_FORMAT, CMD_GEN_STUB_LIB, CMD_GLUE, CMD_INSPECT, CMD_LINKER,
+    CMD_LIST, CMD_PREPROCESS_HOST, CMD_PRUNE, CMD_REPL, CMD_RUN, CMD_TEST, CMD_VENDOR, CMD_VERIFY,
+    CMD_VERSION, DIRECTORY_OR_FILES, FLAG_APP, FLAG_BUNDLE, FLAG_CHECK, FLAG_DEV, FLAG_LIB,
+    FLAG_LOCKED, FLAG_MAIN, FLAG_NO_LINK, FLAG_OLDER_THAN, FLAG_OUTPUT, FLAG_STDIN, FLAG_STDOUT,
+    FLAG_TARGET, FLAG_TIME, FLAG_UNUSED_BY, GLUE_DIR, GLUE_SPEC, HOST_FILE, ROC_FILE,
 };
 use roc_docs::generate_docs_html;
 use roc_error_macros::user_error;
@@ -15,11 +16,13 @@ use roc_gen_dev::AssemblyBackendMode;
 use roc_gen_llvm::llvm::build::LlvmBackendMode;
 use roc_load::{FunctionKind, LoadingProblem, Threading};
 use roc_packaging::cache::{self, RocCacheDir};
+use roc_packaging::{lockfile, vendor};
 use roc_target::Target;
 use std::fs::{self, FileType};
 use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::{Duration, SystemTime};
 use target_lexicon::Triple;
 
 #[macro_use]
@@ -163,6 +166,38 @@ fn main() -> io::Result<()> {
             );
             Ok(0)
         }
+        Some((CMD_LINKER, matches)) => match matches.subcommand() {
+            Some((CMD_INSPECT, matches)) => {
+                let host_path = matches.get_one::<PathBuf>(HOST_FILE).unwrap();
+                let report = roc_linker::inspect_host(host_path);
+                print!("{report}");
+
+                match matches.get_one::<PathBuf>(FLAG_APP) {
+                    Some(app_path) => {
+                        let target = matches
+                            .get_one::<String>(FLAG_TARGET)
+                            .and_then(|s| Target::from_str(s).ok())
+                            .unwrap_or_default();
+
+                        let (_, exposed_symbols) = roc_linker::load_exposed_symbols(
+                            app_path,
+                            RocCacheDir::Persistent(cache::roc_cache_dir().as_path()),
+                            target,
+                            FunctionKind::LambdaSet,
+                        );
+
+                        let mismatches = report.mismatches(&exposed_symbols);
+                        println!();
+                        print!("{mismatches}");
+
+                        // Only symbols the app is missing make linking fail.
+                        Ok(i32::from(!mismatches.missing_from_app.is_empty()))
+                    }
+                    None => Ok(0),
+                }
+            }
+            _ => unreachable!(),
+        },
         Some((CMD_BUILD, matches)) => {
             let target = matches
                 .get_one::<String>(FLAG_TARGET)
@@ -177,16 +212,33 @@ fn main() -> io::Result<()> {
             let out_path = matches
                 .get_one::<OsString>(FLAG_OUTPUT)
                 .map(OsString::as_ref);
-
-            Ok(build(
-                matches,
-                &subcommands,
-                BuildConfig::BuildOnly,
-                target,
-                out_path,
-                RocCacheDir::Persistent(cache::roc_cache_dir().as_path()),
-                link_type,
-            )?)
+            let roc_file_path = matches.get_one::<PathBuf>(ROC_FILE).unwrap();
+            let cache_dir = cache::roc_cache_dir();
+            // Bundling doesn't resolve any packages, so there's nothing to lock.
+            let uses_lockfile = !matches.contains_id(FLAG_BUNDLE);
+            let locked = matches.get_flag(FLAG_LOCKED);
+
+            match package_lock(roc_file_path, uses_lockfile && locked) {
+                Err(exit_code) => Ok(exit_code),
+                Ok(lock) => {
+                    let exit_code = build(
+                        matches,
+                        &subcommands,
+                        BuildConfig::BuildOnly,
+                        target,
+                        out_path,
+                        RocCacheDir::WithLock(cache_dir.as_path(), &lock),
+                        link_type,
+                    )?;
+
+                    // Only errors (exit code 1) could mean that some packages failed to load.
+                    if uses_lockfile && exit_code != 1 {
+                        Ok(update_lockfile(roc_file_path, &lock, locked).unwrap_or(exit_code))
+                    } else {
+                        Ok(exit_code)
+                    }
+                }
+            }
         }
         Some((CMD_CHECK, matches)) => {
             let arena = Bump::new();
@@ -201,28 +253,38 @@ fn main() -> io::Result<()> {
             };
 
             let opt_main_path = matches.get_one::<PathBuf>(FLAG_MAIN);
+            // The lockfile is for the main module, whose packages are the ones being used.
+            let lockfile_root = opt_main_path.unwrap_or(roc_file_path);
+            let cache_dir = cache::roc_cache_dir();
+            let locked = matches.get_flag(FLAG_LOCKED);
+
+            match package_lock(lockfile_root, locked) {
+                Err(exit_code) => Ok(exit_code),
+                Ok(lock) => match check_file(
+                    &arena,
+                    roc_file_path.to_owned(),
+                    opt_main_path.cloned(),
+                    emit_timings,
+                    RocCacheDir::WithLock(cache_dir.as_path(), &lock),
+                    threading,
+                ) {
+                    Ok((problems, total_time)) => {
+                        problems.print_error_warning_count(total_time);
+
+                        // Every package loaded, even if there were problems in the code.
+                        Ok(update_lockfile(lockfile_root, &lock, locked)
+                            .unwrap_or(problems.exit_code()))
+                    }
 
-            match check_file(
-                &arena,
-                roc_file_path.to_owned(),
-                opt_main_path.cloned(),
-                emit_timings,
-                RocCacheDir::Persistent(cache::roc_cache_dir().as_path()),
-                threading,
-            ) {
-                Ok((problems, total_time)) => {
-                    problems.print_error_warning_count(total_time);
-                    Ok(problems.exit_code())
-                }
-
-                Err(LoadingProblem::FormattedReport(report)) => {
-                    print!("{report}");
+                    Err(LoadingProblem::FormattedReport(report)) => {
+                        print!("{report}");
 
-                    Ok(1)
-                }
-                Err(other) => {
-                    panic!("build_file failed with error:\n{other:?}");
-                }
+                        Ok(1)
+                    }
+                    Err(other) => {
+                        panic!("build_file failed with error:\n{other:?}");
+                    }
+                },
             }
         }
         Some((CMD_REPL, _)) => Ok(roc_repl_cli::main()),
@@ -234,6 +296,133 @@ fn main() -> io::Result<()> {
 
             Ok(0)
         }
+        Some((CMD_VENDOR, matches)) => {
+            let root_path = matches.get_one::<PathBuf>(ROC_FILE).unwrap();
+            let vendor_dir = match matches.get_one::<PathBuf>(FLAG_OUTPUT) {
+                Some(dir) => dir.to_owned(),
+                None => root_path.with_file_name("vendor"),
+            };
+            let mirror = vendor::mirror();
+
+            match vendor::vendor(root_path, &vendor_dir, mirror.as_deref()) {
+                Ok(urls) => {
+                    let vendor_dir = vendor_dir.canonicalize().unwrap_or(vendor_dir);
+
+                    println!(
+                        "Vendored {} package(s) into {}\n",
+                        urls.len(),
+                        vendor_dir.display(),
+                    );
+
+                    if let Ok(mirror) = vendor::file_url(&vendor_dir) {
+                        println!(
+                            "To build with them instead of downloading, set:\n\n    {}={mirror}\n",
+                            vendor::ROC_PACKAGE_MIRROR,
+                        );
+                    }
+
+                    Ok(0)
+                }
+                Err((url, problem)) => {
+                    let report = roc_reporting::report::to_https_problem_report_string(
+                        &url,
+                        problem,
+                        root_path.to_owned(),
+                    );
+                    eprintln!("{report}");
+
+                    Ok(1)
+                }
+            }
+        }
+        Some((CMD_CACHE, matches)) => {
+            let cache_dir = cache::roc_cache_dir();
+            let result = match matches.subcommand() {
+                Some((CMD_LIST, _)) => cache::cached_packages(&cache_dir).map(|mut packages| {
+                    packages.sort_by_key(|package| std::cmp::Reverse(package.last_used));
+
+                    for package in packages.iter() {
+                        println!(
+                            "{}\n    hash: {}\n    size: {}\n    last used: {}\n",
+                            package_label(package),
+                            package.content_hash,
+                            format_size(package.size),
+                            format_last_used(package.last_used)
+                        );
+                    }
+
+                    println!("{} package(s) in {}", packages.len(), cache_dir.display());
+
+                    0
+                }),
+                Some((CMD_VERIFY, _)) => cache::cached_packages(&cache_dir).and_then(|packages| {
+                    let mut modified = 0;
+
+                    for package in packages.iter() {
+                        match cache::verify(package)? {
+                            cache::Verification::Verified => {
+                                println!("Verified {}", package_label(package));
+                            }
+                            cache::Verification::Modified => {
+                                modified += 1;
+                                println!(
+                                    "\u{001b}[31mModified\u{001b}[0m {}\n    Its files in {} no longer match what was downloaded. Delete that directory to download it again.",
+                                    package_label(package),
+                                    package.dir.display()
+                                );
+                            }
+                            cache::Verification::Unverifiable => {
+                                println!(
+                                    "\u{001b}[33mUnverifiable\u{001b}[0m {}\n    It was downloaded by an older version of roc. Delete {} to download it again, so it can be verified.",
+                                    package_label(package),
+                                    package.dir.display()
+                                );
+                            }
+                        }
+                    }
+
+                    Ok(i32::from(modified > 0))
+                }),
+                Some((CMD_PRUNE, matches)) => {
+                    let older_than = matches
+                        .get_one::<u64>(FLAG_OLDER_THAN)
+                        .map(|days| Duration::from_secs(days * 24 * 60 * 60));
+                    let used_by: Vec<PathBuf> = matches
+                        .get_many::<PathBuf>(FLAG_UNUSED_BY)
+                        .map(|paths| paths.cloned().collect())
+                        .unwrap_or_default();
+
+                    cache::prune(&cache_dir, older_than, &used_by).map(|removed| {
+                        let freed: u64 = removed.iter().map(|package| package.size).sum();
+
+                        for package in removed.iter() {
+                            println!("Removed {}", package_label(package));
+                        }
+
+                        println!(
+                            "Removed {} package(s), freeing {}",
+                            removed.len(),
+                            format_size(freed)
+                        );
+
+                        0
+                    })
+                }
+                _ => unreachable!(),
+            };
+
+            match result {
+                Ok(exit_code) => Ok(exit_code),
+                Err(err) => {
+                    eprintln!(
+                        "\nUnable to read the package cache in {}: {err}\n",
+                        cache_dir.display()
+                    );
+
+                    Ok(1)
+                }
+            }
+        }
         Some((CMD_FORMAT, matches)) => {
             let from_stdin = matches.get_flag(FLAG_STDIN);
             let to_stdout = matches.get_flag(FLAG_STDOUT);
@@ -354,6 +543,70 @@ fn main() -> io::Result<()> {
     std::process::exit(exit_code);
 }
 
+/// What records the packages that get loaded, for the lockfile. With `--locked`, it also keeps
+/// anything outside the lockfile from being downloaded. Returns an exit code if there's no lockfile.
+fn package_lock(root_module: &Path, locked: bool) -> Result<lockfile::PackageLock, i32> {
+    if !locked {
+        return Ok(lockfile::PackageLock::unlocked());
+    }
+
+    lockfile::PackageLock::locked(root_module).map_err(|problem| {
+        eprintln!("\n{problem}\n");
+
+        1
+    })
+}
+
+/// Once every package has loaded, write the lockfile (or with `--locked`, check it).
+/// Returns an exit code if that failed.
+fn update_lockfile(root_module: &Path, lock: &lockfile::PackageLock, locked: bool) -> Option<i32> {
+    match lockfile::update(root_module, lock, locked) {
+        Ok(()) => None,
+        Err(problem) => {
+            eprintln!("\n{problem}\n");
+
+            Some(1)
+        }
+    }
+}
+
+/// The package's URL, or its hash if the URL wasn't recorded.
+fn package_label(package: &cache::CachedPackage) -> &str {
+    package.url.as_deref().unwrap_or(&package.content_hash)
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    if bytes < 1000 {
+        return format!("{bytes} B");
+    }
+
+    let mut size = bytes as f64 / 1000.0;
+    let mut unit = 0;
+
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+fn format_last_used(last_used: SystemTime) -> String {
+    let days = SystemTime::now()
+        .duration_since(last_used)
+        .unwrap_or_default()
+        .as_secs()
+        / (24 * 60 * 60);
+
+    match days {
+        0 => "today".to_string(),
+        1 => "yesterday".to_string(),
+        _ => format!("{days} days ago"),
+    }
+}
+
 fn read_all_roc_files(
     dir: &OsString,
     roc_file_paths: &mut Vec<OsString>,
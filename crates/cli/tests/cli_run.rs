@@ -54,6 +54,7 @@ mod cli_run {
     const OPTIMIZE_FLAG: &str = concatcp!("--", roc_cli::FLAG_OPTIMIZE);
     const LINKER_FLAG: &str = concatcp!("--", roc_cli::FLAG_LINKER);
     const CHECK_FLAG: &str = concatcp!("--", roc_cli::FLAG_CHECK);
+    const DEV_FLAG: &str = concatcp!("--", roc_cli::FLAG_DEV);
     #[allow(dead_code)]
     const PREBUILT_PLATFORM: &str = concatcp!("--", roc_cli::FLAG_PREBUILT);
     #[allow(dead_code)]
@@ -645,6 +646,82 @@ mod cli_run {
             UseValgrind::Yes,
             TestCliCommands::Test,
         );
+
+        // the dev backend clones the lookups of a failed expect itself
+        check_output_with_stdin(
+            &file_path_from_root("crates/cli/tests/expects", "expectLookups.roc"),
+            &[],
+            &[DEV_FLAG],
+            &[],
+            &[],
+            indoc!(
+                r#"
+                ── EXPECT FAILED in tests/expects/expectLookups.roc ────────────────────────────
+
+                This expectation failed:
+
+                10│      expect name == ""
+                                ^^^^^^^^^^
+
+                When it failed, these variables had these values:
+
+                name : Str
+                name = "Astra mortemque praestare gradatim"
+
+                ── EXPECT FAILED in tests/expects/expectLookups.roc ────────────────────────────
+
+                This expectation failed:
+
+                13│      expect List.isEmpty names
+                                ^^^^^^^^^^^^^^^^^^
+
+                When it failed, these variables had these values:
+
+                names : List Str
+                names = ["Profundum et fundamentum", "gradatim"]
+
+                ── EXPECT FAILED in tests/expects/expectLookups.roc ────────────────────────────
+
+                This expectation failed:
+
+                17│      expect Result.isOk result
+                                ^^^^^^^^^^^^^^^^^^
+
+                When it failed, these variables had these values:
+
+                result : Result (List U8) Str
+                result = Err "Astra mortemque praestare gradatim"
+
+                ── EXPECT FAILED in tests/expects/expectLookups.roc ────────────────────────────
+
+                This expectation failed:
+
+                21│      expect tree == Leaf
+                                ^^^^^^^^^^^^
+
+                When it failed, these variables had these values:
+
+                tree : Tree
+                tree = Node (Node Leaf "Profundum et fundamentum" Leaf) "gradatim" Leaf
+
+                ── EXPECT FAILED in tests/expects/expectLookups.roc ────────────────────────────
+
+                This expectation failed:
+
+                24│      expect Box.unbox boxed == []
+                                ^^^^^^^^^^^^^^^^^^^^^
+
+                When it failed, these variables had these values:
+
+                boxed : Box (List Str)
+                boxed = Box.box ["Profundum et fundamentum", "gradatim"]
+
+                Program finished!
+                "#
+            ),
+            UseValgrind::Yes,
+            TestCliCommands::Dev,
+        );
     }
 
     #[test]
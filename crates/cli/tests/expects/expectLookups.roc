@@ -0,0 +1,26 @@
+app "expect-lookups-test"
+    packages { pf: "zig-platform/main.roc" }
+    imports []
+    provides [main] to pf
+
+Tree : [Leaf, Node Tree Str Tree]
+
+main =
+    name = Str.concat "Astra mortemque praestare " "gradatim"
+    expect name == ""
+
+    names = ["Profundum et fundamentum", "gradatim"]
+    expect List.isEmpty names
+
+    result : Result (List U8) Str
+    result = Err "Astra mortemque praestare gradatim"
+    expect Result.isOk result
+
+    tree : Tree
+    tree = Node (Node Leaf "Profundum et fundamentum" Leaf) "gradatim" Leaf
+    expect tree == Leaf
+
+    boxed = Box.box names
+    expect Box.unbox boxed == []
+
+    "Program finished!\n"
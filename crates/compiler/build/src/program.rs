@@ -17,7 +17,7 @@ use roc_reporting::{
     cli::{report_problems, Problems},
     report::{RenderTarget, DEFAULT_PALETTE},
 };
-use roc_target::{Architecture, Target};
+use roc_target::{Architecture, Target, WasmFeatures};
 use std::ffi::OsStr;
 use std::ops::Deref;
 use std::{
@@ -86,6 +86,8 @@ pub struct CodeGenOptions {
     pub emit_debug_info: bool,
     pub emit_llvm_ir: bool,
     pub fuzz: bool,
+    /// Wasm proposals the dev backend may use, beyond the MVP
+    pub wasm_features: WasmFeatures,
 }
 
 type GenFromMono<'a> = (CodeObject, CodeGenTiming, ExpectMetadata<'a>);
@@ -114,6 +116,8 @@ pub fn gen_from_mono_module<'a>(
             preprocessed_host_path,
             wasm_dev_stack_bytes,
             AssemblyBackendMode::Binary, // dummy value, unused in practice
+            debug,
+            code_gen_options.wasm_features,
         ),
         CodeGenBackend::Assembly(backend_mode) => gen_from_mono_module_dev(
             arena,
@@ -122,6 +126,8 @@ pub fn gen_from_mono_module<'a>(
             preprocessed_host_path,
             wasm_dev_stack_bytes,
             backend_mode,
+            debug,
+            code_gen_options.wasm_features,
         ),
         CodeGenBackend::Llvm(backend_mode) => gen_from_mono_module_llvm(
             arena,
@@ -433,6 +439,8 @@ fn gen_from_mono_module_dev<'a>(
     preprocessed_host_path: &Path,
     wasm_dev_stack_bytes: Option<u32>,
     backend_mode: AssemblyBackendMode,
+    emit_debug_info: bool,
+    wasm_features: WasmFeatures,
 ) -> GenFromMono<'a> {
     match target.architecture() {
         Architecture::Wasm32 => gen_from_mono_module_dev_wasm32(
@@ -440,9 +448,11 @@ fn gen_from_mono_module_dev<'a>(
             loaded,
             preprocessed_host_path,
             wasm_dev_stack_bytes,
+            emit_debug_info,
+            wasm_features,
         ),
         Architecture::X86_64 | Architecture::Aarch64 => {
-            gen_from_mono_module_dev_assembly(arena, loaded, target, backend_mode)
+            gen_from_mono_module_dev_assembly(arena, loaded, target, backend_mode, emit_debug_info)
         }
         _ => todo!(),
     }
@@ -456,10 +466,12 @@ pub fn gen_from_mono_module_dev<'a>(
     _host_input_path: &Path,
     _wasm_dev_stack_bytes: Option<u32>,
     backend_mode: AssemblyBackendMode,
+    emit_debug_info: bool,
+    _wasm_features: WasmFeatures,
 ) -> GenFromMono<'a> {
     match target.architecture() {
         Architecture::X86_64 | Architecture::Aarch64 => {
-            gen_from_mono_module_dev_assembly(arena, loaded, target, backend_mode)
+            gen_from_mono_module_dev_assembly(arena, loaded, target, backend_mode, emit_debug_info)
         }
         _ => todo!(),
     }
@@ -471,6 +483,8 @@ fn gen_from_mono_module_dev_wasm32<'a>(
     loaded: MonomorphizedModule<'a>,
     preprocessed_host_path: &Path,
     wasm_dev_stack_bytes: Option<u32>,
+    emit_debug_info: bool,
+    wasm_features: WasmFeatures,
 ) -> GenFromMono<'a> {
     let all_code_gen_start = Instant::now();
     let MonomorphizedModule {
@@ -478,9 +492,19 @@ fn gen_from_mono_module_dev_wasm32<'a>(
         procedures,
         mut interns,
         mut layout_interner,
+        sources,
+        def_regions,
+        stmt_regions,
         ..
     } = loaded;
 
+    // Browser devtools use this to step through Roc source
+    let source_info = emit_debug_info.then(|| roc_mono::ir::SourceInfo {
+        sources: arena.alloc(sources),
+        def_regions: arena.alloc(def_regions),
+        stmt_regions: arena.alloc(stmt_regions),
+    });
+
     let exposed_to_host = loaded
         .exposed_to_host
         .top_level_values
@@ -493,6 +517,8 @@ fn gen_from_mono_module_dev_wasm32<'a>(
         module_id,
         exposed_to_host,
         stack_bytes: wasm_dev_stack_bytes.unwrap_or(roc_gen_wasm::Env::DEFAULT_STACK_BYTES),
+        source_info,
+        features: wasm_features,
     };
 
     let host_bytes = std::fs::read(preprocessed_host_path).unwrap_or_else(|_| {
@@ -544,6 +570,7 @@ fn gen_from_mono_module_dev_assembly<'a>(
     loaded: MonomorphizedModule<'a>,
     target: Target,
     backend_mode: AssemblyBackendMode,
+    emit_debug_info: bool,
 ) -> GenFromMono<'a> {
     let all_code_gen_start = Instant::now();
 
@@ -555,15 +582,27 @@ fn gen_from_mono_module_dev_assembly<'a>(
         mut interns,
         exposed_to_host,
         mut layout_interner,
+        sources,
+        def_regions,
+        stmt_regions,
         ..
     } = loaded;
 
+    // Debuggers and profilers use this to map machine code back to Roc source
+    let source_info = emit_debug_info.then(|| roc_mono::ir::SourceInfo {
+        sources: arena.alloc(sources),
+        def_regions: arena.alloc(def_regions),
+        stmt_regions: arena.alloc(stmt_regions),
+    });
+
     let env = roc_gen_dev::Env {
         arena,
         module_id,
         exposed_to_host: exposed_to_host.top_level_values.keys().copied().collect(),
         lazy_literals,
+        fold_constants: true,
         mode: backend_mode,
+        source_info,
     };
 
     let module_object =
@@ -1282,6 +1321,7 @@ pub fn build_str_test<'a>(
         emit_debug_info: false,
         emit_llvm_ir: false,
         fuzz: false,
+        wasm_features: WasmFeatures::default(),
     };
 
     let emit_timings = false;
@@ -1017,6 +1017,33 @@ pub fn listAllocationPtr(
     return list.getAllocationPtr();
 }
 
+/// Copies a list whose elements contain no pointers to `ptr[offset..]`, with the elements
+/// at `extra_offset` and the list's pointer replaced by that offset.
+pub fn listCloneTo(
+    list: RocList,
+    element_width: usize,
+    ptr: [*]u8,
+    offset: usize,
+    extra_offset: usize,
+) callconv(.C) usize {
+    const WIDTH: usize = @sizeOf(RocList);
+    const elements_width = list.len() * element_width;
+
+    // we only copy the elements we actually have (and skip extra capacity)
+    var relative = list;
+    relative.bytes = @as(?[*]u8, @ptrFromInt(extra_offset));
+    relative.capacity_or_alloc_ptr = list.len();
+
+    const array = @as([WIDTH]u8, @bitCast(relative));
+    @memcpy(ptr[offset..(offset + WIDTH)], array[0..WIDTH]);
+
+    if (list.bytes) |source| {
+        @memcpy(ptr[extra_offset..(extra_offset + elements_width)], source[0..elements_width]);
+    }
+
+    return extra_offset + elements_width;
+}
+
 test "listConcat: non-unique with unique overlapping" {
     var nonUnique = RocList.fromSlice(u8, ([_]u8{1})[0..]);
     var bytes: [*]u8 = @as([*]u8, @ptrCast(nonUnique.bytes));
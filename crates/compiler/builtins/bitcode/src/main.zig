@@ -84,6 +84,7 @@ comptime {
     exportListFn(list.listClone, "clone");
     exportListFn(list.listCapacity, "capacity");
     exportListFn(list.listAllocationPtr, "allocation_ptr");
+    exportListFn(list.listCloneTo, "clone_to");
     exportListFn(list.listReleaseExcessCapacity, "release_excess_capacity");
     exportListFn(list.listConcatUtf8, "concat_utf8");
 }
@@ -99,6 +100,10 @@ const NUMBERS = INTEGERS ++ FLOATS;
 comptime {
     exportNumFn(num.shiftRightZeroFillI128, "shift_right_zero_fill.i128");
     exportNumFn(num.shiftRightZeroFillU128, "shift_right_zero_fill.u128");
+    exportNumFn(num.shiftLeftByI128, "shift_left_by.i128");
+    exportNumFn(num.shiftLeftByU128, "shift_left_by.u128");
+    exportNumFn(num.shiftRightByI128, "shift_right_by.i128");
+    exportNumFn(num.shiftRightByU128, "shift_right_by.u128");
 
     exportNumFn(num.compareI128, "compare.i128");
     exportNumFn(num.compareU128, "compare.u128");
@@ -119,7 +124,10 @@ comptime {
 
     inline for (INTEGERS, 0..) |T, i| {
         num.exportPow(T, ROC_BUILTINS ++ "." ++ NUM ++ ".pow_int.");
+        num.exportDivTrunc(T, ROC_BUILTINS ++ "." ++ NUM ++ ".div_trunc.");
         num.exportDivCeil(T, ROC_BUILTINS ++ "." ++ NUM ++ ".div_ceil.");
+        num.exportRem(T, ROC_BUILTINS ++ "." ++ NUM ++ ".rem.");
+        num.exportAbsOrPanic(T, ROC_BUILTINS ++ "." ++ NUM ++ ".abs_or_panic.");
 
         num.exportRound(f32, T, ROC_BUILTINS ++ "." ++ NUM ++ ".round_f32.");
         num.exportRound(f64, T, ROC_BUILTINS ++ "." ++ NUM ++ ".round_f64.");
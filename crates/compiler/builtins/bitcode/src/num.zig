@@ -251,6 +251,49 @@ pub fn exportCeiling(comptime F: type, comptime T: type, comptime name: []const
     @export(f, .{ .name = name ++ @typeName(T), .linkage = .Strong });
 }
 
+pub fn exportDivTrunc(comptime T: type, comptime name: []const u8) void {
+    comptime var f = struct {
+        fn func(a: T, b: T) callconv(.C) T {
+            if (b == 0) {
+                roc_panic("Integer division by 0!", 0);
+            }
+
+            return @divTrunc(a, b);
+        }
+    }.func;
+    @export(f, .{ .name = name ++ @typeName(T), .linkage = .Strong });
+}
+
+pub fn exportRem(comptime T: type, comptime name: []const u8) void {
+    comptime var f = struct {
+        fn func(a: T, b: T) callconv(.C) T {
+            if (b == 0) {
+                roc_panic("Integer division by 0!", 0);
+            }
+
+            return @rem(a, b);
+        }
+    }.func;
+    @export(f, .{ .name = name ++ @typeName(T), .linkage = .Strong });
+}
+
+pub fn exportAbsOrPanic(comptime T: type, comptime name: []const u8) void {
+    comptime var f = struct {
+        fn func(self: T) callconv(.C) T {
+            if (@typeInfo(T).Int.signedness == .unsigned) {
+                return self;
+            } else if (self == std.math.minInt(T)) {
+                roc_panic("Integer absolute overflowed because its argument is the minimum value", 0);
+            } else if (self < 0) {
+                return -self;
+            } else {
+                return self;
+            }
+        }
+    }.func;
+    @export(f, .{ .name = name ++ @typeName(T), .linkage = .Strong });
+}
+
 pub fn exportDivCeil(comptime T: type, comptime name: []const u8) void {
     comptime var f = struct {
         fn func(a: T, b: T) callconv(.C) T {
@@ -534,14 +577,40 @@ pub fn exportMulWrappedInt(comptime T: type, comptime name: []const u8) void {
     @export(f, .{ .name = name ++ @typeName(T), .linkage = .Strong });
 }
 
-pub fn shiftRightZeroFillI128(self: i128, other: u8) callconv(.C) i128 {
+pub fn shiftLeftByI128(self: i128, other: u8) callconv(.C) i128 {
+    if (other & 0b1000_0000 > 0) {
+        return 0;
+    } else {
+        return self << @as(u7, @intCast(other));
+    }
+}
+
+pub fn shiftLeftByU128(self: u128, other: u8) callconv(.C) u128 {
     if (other & 0b1000_0000 > 0) {
         return 0;
+    } else {
+        return self << @as(u7, @intCast(other));
+    }
+}
+
+pub fn shiftRightByI128(self: i128, other: u8) callconv(.C) i128 {
+    if (other & 0b1000_0000 > 0) {
+        return self >> 127;
     } else {
         return self >> @as(u7, @intCast(other));
     }
 }
 
+pub fn shiftRightByU128(self: u128, other: u8) callconv(.C) u128 {
+    // `Num.shiftRightBy` is arithmetic for every integer type, unsigned ones included
+    return @as(u128, @bitCast(shiftRightByI128(@as(i128, @bitCast(self)), other)));
+}
+
+pub fn shiftRightZeroFillI128(self: i128, other: u8) callconv(.C) i128 {
+    // shift the bits as unsigned, so zeroes come in instead of copies of the sign bit
+    return @as(i128, @bitCast(shiftRightZeroFillU128(@as(u128, @bitCast(self)), other)));
+}
+
 pub fn shiftRightZeroFillU128(self: u128, other: u8) callconv(.C) u128 {
     if (other & 0b1000_0000 > 0) {
         return 0;
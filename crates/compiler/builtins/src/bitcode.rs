@@ -285,7 +285,10 @@ pub const NUM_FABS: IntrinsicName = float_intrinsic!("roc_builtins.num.fabs");
 pub const NUM_SQRT: IntrinsicName = float_intrinsic!("roc_builtins.num.sqrt");
 
 pub const NUM_POW_INT: IntrinsicName = int_intrinsic!("roc_builtins.num.pow_int");
+pub const NUM_DIV_TRUNC: IntrinsicName = int_intrinsic!("roc_builtins.num.div_trunc");
 pub const NUM_DIV_CEIL: IntrinsicName = int_intrinsic!("roc_builtins.num.div_ceil");
+pub const NUM_REM: IntrinsicName = int_intrinsic!("roc_builtins.num.rem");
+pub const NUM_ABS_OR_PANIC_INT: IntrinsicName = int_intrinsic!("roc_builtins.num.abs_or_panic");
 pub const NUM_CEILING_F32: IntrinsicName = int_intrinsic!("roc_builtins.num.ceiling_f32");
 pub const NUM_CEILING_F64: IntrinsicName = int_intrinsic!("roc_builtins.num.ceiling_f64");
 pub const NUM_FLOOR_F32: IntrinsicName = int_intrinsic!("roc_builtins.num.floor_f32");
@@ -318,6 +321,8 @@ pub const NUM_MUL_CHECKED_FLOAT: IntrinsicName =
 
 pub const NUM_IS_MULTIPLE_OF: IntrinsicName = int_intrinsic!("roc_builtins.num.is_multiple_of");
 
+pub const NUM_SHIFT_LEFT_BY: IntrinsicName = int_intrinsic!("roc_builtins.num.shift_left_by");
+pub const NUM_SHIFT_RIGHT_BY: IntrinsicName = int_intrinsic!("roc_builtins.num.shift_right_by");
 pub const NUM_SHIFT_RIGHT_ZERO_FILL: IntrinsicName =
     int_intrinsic!("roc_builtins.num.shift_right_zero_fill");
 
@@ -389,6 +394,7 @@ pub const LIST_APPEND_UNSAFE: &str = "roc_builtins.list.append_unsafe";
 pub const LIST_RESERVE: &str = "roc_builtins.list.reserve";
 pub const LIST_CAPACITY: &str = "roc_builtins.list.capacity";
 pub const LIST_ALLOCATION_PTR: &str = "roc_builtins.list.allocation_ptr";
+pub const LIST_CLONE_TO: &str = "roc_builtins.list.clone_to";
 pub const LIST_RELEASE_EXCESS_CAPACITY: &str = "roc_builtins.list.release_excess_capacity";
 pub const LIST_CONCAT_UTF8: &str = "roc_builtins.list.concat_utf8";
 
@@ -419,6 +425,7 @@ pub const DEC_SUB_OR_PANIC: &str = "roc_builtins.dec.sub_or_panic";
 pub const DEC_SUB_SATURATED: &str = "roc_builtins.dec.sub_saturated";
 pub const DEC_SUB_WITH_OVERFLOW: &str = "roc_builtins.dec.sub_with_overflow";
 pub const DEC_TAN: &str = "roc_builtins.dec.tan";
+pub const DEC_TO_F64: &str = "roc_builtins.dec.to_f64";
 pub const DEC_TO_I128: &str = "roc_builtins.dec.to_i128";
 pub const DEC_FROM_I128: &str = "roc_builtins.dec.from_i128";
 pub const DEC_TO_STR: &str = "roc_builtins.dec.to_str";
@@ -31,6 +31,7 @@ pub enum DeclarationInfo<'a> {
         expr_var: Variable,
         pattern: Pattern,
         function: &'a Loc<expr::FunctionDef>,
+        annotation: Option<&'a Annotation>,
     },
     Destructure {
         loc_pattern: &'a Loc<Pattern>,
@@ -130,6 +131,7 @@ pub fn walk_decls<V: Visitor>(visitor: &mut V, decls: &Declarations) {
                     expr_var,
                     pattern,
                     function: function_def,
+                    annotation: decls.annotations[index].as_ref(),
                 }
             }
             Destructure(destructure_index) => {
@@ -191,6 +193,7 @@ pub fn walk_decl<V: Visitor>(visitor: &mut V, decl: DeclarationInfo<'_>) {
             expr_var,
             pattern,
             function,
+            annotation,
         } => {
             visitor.visit_pattern(&pattern, loc_symbol.region, Some(expr_var));
 
@@ -199,7 +202,11 @@ pub fn walk_decl<V: Visitor>(visitor: &mut V, decl: DeclarationInfo<'_>) {
                 &function.value.arguments,
                 loc_body,
                 function.value.return_type,
-            )
+            );
+
+            if let Some(annot) = annotation {
+                visitor.visit_annotation(annot);
+            }
         }
         Destructure {
             loc_pattern,
@@ -963,3 +970,117 @@ pub fn find_declaration(symbol: Symbol, decls: &'_ Declarations) -> Option<Found
         }
     }
 }
+
+/// A mention of a symbol, as found by [find_references].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FoundReference {
+    /// The symbol is introduced here, by a declaration or a pattern.
+    Definition(Region),
+    /// The symbol is looked up here. For qualified lookups like `Foo.bar`, the region spans the
+    /// whole qualified name.
+    Lookup(Region),
+    /// The signature of a type annotation on a definition of the symbol.
+    Annotation(Region),
+}
+
+impl FoundReference {
+    pub fn region(&self) -> Region {
+        match self {
+            FoundReference::Definition(region)
+            | FoundReference::Lookup(region)
+            | FoundReference::Annotation(region) => *region,
+        }
+    }
+}
+
+/// Finds every definition and lookup of `symbol` in `decls`.
+pub fn find_references(symbol: Symbol, decls: &Declarations) -> Vec<FoundReference> {
+    let mut visitor = Finder {
+        symbol,
+        found: Vec::new(),
+    };
+    visitor.visit_decls(decls);
+    return visitor.found;
+
+    struct Finder {
+        symbol: Symbol,
+        found: Vec<FoundReference>,
+    }
+
+    impl Visitor for Finder {
+        fn visit_decl(&mut self, decl: DeclarationInfo<'_>) {
+            match &decl {
+                DeclarationInfo::Value {
+                    loc_symbol,
+                    annotation: Some(annot),
+                    ..
+                }
+                | DeclarationInfo::Function {
+                    loc_symbol,
+                    annotation: Some(annot),
+                    ..
+                } if loc_symbol.value == self.symbol => {
+                    self.found.push(FoundReference::Annotation(annot.region));
+                }
+                _ => {}
+            }
+
+            walk_decl(self, decl);
+        }
+
+        fn visit_def(&mut self, def: &Def) {
+            if let (Pattern::Identifier(symbol), Some(annot)) =
+                (&def.loc_pattern.value, &def.annotation)
+            {
+                if *symbol == self.symbol {
+                    self.found.push(FoundReference::Annotation(annot.region));
+                }
+            }
+
+            walk_def(self, def);
+        }
+
+        fn visit_pattern(&mut self, pattern: &Pattern, region: Region, _opt_var: Option<Variable>) {
+            match pattern {
+                Pattern::Identifier(symbol)
+                | Pattern::As(_, symbol)
+                | Pattern::AbilityMemberSpecialization { ident: symbol, .. }
+                    if *symbol == self.symbol =>
+                {
+                    self.found.push(FoundReference::Definition(region));
+                }
+                Pattern::Shadowed(_, loc_ident, symbol) if *symbol == self.symbol => {
+                    self.found
+                        .push(FoundReference::Definition(loc_ident.region));
+                }
+                _ => {}
+            }
+
+            walk_pattern(self, pattern);
+        }
+
+        fn visit_record_destruct(&mut self, destruct: &RecordDestruct, region: Region) {
+            // with a guard, the field label introduces no symbol of its own
+            if destruct.symbol == self.symbol && !matches!(destruct.typ, DestructType::Guard(..)) {
+                self.found.push(FoundReference::Definition(region));
+            }
+
+            walk_record_destruct(self, destruct);
+        }
+
+        fn visit_expr(&mut self, expr: &Expr, region: Region, var: Variable) {
+            match expr {
+                Expr::Var(symbol, _)
+                | Expr::AbilityMember(symbol, _, _)
+                | Expr::RecordUpdate { symbol, .. }
+                    if *symbol == self.symbol =>
+                {
+                    self.found.push(FoundReference::Lookup(region));
+                }
+                _ => {}
+            }
+
+            walk_expr(self, expr, var);
+        }
+    }
+}
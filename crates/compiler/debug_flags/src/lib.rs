@@ -158,6 +158,12 @@ flags! {
     /// Prints LLVM function verification output.
     ROC_PRINT_LLVM_FN_VERIFICATION
 
+    // ===Dev Gen===
+
+    /// Skips constant folding and dead branch elimination in the dev backend,
+    /// so that the generated code still does all the work.
+    ROC_DEV_SKIP_CONST_FOLDING
+
     // ===WASM Gen===
 
     /// Writes a `final.wasm` file to /tmp
@@ -166,6 +172,9 @@ flags! {
     /// Prints Wasm interpreter debug log in test_gen
     ROC_LOG_WASM_INTERP
 
+    /// Validates the final Wasm module in `build_app_binary`, and reports the first invalid function
+    ROC_VALIDATE_WASM
+
     // ===Load===
 
     /// Print load phases as they complete.
@@ -0,0 +1,490 @@
+use bumpalo::Bump;
+use roc_builtins::bitcode::IntWidth;
+use roc_collections::all::MutMap;
+use roc_module::low_level::LowLevel;
+use roc_module::symbol::Symbol;
+use roc_mono::ir::{Call, CallType, Expr, JoinPointId, Literal, Stmt};
+use roc_mono::layout::{Builtin, InLayout, LayoutInterner, LayoutRepr, STLayoutInterner};
+
+// Constant folding and dead branch elimination over the mono IR of a proc, before code gen.
+//
+// This is deliberately simple. It only knows about integers up to 64 bits, bools, and bytes:
+//  - low levels with only constant arguments are replaced by a literal.
+//  - switches on a constant condition are replaced by the branch that would be taken.
+//  - join points that can no longer be jumped to are removed.
+// Anything that could fail at runtime (overflow, division by zero) is left alone,
+// so that it still fails in the same way.
+//
+// The test_gen tests turn this pass off with `Env::fold_constants`, so that they exercise the real
+// code gen. Setting ROC_DEV_SKIP_CONST_FOLDING turns it off everywhere else, for debugging.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Const {
+    /// Stored sign or zero extended, depending on the width.
+    Int(i128, IntWidth),
+    Bool(bool),
+    Byte(u8),
+}
+
+impl Const {
+    fn from_literal(literal: &Literal, repr: LayoutRepr) -> Option<Self> {
+        match (literal, repr) {
+            (Literal::Int(bytes), LayoutRepr::Builtin(Builtin::Int(width))) if fits_i64(width) => {
+                Some(Const::Int(i128::from_ne_bytes(*bytes), width))
+            }
+            (Literal::Bool(b), _) => Some(Const::Bool(*b)),
+            (Literal::Byte(b), _) => Some(Const::Byte(*b)),
+            _ => None,
+        }
+    }
+
+    fn to_literal<'a>(self) -> Literal<'a> {
+        match self {
+            Const::Int(value, _) => Literal::Int(value.to_ne_bytes()),
+            Const::Bool(b) => Literal::Bool(b),
+            Const::Byte(b) => Literal::Byte(b),
+        }
+    }
+
+    /// The value this constant is compared against in a switch.
+    fn switch_value(self) -> Option<u64> {
+        match self {
+            // Negative values depend on how the condition is extended in a register.
+            Const::Int(value, _) => u64::try_from(value).ok(),
+            Const::Bool(b) => Some(b as u64),
+            Const::Byte(b) => Some(b as u64),
+        }
+    }
+}
+
+/// Folds the constants in the body of a proc.
+/// Returns the original statement if nothing could be folded.
+pub(crate) fn fold_constants<'a>(
+    arena: &'a Bump,
+    interner: &STLayoutInterner<'a>,
+    body: &'a Stmt<'a>,
+) -> &'a Stmt<'a> {
+    let mut folder = ConstFolder {
+        arena,
+        interner,
+        constants: MutMap::default(),
+    };
+
+    folder.fold_stmt(body)
+}
+
+struct ConstFolder<'a, 'r> {
+    arena: &'a Bump,
+    interner: &'r STLayoutInterner<'a>,
+    // Symbols are only defined once in a proc, so this does not need to be scoped.
+    constants: MutMap<Symbol, Const>,
+}
+
+impl<'a, 'r> ConstFolder<'a, 'r> {
+    fn fold_stmt(&mut self, stmt: &'a Stmt<'a>) -> &'a Stmt<'a> {
+        match stmt {
+            Stmt::Let(sym, expr, layout, following) => {
+                let folded = self.fold_expr(*sym, expr, *layout);
+                let new_following = self.fold_stmt(following);
+
+                match folded {
+                    Some(literal) => self.arena.alloc(Stmt::Let(
+                        *sym,
+                        Expr::Literal(literal),
+                        *layout,
+                        new_following,
+                    )),
+                    None if std::ptr::eq(new_following, *following) => stmt,
+                    None => self
+                        .arena
+                        .alloc(Stmt::Let(*sym, expr.clone(), *layout, new_following)),
+                }
+            }
+            Stmt::Switch {
+                cond_symbol,
+                cond_layout,
+                branches,
+                default_branch,
+                ret_layout,
+            } => {
+                if let Some(value) = self
+                    .constants
+                    .get(cond_symbol)
+                    .and_then(|cond| cond.switch_value())
+                {
+                    let taken = branches
+                        .iter()
+                        .find(|(branch_value, _, _)| *branch_value == value)
+                        .map_or(default_branch.1, |(_, _, branch)| branch);
+
+                    return self.fold_stmt(taken);
+                }
+
+                let arena = self.arena;
+                let mut changed = false;
+                let new_branches =
+                    arena.alloc_slice_fill_iter(branches.iter().map(|(value, info, branch)| {
+                        let new_branch = self.fold_stmt(branch);
+                        changed |= !std::ptr::eq(new_branch, branch);
+                        (*value, info.clone(), new_branch.clone())
+                    }));
+                let new_default = self.fold_stmt(default_branch.1);
+                changed |= !std::ptr::eq(new_default, default_branch.1);
+
+                if !changed {
+                    return stmt;
+                }
+
+                self.arena.alloc(Stmt::Switch {
+                    cond_symbol: *cond_symbol,
+                    cond_layout: *cond_layout,
+                    branches: new_branches,
+                    default_branch: (default_branch.0.clone(), new_default),
+                    ret_layout: *ret_layout,
+                })
+            }
+            Stmt::Join {
+                id,
+                parameters,
+                body,
+                remainder,
+            } => {
+                let new_remainder = self.fold_stmt(remainder);
+
+                // The body can only be reached through the remainder.
+                if !jumps_to(new_remainder, *id) {
+                    return new_remainder;
+                }
+
+                let new_body = self.fold_stmt(body);
+
+                if std::ptr::eq(new_remainder, *remainder) && std::ptr::eq(new_body, *body) {
+                    return stmt;
+                }
+
+                self.arena.alloc(Stmt::Join {
+                    id: *id,
+                    parameters,
+                    body: new_body,
+                    remainder: new_remainder,
+                })
+            }
+            Stmt::Refcounting(modify, following) => {
+                let new_following = self.fold_stmt(following);
+
+                if std::ptr::eq(new_following, *following) {
+                    return stmt;
+                }
+
+                self.arena.alloc(Stmt::Refcounting(*modify, new_following))
+            }
+            Stmt::Dbg { remainder, .. }
+            | Stmt::Expect { remainder, .. }
+            | Stmt::ExpectFx { remainder, .. } => {
+                let new_remainder = self.fold_stmt(remainder);
+
+                if std::ptr::eq(new_remainder, *remainder) {
+                    return stmt;
+                }
+
+                let mut new_stmt = stmt.clone();
+                if let Stmt::Dbg { remainder, .. }
+                | Stmt::Expect { remainder, .. }
+                | Stmt::ExpectFx { remainder, .. } = &mut new_stmt
+                {
+                    *remainder = new_remainder;
+                }
+                self.arena.alloc(new_stmt)
+            }
+            Stmt::Ret(_) | Stmt::Jump(_, _) | Stmt::Crash(_, _) => stmt,
+        }
+    }
+
+    /// Records `sym` if it is a constant.
+    /// Returns the literal to replace `expr` with, if it can be computed at compile time.
+    fn fold_expr(
+        &mut self,
+        sym: Symbol,
+        expr: &Expr<'a>,
+        layout: InLayout<'a>,
+    ) -> Option<Literal<'a>> {
+        let repr = self.interner.get_repr(layout);
+
+        match expr {
+            Expr::Literal(literal) => {
+                if let Some(value) = Const::from_literal(literal, repr) {
+                    self.constants.insert(sym, value);
+                }
+
+                None
+            }
+            Expr::Call(Call {
+                call_type: CallType::LowLevel { op, .. },
+                arguments,
+            }) => {
+                let mut args =
+                    bumpalo::collections::Vec::with_capacity_in(arguments.len(), self.arena);
+                for arg in arguments.iter() {
+                    args.push(*self.constants.get(arg)?);
+                }
+
+                let value = eval_lowlevel(*op, &args, repr)?;
+                self.constants.insert(sym, value);
+
+                Some(value.to_literal())
+            }
+            _ => None,
+        }
+    }
+}
+
+fn eval_lowlevel(op: LowLevel, args: &[Const], ret_repr: LayoutRepr) -> Option<Const> {
+    use Const::*;
+    use LowLevel::*;
+
+    let ret_width = match ret_repr {
+        LayoutRepr::Builtin(Builtin::Int(width)) if fits_i64(width) => Some(width),
+        _ => None,
+    };
+
+    // Checked arithmetic only folds when the result fits, so that overflow still panics at runtime.
+    let checked = |value: Option<i128>| {
+        let width = ret_width?;
+        let value = value?;
+        (wrap(value, width) == value).then_some(Int(value, width))
+    };
+    let wrapping = |value: i128| {
+        let width = ret_width?;
+        Some(Int(wrap(value, width), width))
+    };
+
+    match (op, args) {
+        (NumAdd, [Int(a, _), Int(b, _)]) => checked(a.checked_add(*b)),
+        (NumSub, [Int(a, _), Int(b, _)]) => checked(a.checked_sub(*b)),
+        (NumMul, [Int(a, _), Int(b, _)]) => checked(a.checked_mul(*b)),
+        (NumNeg, [Int(a, _)]) => checked(a.checked_neg()),
+        (NumDivTruncUnchecked, [Int(a, _), Int(b, _)]) => checked(a.checked_div(*b)),
+        (NumRemUnchecked, [Int(a, _), Int(b, _)]) => checked(a.checked_rem(*b)),
+
+        (NumAddWrap, [Int(a, _), Int(b, _)]) => wrapping(a.wrapping_add(*b)),
+        (NumSubWrap, [Int(a, _), Int(b, _)]) => wrapping(a.wrapping_sub(*b)),
+        (NumMulWrap, [Int(a, _), Int(b, _)]) => wrapping(a.wrapping_mul(*b)),
+        (NumBitwiseAnd, [Int(a, _), Int(b, _)]) => wrapping(a & b),
+        (NumBitwiseOr, [Int(a, _), Int(b, _)]) => wrapping(a | b),
+        (NumBitwiseXor, [Int(a, _), Int(b, _)]) => wrapping(a ^ b),
+        (NumIntCast, [Int(a, _)]) => wrapping(*a),
+
+        (NumGt, [Int(a, _), Int(b, _)]) => Some(Bool(a > b)),
+        (NumGte, [Int(a, _), Int(b, _)]) => Some(Bool(a >= b)),
+        (NumLt, [Int(a, _), Int(b, _)]) => Some(Bool(a < b)),
+        (NumLte, [Int(a, _), Int(b, _)]) => Some(Bool(a <= b)),
+        (Eq, [a, b]) if same_kind(a, b) => Some(Bool(a == b)),
+        (NotEq, [a, b]) if same_kind(a, b) => Some(Bool(a != b)),
+
+        (And, [Bool(a), Bool(b)]) => Some(Bool(*a && *b)),
+        (Or, [Bool(a), Bool(b)]) => Some(Bool(*a || *b)),
+        (Not, [Bool(a)]) => Some(Bool(!a)),
+
+        _ => None,
+    }
+}
+
+fn same_kind(a: &Const, b: &Const) -> bool {
+    matches!(
+        (a, b),
+        (Const::Int(..), Const::Int(..))
+            | (Const::Bool(_), Const::Bool(_))
+            | (Const::Byte(_), Const::Byte(_))
+    )
+}
+
+fn fits_i64(width: IntWidth) -> bool {
+    !matches!(width, IntWidth::I128 | IntWidth::U128)
+}
+
+/// Truncates `value` to `width` and extends it back, like storing it in an integer of that width.
+fn wrap(value: i128, width: IntWidth) -> i128 {
+    match width {
+        IntWidth::U8 => value as u8 as i128,
+        IntWidth::U16 => value as u16 as i128,
+        IntWidth::U32 => value as u32 as i128,
+        IntWidth::U64 => value as u64 as i128,
+        IntWidth::I8 => value as i8 as i128,
+        IntWidth::I16 => value as i16 as i128,
+        IntWidth::I32 => value as i32 as i128,
+        IntWidth::I64 => value as i64 as i128,
+        IntWidth::U128 | IntWidth::I128 => value,
+    }
+}
+
+/// Whether any path through `stmt` jumps to the join point `id`.
+fn jumps_to(stmt: &Stmt, id: JoinPointId) -> bool {
+    match stmt {
+        Stmt::Jump(target, _) => *target == id,
+        Stmt::Let(_, _, _, following) | Stmt::Refcounting(_, following) => jumps_to(following, id),
+        Stmt::Switch {
+            branches,
+            default_branch,
+            ..
+        } => {
+            branches.iter().any(|(_, _, branch)| jumps_to(branch, id))
+                || jumps_to(default_branch.1, id)
+        }
+        Stmt::Join {
+            body, remainder, ..
+        } => jumps_to(body, id) || jumps_to(remainder, id),
+        Stmt::Dbg { remainder, .. }
+        | Stmt::Expect { remainder, .. }
+        | Stmt::ExpectFx { remainder, .. } => jumps_to(remainder, id),
+        Stmt::Ret(_) | Stmt::Crash(_, _) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use roc_mono::ir::{BranchInfo, Param, UpdateModeId};
+    use roc_mono::layout::Layout;
+    use roc_target::Target;
+
+    fn int<'a>(value: i128) -> Expr<'a> {
+        Expr::Literal(Literal::Int(value.to_ne_bytes()))
+    }
+
+    fn lowlevel<'a>(arena: &'a Bump, op: LowLevel, arguments: &[Symbol]) -> Expr<'a> {
+        Expr::Call(Call {
+            call_type: CallType::LowLevel {
+                op,
+                update_mode: UpdateModeId::BACKEND_DUMMY,
+            },
+            arguments: arena.alloc_slice_copy(arguments),
+        })
+    }
+
+    fn let_<'a>(
+        arena: &'a Bump,
+        sym: Symbol,
+        expr: Expr<'a>,
+        layout: InLayout<'a>,
+        following: &'a Stmt<'a>,
+    ) -> &'a Stmt<'a> {
+        arena.alloc(Stmt::Let(sym, expr, layout, following))
+    }
+
+    fn let_exprs<'a>(mut stmt: &'a Stmt<'a>) -> std::vec::Vec<&'a Expr<'a>> {
+        let mut exprs = std::vec::Vec::new();
+        while let Stmt::Let(_, expr, _, following) = stmt {
+            exprs.push(expr);
+            stmt = following;
+        }
+        exprs
+    }
+
+    #[test]
+    fn folds_arithmetic_and_keeps_overflow() {
+        let arena = Bump::new();
+        let interner = STLayoutInterner::with_capacity(4, Target::LinuxX64);
+        let (a, b, c, d) = (Symbol::ARG_1, Symbol::ARG_2, Symbol::ARG_3, Symbol::ARG_4);
+
+        // a = 100i8
+        // b = a + a    # overflows
+        // c = a +% a   # wraps to -56
+        // d = c < a
+        // ret d
+        let ret = arena.alloc(Stmt::Ret(d));
+        let body = let_(
+            &arena,
+            d,
+            lowlevel(&arena, LowLevel::NumLt, &[c, a]),
+            Layout::BOOL,
+            ret,
+        );
+        let body = let_(
+            &arena,
+            c,
+            lowlevel(&arena, LowLevel::NumAddWrap, &[a, a]),
+            Layout::I8,
+            body,
+        );
+        let body = let_(
+            &arena,
+            b,
+            lowlevel(&arena, LowLevel::NumAdd, &[a, a]),
+            Layout::I8,
+            body,
+        );
+        let body = let_(&arena, a, int(100), Layout::I8, body);
+
+        let folded = fold_constants(&arena, &interner, body);
+
+        let exprs = let_exprs(folded);
+        assert_eq!(exprs[0], &int(100));
+        assert!(matches!(exprs[1], Expr::Call(_)));
+        assert_eq!(exprs[2], &int(-56));
+        assert_eq!(exprs[3], &Expr::Literal(Literal::Bool(true)));
+    }
+
+    #[test]
+    fn removes_dead_branches_and_join_points() {
+        let arena = Bump::new();
+        let interner = STLayoutInterner::with_capacity(4, Target::LinuxX64);
+        let (cond, x, y, param) = (Symbol::ARG_1, Symbol::ARG_2, Symbol::ARG_3, Symbol::ARG_4);
+        let id = JoinPointId(Symbol::ARG_5);
+
+        // cond = False
+        // joinpoint j = \param -> ret param
+        // in
+        //     when cond is
+        //         True -> jump j x
+        //         _ -> ret y
+        let ret_y = arena.alloc(Stmt::Ret(y));
+        let switch = arena.alloc(Stmt::Switch {
+            cond_symbol: cond,
+            cond_layout: Layout::BOOL,
+            branches: arena.alloc([(1, BranchInfo::None, Stmt::Jump(id, arena.alloc([x])))]),
+            default_branch: (BranchInfo::None, ret_y),
+            ret_layout: Layout::I64,
+        });
+        let join = arena.alloc(Stmt::Join {
+            id,
+            parameters: arena.alloc([Param {
+                symbol: param,
+                layout: Layout::I64,
+            }]),
+            body: arena.alloc(Stmt::Ret(param)),
+            remainder: switch,
+        });
+        let body = let_(
+            &arena,
+            cond,
+            Expr::Literal(Literal::Bool(false)),
+            Layout::BOOL,
+            join,
+        );
+
+        let folded = fold_constants(&arena, &interner, body);
+
+        let Stmt::Let(_, _, _, following) = folded else {
+            panic!()
+        };
+        assert!(std::ptr::eq(*following, ret_y));
+    }
+
+    #[test]
+    fn unchanged_procs_are_not_copied() {
+        let arena = Bump::new();
+        let interner = STLayoutInterner::with_capacity(4, Target::LinuxX64);
+        let (a, b) = (Symbol::ARG_1, Symbol::ARG_2);
+
+        let ret = arena.alloc(Stmt::Ret(b));
+        let body = let_(
+            &arena,
+            b,
+            lowlevel(&arena, LowLevel::NumAdd, &[a, a]),
+            Layout::I64,
+            ret,
+        );
+
+        assert!(std::ptr::eq(fold_constants(&arena, &interner, body), body));
+    }
+}
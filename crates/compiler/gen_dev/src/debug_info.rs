@@ -0,0 +1,787 @@
+//! DWARF debug info and `.eh_frame` unwind tables for the objects built by the dev backend.
+//!
+//! When debug info is requested, every procedure gets a frame description entry, so debuggers and
+//! profilers can walk the stack through Roc code. When the source of a procedure is known, it also
+//! gets a `DW_TAG_subprogram` and a line table sequence, with a row for its definition and one for
+//! every nested definition whose code starts somewhere in it. Other statements and expressions
+//! get no rows of their own.
+//! For now, this is only emitted for ELF objects.
+
+use std::path::Path;
+
+use gimli::write::{
+    Address, AttributeValue, CallFrameInstruction, CieId, CommonInformationEntry, DwarfUnit,
+    EhFrame, EndianVec, FileId, FrameDescriptionEntry, FrameTable, LineProgram, LineString, Range,
+    RangeList, Sections, Writer,
+};
+use gimli::{LineEncoding, LittleEndian};
+use object::write::{self, Object, SymbolId};
+use object::{BinaryFormat, RelocationEncoding, RelocationKind, SectionKind};
+use roc_collections::all::MutMap;
+use roc_error_macros::internal_error;
+use roc_module::symbol::{Interns, ModuleId, Symbol};
+use roc_mono::ir::SourceInfo;
+use roc_region::all::{LineInfo, Region};
+
+struct LineTables<'a> {
+    source_info: SourceInfo<'a>,
+    dwarf: DwarfUnit,
+    files: MutMap<ModuleId, Option<(FileId, LineInfo)>>,
+    ranges: Vec<Range>,
+}
+
+impl<'a> LineTables<'a> {
+    fn new(source_info: SourceInfo<'a>, home: ModuleId) -> Self {
+        let encoding = gimli::Encoding {
+            format: gimli::Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+
+        let (comp_dir, comp_file) = match source_info.sources.get(&home) {
+            Some((path, _)) => split_path(path),
+            None => (".".to_string(), "main.roc".to_string()),
+        };
+
+        let mut dwarf = DwarfUnit::new(encoding);
+        dwarf.unit.line_program = LineProgram::new(
+            encoding,
+            LineEncoding::default(),
+            LineString::String(comp_dir.clone().into_bytes()),
+            LineString::String(comp_file.clone().into_bytes()),
+            None,
+        );
+
+        let root = dwarf.unit.get_mut(dwarf.unit.root());
+        root.set(
+            gimli::DW_AT_producer,
+            AttributeValue::String(b"roc dev backend".to_vec()),
+        );
+        root.set(
+            gimli::DW_AT_name,
+            AttributeValue::String(comp_file.into_bytes()),
+        );
+        root.set(
+            gimli::DW_AT_comp_dir,
+            AttributeValue::String(comp_dir.into_bytes()),
+        );
+        root.set(
+            gimli::DW_AT_low_pc,
+            AttributeValue::Address(Address::Constant(0)),
+        );
+
+        LineTables {
+            source_info,
+            dwarf,
+            files: MutMap::default(),
+            ranges: Vec::new(),
+        }
+    }
+
+    /// The file and 1-based line and column a region starts at, if the source of its module is known.
+    fn location(&mut self, module_id: ModuleId, region: Region) -> Option<(FileId, u64, u64)> {
+        let sources = self.source_info.sources;
+        let line_program = &mut self.dwarf.unit.line_program;
+        let (file_id, line_info) = self
+            .files
+            .entry(module_id)
+            .or_insert_with(|| {
+                let (path, src) = sources.get(&module_id)?;
+                let (dir, file) = split_path(path);
+
+                let dir_id = line_program.add_directory(LineString::String(dir.into_bytes()));
+                let file_id =
+                    line_program.add_file(LineString::String(file.into_bytes()), dir_id, None);
+
+                Some((file_id, LineInfo::new(src)))
+            })
+            .as_ref()?;
+
+        let position = line_info.convert_pos(region.start());
+
+        Some((
+            *file_id,
+            position.line as u64 + 1,
+            position.column as u64 + 1,
+        ))
+    }
+
+    /// The rows of the line table for a procedure, from the locations of its definition and the
+    /// definitions nested in it. If several statements start at the same offset (because some
+    /// of them generated no code), the last one is the one whose code is there.
+    fn rows(
+        &mut self,
+        location: Option<(FileId, u64, u64)>,
+        size: u64,
+        statement_offsets: &[(u32, Symbol)],
+    ) -> Vec<(u64, (FileId, u64, u64))> {
+        let mut rows = Vec::with_capacity(statement_offsets.len() + 1);
+        rows.extend(location.map(|location| (0, location)));
+
+        for (offset, symbol) in statement_offsets {
+            let offset = *offset as u64;
+            if offset >= size {
+                continue;
+            }
+
+            let source_info = self.source_info;
+            let location = source_info
+                .let_region(*symbol)
+                .and_then(|(module_id, region)| self.location(module_id, region));
+
+            if let Some(location) = location {
+                match rows.last_mut() {
+                    Some((last_offset, last_location)) if *last_offset == offset => {
+                        *last_location = location;
+                    }
+                    _ => rows.push((offset, location)),
+                }
+            }
+        }
+
+        rows
+    }
+
+    fn add_proc(
+        &mut self,
+        interns: &Interns,
+        name: &str,
+        symbol: Symbol,
+        address: Address,
+        size: u64,
+        statement_offsets: &[(u32, Symbol)],
+    ) {
+        let source_info = self.source_info;
+        let location = source_info
+            .def_regions
+            .get(&symbol)
+            .and_then(|region| self.location(symbol.module_id(), *region));
+
+        let root = self.dwarf.unit.root();
+        let subprogram = self.dwarf.unit.add(root, gimli::DW_TAG_subprogram);
+        let entry = self.dwarf.unit.get_mut(subprogram);
+
+        entry.set(
+            gimli::DW_AT_linkage_name,
+            AttributeValue::String(name.into()),
+        );
+        entry.set(gimli::DW_AT_low_pc, AttributeValue::Address(address));
+        entry.set(gimli::DW_AT_high_pc, AttributeValue::Udata(size));
+
+        if let Some((file_id, line, _)) = location {
+            // Only definitions from source have a name that is known to be interned.
+            let qualified_name = format!(
+                "{}.{}",
+                symbol.module_string(interns).as_str(),
+                symbol.as_str(interns)
+            );
+
+            entry.set(
+                gimli::DW_AT_name,
+                AttributeValue::String(qualified_name.into_bytes()),
+            );
+            entry.set(
+                gimli::DW_AT_decl_file,
+                AttributeValue::FileIndex(Some(file_id)),
+            );
+            entry.set(gimli::DW_AT_decl_line, AttributeValue::Udata(line));
+        } else {
+            entry.set(gimli::DW_AT_name, AttributeValue::String(name.into()));
+        }
+
+        let rows = self.rows(location, size, statement_offsets);
+        if !rows.is_empty() {
+            let line_program = &mut self.dwarf.unit.line_program;
+            line_program.begin_sequence(Some(address));
+            for (offset, (file_id, line, column)) in rows {
+                let row = line_program.row();
+                row.address_offset = offset;
+                row.file = file_id;
+                row.line = line;
+                row.column = column;
+                line_program.generate_row();
+            }
+            line_program.end_sequence(size);
+        }
+
+        self.ranges.push(Range::StartLength {
+            begin: address,
+            length: size,
+        });
+    }
+}
+
+/// Collects the debug info and unwind tables of every procedure in an object.
+pub(crate) struct DebugInfo<'a> {
+    line_tables: Option<LineTables<'a>>,
+    frames: Option<(FrameTable, CieId)>,
+    /// The object symbols that `Address::Symbol` refers to by index.
+    symbols: Vec<SymbolId>,
+}
+
+impl<'a> DebugInfo<'a> {
+    pub fn new(
+        source_info: Option<SourceInfo<'a>>,
+        home: ModuleId,
+        format: BinaryFormat,
+        mut cie: CommonInformationEntry,
+    ) -> Self {
+        if format != BinaryFormat::Elf || source_info.is_none() {
+            return DebugInfo {
+                line_tables: None,
+                frames: None,
+                symbols: Vec::new(),
+            };
+        }
+
+        cie.fde_address_encoding =
+            gimli::DwEhPe(gimli::DW_EH_PE_pcrel.0 | gimli::DW_EH_PE_sdata4.0);
+
+        let mut frame_table = FrameTable::default();
+        let cie_id = frame_table.add_cie(cie);
+
+        DebugInfo {
+            line_tables: source_info.map(|source_info| LineTables::new(source_info, home)),
+            frames: Some((frame_table, cie_id)),
+            symbols: Vec::new(),
+        }
+    }
+
+    /// Records a procedure whose code is `size` bytes long, starting at the object symbol `proc_id`.
+    /// `statement_offsets` are where the code for the definitions nested in it starts.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_proc(
+        &mut self,
+        interns: &Interns,
+        name: &str,
+        symbol: Symbol,
+        proc_id: SymbolId,
+        size: u64,
+        statement_offsets: &[(u32, Symbol)],
+        call_frame_instructions: impl IntoIterator<Item = (u32, CallFrameInstruction)>,
+    ) {
+        if self.line_tables.is_none() && self.frames.is_none() {
+            return;
+        }
+
+        let address = Address::Symbol {
+            symbol: self.symbols.len(),
+            addend: 0,
+        };
+        self.symbols.push(proc_id);
+
+        if let Some(line_tables) = &mut self.line_tables {
+            line_tables.add_proc(interns, name, symbol, address, size, statement_offsets);
+        }
+
+        if let Some((frame_table, cie_id)) = &mut self.frames {
+            let mut fde = FrameDescriptionEntry::new(address, size as u32);
+            for (offset, instruction) in call_frame_instructions {
+                fde.add_instruction(offset, instruction);
+            }
+            frame_table.add_fde(*cie_id, fde);
+        }
+    }
+
+    /// Adds the DWARF sections and `.eh_frame` to the object.
+    pub fn write(self, output: &mut Object) {
+        if let Some(mut line_tables) = self.line_tables {
+            let ranges = line_tables
+                .dwarf
+                .unit
+                .ranges
+                .add(RangeList(line_tables.ranges));
+            let root = line_tables.dwarf.unit.root();
+            line_tables
+                .dwarf
+                .unit
+                .get_mut(root)
+                .set(gimli::DW_AT_ranges, AttributeValue::RangeListRef(ranges));
+
+            let mut sections = Sections::new(RelocatingWriter::default());
+            if let Err(e) = line_tables.dwarf.write(&mut sections) {
+                internal_error!("failed to write debug info: {:?}", e);
+            }
+
+            let mut section_ids = MutMap::default();
+            let _: Result<(), ()> = sections.for_each(|id, writer| {
+                if !writer.data.slice().is_empty() {
+                    let section_id = output.add_section(
+                        vec![],
+                        id.name().as_bytes().to_vec(),
+                        SectionKind::Debug,
+                    );
+                    output.set_section_data(section_id, writer.data.slice().to_vec(), 1);
+                    section_ids.insert(id, section_id);
+                }
+                Ok(())
+            });
+
+            let _: Result<(), ()> = sections.for_each(|id, writer| {
+                if let Some(section_id) = section_ids.get(&id) {
+                    for reloc in writer.relocations.iter() {
+                        let symbol = match reloc.target {
+                            RelocationTarget::Symbol(index) => self.symbols[index],
+                            RelocationTarget::Section(target) => match section_ids.get(&target) {
+                                Some(target_id) => output.section_symbol(*target_id),
+                                None => internal_error!("missing debug section {:?}", target),
+                            },
+                        };
+                        add_relocation(output, *section_id, reloc, symbol);
+                    }
+                }
+                Ok(())
+            });
+        }
+
+        if let Some((frame_table, _)) = self.frames {
+            let mut eh_frame = EhFrame(RelocatingWriter::default());
+            if let Err(e) = frame_table.write_eh_frame(&mut eh_frame) {
+                internal_error!("failed to write unwind tables: {:?}", e);
+            }
+
+            let section_id =
+                output.add_section(vec![], b".eh_frame".to_vec(), SectionKind::ReadOnlyData);
+            output.set_section_data(section_id, eh_frame.0.data.slice().to_vec(), 8);
+
+            for reloc in eh_frame.0.relocations.iter() {
+                let symbol = match reloc.target {
+                    RelocationTarget::Symbol(index) => self.symbols[index],
+                    RelocationTarget::Section(target) => {
+                        internal_error!("unexpected reference to {:?} in .eh_frame", target)
+                    }
+                };
+                add_relocation(output, section_id, reloc, symbol);
+            }
+        }
+    }
+}
+
+fn split_path(path: &Path) -> (String, String) {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_string_lossy().into_owned(),
+        _ => ".".to_string(),
+    };
+    let file = match path.file_name() {
+        Some(file) => file.to_string_lossy().into_owned(),
+        None => path.to_string_lossy().into_owned(),
+    };
+
+    (dir, file)
+}
+
+fn add_relocation(
+    output: &mut Object,
+    section_id: write::SectionId,
+    reloc: &DebugRelocation,
+    symbol: SymbolId,
+) {
+    let relocation = write::Relocation {
+        offset: reloc.offset,
+        size: reloc.size * 8,
+        kind: if reloc.pc_relative {
+            RelocationKind::Relative
+        } else {
+            RelocationKind::Absolute
+        },
+        encoding: RelocationEncoding::Generic,
+        symbol,
+        addend: reloc.addend,
+    };
+
+    if let Err(e) = output.add_relocation(section_id, relocation) {
+        internal_error!("{:?}", e);
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum RelocationTarget {
+    /// An index into `DebugInfo::symbols`.
+    Symbol(usize),
+    /// The start of another debug section.
+    Section(gimli::SectionId),
+}
+
+#[derive(Debug, Clone)]
+struct DebugRelocation {
+    offset: u64,
+    size: u8,
+    target: RelocationTarget,
+    addend: i64,
+    pc_relative: bool,
+}
+
+/// A gimli writer that records relocations for addresses of procedures and offsets into other sections,
+/// instead of assuming that everything ends up at a fixed address.
+#[derive(Debug, Clone)]
+struct RelocatingWriter {
+    data: EndianVec<LittleEndian>,
+    relocations: Vec<DebugRelocation>,
+}
+
+impl Default for RelocatingWriter {
+    fn default() -> Self {
+        RelocatingWriter {
+            data: EndianVec::new(LittleEndian),
+            relocations: Vec::new(),
+        }
+    }
+}
+
+impl RelocatingWriter {
+    fn relocate(
+        &mut self,
+        offset: usize,
+        size: u8,
+        target: RelocationTarget,
+        addend: i64,
+        pc_relative: bool,
+    ) {
+        self.relocations.push(DebugRelocation {
+            offset: offset as u64,
+            size,
+            target,
+            addend,
+            pc_relative,
+        });
+    }
+}
+
+impl Writer for RelocatingWriter {
+    type Endian = LittleEndian;
+
+    fn endian(&self) -> Self::Endian {
+        LittleEndian
+    }
+
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> gimli::write::Result<()> {
+        self.data.write(bytes)
+    }
+
+    fn write_at(&mut self, offset: usize, bytes: &[u8]) -> gimli::write::Result<()> {
+        self.data.write_at(offset, bytes)
+    }
+
+    fn write_address(&mut self, address: Address, size: u8) -> gimli::write::Result<()> {
+        match address {
+            Address::Constant(value) => self.write_udata(value, size),
+            Address::Symbol { symbol, addend } => {
+                let offset = self.len();
+                self.relocate(
+                    offset,
+                    size,
+                    RelocationTarget::Symbol(symbol),
+                    addend,
+                    false,
+                );
+                self.write_udata(0, size)
+            }
+        }
+    }
+
+    fn write_eh_pointer(
+        &mut self,
+        address: Address,
+        eh_pe: gimli::DwEhPe,
+        size: u8,
+    ) -> gimli::write::Result<()> {
+        match address {
+            Address::Constant(_) => self.data.write_eh_pointer(address, eh_pe, size),
+            Address::Symbol { symbol, addend } => {
+                let pc_relative = match eh_pe.application() {
+                    gimli::DW_EH_PE_absptr => false,
+                    gimli::DW_EH_PE_pcrel => true,
+                    _ => return Err(gimli::write::Error::UnsupportedPointerEncoding(eh_pe)),
+                };
+                let size = match eh_pe.format() {
+                    gimli::DW_EH_PE_absptr => size,
+                    gimli::DW_EH_PE_sdata4 | gimli::DW_EH_PE_udata4 => 4,
+                    gimli::DW_EH_PE_sdata8 | gimli::DW_EH_PE_udata8 => 8,
+                    _ => return Err(gimli::write::Error::UnsupportedPointerEncoding(eh_pe)),
+                };
+
+                let offset = self.len();
+                self.relocate(
+                    offset,
+                    size,
+                    RelocationTarget::Symbol(symbol),
+                    addend,
+                    pc_relative,
+                );
+                self.write_udata(0, size)
+            }
+        }
+    }
+
+    fn write_offset(
+        &mut self,
+        val: usize,
+        section: gimli::SectionId,
+        size: u8,
+    ) -> gimli::write::Result<()> {
+        let offset = self.len();
+        self.relocate(
+            offset,
+            size,
+            RelocationTarget::Section(section),
+            val as i64,
+            false,
+        );
+        self.write_udata(0, size)
+    }
+
+    fn write_offset_at(
+        &mut self,
+        offset: usize,
+        val: usize,
+        section: gimli::SectionId,
+        size: u8,
+    ) -> gimli::write::Result<()> {
+        self.relocate(
+            offset,
+            size,
+            RelocationTarget::Section(section),
+            val as i64,
+            false,
+        );
+        self.write_udata_at(offset, 0, size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generic64::x86_64::{X86_64GeneralReg, X86_64SystemV};
+    use crate::generic64::{CallConv, FrameOffsets, RegTrait};
+    use gimli::read::{BaseAddresses, CfaRule, UnwindContext, UnwindSection};
+    use object::{Architecture, Endianness, Object as _, ObjectSection};
+    use roc_region::all::Position;
+    use std::path::PathBuf;
+
+    const SRC: &str = "app [main] {}\n\nmain =\n    x = 1\n    y = x + 1\n    y\n";
+
+    fn region_of(name: &str) -> Region {
+        let start = SRC.find(name).unwrap() as u32;
+
+        Region::new(
+            Position::new(start),
+            Position::new(start + name.len() as u32),
+        )
+    }
+
+    fn read_section<'data>(
+        file: &object::File<'data>,
+        name: &str,
+    ) -> gimli::EndianSlice<'data, LittleEndian> {
+        let section = file.section_by_name(name).unwrap();
+
+        gimli::EndianSlice::new(section.data().unwrap(), LittleEndian)
+    }
+
+    /// Builds an object with one procedure for `main`, whose body has code for `x` and then `y`,
+    /// and returns the object along with the offsets of the stack setup, `y`, and the return.
+    /// Without `with_source`, the object is built as if debug info was not requested.
+    fn build_object(with_source: bool) -> (Vec<u8>, u32, u32, u32) {
+        let arena = bumpalo::Bump::new();
+        let mut interns = Interns::default();
+        let home = interns.module_id(&"Test".into());
+        let ident_ids = interns.all_ident_ids.get_or_insert(home);
+        let main = Symbol::new(home, ident_ids.add_str("main"));
+        let x = Symbol::new(home, ident_ids.add_str("x"));
+        let y = Symbol::new(home, ident_ids.add_str("y"));
+
+        let mut sources = MutMap::default();
+        sources.insert(home, (PathBuf::from("/src/Test.roc"), SRC.into()));
+        let mut def_regions = MutMap::default();
+        def_regions.insert(main, region_of("main ="));
+        let mut stmt_regions = MutMap::default();
+        stmt_regions.insert(x, region_of("x = 1"));
+        stmt_regions.insert(y, region_of("y = x + 1"));
+        let source_info = SourceInfo {
+            sources: &sources,
+            def_regions: &def_regions,
+            stmt_regions: &stmt_regions,
+        };
+
+        let saved_regs = [X86_64GeneralReg::RBX];
+        let mut code = bumpalo::vec![in &arena];
+        let stack_size = X86_64SystemV::setup_stack(&mut code, &saved_regs, &[], 8, 0);
+        let setup_end = code.len() as u32;
+        code.extend([0x90; 3]);
+        let y_offset = code.len() as u32;
+        code.extend([0x90; 3]);
+        let cleanup_start = code.len() as u32;
+        X86_64SystemV::cleanup_stack(&mut code, &saved_regs, &[], stack_size, 0);
+        let ret = code.len() as u32;
+        code.push(0xc3);
+
+        let mut call_frame_instructions = bumpalo::vec![in &arena];
+        X86_64SystemV::call_frame_instructions(
+            &mut call_frame_instructions,
+            &saved_regs,
+            &[],
+            stack_size,
+            0,
+            FrameOffsets {
+                setup_end,
+                cleanup_start,
+                ret,
+            },
+        );
+
+        let mut output = Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+        let text = output.section_id(write::StandardSection::Text);
+        let proc_id = output.add_symbol(write::Symbol {
+            name: b"roc__main".to_vec(),
+            value: 0,
+            size: 0,
+            kind: object::SymbolKind::Text,
+            scope: object::SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Section(text),
+            flags: object::SymbolFlags::None,
+        });
+        output.add_symbol_data(proc_id, text, &code, 16);
+
+        let mut debug_info = DebugInfo::new(
+            with_source.then_some(source_info),
+            home,
+            BinaryFormat::Elf,
+            X86_64SystemV::common_information_entry(),
+        );
+        debug_info.add_proc(
+            &interns,
+            "roc__main",
+            main,
+            proc_id,
+            code.len() as u64,
+            // `x` generates no code of its own here, so `y` is the statement at its offset.
+            &[(setup_end, x), (setup_end, x), (y_offset, y)],
+            call_frame_instructions,
+        );
+        debug_info.write(&mut output);
+
+        (output.write().unwrap(), setup_end, y_offset, ret)
+    }
+
+    #[test]
+    fn debug_line_has_a_row_per_nested_def() {
+        let (bytes, setup_end, y_offset, _) = build_object(true);
+        let file = object::File::parse(bytes.as_slice()).unwrap();
+        let debug_line = gimli::read::DebugLine::from(read_section(&file, ".debug_line"));
+        let program = debug_line
+            .program(gimli::DebugLineOffset(0), 8, None, None)
+            .unwrap();
+
+        let mut rows = program.rows();
+        let mut found = Vec::new();
+        while let Some((header, row)) = rows.next_row().unwrap() {
+            let file_name = match row.file(header).unwrap().path_name() {
+                gimli::read::AttributeValue::String(name) => name.to_string().unwrap().to_string(),
+                other => panic!("unexpected file name {other:?}"),
+            };
+            let column = match row.column() {
+                gimli::read::ColumnType::Column(column) => column.get(),
+                gimli::read::ColumnType::LeftEdge => 0,
+            };
+
+            found.push((
+                row.address(),
+                row.line().map_or(0, |line| line.get()),
+                column,
+                file_name,
+                row.end_sequence(),
+            ));
+        }
+
+        // The symbol's address is filled in by a relocation, so addresses are offsets into `main`.
+        let test_roc = || "Test.roc".to_string();
+        assert_eq!(
+            found,
+            vec![
+                (0, 3, 1, test_roc(), false),
+                (setup_end as u64, 4, 5, test_roc(), false),
+                (y_offset as u64, 5, 5, test_roc(), false),
+                (found.last().unwrap().0, 5, 5, test_roc(), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn eh_frame_tracks_the_cfa() {
+        let (bytes, setup_end, _, ret) = build_object(true);
+        let file = object::File::parse(bytes.as_slice()).unwrap();
+        let mut eh_frame = gimli::read::EhFrame::from(read_section(&file, ".eh_frame"));
+        eh_frame.set_address_size(8);
+
+        let bases = BaseAddresses::default().set_eh_frame(0);
+        let mut entries = eh_frame.entries(&bases);
+        let mut fde = None;
+        while let Some(entry) = entries.next().unwrap() {
+            if let gimli::read::CieOrFde::Fde(partial) = entry {
+                fde = Some(
+                    partial
+                        .parse(|eh_frame, bases, offset| eh_frame.cie_from_offset(bases, offset))
+                        .unwrap(),
+                );
+            }
+        }
+        let fde = fde.expect("no frame description entry for main");
+
+        let rsp = X86_64GeneralReg::RSP.dwarf_register();
+        let rbp = X86_64GeneralReg::RBP.dwarf_register();
+        let mut ctx = Box::new(UnwindContext::new());
+        let mut cfa_at = |offset: u32| {
+            let address = fde.initial_address() + offset as u64;
+            fde.unwind_info_for_address(&eh_frame, &bases, &mut ctx, address)
+                .unwrap()
+                .cfa()
+                .clone()
+        };
+
+        // The return address was just pushed, then rbp, then the frame is addressed from rbp.
+        assert_eq!(
+            cfa_at(0),
+            CfaRule::RegisterAndOffset {
+                register: rsp,
+                offset: 8
+            }
+        );
+        assert_eq!(
+            cfa_at(1),
+            CfaRule::RegisterAndOffset {
+                register: rsp,
+                offset: 16
+            }
+        );
+        assert_eq!(
+            cfa_at(setup_end),
+            CfaRule::RegisterAndOffset {
+                register: rbp,
+                offset: 16
+            }
+        );
+        assert_eq!(
+            cfa_at(ret),
+            CfaRule::RegisterAndOffset {
+                register: rsp,
+                offset: 8
+            }
+        );
+        assert_eq!(fde.len(), ret as u64 + 1);
+    }
+
+    #[test]
+    fn nothing_is_emitted_without_source_info() {
+        let (bytes, _, _, _) = build_object(false);
+        let file = object::File::parse(bytes.as_slice()).unwrap();
+
+        for name in [".debug_info", ".debug_line", ".eh_frame"] {
+            assert!(file.section_by_name(name).is_none(), "unexpected {name}");
+        }
+    }
+}
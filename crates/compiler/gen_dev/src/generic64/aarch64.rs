@@ -1,12 +1,13 @@
 #![allow(clippy::redundant_closure_call)]
 //|> clippy false positive: https://github.com/rust-lang/rust-clippy/issues/1553
 
-use crate::generic64::{storage::StorageManager, Assembler, CallConv, RegTrait};
+use crate::generic64::{storage::StorageManager, Assembler, CallConv, FrameOffsets, RegTrait};
 use crate::{
     pointer_layouts, single_register_floats, single_register_int_builtins,
     single_register_integers, single_register_layouts, Relocation,
 };
 use bumpalo::collections::Vec;
+use gimli::write::{CallFrameInstruction, CommonInformationEntry};
 use packed_struct::prelude::*;
 use roc_builtins::bitcode::{FloatWidth, IntWidth};
 use roc_error_macros::internal_error;
@@ -136,6 +137,11 @@ impl RegTrait for AArch64GeneralReg {
     fn value(&self) -> u8 {
         *self as u8
     }
+
+    fn dwarf_register(&self) -> gimli::Register {
+        // x0 through x30 and sp share their numbering with DWARF
+        gimli::Register(*self as u16)
+    }
 }
 
 impl std::fmt::Display for AArch64GeneralReg {
@@ -191,6 +197,10 @@ impl RegTrait for AArch64FloatReg {
     fn value(&self) -> u8 {
         *self as u8
     }
+
+    fn dwarf_register(&self) -> gimli::Register {
+        gimli::Register(gimli::AArch64::V0.0 + *self as u16)
+    }
 }
 impl std::fmt::Display for AArch64FloatReg {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -506,6 +516,77 @@ impl CallConv<AArch64GeneralReg, AArch64FloatReg, AArch64Assembler> for AArch64C
         }
     }
 
+    fn common_information_entry() -> CommonInformationEntry {
+        let encoding = gimli::Encoding {
+            format: gimli::Format::Dwarf32,
+            version: 1,
+            address_size: 8,
+        };
+        let mut cie = CommonInformationEntry::new(encoding, 4, -8, gimli::AArch64::X30);
+
+        // On entry, the return address is in the link register and nothing is on the stack yet.
+        cie.add_instruction(CallFrameInstruction::Cfa(gimli::AArch64::SP, 0));
+
+        cie
+    }
+
+    fn call_frame_instructions(
+        instructions: &mut Vec<'_, (u32, CallFrameInstruction)>,
+        saved_general_regs: &[AArch64GeneralReg],
+        saved_float_regs: &[AArch64FloatReg],
+        aligned_stack_size: i32,
+        fn_call_stack_size: i32,
+        offsets: FrameOffsets,
+    ) {
+        let frame_pointer_link_register = 16;
+
+        // Without any stack space, the frame is never set up.
+        if aligned_stack_size <= 0 {
+            return;
+        }
+
+        let fp = AArch64GeneralReg::FP.dwarf_register();
+        let lr = AArch64GeneralReg::LR.dwarf_register();
+        let sp = AArch64GeneralReg::ZRSP.dwarf_register();
+
+        // sub sp, sp, #aligned_stack_size
+        instructions.push((4, CallFrameInstruction::CfaOffset(aligned_stack_size)));
+        // str fp, [sp, #aligned_stack_size - 0x10]
+        instructions.push((8, CallFrameInstruction::Offset(fp, -0x10)));
+        // str lr, [sp, #aligned_stack_size - 0x08]
+        instructions.push((12, CallFrameInstruction::Offset(lr, -0x08)));
+        // add fp, sp, #aligned_stack_size - 0x10
+        instructions.push((
+            16,
+            CallFrameInstruction::Cfa(fp, frame_pointer_link_register),
+        ));
+
+        // The callee saved registers are stored below the frame pointer, which is 16 bytes below the CFA.
+        let mut offset = aligned_stack_size - fn_call_stack_size - frame_pointer_link_register;
+        let saved_regs = saved_general_regs
+            .iter()
+            .map(|reg| reg.dwarf_register())
+            .chain(saved_float_regs.iter().map(|reg| reg.dwarf_register()));
+        for reg in saved_regs {
+            instructions.push((
+                offsets.setup_end,
+                CallFrameInstruction::Offset(reg, -offset - frame_pointer_link_register),
+            ));
+            offset -= 8;
+        }
+
+        // The frame pointer is restored during cleanup, so switch back to the stack pointer first.
+        instructions.push((
+            offsets.cleanup_start,
+            CallFrameInstruction::Cfa(sp, aligned_stack_size),
+        ));
+
+        // add sp, sp, #aligned_stack_size
+        instructions.push((offsets.ret, CallFrameInstruction::Cfa(sp, 0)));
+        instructions.push((offsets.ret, CallFrameInstruction::Restore(fp)));
+        instructions.push((offsets.ret, CallFrameInstruction::Restore(lr)));
+    }
+
     #[inline(always)]
     fn load_args<'a>(
         buf: &mut Vec<'a, u8>,
@@ -3340,6 +3421,7 @@ fn cmp_reg64_reg64(buf: &mut Vec<'_, u8>, src1: AArch64GeneralReg, src2: AArch64
 }
 
 /// `CNEG Xd, Xn, cond` -> If cond is true, then Xd = -Xn, else Xd = Xn.
+#[allow(dead_code)]
 #[inline(always)]
 fn cneg_reg64_reg64_cond(
     buf: &mut Vec<'_, u8>,
@@ -3385,6 +3467,7 @@ fn csinc_reg64_reg64_reg64_cond(
 }
 
 /// `CSNEG Xd, Xn, Xm, cond` -> If cond is true, then Xd = Xn, else Xd = -Xm.
+#[allow(dead_code)]
 #[inline(always)]
 fn csneg_reg64_reg64_reg64_cond(
     buf: &mut Vec<'_, u8>,
@@ -1,22 +1,24 @@
 use crate::{
     pointer_layouts, single_register_floats, single_register_int_builtins,
-    single_register_integers, Backend, Env, Relocation,
+    single_register_integers, AssemblyBackendMode, Backend, Env, Relocation,
 };
 use bumpalo::collections::{CollectIn, Vec};
+use gimli::write::{CallFrameInstruction, CommonInformationEntry};
 use roc_builtins::bitcode::{self, FloatWidth, IntWidth};
 use roc_collections::all::MutMap;
 use roc_error_macros::{internal_error, todo_lambda_erasure};
 use roc_module::symbol::{Interns, ModuleId, Symbol};
 use roc_mono::code_gen_help::{CallerProc, CodeGenHelp, HelperOp};
 use roc_mono::ir::{
-    BranchInfo, HigherOrderLowLevel, JoinPointId, ListLiteralElement, Literal, Param, ProcLayout,
-    SelfRecursive, Stmt,
+    BranchInfo, HigherOrderLowLevel, JoinPointId, ListLiteralElement, Literal, LookupType, Param,
+    ProcLayout, SelfRecursive, Stmt,
 };
 use roc_mono::layout::{
     Builtin, InLayout, LambdaName, Layout, LayoutIds, LayoutInterner, LayoutRepr, STLayoutInterner,
     TagIdIntType, UnionLayout,
 };
 use roc_mono::low_level::HigherOrder;
+use roc_region::all::Region;
 use roc_target::Target;
 use std::marker::PhantomData;
 
@@ -33,6 +35,17 @@ use storage::{RegStorage, StorageManager};
 // NOTE: must fit in 27 bits and aligned to 4 for aarch64
 const JUMP_PLACEHOLDER: i32 = 0x0011_1100;
 
+/// Where in a procedure its stack frame is set up and torn down.
+#[derive(Debug, Clone, Copy)]
+pub struct FrameOffsets {
+    /// The end of the code emitted by `CallConv::setup_stack`.
+    pub setup_end: u32,
+    /// The start of the code emitted by `CallConv::cleanup_stack`.
+    pub cleanup_start: u32,
+    /// The return instruction that follows `CallConv::cleanup_stack`.
+    pub ret: u32,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum RegisterWidth {
     W8 = 0b00,
@@ -95,6 +108,21 @@ pub trait CallConv<GeneralReg: RegTrait, FloatReg: RegTrait, ASM: Assembler<Gene
         fn_call_stack_size: i32,
     );
 
+    /// The call frame rules that hold when a procedure is entered.
+    fn common_information_entry() -> CommonInformationEntry;
+
+    /// call_frame_instructions describes the frame built by setup_stack and torn down by cleanup_stack,
+    /// so that debuggers and profilers can unwind through it.
+    /// Each instruction is paired with the code offset from which it applies.
+    fn call_frame_instructions(
+        instructions: &mut Vec<'_, (u32, CallFrameInstruction)>,
+        saved_general_regs: &[GeneralReg],
+        saved_float_regs: &[FloatReg],
+        aligned_stack_size: i32,
+        fn_call_stack_size: i32,
+        offsets: FrameOffsets,
+    );
+
     /// load_args updates the storage manager to know where every arg is stored.
     fn load_args<'a>(
         buf: &mut Vec<'a, u8>,
@@ -745,6 +773,9 @@ pub trait RegTrait:
     Copy + PartialEq + Eq + std::hash::Hash + std::fmt::Debug + std::fmt::Display + 'static
 {
     fn value(&self) -> u8;
+
+    /// The number DWARF uses for this register.
+    fn dwarf_register(&self) -> gimli::Register;
 }
 
 pub struct Backend64Bit<
@@ -776,6 +807,8 @@ pub struct Backend64Bit<
 
     literal_map: MutMap<Symbol, (*const Literal<'a>, *const InLayout<'a>)>,
     join_map: MutMap<JoinPointId, Vec<'a, (u64, u64)>>,
+    call_frame_instructions: Vec<'a, (u32, CallFrameInstruction)>,
+    statement_offsets: Vec<'a, (u32, Symbol)>,
 
     storage_manager: StorageManager<'a, 'r, GeneralReg, FloatReg, ASM, CC>,
 }
@@ -812,6 +845,8 @@ pub fn new_backend_64bit<
         free_map: MutMap::default(),
         literal_map: MutMap::default(),
         join_map: MutMap::default(),
+        call_frame_instructions: bumpalo::vec![in env.arena],
+        statement_offsets: bumpalo::vec![in env.arena],
         storage_manager: storage::new_storage_manager(env, target),
     }
 }
@@ -885,6 +920,31 @@ impl<
     fn caller_procs(&self) -> &Vec<'a, CallerProc<'a>> {
         &self.caller_procs
     }
+    fn common_information_entry(&self) -> CommonInformationEntry {
+        CC::common_information_entry()
+    }
+    fn take_call_frame_instructions(&mut self) -> Vec<'a, (u32, CallFrameInstruction)> {
+        std::mem::replace(
+            &mut self.call_frame_instructions,
+            bumpalo::vec![in self.env.arena],
+        )
+    }
+    fn mark_statement(&mut self, symbol: Symbol) {
+        let from_source = match self.env.source_info {
+            Some(source_info) => source_info.stmt_regions.contains_key(&symbol),
+            None => false,
+        };
+
+        if from_source {
+            self.statement_offsets.push((self.buf.len() as u32, symbol));
+        }
+    }
+    fn take_statement_offsets(&mut self) -> Vec<'a, (u32, Symbol)> {
+        std::mem::replace(
+            &mut self.statement_offsets,
+            bumpalo::vec![in self.env.arena],
+        )
+    }
 
     fn reset(&mut self, name: String, is_self_recursive: SelfRecursive) {
         self.proc_name = Some(name);
@@ -894,6 +954,7 @@ impl<
         self.join_map.clear();
         self.free_map.clear();
         self.buf.clear();
+        self.statement_offsets.clear();
         self.storage_manager.reset();
     }
 
@@ -983,7 +1044,14 @@ impl<
         // Add function body.
         out.extend(&self.buf[..self.buf.len() - end_jmp_size]);
 
+        // Statements are now after the stack setup, and none of them can start in the removed jump.
+        let body_end = out.len() as u32;
+        for (offset, _) in self.statement_offsets.iter_mut() {
+            *offset = (*offset + setup_offset as u32).min(body_end);
+        }
+
         // Cleanup stack.
+        let cleanup_start = out.len();
         CC::cleanup_stack(
             &mut out,
             &used_general_regs,
@@ -991,8 +1059,23 @@ impl<
             aligned_stack_size,
             self.storage_manager.fn_call_stack_size() as i32,
         );
+        let ret = out.len();
         ASM::ret(&mut out);
 
+        self.call_frame_instructions.clear();
+        CC::call_frame_instructions(
+            &mut self.call_frame_instructions,
+            &used_general_regs,
+            &used_float_regs,
+            aligned_stack_size,
+            self.storage_manager.fn_call_stack_size() as i32,
+            FrameOffsets {
+                setup_end: setup_offset as u32,
+                cleanup_start: cleanup_start as u32,
+                ret: ret as u32,
+            },
+        );
+
         // Update other relocs to include stack setup offset.
         let mut out_relocs = bumpalo::vec![in self.env.arena];
         out_relocs.extend(
@@ -1156,6 +1239,58 @@ impl<
         }
     }
 
+    fn build_expect(
+        &mut self,
+        condition: &Symbol,
+        region: Region,
+        lookups: &[Symbol],
+        variables: &[LookupType],
+    ) {
+        // The failure is reported in code that is skipped over when the condition holds.
+        // Everything is moved to the stack first, so that values stay where the storage manager
+        // expects them whether or not that code ran.
+        self.storage_manager.free_all_to_stack(&mut self.buf);
+
+        let base_storage = self.storage_manager.clone();
+        let base_literal_map = self.literal_map.clone();
+
+        let cond_reg = self
+            .storage_manager
+            .load_to_general_reg(&mut self.buf, condition);
+
+        // Since we don't know the offset yet, set it to 0 and overwrite later.
+        let jne_location = self.buf.len();
+        let start_offset =
+            ASM::jne_reg64_imm64_imm32(&mut self.buf, &mut self.storage_manager, cond_reg, 0, 0);
+
+        self.expect_failed(*condition, region, lookups, variables);
+
+        // Overwrite the original jne with the correct offset.
+        let mut tmp = bumpalo::vec![in self.env.arena];
+        let jne_offset = self.buf.len() - start_offset;
+        ASM::jne_reg64_imm64_imm32(
+            &mut tmp,
+            &mut self.storage_manager,
+            cond_reg,
+            0,
+            jne_offset as i32,
+        );
+        self.buf[jne_location..][..tmp.len()].copy_from_slice(tmp.as_slice());
+
+        let stack_size = self.storage_manager.stack_size();
+        let fn_call_stack_size = self.storage_manager.fn_call_stack_size();
+        let used_callee_saved_regs = self.storage_manager.used_callee_saved_regs.clone();
+
+        self.storage_manager = base_storage;
+        self.literal_map = base_literal_map;
+        self.storage_manager.update_stack_size(stack_size);
+        self.storage_manager
+            .update_fn_call_stack_size(fn_call_stack_size);
+        self.storage_manager
+            .used_callee_saved_regs
+            .extend(&used_callee_saved_regs);
+    }
+
     fn build_switch(
         &mut self,
         layout_ids: &mut LayoutIds<'a>,
@@ -1313,11 +1448,20 @@ impl<
 
     fn build_num_abs(&mut self, dst: &Symbol, src: &Symbol, layout: &InLayout<'a>) {
         match self.interner().get_repr(*layout) {
-            LayoutRepr::Builtin(Builtin::Int(IntWidth::I64 | IntWidth::U64)) => {
-                let dst_reg = self.storage_manager.claim_general_reg(&mut self.buf, dst);
-                let src_reg = self.storage_manager.load_to_general_reg(&mut self.buf, src);
-                ASM::abs_reg64_reg64(&mut self.buf, dst_reg, src_reg);
-            }
+            LayoutRepr::Builtin(Builtin::Int(int_width)) => self.build_fn_call(
+                dst,
+                bitcode::NUM_ABS_OR_PANIC_INT[int_width].to_string(),
+                &[*src],
+                &[*layout],
+                layout,
+            ),
+            LayoutRepr::Builtin(Builtin::Decimal) => self.build_fn_call(
+                dst,
+                bitcode::DEC_ABS.to_string(),
+                &[*src],
+                &[*layout],
+                layout,
+            ),
             LayoutRepr::Builtin(Builtin::Float(FloatWidth::F64)) => {
                 let dst_reg = self.storage_manager.claim_float_reg(&mut self.buf, dst);
                 let src_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src);
@@ -1328,7 +1472,7 @@ impl<
                 let src_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src);
                 ASM::abs_freg32_freg32(&mut self.buf, &mut self.relocs, dst_reg, src_reg);
             }
-            x => todo!("NumAbs: layout, {:?}", x),
+            x => internal_error!("NumAbs is not defined for {:?}", x),
         }
     }
 
@@ -1385,6 +1529,9 @@ impl<
                     .load_to_general_reg(&mut self.buf, src2);
                 ASM::add_reg64_reg64_reg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
             }
+            LayoutRepr::Builtin(Builtin::Int(IntWidth::I128 | IntWidth::U128)) => {
+                self.add_wrap_128bit(dst, src1, src2)
+            }
 
             LayoutRepr::Builtin(Builtin::Float(FloatWidth::F64)) => {
                 let dst_reg = self.storage_manager.claim_float_reg(&mut self.buf, dst);
@@ -1419,7 +1566,7 @@ impl<
         layout: InLayout<'a>,
     ) {
         match self.layout_interner.get_repr(layout) {
-            LayoutRepr::Builtin(Builtin::Int(width @ quadword_and_smaller!())) => {
+            LayoutRepr::Builtin(Builtin::Int(width)) => {
                 let intrinsic = bitcode::NUM_ADD_SATURATED_INT[width].to_string();
                 self.build_fn_call(&dst, intrinsic, &[src1, src2], &[layout, layout], &layout);
             }
@@ -1439,7 +1586,7 @@ impl<
                 let intrinsic = bitcode::DEC_ADD_SATURATED.to_string();
                 self.build_fn_call(&dst, intrinsic, &[src1, src2], &[layout, layout], &layout);
             }
-            x => todo!("NumAddSaturated: layout, {:?}", x),
+            x => internal_error!("NumAddSaturated is not defined for {:?}", x),
         }
     }
 
@@ -1567,13 +1714,7 @@ impl<
                     src2_reg,
                 );
             }
-            LayoutRepr::Builtin(Builtin::Int(IntWidth::I128 | IntWidth::U128)) => {
-                let int_width = match *layout {
-                    Layout::I128 => IntWidth::I128,
-                    Layout::U128 => IntWidth::U128,
-                    _ => unreachable!(),
-                };
-
+            LayoutRepr::Builtin(Builtin::Int(int_width @ (IntWidth::I128 | IntWidth::U128))) => {
                 self.build_fn_call(
                     dst,
                     bitcode::NUM_MUL_WRAP_INT[int_width].to_string(),
@@ -1594,7 +1735,7 @@ impl<
                 let src2_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src2);
                 ASM::mul_freg32_freg32_freg32(&mut self.buf, dst_reg, src1_reg, src2_reg);
             }
-            x => todo!("NumMulWrap: layout, {:?}", x),
+            x => internal_error!("NumMulWrap is not defined for {:?}", x),
         }
     }
 
@@ -1606,7 +1747,7 @@ impl<
         layout: InLayout<'a>,
     ) {
         match self.layout_interner.get_repr(layout) {
-            LayoutRepr::Builtin(Builtin::Int(width @ quadword_and_smaller!())) => {
+            LayoutRepr::Builtin(Builtin::Int(width)) => {
                 let intrinsic = bitcode::NUM_MUL_SATURATED_INT[width].to_string();
                 self.build_fn_call(&dst, intrinsic, &[src1, src2], &[layout, layout], &layout);
             }
@@ -1626,7 +1767,7 @@ impl<
                 let intrinsic = bitcode::DEC_MUL_SATURATED.to_string();
                 self.build_fn_call(&dst, intrinsic, &[src1, src2], &[layout, layout], &layout);
             }
-            x => todo!("NumMulSaturated: layout, {:?}", x),
+            x => internal_error!("NumMulSaturated is not defined for {:?}", x),
         }
     }
 
@@ -1706,6 +1847,15 @@ impl<
                 let src2_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src2);
                 ASM::div_freg32_freg32_freg32(&mut self.buf, dst_reg, src1_reg, src2_reg);
             }
+            LayoutRepr::Builtin(Builtin::Int(int_width @ (IntWidth::I128 | IntWidth::U128))) => {
+                self.build_fn_call(
+                    dst,
+                    bitcode::NUM_DIV_TRUNC[int_width].to_string(),
+                    &[*src1, *src2],
+                    &[*layout, *layout],
+                    layout,
+                );
+            }
             LayoutRepr::Builtin(Builtin::Decimal) => {
                 self.build_fn_call(
                     dst,
@@ -1715,7 +1865,7 @@ impl<
                     layout,
                 );
             }
-            x => todo!("NumDiv: layout, {:?}", x),
+            x => internal_error!("NumDiv is not defined for {:?}", x),
         }
     }
 
@@ -1734,7 +1884,7 @@ impl<
                 &[*layout, *layout],
                 layout,
             ),
-            x => todo!("NumDivCeilUnchecked: layout, {:?}", x),
+            x => internal_error!("NumDivCeilUnchecked is not defined for {:?}", x),
         }
     }
 
@@ -1778,18 +1928,70 @@ impl<
                     src2_reg,
                 );
             }
-            x => todo!("NumDiv: layout, {:?}", x),
+            LayoutRepr::Builtin(Builtin::Int(int_width @ (IntWidth::I128 | IntWidth::U128))) => {
+                self.build_fn_call(
+                    dst,
+                    bitcode::NUM_REM[int_width].to_string(),
+                    &[*src1, *src2],
+                    &[*layout, *layout],
+                    layout,
+                );
+            }
+            x => internal_error!("NumRem is not defined for {:?}", x),
         }
     }
 
     fn build_num_neg(&mut self, dst: &Symbol, src: &Symbol, layout: &InLayout<'a>) {
         match self.layout_interner.get_repr(*layout) {
-            LayoutRepr::Builtin(Builtin::Int(IntWidth::I64 | IntWidth::U64)) => {
+            LayoutRepr::Builtin(Builtin::Int(quadword_and_smaller!())) => {
                 let dst_reg = self.storage_manager.claim_general_reg(&mut self.buf, dst);
                 let src_reg = self.storage_manager.load_to_general_reg(&mut self.buf, src);
                 ASM::neg_reg64_reg64(&mut self.buf, dst_reg, src_reg);
             }
-            x => todo!("NumNeg: layout, {:?}", x),
+            LayoutRepr::Builtin(Builtin::Float(float_width)) => {
+                let dst_reg = self.storage_manager.claim_float_reg(&mut self.buf, dst);
+                let src_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src);
+
+                // multiplying by -1 flips the sign bit, also for zero
+                let minus_one = self.debug_symbol("minus_one");
+                let minus_one_reg = self
+                    .storage_manager
+                    .claim_float_reg(&mut self.buf, &minus_one);
+
+                match float_width {
+                    FloatWidth::F64 => {
+                        ASM::mov_freg64_imm64(&mut self.buf, &mut self.relocs, minus_one_reg, -1.0);
+                        ASM::mul_freg64_freg64_freg64(
+                            &mut self.buf,
+                            dst_reg,
+                            src_reg,
+                            minus_one_reg,
+                        );
+                    }
+                    FloatWidth::F32 => {
+                        ASM::mov_freg32_imm32(&mut self.buf, &mut self.relocs, minus_one_reg, -1.0);
+                        ASM::mul_freg32_freg32_freg32(
+                            &mut self.buf,
+                            dst_reg,
+                            src_reg,
+                            minus_one_reg,
+                        );
+                    }
+                }
+
+                self.free_symbol(&minus_one);
+            }
+            LayoutRepr::Builtin(Builtin::Int(IntWidth::I128 | IntWidth::U128)) => {
+                self.neg_128bit(dst, src)
+            }
+            LayoutRepr::Builtin(Builtin::Decimal) => self.build_fn_call(
+                dst,
+                bitcode::DEC_NEGATE.to_string(),
+                &[*src],
+                &[*layout],
+                layout,
+            ),
+            x => internal_error!("NumNeg is not defined for {:?}", x),
         }
     }
 
@@ -1846,7 +2048,22 @@ impl<
                     .load_to_general_reg(&mut self.buf, src2);
                 ASM::sub_reg64_reg64_reg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
             }
-            x => todo!("NumSubWrap: layout, {:?}", x),
+            LayoutRepr::Builtin(Builtin::Int(IntWidth::I128 | IntWidth::U128)) => {
+                self.sub_wrap_128bit(dst, src1, src2)
+            }
+            LayoutRepr::Builtin(Builtin::Float(FloatWidth::F64)) => {
+                let dst_reg = self.storage_manager.claim_float_reg(&mut self.buf, dst);
+                let src1_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src1);
+                let src2_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src2);
+                ASM::sub_freg64_freg64_freg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
+            }
+            LayoutRepr::Builtin(Builtin::Float(FloatWidth::F32)) => {
+                let dst_reg = self.storage_manager.claim_float_reg(&mut self.buf, dst);
+                let src1_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src1);
+                let src2_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src2);
+                ASM::sub_freg32_freg32_freg32(&mut self.buf, dst_reg, src1_reg, src2_reg);
+            }
+            x => internal_error!("NumSubWrap is not defined for {:?}", x),
         }
     }
 
@@ -2080,7 +2297,7 @@ impl<
             Layout::F64 => self.num_to_f64(dst, src, arg_layout),
             Layout::DEC => self.num_to_dec(dst, src, arg_layout),
 
-            other => todo!("NumToFrac: layout {other:?} is not Frac"),
+            other => internal_error!("NumToFrac: layout {other:?} is not Frac"),
         }
     }
 
@@ -4648,7 +4865,9 @@ impl<
         let buf = &mut self.buf;
 
         match int_width {
-            IntWidth::U128 | IntWidth::I128 => todo!(),
+            IntWidth::U128 | IntWidth::I128 => {
+                self.int128_halves(dst, src1, src2, ASM::and_reg64_reg64_reg64)
+            }
             _ => {
                 let dst_reg = self.storage_manager.claim_general_reg(buf, dst);
                 let src1_reg = self.storage_manager.load_to_general_reg(buf, src1);
@@ -4668,7 +4887,9 @@ impl<
         let buf = &mut self.buf;
 
         match int_width {
-            IntWidth::U128 | IntWidth::I128 => todo!(),
+            IntWidth::U128 | IntWidth::I128 => {
+                self.int128_halves(dst, src1, src2, ASM::or_reg64_reg64_reg64)
+            }
             _ => {
                 let dst_reg = self.storage_manager.claim_general_reg(buf, dst);
                 let src1_reg = self.storage_manager.load_to_general_reg(buf, src1);
@@ -4688,7 +4909,9 @@ impl<
         let buf = &mut self.buf;
 
         match int_width {
-            IntWidth::U128 | IntWidth::I128 => todo!(),
+            IntWidth::U128 | IntWidth::I128 => {
+                self.int128_halves(dst, src1, src2, ASM::xor_reg64_reg64_reg64)
+            }
             _ => {
                 let dst_reg = self.storage_manager.claim_general_reg(buf, dst);
                 let src1_reg = self.storage_manager.load_to_general_reg(buf, src1);
@@ -4708,7 +4931,17 @@ impl<
         let buf = &mut self.buf;
 
         match int_width {
-            IntWidth::U128 | IntWidth::I128 => todo!(),
+            IntWidth::U128 | IntWidth::I128 => {
+                let layout = Layout::from_int_width(int_width);
+
+                self.build_fn_call(
+                    dst,
+                    bitcode::NUM_SHIFT_LEFT_BY[int_width].to_string(),
+                    &[*src1, *src2],
+                    &[layout, Layout::U8],
+                    &layout,
+                )
+            }
             _ => {
                 let dst_reg = self.storage_manager.claim_general_reg(buf, dst);
                 let src1_reg = self.storage_manager.load_to_general_reg(buf, src1);
@@ -4735,7 +4968,17 @@ impl<
         let buf = &mut self.buf;
 
         match int_width {
-            IntWidth::U128 | IntWidth::I128 => todo!(),
+            IntWidth::U128 | IntWidth::I128 => {
+                let layout = Layout::from_int_width(int_width);
+
+                self.build_fn_call(
+                    dst,
+                    bitcode::NUM_SHIFT_RIGHT_BY[int_width].to_string(),
+                    &[*src1, *src2],
+                    &[layout, Layout::U8],
+                    &layout,
+                )
+            }
             _ => {
                 let dst_reg = self.storage_manager.claim_general_reg(buf, dst);
                 let src1_reg = self.storage_manager.load_to_general_reg(buf, src1);
@@ -4813,7 +5056,7 @@ impl<
                     dst,
                     bitcode::NUM_SHIFT_RIGHT_ZERO_FILL[int_width].to_string(),
                     &[*src1, *src2],
-                    &[layout, layout],
+                    &[layout, Layout::U8],
                     &layout,
                 );
             }
@@ -4852,114 +5095,88 @@ impl<
         source: IntWidth,
         target: IntWidth,
     ) {
-        use IntWidth::*;
-
-        let buf = &mut self.buf;
-
-        match (source, target) {
-            (U128, U64) => {
-                let dst_reg = self.storage_manager.claim_general_reg(buf, dst);
-
-                let (offset, _size) = self.storage_manager.stack_offset_and_size(src);
-
-                ASM::mov_reg64_base32(buf, dst_reg, offset + 8);
-
-                return;
-            }
-            (U64, U128) => {
-                let src_reg = self.storage_manager.load_to_general_reg(buf, src);
+        let register_width = |width: IntWidth| {
+            RegisterWidth::try_from_layout(LayoutRepr::Builtin(Builtin::Int(width)))
+        };
 
-                let base_offset = self.storage_manager.claim_stack_area_layout(
+        match (register_width(source), register_width(target)) {
+            (None, None) => {
+                // between I128 and U128, the bits stay the same
+                let to_offset = self.storage_manager.claim_stack_area_layout(
                     self.layout_interner,
                     *dst,
-                    Layout::U128,
+                    Layout::from_int_width(target),
                 );
 
-                let tmp = Symbol::DEV_TMP;
-                let tmp_reg = self.storage_manager.claim_general_reg(buf, &tmp);
-
-                // move a zero into the lower 8 bytes
-                ASM::mov_reg64_imm64(buf, tmp_reg, 0x0);
-                ASM::mov_base32_reg64(buf, base_offset + 8, tmp_reg);
-
-                ASM::mov_base32_reg64(buf, base_offset, src_reg);
+                let (from_offset, size) = self.storage_manager.stack_offset_and_size(src);
 
-                self.free_symbol(&tmp);
+                self.storage_manager.copy_to_stack_offset(
+                    &mut self.buf,
+                    size,
+                    from_offset,
+                    to_offset,
+                );
+            }
+            (None, Some(target_width)) => {
+                // truncate by only reading the lower bytes
+                let dst_reg = self.storage_manager.claim_general_reg(&mut self.buf, dst);
+                let (offset, _) = self.storage_manager.stack_offset_and_size(src);
 
-                return;
+                ASM::mov_reg_base32(&mut self.buf, target_width, dst_reg, offset);
             }
-            (U128, I128) | (I128, U128) => {
-                let to_offset = self.storage_manager.claim_stack_area_layout(
+            (Some(source_width), None) => {
+                let src_reg = self.storage_manager.load_to_general_reg(&mut self.buf, src);
+                let base_offset = self.storage_manager.claim_stack_area_layout(
                     self.layout_interner,
                     *dst,
                     Layout::from_int_width(target),
                 );
 
-                let (from_offset, size) = self.storage_manager.stack_offset_and_size(src);
-
-                self.storage_manager
-                    .copy_to_stack_offset(buf, size, from_offset, to_offset);
-
-                return;
-            }
-
-            _ => {}
-        }
+                let (symbols, [lower, upper, zero]) = self.claim_tmp_general_regs();
+                let buf = &mut self.buf;
 
-        let dst_reg = self.storage_manager.claim_general_reg(buf, dst);
-        let src_reg = self.storage_manager.load_to_general_reg(buf, src);
+                ASM::mov_reg64_imm64(buf, zero, 0);
 
-        if source.stack_size() == target.stack_size() {
-            match source.stack_size() {
-                8 => ASM::mov_reg64_reg64(buf, dst_reg, src_reg),
-                4 => ASM::mov_reg32_reg32(buf, dst_reg, src_reg),
-                2 => ASM::mov_reg16_reg16(buf, dst_reg, src_reg),
-                1 => ASM::mov_reg8_reg8(buf, dst_reg, src_reg),
-                _ => todo!("int cast from {source:?} to {target:?}"),
-            }
-        } else {
-            match (source, target) {
-                // -- CASTING UP --
-                (I8 | U8, U16 | U32 | U64) => {
-                    // zero  out the register
-                    ASM::xor_reg64_reg64_reg64(buf, dst_reg, dst_reg, dst_reg);
-
-                    // move the 8-bit integer
-                    ASM::mov_reg_reg(buf, RegisterWidth::W8, dst_reg, src_reg);
+                // the upper 8 bytes are all ones for negative numbers, and zero otherwise
+                if source.is_signed() {
+                    ASM::movsx_reg_reg(buf, source_width, lower, src_reg);
+                    ASM::signed_compare_reg64(
+                        buf,
+                        RegisterWidth::W64,
+                        CompareOperation::LessThan,
+                        upper,
+                        lower,
+                        zero,
+                    );
+                    ASM::neg_reg64_reg64(buf, upper, upper);
+                } else {
+                    ASM::movzx_reg_reg(buf, source_width, lower, src_reg);
+                    ASM::mov_reg64_reg64(buf, upper, zero);
                 }
-                (U16, U32 | U64) => {
-                    // zero  out the register
-                    ASM::xor_reg64_reg64_reg64(buf, dst_reg, dst_reg, dst_reg);
 
-                    // move the 16-bit integer
-                    ASM::mov_reg_reg(buf, RegisterWidth::W16, dst_reg, src_reg);
-                }
-                (U32, U64) => {
-                    // zero  out the register
-                    ASM::xor_reg64_reg64_reg64(buf, dst_reg, dst_reg, dst_reg);
+                ASM::mov_base32_reg64(buf, base_offset, lower);
+                ASM::mov_base32_reg64(buf, base_offset + 8, upper);
 
-                    // move the 32-bit integer
-                    ASM::mov_reg_reg(buf, RegisterWidth::W32, dst_reg, src_reg);
-                }
-                (I8, I16 | I32 | I64) => {
-                    ASM::movsx_reg_reg(buf, RegisterWidth::W8, dst_reg, src_reg)
-                }
-                (I16, I32 | I64) => ASM::movsx_reg_reg(buf, RegisterWidth::W16, dst_reg, src_reg),
-                (I32, I64) => ASM::movsx_reg_reg(buf, RegisterWidth::W32, dst_reg, src_reg),
-                // -- CASTING DOWN --
-                (U64 | I64, I32 | U32) => {
-                    // move as a 32-bit integer (leaving any other bits behind)
-                    ASM::mov_reg_reg(buf, RegisterWidth::W32, dst_reg, src_reg);
-                }
-                (U64 | I64 | U32 | I32, I16 | U16) => {
-                    // move as a 16-bit integer (leaving any other bits behind)
-                    ASM::mov_reg_reg(buf, RegisterWidth::W16, dst_reg, src_reg);
+                for symbol in symbols {
+                    self.free_symbol(&symbol);
                 }
-                (U64 | I64 | U32 | I32 | U16 | I16, I8 | U8) => {
-                    // move as an 8-bit integer (leaving any other bits behind)
-                    ASM::mov_reg_reg(buf, RegisterWidth::W8, dst_reg, src_reg);
+            }
+            (Some(source_width), Some(target_width)) => {
+                let dst_reg = self.storage_manager.claim_general_reg(&mut self.buf, dst);
+                let src_reg = self.storage_manager.load_to_general_reg(&mut self.buf, src);
+                let buf = &mut self.buf;
+
+                if source.stack_size() < target.stack_size() {
+                    // casting up extends based on the sign of the source
+                    if source.is_signed() {
+                        ASM::movsx_reg_reg(buf, source_width, dst_reg, src_reg);
+                    } else {
+                        ASM::movzx_reg_reg(buf, source_width, dst_reg, src_reg);
+                    }
+                } else {
+                    // casting down moves the lower bits, leaving any other bits behind
+                    ASM::mov_reg_reg(buf, target_width, dst_reg, src_reg);
                 }
-                _ => todo!("int cast from {source:?} to {target:?}"),
             }
         }
     }
@@ -4976,6 +5193,187 @@ impl<
         CC: CallConv<GeneralReg, FloatReg, ASM>,
     > Backend64Bit<'a, 'r, GeneralReg, FloatReg, ASM, CC>
 {
+    /// Writes a failed expect to the shared memory, in the same format as the LLVM backend:
+    /// the region and module of the expect, then the offset and type variable of each lookup,
+    /// followed by the values of the lookups.
+    fn expect_failed(
+        &mut self,
+        condition: Symbol,
+        region: Region,
+        lookups: &[Symbol],
+        variables: &[LookupType],
+    ) {
+        // `roc test` runs the expects in its own process, and hands us a buffer to write to.
+        // A `roc dev` program instead shares a file with its parent process.
+        let start_function = match self.env.mode {
+            AssemblyBackendMode::CliTest => bitcode::UTILS_EXPECT_FAILED_START_SHARED_BUFFER,
+            _ => bitcode::UTILS_EXPECT_FAILED_START_SHARED_FILE,
+        };
+
+        let buffer = self.debug_symbol("expect_buffer");
+        self.build_fn_call(&buffer, start_function.to_string(), &[], &[], &Layout::U64);
+
+        // The shared memory starts with the number of failures, and the offset of the first
+        // free byte.
+        let count = self.debug_symbol("expect_count");
+        self.build_ptr_load(count, buffer, Layout::U64);
+
+        let zero = self.debug_symbol("zero");
+        self.load_literal_i64(&zero, 0);
+        let offset_offset = self.expect_offset_add(zero, 8);
+        let start = self.debug_symbol("expect_offset");
+        let offset_ptr = self.debug_symbol("expect_offset_ptr");
+        self.build_num_add(&offset_ptr, &buffer, &offset_offset, &Layout::U64);
+        self.build_ptr_load(start, offset_ptr, Layout::U64);
+
+        // The header of this failure
+        let module_id: u32 = unsafe { std::mem::transmute(condition.module_id()) };
+        let mut offset = start;
+        for value in [region.start().offset, region.end().offset, module_id] {
+            let value_sym = self.debug_symbol("expect_header");
+            self.load_literal(
+                &value_sym,
+                &Layout::U32,
+                &Literal::Int((value as i128).to_ne_bytes()),
+            );
+            self.expect_store(buffer, offset, value_sym, Layout::U32);
+            offset = self.expect_offset_add(offset, 4);
+        }
+
+        let after_header = offset;
+
+        // Space for the offset and type variable of each lookup
+        let space_for_offsets = lookups.len() as u64 * (8 + 4);
+        offset = self.expect_offset_add(offset, space_for_offsets);
+
+        let mut lookup_starts = bumpalo::vec![in self.env.arena];
+        for lookup in lookups {
+            lookup_starts.push(offset);
+
+            let layout = *self.layout_map.get(lookup).unwrap_or_else(|| {
+                internal_error!("the lookup, {:?}, has no known layout", lookup)
+            });
+            let stack_size = self.layout_interner.stack_size(layout);
+            let extra_offset = self.expect_offset_add(offset, stack_size as u64);
+
+            offset = self.expect_clone(buffer, offset, extra_offset, *lookup, layout);
+        }
+
+        let mut lookup_offset = after_header;
+        for (lookup_start, variable) in lookup_starts.into_iter().zip(variables) {
+            self.expect_store(buffer, lookup_offset, lookup_start, Layout::U64);
+            lookup_offset = self.expect_offset_add(lookup_offset, 8);
+
+            let variable_sym = self.debug_symbol("expect_variable");
+            self.load_literal(
+                &variable_sym,
+                &Layout::U32,
+                &Literal::Int((variable.index() as i128).to_ne_bytes()),
+            );
+            self.expect_store(buffer, lookup_offset, variable_sym, Layout::U32);
+            lookup_offset = self.expect_offset_add(lookup_offset, 4);
+        }
+
+        let new_count = self.expect_offset_add(count, 1);
+        self.expect_store(buffer, zero, new_count, Layout::U64);
+        self.expect_store(buffer, offset_offset, offset, Layout::U64);
+
+        // `roc test` reads the buffer once the expect returns, so there is no parent to wait for
+        if !matches!(self.env.mode, AssemblyBackendMode::CliTest) {
+            self.build_fn_call(
+                &Symbol::DEV_TMP,
+                bitcode::NOTIFY_PARENT_EXPECT.to_string(),
+                &[buffer],
+                &[Layout::U64],
+                &Layout::UNIT,
+            );
+            self.free_symbol(&Symbol::DEV_TMP);
+        }
+    }
+
+    /// Copies `value` to `offset` in the shared memory, and the data it points to from
+    /// `extra_offset` on. Returns the offset after that data.
+    fn expect_clone(
+        &mut self,
+        buffer: Symbol,
+        offset: Symbol,
+        extra_offset: Symbol,
+        value: Symbol,
+        layout: InLayout<'a>,
+    ) -> Symbol {
+        if self.layout_interner.safe_to_memcpy(layout) {
+            self.expect_store(buffer, offset, value, layout);
+
+            return extra_offset;
+        }
+
+        // Anything with pointers is cloned by a helper proc, which recurses into the pointers
+        let ident_ids = self
+            .interns
+            .all_ident_ids
+            .get_mut(&self.env.module_id)
+            .unwrap();
+
+        let (clone_symbol, clone_linker_data) = self.helper_proc_gen.gen_refcount_proc(
+            ident_ids,
+            self.layout_interner,
+            layout,
+            HelperOp::CloneTo,
+        );
+
+        // the helper proc takes and returns offsets as isize
+        let isize = Layout::isize(self.target());
+        let arg_layouts = [layout, isize, isize, isize];
+
+        let fn_name = self.lambda_name_to_string(
+            LambdaName::no_niche(clone_symbol),
+            arg_layouts.into_iter(),
+            None,
+            isize,
+        );
+
+        self.helper_proc_symbols.extend(clone_linker_data);
+
+        let new_offset = self.debug_symbol("expect_extra_offset");
+        self.build_fn_call(
+            &new_offset,
+            fn_name,
+            &[value, buffer, offset, extra_offset],
+            &arg_layouts,
+            &isize,
+        );
+
+        new_offset
+    }
+
+    /// Stores `value` at `offset` in the shared memory.
+    fn expect_store(
+        &mut self,
+        buffer: Symbol,
+        offset: Symbol,
+        value: Symbol,
+        layout: InLayout<'a>,
+    ) {
+        let ptr = self.debug_symbol("expect_ptr");
+        let ignored = self.debug_symbol("ignored");
+        self.build_num_add(&ptr, &buffer, &offset, &Layout::U64);
+        self.build_ptr_store(ignored, ptr, value, layout);
+
+        self.free_symbol(&ptr);
+        self.free_symbol(&ignored);
+    }
+
+    fn expect_offset_add(&mut self, offset: Symbol, amount: u64) -> Symbol {
+        let amount_sym = self.debug_symbol("amount");
+        self.load_literal_i64(&amount_sym, amount as i64);
+
+        let new_offset = self.debug_symbol("expect_offset");
+        self.build_num_add(&new_offset, &offset, &amount_sym, &Layout::U64);
+        self.free_symbol(&amount_sym);
+
+        new_offset
+    }
+
     fn build_fn_call_stack_return<const N: usize>(
         &mut self,
         function_name: String,
@@ -5045,7 +5443,19 @@ impl<
                 let int_width = arg_layout.to_int_width();
                 self.build_int_to_float_cast(dst, src, int_width, FloatWidth::F32);
             }
-            arg => todo!("NumToFrac: layout, arg {arg:?}, ret {:?}", Layout::F32),
+            LayoutRepr::Builtin(Builtin::Decimal) => {
+                let as_f64 = self.debug_symbol("dec_as_f64");
+                self.num_to_f64(&as_f64, src, arg_layout);
+
+                let dst_reg = self.storage_manager.claim_float_reg(&mut self.buf, dst);
+                let src_reg = self
+                    .storage_manager
+                    .load_to_float_reg(&mut self.buf, &as_f64);
+                ASM::to_float_freg32_freg64(&mut self.buf, dst_reg, src_reg);
+
+                self.free_symbol(&as_f64);
+            }
+            arg => internal_error!("NumToFrac is not defined for {arg:?} -> F32"),
         }
     }
 
@@ -5070,7 +5480,16 @@ impl<
                 let int_width = arg_layout.to_int_width();
                 self.build_int_to_float_cast(dst, src, int_width, FloatWidth::F64);
             }
-            arg => todo!("NumToFrac: layout, arg {arg:?}, ret {:?}", Layout::F64),
+            LayoutRepr::Builtin(Builtin::Decimal) => {
+                self.build_fn_call(
+                    dst,
+                    bitcode::DEC_TO_F64.to_string(),
+                    &[*src],
+                    &[*arg_layout],
+                    &Layout::F64,
+                );
+            }
+            arg => internal_error!("NumToFrac is not defined for {arg:?} -> F64"),
         }
     }
 
@@ -5085,8 +5504,174 @@ impl<
                     &Layout::DEC,
                 );
             }
+            LayoutRepr::Builtin(Builtin::Float(float_width)) => {
+                self.build_fn_call(
+                    dst,
+                    bitcode::DEC_FROM_FLOAT[float_width].to_string(),
+                    &[*src],
+                    &[*arg_layout],
+                    &Layout::DEC,
+                );
+            }
+            LayoutRepr::Builtin(Builtin::Decimal) => {
+                let to_offset = self.storage_manager.claim_stack_area_layout(
+                    self.layout_interner,
+                    *dst,
+                    Layout::DEC,
+                );
+                let (from_offset, size) = self.storage_manager.stack_offset_and_size(src);
+
+                self.storage_manager.copy_to_stack_offset(
+                    &mut self.buf,
+                    size,
+                    from_offset,
+                    to_offset,
+                );
+            }
+            arg => internal_error!("NumToFrac is not defined for {arg:?} -> Dec"),
+        }
+    }
+
+    fn claim_tmp_general_regs<const N: usize>(&mut self) -> ([Symbol; N], [GeneralReg; N]) {
+        let symbols = std::array::from_fn(|_| self.debug_symbol("int128_tmp"));
+        let regs = symbols.map(|symbol| {
+            self.storage_manager
+                .claim_general_reg(&mut self.buf, &symbol)
+        });
+
+        (symbols, regs)
+    }
+
+    /// Applies an operation that works on each 64-bit half separately, like bitwise and,
+    /// to two 128-bit integers.
+    fn int128_halves(
+        &mut self,
+        dst: &Symbol,
+        src1: &Symbol,
+        src2: &Symbol,
+        op: fn(&mut Vec<'a, u8>, GeneralReg, GeneralReg, GeneralReg),
+    ) {
+        let (src1_offset, _) = self.storage_manager.stack_offset_and_size(src1);
+        let (src2_offset, _) = self.storage_manager.stack_offset_and_size(src2);
+        let dst_offset =
+            self.storage_manager
+                .claim_stack_area_layout(self.layout_interner, *dst, Layout::U128);
+
+        let (symbols, [tmp1, tmp2]) = self.claim_tmp_general_regs();
+        let buf = &mut self.buf;
+
+        for half in [0, 8] {
+            ASM::mov_reg64_base32(buf, tmp1, src1_offset + half);
+            ASM::mov_reg64_base32(buf, tmp2, src2_offset + half);
+            op(buf, tmp1, tmp1, tmp2);
+            ASM::mov_base32_reg64(buf, dst_offset + half, tmp1);
+        }
+
+        for symbol in symbols {
+            self.free_symbol(&symbol);
+        }
+    }
+
+    fn add_wrap_128bit(&mut self, dst: &Symbol, src1: &Symbol, src2: &Symbol) {
+        let (src1_offset, _) = self.storage_manager.stack_offset_and_size(src1);
+        let (src2_offset, _) = self.storage_manager.stack_offset_and_size(src2);
+        let dst_offset =
+            self.storage_manager
+                .claim_stack_area_layout(self.layout_interner, *dst, Layout::U128);
+
+        let (symbols, [tmp1, tmp2, carry]) = self.claim_tmp_general_regs();
+        let buf = &mut self.buf;
+
+        // the lower 8 bytes carry into the upper 8 bytes if the sum is less than an operand
+        ASM::mov_reg64_base32(buf, tmp1, src1_offset);
+        ASM::mov_reg64_base32(buf, tmp2, src2_offset);
+        ASM::add_reg64_reg64_reg64(buf, tmp1, tmp1, tmp2);
+        ASM::unsigned_compare_reg64(
+            buf,
+            RegisterWidth::W64,
+            CompareOperation::LessThan,
+            carry,
+            tmp1,
+            tmp2,
+        );
+        ASM::mov_base32_reg64(buf, dst_offset, tmp1);
+
+        ASM::mov_reg64_base32(buf, tmp1, src1_offset + 8);
+        ASM::mov_reg64_base32(buf, tmp2, src2_offset + 8);
+        ASM::add_reg64_reg64_reg64(buf, tmp1, tmp1, tmp2);
+        ASM::add_reg64_reg64_reg64(buf, tmp1, tmp1, carry);
+        ASM::mov_base32_reg64(buf, dst_offset + 8, tmp1);
+
+        for symbol in symbols {
+            self.free_symbol(&symbol);
+        }
+    }
+
+    fn sub_wrap_128bit(&mut self, dst: &Symbol, src1: &Symbol, src2: &Symbol) {
+        let (src1_offset, _) = self.storage_manager.stack_offset_and_size(src1);
+        let (src2_offset, _) = self.storage_manager.stack_offset_and_size(src2);
+        let dst_offset =
+            self.storage_manager
+                .claim_stack_area_layout(self.layout_interner, *dst, Layout::U128);
+
+        let (symbols, [tmp1, tmp2, borrow]) = self.claim_tmp_general_regs();
+        let buf = &mut self.buf;
+
+        // the lower 8 bytes borrow from the upper 8 bytes if they are less than what is subtracted
+        ASM::mov_reg64_base32(buf, tmp1, src1_offset);
+        ASM::mov_reg64_base32(buf, tmp2, src2_offset);
+        ASM::unsigned_compare_reg64(
+            buf,
+            RegisterWidth::W64,
+            CompareOperation::LessThan,
+            borrow,
+            tmp1,
+            tmp2,
+        );
+        ASM::sub_reg64_reg64_reg64(buf, tmp1, tmp1, tmp2);
+        ASM::mov_base32_reg64(buf, dst_offset, tmp1);
+
+        ASM::mov_reg64_base32(buf, tmp1, src1_offset + 8);
+        ASM::mov_reg64_base32(buf, tmp2, src2_offset + 8);
+        ASM::sub_reg64_reg64_reg64(buf, tmp1, tmp1, tmp2);
+        ASM::sub_reg64_reg64_reg64(buf, tmp1, tmp1, borrow);
+        ASM::mov_base32_reg64(buf, dst_offset + 8, tmp1);
+
+        for symbol in symbols {
+            self.free_symbol(&symbol);
+        }
+    }
+
+    fn neg_128bit(&mut self, dst: &Symbol, src: &Symbol) {
+        let (src_offset, _) = self.storage_manager.stack_offset_and_size(src);
+        let dst_offset =
+            self.storage_manager
+                .claim_stack_area_layout(self.layout_interner, *dst, Layout::U128);
+
+        let (symbols, [tmp1, tmp2, borrow]) = self.claim_tmp_general_regs();
+        let buf = &mut self.buf;
+
+        // 0 - src, where the lower 8 bytes borrow unless they are zero
+        ASM::mov_reg64_base32(buf, tmp1, src_offset);
+        ASM::mov_reg64_imm64(buf, tmp2, 0);
+        ASM::unsigned_compare_reg64(
+            buf,
+            RegisterWidth::W64,
+            CompareOperation::LessThan,
+            borrow,
+            tmp2,
+            tmp1,
+        );
+        ASM::neg_reg64_reg64(buf, tmp1, tmp1);
+        ASM::mov_base32_reg64(buf, dst_offset, tmp1);
+
+        ASM::mov_reg64_base32(buf, tmp1, src_offset + 8);
+        ASM::neg_reg64_reg64(buf, tmp1, tmp1);
+        ASM::sub_reg64_reg64_reg64(buf, tmp1, tmp1, borrow);
+        ASM::mov_base32_reg64(buf, dst_offset + 8, tmp1);
 
-            arg => todo!("NumToFrac: layout, arg {arg:?}, ret {:?}", Layout::DEC),
+        for symbol in symbols {
+            self.free_symbol(&symbol);
         }
     }
 
@@ -5171,7 +5756,7 @@ impl<
             LayoutRepr::Builtin(Builtin::Decimal) => {
                 self.compare_128bit(op, dst, src1, src2, IntWidth::I128);
             }
-            x => todo!("NumLt: layout, {:?}", x),
+            x => internal_error!("comparison is not defined for {:?}", x),
         }
     }
 
@@ -1,17 +1,19 @@
 #![allow(clippy::redundant_closure_call)]
 //|> clippy false positive: https://github.com/rust-lang/rust-clippy/issues/1553
 
-use crate::generic64::{storage::StorageManager, Assembler, CallConv, RegTrait};
+use crate::generic64::{storage::StorageManager, Assembler, CallConv, FrameOffsets, RegTrait};
 use crate::{
     pointer_layouts, single_register_floats, single_register_int_builtins,
     single_register_integers, single_register_layouts, Relocation,
 };
 use bumpalo::collections::Vec;
+use gimli::write::{CallFrameInstruction, CommonInformationEntry};
 use roc_builtins::bitcode::{FloatWidth, IntWidth};
 use roc_error_macros::internal_error;
 use roc_module::symbol::Symbol;
 use roc_mono::layout::{
-    Builtin, InLayout, Layout, LayoutInterner, LayoutRepr, STLayoutInterner, UnionLayout,
+    Builtin, Discriminant, InLayout, Layout, LayoutInterner, LayoutRepr, STLayoutInterner,
+    UnionLayout,
 };
 
 use super::{CompareOperation, RegisterWidth};
@@ -41,6 +43,21 @@ impl RegTrait for X86_64GeneralReg {
     fn value(&self) -> u8 {
         *self as u8
     }
+
+    fn dwarf_register(&self) -> gimli::Register {
+        match self {
+            X86_64GeneralReg::RAX => gimli::X86_64::RAX,
+            X86_64GeneralReg::RBX => gimli::X86_64::RBX,
+            X86_64GeneralReg::RCX => gimli::X86_64::RCX,
+            X86_64GeneralReg::RDX => gimli::X86_64::RDX,
+            X86_64GeneralReg::RSP => gimli::X86_64::RSP,
+            X86_64GeneralReg::RBP => gimli::X86_64::RBP,
+            X86_64GeneralReg::RSI => gimli::X86_64::RSI,
+            X86_64GeneralReg::RDI => gimli::X86_64::RDI,
+            // r8 through r15 share their numbering with DWARF
+            _ => gimli::Register(*self as u16),
+        }
+    }
 }
 impl std::fmt::Display for X86_64GeneralReg {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -92,6 +109,10 @@ impl RegTrait for X86_64FloatReg {
     fn value(&self) -> u8 {
         *self as u8
     }
+
+    fn dwarf_register(&self) -> gimli::Register {
+        gimli::Register(gimli::X86_64::XMM0.0 + *self as u16)
+    }
 }
 impl std::fmt::Display for X86_64FloatReg {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -255,6 +276,28 @@ impl CallConv<X86_64GeneralReg, X86_64FloatReg, X86_64Assembler> for X86_64Syste
         )
     }
 
+    fn common_information_entry() -> CommonInformationEntry {
+        x86_64_generic_common_information_entry()
+    }
+
+    fn call_frame_instructions(
+        instructions: &mut Vec<'_, (u32, CallFrameInstruction)>,
+        saved_general_regs: &[X86_64GeneralReg],
+        saved_float_regs: &[X86_64FloatReg],
+        aligned_stack_size: i32,
+        fn_call_stack_size: i32,
+        offsets: FrameOffsets,
+    ) {
+        x86_64_generic_call_frame_instructions(
+            instructions,
+            saved_general_regs,
+            saved_float_regs,
+            aligned_stack_size,
+            fn_call_stack_size,
+            offsets,
+        )
+    }
+
     #[inline(always)]
     fn load_args<'a>(
         buf: &mut Vec<'a, u8>,
@@ -358,27 +401,23 @@ impl CallConv<X86_64GeneralReg, X86_64FloatReg, X86_64Assembler> for X86_64Syste
             _ if !Self::returns_via_arg_pointer(layout_interner, layout) => {
                 let (base_offset, size) = storage_manager.stack_offset_and_size(sym);
 
-                if size <= 8 {
-                    X86_64Assembler::mov_reg64_base32(
-                        buf,
-                        Self::GENERAL_RETURN_REGS[0],
-                        base_offset,
-                    );
-                } else if size <= 16 {
-                    X86_64Assembler::mov_reg64_base32(
-                        buf,
-                        Self::GENERAL_RETURN_REGS[0],
-                        base_offset,
-                    );
-                    X86_64Assembler::mov_reg64_base32(
-                        buf,
-                        Self::GENERAL_RETURN_REGS[1],
-                        base_offset + 8,
-                    );
-                } else {
-                    internal_error!(
-                        "types that don't return via arg pointer must be less than 16 bytes"
-                    );
+                let mut general_regs = Self::GENERAL_RETURN_REGS.iter();
+                let mut float_regs = Self::FLOAT_RETURN_REGS.iter();
+
+                for (eightbyte, in_float_reg) in Self::float_eightbytes(layout_interner, *layout)
+                    .into_iter()
+                    .take(size.div_ceil(8) as usize)
+                    .enumerate()
+                {
+                    let offset = base_offset + 8 * eightbyte as i32;
+
+                    if in_float_reg {
+                        let reg = *float_regs.next().unwrap();
+                        X86_64Assembler::mov_freg64_base32(buf, reg, offset);
+                    } else {
+                        let reg = *general_regs.next().unwrap();
+                        X86_64Assembler::mov_reg64_base32(buf, reg, offset);
+                    }
                 }
             }
             _ => {
@@ -417,21 +456,26 @@ impl CallConv<X86_64GeneralReg, X86_64FloatReg, X86_64Assembler> for X86_64Syste
             }
             _ if !Self::returns_via_arg_pointer(layout_interner, layout) => {
                 let size = layout_interner.stack_size(*layout);
-                let offset =
+                let base_offset =
                     storage_manager.claim_stack_area_layout(layout_interner, *sym, *layout);
-                if size <= 8 {
-                    X86_64Assembler::mov_base32_reg64(buf, offset, Self::GENERAL_RETURN_REGS[0]);
-                } else if size <= 16 {
-                    X86_64Assembler::mov_base32_reg64(buf, offset, Self::GENERAL_RETURN_REGS[0]);
-                    X86_64Assembler::mov_base32_reg64(
-                        buf,
-                        offset + 8,
-                        Self::GENERAL_RETURN_REGS[1],
-                    );
-                } else {
-                    internal_error!(
-                        "types that don't return via arg pointer must be less than 16 bytes"
-                    );
+
+                let mut general_regs = Self::GENERAL_RETURN_REGS.iter();
+                let mut float_regs = Self::FLOAT_RETURN_REGS.iter();
+
+                for (eightbyte, in_float_reg) in Self::float_eightbytes(layout_interner, *layout)
+                    .into_iter()
+                    .take(size.div_ceil(8) as usize)
+                    .enumerate()
+                {
+                    let offset = base_offset + 8 * eightbyte as i32;
+
+                    if in_float_reg {
+                        let reg = *float_regs.next().unwrap();
+                        X86_64Assembler::mov_base32_freg64(buf, offset, reg);
+                    } else {
+                        let reg = *general_regs.next().unwrap();
+                        X86_64Assembler::mov_base32_reg64(buf, offset, reg);
+                    }
                 }
             }
             _ => {
@@ -1345,6 +1389,73 @@ impl X86_64SystemV {
         // details here: https://github.com/hjl-tools/x86-psABI/wiki/x86-64-psABI-1.0.pdf
         interner.stack_size(*ret_layout) > 16
     }
+
+    /// For a value that is returned in registers, whether each eightbyte goes in a float
+    /// register. Eightbytes that only hold floats are returned in XMM0 and XMM1, all others
+    /// in RAX and RDX.
+    fn float_eightbytes<'a>(interner: &STLayoutInterner<'a>, layout: InLayout<'a>) -> [bool; 2] {
+        let mut has_float = [false; 2];
+        let mut has_other = [false; 2];
+
+        Self::classify_eightbytes(interner, layout, 0, &mut has_float, &mut has_other);
+
+        [has_float[0] && !has_other[0], has_float[1] && !has_other[1]]
+    }
+
+    fn classify_eightbytes<'a>(
+        interner: &STLayoutInterner<'a>,
+        layout: InLayout<'a>,
+        offset: u32,
+        has_float: &mut [bool; 2],
+        has_other: &mut [bool; 2],
+    ) {
+        let mut classify_fields = |field_layouts: &[InLayout<'a>]| {
+            let mut field_offset = offset;
+
+            for field_layout in field_layouts {
+                Self::classify_eightbytes(
+                    interner,
+                    *field_layout,
+                    field_offset,
+                    has_float,
+                    has_other,
+                );
+                field_offset += interner.stack_size(*field_layout);
+            }
+        };
+
+        match interner.get_repr(layout) {
+            LayoutRepr::Builtin(Builtin::Float(_)) => has_float[offset as usize / 8] = true,
+            LayoutRepr::Struct(field_layouts) => classify_fields(field_layouts),
+            LayoutRepr::Union(union_layout @ UnionLayout::NonRecursive(tags)) => {
+                for field_layouts in tags {
+                    classify_fields(field_layouts);
+                }
+
+                // a union with a single tag does not store its tag id
+                if !matches!(union_layout.discriminant(), Discriminant::U0) {
+                    let tag_id_offset = union_layout.tag_id_offset(interner).unwrap();
+                    has_other[(offset + tag_id_offset) as usize / 8] = true;
+                }
+            }
+            LayoutRepr::LambdaSet(lambda_set) => Self::classify_eightbytes(
+                interner,
+                lambda_set.runtime_representation(),
+                offset,
+                has_float,
+                has_other,
+            ),
+            _ => {
+                let size = interner.stack_size(layout);
+
+                if size > 0 {
+                    for eightbyte in offset / 8..=(offset + size - 1) / 8 {
+                        has_other[eightbyte as usize] = true;
+                    }
+                }
+            }
+        }
+    }
 }
 
 impl CallConv<X86_64GeneralReg, X86_64FloatReg, X86_64Assembler> for X86_64WindowsFastcall {
@@ -1488,6 +1599,28 @@ impl CallConv<X86_64GeneralReg, X86_64FloatReg, X86_64Assembler> for X86_64Windo
         )
     }
 
+    fn common_information_entry() -> CommonInformationEntry {
+        x86_64_generic_common_information_entry()
+    }
+
+    fn call_frame_instructions(
+        instructions: &mut Vec<'_, (u32, CallFrameInstruction)>,
+        saved_general_regs: &[X86_64GeneralReg],
+        saved_float_regs: &[X86_64FloatReg],
+        aligned_stack_size: i32,
+        fn_call_stack_size: i32,
+        offsets: FrameOffsets,
+    ) {
+        x86_64_generic_call_frame_instructions(
+            instructions,
+            saved_general_regs,
+            saved_float_regs,
+            aligned_stack_size,
+            fn_call_stack_size,
+            offsets,
+        )
+    }
+
     #[inline(always)]
     fn load_args<'a>(
         buf: &mut Vec<'a, u8>,
@@ -1914,6 +2047,69 @@ fn x86_64_generic_cleanup_stack(
     X86_64Assembler::pop_reg64(buf, X86_64GeneralReg::RBP);
 }
 
+fn x86_64_generic_common_information_entry() -> CommonInformationEntry {
+    let encoding = gimli::Encoding {
+        format: gimli::Format::Dwarf32,
+        version: 1,
+        address_size: 8,
+    };
+    let mut cie = CommonInformationEntry::new(encoding, 1, -8, gimli::X86_64::RA);
+
+    // On entry, the return address was just pushed by `call`.
+    cie.add_instruction(CallFrameInstruction::Cfa(gimli::X86_64::RSP, 8));
+    cie.add_instruction(CallFrameInstruction::Offset(gimli::X86_64::RA, -8));
+
+    cie
+}
+
+fn x86_64_generic_call_frame_instructions(
+    instructions: &mut Vec<'_, (u32, CallFrameInstruction)>,
+    saved_general_regs: &[X86_64GeneralReg],
+    saved_float_regs: &[X86_64FloatReg],
+    aligned_stack_size: i32,
+    fn_call_stack_size: i32,
+    offsets: FrameOffsets,
+) {
+    // push rbp
+    instructions.push((1, CallFrameInstruction::CfaOffset(16)));
+    instructions.push((
+        1,
+        CallFrameInstruction::Offset(X86_64GeneralReg::RBP.dwarf_register(), -16),
+    ));
+
+    // mov rbp, rsp
+    instructions.push((
+        4,
+        CallFrameInstruction::CfaRegister(X86_64GeneralReg::RBP.dwarf_register()),
+    ));
+
+    // The callee saved registers are stored below the base pointer, which is 16 bytes below the CFA.
+    if aligned_stack_size > 0 {
+        let mut offset = aligned_stack_size - fn_call_stack_size;
+        let saved_regs = saved_general_regs
+            .iter()
+            .map(|reg| reg.dwarf_register())
+            .chain(saved_float_regs.iter().map(|reg| reg.dwarf_register()));
+        for reg in saved_regs {
+            instructions.push((
+                offsets.setup_end,
+                CallFrameInstruction::Offset(reg, -offset - 16),
+            ));
+            offset -= 8;
+        }
+    }
+
+    // pop rbp
+    instructions.push((
+        offsets.ret,
+        CallFrameInstruction::Cfa(X86_64GeneralReg::RSP.dwarf_register(), 8),
+    ));
+    instructions.push((
+        offsets.ret,
+        CallFrameInstruction::Restore(X86_64GeneralReg::RBP.dwarf_register()),
+    ));
+}
+
 type Reg64 = X86_64GeneralReg;
 
 fn binop_move_src_to_dst_reg64<F>(buf: &mut Vec<'_, u8>, f: F, dst: Reg64, src1: Reg64, src2: Reg64)
@@ -3233,6 +3429,7 @@ fn and_reg64_imm8(buf: &mut Vec<'_, u8>, dst: X86_64GeneralReg, imm: i8) {
 }
 
 /// `CMOVL r64,r/m64` -> Move if less (SF≠ OF).
+#[allow(dead_code)]
 #[inline(always)]
 fn cmovl_reg64_reg64(buf: &mut Vec<'_, u8>, dst: X86_64GeneralReg, src: X86_64GeneralReg) {
     let rex = add_reg_extension(dst, REX_W);
@@ -8,6 +8,7 @@
 use std::collections::hash_map::Entry;
 
 use bumpalo::{collections::Vec, Bump};
+use gimli::write::{CallFrameInstruction, CommonInformationEntry};
 use roc_builtins::bitcode::{self, FloatWidth, IntWidth};
 use roc_collections::all::{MutMap, MutSet};
 use roc_error_macros::{internal_error, todo_lambda_erasure};
@@ -17,17 +18,20 @@ use roc_module::symbol::{Interns, ModuleId, Symbol};
 use roc_mono::code_gen_help::{CallerProc, CodeGenHelp};
 use roc_mono::ir::{
     BranchInfo, CallType, CrashTag, Expr, HigherOrderLowLevel, JoinPointId, ListLiteralElement,
-    Literal, ModifyRc, Param, Proc, ProcLayout, SelfRecursive, Stmt,
+    Literal, LookupType, ModifyRc, Param, Proc, ProcLayout, SelfRecursive, SourceInfo, Stmt,
 };
 use roc_mono::layout::{
     Builtin, InLayout, LambdaName, Layout, LayoutIds, LayoutInterner, LayoutRepr, STLayoutInterner,
     TagIdIntType, UnionLayout,
 };
 use roc_mono::list_element_layout;
+use roc_region::all::Region;
 
+mod const_fold;
+mod debug_info;
 mod generic64;
 mod object_builder;
-pub use object_builder::build_module;
+pub use object_builder::{build_module, expect_exposed_name};
 use roc_target::Target;
 mod run_roc;
 
@@ -39,6 +43,9 @@ pub enum AssemblyBackendMode {
     Test,
     /// Provides a testing implementation of primitives (roc_alloc, roc_panic, etc)
     Repl,
+    /// Provides a testing implementation of primitives (roc_alloc, roc_panic, etc), and exposes
+    /// the top-level expects, which report failures to the buffer given to `set_shared_buffer`
+    CliTest,
 }
 
 impl AssemblyBackendMode {
@@ -47,6 +54,7 @@ impl AssemblyBackendMode {
             AssemblyBackendMode::Binary => false,
             AssemblyBackendMode::Test => true,
             AssemblyBackendMode::Repl => true,
+            AssemblyBackendMode::CliTest => true,
         }
     }
 
@@ -55,6 +63,7 @@ impl AssemblyBackendMode {
             AssemblyBackendMode::Binary => false,
             AssemblyBackendMode::Test => true,
             AssemblyBackendMode::Repl => true,
+            AssemblyBackendMode::CliTest => true,
         }
     }
 
@@ -63,6 +72,18 @@ impl AssemblyBackendMode {
             AssemblyBackendMode::Binary => false,
             AssemblyBackendMode::Test => true,
             AssemblyBackendMode::Repl => true,
+            AssemblyBackendMode::CliTest => true,
+        }
+    }
+
+    /// Whether `dbg` and failed `expect`s are reported, which only `roc dev` and `roc test`
+    /// listen for.
+    fn runs_expects(self) -> bool {
+        match self {
+            AssemblyBackendMode::Binary => true,
+            AssemblyBackendMode::Test => false,
+            AssemblyBackendMode::Repl => false,
+            AssemblyBackendMode::CliTest => true,
         }
     }
 }
@@ -72,7 +93,12 @@ pub struct Env<'a> {
     pub module_id: ModuleId,
     pub exposed_to_host: MutSet<Symbol>,
     pub lazy_literals: bool,
+    /// Whether to fold constants and remove dead branches before generating code.
+    pub fold_constants: bool,
     pub mode: AssemblyBackendMode,
+    /// Where to find the source of each procedure, for debug info and unwind tables.
+    /// Without it, the object gets neither.
+    pub source_info: Option<SourceInfo<'a>>,
 }
 
 // These relocations likely will need a length.
@@ -276,9 +302,30 @@ impl<'a> LastSeenMap<'a> {
                 }
             }
 
-            Stmt::Dbg { .. } => todo!("dbg not implemented in the dev backend"),
-            Stmt::Expect { .. } => todo!("expect is not implemented in the dev backend"),
-            Stmt::ExpectFx { .. } => todo!("expect-fx is not implemented in the dev backend"),
+            Stmt::Dbg {
+                symbol, remainder, ..
+            } => {
+                self.set_last_seen(*symbol, stmt);
+                self.scan_ast_help(remainder);
+            }
+            Stmt::Expect {
+                condition,
+                lookups,
+                remainder,
+                ..
+            }
+            | Stmt::ExpectFx {
+                condition,
+                lookups,
+                remainder,
+                ..
+            } => {
+                self.set_last_seen(*condition, stmt);
+                for sym in *lookups {
+                    self.set_last_seen(*sym, stmt);
+                }
+                self.scan_ast_help(remainder);
+            }
 
             Stmt::Crash(msg, _crash_tag) => {
                 self.set_last_seen(*msg, stmt);
@@ -462,6 +509,21 @@ trait Backend<'a> {
     fn helper_proc_symbols(&self) -> &Vec<'a, (Symbol, ProcLayout<'a>)>;
     fn caller_procs(&self) -> &Vec<'a, CallerProc<'a>>;
 
+    /// The call frame rules that hold when any procedure is entered.
+    fn common_information_entry(&self) -> CommonInformationEntry;
+
+    /// take_call_frame_instructions returns how to unwind through the last finalized procedure,
+    /// as instructions paired with the code offset from which they apply.
+    fn take_call_frame_instructions(&mut self) -> Vec<'a, (u32, CallFrameInstruction)>;
+
+    /// mark_statement records that the code for the definition bound to `symbol` starts here,
+    /// if that definition came from source.
+    fn mark_statement(&mut self, symbol: Symbol);
+
+    /// take_statement_offsets returns where the code for each definition from source starts
+    /// in the last finalized procedure, in the order the statements were built.
+    fn take_statement_offsets(&mut self) -> Vec<'a, (u32, Symbol)>;
+
     /// reset resets any registers or other values that may be occupied at the end of a procedure.
     /// It also passes basic procedure information to the builder for setup of the next function.
     fn reset(&mut self, name: String, is_self_recursive: SelfRecursive);
@@ -498,7 +560,13 @@ trait Backend<'a> {
             proc.ret_layout,
         );
 
-        let body = self.env().arena.alloc(proc.body);
+        let arena = self.env().arena;
+        let mut body: &'a Stmt<'a> = arena.alloc(proc.body);
+        if self.env().fold_constants
+            && !roc_debug_flags::dbg_set!(roc_debug_flags::ROC_DEV_SKIP_CONST_FOLDING)
+        {
+            body = const_fold::fold_constants(arena, self.interner(), body);
+        }
 
         self.reset(proc_name, proc.is_self_recursive);
         self.load_args(proc.args, &proc.ret_layout);
@@ -542,6 +610,7 @@ trait Backend<'a> {
     ) {
         match stmt {
             Stmt::Let(sym, expr, layout, following) => {
+                self.mark_statement(*sym);
                 self.build_expr(sym, expr, layout);
                 self.set_layout_map(*sym, layout);
                 self.free_symbols(stmt);
@@ -648,8 +717,42 @@ trait Backend<'a> {
                 self.build_jump(id, args, arg_layouts.into_bump_slice(), ret_layout);
                 self.free_symbols(stmt);
             }
+            Stmt::Dbg {
+                source_location,
+                source,
+                symbol,
+                variable: _,
+                remainder,
+            } => {
+                if self.env().mode.runs_expects() {
+                    self.roc_dbg(source_location, source, *symbol);
+                }
+                self.free_symbols(stmt);
+                self.build_stmt(layout_ids, remainder, ret_layout);
+            }
+            Stmt::Expect {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            }
+            | Stmt::ExpectFx {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => {
+                if self.env().mode.runs_expects() {
+                    self.load_literal_symbols(&[*condition]);
+                    self.load_literal_symbols(lookups);
+                    self.build_expect(condition, *region, lookups, variables);
+                }
+                self.free_symbols(stmt);
+                self.build_stmt(layout_ids, remainder, ret_layout);
+            }
             Stmt::Crash(msg, crash_tag) => self.roc_panic(*msg, *crash_tag),
-            x => todo!("the statement, {:?}", x),
         }
     }
 
@@ -685,6 +788,46 @@ trait Backend<'a> {
         self.free_symbol(&Symbol::DEV_TMP2);
     }
 
+    fn roc_dbg(&mut self, source_location: &'a str, source: &'a str, msg: Symbol) {
+        let location = self.debug_symbol("dbg_location");
+        let source_str = self.debug_symbol("dbg_source");
+        self.load_literal(&location, &Layout::STR, &Literal::Str(source_location));
+        self.load_literal(&source_str, &Layout::STR, &Literal::Str(source));
+        self.load_literal_symbols(&[msg]);
+
+        // the roc_dbg defined by a platform expects its arguments as `*RocStr`
+        let location_ptr = self.debug_symbol("dbg_location_ptr");
+        let msg_ptr = self.debug_symbol("dbg_msg_ptr");
+        let source_ptr = self.debug_symbol("dbg_source_ptr");
+        self.build_alloca(location_ptr, Some(location), Layout::STR);
+        self.build_alloca(msg_ptr, Some(msg), Layout::STR);
+        self.build_alloca(source_ptr, Some(source_str), Layout::STR);
+
+        // NOTE: the order is (loc, msg, src), to match the other backends
+        self.build_fn_call(
+            &Symbol::DEV_TMP2,
+            String::from("roc_dbg"),
+            &[location_ptr, msg_ptr, source_ptr],
+            &[Layout::U64, Layout::U64, Layout::U64],
+            &Layout::UNIT,
+        );
+
+        for sym in [location, source_str, location_ptr, msg_ptr, source_ptr] {
+            self.free_symbol(&sym);
+        }
+        self.free_symbol(&Symbol::DEV_TMP2);
+    }
+
+    /// build_expect checks `condition`, and if it is false, writes the failure and the values of
+    /// the lookups to the shared memory that `roc_repl_expect::run` reads.
+    fn build_expect(
+        &mut self,
+        condition: &Symbol,
+        region: Region,
+        lookups: &[Symbol],
+        variables: &[LookupType],
+    );
+
     // build_switch generates a instructions for a switch statement.
     fn build_switch(
         &mut self,
@@ -1195,10 +1338,13 @@ trait Backend<'a> {
                     // saturated sub is just normal sub
                     self.build_num_sub(sym, &args[0], &args[1], ret_layout)
                 }
-                LayoutRepr::Builtin(Builtin::Decimal) => {
-                    // self.load_args_and_call_zig(backend, bitcode::DEC_SUB_SATURATED)
-                    todo!()
-                }
+                LayoutRepr::Builtin(Builtin::Decimal) => self.build_fn_call(
+                    sym,
+                    bitcode::DEC_SUB_SATURATED.to_string(),
+                    args,
+                    arg_layouts,
+                    ret_layout,
+                ),
                 _ => internal_error!("invalid return type"),
             },
             LowLevel::NumBitwiseAnd => {
@@ -1843,6 +1989,26 @@ trait Backend<'a> {
 
                 self.build_num_int_cast(sym, &args[0], source_width, target_width)
             }
+            LowLevel::NumToIntChecked => {
+                let source_width = match self.interner().get_repr(arg_layouts[0]) {
+                    LayoutRepr::Builtin(Builtin::Int(width)) => width,
+                    x => internal_error!("NumToIntChecked is not defined for {:?}", x),
+                };
+
+                // the return layout is { value : Int, out_of_bounds : Bool }
+                let target_width = match self.interner().get_repr(*ret_layout) {
+                    LayoutRepr::Struct([value, _]) => value.to_int_width(),
+                    x => internal_error!("NumToIntChecked cannot return {:?}", x),
+                };
+
+                let intrinsic = if source_width.is_signed() {
+                    &bitcode::NUM_INT_TO_INT_CHECKING_MAX_AND_MIN[target_width][source_width]
+                } else {
+                    &bitcode::NUM_INT_TO_INT_CHECKING_MAX[target_width][source_width]
+                };
+
+                self.build_fn_call(sym, intrinsic.to_string(), args, arg_layouts, ret_layout)
+            }
             LowLevel::NumIsMultipleOf => {
                 let int_width = arg_layouts[0].try_int_width().unwrap();
                 let intrinsic = bitcode::NUM_IS_MULTIPLE_OF[int_width].to_string();
@@ -1,3 +1,4 @@
+use crate::debug_info::DebugInfo;
 use crate::generic64::{aarch64, new_backend_64bit, x86_64};
 use crate::{AssemblyBackendMode, Backend, Env, Relocation};
 use bumpalo::collections::Vec;
@@ -586,28 +587,51 @@ fn build_object<'a, B: Backend<'a>>(
                         Exposed::TestMain,
                     );
                 }
+                AssemblyBackendMode::CliTest => {
+                    // the top-level expects are called like the repl's main, so that a panic
+                    // is caught and reported instead of ending `roc test`
+                    let expect_helper = roc_mono::code_gen_help::repl_helper(
+                        code_gen_help,
+                        ident_ids,
+                        layout_interner,
+                        &proc,
+                    );
+
+                    build_proc_symbol(
+                        &mut output,
+                        &mut layout_ids,
+                        &mut procs,
+                        &mut backend,
+                        layout,
+                        expect_helper,
+                        Exposed::Expect(sym),
+                    );
+                }
                 AssemblyBackendMode::Binary => { /* do nothing */ }
             }
 
-            build_proc_symbol(
-                &mut output,
-                &mut layout_ids,
-                &mut procs,
-                &mut backend,
-                layout,
-                exposed_proc,
-                Exposed::Exposed,
-            );
+            // the top-level expects are only called by `roc test`, never by a host
+            if !matches!(mode, AssemblyBackendMode::CliTest) {
+                build_proc_symbol(
+                    &mut output,
+                    &mut layout_ids,
+                    &mut procs,
+                    &mut backend,
+                    layout,
+                    exposed_proc,
+                    Exposed::Exposed,
+                );
 
-            build_proc_symbol(
-                &mut output,
-                &mut layout_ids,
-                &mut procs,
-                &mut backend,
-                layout,
-                exposed_generic_proc,
-                Exposed::ExposedGeneric,
-            );
+                build_proc_symbol(
+                    &mut output,
+                    &mut layout_ids,
+                    &mut procs,
+                    &mut backend,
+                    layout,
+                    exposed_generic_proc,
+                    Exposed::ExposedGeneric,
+                );
+            }
         }
 
         build_proc_symbol(
@@ -621,6 +645,13 @@ fn build_object<'a, B: Backend<'a>>(
         )
     }
 
+    let mut debug_info = DebugInfo::new(
+        backend.env().source_info,
+        backend.env().module_id,
+        output.format(),
+        backend.common_information_entry(),
+    );
+
     // Build procedures from user code
     let mut relocations = bumpalo::vec![in arena];
     for (fn_name, section_id, proc_id, proc) in procs {
@@ -628,6 +659,7 @@ fn build_object<'a, B: Backend<'a>>(
             &mut output,
             &mut backend,
             &mut relocations,
+            &mut debug_info,
             &mut layout_ids,
             data_section,
             fn_name,
@@ -719,6 +751,7 @@ fn build_object<'a, B: Backend<'a>>(
             &mut output,
             &mut backend,
             &mut relocations,
+            &mut debug_info,
             &mut layout_ids,
             data_section,
             fn_name,
@@ -735,6 +768,9 @@ fn build_object<'a, B: Backend<'a>>(
             Err(e) => internal_error!("{:?}", e),
         }
     }
+
+    debug_info.write(&mut output);
+
     output
 }
 
@@ -867,6 +903,22 @@ enum Exposed {
     Exposed,
     NotExposed,
     TestMain,
+    /// A top-level expect, called by `roc test`
+    Expect(symbol::Symbol),
+}
+
+/// The name that a top-level expect is exposed under in `AssemblyBackendMode::CliTest`.
+pub fn expect_exposed_name<'a>(
+    layout_ids: &mut LayoutIds<'a>,
+    interns: &Interns,
+    symbol: symbol::Symbol,
+    layout: &ProcLayout<'a>,
+) -> String {
+    let name = layout_ids
+        .get_toplevel(symbol, layout)
+        .to_exposed_symbol_string(symbol, interns);
+
+    format!("Expect_{name}")
 }
 
 fn build_proc_symbol<'a, B: Backend<'a>>(
@@ -900,6 +952,9 @@ fn build_proc_symbol<'a, B: Backend<'a>>(
             layout.result,
         ),
         Exposed::TestMain => String::from("test_main"),
+        Exposed::Expect(expect) => {
+            expect_exposed_name(layout_ids, backend.interns(), expect, &layout)
+        }
     };
 
     let proc_symbol = Symbol {
@@ -910,7 +965,9 @@ fn build_proc_symbol<'a, B: Backend<'a>>(
         // TODO: Depending on whether we are building a static or dynamic lib, this should change.
         // We should use Dynamic -> anyone, Linkage -> static link, Compilation -> this module only.
         scope: match exposed {
-            Exposed::ExposedGeneric | Exposed::Exposed | Exposed::TestMain => SymbolScope::Dynamic,
+            Exposed::ExposedGeneric | Exposed::Exposed | Exposed::TestMain | Exposed::Expect(_) => {
+                SymbolScope::Dynamic
+            }
             Exposed::NotExposed => SymbolScope::Linkage,
         },
         weak: false,
@@ -926,6 +983,7 @@ fn build_proc<'a, B: Backend<'a>>(
     output: &mut Object,
     backend: &mut B,
     relocations: &mut Vec<'a, (SectionId, object::write::Relocation)>,
+    debug_info: &mut DebugInfo<'a>,
     layout_ids: &mut LayoutIds<'a>,
     data_section: SectionId,
     fn_name: String,
@@ -935,8 +993,10 @@ fn build_proc<'a, B: Backend<'a>>(
 ) {
     let mut local_data_index = 0;
     let target = backend.target();
+    let proc_symbol = proc.name.name();
     let (proc_data, relocs, rc_proc_names) = backend.build_proc(proc, layout_ids);
     let proc_offset = output.add_symbol_data(proc_id, section_id, &proc_data, 16);
+    let proc_size = proc_data.len() as u64;
     for reloc in relocs.iter() {
         let elfreloc = match reloc {
             Relocation::LocalData { offset, data } => {
@@ -1064,6 +1124,21 @@ fn build_proc<'a, B: Backend<'a>>(
         };
         relocations.push((section_id, elfreloc));
     }
+
+    // The generated code borrows the backend, which is needed again for the debug info.
+    drop((proc_data, relocs, rc_proc_names));
+
+    let call_frame_instructions = backend.take_call_frame_instructions();
+    let statement_offsets = backend.take_statement_offsets();
+    debug_info.add_proc(
+        backend.interns(),
+        &fn_name,
+        proc_symbol,
+        proc_id,
+        proc_size,
+        &statement_offsets,
+        call_frame_instructions,
+    );
 }
 
 fn add_undefined_rc_proc(
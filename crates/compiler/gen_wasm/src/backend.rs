@@ -8,7 +8,7 @@ use roc_module::low_level::{LowLevel, LowLevelWrapperType};
 use roc_module::symbol::{Interns, Symbol};
 use roc_mono::code_gen_help::{CodeGenHelp, HelperOp, REFCOUNT_MAX};
 use roc_mono::ir::{
-    BranchInfo, CallType, CrashTag, Expr, JoinPointId, ListLiteralElement, Literal, ModifyRc,
+    BranchInfo, Call, CallType, CrashTag, Expr, JoinPointId, ListLiteralElement, Literal, ModifyRc,
     Param, Proc, ProcLayout, Stmt,
 };
 use roc_mono::layout::{
@@ -69,10 +69,18 @@ pub struct WasmBackend<'a, 'r> {
     // Function-level data
     pub code_builder: CodeBuilder<'a>,
     pub storage: Storage<'a>,
+    return_method: ReturnMethod,
+
+    /// Set while generating a call whose result is returned directly
+    is_tail_call: bool,
 
     /// how many blocks deep are we (used for jumps)
     block_depth: u32,
     joinpoint_label_map: MutMap<JoinPointId, (u32, Vec<'a, StoredValue>)>,
+
+    /// For source maps: the body size of each generated function, in order,
+    /// and where the code for each definition from source starts in it
+    pub statement_offsets: Vec<'a, (usize, Vec<'a, (u32, Symbol)>)>,
 }
 
 impl<'a, 'r> WasmBackend<'a, 'r> {
@@ -141,6 +149,9 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
             joinpoint_label_map: MutMap::default(),
             code_builder: CodeBuilder::new(env.arena),
             storage: Storage::new(env.arena),
+            return_method: ReturnMethod::NoReturnValue,
+            is_tail_call: false,
+            statement_offsets: Vec::with_capacity_in(0, env.arena),
         }
     }
 
@@ -339,7 +350,7 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
 
         self.module.add_function_signature(Signature {
             param_types: bumpalo::vec![in self.env.arena],
-            ret_type: None,
+            ret_types: bumpalo::vec![in self.env.arena],
         });
 
         self.module.export.append(Export {
@@ -389,6 +400,12 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
 
     /// Reset function-level data
     fn reset(&mut self) {
+        if self.env.source_info.is_some() {
+            self.statement_offsets.push((
+                self.code_builder.body_size(),
+                self.code_builder.statement_offsets(),
+            ));
+        }
         self.code_builder.insert_into_module(&mut self.module);
         self.code_builder.clear();
         self.storage.clear();
@@ -421,18 +438,28 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
         }
     }
 
+    /// How a Roc procedure returns its value.
+    /// The host expects the C calling convention, but other calls can use multi-value returns.
+    fn proc_return_method(&self, name: Symbol, ret_layout: InLayout<'a>) -> ReturnMethod {
+        let wasm_layout = WasmLayout::new(self.layout_interner, ret_layout);
+        if self.env.features.multi_value && !self.env.exposed_to_host.contains(&name) {
+            wasm_layout.multi_value_return_method()
+        } else {
+            wasm_layout.return_method()
+        }
+    }
+
     fn start_proc(&mut self, proc: &Proc<'a>) {
         use ReturnMethod::*;
-        let ret_layout = WasmLayout::new(self.layout_interner, proc.ret_layout);
-
-        let ret_type = match ret_layout.return_method() {
-            Primitive(ty, _) => Some(ty),
-            NoReturnValue => None,
-            WriteToPointerArg => {
-                self.storage.arg_types.push(PTR_TYPE);
-                None
-            }
-        };
+        self.return_method = self.proc_return_method(proc.name.name(), proc.ret_layout);
+
+        let mut ret_types = Vec::with_capacity_in(1, self.env.arena);
+        match self.return_method {
+            Primitive(ty, _) => ret_types.push(ty),
+            MultiValue(types) => ret_types.extend_from_slice(types),
+            NoReturnValue => {}
+            WriteToPointerArg => self.storage.arg_types.push(PTR_TYPE),
+        }
 
         // Create a block so we can exit the function without skipping stack frame "pop" code.
         // We never use the `return` instruction. Instead, we break from this block.
@@ -445,14 +472,14 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
             self.env.arena,
         );
 
-        if let Some(ty) = ret_type {
-            let ret_var = self.storage.create_anonymous_local(ty);
-            self.storage.return_var = Some(ret_var);
+        for ty in ret_types.iter() {
+            let ret_var = self.storage.create_anonymous_local(*ty);
+            self.storage.return_vars.push(ret_var);
         }
 
         self.module.add_function_signature(Signature {
             param_types: self.storage.arg_types.clone(),
-            ret_type,
+            ret_types,
         });
     }
 
@@ -460,8 +487,8 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
         // end the block from start_proc, to ensure all paths pop stack memory (if any)
         self.end_block();
 
-        if let Some(ret_var) = self.storage.return_var {
-            self.code_builder.get_local(ret_var);
+        for ret_var in self.storage.return_vars.iter() {
+            self.code_builder.get_local(*ret_var);
         }
 
         // Write local declarations and stack frame push/pop code
@@ -516,16 +543,21 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
 
         // Our convention is that the last arg of the wrapper is the heap return pointer
         let heap_return_ptr_id = LocalId(wrapper_arg_layouts.len() as u32 - 1);
-        let inner_ret_layout = match wrapper_arg_layouts
+        let inner_name = self.proc_lookup[inner_lookup_idx].name;
+        let inner_return_method = match wrapper_arg_layouts
             .last()
             .map(|l| self.layout_interner.get_repr(*l))
         {
-            Some(LayoutRepr::Ptr(inner)) => WasmLayout::new(self.layout_interner, inner),
+            Some(LayoutRepr::Ptr(inner)) => self.proc_return_method(inner_name, inner),
             x => internal_error!("Higher-order wrapper: invalid return layout {:?}", x),
         };
 
-        let ret_type_and_size = match inner_ret_layout.return_method() {
-            ReturnMethod::NoReturnValue => None,
+        // All the wrapper's arguments are pointers
+        let n_wrapper_args = wrapper_arg_layouts.len();
+        self.storage.arg_types.resize(n_wrapper_args, I32);
+
+        let ret_type_and_size = match inner_return_method {
+            ReturnMethod::NoReturnValue | ReturnMethod::MultiValue(_) => None,
             ReturnMethod::Primitive(ty, size) => {
                 // If the inner function returns a primitive, load the address to store it at
                 // After the call, it will be under the call result in the value stack
@@ -598,13 +630,16 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
                 }
             }
         }
+        if let ReturnMethod::MultiValue(types) = inner_return_method {
+            self.store_multi_value_results(types, heap_return_ptr_id, 0);
+        }
 
-        // Write empty function header (local variables array with zero length)
-        self.code_builder.build_fn_header_and_footer(&[], 0, None);
+        self.code_builder
+            .build_fn_header_and_footer(&self.storage.local_types, 0, None);
 
         self.module.add_function_signature(Signature {
-            param_types: bumpalo::vec![in self.env.arena; I32; wrapper_arg_layouts.len()],
-            ret_type: None,
+            param_types: bumpalo::vec![in self.env.arena; I32; n_wrapper_args],
+            ret_types: bumpalo::vec![in self.env.arena],
         });
 
         self.append_proc_debug_name(wrapper_name);
@@ -654,7 +689,7 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
 
         self.module.add_function_signature(Signature {
             param_types: bumpalo::vec![in self.env.arena; I32; 3],
-            ret_type: Some(ValueType::I32),
+            ret_types: bumpalo::vec![in self.env.arena; I32],
         });
 
         self.append_proc_debug_name(wrapper_name);
@@ -760,12 +795,37 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
                 print!("\nlet {:?} = {}", sym, expr.to_pretty(200, true));
             }
 
-            let kind = match following {
-                Stmt::Ret(ret_sym) if *sym == *ret_sym => StoredVarKind::ReturnValue,
+            if let Some(source_info) = self.env.source_info {
+                if source_info.stmt_regions.contains_key(sym) {
+                    self.code_builder.mark_statement(*sym);
+                }
+            }
+
+            let is_returned = matches!(following, Stmt::Ret(ret_sym) if *sym == *ret_sym);
+
+            if is_returned && self.stmt_let_multi_value_call(expr, *layout) {
+                return;
+            }
+
+            // Multiple return values are loaded from the stack frame in stmt_ret
+            let kind = match self.return_method {
+                ReturnMethod::MultiValue(_) => StoredVarKind::Variable,
+                _ if is_returned => StoredVarKind::ReturnValue,
                 _ => StoredVarKind::Variable,
             };
 
+            self.is_tail_call = is_returned
+                && self.env.features.tail_call
+                && matches!(
+                    expr,
+                    Expr::Call(Call {
+                        call_type: CallType::ByName { .. },
+                        ..
+                    })
+                );
+
             self.stmt_let_store_expr(*sym, *layout, expr, kind);
+            self.is_tail_call = false;
 
             current_stmt = *following;
         }
@@ -773,6 +833,62 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
         self.stmt(current_stmt);
     }
 
+    /// A call whose multiple return values are returned directly goes straight into our own
+    /// return variables, rather than through the stack frame like other multi-value results.
+    /// That keeps the stack frame out of the way of a tail call (see `build_fn_header_and_footer`).
+    /// Returns false if this is some other kind of expression.
+    fn stmt_let_multi_value_call(&mut self, expr: &Expr<'a>, layout: InLayout<'a>) -> bool {
+        let (func_sym, arg_layouts, result, arguments) = match (self.return_method, expr) {
+            (
+                ReturnMethod::MultiValue(_),
+                Expr::Call(Call {
+                    call_type:
+                        CallType::ByName {
+                            name,
+                            arg_layouts,
+                            ret_layout,
+                            ..
+                        },
+                    arguments,
+                }),
+            ) => (*name, *arg_layouts, *ret_layout, *arguments),
+            _ => return false,
+        };
+
+        // Low-level wrappers get inlined, so there's no call
+        let is_wrapper = matches!(
+            LowLevelWrapperType::from_symbol(func_sym.name()),
+            LowLevelWrapperType::CanBeReplacedBy(_)
+        );
+        if is_wrapper || self.proc_return_method(func_sym.name(), layout) != self.return_method {
+            return false;
+        }
+
+        let proc_layout = ProcLayout {
+            arguments: arg_layouts,
+            result,
+            niche: func_sym.niche(),
+        };
+        let wasm_fn_index = self.roc_proc_fn_index(func_sym.name(), &proc_layout);
+
+        self.storage.load_symbols(&mut self.code_builder, arguments);
+        if self.env.features.tail_call {
+            self.code_builder.call_in_tail_position(wasm_fn_index);
+        } else {
+            self.code_builder.call(wasm_fn_index);
+        }
+
+        // The last value is on top of the VM stack
+        for ret_var in self.storage.return_vars.iter().rev() {
+            self.code_builder.set_local(*ret_var);
+        }
+
+        // This replaces the `ret`, so jump to the "stack frame pop" code at the end of the function
+        self.code_builder.br(self.block_depth - 1);
+
+        true
+    }
+
     fn stmt_let_store_expr(
         &mut self,
         sym: Symbol,
@@ -796,13 +912,31 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
     fn stmt_ret(&mut self, sym: Symbol) {
         use crate::storage::StoredValue::*;
 
-        match self.storage.get(&sym) {
-            StackMemory {
-                location,
-                size,
-                alignment_bytes,
-                ..
-            } => {
+        match (self.storage.get(&sym), self.return_method) {
+            (StackMemory { location, .. }, ReturnMethod::MultiValue(types)) => {
+                // Load each word of the value into its own return variable
+                let (from_ptr, mut offset) =
+                    location.local_and_offset(self.storage.stack_frame_pointer);
+                for (ty, ret_var) in types.iter().zip(self.storage.return_vars.iter()) {
+                    self.code_builder.get_local(from_ptr);
+                    match ty {
+                        ValueType::I64 => self.code_builder.i64_load(Align::Bytes8, offset),
+                        _ => self.code_builder.i32_load(Align::Bytes4, offset),
+                    }
+                    self.code_builder.set_local(*ret_var);
+                    offset += word_size(*ty);
+                }
+            }
+
+            (
+                StackMemory {
+                    location,
+                    size,
+                    alignment_bytes,
+                    ..
+                },
+                _,
+            ) => {
                 let (from_ptr, from_offset) =
                     location.local_and_offset(self.storage.stack_frame_pointer);
                 copy_memory(
@@ -823,8 +957,8 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
 
                 // If we have a return value, store it to the return variable
                 // This avoids complications with block result types when returning from nested blocks
-                if let Some(ret_var) = self.storage.return_var {
-                    self.code_builder.set_local(ret_var);
+                if let Some(ret_var) = self.storage.return_vars.first() {
+                    self.code_builder.set_local(*ret_var);
                 }
             }
         }
@@ -1350,7 +1484,7 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
         ret_layout: InLayout<'a>,
         ret_storage: &StoredValue,
     ) {
-        let wasm_layout = WasmLayout::new(self.layout_interner, ret_layout);
+        let is_tail_call = std::mem::take(&mut self.is_tail_call);
 
         // If this function is just a lowlevel wrapper, then inline it
         if let LowLevelWrapperType::CanBeReplacedBy(lowlevel) =
@@ -1359,13 +1493,40 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
             return self.expr_call_low_level(lowlevel, arguments, ret_sym, ret_layout, ret_storage);
         }
 
-        self.storage.load_symbols_for_call(
-            &mut self.code_builder,
-            arguments,
-            ret_sym,
-            &wasm_layout,
-        );
+        let return_method = self.proc_return_method(func_sym, ret_layout);
+        if let ReturnMethod::MultiValue(_) = return_method {
+            self.storage.load_symbols(&mut self.code_builder, arguments);
+        } else {
+            let wasm_layout = WasmLayout::new(self.layout_interner, ret_layout);
+            self.storage.load_symbols_for_call(
+                &mut self.code_builder,
+                arguments,
+                ret_sym,
+                &wasm_layout,
+            );
+        }
+
+        let wasm_fn_index = self.roc_proc_fn_index(func_sym, proc_layout);
 
+        if is_tail_call && return_method == self.return_method {
+            self.code_builder.call_in_tail_position(wasm_fn_index);
+        } else {
+            self.code_builder.call(wasm_fn_index);
+        }
+
+        if let ReturnMethod::MultiValue(types) = return_method {
+            match ret_storage {
+                StoredValue::StackMemory { location, .. } => {
+                    let (to_ptr, to_offset) =
+                        location.local_and_offset(self.storage.stack_frame_pointer);
+                    self.store_multi_value_results(types, to_ptr, to_offset);
+                }
+                _ => internal_error!("Unexpected storage for {:?}: {:?}", ret_sym, ret_storage),
+            }
+        }
+    }
+
+    fn roc_proc_fn_index(&self, func_sym: Symbol, proc_layout: &ProcLayout<'a>) -> u32 {
         let roc_proc_index = self
             .proc_lookup
             .iter()
@@ -1379,9 +1540,29 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
                 );
             });
 
-        let wasm_fn_index = self.fn_index_offset + roc_proc_index as u32;
+        self.fn_index_offset + roc_proc_index as u32
+    }
+
+    /// Store the values returned from a multi-value call to memory, as the words of one value
+    fn store_multi_value_results(&mut self, types: &[ValueType], to_ptr: LocalId, to_offset: u32) {
+        let mut results = Vec::with_capacity_in(types.len(), self.env.arena);
+        for ty in types.iter() {
+            results.push(self.storage.create_anonymous_local(*ty));
+        }
+        for local in results.iter().rev() {
+            self.code_builder.set_local(*local);
+        }
 
-        self.code_builder.call(wasm_fn_index);
+        let mut offset = to_offset;
+        for (ty, local) in types.iter().zip(results.iter()) {
+            self.code_builder.get_local(to_ptr);
+            self.code_builder.get_local(*local);
+            match ty {
+                ValueType::I64 => self.code_builder.i64_store(Align::Bytes8, offset),
+                _ => self.code_builder.i32_store(Align::Bytes4, offset),
+            }
+            offset += word_size(*ty);
+        }
     }
 
     fn expr_call_low_level(
@@ -1622,17 +1803,47 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
 
             // Store 12 bytes of zeros { elements: null, length: 0, capacity: 0 }
             debug_assert_eq!(Builtin::LIST_WORDS, 3);
-            self.code_builder.get_local(local_id);
-            self.code_builder.i64_const(0);
-            self.code_builder.i64_store(Align::Bytes4, offset);
-            self.code_builder.get_local(local_id);
-            self.code_builder.i32_const(0);
-            self.code_builder.i32_store(Align::Bytes4, offset + 8);
+            self.zero_memory(local_id, offset, 12);
         } else {
             internal_error!("Unexpected storage for {:?}", sym)
         }
     }
 
+    /// Write zeros to memory, using `memory.fill` if the bulk memory proposal is enabled
+    fn zero_memory(&mut self, ptr: LocalId, offset: u32, size: u32) {
+        if self.env.features.bulk_memory {
+            self.code_builder.get_local(ptr);
+            if offset != 0 {
+                self.code_builder.i32_const(offset as i32);
+                self.code_builder.i32_add();
+            }
+            self.code_builder.i32_const(0);
+            self.code_builder.i32_const(size as i32);
+            self.code_builder.memory_fill();
+            return;
+        }
+
+        let mut i = 0;
+        while size - i >= 8 {
+            self.code_builder.get_local(ptr);
+            self.code_builder.i64_const(0);
+            self.code_builder.i64_store(Align::Bytes4, offset + i);
+            i += 8;
+        }
+        while size - i >= 4 {
+            self.code_builder.get_local(ptr);
+            self.code_builder.i32_const(0);
+            self.code_builder.i32_store(Align::Bytes4, offset + i);
+            i += 4;
+        }
+        while size - i > 0 {
+            self.code_builder.get_local(ptr);
+            self.code_builder.i32_const(0);
+            self.code_builder.i32_store8(Align::Bytes1, offset + i);
+            i += 1;
+        }
+    }
+
     /*******************************************************************
      * Tag Unions
      *******************************************************************/
@@ -2132,3 +2343,11 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
         self.fn_index_offset + proc_index as u32
     }
 }
+
+/// Size in bytes of one word of a multi-value return
+fn word_size(ty: ValueType) -> u32 {
+    match ty {
+        ValueType::I64 | ValueType::F64 => 8,
+        ValueType::I32 | ValueType::F32 => 4,
+    }
+}
@@ -5,7 +5,8 @@ use core::panic;
 use roc_wasm_module::linking::IndexRelocType;
 
 use roc_error_macros::internal_error;
-use roc_wasm_module::opcodes::{OpCode, OpCode::*};
+use roc_module::symbol::Symbol;
+use roc_wasm_module::opcodes::{MemoryInstruction, OpCode, OpCode::*};
 use roc_wasm_module::serialize::SerialBuffer;
 use roc_wasm_module::{
     round_up_to_alignment, Align, LocalId, RelocationEntry, ValueType, WasmModule,
@@ -76,6 +77,13 @@ pub struct CodeBuilder<'a> {
 
     /// Keep track of which local variables have been set
     set_locals: BitVec<u32>,
+
+    /// Code locations of calls whose result is returned directly by this function.
+    /// If there's no stack frame to pop, they become tail calls (`return_call`).
+    tail_calls: Vec<'a, usize>,
+
+    /// Code locations where the code for a definition from source starts, for source maps
+    statements: Vec<'a, (usize, Symbol)>,
 }
 
 #[allow(clippy::new_without_default)]
@@ -90,6 +98,8 @@ impl<'a> CodeBuilder<'a> {
             inner_length: Vec::with_capacity_in(5, arena),
             import_relocations: Vec::with_capacity_in(0, arena),
             set_locals: BitVec::with_capacity(64),
+            tail_calls: Vec::with_capacity_in(0, arena),
+            statements: Vec::with_capacity_in(0, arena),
         }
     }
 
@@ -101,6 +111,8 @@ impl<'a> CodeBuilder<'a> {
         self.inner_length.clear();
         self.import_relocations.clear();
         self.set_locals.clear();
+        self.tail_calls.clear();
+        self.statements.clear();
     }
 
     /**********************************************************
@@ -183,12 +195,19 @@ impl<'a> CodeBuilder<'a> {
     ) {
         self.build_local_declarations(local_types);
 
-        if frame_size != 0 {
-            if let Some(frame_ptr_id) = frame_pointer {
+        match frame_pointer {
+            Some(frame_ptr_id) if frame_size != 0 => {
                 let aligned_size = round_up_to_alignment!(frame_size, FRAME_ALIGNMENT_BYTES);
                 self.build_stack_frame_push(aligned_size, frame_ptr_id);
                 self.build_stack_frame_pop(aligned_size, frame_ptr_id); // footer
             }
+            _ => {
+                // Nothing to do after the calls in tail position, so the callee can return for us.
+                // The immediate has the same encoding, so only the opcode changes.
+                for &at in self.tail_calls.iter() {
+                    self.code[at] = RETURNCALL as u8;
+                }
+            }
         }
 
         self.code.push(END as u8);
@@ -210,6 +229,30 @@ impl<'a> CodeBuilder<'a> {
         self.inner_length.len() + self.preamble.len() + self.code.len() + self.insert_bytes.len()
     }
 
+    /// Size of the function body, not including its length
+    pub fn body_size(&self) -> usize {
+        self.preamble.len() + self.code.len() + self.insert_bytes.len()
+    }
+
+    /// Where the code for each marked statement starts, relative to the start of the function body.
+    /// Like `insert_into_module`, this is only valid after `build_fn_header_and_footer`.
+    pub fn statement_offsets(&self) -> Vec<'a, (u32, Symbol)> {
+        let mut offsets = Vec::with_capacity_in(self.statements.len(), self.arena);
+        let mut insertions = self.insertions.iter().peekable();
+        let mut insertion_bytes = 0;
+
+        for &(code_pos, symbol) in self.statements.iter() {
+            // Insertions at this position finish off earlier code, so they come before the statement
+            while let Some(insertion) = insertions.next_if(|ins| ins.at <= code_pos) {
+                insertion_bytes += insertion.end - insertion.start;
+            }
+            let offset = self.preamble.len() + insertion_bytes + code_pos;
+            offsets.push((offset as u32, symbol));
+        }
+
+        offsets
+    }
+
     /// Serialize all byte vectors in the right order
     /// Insert relocations for imported functions
     pub fn insert_into_module(&self, module: &mut WasmModule<'a>) {
@@ -259,6 +302,11 @@ impl<'a> CodeBuilder<'a> {
         }
     }
 
+    /// Record that the code for the definition bound to `symbol` starts here
+    pub fn mark_statement(&mut self, symbol: Symbol) {
+        self.statements.push((self.code.len(), symbol));
+    }
+
     /**********************************************************
 
         INSTRUCTION HELPER METHODS
@@ -348,6 +396,13 @@ impl<'a> CodeBuilder<'a> {
         log_instruction!("{:10}\t{}", format!("{CALL:?}"), function_index);
     }
 
+    /// A call whose result is returned directly. It becomes a `return_call` if possible.
+    /// Requires the tail call proposal.
+    pub fn call_in_tail_position(&mut self, function_index: u32) {
+        self.tail_calls.push(self.code.len());
+        self.call(function_index);
+    }
+
     pub fn call_import(&mut self, function_index: u32) {
         self.import_relocations
             .push((self.code.len(), function_index));
@@ -424,6 +479,12 @@ impl<'a> CodeBuilder<'a> {
         self.inst(GROWMEMORY);
         self.code.push(0);
     }
+    /// Requires the bulk memory proposal
+    pub fn memory_fill(&mut self) {
+        self.inst(MEMORY);
+        self.code.encode_u32(MemoryInstruction::MemoryFill as u32);
+        self.code.push(0);
+    }
 
     fn log_const<T>(&self, opcode: OpCode, x: T)
     where
@@ -13,6 +13,8 @@ pub enum ReturnMethod {
     WriteToPointerArg,
     /// This layout is empty and requires no return value or argument (e.g. refcount helpers)
     NoReturnValue,
+    /// This layout is returned as several Wasm values (requires the multi-value proposal)
+    MultiValue(&'static [ValueType]),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -126,6 +128,39 @@ impl WasmLayout {
             Self::StackMemory { size, format, .. } => stack_memory_return_method(*size, *format),
         }
     }
+
+    /// Like `return_method`, but small stack memory values are returned as Wasm values.
+    /// Only for calls between Roc procedures, since the host and the Zig builtins expect
+    /// the C calling convention.
+    pub fn multi_value_return_method(&self) -> ReturnMethod {
+        use ValueType::*;
+
+        static I32_WORDS: [ValueType; 4] = [I32; 4];
+        static I64_WORDS: [ValueType; 2] = [I64; 2];
+
+        match self {
+            Self::StackMemory {
+                size,
+                alignment_bytes,
+                format,
+            } => match format {
+                StackMemoryFormat::Int128 | StackMemoryFormat::Decimal => {
+                    ReturnMethod::MultiValue(&I64_WORDS)
+                }
+                StackMemoryFormat::DataStructure if *size > 0 && *size <= 16 => {
+                    if *alignment_bytes >= 8 && *size % 8 == 0 {
+                        ReturnMethod::MultiValue(&I64_WORDS[..*size as usize / 8])
+                    } else if *alignment_bytes >= 4 && *size % 4 == 0 {
+                        ReturnMethod::MultiValue(&I32_WORDS[..*size as usize / 4])
+                    } else {
+                        self.return_method()
+                    }
+                }
+                StackMemoryFormat::DataStructure => self.return_method(),
+            },
+            Self::Primitive(..) => self.return_method(),
+        }
+    }
 }
 
 /// The Wasm argument types to use when passing structs or 128-bit numbers
@@ -3,6 +3,7 @@ mod backend;
 mod code_builder;
 mod layout;
 mod low_level;
+mod source_map;
 mod storage;
 
 // Helpers for interfacing to a Wasm module from outside
@@ -14,16 +15,18 @@ use bumpalo::collections::Vec;
 use bumpalo::{self, Bump};
 
 use roc_collections::all::{MutMap, MutSet};
+use roc_error_macros::internal_error;
 use roc_module::symbol::{Interns, ModuleId, Symbol};
 use roc_mono::code_gen_help::CodeGenHelp;
-use roc_mono::ir::{Proc, ProcLayout};
+use roc_mono::ir::{Proc, ProcLayout, SourceInfo};
 use roc_mono::layout::{LayoutIds, STLayoutInterner};
-use roc_target::Target;
+use roc_target::{Target, WasmFeatures};
 use roc_wasm_module::parse::ParseError;
 use roc_wasm_module::{Align, LocalId, ValueType, WasmModule};
 
 use crate::backend::{ProcLookupData, ProcSource, WasmBackend};
 use crate::code_builder::CodeBuilder;
+use crate::source_map::AppFunction;
 
 const TARGET: Target = Target::Wasm32;
 const PTR_SIZE: u32 = {
@@ -46,6 +49,10 @@ pub struct Env<'a> {
     pub module_id: ModuleId,
     pub exposed_to_host: MutSet<Symbol>,
     pub stack_bytes: u32,
+    /// If set, embed a source map that points the code for each Roc definition back to its source
+    pub source_info: Option<SourceInfo<'a>>,
+    /// Wasm proposals that the generated code may use, beyond the MVP
+    pub features: WasmFeatures,
 }
 
 impl Env<'_> {
@@ -71,13 +78,30 @@ pub fn build_app_binary<'a, 'r>(
     host_module: WasmModule<'a>,
     procedures: MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
 ) -> std::vec::Vec<u8> {
-    let (mut wasm_module, called_fns, _) =
-        build_app_module(env, layout_interner, interns, host_module, procedures);
+    // The generated functions come after the host functions
+    let fn_index_offset =
+        host_module.import.function_count() as u32 + host_module.code.function_count;
+
+    let (mut wasm_module, called_fns, _, app_fns) =
+        build_app_module_help(env, layout_interner, interns, host_module, procedures);
 
     wasm_module.eliminate_dead_code(env.arena, called_fns);
 
+    if roc_debug_flags::dbg_set!(roc_debug_flags::ROC_VALIDATE_WASM) {
+        if let Err(e) = wasm_module.validate() {
+            internal_error!("{}", e);
+        }
+    }
+
     let mut buffer = std::vec::Vec::with_capacity(wasm_module.size());
     wasm_module.serialize(&mut buffer);
+
+    if let Some(source_info) = env.source_info {
+        let source_map =
+            source_map::build_source_map(source_info, interns, &buffer, fn_index_offset, &app_fns);
+        source_map::append_source_map_section(&mut buffer, &source_map);
+    }
+
     buffer
 }
 
@@ -92,6 +116,21 @@ pub fn build_app_module<'a, 'r>(
     host_module: WasmModule<'a>,
     procedures: MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
 ) -> (WasmModule<'a>, BitVec<usize>, u32) {
+    let (module, called_fns, main_function_index, _) =
+        build_app_module_help(env, layout_interner, interns, host_module, procedures);
+    (module, called_fns, main_function_index)
+}
+
+/// Like `build_app_module`, but also returns what the source map needs to know about each
+/// generated function, in function index order. They are the last functions in the module.
+/// That's only collected if the `Env` has a `SourceInfo`.
+fn build_app_module_help<'a, 'r>(
+    env: &'r Env<'a>,
+    layout_interner: &'r mut STLayoutInterner<'a>,
+    interns: &'r mut Interns,
+    host_module: WasmModule<'a>,
+    procedures: MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+) -> (WasmModule<'a>, BitVec<usize>, u32, Vec<'a, AppFunction<'a>>) {
     let mut layout_ids = LayoutIds::default();
     let mut procs = Vec::with_capacity_in(procedures.len(), env.arena);
     let mut proc_lookup = Vec::with_capacity_in(procedures.len() * 2, env.arena);
@@ -183,11 +222,25 @@ pub fn build_app_module<'a, 'r>(
         }
     }
 
+    let statement_offsets = std::mem::replace(
+        &mut backend.statement_offsets,
+        Vec::with_capacity_in(0, env.arena),
+    );
+    let app_fns = Vec::from_iter_in(
+        backend.proc_lookup.iter().zip(statement_offsets).map(
+            |(ProcLookupData { name, .. }, (body_size, statements))| AppFunction {
+                symbol: *name,
+                body_size,
+                statements,
+            },
+        ),
+        env.arena,
+    );
     let (module, called_fns) = backend.finalize();
     let main_function_index =
         maybe_main_fn_index.expect("The app must expose at least one value to the host");
 
-    (module, called_fns, main_function_index)
+    (module, called_fns, main_function_index, app_fns)
 }
 
 pub struct CopyMemoryConfig {
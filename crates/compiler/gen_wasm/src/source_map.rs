@@ -0,0 +1,528 @@
+//! Source maps for the Wasm binaries built by this backend.
+//!
+//! The code for each Roc procedure is mapped to the definition it came from, and the code for
+//! each definition nested in it to that nested definition, so browser devtools can show Roc
+//! source when stepping through a module. The source map is embedded in the module as a
+//! data URL in a `sourceMappingURL` custom section, so there are no extra files to serve.
+//! See https://sourcemaps.info/spec.html and the WebAssembly chapter of the source map spec.
+
+use std::fmt::Write;
+
+use roc_collections::all::MutMap;
+use roc_module::symbol::{Interns, ModuleId, Symbol};
+use roc_mono::ir::SourceInfo;
+use roc_region::all::{LineColumn, LineInfo, Region};
+use roc_wasm_module::parse::Parse;
+use roc_wasm_module::sections::{update_section_size, write_custom_section_header, SectionId};
+use roc_wasm_module::Serialize;
+
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// A function generated from a Roc procedure
+pub struct AppFunction<'a> {
+    pub symbol: Symbol,
+    /// Size of the function body when it was generated. Dead code elimination replaces
+    /// the bodies of unused functions, and then the statement offsets don't apply.
+    pub body_size: usize,
+    /// Where the code for each definition nested in the procedure starts,
+    /// relative to the start of the function body
+    pub statements: bumpalo::collections::Vec<'a, (u32, Symbol)>,
+}
+
+/// A source location for a range of code
+struct Mapping {
+    code_offset: usize,
+    source: Option<OriginalPosition>,
+}
+
+struct OriginalPosition {
+    source_index: usize,
+    line: u32,
+    column: u32,
+    name_index: usize,
+}
+
+/// The Roc modules that the source map refers to, in the order they're listed in it
+struct Sources<'a> {
+    source_info: SourceInfo<'a>,
+    module_ids: Vec<ModuleId>,
+    line_infos: MutMap<ModuleId, LineInfo>,
+}
+
+impl<'a> Sources<'a> {
+    fn position(&mut self, module_id: ModuleId, region: Region) -> Option<(usize, LineColumn)> {
+        let (_, src) = self.source_info.sources.get(&module_id)?;
+
+        let source_index = match self.module_ids.iter().position(|m| *m == module_id) {
+            Some(index) => index,
+            None => {
+                self.module_ids.push(module_id);
+                self.module_ids.len() - 1
+            }
+        };
+        let line_info = self
+            .line_infos
+            .entry(module_id)
+            .or_insert_with(|| LineInfo::new(src));
+
+        Some((source_index, line_info.convert_pos(region.start())))
+    }
+}
+
+/// Source map JSON for a serialized module.
+/// `app_fns[i]` is the function at index `fn_index_offset + i`.
+pub fn build_source_map(
+    source_info: SourceInfo,
+    interns: &Interns,
+    module_bytes: &[u8],
+    fn_index_offset: u32,
+    app_fns: &[AppFunction],
+) -> String {
+    let mut sources = Sources {
+        source_info,
+        module_ids: vec![],
+        line_infos: MutMap::default(),
+    };
+    let mut names: Vec<String> = vec![];
+
+    let (import_fn_count, bodies) = function_bodies(module_bytes);
+    let mut mappings = vec![];
+    for (i, body) in bodies.into_iter().enumerate() {
+        let fn_index = import_fn_count + i as u32;
+        let app_fn = fn_index
+            .checked_sub(fn_index_offset)
+            .and_then(|i| app_fns.get(i as usize));
+
+        // Code offsets paired with the source they came from
+        let mut positions: Vec<(usize, Option<(usize, LineColumn)>)> = vec![];
+        if let Some(app_fn) = app_fn {
+            let def_position = source_info
+                .def_regions
+                .get(&app_fn.symbol)
+                .and_then(|region| sources.position(app_fn.symbol.module_id(), *region));
+            positions.push((body.code_start, def_position));
+
+            if app_fn.body_size == body.size {
+                for (offset, symbol) in app_fn.statements.iter() {
+                    let position = source_info
+                        .let_region(*symbol)
+                        .and_then(|(module_id, region)| sources.position(module_id, region));
+                    if position.is_none() {
+                        continue;
+                    }
+
+                    // If some statements generated no code, the last one is the one whose code is here
+                    let code_offset = body.start + *offset as usize;
+                    match positions.last_mut() {
+                        Some((last_offset, last)) if *last_offset == code_offset => {
+                            *last = position;
+                        }
+                        _ => positions.push((code_offset, position)),
+                    }
+                }
+            }
+        } else {
+            positions.push((body.code_start, None));
+        }
+
+        let name_index = match app_fn {
+            Some(app_fn) if positions.iter().any(|(_, position)| position.is_some()) => {
+                names.push(format!(
+                    "{}.{}",
+                    app_fn.symbol.module_string(interns).as_str(),
+                    app_fn.symbol.as_str(interns)
+                ));
+                names.len() - 1
+            }
+            _ => 0,
+        };
+
+        for (code_offset, position) in positions {
+            mappings.push(Mapping {
+                code_offset,
+                source: position.map(|(source_index, position)| OriginalPosition {
+                    source_index,
+                    line: position.line,
+                    column: position.column,
+                    name_index,
+                }),
+            });
+        }
+    }
+
+    let mut json = String::from("{\"version\":3,\"sources\":[");
+    for (i, module_id) in sources.module_ids.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        let (path, _) = &source_info.sources[module_id];
+        write_json_string(&mut json, &path.to_string_lossy());
+    }
+    json.push_str("],\"sourcesContent\":[");
+    for (i, module_id) in sources.module_ids.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        let (_, src) = &source_info.sources[module_id];
+        write_json_string(&mut json, src);
+    }
+    json.push_str("],\"names\":[");
+    for (i, name) in names.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        write_json_string(&mut json, name);
+    }
+    json.push_str("],\"mappings\":\"");
+    write_mappings(&mut json, &mappings);
+    json.push_str("\"}");
+    json
+}
+
+/// Append a `sourceMappingURL` custom section, with the source map embedded as a data URL
+pub fn append_source_map_section(buffer: &mut Vec<u8>, source_map: &str) {
+    let mut url = String::from("data:application/json;base64,");
+    write_base64(&mut url, source_map.as_bytes());
+
+    let header_indices = write_custom_section_header(buffer, "sourceMappingURL");
+    url.as_str().serialize(buffer);
+    update_section_size(buffer, header_indices);
+}
+
+/// Where a function body is in a serialized module
+struct FunctionBody {
+    /// Byte offset of the start of the body, just after its size
+    start: usize,
+    /// Byte offset of the first instruction, after the local declarations
+    code_start: usize,
+    size: usize,
+}
+
+/// The number of imported functions in a serialized module, and where each function body is
+fn function_bodies(module_bytes: &[u8]) -> (u32, Vec<FunctionBody>) {
+    let mut import_fn_count = 0;
+    let mut bodies = vec![];
+    let mut cursor = 8; // magic number and version
+
+    while cursor < module_bytes.len() {
+        let id = module_bytes[cursor];
+        cursor += 1;
+        let size = u32::parse((), module_bytes, &mut cursor).unwrap() as usize;
+        let section_end = cursor + size;
+
+        if id == SectionId::Import as u8 {
+            let count = u32::parse((), module_bytes, &mut cursor).unwrap();
+            for _ in 0..count {
+                for _ in 0..2 {
+                    let name_len = u32::parse((), module_bytes, &mut cursor).unwrap() as usize;
+                    cursor += name_len;
+                }
+                let kind = module_bytes[cursor];
+                cursor += 1;
+                match kind {
+                    // function
+                    0 => {
+                        u32::parse((), module_bytes, &mut cursor).unwrap();
+                        import_fn_count += 1;
+                    }
+                    // table
+                    1 => {
+                        cursor += 1; // reference type
+                        skip_limits(module_bytes, &mut cursor);
+                    }
+                    // memory
+                    2 => skip_limits(module_bytes, &mut cursor),
+                    // global
+                    _ => cursor += 2,
+                }
+            }
+        } else if id == SectionId::Code as u8 {
+            let count = u32::parse((), module_bytes, &mut cursor).unwrap();
+            for _ in 0..count {
+                let size = u32::parse((), module_bytes, &mut cursor).unwrap() as usize;
+                let start = cursor;
+                let local_group_count = u32::parse((), module_bytes, &mut cursor).unwrap();
+                for _ in 0..local_group_count {
+                    u32::parse((), module_bytes, &mut cursor).unwrap();
+                    cursor += 1; // value type
+                }
+                bodies.push(FunctionBody {
+                    start,
+                    code_start: cursor,
+                    size,
+                });
+                cursor = start + size;
+            }
+        }
+
+        cursor = section_end;
+    }
+
+    (import_fn_count, bodies)
+}
+
+fn skip_limits(module_bytes: &[u8], cursor: &mut usize) {
+    let has_max = module_bytes[*cursor] != 0;
+    *cursor += 1;
+    u32::parse((), module_bytes, cursor).unwrap();
+    if has_max {
+        u32::parse((), module_bytes, cursor).unwrap();
+    }
+}
+
+/// Write the mappings for a single line of "generated code", where the columns are byte offsets
+fn write_mappings(json: &mut String, mappings: &[Mapping]) {
+    let mut prev_code_offset = 0;
+    let mut prev_source_index = 0;
+    let mut prev_line = 0;
+    let mut prev_column = 0;
+    let mut prev_name_index = 0;
+
+    for (i, mapping) in mappings.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        write_vlq(json, mapping.code_offset as i64 - prev_code_offset);
+        prev_code_offset = mapping.code_offset as i64;
+
+        // A mapping with no source marks code that didn't come from Roc source, like the host
+        if let Some(source) = &mapping.source {
+            write_vlq(json, source.source_index as i64 - prev_source_index);
+            write_vlq(json, source.line as i64 - prev_line);
+            write_vlq(json, source.column as i64 - prev_column);
+            write_vlq(json, source.name_index as i64 - prev_name_index);
+            prev_source_index = source.source_index as i64;
+            prev_line = source.line as i64;
+            prev_column = source.column as i64;
+            prev_name_index = source.name_index as i64;
+        }
+    }
+}
+
+/// Base64 variable-length quantity, as used in source map segments
+fn write_vlq(out: &mut String, value: i64) {
+    let mut vlq = if value < 0 {
+        ((-value as u64) << 1) | 1
+    } else {
+        (value as u64) << 1
+    };
+    loop {
+        let mut digit = (vlq & 0x1f) as usize;
+        vlq >>= 5;
+        if vlq > 0 {
+            digit |= 0x20; // continuation bit
+        }
+        out.push(BASE64_CHARS[digit] as char);
+        if vlq == 0 {
+            break;
+        }
+    }
+}
+
+fn write_base64(out: &mut String, bytes: &[u8]) {
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = ((b[0] as usize) << 16) | ((b[1] as usize) << 8) | b[2] as usize;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_CHARS[(n >> (18 - 6 * i)) & 0x3f] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+}
+
+fn write_json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code_builder::CodeBuilder;
+    use bumpalo::Bump;
+    use roc_region::all::Position;
+    use roc_wasm_module::{LocalId, Signature, ValueType, WasmModule};
+    use std::path::PathBuf;
+
+    fn vlq(value: i64) -> String {
+        let mut out = String::new();
+        write_vlq(&mut out, value);
+        out
+    }
+
+    #[test]
+    fn test_vlq() {
+        assert_eq!(vlq(0), "A");
+        assert_eq!(vlq(1), "C");
+        assert_eq!(vlq(-1), "D");
+        assert_eq!(vlq(15), "e");
+        assert_eq!(vlq(16), "gB");
+        assert_eq!(vlq(-17), "jB");
+        assert_eq!(vlq(1000), "w+B");
+    }
+
+    #[test]
+    fn test_base64() {
+        for (input, expected) in [("", ""), ("M", "TQ=="), ("Ma", "TWE="), ("Man", "TWFu")] {
+            let mut out = String::new();
+            write_base64(&mut out, input.as_bytes());
+            assert_eq!(out, expected);
+        }
+    }
+
+    #[test]
+    fn test_mappings() {
+        let mappings = [
+            Mapping {
+                code_offset: 100,
+                source: None,
+            },
+            Mapping {
+                code_offset: 120,
+                source: Some(OriginalPosition {
+                    source_index: 0,
+                    line: 3,
+                    column: 0,
+                    name_index: 0,
+                }),
+            },
+            Mapping {
+                code_offset: 150,
+                source: Some(OriginalPosition {
+                    source_index: 0,
+                    line: 1,
+                    column: 4,
+                    name_index: 1,
+                }),
+            },
+        ];
+        let mut json = String::new();
+        write_mappings(&mut json, &mappings);
+        assert_eq!(json, "oG,oBAGAA,8BAFIC");
+    }
+
+    #[test]
+    fn test_statement_mappings() {
+        let src = "app [main] {}\n\nmain =\n    x = 1\n    y = x + 1\n    y\n";
+        let region_of = |name: &str| {
+            let start = src.find(name).unwrap() as u32;
+            Region::new(
+                Position::new(start),
+                Position::new(start + name.len() as u32),
+            )
+        };
+
+        let mut interns = Interns::default();
+        let home = interns.module_id(&"Test".into());
+        let ident_ids = interns.all_ident_ids.get_or_insert(home);
+        let main = Symbol::new(home, ident_ids.add_str("main"));
+        let x = Symbol::new(home, ident_ids.add_str("x"));
+        let y = Symbol::new(home, ident_ids.add_str("y"));
+
+        let mut sources = MutMap::default();
+        sources.insert(home, (PathBuf::from("Test.roc"), src.into()));
+        let mut def_regions = MutMap::default();
+        def_regions.insert(main, region_of("main ="));
+        let mut stmt_regions = MutMap::default();
+        stmt_regions.insert(x, region_of("x = 1"));
+        stmt_regions.insert(y, region_of("y = x + 1"));
+        let source_info = SourceInfo {
+            sources: &sources,
+            def_regions: &def_regions,
+            stmt_regions: &stmt_regions,
+        };
+
+        // main = x = 1; y = x + 1; y
+        let arena = Bump::new();
+        let mut module = WasmModule::new(&arena);
+        module.add_function_signature(Signature {
+            param_types: bumpalo::vec![in &arena],
+            ret_types: bumpalo::vec![in &arena; ValueType::I32],
+        });
+        let mut code_builder = CodeBuilder::new(&arena);
+        code_builder.mark_statement(x);
+        code_builder.i32_const(1);
+        code_builder.set_local(LocalId(0));
+        code_builder.mark_statement(y);
+        code_builder.get_local(LocalId(0));
+        code_builder.i32_const(1);
+        code_builder.i32_add();
+        code_builder.build_fn_header_and_footer(&[ValueType::I32], 0, None);
+
+        let app_fns = [AppFunction {
+            symbol: main,
+            body_size: code_builder.body_size(),
+            statements: code_builder.statement_offsets(),
+        }];
+        code_builder.insert_into_module(&mut module);
+        let mut module_bytes = vec![];
+        module.serialize(&mut module_bytes);
+
+        let json = build_source_map(source_info, &interns, &module_bytes, 0, &app_fns);
+
+        // `x` starts right after the local declarations, where `main` does.
+        // `y` starts after `i32.const 1` (2 bytes) and `local.set 0` (2 bytes).
+        let (_, bodies) = function_bodies(&module_bytes);
+        let position = |line, column| {
+            Some(OriginalPosition {
+                source_index: 0,
+                line,
+                column,
+                name_index: 0,
+            })
+        };
+        let mut expected_mappings = String::new();
+        write_mappings(
+            &mut expected_mappings,
+            &[
+                Mapping {
+                    code_offset: bodies[0].code_start,
+                    source: position(3, 4),
+                },
+                Mapping {
+                    code_offset: bodies[0].code_start + 4,
+                    source: position(4, 4),
+                },
+            ],
+        );
+
+        assert!(json.starts_with("{\"version\":3,\"sources\":[\"Test.roc\"]"));
+        assert!(json.contains("\"names\":[\"Test.main\"]"));
+        assert!(json.ends_with(&format!("\"mappings\":\"{expected_mappings}\"}}")));
+
+        // After dead code elimination replaces the body, only the definition is mapped.
+        let replaced = [AppFunction {
+            body_size: app_fns[0].body_size + 1,
+            statements: bumpalo::vec![in &arena; (4, y)],
+            ..app_fns[0]
+        }];
+        let json = build_source_map(source_info, &interns, &module_bytes, 0, &replaced);
+        let mut expected_mappings = String::new();
+        write_mappings(
+            &mut expected_mappings,
+            &[Mapping {
+                code_offset: bodies[0].code_start,
+                source: position(2, 0),
+            }],
+        );
+        assert!(json.ends_with(&format!("\"mappings\":\"{expected_mappings}\"}}")));
+    }
+}
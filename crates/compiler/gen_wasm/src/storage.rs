@@ -60,7 +60,8 @@ pub enum AddressValue {
 /// including the VM stack, local variables, and linear memory
 #[derive(Debug)]
 pub struct Storage<'a> {
-    pub return_var: Option<LocalId>,
+    /// Locals for the return values, in order. More than one requires the multi-value proposal.
+    pub return_vars: Vec<'a, LocalId>,
     pub arg_types: Vec<'a, ValueType>,
     pub local_types: Vec<'a, ValueType>,
     pub symbol_layouts: MutMap<Symbol, InLayout<'a>>,
@@ -72,7 +73,7 @@ pub struct Storage<'a> {
 impl<'a> Storage<'a> {
     pub fn new(arena: &'a Bump) -> Self {
         Storage {
-            return_var: None,
+            return_vars: Vec::with_capacity_in(4, arena),
             arg_types: Vec::with_capacity_in(8, arena),
             local_types: Vec::with_capacity_in(32, arena),
             symbol_layouts: MutMap::default(),
@@ -83,7 +84,7 @@ impl<'a> Storage<'a> {
     }
 
     pub fn clear(&mut self) {
-        self.return_var = None;
+        self.return_vars.clear();
         self.arg_types.clear();
         self.local_types.clear();
         self.symbol_layouts.clear();
@@ -16,6 +16,7 @@ const SKIP_SUBS_CACHE: bool = {
     }
 };
 
+pub use roc_load_internal::analysis_cache::AnalysisCache;
 pub use roc_load_internal::docs;
 pub use roc_load_internal::file::{
     ExecutionMode, ExpectMetadata, LoadConfig, LoadResult, LoadStart, LoadingProblem, Phase,
@@ -185,8 +186,6 @@ pub fn load_and_typecheck_str<'a>(
     roc_cache_dir: RocCacheDir<'_>,
     palette: Palette,
 ) -> Result<LoadedModule, LoadingProblem<'a>> {
-    use LoadResult::*;
-
     let load_start = LoadStart::from_str(
         arena,
         filename,
@@ -196,6 +195,66 @@ pub fn load_and_typecheck_str<'a>(
         src_dir,
     )?;
 
+    typecheck_single_threaded(
+        arena,
+        load_start,
+        target,
+        function_kind,
+        render,
+        roc_cache_dir,
+        palette,
+    )
+}
+
+/// Like [load_and_typecheck_str], but takes the analysis of every module that hasn't changed
+/// since an earlier load with the same `analysis_cache` from that cache, and adds the modules it
+/// did analyse to it.
+#[allow(clippy::too_many_arguments)]
+pub fn load_and_typecheck_str_with_cache<'a>(
+    arena: &'a Bump,
+    filename: PathBuf,
+    source: &'a str,
+    src_dir: PathBuf,
+    opt_main_path: Option<PathBuf>,
+    target: Target,
+    function_kind: FunctionKind,
+    render: RenderTarget,
+    roc_cache_dir: RocCacheDir<'_>,
+    palette: Palette,
+    analysis_cache: &'a mut AnalysisCache,
+) -> Result<LoadedModule, LoadingProblem<'a>> {
+    let load_start = LoadStart::from_str_with_cache(
+        arena,
+        filename,
+        opt_main_path,
+        source,
+        roc_cache_dir,
+        src_dir,
+        analysis_cache,
+    )?;
+
+    typecheck_single_threaded(
+        arena,
+        load_start,
+        target,
+        function_kind,
+        render,
+        roc_cache_dir,
+        palette,
+    )
+}
+
+fn typecheck_single_threaded<'a>(
+    arena: &'a Bump,
+    load_start: LoadStart<'a>,
+    target: Target,
+    function_kind: FunctionKind,
+    render: RenderTarget,
+    roc_cache_dir: RocCacheDir<'_>,
+    palette: Palette,
+) -> Result<LoadedModule, LoadingProblem<'a>> {
+    use LoadResult::*;
+
     // NOTE: this function is meant for tests, and so we use single-threaded
     // solving so we don't use too many threads per-test. That gives higher
     // throughput for the test run overall
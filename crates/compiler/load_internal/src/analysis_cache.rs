@@ -0,0 +1,354 @@
+//! Lets a load reuse what earlier loads found out about modules that haven't changed since.
+use crate::docs::ModuleDocumentation;
+use crate::module::{ModuleHeader, ParsedModule};
+use bumpalo::Bump;
+use roc_can::abilities::{AbilitiesStore, PendingAbilitiesStore};
+use roc_can::expr::{DbgLookup, Declarations, ExpectLookup};
+use roc_collections::{MutMap, MutSet, VecMap};
+use roc_module::ident::ModuleName;
+use roc_module::symbol::{
+    IdentIds, IdentIdsByModule, ModuleId, PQModuleName, PackageModuleIds, PackageQualified, Symbol,
+};
+use roc_parse::ast::Defs;
+use roc_parse::header::HeaderType;
+use roc_region::all::Region;
+use roc_solve::module::{Solved, SolvedModule};
+use roc_types::subs::Subs;
+use roc_types::types::Alias;
+use std::path::{Path, PathBuf};
+
+/// The analyses of the modules earlier loads went through, so a load only has to parse,
+/// canonicalize and solve the modules whose source changed since, and the modules that import
+/// those. Everything else is taken from the cache.
+///
+/// Only plain `module`s of the root's own package are cached, and the root module itself is
+/// always analysed again.
+#[derive(Debug, Default)]
+pub struct AnalysisCache {
+    /// The name of every module the earlier loads gave a [ModuleId] to, by id. The cached analyses
+    /// refer to modules by their ids, so a load has to hand out the same ids.
+    module_names: Vec<(Option<Box<str>>, ModuleName)>,
+    modules: MutMap<ModuleId, CachedModule>,
+    last_reused: MutSet<ModuleId>,
+}
+
+impl AnalysisCache {
+    /// The modules the last load took from the cache instead of analysing them.
+    pub fn last_reused(&self) -> &MutSet<ModuleId> {
+        &self.last_reused
+    }
+
+    /// The module ids for a load to start with, so that it gives every module it shares with an
+    /// earlier load the same id.
+    pub(crate) fn module_ids<'a>(&self, arena: &'a Bump) -> PackageModuleIds<'a> {
+        let mut module_ids = PackageModuleIds::default();
+
+        for (shorthand, name) in self.module_names.iter() {
+            let pq_name = match shorthand {
+                None => PackageQualified::Unqualified(name.clone()),
+                Some(shorthand) => {
+                    PackageQualified::Qualified(arena.alloc_str(shorthand), name.clone())
+                }
+            };
+
+            module_ids.get_or_insert(&pq_name);
+        }
+
+        module_ids
+    }
+
+    /// Finds the modules whose cached analysis is still right: the ones in `src_dir` whose file
+    /// has the same source as when we analysed it, and all of whose imports are still right too.
+    fn reusable_modules(&self, root_id: ModuleId, src_dir: &Path) -> MutSet<ModuleId> {
+        let mut reusable: MutSet<ModuleId> = self
+            .modules
+            .iter()
+            .filter(|(module_id, module)| {
+                **module_id != root_id
+                    && module.src_dir == src_dir
+                    && std::fs::read_to_string(&module.path).is_ok_and(|src| *src == *module.src)
+            })
+            .map(|(module_id, _)| *module_id)
+            .collect();
+
+        loop {
+            let changed: Vec<ModuleId> = reusable
+                .iter()
+                .filter(|module_id| {
+                    self.modules[module_id]
+                        .parsed
+                        .available_modules
+                        .keys()
+                        .any(|dep| !dep.is_builtin() && !reusable.contains(dep))
+                })
+                .copied()
+                .collect();
+
+            if changed.is_empty() {
+                return reusable;
+            }
+
+            for module_id in changed {
+                reusable.remove(&module_id);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CachedModule {
+    src_dir: PathBuf,
+    path: PathBuf,
+    src: Box<str>,
+    parsed: CachedParse,
+    canonicalized: CachedCanonicalization,
+    solved: CachedSolve,
+}
+
+#[derive(Debug, Clone)]
+struct CachedParse {
+    deps_by_name: Vec<(ModuleName, ModuleId)>,
+    exposed_ident_ids: IdentIds,
+    available_modules: MutMap<ModuleId, Region>,
+    exposes: Vec<Symbol>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct CachedCanonicalization {
+    pub(crate) problems: Vec<roc_problem::can::Problem>,
+    pub(crate) module_docs: Option<ModuleDocumentation>,
+    pub(crate) exposed_imports: MutMap<Symbol, Region>,
+    pub(crate) aliases: MutMap<Symbol, (bool, Alias)>,
+    pub(crate) abilities_store: PendingAbilitiesStore,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct CachedSolve {
+    pub(crate) ident_ids: IdentIds,
+    pub(crate) solved_module: SolvedModule,
+    pub(crate) solved_subs: Solved<Subs>,
+    pub(crate) decls: Declarations,
+    pub(crate) dep_idents: IdentIdsByModule,
+    pub(crate) abilities_store: AbilitiesStore,
+    pub(crate) loc_expects: VecMap<Region, Vec<ExpectLookup>>,
+    pub(crate) loc_dbgs: VecMap<Symbol, DbgLookup>,
+}
+
+/// A load that takes what it can from an [AnalysisCache], and adds what it analyses to it.
+#[derive(Debug)]
+pub(crate) struct IncrementalLoad<'a> {
+    cache: &'a mut AnalysisCache,
+    src_dir: PathBuf,
+    reusable: MutSet<ModuleId>,
+    reused: MutSet<ModuleId>,
+    parsed: MutMap<ModuleId, (PathBuf, Box<str>, CachedParse)>,
+    canonicalized: MutMap<ModuleId, CachedCanonicalization>,
+    analysed: MutMap<ModuleId, CachedModule>,
+}
+
+impl<'a> IncrementalLoad<'a> {
+    /// Works out which modules can be reused, and gives them back the ident ids their
+    /// dependents were canonicalized against.
+    pub(crate) fn new(
+        cache: &'a mut AnalysisCache,
+        root_id: ModuleId,
+        src_dir: &Path,
+        ident_ids_by_module: &mut IdentIdsByModule,
+    ) -> Self {
+        let reusable = cache.reusable_modules(root_id, src_dir);
+
+        for module_id in reusable.iter() {
+            let exposed_ident_ids = &cache.modules[module_id].parsed.exposed_ident_ids;
+
+            *ident_ids_by_module.get_or_insert(*module_id) = exposed_ident_ids.clone();
+        }
+
+        Self {
+            cache,
+            src_dir: src_dir.to_path_buf(),
+            reusable,
+            reused: MutSet::default(),
+            parsed: MutMap::default(),
+            canonicalized: MutMap::default(),
+            analysed: MutMap::default(),
+        }
+    }
+
+    /// Whether this load took the module's analysis from the cache.
+    pub(crate) fn is_reused(&self, module_id: ModuleId) -> bool {
+        self.reused.contains(&module_id)
+    }
+
+    /// The source of a module we can take the parse of from the cache, as read for this load.
+    fn reusable_src(&self, header: &ModuleHeader<'a>) -> Option<&'a str> {
+        let module_id = header.module_id;
+        let src = std::str::from_utf8(header.parse_state.original_bytes()).ok()?;
+
+        // The file could have changed since we decided what to reuse.
+        let is_reusable =
+            self.reusable.contains(&module_id) && *src == *self.cache.modules[&module_id].src;
+
+        is_reusable.then_some(src)
+    }
+
+    /// Whether [IncrementalLoad::reuse_parse] can stand in for parsing this module.
+    pub(crate) fn can_reuse_parse(&self, header: &ModuleHeader<'a>) -> bool {
+        self.reusable_src(header).is_some()
+    }
+
+    /// What parsing the module would give, apart from its defs; a reused module is never
+    /// canonicalized, so nothing looks at those.
+    pub(crate) fn reuse_parse(&mut self, header: ModuleHeader<'a>) -> ParsedModule<'a> {
+        let module_id = header.module_id;
+        let src = self
+            .reusable_src(&header)
+            .expect("reuse_parse called for a module that has to be parsed");
+
+        self.reused.insert(module_id);
+
+        let CachedParse {
+            deps_by_name,
+            exposed_ident_ids,
+            available_modules,
+            exposes,
+        } = self.cache.modules[&module_id].parsed.clone();
+
+        let deps_by_name: MutMap<PQModuleName<'a>, ModuleId> = deps_by_name
+            .into_iter()
+            .map(|(name, module_id)| (PackageQualified::Unqualified(name), module_id))
+            .collect();
+
+        let package_qualified_available_modules = deps_by_name
+            .values()
+            .map(|module_id| PackageQualified::Unqualified(*module_id))
+            .collect();
+
+        ParsedModule {
+            module_id,
+            module_path: header.module_path,
+            src,
+            module_timing: header.module_timing,
+            deps_by_name,
+            exposed_ident_ids,
+            parsed_defs: Defs::default(),
+            symbols_from_requires: Vec::new(),
+            header_type: header.header_type,
+            header_comments: header.header_comments,
+            available_modules,
+            package_qualified_available_modules,
+            packages: header.packages,
+            initial_scope: MutMap::default(),
+            exposes,
+            opt_shorthand: header.opt_shorthand,
+        }
+    }
+
+    pub(crate) fn reused_canonicalization(&self, module_id: ModuleId) -> CachedCanonicalization {
+        self.cache.modules[&module_id].canonicalized.clone()
+    }
+
+    pub(crate) fn reused_solve(&self, module_id: ModuleId) -> CachedSolve {
+        self.cache.modules[&module_id].solved.clone()
+    }
+
+    /// Remembers how a module we analysed in this load parsed, if it is one we can cache.
+    pub(crate) fn record_parse(
+        &mut self,
+        parsed: &ParsedModule<'a>,
+        module_ids: &PackageModuleIds,
+    ) {
+        let module_id = parsed.module_id;
+
+        let is_cacheable = !module_id.is_builtin()
+            && !self.is_reused(module_id)
+            && matches!(parsed.header_type, HeaderType::Module { .. })
+            && matches!(
+                module_ids.get_name(module_id),
+                Some(PackageQualified::Unqualified(_))
+            )
+            && parsed
+                .deps_by_name
+                .keys()
+                .all(|name| matches!(name, PackageQualified::Unqualified(_)));
+
+        if !is_cacheable {
+            return;
+        }
+
+        let deps_by_name = parsed
+            .deps_by_name
+            .iter()
+            .map(|(name, module_id)| (name.as_inner().clone(), *module_id))
+            .collect();
+
+        self.parsed.insert(
+            module_id,
+            (
+                parsed.module_path.clone(),
+                parsed.src.into(),
+                CachedParse {
+                    deps_by_name,
+                    exposed_ident_ids: parsed.exposed_ident_ids.clone(),
+                    available_modules: parsed.available_modules.clone(),
+                    exposes: parsed.exposes.clone(),
+                },
+            ),
+        );
+    }
+
+    /// Whether we are keeping the analysis of this module, so should record its later phases.
+    pub(crate) fn is_recording(&self, module_id: ModuleId) -> bool {
+        self.parsed.contains_key(&module_id)
+    }
+
+    pub(crate) fn record_canonicalization(
+        &mut self,
+        module_id: ModuleId,
+        canonicalized: CachedCanonicalization,
+    ) {
+        self.canonicalized.insert(module_id, canonicalized);
+    }
+
+    pub(crate) fn record_solve(&mut self, module_id: ModuleId, solved: CachedSolve) {
+        let (Some((path, src, parsed)), Some(canonicalized)) = (
+            self.parsed.remove(&module_id),
+            self.canonicalized.remove(&module_id),
+        ) else {
+            return;
+        };
+
+        self.analysed.insert(
+            module_id,
+            CachedModule {
+                src_dir: self.src_dir.clone(),
+                path,
+                src,
+                parsed,
+                canonicalized,
+                solved,
+            },
+        );
+    }
+
+    /// Adds the modules analysed by a load that finished type checking to the cache.
+    pub(crate) fn finish(self, module_ids: &PackageModuleIds) {
+        let IncrementalLoad {
+            cache,
+            reused,
+            analysed,
+            ..
+        } = self;
+
+        cache.module_names = module_ids
+            .available_modules()
+            .map(|name| {
+                (
+                    name.package_shorthand().map(Box::from),
+                    name.as_inner().clone(),
+                )
+            })
+            .collect();
+        cache.modules.extend(analysed);
+        cache.last_reused = reused;
+    }
+}
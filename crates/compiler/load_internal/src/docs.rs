@@ -10,7 +10,7 @@ use roc_parse::ast::{CommentOrNewline, TypeDef, ValueDef};
 
 // Documentation generation requirements
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ModuleDocumentation {
     pub name: String,
     pub entries: Vec<DocEntry>,
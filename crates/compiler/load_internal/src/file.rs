@@ -1,5 +1,6 @@
 #![allow(clippy::too_many_arguments)]
 
+use crate::analysis_cache::{AnalysisCache, CachedCanonicalization, CachedSolve, IncrementalLoad};
 use crate::docs::ModuleDocumentation;
 use crate::module::{
     CheckedModule, ConstrainedModule, EntryPoint, Expectations, ExposedToHost,
@@ -15,11 +16,14 @@ use parking_lot::Mutex;
 use roc_builtins::roc::module_source;
 use roc_can::abilities::{AbilitiesStore, PendingAbilitiesStore, ResolvedImpl};
 use roc_can::constraint::{Constraint as ConstraintSoa, Constraints, TypeOrVar};
+use roc_can::def::Def;
 use roc_can::expr::{DbgLookup, Declarations, ExpectLookup, PendingDerives};
 use roc_can::module::{
     canonicalize_module_defs, ExposedByModule, ExposedForModule, ExposedModuleTypes, Module,
     ResolvedImplementations, TypeState,
 };
+use roc_can::pattern::Pattern;
+use roc_can::traverse::{walk_def, Visitor};
 use roc_collections::{default_hasher, BumpMap, MutMap, MutSet, VecMap, VecSet};
 use roc_constrain::module::constrain_module;
 use roc_debug_flags::dbg_do;
@@ -167,6 +171,10 @@ fn start_phase<'a>(
         }
     }
 
+    if let Some(task) = start_reused_phase(module_id, phase, state) {
+        return vec![task];
+    }
+
     let task = {
         match phase {
             Phase::LoadHeader => {
@@ -548,6 +556,68 @@ fn start_phase<'a>(
     vec![task]
 }
 
+/// Skips the work of a phase for a module whose analysis this load takes from an [AnalysisCache].
+fn start_reused_phase<'a>(
+    module_id: ModuleId,
+    phase: Phase,
+    state: &mut State<'a>,
+) -> Option<BuildTask<'a>> {
+    let incremental = state.incremental.as_mut()?;
+
+    let msg = match phase {
+        Phase::Parse => {
+            let header = state.module_cache.headers.get(&module_id).unwrap();
+
+            if !incremental.can_reuse_parse(header) {
+                return None;
+            }
+
+            let header = state.module_cache.headers.remove(&module_id).unwrap();
+
+            Msg::Parsed(incremental.reuse_parse(header))
+        }
+        Phase::CanonicalizeAndConstrain if incremental.is_reused(module_id) => {
+            state.module_cache.parsed.remove(&module_id);
+
+            Msg::ReusedCanonicalization {
+                module_id,
+                canonicalization: incremental.reused_canonicalization(module_id),
+            }
+        }
+        Phase::SolveTypes if incremental.is_reused(module_id) => {
+            let CachedSolve {
+                ident_ids,
+                solved_module,
+                solved_subs,
+                decls,
+                dep_idents,
+                abilities_store,
+                loc_expects,
+                loc_dbgs,
+            } = incremental.reused_solve(module_id);
+
+            Msg::SolvedTypes {
+                module_id,
+                ident_ids,
+                solved_module,
+                solved_subs,
+                decls,
+                dep_idents,
+                module_timing: ModuleTiming::new(Instant::now()),
+                abilities_store,
+                loc_expects,
+                loc_dbgs,
+
+                #[cfg(debug_assertions)]
+                checkmate: None,
+            }
+        }
+        _ => return None,
+    };
+
+    Some(BuildTask::Reuse { msg })
+}
+
 /// Values used to render expect output
 pub struct ExpectMetadata<'a> {
     pub interns: Interns,
@@ -566,6 +636,11 @@ enum Msg<'a> {
     Header(ModuleHeader<'a>),
     Parsed(ParsedModule<'a>),
     CanonicalizedAndConstrained(CanAndCon),
+    /// The canonicalization of a module whose analysis this load takes from an [AnalysisCache].
+    ReusedCanonicalization {
+        module_id: ModuleId,
+        canonicalization: CachedCanonicalization,
+    },
     SolvedTypes {
         module_id: ModuleId,
         ident_ids: IdentIds,
@@ -602,6 +677,8 @@ enum Msg<'a> {
         solved_subs: Solved<Subs>,
         module_timing: ModuleTiming,
         toplevel_expects: ToplevelExpects,
+        def_regions: VecMap<Symbol, Region>,
+        stmt_regions: MutMap<Symbol, Region>,
         expectations: Option<Expectations>,
     },
     MadeSpecializations {
@@ -710,6 +787,8 @@ struct State<'a> {
     pub procedures: MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
     pub host_exposed_lambda_sets: HostExposedLambdaSets<'a>,
     pub toplevel_expects: MutMap<ModuleId, ToplevelExpects>,
+    pub def_regions: MutMap<Symbol, Region>,
+    pub stmt_regions: MutMap<Symbol, Region>,
     pub exposed_to_host: ExposedToHost,
 
     /// This is the "final" list of IdentIds, after canonicalization and constraint gen
@@ -748,6 +827,9 @@ struct State<'a> {
     // cached types (used for builtin modules, could include packages in the future too)
     cached_types: CachedTypeState,
 
+    /// Analyses of unchanged modules from earlier loads, if this load uses them.
+    incremental: Option<IncrementalLoad<'a>>,
+
     layout_interner: GlobalLayoutInterner<'a>,
 }
 
@@ -770,6 +852,7 @@ impl<'a> State<'a> {
         ident_ids_by_module: SharedIdentIdsByModule,
         arc_shorthands: Arc<Mutex<MutMap<&'a str, ShorthandPath>>>,
         cached_types: MutMap<ModuleId, TypeState>,
+        incremental: Option<IncrementalLoad<'a>>,
         render: RenderTarget,
         palette: Palette,
         number_of_workers: usize,
@@ -794,6 +877,8 @@ impl<'a> State<'a> {
             procedures: MutMap::default(),
             host_exposed_lambda_sets: std::vec::Vec::new(),
             toplevel_expects: MutMap::default(),
+            def_regions: MutMap::default(),
+            stmt_regions: MutMap::default(),
             exposed_to_host: ExposedToHost::default(),
             exposed_modules: &[],
             exposed_types,
@@ -807,6 +892,7 @@ impl<'a> State<'a> {
             timings: MutMap::default(),
             layout_caches: std::vec::Vec::with_capacity(number_of_workers),
             cached_types: Arc::new(Mutex::new(cached_types)),
+            incremental,
             render,
             palette,
             exec_mode,
@@ -936,6 +1022,8 @@ enum BuildTask<'a> {
         derived_module: SharedDerivedModule,
         expectations: Option<Expectations>,
     },
+    /// Sends back what an earlier load found in a phase of a module that hasn't changed since.
+    Reuse { msg: Msg<'a> },
 }
 
 #[derive(Debug)]
@@ -1146,6 +1234,7 @@ pub struct LoadStart<'a> {
     root_type: RootType,
     opt_platform_shorthand: Option<&'a str>,
     src_dir: PathBuf,
+    incremental: Option<IncrementalLoad<'a>>,
 }
 
 #[derive(Debug, Clone)]
@@ -1227,18 +1316,67 @@ impl<'a> LoadStart<'a> {
             root_msg: header_output.msg,
             root_type,
             opt_platform_shorthand: header_output.opt_platform_shorthand,
+            incremental: None,
         })
     }
 
     pub fn from_str(
+        arena: &'a Bump,
+        filename: PathBuf,
+        opt_main_path: Option<PathBuf>,
+        src: &'a str,
+        roc_cache_dir: RocCacheDir<'_>,
+        src_dir: PathBuf,
+    ) -> Result<Self, LoadingProblem<'a>> {
+        Self::from_str_help(
+            arena,
+            filename,
+            opt_main_path,
+            src,
+            roc_cache_dir,
+            src_dir,
+            None,
+        )
+    }
+
+    /// Like [LoadStart::from_str], but the load takes the analysis of every module that didn't
+    /// change since an earlier load from `analysis_cache`, and adds the modules it does analyse
+    /// to it. Only worth it for loads that stop after type checking.
+    pub fn from_str_with_cache(
+        arena: &'a Bump,
+        filename: PathBuf,
+        opt_main_path: Option<PathBuf>,
+        src: &'a str,
+        roc_cache_dir: RocCacheDir<'_>,
+        src_dir: PathBuf,
+        analysis_cache: &'a mut AnalysisCache,
+    ) -> Result<Self, LoadingProblem<'a>> {
+        Self::from_str_help(
+            arena,
+            filename,
+            opt_main_path,
+            src,
+            roc_cache_dir,
+            src_dir,
+            Some(analysis_cache),
+        )
+    }
+
+    fn from_str_help(
         arena: &'a Bump,
         filename: PathBuf,
         opt_main_path: Option<PathBuf>,
         src: &'a str,
         roc_cache_dir: RocCacheDir<'_>,
         mut src_dir: PathBuf,
+        analysis_cache: Option<&'a mut AnalysisCache>,
     ) -> Result<Self, LoadingProblem<'a>> {
-        let arc_modules = Arc::new(Mutex::new(PackageModuleIds::default()));
+        // Modules have to get the same ids as in the loads the cached analyses come from.
+        let module_ids = match &analysis_cache {
+            Some(analysis_cache) => analysis_cache.module_ids(arena),
+            None => PackageModuleIds::default(),
+        };
+        let arc_modules = Arc::new(Mutex::new(module_ids));
         let arc_shorthands = Arc::new(Mutex::new(MutMap::default()));
         let root_exposed_ident_ids = IdentIds::exposed_builtins(0);
         let ident_ids_by_module = Arc::new(Mutex::new(root_exposed_ident_ids));
@@ -1273,6 +1411,15 @@ impl<'a> LoadStart<'a> {
             opt_platform_shorthand: opt_platform_id,
         } = header_output;
 
+        let incremental = analysis_cache.map(|analysis_cache| {
+            IncrementalLoad::new(
+                analysis_cache,
+                root_id,
+                &src_dir,
+                &mut ident_ids_by_module.lock(),
+            )
+        });
+
         Ok(LoadStart {
             arc_modules,
             arc_shorthands,
@@ -1283,6 +1430,7 @@ impl<'a> LoadStart<'a> {
             root_msg,
             root_type,
             opt_platform_shorthand: opt_platform_id,
+            incremental,
         })
     }
 }
@@ -1325,6 +1473,7 @@ fn handle_root_type<'a>(
                         Arc::clone(&arc_modules),
                         Arc::clone(&ident_ids_by_module),
                         Arc::clone(&arc_shorthands),
+                        roc_cache_dir,
                         cache_dir,
                     )?;
 
@@ -1365,6 +1514,7 @@ fn load_packages_from_main<'a>(
     module_ids: Arc<Mutex<PackageModuleIds<'a>>>,
     ident_ids_by_module: SharedIdentIdsByModule,
     arc_shorthands: Arc<Mutex<MutMap<&'a str, ShorthandPath>>>,
+    roc_cache_dir: RocCacheDir<'_>,
     cache_dir: &Path,
 ) -> Result<(), LoadingProblem<'a>> {
     let src_bytes = fs::read(&filename).map_err(|err| LoadingProblem::FileProblem {
@@ -1395,7 +1545,7 @@ fn load_packages_from_main<'a>(
     load_packages(
         packages,
         messages,
-        RocCacheDir::Persistent(cache_dir),
+        roc_cache_dir,
         src_dir.clone(),
         arena,
         None,
@@ -1566,7 +1716,7 @@ pub fn load_single_threaded<'a>(
         root_type,
         src_dir,
         opt_platform_shorthand,
-        ..
+        incremental,
     } = load_start;
 
     let (msg_tx, msg_rx) = bounded(1024);
@@ -1588,6 +1738,7 @@ pub fn load_single_threaded<'a>(
         ident_ids_by_module,
         arc_shorthands,
         cached_types,
+        incremental,
         render,
         palette,
         number_of_workers,
@@ -1967,7 +2118,7 @@ fn load_multi_threaded<'a>(
         root_type,
         src_dir,
         opt_platform_shorthand,
-        ..
+        incremental,
     } = load_start;
 
     let (msg_tx, msg_rx) = bounded(1024);
@@ -2004,6 +2155,7 @@ fn load_multi_threaded<'a>(
         ident_ids_by_module,
         arc_shorthands,
         cached_types,
+        incremental,
         render,
         palette,
         num_workers,
@@ -2325,6 +2477,7 @@ fn worker_task<'a>(
                             BuildTask::MakeSpecializations { module_id, .. } => {
                                 format!("BuildTask::MakeSpecializations({module_id:?})")
                             }
+                            BuildTask::Reuse { .. } => "BuildTask::Reuse".to_string(),
                         }
                     );
 
@@ -2599,6 +2752,10 @@ fn update<'a>(
                 extend_module_with_builtin_import(parsed, ModuleId::INSPECT);
             }
 
+            if let Some(incremental) = &mut state.incremental {
+                incremental.record_parse(&parsed, &state.arc_modules.lock());
+            }
+
             state
                 .module_cache
                 .imports
@@ -2650,6 +2807,24 @@ fn update<'a>(
         }) => {
             let module_id = constrained_module.module.module_id;
             log!("generated constraints for {:?}", module_id);
+
+            if let Some(incremental) = &mut state.incremental {
+                if incremental.is_recording(module_id) {
+                    let module = &constrained_module.module;
+
+                    incremental.record_canonicalization(
+                        module_id,
+                        CachedCanonicalization {
+                            problems: canonicalization_problems.clone(),
+                            module_docs: module_docs.clone(),
+                            exposed_imports: module.exposed_imports.clone(),
+                            aliases: module.aliases.clone(),
+                            abilities_store: module.abilities_store.clone(),
+                        },
+                    );
+                }
+            }
+
             state
                 .module_cache
                 .can_problems
@@ -2687,6 +2862,44 @@ fn update<'a>(
 
             Ok(state)
         }
+        ReusedCanonicalization {
+            module_id,
+            canonicalization,
+        } => {
+            log!("reused the canonicalization of {:?}", module_id);
+
+            let CachedCanonicalization {
+                problems,
+                module_docs,
+                exposed_imports,
+                aliases,
+                abilities_store,
+            } = canonicalization;
+
+            state.module_cache.can_problems.insert(module_id, problems);
+
+            if let Some(docs) = module_docs {
+                state.module_cache.documentation.insert(module_id, docs);
+            }
+
+            state
+                .module_cache
+                .exposed_imports
+                .insert(module_id, exposed_imports);
+            state.module_cache.aliases.insert(module_id, aliases);
+            state
+                .module_cache
+                .pending_abilities
+                .insert(module_id, abilities_store);
+
+            let work = state
+                .dependencies
+                .notify(module_id, Phase::CanonicalizeAndConstrain);
+
+            start_tasks(arena, &mut state, work, injector, worker_listeners)?;
+
+            Ok(state)
+        }
         SolvedTypes {
             module_id,
             ident_ids,
@@ -2705,6 +2918,24 @@ fn update<'a>(
             log!("solved types for {:?}", module_id);
             module_timing.end_time = Instant::now();
 
+            if let Some(incremental) = &mut state.incremental {
+                if incremental.is_recording(module_id) {
+                    incremental.record_solve(
+                        module_id,
+                        CachedSolve {
+                            ident_ids: ident_ids.clone(),
+                            solved_module: solved_module.clone(),
+                            solved_subs: solved_subs.clone(),
+                            decls: decls.clone(),
+                            dep_idents: dep_idents.clone(),
+                            abilities_store: abilities_store.clone(),
+                            loc_expects: loc_expects.clone(),
+                            loc_dbgs: loc_dbgs.clone(),
+                        },
+                    );
+                }
+            }
+
             state
                 .module_cache
                 .type_problems
@@ -2903,6 +3134,8 @@ fn update<'a>(
             layout_cache,
             module_timing,
             toplevel_expects,
+            def_regions,
+            stmt_regions,
             expectations,
         } => {
             log!("found specializations for {:?}", module_id);
@@ -2913,6 +3146,9 @@ fn update<'a>(
                 state.toplevel_expects.insert(module_id, toplevel_expects);
             }
 
+            state.def_regions.extend(def_regions);
+            state.stmt_regions.extend(stmt_regions);
+
             state
                 .module_cache
                 .top_level_thunks
@@ -3467,6 +3703,8 @@ fn finish_specialization<'a>(
 
     let State {
         toplevel_expects,
+        def_regions,
+        stmt_regions,
         procedures,
         host_exposed_lambda_sets,
         module_cache,
@@ -3509,6 +3747,8 @@ fn finish_specialization<'a>(
         sources,
         timings: state.timings,
         toplevel_expects,
+        def_regions,
+        stmt_regions,
         glue_layouts: GlueLayouts { getters: vec![] },
         uses_prebuilt_platform,
     })
@@ -3546,6 +3786,10 @@ fn finish(
     //
     #[cfg(debug_assertions)] checkmate: Option<roc_checkmate::Collector>,
 ) -> LoadedModule {
+    if let Some(incremental) = state.incremental.take() {
+        incremental.finish(&state.arc_modules.lock());
+    }
+
     let module_ids = Arc::try_unwrap(state.arc_modules)
         .unwrap_or_else(|_| panic!("There were still outstanding Arc references to module_ids"))
         .into_inner()
@@ -4328,7 +4572,7 @@ fn load_packages<'a>(
                 // TODO we should do this async; however, with the current
                 // architecture of file.rs (which doesn't use async/await),
                 // this would be very difficult!
-                match cache::install_package(roc_cache_dir, src) {
+                match cache::install_package(roc_cache_dir, src, &filename) {
                     Ok((package_dir, opt_root_module)) => {
                         // You can optionally specify the root module using the URL fragment,
                         // e.g. #foo.roc
@@ -5783,6 +6027,9 @@ fn build_pending_specializations<'a>(
 
     let mut module_thunks = bumpalo::collections::Vec::new_in(arena);
     let mut toplevel_expects = ToplevelExpects::default();
+    let mut def_regions = VecMap::default();
+    let mut stmt_regions = StmtRegions::default();
+    stmt_regions.visit_decls(&declarations);
 
     let mut procs_base = ProcsBase {
         partial_procs: BumpMap::default(),
@@ -5830,6 +6077,8 @@ fn build_pending_specializations<'a>(
         let symbol = declarations.symbols[index].value;
         let expr_var = declarations.variables[index];
 
+        def_regions.insert(symbol, declarations.symbols[index].region);
+
         let is_host_exposed = exposed_to_host.top_level_values.contains_key(&symbol);
 
         let body = declarations.expressions[index].clone();
@@ -5969,7 +6218,6 @@ fn build_pending_specializations<'a>(
             Destructure(d_index) => {
                 let loc_pattern = &declarations.destructs[d_index.index()].loc_pattern;
 
-                use roc_can::pattern::Pattern;
                 let symbol = match &loc_pattern.value {
                     Pattern::Identifier(_) => {
                         debug_assert!(false, "identifier ended up in Destructure {symbol:?}");
@@ -6208,10 +6456,26 @@ fn build_pending_specializations<'a>(
         procs_base,
         module_timing,
         toplevel_expects,
+        def_regions,
+        stmt_regions: stmt_regions.0,
         expectations,
     }
 }
 
+/// Collects the region of every definition nested in a top-level one, for debug info.
+#[derive(Default)]
+struct StmtRegions(MutMap<Symbol, Region>);
+
+impl Visitor for StmtRegions {
+    fn visit_def(&mut self, def: &Def) {
+        if let Pattern::Identifier(symbol) = def.loc_pattern.value {
+            self.0.insert(symbol, def.loc_pattern.region);
+        }
+
+        walk_def(self, def);
+    }
+}
+
 fn register_toplevel_function_into_procs_base<'a>(
     mono_env: &mut roc_mono::ir::Env<'a, '_>,
     procs_base: &mut ProcsBase<'a>,
@@ -6360,6 +6624,7 @@ fn run_task<'a>(
     use BuildTask::*;
 
     let msg = match task {
+        Reuse { msg } => Ok(msg),
         LoadModule {
             module_name,
             module_ids,
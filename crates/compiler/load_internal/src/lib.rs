@@ -4,6 +4,7 @@
 #![allow(clippy::large_enum_variant)]
 
 use roc_module::symbol::ModuleId;
+pub mod analysis_cache;
 pub mod docs;
 pub mod file;
 pub mod module;
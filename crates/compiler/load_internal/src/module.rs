@@ -181,6 +181,11 @@ pub struct MonomorphizedModule<'a> {
     pub procedures: MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
     pub host_exposed_lambda_sets: HostExposedLambdaSets<'a>,
     pub toplevel_expects: MutMap<ModuleId, ToplevelExpects>,
+    /// The region of the name of every top-level definition, for debug info.
+    /// Backends get these and `stmt_regions` through [roc_mono::ir::SourceInfo].
+    pub def_regions: MutMap<Symbol, Region>,
+    /// The region of every definition nested in a top-level one, for debug info.
+    pub stmt_regions: MutMap<Symbol, Region>,
     pub entry_point: EntryPoint<'a>,
     pub exposed_to_host: ExposedToHost,
     pub sources: MutMap<ModuleId, (PathBuf, Box<str>)>,
@@ -17,6 +17,7 @@ mod helpers;
 use crate::helpers::fixtures_dir;
 use bumpalo::Bump;
 use roc_can::module::ExposedByModule;
+use roc_load_internal::analysis_cache::AnalysisCache;
 use roc_load_internal::docs::DocDef;
 use roc_load_internal::file::{
     ExecutionMode, LoadConfig, LoadResult, LoadStart, LoadingProblem, Threading,
@@ -36,7 +37,7 @@ use roc_test_utils_dir::TmpDir;
 use roc_types::pretty_print::name_and_print_var;
 use roc_types::pretty_print::DebugPrint;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 fn load_and_typecheck(
     arena: &Bump,
@@ -77,6 +78,48 @@ fn load_and_typecheck(
     }
 }
 
+fn load_and_typecheck_with_cache<'a>(
+    arena: &'a Bump,
+    dir: &Path,
+    filename: &str,
+    src: &'a str,
+    analysis_cache: &'a mut AnalysisCache,
+) -> LoadedModule {
+    use LoadResult::*;
+
+    let load_start = LoadStart::from_str_with_cache(
+        arena,
+        dir.join(filename),
+        None,
+        src,
+        RocCacheDir::Disallowed,
+        dir.to_path_buf(),
+        analysis_cache,
+    )
+    .unwrap();
+    let load_config = LoadConfig {
+        target: TARGET,
+        function_kind: FunctionKind::LambdaSet,
+        render: RenderTarget::Generic,
+        palette: DEFAULT_PALETTE,
+        threading: Threading::Single,
+        exec_mode: ExecutionMode::Check,
+    };
+
+    match roc_load_internal::file::load(
+        arena,
+        load_start,
+        Default::default(),
+        Default::default(),
+        RocCacheDir::Disallowed,
+        load_config,
+    ) {
+        Ok(Monomorphized(_)) => unreachable!(""),
+        Ok(TypeChecked(module)) => module,
+        Err(problem) => panic!("{problem:?}"),
+    }
+}
+
 const TARGET: Target = Target::LinuxX64;
 
 // HELPERS
@@ -1810,3 +1853,146 @@ fn roc_file_no_extension() {
 
     assert_eq!(err, expected, "\n{}", err);
 }
+
+const CACHED_DEP: &str = indoc!(
+    r"
+    module [double]
+
+    double : I64 -> I64
+    double = \n -> n * 2
+    "
+);
+
+const CACHED_OTHER: &str = indoc!(
+    r"
+    module [quadruple]
+
+    import Dep
+
+    quadruple = \n -> Dep.double (Dep.double n)
+    "
+);
+
+const CACHED_MAIN: &str = indoc!(
+    r"
+    module [octuple]
+
+    import Dep
+    import Other
+
+    octuple = \n -> Dep.double (Other.quadruple n)
+    "
+);
+
+fn cached_module_ids(loaded_module: &LoadedModule) -> (ModuleId, ModuleId) {
+    let module_ids = &loaded_module.interns.module_ids;
+
+    (
+        module_ids.get_id(&"Dep".into()).unwrap(),
+        module_ids.get_id(&"Other".into()).unwrap(),
+    )
+}
+
+#[test]
+fn analysis_cache_reuses_unchanged_modules() {
+    let dir = TmpDir::new("tmp/analysis_cache_reuses_unchanged_modules");
+    std::fs::write(dir.path().join("Dep.roc"), CACHED_DEP).unwrap();
+    std::fs::write(dir.path().join("Other.roc"), CACHED_OTHER).unwrap();
+    std::fs::write(dir.path().join("Main.roc"), CACHED_MAIN).unwrap();
+
+    let mut cache = AnalysisCache::default();
+
+    let loaded_module = load_and_typecheck_with_cache(
+        &Bump::new(),
+        dir.path(),
+        "Main.roc",
+        CACHED_MAIN,
+        &mut cache,
+    );
+    assert!(cache.last_reused().is_empty());
+    expect_types(loaded_module, hashmap! { "octuple" => "I64 -> I64" });
+
+    // Only the root changed, so neither of its imports has to be analysed again.
+    let edited_main = indoc!(
+        r#"
+        module [octuple, name]
+
+        import Dep
+        import Other
+
+        octuple = \n -> Dep.double (Other.quadruple n)
+
+        name = "Main"
+        "#
+    );
+    let loaded_module = load_and_typecheck_with_cache(
+        &Bump::new(),
+        dir.path(),
+        "Main.roc",
+        edited_main,
+        &mut cache,
+    );
+    let (dep_id, other_id) = cached_module_ids(&loaded_module);
+    assert_eq!(
+        cache.last_reused(),
+        &[dep_id, other_id].into_iter().collect()
+    );
+    expect_types(
+        loaded_module,
+        hashmap! {
+            "octuple" => "I64 -> I64",
+            "name" => "Str",
+        },
+    );
+}
+
+#[test]
+fn analysis_cache_reanalyses_changed_modules_and_their_dependents() {
+    let dir = TmpDir::new("tmp/analysis_cache_reanalyses_changed_modules_and_their_dependents");
+    std::fs::write(dir.path().join("Dep.roc"), CACHED_DEP).unwrap();
+    std::fs::write(dir.path().join("Other.roc"), CACHED_OTHER).unwrap();
+    std::fs::write(dir.path().join("Main.roc"), CACHED_MAIN).unwrap();
+
+    let mut cache = AnalysisCache::default();
+
+    load_and_typecheck_with_cache(
+        &Bump::new(),
+        dir.path(),
+        "Main.roc",
+        CACHED_MAIN,
+        &mut cache,
+    );
+
+    // Dep doesn't import Other, so it is still reused.
+    std::fs::write(
+        dir.path().join("Other.roc"),
+        CACHED_OTHER.replace("Dep.double (Dep.double n)", "Dep.double n"),
+    )
+    .unwrap();
+    let loaded_module = load_and_typecheck_with_cache(
+        &Bump::new(),
+        dir.path(),
+        "Main.roc",
+        CACHED_MAIN,
+        &mut cache,
+    );
+    let (dep_id, _) = cached_module_ids(&loaded_module);
+    assert_eq!(cache.last_reused(), &[dep_id].into_iter().collect());
+    expect_types(loaded_module, hashmap! { "octuple" => "I64 -> I64" });
+
+    // Other imports Dep, so it has to be analysed again along with it.
+    std::fs::write(
+        dir.path().join("Dep.roc"),
+        CACHED_DEP.replace("I64 -> I64", "U8 -> U8"),
+    )
+    .unwrap();
+    let loaded_module = load_and_typecheck_with_cache(
+        &Bump::new(),
+        dir.path(),
+        "Main.roc",
+        CACHED_MAIN,
+        &mut cache,
+    );
+    assert!(cache.last_reused().is_empty());
+    expect_types(loaded_module, hashmap! { "octuple" => "U8 -> U8" });
+}
@@ -0,0 +1,629 @@
+use bumpalo::collections::vec::Vec;
+use roc_builtins::bitcode;
+use roc_error_macros::todo_lambda_erasure;
+use roc_module::ident::ForeignSymbol;
+use roc_module::low_level::LowLevel;
+use roc_module::symbol::{IdentIds, Symbol};
+
+use crate::ir::{
+    BranchInfo, Call, CallType, Expr, JoinPointId, Literal, Param, Stmt, UpdateModeId,
+};
+use crate::layout::{
+    Discriminant, InLayout, Layout, LayoutInterner, LayoutRepr, STLayoutInterner, TagIdIntType,
+    UnionLayout,
+};
+
+use super::{CodeGenHelp, Context, LAYOUT_UNIT};
+
+const ARG_1: Symbol = Symbol::ARG_1;
+const ARG_2: Symbol = Symbol::ARG_2;
+const ARG_3: Symbol = Symbol::ARG_3;
+const ARG_4: Symbol = Symbol::ARG_4;
+
+/// The bindings that clone a value, in order. They are nested into `Let`s by `let_all`.
+type Lets<'a> = Vec<'a, (Symbol, Expr<'a>, InLayout<'a>)>;
+
+/// Clone a value (ARG_1) into a buffer (ARG_2) at an offset (ARG_3), in the same format as
+/// the LLVM backend's `clone_to_shared_memory`. Data behind pointers is written from the
+/// extra offset (ARG_4) on, and pointers are replaced by offsets into the buffer.
+/// Returns the extra offset after all of the written data.
+pub fn clone_generic<'a>(
+    root: &mut CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    ctx: &mut Context<'a>,
+    layout_interner: &mut STLayoutInterner<'a>,
+    layout: InLayout<'a>,
+) -> Stmt<'a> {
+    use crate::layout::Builtin::*;
+    use LayoutRepr::*;
+
+    if layout_interner.safe_to_memcpy(layout) {
+        let mut lets = Vec::new_in(root.arena);
+        store(
+            root,
+            ident_ids,
+            layout_interner,
+            &mut lets,
+            ARG_3,
+            ARG_1,
+            layout,
+        );
+
+        return let_all(root, lets, Stmt::Ret(ARG_4));
+    }
+
+    match layout_interner.get_repr(layout) {
+        Builtin(Int(_) | Float(_) | Bool | Decimal) => {
+            unreachable!("{:?} is safe to memcpy", layout)
+        }
+        Builtin(Str) => {
+            let extra_offset = root.create_symbol(ident_ids, "extra_offset");
+            let clone_expr = foreign_call(
+                root,
+                bitcode::STR_CLONE_TO,
+                root.layout_isize,
+                &[ARG_1, ARG_2, ARG_3, ARG_4],
+            );
+
+            Stmt::Let(
+                extra_offset,
+                clone_expr,
+                root.layout_isize,
+                root.arena.alloc(Stmt::Ret(extra_offset)),
+            )
+        }
+        Builtin(List(elem_layout)) => {
+            clone_list(root, ident_ids, ctx, layout_interner, elem_layout)
+        }
+        Struct(field_layouts) => {
+            let mut lets = Vec::new_in(root.arena);
+            let extra_offset = clone_fields(
+                root,
+                ident_ids,
+                ctx,
+                layout_interner,
+                &mut lets,
+                field_layouts,
+                |index| Expr::StructAtIndex {
+                    index,
+                    field_layouts,
+                    structure: ARG_1,
+                },
+                ARG_3,
+                ARG_4,
+            );
+
+            let_all(root, lets, Stmt::Ret(extra_offset))
+        }
+        Union(union_layout) => clone_tag_union(root, ident_ids, ctx, layout_interner, union_layout),
+        // Functions are never rendered, so we write nothing for them
+        LambdaSet(_) => Stmt::Ret(ARG_4),
+        Ptr(_) => unreachable!("for internal use only"),
+        RecursivePointer(_) => {
+            unreachable!(
+                "Can't clone a RecursivePointer. Should have been replaced by a tag union."
+            )
+        }
+        FunctionPointer(_) | Erased(_) => todo_lambda_erasure!(),
+    }
+}
+
+fn let_all<'a>(root: &CodeGenHelp<'a>, lets: Lets<'a>, following: Stmt<'a>) -> Stmt<'a> {
+    lets.into_iter()
+        .rev()
+        .fold(following, |next, (symbol, expr, layout)| {
+            Stmt::Let(symbol, expr, layout, root.arena.alloc(next))
+        })
+}
+
+fn lowlevel<'a>(root: &CodeGenHelp<'a>, op: LowLevel, arguments: &[Symbol]) -> Expr<'a> {
+    Expr::Call(Call {
+        call_type: CallType::LowLevel {
+            op,
+            update_mode: UpdateModeId::BACKEND_DUMMY,
+        },
+        arguments: root.arena.alloc_slice_copy(arguments),
+    })
+}
+
+fn foreign_call<'a>(
+    root: &CodeGenHelp<'a>,
+    name: &str,
+    ret_layout: InLayout<'a>,
+    arguments: &[Symbol],
+) -> Expr<'a> {
+    Expr::Call(Call {
+        call_type: CallType::Foreign {
+            foreign_symbol: ForeignSymbol::from(name.to_string()),
+            ret_layout,
+        },
+        arguments: root.arena.alloc_slice_copy(arguments),
+    })
+}
+
+fn int_literal<'a>(value: u64) -> Expr<'a> {
+    Expr::Literal(Literal::Int((value as i128).to_ne_bytes()))
+}
+
+fn add_offset<'a>(
+    root: &CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    lets: &mut Lets<'a>,
+    offset: Symbol,
+    amount: u32,
+) -> Symbol {
+    if amount == 0 {
+        return offset;
+    }
+
+    let amount_sym = root.create_symbol(ident_ids, "amount");
+    lets.push((amount_sym, int_literal(amount as u64), root.layout_isize));
+
+    let new_offset = root.create_symbol(ident_ids, "offset");
+    let add_expr = lowlevel(root, LowLevel::NumAdd, &[offset, amount_sym]);
+    lets.push((new_offset, add_expr, root.layout_isize));
+
+    new_offset
+}
+
+/// Write `value` into the buffer at `offset`, as is
+fn store<'a>(
+    root: &CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    layout_interner: &mut STLayoutInterner<'a>,
+    lets: &mut Lets<'a>,
+    offset: Symbol,
+    value: Symbol,
+    layout: InLayout<'a>,
+) {
+    let ptr_layout = layout_interner.insert_direct_no_semantic(LayoutRepr::Ptr(layout));
+
+    let addr = root.create_symbol(ident_ids, "addr");
+    let addr_expr = lowlevel(root, LowLevel::NumAdd, &[ARG_2, offset]);
+    lets.push((addr, addr_expr, root.layout_isize));
+
+    let ptr = root.create_symbol(ident_ids, "ptr");
+    lets.push((ptr, lowlevel(root, LowLevel::PtrCast, &[addr]), ptr_layout));
+
+    let stored = root.create_symbol(ident_ids, "stored");
+    let store_expr = Expr::ptr_store(root.arena.alloc([ptr, value]));
+    lets.push((stored, store_expr, LAYOUT_UNIT));
+}
+
+/// Clone `value` to `offset`, inline if it can be copied as is, or with a helper proc.
+/// Returns the extra offset after any data behind its pointers.
+#[allow(clippy::too_many_arguments)]
+fn clone_value<'a>(
+    root: &mut CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    ctx: &mut Context<'a>,
+    layout_interner: &mut STLayoutInterner<'a>,
+    lets: &mut Lets<'a>,
+    value: Symbol,
+    layout: InLayout<'a>,
+    offset: Symbol,
+    extra_offset: Symbol,
+) -> Symbol {
+    if layout_interner.safe_to_memcpy(layout) {
+        store(
+            root,
+            ident_ids,
+            layout_interner,
+            lets,
+            offset,
+            value,
+            layout,
+        );
+
+        return extra_offset;
+    }
+
+    let arguments = root.arena.alloc([value, ARG_2, offset, extra_offset]);
+    let clone_expr = root
+        .call_specialized_op(ident_ids, ctx, layout_interner, layout, arguments)
+        .unwrap();
+
+    let new_extra_offset = root.create_symbol(ident_ids, "extra_offset");
+    lets.push((new_extra_offset, clone_expr, root.layout_isize));
+
+    new_extra_offset
+}
+
+/// Clone the fields of a struct or tag payload, one after the other from `offset` on
+#[allow(clippy::too_many_arguments)]
+fn clone_fields<'a>(
+    root: &mut CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    ctx: &mut Context<'a>,
+    layout_interner: &mut STLayoutInterner<'a>,
+    lets: &mut Lets<'a>,
+    field_layouts: &'a [InLayout<'a>],
+    field_expr: impl Fn(u64) -> Expr<'a>,
+    offset: Symbol,
+    extra_offset: Symbol,
+) -> Symbol {
+    let mut extra_offset = extra_offset;
+    let mut field_offset = 0;
+
+    for (i, field_layout) in field_layouts.iter().enumerate() {
+        let field = root.create_symbol(ident_ids, &format!("field_{i}"));
+        lets.push((field, field_expr(i as u64), *field_layout));
+
+        let offset = add_offset(root, ident_ids, lets, offset, field_offset);
+        extra_offset = clone_value(
+            root,
+            ident_ids,
+            ctx,
+            layout_interner,
+            lets,
+            field,
+            *field_layout,
+            offset,
+            extra_offset,
+        );
+
+        field_offset += layout_interner.stack_size(*field_layout);
+    }
+
+    extra_offset
+}
+
+/// Lists are written as `{ extra_offset, len, len }`, followed by their elements at the extra
+/// offset. Any data behind the pointers of the elements goes after all of the elements.
+fn clone_list<'a>(
+    root: &mut CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    ctx: &mut Context<'a>,
+    layout_interner: &mut STLayoutInterner<'a>,
+    elem_layout: InLayout<'a>,
+) -> Stmt<'a> {
+    use LowLevel::*;
+    let layout_isize = root.layout_isize;
+    let arena = root.arena;
+
+    let mut lets = Vec::new_in(arena);
+
+    // Write the list and a copy of its elements. This is all there is to it for elements
+    // without pointers, other elements are cloned over their copies below.
+    let size = root.create_symbol(ident_ids, "size");
+    let elem_size = layout_interner.stack_size(elem_layout) as u64;
+    lets.push((size, int_literal(elem_size), layout_isize));
+
+    let rest_start = root.create_symbol(ident_ids, "rest_start");
+    let clone_expr = foreign_call(
+        root,
+        bitcode::LIST_CLONE_TO,
+        layout_isize,
+        &[ARG_1, size, ARG_2, ARG_3, ARG_4],
+    );
+    lets.push((rest_start, clone_expr, layout_isize));
+
+    if layout_interner.safe_to_memcpy(elem_layout) {
+        return let_all(root, lets, Stmt::Ret(rest_start));
+    }
+
+    // A pointer layout (heap pointer to a single list element)
+    let ptr_layout = layout_interner.insert_direct_no_semantic(LayoutRepr::Ptr(elem_layout));
+
+    let len = root.create_symbol(ident_ids, "len");
+    lets.push((len, lowlevel(root, ListLenUsize, &[ARG_1]), layout_isize));
+
+    let elements = root.create_symbol(ident_ids, "elements");
+    let elements_expr = Expr::StructAtIndex {
+        index: 0,
+        field_layouts: arena.alloc([ptr_layout, layout_isize]),
+        structure: ARG_1,
+    };
+    lets.push((elements, elements_expr, ptr_layout));
+
+    let start = root.create_symbol(ident_ids, "start");
+    lets.push((start, lowlevel(root, PtrCast, &[elements]), layout_isize));
+
+    let list_size = root.create_symbol(ident_ids, "list_size");
+    let list_size_expr = lowlevel(root, NumMul, &[len, size]);
+    lets.push((list_size, list_size_expr, layout_isize));
+
+    let end = root.create_symbol(ident_ids, "end");
+    lets.push((
+        end,
+        lowlevel(root, NumAdd, &[start, list_size]),
+        layout_isize,
+    ));
+
+    //
+    // Loop over the elements, with the address of the element, the offset of its copy,
+    // and the extra offset for the data behind its pointers
+    //
+
+    let elems_loop = JoinPointId(root.create_symbol(ident_ids, "elems_loop"));
+    let addr = root.create_symbol(ident_ids, "addr");
+    let offset = root.create_symbol(ident_ids, "offset");
+    let extra_offset = root.create_symbol(ident_ids, "extra_offset");
+
+    let mut loop_lets = Vec::new_in(arena);
+
+    let ptr = root.create_symbol(ident_ids, "ptr");
+    loop_lets.push((ptr, lowlevel(root, PtrCast, &[addr]), ptr_layout));
+
+    let elem = root.create_symbol(ident_ids, "elem");
+    loop_lets.push((elem, Expr::ptr_load(arena.alloc(ptr)), elem_layout));
+
+    let new_extra_offset = clone_value(
+        root,
+        ident_ids,
+        ctx,
+        layout_interner,
+        &mut loop_lets,
+        elem,
+        elem_layout,
+        offset,
+        extra_offset,
+    );
+
+    let next_addr = root.create_symbol(ident_ids, "next_addr");
+    loop_lets.push((
+        next_addr,
+        lowlevel(root, NumAdd, &[addr, size]),
+        layout_isize,
+    ));
+
+    let next_offset = root.create_symbol(ident_ids, "next_offset");
+    loop_lets.push((
+        next_offset,
+        lowlevel(root, NumAdd, &[offset, size]),
+        layout_isize,
+    ));
+
+    let jump_back = Stmt::Jump(
+        elems_loop,
+        arena.alloc([next_addr, next_offset, new_extra_offset]),
+    );
+
+    let is_end = root.create_symbol(ident_ids, "is_end");
+    let if_end_of_list = Stmt::if_then_else(
+        arena,
+        is_end,
+        layout_isize,
+        Stmt::Ret(extra_offset),
+        arena.alloc(let_all(root, loop_lets, jump_back)),
+    );
+
+    let loop_body = Stmt::Let(
+        is_end,
+        lowlevel(root, NumGte, &[addr, end]),
+        Layout::BOOL,
+        arena.alloc(if_end_of_list),
+    );
+
+    let params = [addr, offset, extra_offset].map(|symbol| Param {
+        symbol,
+        layout: layout_isize,
+    });
+
+    let joinpoint_loop = Stmt::Join {
+        id: elems_loop,
+        parameters: arena.alloc(params),
+        body: arena.alloc(loop_body),
+        remainder: arena.alloc(Stmt::Jump(
+            elems_loop,
+            arena.alloc([start, ARG_4, rest_start]),
+        )),
+    };
+
+    let_all(root, lets, joinpoint_loop)
+}
+
+fn clone_tag_union<'a>(
+    root: &mut CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    ctx: &mut Context<'a>,
+    layout_interner: &mut STLayoutInterner<'a>,
+    union_layout: UnionLayout<'a>,
+) -> Stmt<'a> {
+    use UnionLayout::*;
+
+    let parent_rec_ptr_layout = ctx.recursive_union;
+    if !matches!(union_layout, NonRecursive(_)) {
+        ctx.recursive_union = Some(union_layout);
+    }
+
+    let mut tags = Vec::new_in(root.arena);
+    let nullable_id = match union_layout {
+        NonRecursive(tag_layouts) | Recursive(tag_layouts) => {
+            tags.extend(
+                tag_layouts
+                    .iter()
+                    .enumerate()
+                    .map(|(i, t)| (i as TagIdIntType, *t)),
+            );
+            None
+        }
+        NonNullableUnwrapped(field_layouts) => {
+            tags.push((0, field_layouts));
+            None
+        }
+        NullableWrapped {
+            nullable_id,
+            other_tags,
+        } => {
+            let tag_ids = (0..).filter(|tag_id| *tag_id != nullable_id);
+            tags.extend(tag_ids.zip(other_tags.iter().copied()));
+            Some(nullable_id)
+        }
+        NullableUnwrapped {
+            nullable_id,
+            other_fields,
+        } => {
+            tags.push(((!nullable_id) as TagIdIntType, other_fields));
+            Some(nullable_id as TagIdIntType)
+        }
+    };
+
+    let mut branches = Vec::with_capacity_in(tags.len() + 1, root.arena);
+
+    if let Some(tag_id) = nullable_id {
+        // the null pointer is written as is
+        let mut lets = Vec::new_in(root.arena);
+        let null = root.create_symbol(ident_ids, "null");
+        lets.push((null, int_literal(0), root.layout_isize));
+        store(
+            root,
+            ident_ids,
+            layout_interner,
+            &mut lets,
+            ARG_3,
+            null,
+            root.layout_isize,
+        );
+
+        let stmt = let_all(root, lets, Stmt::Ret(ARG_4));
+        branches.push((tag_id as u64, BranchInfo::None, stmt));
+    }
+
+    for (tag_id, field_layouts) in tags {
+        let stmt = clone_tag(
+            root,
+            ident_ids,
+            ctx,
+            layout_interner,
+            union_layout,
+            tag_id,
+            field_layouts,
+        );
+        branches.push((tag_id as u64, BranchInfo::None, stmt));
+    }
+
+    ctx.recursive_union = parent_rec_ptr_layout;
+
+    let (_, _, default_stmt) = branches.pop().unwrap();
+    if branches.is_empty() {
+        return default_stmt;
+    }
+
+    let tag_id_layout = union_layout.tag_id_layout();
+    let tag_id = root.create_symbol(ident_ids, "tag_id");
+
+    let switch_stmt = Stmt::Switch {
+        cond_symbol: tag_id,
+        cond_layout: tag_id_layout,
+        branches: branches.into_bump_slice(),
+        default_branch: (BranchInfo::None, root.arena.alloc(default_stmt)),
+        ret_layout: root.layout_isize,
+    };
+
+    Stmt::Let(
+        tag_id,
+        Expr::GetTagId {
+            structure: ARG_1,
+            union_layout,
+        },
+        tag_id_layout,
+        root.arena.alloc(switch_stmt),
+    )
+}
+
+fn clone_tag<'a>(
+    root: &mut CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    ctx: &mut Context<'a>,
+    layout_interner: &mut STLayoutInterner<'a>,
+    union_layout: UnionLayout<'a>,
+    tag_id: TagIdIntType,
+    field_layouts: &'a [InLayout<'a>],
+) -> Stmt<'a> {
+    let mut lets = Vec::new_in(root.arena);
+
+    let field_expr = |index| Expr::UnionAtIndex {
+        structure: ARG_1,
+        tag_id,
+        union_layout,
+        index,
+    };
+
+    // The data is written in place for non-recursive unions. Otherwise, it goes to the extra
+    // offset, and the offset takes the place of the pointer.
+    let (data_offset, extra_offset) = if let UnionLayout::NonRecursive(_) = union_layout {
+        (ARG_3, ARG_4)
+    } else {
+        if union_layout.stores_tag_id_in_pointer(root.target) {
+            // offsets into the buffer have no spare bits, so the tag id goes next to them
+            let id = root.create_symbol(ident_ids, "id");
+            lets.push((id, int_literal(tag_id as u64), Layout::U32));
+            store(
+                root,
+                ident_ids,
+                layout_interner,
+                &mut lets,
+                ARG_3,
+                id,
+                Layout::U32,
+            );
+
+            let data_offset = root.create_symbol(ident_ids, "data_offset");
+            let cast_expr = lowlevel(root, LowLevel::NumIntCast, &[ARG_4]);
+            lets.push((data_offset, cast_expr, Layout::U32));
+
+            let offset = add_offset(root, ident_ids, &mut lets, ARG_3, 4);
+            store(
+                root,
+                ident_ids,
+                layout_interner,
+                &mut lets,
+                offset,
+                data_offset,
+                Layout::U32,
+            );
+        } else {
+            store(
+                root,
+                ident_ids,
+                layout_interner,
+                &mut lets,
+                ARG_3,
+                ARG_4,
+                root.layout_isize,
+            );
+        }
+
+        let (data_size, _) = union_layout.data_size_and_alignment(layout_interner);
+        let extra_offset = add_offset(root, ident_ids, &mut lets, ARG_4, data_size);
+
+        (ARG_4, extra_offset)
+    };
+
+    let extra_offset = clone_fields(
+        root,
+        ident_ids,
+        ctx,
+        layout_interner,
+        &mut lets,
+        field_layouts,
+        field_expr,
+        data_offset,
+        extra_offset,
+    );
+
+    let stores_tag_id = union_layout.stores_tag_id_as_data(root.target)
+        && !matches!(union_layout.discriminant(), Discriminant::U0);
+
+    if stores_tag_id {
+        let tag_id_layout = union_layout.tag_id_layout();
+        let tag_id_offset = union_layout.tag_id_offset(layout_interner).unwrap();
+
+        let id = root.create_symbol(ident_ids, "id");
+        lets.push((id, int_literal(tag_id as u64), tag_id_layout));
+
+        let offset = add_offset(root, ident_ids, &mut lets, data_offset, tag_id_offset);
+        store(
+            root,
+            ident_ids,
+            layout_interner,
+            &mut lets,
+            offset,
+            id,
+            tag_id_layout,
+        );
+    }
+
+    let_all(root, lets, Stmt::Ret(extra_offset))
+}
@@ -14,6 +14,7 @@ use crate::layout::{
     STLayoutInterner, UnionLayout,
 };
 
+mod clone;
 mod equality;
 mod refcount;
 
@@ -41,6 +42,7 @@ pub enum HelperOp {
     Reset,
     ResetRef,
     Eq,
+    CloneTo,
 }
 
 impl HelperOp {
@@ -76,7 +78,7 @@ pub struct Context<'a> {
 /// ----------------------------------------------
 ///
 /// Some low level operations need specialized helper procs to traverse data structures at runtime.
-/// This includes refcounting, hashing, and equality checks.
+/// This includes refcounting, hashing, equality checks, and cloning the lookups of failed `expect`s.
 ///
 /// For example, when checking List equality, we need to visit each element and compare them.
 /// Depending on the type of the list elements, we may need to recurse deeper into each element.
@@ -314,6 +316,10 @@ impl<'a> CodeGenHelp<'a> {
                     IndirectDec => (LAYOUT_UNIT, arena.alloc([ptr_arg])),
                     IndirectInc => (LAYOUT_UNIT, arena.alloc([ptr_arg, self.layout_isize])),
                     Eq => (LAYOUT_BOOL, self.arena.alloc([arg, arg])),
+                    CloneTo => {
+                        let offset = self.layout_isize;
+                        (offset, arena.alloc([arg, offset, offset, offset]))
+                    }
                 }
             };
 
@@ -423,6 +429,10 @@ impl<'a> CodeGenHelp<'a> {
                 LAYOUT_BOOL,
                 equality::eq_generic(self, ident_ids, ctx, layout_interner, layout),
             ),
+            CloneTo => (
+                self.layout_isize,
+                clone::clone_generic(self, ident_ids, ctx, layout_interner, layout),
+            ),
         };
 
         let args: &'a [(InLayout<'a>, Symbol)] = {
@@ -445,6 +455,11 @@ impl<'a> CodeGenHelp<'a> {
                     self.arena.alloc([(ptr_layout, ARG_1)])
                 }
                 Eq => self.arena.alloc([roc_value, (layout, ARG_2)]),
+                CloneTo => {
+                    let offset = self.layout_isize;
+                    self.arena
+                        .alloc([roc_value, (offset, ARG_2), (offset, ARG_3), (offset, ARG_4)])
+                }
             }
         };
 
@@ -517,6 +532,15 @@ impl<'a> CodeGenHelp<'a> {
                 result: LAYOUT_BOOL,
                 niche: Niche::NONE,
             },
+            HelperOp::CloneTo => {
+                let offset = self.layout_isize;
+
+                ProcLayout {
+                    arguments: self.arena.alloc([layout, offset, offset, offset]),
+                    result: offset,
+                    niche: Niche::NONE,
+                }
+            }
         };
 
         (proc_symbol, proc_layout)
@@ -1021,7 +1045,11 @@ fn layout_needs_helper_proc<'a>(
             // Str type can use either Zig functions or generated IR, since it's not generic.
             // Eq uses a Zig function, refcount uses generated IR.
             // Both are fine, they were just developed at different times.
-            matches!(op, HelperOp::Inc | HelperOp::Dec | HelperOp::DecRef(_))
+            // CloneTo wraps a Zig function in a helper proc, so the caller needs no special case.
+            matches!(
+                op,
+                HelperOp::Inc | HelperOp::Dec | HelperOp::DecRef(_) | HelperOp::CloneTo
+            )
         }
         LayoutRepr::Builtin(Builtin::List(_)) => true,
         LayoutRepr::Struct { .. } => true, // note: we do generate a helper for Unit, with just a Stmt::Ret
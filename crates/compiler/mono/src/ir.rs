@@ -37,6 +37,7 @@ use roc_types::subs::{
     StorageSubs, Subs, Variable, VariableSubsSlice,
 };
 use std::collections::HashMap;
+use std::path::PathBuf;
 use ven_pretty::{text, BoxAllocator, DocAllocator, DocBuilder};
 
 use pattern::{from_can_pattern, store_pattern, Pattern};
@@ -310,6 +311,27 @@ pub struct Proc<'a> {
     pub is_erased: bool,
 }
 
+/// Where to find the source of the procedures being built, for backends that emit debug info.
+#[derive(Debug, Clone, Copy)]
+pub struct SourceInfo<'a> {
+    /// The path and source of every module.
+    pub sources: &'a MutMap<ModuleId, (PathBuf, Box<str>)>,
+    /// The region of the name of every top-level definition.
+    pub def_regions: &'a MutMap<Symbol, Region>,
+    /// The region of every definition nested in a top-level one. A `Stmt::Let` binding one of
+    /// these symbols is where the code for that definition starts.
+    pub stmt_regions: &'a MutMap<Symbol, Region>,
+}
+
+impl SourceInfo<'_> {
+    /// The module and region of the definition bound by a `Stmt::Let`, if it came from source.
+    pub fn let_region(&self, symbol: Symbol) -> Option<(ModuleId, Region)> {
+        let region = self.stmt_regions.get(&symbol)?;
+
+        Some((symbol.module_id(), *region))
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct HostExposedLambdaSet<'a> {
     pub id: LambdaSetId,
@@ -298,3 +298,76 @@ impl std::fmt::Display for Target {
         write!(f, "{}", Into::<&'static str>::into(self))
     }
 }
+
+/// Post-MVP WebAssembly proposals that the Wasm backend may use.
+/// All of them are off by default, so that the output runs on any Wasm engine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WasmFeatures {
+    /// `memory.copy` and `memory.fill`
+    pub bulk_memory: bool,
+    /// Functions with more than one return value
+    pub multi_value: bool,
+    /// `return_call` and `return_call_indirect`
+    pub tail_call: bool,
+}
+
+impl WasmFeatures {
+    pub const MVP: Self = WasmFeatures {
+        bulk_memory: false,
+        multi_value: false,
+        tail_call: false,
+    };
+
+    pub const ALL: Self = WasmFeatures {
+        bulk_memory: true,
+        multi_value: true,
+        tail_call: true,
+    };
+
+    pub const NAMES: [&'static str; 3] = ["bulk-memory", "multi-value", "tail-call"];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownWasmFeature(pub String);
+
+impl std::fmt::Display for UnknownWasmFeature {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Unknown Wasm feature `{}`. Expected one of: {}",
+            self.0,
+            WasmFeatures::NAMES.join(", ")
+        )
+    }
+}
+
+/// Parse a comma-separated list of feature names, like `bulk-memory,tail-call`
+impl FromStr for WasmFeatures {
+    type Err = UnknownWasmFeature;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut features = WasmFeatures::MVP;
+        for name in s.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            match name {
+                "bulk-memory" => features.bulk_memory = true,
+                "multi-value" => features.multi_value = true,
+                "tail-call" => features.tail_call = true,
+                "all" => features = WasmFeatures::ALL,
+                _ => return Err(UnknownWasmFeature(name.to_string())),
+            }
+        }
+        Ok(features)
+    }
+}
+
+impl std::fmt::Display for WasmFeatures {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let enabled = [self.bulk_memory, self.multi_value, self.tail_call];
+        let names: Vec<&str> = WasmFeatures::NAMES
+            .iter()
+            .zip(enabled)
+            .filter_map(|(name, is_enabled)| is_enabled.then_some(*name))
+            .collect();
+        write!(f, "{}", names.join(","))
+    }
+}
@@ -33,7 +33,7 @@ impl<T> Solved<T> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SolvedModule {
     pub problems: Vec<TypeError>,
 
@@ -0,0 +1,288 @@
+#[cfg(feature = "gen-dev")]
+use crate::helpers::dev::assert_expect_failures;
+
+#[allow(unused_imports)]
+use indoc::indoc;
+
+#[test]
+#[cfg(feature = "gen-dev")]
+fn expect_passes() {
+    assert_expect_failures(
+        indoc!(
+            r#"
+            app "test" provides [main] to "./platform"
+
+            main = 0
+
+            expect 1 == 1
+            "#
+        ),
+        "",
+    );
+}
+
+#[test]
+#[cfg(feature = "gen-dev")]
+fn expect_fails() {
+    assert_expect_failures(
+        indoc!(
+            r#"
+            app "test" provides [main] to "./platform"
+
+            main = 0
+
+            expect 1 == 2
+            "#
+        ),
+        indoc!(
+            r"
+            This expectation failed:
+
+            5│  expect 1 == 2
+                ^^^^^^^^^^^^^
+            "
+        ),
+    );
+}
+
+#[test]
+#[cfg(feature = "gen-dev")]
+fn expect_crashes() {
+    assert_expect_failures(
+        indoc!(
+            r#"
+            app "test" provides [main] to "./platform"
+
+            main = 0
+
+            expect
+                crash "hello crash"
+            "#
+        ),
+        indoc!(
+            r#"
+            This expectation crashed while running:
+
+            5│>  expect
+            6│>      crash "hello crash"
+
+            The crash reported this message:
+
+            hello crash
+            "#
+        ),
+    );
+}
+
+#[test]
+#[cfg(feature = "gen-dev")]
+fn lookup_integers() {
+    assert_expect_failures(
+        indoc!(
+            r#"
+            app "test" provides [main] to "./platform"
+
+            main = 0
+
+            expect
+                a = 1
+                b = 2
+
+                a == b
+            "#
+        ),
+        indoc!(
+            r"
+            This expectation failed:
+
+            5│>  expect
+            6│>      a = 1
+            7│>      b = 2
+            8│>
+            9│>      a == b
+
+            When it failed, these variables had these values:
+
+            a : Num *
+            a = 1
+
+            b : Num *
+            b = 2
+            "
+        ),
+    );
+}
+
+#[test]
+#[cfg(feature = "gen-dev")]
+fn lookup_list_of_strings() {
+    assert_expect_failures(
+        indoc!(
+            r#"
+            app "test" provides [main] to "./platform"
+
+            main = 0
+
+            expect
+                a = ["foo"]
+                b = ["a string so long that it cannot be short"]
+
+                a == b
+            "#
+        ),
+        indoc!(
+            r#"
+            This expectation failed:
+
+            5│>  expect
+            6│>      a = ["foo"]
+            7│>      b = ["a string so long that it cannot be short"]
+            8│>
+            9│>      a == b
+
+            When it failed, these variables had these values:
+
+            a : List Str
+            a = ["foo"]
+
+            b : List Str
+            b = ["a string so long that it cannot be short"]
+            "#
+        ),
+    );
+}
+
+#[test]
+#[cfg(feature = "gen-dev")]
+fn lookup_result_of_strings() {
+    assert_expect_failures(
+        indoc!(
+            r#"
+            app "test" provides [main] to "./platform"
+
+            main = 0
+
+            expect
+                a : Result Str Str
+                a = Ok "foo"
+
+                b : Result Str Str
+                b = Err "bar"
+
+                a == b
+            "#
+        ),
+        indoc!(
+            r#"
+            This expectation failed:
+
+             5│>  expect
+             6│>      a : Result Str Str
+             7│>      a = Ok "foo"
+             8│>
+             9│>      b : Result Str Str
+            10│>      b = Err "bar"
+            11│>
+            12│>      a == b
+
+            When it failed, these variables had these values:
+
+            a : Result Str Str
+            a = Ok "foo"
+
+            b : Result Str Str
+            b = Err "bar"
+            "#
+        ),
+    );
+}
+
+#[test]
+#[cfg(feature = "gen-dev")]
+fn lookup_record_of_strings() {
+    assert_expect_failures(
+        indoc!(
+            r#"
+            app "test" provides [main] to "./platform"
+
+            main = 0
+
+            expect
+                a = {
+                    utopia: "Astra mortemque praestare gradatim",
+                    brillist: "Profundum et fundamentum",
+                }
+
+                a != a
+            "#
+        ),
+        indoc!(
+            r#"
+            This expectation failed:
+
+             5│>  expect
+             6│>      a = {
+             7│>          utopia: "Astra mortemque praestare gradatim",
+             8│>          brillist: "Profundum et fundamentum",
+             9│>      }
+            10│>
+            11│>      a != a
+
+            When it failed, these variables had these values:
+
+            a : {
+                brillist : Str,
+                utopia : Str,
+            }
+            a = { brillist: "Profundum et fundamentum", utopia: "Astra mortemque praestare gradatim" }
+            "#
+        ),
+    );
+}
+
+#[test]
+#[cfg(feature = "gen-dev")]
+fn lookup_recursive_tag_union() {
+    assert_expect_failures(
+        indoc!(
+            r#"
+            app "test" provides [main] to "./platform"
+
+            main = 0
+
+            Tree a : [ Empty, Leaf a, Node (Tree a) (Tree a) ]
+
+            expect
+                a : Tree Str
+                a = Leaf "Astra mortemque praestare gradatim"
+
+                b : Tree Str
+                b = Node Empty Empty
+
+                a == b
+            "#
+        ),
+        indoc!(
+            r#"
+            This expectation failed:
+
+             7│>  expect
+             8│>      a : Tree Str
+             9│>      a = Leaf "Astra mortemque praestare gradatim"
+            10│>
+            11│>      b : Tree Str
+            12│>      b = Node Empty Empty
+            13│>
+            14│>      a == b
+
+            When it failed, these variables had these values:
+
+            a : Tree Str
+            a = Leaf "Astra mortemque praestare gradatim"
+
+            b : Tree Str
+            b = Node Empty Empty
+            "#
+        ),
+    );
+}
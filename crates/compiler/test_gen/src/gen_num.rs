@@ -480,13 +480,13 @@ fn f64_sqrt_checked_0() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn f64_sqrt_checked_positive() {
     assert_evals_to!("Num.sqrtChecked 100f64", RocResult::ok(10.0), RocResult<f64, ()>);
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn f64_sqrt_checked_negative() {
     assert_evals_to!("Num.sqrtChecked -1f64", RocResult::err(()), RocResult<f64, ()>);
 }
@@ -498,19 +498,19 @@ fn f64_log() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn f64_log_checked_one() {
     assert_evals_to!("Num.logChecked 1f64", RocResult::ok(0.0), RocResult<f64, ()>);
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn f64_log_checked_zero() {
     assert_evals_to!("Num.logChecked 0f64", RocResult::err(()), RocResult<f64, ()>);
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn f64_log_negative() {
     assert_evals_to!("Num.log -1f64", true, f64, |f: f64| f.is_nan());
 }
@@ -546,7 +546,7 @@ fn f32_abs() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn i64_abs() {
     assert_evals_to!("Num.abs -6", 6, i64);
     assert_evals_to!("Num.abs 7", 7, i64);
@@ -561,7 +561,7 @@ fn i64_abs() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn various_sized_abs() {
     assert_evals_to!("Num.abs -6i8", 6, i8);
     assert_evals_to!("Num.abs -6i16", 6, i16);
@@ -580,7 +580,7 @@ fn various_sized_abs() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 #[should_panic(
     expected = r#"Roc failed with message: "Integer absolute overflowed because its argument is the minimum value"#
 )]
@@ -621,7 +621,7 @@ fn gen_if_fn() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn gen_float_eq() {
     assert_evals_to!(
         indoc!(
@@ -635,7 +635,7 @@ fn gen_float_eq() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn gen_add_dec() {
     assert_evals_to!(
         indoc!(
@@ -696,7 +696,7 @@ fn gen_div_f32() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn gen_div_checked_f64() {
     assert_evals_to!(
         indoc!(
@@ -712,7 +712,7 @@ fn gen_div_checked_f64() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn gen_div_checked_by_zero_f64() {
     assert_evals_to!(
         indoc!(
@@ -1625,7 +1625,7 @@ fn int_negate() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 #[should_panic(
     expected = r#"Roc failed with message: "Integer negation overflowed because its argument is the minimum value"#
 )]
@@ -1939,7 +1939,7 @@ fn int_add_wrap() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn float_add_checked_pass() {
     assert_evals_to!(
         "Num.addChecked 1.0 0.0f64",
@@ -1949,7 +1949,7 @@ fn float_add_checked_pass() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn float_add_checked_fail() {
     assert_evals_to!(
         "Num.addChecked 1.7976931348623157e308f64 1.7976931348623157e308",
@@ -1959,7 +1959,7 @@ fn float_add_checked_fail() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn float_add_overflow() {
     assert_evals_to!(
         "1.7976931348623157e308f64 + 1.7976931348623157e308",
@@ -2014,7 +2014,7 @@ fn int_sub_checked() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn float_sub_checked() {
     assert_evals_to!(
         "Num.subChecked 1.0 0.0f64",
@@ -2084,7 +2084,7 @@ fn int_mul_checked() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn float_mul_checked() {
     assert_evals_to!(
         "Num.mulChecked 20.0 2.0f64",
@@ -2192,6 +2192,136 @@ fn shift_right_cast_i8() {
     );
 }
 
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev"))]
+fn shift_128_bit() {
+    assert_evals_to!(
+        "Num.shiftLeftBy 0x0000_0000_0000_0001_8000_0000_0000_0001u128 63",
+        0xc000_0000_0000_0000_8000_0000_0000_0000u128,
+        u128
+    );
+    assert_evals_to!("Num.shiftRightBy -256i128 4", I128::from(-16), I128);
+
+    // like the smaller unsigned integers, U128 copies the high bit (see `shift_right_by`)
+    assert_evals_to!(
+        "Num.shiftRightBy 0x8000_0000_0000_0000_0000_0000_0000_0001u128 4",
+        0xf800_0000_0000_0000_0000_0000_0000_0000u128,
+        u128
+    );
+    assert_evals_to!(
+        "Num.shiftRightZfBy 0x8000_0000_0000_0000_0000_0000_0000_0001u128 4",
+        0x0800_0000_0000_0000_0000_0000_0000_0000u128,
+        u128
+    );
+    assert_evals_to!(
+        "Num.shiftRightZfBy -256i128 4",
+        I128::from(0x0fff_ffff_ffff_ffff_ffff_ffff_ffff_fff0i128),
+        I128
+    );
+
+    // LLVM leaves shifting by the full width or more undefined
+    if cfg!(feature = "gen-dev") {
+        assert_evals_to!("Num.shiftLeftBy 1u128 128", 0u128, u128);
+        assert_evals_to!("Num.shiftRightBy -256i128 200", I128::from(-1), I128);
+        assert_evals_to!("Num.shiftRightBy 256i128 200", I128::from(0), I128);
+        assert_evals_to!(
+            "Num.shiftRightBy 0x8000_0000_0000_0000_0000_0000_0000_0000u128 200",
+            u128::MAX,
+            u128
+        );
+        assert_evals_to!(
+            "Num.shiftRightZfBy 0x8000_0000_0000_0000_0000_0000_0000_0000u128 200",
+            0u128,
+            u128
+        );
+    }
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev"))]
+fn bitwise_128_bit() {
+    assert_evals_to!(
+        "Num.bitwiseAnd 0xffff_0000_0000_ffff_ffff_0000_0000_ffffu128 0x00ff_ff00_00ff_ff00_00ff_ff00_00ff_ff00u128",
+        0x00ff_0000_0000_ff00_00ff_0000_0000_ff00u128,
+        u128
+    );
+    assert_evals_to!(
+        "Num.bitwiseOr 0xffff_0000_0000_0000_0000_0000_0000_0000u128 0xffffu128",
+        0xffff_0000_0000_0000_0000_0000_0000_ffffu128,
+        u128
+    );
+    assert_evals_to!("Num.bitwiseXor -1i128 1i128", I128::from(-2), I128);
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev"))]
+fn wrapping_128_bit() {
+    assert_evals_to!(
+        "Num.addWrap 0xffff_ffff_ffff_ffffu128 1",
+        0x1_0000_0000_0000_0000u128,
+        u128
+    );
+    assert_evals_to!("Num.addWrap Num.maxI128 1", I128::from(i128::MIN), I128);
+    assert_evals_to!(
+        "Num.subWrap 0x1_0000_0000_0000_0000u128 1",
+        0xffff_ffff_ffff_ffffu128,
+        u128
+    );
+    assert_evals_to!("Num.subWrap 0u128 1", u128::MAX, u128);
+    assert_evals_to!("Num.subWrap Num.minI128 1", I128::from(i128::MAX), I128);
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev"))]
+fn neg_abs_128_bit() {
+    assert_evals_to!(
+        "-(Num.toI128 Num.maxI64)",
+        I128::from(-(i64::MAX as i128)),
+        I128
+    );
+    assert_evals_to!(
+        "Num.neg 0x1_0000_0000_0000_0000i128",
+        I128::from(-0x1_0000_0000_0000_0000i128),
+        I128
+    );
+    assert_evals_to!("Num.neg 0i128", I128::from(0), I128);
+    assert_evals_to!(
+        "Num.abs -0x1_0000_0000_0000_0001i128",
+        I128::from(0x1_0000_0000_0000_0001i128),
+        I128
+    );
+    assert_evals_to!("Num.abs (Num.minI128 + 1)", I128::from(i128::MAX), I128);
+    assert_evals_to!("Num.abs 5u128", U128::from(5), U128);
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev"))]
+fn div_rem_128_bit() {
+    assert_evals_to!(
+        "Num.divTrunc -0x10_0000_0000_0000_0000i128 16",
+        I128::from(-0x1_0000_0000_0000_0000i128),
+        I128
+    );
+    assert_evals_to!("Num.rem -7i128 2", I128::from(-1), I128);
+    assert_evals_to!(
+        "Num.rem 0x1_0000_0000_0000_0001u128 16",
+        U128::from(1),
+        U128
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev"))]
+fn dec_neg_abs_sub() {
+    assert_evals_to!("Num.neg 1.5dec", RocDec::from_str("-1.5").unwrap(), RocDec);
+    assert_evals_to!("Num.abs -1.5dec", RocDec::from_str("1.5").unwrap(), RocDec);
+    assert_evals_to!(
+        "Num.subSaturated 1.5dec 0.25",
+        RocDec::from_str("1.25").unwrap(),
+        RocDec
+    );
+}
+
 #[test]
 #[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn min_i128() {
@@ -2430,7 +2560,7 @@ num_conversion_tests! {
 macro_rules! to_int_checked_tests {
     ($($fn:expr, $typ:ty, ($($test_name:ident, $input:expr, $output:expr)*))*) => {$($(
         #[test]
-        #[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+        #[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
         fn $test_name() {
             let sentinel = 23;
             // Some n = Ok n, None = OutOfBounds
@@ -2551,35 +2681,35 @@ fn wrap_with_default(test_roc_code: &str) -> String {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn to_i128_checked_smaller_width_pos() {
     let test_roc_code = wrap_with_default("Num.toI128Checked 15i8");
     assert_evals_to!(&test_roc_code, I128::from(15), I128)
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn to_i128_checked_smaller_width_neg() {
     let test_roc_code = wrap_with_default("Num.toI128Checked -15i8");
     assert_evals_to!(&test_roc_code, I128::from(-15), I128)
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn to_i128_checked_same() {
     let test_roc_code = wrap_with_default("Num.toI128Checked 15i128");
     assert_evals_to!(&test_roc_code, I128::from(15), I128)
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn to_i128_checked_same_width_unsigned_fits() {
     let test_roc_code = wrap_with_default("Num.toI128Checked 15u128");
     assert_evals_to!(&test_roc_code, I128::from(15), I128)
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn to_i128_checked_same_width_unsigned_oob() {
     let test_roc_code =
         "Result.isErr (Num.toI128Checked 170141183460469231731687303715884105728u128)";
@@ -2587,35 +2717,35 @@ fn to_i128_checked_same_width_unsigned_oob() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn to_u128_checked_smaller_width_pos() {
     let test_roc_code = wrap_with_default("Num.toU128Checked 15i8");
     assert_evals_to!(&test_roc_code, U128::from(15), U128)
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn to_u128_checked_smaller_width_neg_oob() {
     let test_roc_code = "Result.isErr (Num.toU128Checked -15i8)";
     assert_evals_to!(&test_roc_code, true, bool)
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn to_u128_checked_same() {
     let test_roc_code = wrap_with_default("Num.toU128Checked 15u128");
     assert_evals_to!(&test_roc_code, U128::from(15), U128)
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn to_u128_checked_same_width_signed_fits() {
     let test_roc_code = wrap_with_default("Num.toU128Checked 15i128");
     assert_evals_to!(&test_roc_code, U128::from(15), U128)
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn to_u128_checked_same_width_signed_oob() {
     let test_roc_code = "Result.isErr (Num.toU128Checked -1i128)";
     assert_evals_to!(&test_roc_code, true, bool)
@@ -3271,7 +3401,7 @@ fn upcast_of_int_is_zext() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 // https://github.com/roc-lang/roc/issues/2696
 fn upcast_of_int_checked_is_zext() {
     assert_evals_to!(
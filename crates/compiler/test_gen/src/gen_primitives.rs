@@ -4523,7 +4523,7 @@ fn layout_cache_structure_with_multiple_recursive_structures() {
 }
 
 #[test]
-#[cfg(feature = "gen-llvm")]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev"))]
 fn reset_recursive_type_wraps_in_named_type() {
     assert_evals_to!(
         indoc!(
@@ -195,7 +195,10 @@ pub fn helper(
         module_id,
         exposed_to_host: exposed_to_host.top_level_values.keys().copied().collect(),
         lazy_literals,
+        // Most tests only use literals, which constant folding would compute at compile time.
+        fold_constants: false,
         mode: roc_gen_dev::AssemblyBackendMode::Test,
+        source_info: None,
     };
 
     let target = target_lexicon::Triple::host().into();
@@ -400,3 +403,86 @@ macro_rules! assert_evals_to {
 
 #[allow(unused_imports)]
 pub(crate) use assert_evals_to;
+
+/// Runs the top-level expects of `src` through the dev backend like `roc test --dev` does,
+/// and checks the report of the failed ones. The first line of the report, which contains the
+/// path of a temporary file, is not compared.
+#[allow(dead_code)]
+pub(crate) fn assert_expect_failures(src: &str, expected: &str) {
+    use roc_load::LoadMonomorphizedError;
+    use roc_repl_expect::run::{expect_mono_module_to_dylib_asm, ExpectMemory};
+
+    let arena = bumpalo::Bump::new();
+    let arena = &arena;
+
+    // the report quotes the source, so it has to be on disk
+    let src_dir = tempdir().unwrap();
+    let filename = src_dir.path().join("Test.roc");
+    std::fs::write(&filename, src).unwrap();
+
+    let target = target_lexicon::Triple::host().into();
+
+    let load_config = LoadConfig {
+        target,
+        render: roc_reporting::report::RenderTarget::ColorTerminal,
+        palette: roc_reporting::report::DEFAULT_PALETTE,
+        threading: Threading::Single,
+        exec_mode: ExecutionMode::Test,
+        function_kind: FunctionKind::LambdaSet,
+    };
+    let mut loaded = match roc_load::load_and_monomorphize_from_str(
+        arena,
+        filename,
+        src,
+        src_dir.path().to_path_buf(),
+        None,
+        RocCacheDir::Disallowed,
+        load_config,
+    ) {
+        Ok(loaded) => loaded,
+        Err(LoadMonomorphizedError::ErrorModule(module)) => {
+            panic!("{:?}", (module.can_problems, module.type_problems))
+        }
+        Err(e) => panic!("{e:?}"),
+    };
+
+    let mut expectations = std::mem::take(&mut loaded.expectations);
+    let interns = loaded.interns.clone();
+
+    let (lib, expects_by_module, layout_interner) =
+        expect_mono_module_to_dylib_asm(arena, target, loaded).unwrap();
+
+    let interns = arena.alloc(interns);
+    let layout_interner = layout_interner.into_global();
+
+    // tests run in parallel, so they can't share the memory that `roc test` maps by process id
+    let mut shared_buffer = [0u8; 1024];
+    let mut memory = ExpectMemory::from_slice(&mut shared_buffer);
+
+    let mut writer = Vec::new();
+
+    for (_, expects) in expects_by_module {
+        roc_repl_expect::run::run_expects_with_memory(
+            &mut writer,
+            roc_reporting::report::RenderTarget::ColorTerminal,
+            arena,
+            interns,
+            &layout_interner,
+            &lib,
+            &mut expectations,
+            expects,
+            &mut memory,
+        )
+        .unwrap();
+    }
+
+    let bytes = strip_ansi_escapes::strip(writer).unwrap();
+    let actual = String::from_utf8(bytes).unwrap();
+
+    let actual = match actual.split_once('\n') {
+        Some((_title, report)) => report.trim(),
+        None => actual.as_str(),
+    };
+
+    assert_eq!(expected.trim_end(), actual);
+}
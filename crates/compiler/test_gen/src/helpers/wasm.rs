@@ -130,6 +130,11 @@ fn compile_roc_to_wasm_bytes<'a, T: Wasm32Result>(
         module_id,
         exposed_to_host,
         stack_bytes: roc_gen_wasm::Env::DEFAULT_STACK_BYTES,
+        source_info: None,
+        // e.g. ROC_WASM_FEATURES=all to test code that uses the post-MVP proposals
+        features: std::env::var("ROC_WASM_FEATURES")
+            .map(|names| names.parse().unwrap_or_else(|e| panic!("{e}")))
+            .unwrap_or_default(),
     };
 
     let host_module = roc_gen_wasm::parse_host(env.arena, host_bytes).unwrap_or_else(|e| {
@@ -168,6 +173,7 @@ fn compile_roc_to_wasm_bytes<'a, T: Wasm32Result>(
     });
 
     module.eliminate_dead_code(env.arena, called_fns);
+    module.validate().unwrap_or_else(|e| panic!("{e}"));
 
     let mut app_module_bytes = std::vec::Vec::with_capacity(module.size());
     module.serialize(&mut app_module_bytes);
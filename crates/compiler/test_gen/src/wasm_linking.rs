@@ -2,7 +2,7 @@
 
 use bumpalo::Bump;
 use roc_gen_wasm::Env;
-use roc_target::Target;
+use roc_target::{Target, WasmFeatures};
 use std::fs;
 use std::process::Command;
 
@@ -159,6 +159,8 @@ impl<'a> BackendInputs<'a> {
             module_id,
             exposed_to_host,
             stack_bytes: Env::DEFAULT_STACK_BYTES,
+            source_info: None,
+            features: WasmFeatures::default(),
         };
 
         // Identifier stuff for the backend
@@ -0,0 +1,1046 @@
+app "c-glue"
+    packages { pf: "../platform/main.roc" }
+    imports [
+        pf.Types.{ Types },
+        pf.Shape.{ Shape, RocFn, RocStructFields },
+        pf.File.{ File },
+        pf.TypeId.{ TypeId },
+        "../static/roc_std.h" as rocStdHeader : Str,
+    ]
+    provides [makeGlue] to pf
+
+makeGlue : List Types -> Result (List File) Str
+makeGlue = \typesByArch ->
+    archSections =
+        List.walkWithIndex typesByArch "" \content, types, index ->
+            arch = (Types.target types).architecture
+            directive = if index == 0 then "#if" else "#elif"
+            condition = archCondition arch
+            archStr = archName arch
+            declarations = generateDeclarations types
+
+            Str.concat
+                content
+                """
+                $(directive) $(condition)
+
+                // Declarations for $(archStr)
+
+                $(declarations)
+
+                """
+
+    appHeader =
+        """
+        $(fileHeader)
+        #ifndef ROC_APP_H
+        #define ROC_APP_H
+
+        #include "roc_std.h"
+
+        #ifdef __cplusplus
+        extern "C" {
+        #endif
+
+        $(archSections)#else
+        #error "roc glue did not generate declarations for this architecture"
+        #endif
+
+        #ifdef __cplusplus
+        }
+        #endif
+
+        #endif // ROC_APP_H
+
+        """
+
+    Ok [
+        { name: "roc_app.h", content: appHeader },
+        { name: "roc_std.h", content: rocStdHeader },
+    ]
+
+generateDeclarations : Types -> Str
+generateDeclarations = \types ->
+    order = declarationOrder types
+
+    # Values of recursive tag unions and functions are just pointers, so declare them
+    # first; then the types that refer to them can come in any order.
+    pointers = List.walk order "" \buf, id -> generatePointerStruct buf types id
+
+    order
+    |> List.walk pointers \buf, id -> generateDeclaration buf types id
+    |> generateEntryPoints types
+
+generatePointerStruct : Str, Types, TypeId -> Str
+generatePointerStruct = \buf, types, id ->
+    when Types.shape types id is
+        TagUnion (Recursive { name, tags }) ->
+            if List.isEmpty tags then
+                buf
+            else
+                pointerStruct buf name "pointer"
+
+        TagUnion (NullableWrapped { name }) | TagUnion (NullableUnwrapped { name }) | TagUnion (NonNullableUnwrapped { name }) ->
+            pointerStruct buf name "pointer"
+
+        Function { functionName, isToplevel } ->
+            if isToplevel then
+                buf
+            else
+                pointerStruct buf functionName "closure_data"
+
+        _ ->
+            buf
+
+pointerStruct : Str, Str, Str -> Str
+pointerStruct = \buf, name, fieldName ->
+    escapedName = escapeKW name
+
+    Str.concat
+        buf
+        """
+        typedef struct $(escapedName) {
+            void *$(fieldName);
+        } $(escapedName);
+
+
+        """
+
+generateDeclaration : Str, Types, TypeId -> Str
+generateDeclaration = \buf, types, id ->
+    when Types.shape types id is
+        Struct { name, fields } ->
+            generateStruct buf types id name fields
+
+        TagUnionPayload { name, fields } ->
+            generateStruct buf types id name (nameTagUnionPayloadFields fields)
+
+        TagUnion (Enumeration { name, tags, size }) ->
+            generateEnumeration buf name tags size
+
+        TagUnion (NonRecursive { name, tags, discriminantSize, discriminantOffset }) ->
+            if List.isEmpty tags then
+                buf
+            else
+                generateNonRecursiveTagUnion buf types id name tags discriminantSize discriminantOffset
+
+        TagUnion (Recursive { name, tags, discriminantSize, discriminantOffset }) ->
+            if List.isEmpty tags then
+                buf
+            else
+                generateRecursiveTagUnion buf types name tags discriminantSize discriminantOffset None
+
+        TagUnion (NullableWrapped { name, indexOfNullTag, tags, discriminantSize, discriminantOffset }) ->
+            generateRecursiveTagUnion buf types name tags discriminantSize discriminantOffset (Some indexOfNullTag)
+
+        TagUnion (NullableUnwrapped { name, nullTag, nonNullTag, nonNullPayload, whichTagIsNull }) ->
+            generateNullableUnwrapped buf types name nullTag nonNullTag nonNullPayload whichTagIsNull
+
+        TagUnion (SingleTagStruct { name, tagName, payload }) ->
+            generateSingleTagStruct buf types id name tagName payload
+
+        TagUnion (NonNullableUnwrapped { name, tagName, payload }) ->
+            generateBoxedTag buf (escapeKW name) tagName (typeName types payload)
+
+        RocResult ok err ->
+            generateResult buf types id ok err
+
+        Function rocFn ->
+            if rocFn.isToplevel then
+                buf
+            else
+                generateFunction buf types rocFn
+
+        RecursivePointer _ ->
+            # This is recursively pointing to a type that should already have been added,
+            # so no extra work needs to happen.
+            buf
+
+        Unit
+        | Unsized
+        | EmptyTagUnion
+        | Num _
+        | Bool
+        | RocStr
+        | RocDict _ _
+        | RocSet _
+        | RocList _
+        | RocBox _ ->
+            # These are either defined in roc_std.h, or have no representation in C.
+            buf
+
+## C needs a type to be defined before another type can hold it by value,
+## so this lists every type after the ones it holds.
+declarationOrder : Types -> List TypeId
+declarationOrder = \types ->
+    Types.walkShapes types { visited: Set.empty {}, order: [] } \state, _, id ->
+        visitType types state id
+    |> .order
+
+visitType : Types, { visited : Set TypeId, order : List TypeId }, TypeId -> { visited : Set TypeId, order : List TypeId }
+visitType = \types, state, id ->
+    if Set.contains state.visited id then
+        state
+    else
+        marked = { state & visited: Set.insert state.visited id }
+        visitedHeld = List.walk (heldTypes types id) marked \accum, heldId -> visitType types accum heldId
+
+        { visitedHeld & order: List.append visitedHeld.order id }
+
+heldTypes : Types, TypeId -> List TypeId
+heldTypes = \types, id ->
+    when Types.shape types id is
+        Struct { fields } | TagUnionPayload { fields } ->
+            structFieldList fields |> List.map .id
+
+        TagUnion (NonRecursive { tags }) | TagUnion (Recursive { tags }) | TagUnion (NullableWrapped { tags }) ->
+            List.keepOks tags \{ payload } ->
+                when payload is
+                    Some payloadId -> Ok payloadId
+                    None -> Err NoPayload
+
+        TagUnion (NullableUnwrapped { nonNullPayload }) ->
+            [nonNullPayload]
+
+        TagUnion (NonNullableUnwrapped { payload }) ->
+            [payload]
+
+        TagUnion (SingleTagStruct { payload }) ->
+            singleTagFieldIds payload
+
+        RocResult ok err ->
+            [ok, err]
+
+        Function rocFn ->
+            List.append rocFn.args rocFn.ret
+
+        _ ->
+            []
+
+generateEntryPoints : Str, Types -> Str
+generateEntryPoints = \buf, types ->
+    List.walk (Types.entryPoints types) buf \accum, T name id -> generateEntryPoint accum types name id
+
+generateEntryPoint : Str, Types, Str, TypeId -> Str
+generateEntryPoint = \buf, types, name, id ->
+    escapedName = escapeKW name
+
+    (args, ret) =
+        when Types.shape types id is
+            Function rocFn -> (nonUnitArgs types rocFn.args, rocFn.ret)
+            _ -> ([], id)
+
+    externArgs =
+        List.map args \{ id: argId, index } ->
+            type = typeName types argId
+            indexStr = Num.toStr index
+
+            if isPlainData types (Types.shape types argId) then
+                "$(type) arg$(indexStr)"
+            else
+                "$(type) *arg$(indexStr)"
+
+    publicArgs =
+        List.map args \{ id: argId, index } ->
+            type = typeName types argId
+            indexStr = Num.toStr index
+
+            "$(type) arg$(indexStr)"
+
+    callArgs =
+        List.map args \{ id: argId, index } ->
+            indexStr = Num.toStr index
+
+            if isPlainData types (Types.shape types argId) then
+                "arg$(indexStr)"
+            else
+                "&arg$(indexStr)"
+
+    retType = typeName types ret
+
+    when Types.shape types ret is
+        Function _ ->
+            externParams = List.prepend externArgs "void *closure_data" |> Str.joinWith ", "
+            publicParams = List.prepend publicArgs "void *closure_data" |> Str.joinWith ", "
+            callParams = List.prepend callArgs "closure_data" |> Str.joinWith ", "
+
+            """
+            $(buf)void roc__$(name)_1_exposed_generic($(externParams));
+            int64_t roc__$(name)_1_exposed_size(void);
+
+            // `closure_data` must point to roc__$(name)_1_exposed_size() bytes, which the returned function refers to.
+            static inline $(retType) $(escapedName)($(publicParams)) {
+                roc__$(name)_1_exposed_generic($(callParams));
+
+                $(retType) ret = { closure_data };
+                return ret;
+            }
+
+            // The name of the returned function's type changes whenever the app does, so hosts should use these.
+            typedef $(retType) $(escapedName)_Fn;
+            #define $(escapedName)_Fn_call $(retType)_call
+
+
+            """
+
+        shape ->
+            externParams = List.prepend externArgs "$(retType) *ret" |> Str.joinWith ", "
+            publicParams = if List.isEmpty publicArgs then "void" else Str.joinWith publicArgs ", "
+            callParams = List.prepend callArgs "&ret" |> Str.joinWith ", "
+
+            if isUnit shape then
+                """
+                $(buf)void roc__$(name)_1_exposed_generic($(externParams));
+
+                static inline void $(escapedName)($(publicParams)) {
+                    unsigned char ret;
+                    roc__$(name)_1_exposed_generic($(callParams));
+                }
+
+
+                """
+            else
+                """
+                $(buf)void roc__$(name)_1_exposed_generic($(externParams));
+
+                static inline $(retType) $(escapedName)($(publicParams)) {
+                    $(retType) ret;
+                    roc__$(name)_1_exposed_generic($(callParams));
+                    return ret;
+                }
+
+
+                """
+
+generateFunction : Str, Types, RocFn -> Str
+generateFunction = \buf, types, rocFn ->
+    name = escapeKW rocFn.functionName
+    externName = rocFn.externName
+    args = nonUnitArgs types rocFn.args
+
+    externArgs =
+        if List.isEmpty args then
+            # These always have a first argument that's a pointer, even if it's to nothing.
+            "const void *arg0"
+        else
+            List.map args \{ id: argId, index } ->
+                type = typeName types argId
+                indexStr = Num.toStr index
+
+                "const $(type) *arg$(indexStr)"
+            |> Str.joinWith ", "
+
+    publicArgs =
+        List.map args \{ id: argId, index } ->
+            type = typeName types argId
+            indexStr = Num.toStr index
+
+            ", $(type) arg$(indexStr)"
+        |> Str.joinWith ""
+
+    callArgs =
+        if List.isEmpty args then
+            "NULL"
+        else
+            List.map args \{ index } ->
+                indexStr = Num.toStr index
+
+                "&arg$(indexStr)"
+            |> Str.joinWith ", "
+
+    retShape = Types.shape types rocFn.ret
+    ret = typeName types rocFn.ret
+
+    if isUnit retShape then
+        """
+        $(buf)void $(externName)($(externArgs), void *closure_data, void *output);
+
+        static inline void $(name)_call(const $(name) *self$(publicArgs)) {
+            unsigned char output;
+            $(externName)($(callArgs), self->closure_data, &output);
+        }
+
+
+        """
+    else
+        """
+        $(buf)void $(externName)($(externArgs), void *closure_data, $(ret) *output);
+
+        static inline $(ret) $(name)_call(const $(name) *self$(publicArgs)) {
+            $(ret) output;
+            $(externName)($(callArgs), self->closure_data, &output);
+            return output;
+        }
+
+
+        """
+
+generateStruct : Str, Types, TypeId, Str, RocStructFields -> Str
+generateStruct = \buf, types, id, name, structFields ->
+    escapedName = escapeKW name
+    body =
+        structFieldList structFields
+        |> List.walk "" \accum, field -> generateField accum types field.name field.id
+
+    buf
+    |> Str.concat
+        """
+        typedef struct $(escapedName) {
+        $(body)} $(escapedName);
+
+
+        """
+    |> generateLayoutChecks types id escapedName
+
+generateField : Str, Types, Str, TypeId -> Str
+generateField = \buf, types, fieldName, fieldId ->
+    if Types.size types fieldId == 0 then
+        # C has no zero-sized types, and they take up no room in Roc either.
+        buf
+    else
+        type = typeName types fieldId
+        escapedFieldName = escapeKW fieldName
+
+        Str.concat buf "$(indent)$(type) $(escapedFieldName);\n"
+
+nameTagUnionPayloadFields = \payloadFields ->
+    # Tag union payloads have numbered fields, so we prefix them
+    # with an "f" because C doesn't allow struct fields to be numbers.
+    when payloadFields is
+        HasNoClosure fields ->
+            renamedFields = List.map fields \{ name, id } -> { name: "f$(name)", id }
+            HasNoClosure renamedFields
+
+        HasClosure fields ->
+            renamedFields = List.map fields \{ name, id, accessors } -> { name: "f$(name)", id, accessors }
+            HasClosure renamedFields
+
+structFieldList : RocStructFields -> List { name : Str, id : TypeId }
+structFieldList = \structFields ->
+    when structFields is
+        HasNoClosure fields -> fields
+        HasClosure fields -> List.map fields \{ name, id } -> { name, id }
+
+singleTagFieldIds = \payload ->
+    when payload is
+        HasNoClosure fields -> List.map fields .id
+        HasClosure fields -> List.map fields .id
+
+generateLayoutChecks : Str, Types, TypeId, Str -> Str
+generateLayoutChecks = \buf, types, id, name ->
+    size = Types.size types id
+
+    if size == 0 then
+        buf
+    else
+        alignment = Types.alignment types id
+        sizeStr = roundUpToAlignment size alignment |> Num.toStr
+        alignmentStr = Num.toStr alignment
+
+        Str.concat
+            buf
+            """
+            ROC_STATIC_ASSERT(sizeof($(name)) == $(sizeStr), "$(name) must have the same size as in Roc");
+            ROC_STATIC_ASSERT(ROC_ALIGNOF($(name)) == $(alignmentStr), "$(name) must have the same alignment as in Roc");
+
+
+            """
+
+generateEnumeration : Str, Str, List Str, U32 -> Str
+generateEnumeration = \buf, name, tags, size ->
+    escapedName = escapeKW name
+    reprType = unsignedType size
+    constants =
+        List.walkWithIndex tags "" \accum, tagName, index ->
+            indexStr = Num.toStr index
+
+            Str.concat accum "$(indent)$(escapedName)_$(tagName) = $(indexStr),\n"
+
+    Str.concat
+        buf
+        """
+        typedef $(reprType) $(escapedName);
+
+        enum {
+        $(constants)};
+
+
+        """
+
+generateNonRecursiveTagUnion : Str, Types, TypeId, Str, List { name : Str, payload : [Some TypeId, None] }, U32, U32 -> Str
+generateNonRecursiveTagUnion = \buf, types, id, name, tags, discriminantSize, discriminantOffset ->
+    escapedName = escapeKW name
+    discriminantName = "discriminant_$(escapedName)"
+    unionName = "union_$(escapedName)"
+    tagNames = List.map tags .name
+    offsetStr = Num.toStr discriminantOffset
+    members = List.walk tags "" (generateUnionMember types)
+
+    # A C union needs at least one member.
+    (unionDecl, payloadField) =
+        if Str.isEmpty members then
+            ("", "")
+        else
+            (
+                """
+                typedef union $(unionName) {
+                $(members)} $(unionName);
+
+
+                """,
+                "$(indent)$(unionName) payload;\n",
+            )
+
+    buf
+    |> generateEnumeration discriminantName tagNames discriminantSize
+    |> Str.concat unionDecl
+    |> Str.concat
+        """
+        typedef struct $(escapedName) {
+        $(payloadField)$(indent)$(discriminantName) discriminant;
+        } $(escapedName);
+
+
+        """
+    |> generateLayoutChecks types id escapedName
+    |> Str.concat
+        """
+        ROC_STATIC_ASSERT(offsetof($(escapedName), discriminant) == $(offsetStr), "$(escapedName) must store its discriminant where Roc does");
+
+        static inline $(discriminantName) $(escapedName)_discriminant(const $(escapedName) *self) {
+            return self->discriminant;
+        }
+
+
+        """
+    |> \b -> List.walk tags b \accum, tag -> generateNonRecursiveTag accum types escapedName tag
+
+generateNonRecursiveTag = \buf, types, unionName, { name: tagName, payload } ->
+    discriminant = "discriminant_$(unionName)_$(tagName)"
+
+    isFunction =
+        """
+        static inline bool $(unionName)_is_$(tagName)(const $(unionName) *self) {
+            return self->discriminant == $(discriminant);
+        }
+
+
+        """
+
+    when storedPayload types payload is
+        Some payloadId ->
+            payloadType = typeName types payloadId
+
+            """
+            $(buf)$(isFunction)static inline $(unionName) $(unionName)_new_$(tagName)($(payloadType) payload) {
+                $(unionName) self;
+                memset(&self, 0, sizeof(self));
+                self.payload.$(tagName) = payload;
+                self.discriminant = $(discriminant);
+                return self;
+            }
+
+            static inline const $(payloadType) *$(unionName)_get_$(tagName)(const $(unionName) *self) {
+                return &self->payload.$(tagName);
+            }
+
+
+            """
+
+        None ->
+            """
+            $(buf)$(isFunction)static inline $(unionName) $(unionName)_new_$(tagName)(void) {
+                $(unionName) self;
+                memset(&self, 0, sizeof(self));
+                self.discriminant = $(discriminant);
+                return self;
+            }
+
+
+            """
+
+generateUnionMember = \types ->
+    \accum, { name: tagName, payload } ->
+        when storedPayload types payload is
+            Some payloadId -> generateField accum types tagName payloadId
+            None -> accum
+
+generateRecursiveTagUnion : Str, Types, Str, List { name : Str, payload : [Some TypeId, None] }, U32, U32, [Some U16, None] -> Str
+generateRecursiveTagUnion = \buf, types, name, tags, discriminantSize, discriminantOffset, nullTagIndex ->
+    escapedName = escapeKW name
+    discriminantName = "discriminant_$(escapedName)"
+    unionName = "union_$(escapedName)"
+    tagNames = List.map tags .name
+    members = List.walk tags "" (generateUnionMember types)
+    offsetStr = Num.toStr discriminantOffset
+    mask = if ptrWidth types == 8 then "7" else "3"
+
+    # Roc stores the discriminant in the low bits of the pointer when there is room,
+    # and after the payload otherwise.
+    nonNullTagCount =
+        when nullTagIndex is
+            Some _ -> List.len tags - 1
+            None -> List.len tags
+
+    discriminantInPointer = nonNullTagCount < ptrWidth types
+
+    nullCheck =
+        when nullTagIndex is
+            Some index ->
+                nullTagName = List.get tagNames (Num.intCast index) |> Result.withDefault ""
+
+                """
+                    if (self->pointer == NULL) {
+                        return $(discriminantName)_$(nullTagName);
+                    }
+
+
+                """
+
+            None ->
+                ""
+
+    (readDiscriminant, unmaskedPointer, dataSize) =
+        if discriminantInPointer then
+            (
+                "$(indent)return ($(discriminantName))((uintptr_t)self->pointer & $(mask));\n",
+                "($(unionName) *)((uintptr_t)self->pointer & ~(uintptr_t)$(mask))",
+                "sizeof($(unionName))",
+            )
+        else
+            (
+                """
+                    $(discriminantName) discriminant;
+                    memcpy(&discriminant, (const unsigned char *)self->pointer + $(offsetStr), sizeof(discriminant));
+                    return discriminant;
+
+                """,
+                "($(unionName) *)self->pointer",
+                Num.toStr (discriminantOffset + discriminantSize),
+            )
+
+    tagFunctions =
+        List.walkWithIndex tags "" \accum, { name: tagName, payload }, index ->
+            discriminant = "$(discriminantName)_$(tagName)"
+            isNull = nullTagIndex == Some (Num.intCast index)
+
+            isFunction =
+                """
+                static inline bool $(escapedName)_is_$(tagName)(const $(escapedName) *self) {
+                    return $(escapedName)_discriminant(self) == $(discriminant);
+                }
+
+
+                """
+
+            storeDiscriminant =
+                if discriminantInPointer then
+                    "$(indent)$(escapedName) self = { (void *)((uintptr_t)data | $(discriminant)) };\n"
+                else
+                    """
+                        $(discriminantName) discriminant = $(discriminant);
+                        memcpy((unsigned char *)data + $(offsetStr), &discriminant, sizeof(discriminant));
+                        $(escapedName) self = { data };
+
+                    """
+
+            allocate = "$(indent)$(unionName) *data = ($(unionName) *)roc_alloc_refcounted($(dataSize), ROC_ALIGNOF($(unionName)));\n"
+
+            if isNull then
+                """
+                $(accum)$(isFunction)static inline $(escapedName) $(escapedName)_new_$(tagName)(void) {
+                    $(escapedName) self = { NULL };
+                    return self;
+                }
+
+
+                """
+            else
+                when storedPayload types payload is
+                    Some payloadId ->
+                        payloadType = typeName types payloadId
+
+                        """
+                        $(accum)$(isFunction)static inline $(escapedName) $(escapedName)_new_$(tagName)($(payloadType) payload) {
+                        $(allocate)    data->$(tagName) = payload;
+                        $(storeDiscriminant)    return self;
+                        }
+
+                        static inline const $(payloadType) *$(escapedName)_get_$(tagName)(const $(escapedName) *self) {
+                            return &$(escapedName)_unmasked_pointer(self)->$(tagName);
+                        }
+
+
+                        """
+
+                    None ->
+                        """
+                        $(accum)$(isFunction)static inline $(escapedName) $(escapedName)_new_$(tagName)(void) {
+                        $(allocate)$(storeDiscriminant)    return self;
+                        }
+
+
+                        """
+
+    buf
+    |> generateEnumeration discriminantName tagNames discriminantSize
+    |> Str.concat
+        """
+        typedef union $(unionName) {
+        $(members)} $(unionName);
+
+        static inline $(discriminantName) $(escapedName)_discriminant(const $(escapedName) *self) {
+        $(nullCheck)$(readDiscriminant)}
+
+        static inline $(unionName) *$(escapedName)_unmasked_pointer(const $(escapedName) *self) {
+            return $(unmaskedPointer);
+        }
+
+
+        """
+    |> Str.concat tagFunctions
+
+generateNullableUnwrapped : Str, Types, Str, Str, Str, TypeId, [FirstTagIsNull, SecondTagIsNull] -> Str
+generateNullableUnwrapped = \buf, types, name, nullTag, nonNullTag, nonNullPayload, whichTagIsNull ->
+    escapedName = escapeKW name
+    discriminantName = "discriminant_$(escapedName)"
+    tagNames =
+        when whichTagIsNull is
+            FirstTagIsNull -> [nullTag, nonNullTag]
+            SecondTagIsNull -> [nonNullTag, nullTag]
+
+    buf
+    |> generateEnumeration discriminantName tagNames 1
+    |> Str.concat
+        """
+        static inline $(discriminantName) $(escapedName)_discriminant(const $(escapedName) *self) {
+            return self->pointer == NULL ? $(discriminantName)_$(nullTag) : $(discriminantName)_$(nonNullTag);
+        }
+
+        static inline bool $(escapedName)_is_$(nullTag)(const $(escapedName) *self) {
+            return self->pointer == NULL;
+        }
+
+        static inline bool $(escapedName)_is_$(nonNullTag)(const $(escapedName) *self) {
+            return self->pointer != NULL;
+        }
+
+        static inline $(escapedName) $(escapedName)_new_$(nullTag)(void) {
+            $(escapedName) self = { NULL };
+            return self;
+        }
+
+
+        """
+    |> generateBoxedTag escapedName nonNullTag (typeName types nonNullPayload)
+
+## A tag whose payload is heap-allocated, with nothing but a pointer to it in the tag union.
+generateBoxedTag : Str, Str, Str, Str -> Str
+generateBoxedTag = \buf, name, tagName, payloadType ->
+    Str.concat
+        buf
+        """
+        static inline $(name) $(name)_new_$(tagName)($(payloadType) payload) {
+            $(payloadType) *data = ($(payloadType) *)roc_alloc_refcounted(sizeof($(payloadType)), ROC_ALIGNOF($(payloadType)));
+            *data = payload;
+
+            $(name) self = { data };
+            return self;
+        }
+
+        static inline const $(payloadType) *$(name)_get_$(tagName)(const $(name) *self) {
+            return (const $(payloadType) *)self->pointer;
+        }
+
+
+        """
+
+generateSingleTagStruct = \buf, types, id, name, tagName, payload ->
+    # Store single-tag unions as structs rather than tag unions,
+    # because they have only one alternative.
+    escapedName = escapeKW name
+    fields =
+        singleTagFieldIds payload
+        |> List.mapWithIndex \fieldId, index ->
+            indexStr = Num.toStr index
+
+            { name: "f$(indexStr)", id: fieldId }
+        |> List.keepIf \field -> Types.size types field.id > 0
+
+    if List.isEmpty fields then
+        # C structs need at least one field, but Roc never reads or writes this one.
+        """
+        $(buf)typedef struct $(escapedName) {
+            unsigned char unused;
+        } $(escapedName);
+
+        static inline $(escapedName) $(escapedName)_new_$(tagName)(void) {
+            $(escapedName) self = { 0 };
+            return self;
+        }
+
+
+        """
+    else
+        body = List.walk fields "" \accum, field -> generateField accum types field.name field.id
+        params =
+            List.map fields \field ->
+                type = typeName types field.id
+
+                "$(type) $(field.name)"
+            |> Str.joinWith ", "
+        assignments =
+            List.map fields \field -> "$(indent)self.$(field.name) = $(field.name);\n"
+            |> Str.joinWith ""
+
+        buf
+        |> Str.concat
+            """
+            typedef struct $(escapedName) {
+            $(body)} $(escapedName);
+
+
+            """
+        |> generateLayoutChecks types id escapedName
+        |> Str.concat
+            """
+            static inline $(escapedName) $(escapedName)_new_$(tagName)($(params)) {
+                $(escapedName) self;
+            $(assignments)    return self;
+            }
+
+
+            """
+
+generateResult : Str, Types, TypeId, TypeId, TypeId -> Str
+generateResult = \buf, types, id, ok, err ->
+    max = \a, b -> if a >= b then a else b
+
+    # Results are regular tag unions, whose discriminant comes after the larger payload.
+    discriminantOffset =
+        roundUpToAlignment
+            (max (Types.size types ok) (Types.size types err))
+            (max (Types.alignment types ok) (Types.alignment types err))
+
+    tags = [
+        { name: "Err", payload: Some err },
+        { name: "Ok", payload: Some ok },
+    ]
+
+    generateNonRecursiveTagUnion buf types id (resultName types ok err) tags 1 discriminantOffset
+
+## Zero-sized payloads have no representation in C, so these are treated like tags without payloads.
+storedPayload : Types, [Some TypeId, None] -> [Some TypeId, None]
+storedPayload = \types, payload ->
+    when payload is
+        Some id ->
+            if Types.size types id == 0 then
+                None
+            else
+                Some id
+
+        None ->
+            None
+
+## Whether values of this type own no reference-counted memory, which decides whether
+## the entry points take them by value or by pointer.
+isPlainData : Types, Shape -> Bool
+isPlainData = \types, type ->
+    when type is
+        Function rocFn ->
+            runtimeRepresentation = Types.shape types rocFn.lambdaSet
+            isPlainData types runtimeRepresentation
+
+        # unsized values are heap-allocated
+        Unsized -> Bool.false
+        Unit | EmptyTagUnion | Bool | Num _ | TagUnion (Enumeration _) -> Bool.true
+        RocStr | RocList _ | RocDict _ _ | RocSet _ | RocBox _ | TagUnion (NullableUnwrapped _) | TagUnion (NullableWrapped _) | TagUnion (Recursive _) | TagUnion (NonNullableUnwrapped _) | RecursivePointer _ -> Bool.false
+        TagUnion (SingleTagStruct { payload }) ->
+            List.all (singleTagFieldIds payload) \id -> isPlainData types (Types.shape types id)
+
+        TagUnion (NonRecursive { tags }) ->
+            List.all tags \{ payload } ->
+                when payload is
+                    Some id -> isPlainData types (Types.shape types id)
+                    None -> Bool.true
+
+        RocResult okId errId ->
+            isPlainData types (Types.shape types okId)
+            && isPlainData types (Types.shape types errId)
+
+        Struct { fields } | TagUnionPayload { fields } ->
+            List.all (structFieldList fields) \{ id } -> isPlainData types (Types.shape types id)
+
+typeName = \types, id ->
+    when Types.shape types id is
+        Unit -> "void"
+        Unsized -> "RocList"
+        EmptyTagUnion -> "void"
+        RocStr -> "RocStr"
+        Bool -> "bool"
+        Num U8 -> "uint8_t"
+        Num U16 -> "uint16_t"
+        Num U32 -> "uint32_t"
+        Num U64 -> "uint64_t"
+        Num U128 -> "RocU128"
+        Num I8 -> "int8_t"
+        Num I16 -> "int16_t"
+        Num I32 -> "int32_t"
+        Num I64 -> "int64_t"
+        Num I128 -> "RocI128"
+        Num F32 -> "float"
+        Num F64 -> "double"
+        Num Dec -> "RocDec"
+        RocDict _ _ -> "RocDict"
+        RocSet _ -> "RocSet"
+        RocList _ -> "RocList"
+        RocBox _ -> "RocBox"
+        RocResult ok err -> resultName types ok err
+        RecursivePointer content -> typeName types content
+        Struct { name } -> escapeKW name
+        TagUnionPayload { name } -> escapeKW name
+        TagUnion (NonRecursive { name }) -> escapeKW name
+        TagUnion (Recursive { name }) -> escapeKW name
+        TagUnion (Enumeration { name }) -> escapeKW name
+        TagUnion (NullableWrapped { name }) -> escapeKW name
+        TagUnion (NullableUnwrapped { name }) -> escapeKW name
+        TagUnion (NonNullableUnwrapped { name }) -> escapeKW name
+        TagUnion (SingleTagStruct { name }) -> escapeKW name
+        Function { functionName } -> escapeKW functionName
+
+## C has no generics, so every kind of Result gets its own struct.
+resultName = \types, ok, err ->
+    okName = typeName types ok
+    errName = typeName types err
+
+    "RocResult_$(okName)_$(errName)"
+
+unsignedType = \bytes ->
+    when bytes is
+        2 -> "uint16_t"
+        4 -> "uint32_t"
+        8 -> "uint64_t"
+        _ -> "uint8_t"
+
+ptrWidth : Types -> U64
+ptrWidth = \types ->
+    when (Types.target types).architecture is
+        Aarch64 | X86x64 -> 8
+        Aarch32 | Wasm32 | X86x32 -> 4
+
+roundUpToAlignment = \width, alignment ->
+    when alignment is
+        0 -> width
+        1 -> width
+        _ ->
+            if width % alignment > 0 then
+                width + alignment - (width % alignment)
+            else
+                width
+
+archName = \arch ->
+    when arch is
+        Aarch32 ->
+            "arm"
+
+        Aarch64 ->
+            "aarch64"
+
+        Wasm32 ->
+            "wasm32"
+
+        X86x32 ->
+            "x86"
+
+        X86x64 ->
+            "x86_64"
+
+archCondition = \arch ->
+    when arch is
+        Aarch32 ->
+            "defined(__arm__) || defined(_M_ARM)"
+
+        Aarch64 ->
+            "defined(__aarch64__) || defined(_M_ARM64)"
+
+        Wasm32 ->
+            "defined(__wasm32__)"
+
+        X86x32 ->
+            "defined(__i386__) || defined(_M_IX86)"
+
+        X86x64 ->
+            "defined(__x86_64__) || defined(_M_X64)"
+
+fileHeader =
+    """
+    // ⚠️ GENERATED CODE ⚠️ - this entire file was generated by the `roc glue` CLI command
+    //
+    // The `_new_` constructors take ownership of their arguments, and the `_get_` accessors
+    // borrow from the value they are given, so neither changes any reference counts.
+    """
+
+indent = "    "
+
+reservedKeywords = Set.fromList [
+    "auto",
+    "bool",
+    "break",
+    "case",
+    "char",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extern",
+    "false",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "register",
+    "restrict",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "true",
+    "typedef",
+    "union",
+    "unsigned",
+    "void",
+    "volatile",
+    "while",
+]
+
+escapeKW = \input ->
+    # C has no raw identifiers, so add an underscore to names that would be a syntax error.
+    if Set.contains reservedKeywords input then
+        "$(input)_"
+    else
+        input
+
+isUnit : Shape -> Bool
+isUnit = \shape ->
+    when shape is
+        Unit -> Bool.true
+        _ -> Bool.false
+
+## The arguments that actually get passed; `{}` args aren't FFI-safe, and nothing
+## would get passed for them anyway.
+nonUnitArgs : Types, List TypeId -> List { id : TypeId, index : U64 }
+nonUnitArgs = \types, args ->
+    List.walkWithIndex args [] \accum, argId, index ->
+        if isUnit (Types.shape types argId) then
+            accum
+        else
+            List.append accum { id: argId, index }
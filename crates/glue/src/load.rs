@@ -17,7 +17,7 @@ use roc_mono::ir::{generate_glue_procs, CrashTag, GlueProc, OptLevel};
 use roc_mono::layout::{GlobalLayoutInterner, LayoutCache, LayoutInterner};
 use roc_packaging::cache::{self, RocCacheDir};
 use roc_reporting::report::{RenderTarget, DEFAULT_PALETTE};
-use roc_target::{Architecture, Target, TargetFromTripleError::TripleUnsupported};
+use roc_target::{Architecture, Target, TargetFromTripleError::TripleUnsupported, WasmFeatures};
 use roc_types::subs::{Subs, Variable};
 use std::fs::File;
 use std::io::{self, ErrorKind, Write};
@@ -59,6 +59,7 @@ pub fn generate(
                 emit_debug_info: false,
                 emit_llvm_ir: false,
                 fuzz: false,
+                wasm_features: WasmFeatures::default(),
             };
 
             let load_config = standard_load_config(
@@ -0,0 +1,278 @@
+// ⚠️ GENERATED CODE ⚠️ - this entire file was generated by the `roc glue` CLI command
+//
+// Definitions of Roc's builtin types, and helpers for their reference counts.
+
+#ifndef ROC_STD_H
+#define ROC_STD_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <string.h>
+
+#ifdef __cplusplus
+#define ROC_STATIC_ASSERT static_assert
+#define ROC_ALIGNOF alignof
+extern "C" {
+#else
+#define ROC_STATIC_ASSERT _Static_assert
+#define ROC_ALIGNOF _Alignof
+#endif
+
+// The host provides these to the app, and the helpers below use them too.
+void *roc_alloc(size_t size, unsigned int alignment);
+void roc_dealloc(void *ptr, unsigned int alignment);
+
+#if defined(__SIZEOF_INT128__)
+__extension__ typedef __int128 RocI128;
+__extension__ typedef unsigned __int128 RocU128;
+#else
+typedef struct RocI128 {
+    uint64_t lo;
+    int64_t hi;
+} RocI128;
+
+typedef struct RocU128 {
+    uint64_t lo;
+    uint64_t hi;
+} RocU128;
+#endif
+
+// A fixed-point decimal, which stores its value multiplied by 10^18.
+typedef RocI128 RocDec;
+
+// Reference counts live in the word right before the data they count.
+// Readonly values, like string literals in the app, are never freed.
+#define ROC_REFCOUNT_READONLY ((intptr_t)0)
+#define ROC_REFCOUNT_ONE INTPTR_MIN
+
+#define ROC_SEAMLESS_SLICE_BIT ((size_t)1 << (sizeof(size_t) * 8 - 1))
+
+static inline void roc_refcount_inc(intptr_t *refcount) {
+    if (*refcount != ROC_REFCOUNT_READONLY) {
+        *refcount += 1;
+    }
+}
+
+// Returns true if that was the last reference, so the allocation must be freed.
+static inline bool roc_refcount_dec(intptr_t *refcount) {
+    if (*refcount == ROC_REFCOUNT_READONLY) {
+        return false;
+    }
+
+    if (*refcount == ROC_REFCOUNT_ONE) {
+        return true;
+    }
+
+    *refcount -= 1;
+    return false;
+}
+
+// The number of bytes between the start of an allocation and the data in it.
+static inline size_t roc_allocation_header(unsigned int alignment) {
+    return alignment > sizeof(size_t) ? alignment : sizeof(size_t);
+}
+
+// Allocates `size` bytes behind a reference count of one, and returns a pointer to those bytes.
+static inline void *roc_alloc_refcounted(size_t size, unsigned int alignment) {
+    size_t header = roc_allocation_header(alignment);
+    unsigned char *data = (unsigned char *)roc_alloc(header + size, (unsigned int)header) + header;
+
+    ((intptr_t *)data)[-1] = ROC_REFCOUNT_ONE;
+
+    return data;
+}
+
+static inline void roc_incref(void *data) {
+    if (data != NULL) {
+        roc_refcount_inc((intptr_t *)data - 1);
+    }
+}
+
+// Frees the allocation once nothing refers to `data` anymore.
+// This does not decrement anything that `data` itself refers to.
+static inline void roc_decref(void *data, unsigned int alignment) {
+    if (data != NULL && roc_refcount_dec((intptr_t *)data - 1)) {
+        size_t header = roc_allocation_header(alignment);
+
+        roc_dealloc((unsigned char *)data - header, (unsigned int)header);
+    }
+}
+
+// Strings shorter than a RocStr are stored inside it, with their length in the last byte.
+typedef struct RocStr {
+    uint8_t *bytes;
+    size_t length;
+    size_t capacity_or_alloc_ptr;
+} RocStr;
+
+static inline bool roc_str_is_small(const RocStr *str) {
+    return (intptr_t)str->capacity_or_alloc_ptr < 0;
+}
+
+static inline bool roc_str_is_seamless_slice(const RocStr *str) {
+    return !roc_str_is_small(str) && (intptr_t)str->length < 0;
+}
+
+static inline size_t roc_str_len(const RocStr *str) {
+    if (roc_str_is_small(str)) {
+        return ((const uint8_t *)str)[sizeof(RocStr) - 1] & 0x7F;
+    }
+
+    return str->length & ~ROC_SEAMLESS_SLICE_BIT;
+}
+
+static inline const uint8_t *roc_str_bytes(const RocStr *str) {
+    return roc_str_is_small(str) ? (const uint8_t *)str : str->bytes;
+}
+
+static inline RocStr roc_str_from_bytes(const uint8_t *bytes, size_t length) {
+    RocStr str;
+    memset(&str, 0, sizeof(str));
+
+    if (length < sizeof(RocStr)) {
+        memcpy(&str, bytes, length);
+        ((uint8_t *)&str)[sizeof(RocStr) - 1] = (uint8_t)length | 0x80;
+    } else {
+        str.bytes = (uint8_t *)roc_alloc_refcounted(length, ROC_ALIGNOF(size_t));
+        str.length = length;
+        str.capacity_or_alloc_ptr = length;
+        memcpy(str.bytes, bytes, length);
+    }
+
+    return str;
+}
+
+static inline RocStr roc_str_from_cstr(const char *cstr) {
+    return roc_str_from_bytes((const uint8_t *)cstr, strlen(cstr));
+}
+
+// Small and empty strings have no reference count, so this returns NULL for them.
+static inline intptr_t *roc_str_refcount(const RocStr *str) {
+    if (roc_str_is_small(str)) {
+        return NULL;
+    }
+
+    if (roc_str_is_seamless_slice(str)) {
+        return (intptr_t *)(str->capacity_or_alloc_ptr << 1) - 1;
+    }
+
+    if (str->capacity_or_alloc_ptr == 0) {
+        return NULL;
+    }
+
+    return (intptr_t *)str->bytes - 1;
+}
+
+static inline void roc_str_incref(const RocStr *str) {
+    intptr_t *refcount = roc_str_refcount(str);
+
+    if (refcount != NULL) {
+        roc_refcount_inc(refcount);
+    }
+}
+
+static inline void roc_str_decref(const RocStr *str) {
+    intptr_t *refcount = roc_str_refcount(str);
+
+    if (refcount != NULL && roc_refcount_dec(refcount)) {
+        roc_dealloc(refcount, (unsigned int)sizeof(size_t));
+    }
+}
+
+// A list of any element type; cast `elements` to a pointer to the element type.
+typedef struct RocList {
+    void *elements;
+    size_t length;
+    size_t capacity_or_alloc_ptr;
+} RocList;
+
+static inline size_t roc_list_len(const RocList *list) {
+    return list->length & ~ROC_SEAMLESS_SLICE_BIT;
+}
+
+static inline bool roc_list_is_seamless_slice(const RocList *list) {
+    return (intptr_t)(list->length | list->capacity_or_alloc_ptr) < 0;
+}
+
+// Returns an empty list with room for `capacity` elements.
+static inline RocList roc_list_with_capacity(size_t capacity, size_t element_size, unsigned int alignment) {
+    RocList list;
+    memset(&list, 0, sizeof(list));
+
+    if (capacity > 0) {
+        list.elements = roc_alloc_refcounted(capacity * element_size, alignment);
+        list.capacity_or_alloc_ptr = capacity;
+    }
+
+    return list;
+}
+
+// Empty lists have no reference count, so this returns NULL for them.
+static inline intptr_t *roc_list_refcount(const RocList *list) {
+    if (roc_list_is_seamless_slice(list)) {
+        return (intptr_t *)(list->capacity_or_alloc_ptr << 1) - 1;
+    }
+
+    if (list->elements == NULL) {
+        return NULL;
+    }
+
+    return (intptr_t *)list->elements - 1;
+}
+
+static inline void roc_list_incref(const RocList *list) {
+    intptr_t *refcount = roc_list_refcount(list);
+
+    if (refcount != NULL) {
+        roc_refcount_inc(refcount);
+    }
+}
+
+// Whether this is the only reference to the list, so its elements can be changed in place.
+static inline bool roc_list_is_unique(const RocList *list) {
+    intptr_t *refcount = roc_list_refcount(list);
+
+    return refcount == NULL || *refcount == ROC_REFCOUNT_ONE;
+}
+
+// `alignment` is the alignment of the element type. This only frees the list itself, so if the
+// elements are reference counted too and the list is unique, decrement them first.
+static inline void roc_list_decref(const RocList *list, unsigned int alignment) {
+    intptr_t *refcount = roc_list_refcount(list);
+
+    if (refcount != NULL && roc_refcount_dec(refcount)) {
+        size_t header = roc_allocation_header(alignment);
+
+        roc_dealloc((unsigned char *)(refcount + 1) - header, (unsigned int)header);
+    }
+}
+
+// A pointer to a reference-counted value; use roc_incref and roc_decref on it.
+typedef void *RocBox;
+
+static inline RocBox roc_box_new(const void *value, size_t size, unsigned int alignment) {
+    RocBox box = roc_alloc_refcounted(size, alignment);
+    memcpy(box, value, size);
+
+    return box;
+}
+
+// Dict and Set are best read from Roc; these definitions only give them the right layout.
+typedef struct RocDict {
+    RocList buckets;
+    RocList data;
+    uint64_t max_bucket_capacity;
+    float max_load_factor;
+    uint8_t shifts;
+} RocDict;
+
+typedef struct RocSet {
+    RocDict dict;
+} RocSet;
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif // ROC_STD_H
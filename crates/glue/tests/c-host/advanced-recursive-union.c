@@ -0,0 +1,35 @@
+#include "host.h"
+
+static void print_tool(const Tool *tool) {
+    if (Tool_is_SystemTool(tool)) {
+        printf("Tool_SystemTool(R4 { name: ");
+        print_str(&Tool_get_SystemTool(tool)->name);
+        printf(", num: %u })", (unsigned)Tool_get_SystemTool(tool)->num);
+    } else {
+        printf("Tool_FromJob(...)");
+    }
+}
+
+int main(void) {
+    Rbt rbt = mainForHost();
+
+    printf("rbt was: Rbt { default: ");
+
+    if (Job_is_Job(&rbt.default_)) {
+        const Job_Job *job = Job_get_Job(&rbt.default_);
+
+        printf("Job_Job(R1 { command: Command_Command(R2 { tool: ");
+        print_tool(&job->f0.command.f0.tool);
+        printf(" }), inputFiles: ");
+        print_str_list(&job->f0.inputFiles);
+        printf(" })");
+    } else {
+        printf("Job_Foo(");
+        print_str(&Job_get_Foo(&rbt.default_)->f0);
+        printf(")");
+    }
+
+    printf(" }\n");
+
+    return 0;
+}
@@ -0,0 +1,7 @@
+#include "host.h"
+
+int main(void) {
+    printf("Answer was: %lld\n", (long long)mainForHost(42));
+
+    return 0;
+}
@@ -0,0 +1,9 @@
+#include "host.h"
+
+int main(void) {
+    MyRcd record = mainForHost();
+
+    printf("Record was: MyRcd { b: %llu, a: %llu }\n", (unsigned long long)record.b, (unsigned long long)record.a);
+
+    return 0;
+}
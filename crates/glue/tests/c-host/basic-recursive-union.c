@@ -0,0 +1,45 @@
+#include "host.h"
+
+static void print_expr(const Expr *expr) {
+    if (Expr_is_String(expr)) {
+        printf("Expr_String(");
+        print_str(&Expr_get_String(expr)->f0);
+        printf(")");
+    } else {
+        const Expr_Concat *concat = Expr_get_Concat(expr);
+
+        printf("Expr_Concat(");
+        print_expr(&concat->f0);
+        printf(", ");
+        print_expr(&concat->f1);
+        printf(")");
+    }
+}
+
+static Expr string(const char *str) {
+    Expr_String payload;
+    payload.f0 = roc_str_from_cstr(str);
+
+    return Expr_new_String(payload);
+}
+
+int main(void) {
+    Expr tag_union = mainForHost();
+
+    Expr_Concat payload;
+    payload.f0 = string("Hello, ");
+    payload.f1 = string("World!");
+
+    Expr concat = Expr_new_Concat(payload);
+    Expr str = string("this is a test");
+
+    printf("tag_union was: ");
+    print_expr(&tag_union);
+    printf("\n`Concat (String \"Hello, \") (String \"World!\")` is: ");
+    print_expr(&concat);
+    printf("\n`String \"this is a test\"` is: ");
+    print_expr(&str);
+    printf("\n");
+
+    return 0;
+}
@@ -0,0 +1,12 @@
+#include "host.h"
+
+int main(void) {
+    void *closure_data = malloc((size_t)roc__mainForHost_1_exposed_size());
+    mainForHost_Fn thunk = mainForHost(closure_data, 42);
+
+    printf("Answer was: %lld\n", (long long)mainForHost_Fn_call(&thunk));
+
+    free(closure_data);
+
+    return 0;
+}
@@ -0,0 +1,23 @@
+#include "host.h"
+
+static const char *my_enum_name(MyEnum tag) {
+    switch (tag) {
+    case MyEnum_Foo:
+        return "MyEnum_Foo";
+    case MyEnum_Bar:
+        return "MyEnum_Bar";
+    case MyEnum_Baz:
+        return "MyEnum_Baz";
+    default:
+        return "an unknown MyEnum";
+    }
+}
+
+int main(void) {
+    MyEnum tag_union = mainForHost();
+
+    printf("tag_union was: %s, Bar is: %s, Baz is: %s\n", my_enum_name(tag_union), my_enum_name(MyEnum_Bar),
+           my_enum_name(MyEnum_Baz));
+
+    return 0;
+}
@@ -0,0 +1,67 @@
+// Included by every fixture's host. It defines what the Roc app needs from its host,
+// and a helper for printing Roc strings.
+// The hosts exit right after printing, so they don't release what they allocate.
+
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "roc_app.h"
+
+void *roc_alloc(size_t size, unsigned int alignment) {
+    (void)alignment;
+
+    return malloc(size);
+}
+
+void *roc_realloc(void *ptr, size_t new_size, size_t old_size, unsigned int alignment) {
+    (void)old_size;
+    (void)alignment;
+
+    return realloc(ptr, new_size);
+}
+
+void roc_dealloc(void *ptr, unsigned int alignment) {
+    (void)alignment;
+
+    free(ptr);
+}
+
+void roc_panic(const RocStr *msg, unsigned int tag_id) {
+    (void)tag_id;
+
+    fprintf(stderr, "Roc crashed with: %.*s\n", (int)roc_str_len(msg), (const char *)roc_str_bytes(msg));
+    exit(1);
+}
+
+void roc_dbg(const RocStr *loc, const RocStr *msg, const RocStr *src) {
+    (void)src;
+
+    fprintf(stderr, "[%.*s] %.*s\n", (int)roc_str_len(loc), (const char *)roc_str_bytes(loc), (int)roc_str_len(msg),
+            (const char *)roc_str_bytes(msg));
+}
+
+void *roc_memset(void *str, int c, size_t n) {
+    return memset(str, c, n);
+}
+
+// Prints a string in quotes, like Roc's `Inspect` would.
+static inline void print_str(const RocStr *str) {
+    printf("\"%.*s\"", (int)roc_str_len(str), (const char *)roc_str_bytes(str));
+}
+
+// Prints a `List Str`.
+static inline void print_str_list(const RocList *list) {
+    const RocStr *strs = (const RocStr *)list->elements;
+
+    printf("[");
+
+    for (size_t i = 0; i < roc_list_len(list); i++) {
+        if (i > 0) {
+            printf(", ");
+        }
+
+        print_str(&strs[i]);
+    }
+
+    printf("]");
+}
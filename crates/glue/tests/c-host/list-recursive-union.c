@@ -0,0 +1,34 @@
+#include "host.h"
+
+int main(void) {
+    Rbt rbt = mainForHost();
+
+    printf("rbt was: Rbt { default: ");
+
+    if (Job_is_Job(&rbt.default_)) {
+        const Job_Job *job = Job_get_Job(&rbt.default_);
+
+        printf("Job_Job(R1 { command: Command_Command(R2 { args: ");
+        print_str_list(&job->f0.command.f0.args);
+        printf(", tool: Tool_SystemTool(R3 { name: ");
+        print_str(&job->f0.command.f0.tool.f0.name);
+        printf(" }) }), inputFiles: ");
+        print_str_list(&job->f0.inputFiles);
+        printf(", job: [");
+
+        // Only the number of nested jobs gets printed.
+        if (roc_list_len(&job->f0.job) > 0) {
+            printf("%zu jobs", roc_list_len(&job->f0.job));
+        }
+
+        printf("] })");
+    } else {
+        printf("Job_Blah(");
+        print_str(&Job_get_Blah(&rbt.default_)->f0);
+        printf(")");
+    }
+
+    printf(" }\n");
+
+    return 0;
+}
@@ -0,0 +1,13 @@
+#include "host.h"
+
+int main(void) {
+    Combined combined = mainForHost();
+
+    printf("combined was: Combined { s1: DepStr1_S(");
+    print_str(&combined.s1.f0);
+    printf("), s2: DepStr2_R(");
+    print_str(&combined.s2.f0);
+    printf(") }\n");
+
+    return 0;
+}
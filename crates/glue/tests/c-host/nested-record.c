@@ -0,0 +1,22 @@
+#include "host.h"
+
+int main(void) {
+    Outer outer = mainForHost();
+    const uint8_t *z = (const uint8_t *)outer.z.elements;
+
+    printf("Record was: Outer { y: ");
+    print_str(&outer.y);
+    printf(", z: [");
+
+    for (size_t i = 0; i < roc_list_len(&outer.z); i++) {
+        if (i > 0) {
+            printf(", ");
+        }
+
+        printf("%u", (unsigned)z[i]);
+    }
+
+    printf("], x: Inner { b: %.1f, a: %u } }\n", (double)outer.x.b, (unsigned)outer.x.a);
+
+    return 0;
+}
@@ -0,0 +1,38 @@
+#include "host.h"
+
+static void print_tree(const StrRoseTree *tree) {
+    const StrRoseTree_Tree *payload = StrRoseTree_get_Tree(tree);
+    const StrRoseTree *children = (const StrRoseTree *)payload->f1.elements;
+
+    printf("StrRoseTree_Tree(");
+    print_str(&payload->f0);
+    printf(", [");
+
+    for (size_t i = 0; i < roc_list_len(&payload->f1); i++) {
+        if (i > 0) {
+            printf(", ");
+        }
+
+        print_tree(&children[i]);
+    }
+
+    printf("])");
+}
+
+int main(void) {
+    StrRoseTree tag_union = mainForHost();
+
+    StrRoseTree_Tree payload;
+    payload.f0 = roc_str_from_cstr("foo");
+    payload.f1 = roc_list_with_capacity(0, sizeof(StrRoseTree), ROC_ALIGNOF(StrRoseTree));
+
+    StrRoseTree tree = StrRoseTree_new_Tree(payload);
+
+    printf("tag_union was: ");
+    print_tree(&tag_union);
+    printf("\nTree \"foo\" [] is: ");
+    print_tree(&tree);
+    printf("\n");
+
+    return 0;
+}
@@ -0,0 +1,36 @@
+#include "host.h"
+
+static void print_list(const StrConsList *list) {
+    if (StrConsList_is_Nil(list)) {
+        printf("StrConsList_Nil");
+    } else {
+        const StrConsList_Cons *cons = StrConsList_get_Cons(list);
+
+        printf("StrConsList_Cons(");
+        print_str(&cons->f0);
+        printf(", ");
+        print_list(&cons->f1);
+        printf(")");
+    }
+}
+
+int main(void) {
+    StrConsList tag_union = mainForHost();
+
+    StrConsList_Cons payload;
+    payload.f0 = roc_str_from_cstr("small str");
+    payload.f1 = StrConsList_new_Nil();
+
+    StrConsList cons = StrConsList_new_Cons(payload);
+    StrConsList nil = StrConsList_new_Nil();
+
+    printf("tag_union was: ");
+    print_list(&tag_union);
+    printf("\n`Cons \"small str\" Nil` is: ");
+    print_list(&cons);
+    printf("\n`Nil` is: ");
+    print_list(&nil);
+    printf("\n");
+
+    return 0;
+}
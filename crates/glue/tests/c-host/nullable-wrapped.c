@@ -0,0 +1,56 @@
+#include "host.h"
+
+static void print_tree(const StrFingerTree *tree) {
+    if (StrFingerTree_is_Empty(tree)) {
+        printf("StrFingerTree_Empty");
+    } else if (StrFingerTree_is_Single(tree)) {
+        printf("StrFingerTree_Single(");
+        print_str(&StrFingerTree_get_Single(tree)->f0);
+        printf(")");
+    } else {
+        const StrFingerTree_More *more = StrFingerTree_get_More(tree);
+
+        printf("StrFingerTree_More(");
+        print_str(&more->f0);
+        printf(", ");
+        print_tree(&more->f1);
+        printf(")");
+    }
+}
+
+static StrFingerTree single(const char *str) {
+    StrFingerTree_Single payload;
+    payload.f0 = roc_str_from_cstr(str);
+
+    return StrFingerTree_new_Single(payload);
+}
+
+static StrFingerTree more(const char *str, StrFingerTree rest) {
+    StrFingerTree_More payload;
+    payload.f0 = roc_str_from_cstr(str);
+    payload.f1 = rest;
+
+    return StrFingerTree_new_More(payload);
+}
+
+int main(void) {
+    StrFingerTree tag_union = mainForHost();
+    StrFingerTree more_single = more("small str", single("other str"));
+    StrFingerTree more_empty = more("small str", StrFingerTree_new_Empty());
+    StrFingerTree single_tree = single("small str");
+    StrFingerTree empty = StrFingerTree_new_Empty();
+
+    printf("tag_union was: ");
+    print_tree(&tag_union);
+    printf("\n`More \"small str\" (Single \"other str\")` is: ");
+    print_tree(&more_single);
+    printf("\n`More \"small str\" Empty` is: ");
+    print_tree(&more_empty);
+    printf("\n`Single \"small str\"` is: ");
+    print_tree(&single_tree);
+    printf("\n`Empty` is: ");
+    print_tree(&empty);
+    printf("\n");
+
+    return 0;
+}
@@ -0,0 +1,22 @@
+#include "host.h"
+
+// `[Some Str, None]` has no name in the platform, so the glue calls it `U1`.
+static void print_option(const U1 *option) {
+    if (U1_is_Some(option)) {
+        printf("Answer was: Some ");
+        print_str(U1_get_Some(option));
+        printf("\n");
+    } else {
+        printf("Answer was: None\n");
+    }
+}
+
+int main(void) {
+    U1 string = mainForHost(true);
+    U1 none = mainForHost(false);
+
+    print_option(&string);
+    print_option(&none);
+
+    return 0;
+}
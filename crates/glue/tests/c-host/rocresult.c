@@ -0,0 +1,21 @@
+#include "host.h"
+
+static void print_result(const RocResult_RocStr_int32_t *result) {
+    if (RocResult_RocStr_int32_t_is_Ok(result)) {
+        printf("Answer was: Ok ");
+        print_str(RocResult_RocStr_int32_t_get_Ok(result));
+        printf("\n");
+    } else {
+        printf("Answer was: Err %d\n", (int)*RocResult_RocStr_int32_t_get_Err(result));
+    }
+}
+
+int main(void) {
+    RocResult_RocStr_int32_t string = mainForHost(true);
+    RocResult_RocStr_int32_t integer = mainForHost(false);
+
+    print_result(&string);
+    print_result(&integer);
+
+    return 0;
+}
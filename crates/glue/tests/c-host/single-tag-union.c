@@ -0,0 +1,15 @@
+#include "host.h"
+
+int main(void) {
+    // There is nothing to read from a single tag without a payload,
+    // so this only checks that the entry point and constructor exist.
+    SingleTagUnion tag_union = mainForHost();
+    SingleTagUnion one_tag = SingleTagUnion_new_OneTag();
+
+    (void)tag_union;
+    (void)one_tag;
+
+    printf("tag_union was: SingleTagUnion_OneTag\n");
+
+    return 0;
+}
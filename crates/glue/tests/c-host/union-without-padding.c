@@ -0,0 +1,45 @@
+#include "host.h"
+
+static void print_non_recursive(const NonRecursive *tag_union) {
+    switch (NonRecursive_discriminant(tag_union)) {
+    case discriminant_NonRecursive_Foo:
+        printf("NonRecursive_Foo(");
+        print_str(NonRecursive_get_Foo(tag_union));
+        printf(")");
+        break;
+    case discriminant_NonRecursive_Bar:
+        printf("NonRecursive_Bar(%lld)", (long long)*NonRecursive_get_Bar(tag_union));
+        break;
+    case discriminant_NonRecursive_Baz:
+        printf("NonRecursive_Baz");
+        break;
+    case discriminant_NonRecursive_Blah:
+        printf("NonRecursive_Blah(%d)", (int)*NonRecursive_get_Blah(tag_union));
+        break;
+    default:
+        printf("an unknown NonRecursive");
+        break;
+    }
+}
+
+int main(void) {
+    NonRecursive tag_union = mainForHost();
+    NonRecursive small_str = NonRecursive_new_Foo(roc_str_from_cstr("small str"));
+    NonRecursive bar = NonRecursive_new_Bar(123);
+    NonRecursive baz = NonRecursive_new_Baz();
+    NonRecursive blah = NonRecursive_new_Blah(456);
+
+    printf("tag_union was: ");
+    print_non_recursive(&tag_union);
+    printf("\n`Foo \"small str\"` is: ");
+    print_non_recursive(&small_str);
+    printf("\n`Bar 123` is: ");
+    print_non_recursive(&bar);
+    printf("\n`Baz` is: ");
+    print_non_recursive(&baz);
+    printf("\n`Blah 456` is: ");
+    print_non_recursive(&blah);
+    printf("\n");
+
+    return 0;
+}
@@ -0,0 +1,215 @@
+mod helpers;
+
+#[cfg(test)]
+mod c_glue {
+    use crate::helpers::{fixtures_dir, root_dir};
+    use cli_utils::helpers::{has_error, run_glue, run_roc, Out};
+    use std::fs;
+    use std::path::Path;
+    use std::process::Command;
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    const TEST_LEGACY_LINKER: bool = true;
+
+    // Surgical linker currently only supports linux x86_64,
+    // so we're always testing the legacy linker on other targets.
+    #[cfg(not(all(target_os = "linux", target_arch = "x86_64")))]
+    const TEST_LEGACY_LINKER: bool = false;
+
+    /// Generates C glue for each of the given fixtures, and builds the fixture's app
+    /// with its C host from c-host/. The host calls the generated entry points and accessors,
+    /// and prints what it got, so the stdout check verifies the layouts end to end.
+    /// The generated header also asserts that every type has the size, alignment,
+    /// and discriminant offset that Roc gives it.
+    macro_rules! fixtures {
+        ($($test_name:ident:$fixture_dir:expr => $ends_with:expr,)+) => {
+            $(
+                #[test]
+                #[allow(non_snake_case)]
+                fn $test_name() {
+                    let platform_dir = tempfile::tempdir().unwrap();
+
+                    build_c_host_for($fixture_dir, platform_dir.path());
+
+                    let validate = |args: &[&str]| {
+                        let out = run_app(&platform_dir.path().join("app.roc"), args.iter().copied());
+
+                        assert!(out.status.success());
+                        let ignorable = "🔨 Rebuilding platform...\n";
+                        let stderr = out.stderr.replacen(ignorable, "", 1);
+                        assert_eq!(stderr, "");
+                        assert!(
+                            out.stdout.ends_with($ends_with),
+                            "Unexpected stdout ending\n\n  expected:\n\n    {}\n\n  but stdout was:\n\n    {}",
+                            $ends_with,
+                            out.stdout
+                        );
+                    };
+
+                    validate(&[]);
+
+                    if TEST_LEGACY_LINKER {
+                        validate(&["--linker=legacy"]);
+                    }
+                }
+            )*
+        }
+    }
+
+    fixtures! {
+        basic_record:"basic-record" => "Record was: MyRcd { b: 42, a: 1995 }\n",
+        nested_record:"nested-record" => "Record was: Outer { y: \"foo\", z: [1, 2], x: Inner { b: 24.0, a: 5 } }\n",
+        enumeration:"enumeration" => "tag_union was: MyEnum_Foo, Bar is: MyEnum_Bar, Baz is: MyEnum_Baz\n",
+        single_tag_union:"single-tag-union" => "tag_union was: SingleTagUnion_OneTag\n",
+        union_with_padding:"union-with-padding" => indoc::indoc!(r#"
+            tag_union was: NonRecursive_Foo("This is a test")
+            `Foo "small str"` is: NonRecursive_Foo("small str")
+            `Foo "A long enough string to not be small"` is: NonRecursive_Foo("A long enough string to not be small")
+            `Bar 123` is: NonRecursive_Bar(123)
+            `Baz` is: NonRecursive_Baz
+            `Blah 456` is: NonRecursive_Blah(456)
+        "#),
+        union_without_padding:"union-without-padding" => indoc::indoc!(r#"
+            tag_union was: NonRecursive_Foo("This is a test")
+            `Foo "small str"` is: NonRecursive_Foo("small str")
+            `Bar 123` is: NonRecursive_Bar(123)
+            `Baz` is: NonRecursive_Baz
+            `Blah 456` is: NonRecursive_Blah(456)
+        "#),
+        nullable_wrapped:"nullable-wrapped" => indoc::indoc!(r#"
+            tag_union was: StrFingerTree_More("foo", StrFingerTree_More("bar", StrFingerTree_Empty))
+            `More "small str" (Single "other str")` is: StrFingerTree_More("small str", StrFingerTree_Single("other str"))
+            `More "small str" Empty` is: StrFingerTree_More("small str", StrFingerTree_Empty)
+            `Single "small str"` is: StrFingerTree_Single("small str")
+            `Empty` is: StrFingerTree_Empty
+        "#),
+        nullable_unwrapped:"nullable-unwrapped" => indoc::indoc!(r#"
+            tag_union was: StrConsList_Cons("World!", StrConsList_Cons("Hello ", StrConsList_Nil))
+            `Cons "small str" Nil` is: StrConsList_Cons("small str", StrConsList_Nil)
+            `Nil` is: StrConsList_Nil
+        "#),
+        nonnullable_unwrapped:"nonnullable-unwrapped" => indoc::indoc!(r#"
+            tag_union was: StrRoseTree_Tree("root", [StrRoseTree_Tree("leaf1", []), StrRoseTree_Tree("leaf2", [])])
+            Tree "foo" [] is: StrRoseTree_Tree("foo", [])
+        "#),
+        basic_recursive_union:"basic-recursive-union" => indoc::indoc!(r#"
+            tag_union was: Expr_Concat(Expr_String("Hello, "), Expr_String("World!"))
+            `Concat (String "Hello, ") (String "World!")` is: Expr_Concat(Expr_String("Hello, "), Expr_String("World!"))
+            `String "this is a test"` is: Expr_String("this is a test")
+        "#),
+        advanced_recursive_union:"advanced-recursive-union" => indoc::indoc!(r#"
+            rbt was: Rbt { default: Job_Job(R1 { command: Command_Command(R2 { tool: Tool_SystemTool(R4 { name: "test", num: 42 }) }), inputFiles: ["foo"] }) }
+        "#),
+        list_recursive_union:"list-recursive-union" => indoc::indoc!(r#"
+            rbt was: Rbt { default: Job_Job(R1 { command: Command_Command(R2 { args: [], tool: Tool_SystemTool(R3 { name: "test" }) }), inputFiles: ["foo"], job: [] }) }
+        "#),
+        multiple_modules:"multiple-modules" => indoc::indoc!(r#"
+            combined was: Combined { s1: DepStr1_S("hello"), s2: DepStr2_R("world") }
+        "#),
+        arguments:"arguments" => "Answer was: 84\n",
+        closures:"closures" => "Answer was: 672\n",
+        rocresult:"rocresult" => indoc::indoc!(r#"
+            Answer was: Ok "Hello World!"
+            Answer was: Err 42
+        "#),
+        option:"option" => indoc::indoc!(r#"
+            Answer was: Some "Hello World!"
+            Answer was: None
+        "#),
+    }
+
+    /// Sets up `platform_dir` with the fixture's Roc modules, its C host as host.c,
+    /// and freshly generated C glue, and checks that the host compiles as both C and C++.
+    fn build_c_host_for(fixture_name: &str, platform_dir: &Path) {
+        for entry in fs::read_dir(fixtures_dir(fixture_name)).unwrap() {
+            let path = entry.unwrap().path();
+
+            if path.extension().is_some_and(|ext| ext == "roc") {
+                fs::copy(&path, platform_dir.join(path.file_name().unwrap())).unwrap();
+            }
+        }
+
+        let c_host_dir = root_dir()
+            .join("crates")
+            .join("glue")
+            .join("tests")
+            .join("c-host");
+
+        fs::copy(c_host_dir.join("host.h"), platform_dir.join("host.h")).unwrap();
+        fs::copy(
+            c_host_dir.join(format!("{fixture_name}.c")),
+            platform_dir.join("host.c"),
+        )
+        .unwrap();
+
+        let c_glue_spec = root_dir()
+            .join("crates")
+            .join("glue")
+            .join("src")
+            .join("CGlue.roc");
+
+        let glue_out = run_glue([
+            "glue".to_string(),
+            c_glue_spec.to_str().unwrap().to_string(),
+            platform_dir.to_str().unwrap().to_string(),
+            platform_dir
+                .join("platform.roc")
+                .to_str()
+                .unwrap()
+                .to_string(),
+        ]);
+
+        if has_error(&glue_out.stderr) {
+            panic!(
+                "`roc glue` command had unexpected stderr: {}",
+                glue_out.stderr
+            );
+        }
+
+        assert!(glue_out.status.success(), "bad status {glue_out:?}");
+
+        // The header has to work in both C and C++ hosts.
+        let object_dir = tempfile::tempdir().unwrap();
+
+        for (language, standard) in [("c", "-std=c11"), ("c++", "-std=c++11")] {
+            let output = Command::new("cc")
+                .args(["-x", language, standard, "-Wall", "-Wextra", "-Werror"])
+                .arg("-I")
+                .arg(platform_dir)
+                .arg("-c")
+                .arg(platform_dir.join("host.c"))
+                .arg("-o")
+                .arg(object_dir.path().join("host.o"))
+                .output()
+                .unwrap();
+
+            assert!(
+                output.status.success(),
+                "the generated {language} glue did not compile:\n{}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+    }
+
+    fn run_app<'a, I: IntoIterator<Item = &'a str>>(app_file: &Path, args: I) -> Out {
+        let compile_out = run_roc(
+            // converting these all to String avoids lifetime issues
+            args.into_iter()
+                .map(|arg| arg.to_string())
+                .chain([app_file.to_str().unwrap().to_string()]),
+            &[],
+            &[],
+        );
+
+        if has_error(&compile_out.stderr) {
+            panic!(
+                "`roc` command had unexpected stderr: {}",
+                compile_out.stderr
+            );
+        }
+
+        assert!(compile_out.status.success(), "bad status {compile_out:?}");
+
+        compile_out
+    }
+}
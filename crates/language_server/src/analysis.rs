@@ -9,7 +9,7 @@ use bumpalo::Bump;
 use parking_lot::Mutex;
 use roc_can::{abilities::AbilitiesStore, expr::Declarations};
 use roc_collections::{MutMap, MutSet, VecMap};
-use roc_load::{docs::ModuleDocumentation, CheckedModule, LoadedModule};
+use roc_load::{docs::ModuleDocumentation, AnalysisCache, CheckedModule, LoadedModule};
 use roc_module::symbol::{Interns, ModuleId, Symbol};
 use roc_packaging::cache::{self, RocCacheDir};
 use roc_region::all::LineInfo;
@@ -17,16 +17,21 @@ use roc_reporting::report::RocDocAllocator;
 use roc_solve_problem::TypeError;
 use roc_types::subs::{Subs, Variable};
 
-use tower_lsp::lsp_types::{Diagnostic, SemanticTokenType, Url};
+use tower_lsp::lsp_types::{CodeAction, Diagnostic, SemanticTokenType, Url};
 
 mod analysed_doc;
 mod completion;
+mod inlay_hints;
 mod parse_ast;
+mod refactor;
+mod references;
 mod semantic_tokens;
+mod signature_help;
+mod symbols;
 mod tokens;
 mod utils;
 
-use crate::convert::diag::{IntoLspDiagnostic, ProblemFmt};
+use crate::convert::diag::{IntoLspDiagnostic, ProblemFmt, ToLspCodeActions};
 
 pub(crate) use self::analysed_doc::{AnalyzedDocument, DocInfo};
 use self::{analysed_doc::ModuleIdToUrl, tokens::Token};
@@ -98,14 +103,22 @@ pub(super) struct AnalyzedModule {
 pub struct AnalysisResult {
     module: Option<AnalyzedModule>,
     diagnostics: Vec<Diagnostic>,
+    /// Quick fixes for the diagnostics.
+    code_actions: Vec<CodeAction>,
 }
 
-pub(crate) fn global_analysis(doc_info: DocInfo) -> Vec<AnalyzedDocument> {
+/// Loads and typechecks the document along with every module it imports, and analyses all of them.
+/// Imported modules that haven't changed since an earlier load with the same `analysis_cache`
+/// (nor have any of their own imports) are taken from it instead of being checked again.
+pub(crate) fn global_analysis(
+    doc_info: DocInfo,
+    analysis_cache: &mut AnalysisCache,
+) -> Vec<AnalyzedDocument> {
     let fi = doc_info.url.to_file_path().unwrap();
     let src_dir = find_src_dir(&fi).to_path_buf();
 
     let arena = Bump::new();
-    let loaded = roc_load::load_and_typecheck_str(
+    let loaded = roc_load::load_and_typecheck_str_with_cache(
         &arena,
         fi,
         &doc_info.source,
@@ -116,6 +129,7 @@ pub(crate) fn global_analysis(doc_info: DocInfo) -> Vec<AnalyzedDocument> {
         roc_reporting::report::RenderTarget::LanguageServer,
         RocCacheDir::Persistent(cache::roc_cache_dir().as_path()),
         roc_reporting::report::DEFAULT_PALETTE,
+        analysis_cache,
     );
 
     let module = match loaded {
@@ -131,6 +145,7 @@ pub(crate) fn global_analysis(doc_info: DocInfo) -> Vec<AnalyzedDocument> {
                 analysis_result: AnalysisResult {
                     module: None,
                     diagnostics: all_problems,
+                    code_actions: vec![],
                 },
             };
 
@@ -314,11 +329,13 @@ impl<'a> AnalyzedDocumentBuilder<'a> {
         };
 
         let line_info = LineInfo::new(&source);
-        let diagnostics = self.build_diagnostics(&path, &source, &line_info, module_id);
+        let url = path_to_url(&path);
+        let (diagnostics, code_actions) =
+            self.build_diagnostics(&path, &url, &source, &line_info, module_id);
 
         AnalyzedDocument {
             doc_info: DocInfo {
-                url: path_to_url(&path),
+                url,
                 line_info,
                 source: source.into(),
                 version,
@@ -326,6 +343,7 @@ impl<'a> AnalyzedDocumentBuilder<'a> {
             analysis_result: AnalysisResult {
                 module: Some(analyzed_module),
                 diagnostics,
+                code_actions,
             },
         }
     }
@@ -353,19 +371,23 @@ impl<'a> AnalyzedDocumentBuilder<'a> {
     fn build_diagnostics(
         &mut self,
         source_path: &Path,
+        url: &Url,
         source: &str,
         line_info: &LineInfo,
         module_id: ModuleId,
-    ) -> Vec<Diagnostic> {
+    ) -> (Vec<Diagnostic>, Vec<CodeAction>) {
         let lines: Vec<_> = source.lines().collect();
 
         let alloc = RocDocAllocator::new(&lines, module_id, self.interns);
 
         let mut all_problems = Vec::new();
+        let mut code_actions = Vec::new();
         let fmt = ProblemFmt {
             alloc: &alloc,
             line_info,
             path: source_path,
+            url,
+            source,
         };
 
         let can_problems = self.can_problems.remove(&module_id).unwrap_or_default();
@@ -373,7 +395,21 @@ impl<'a> AnalyzedDocumentBuilder<'a> {
         let type_problems = self.type_problems.remove(&module_id).unwrap_or_default();
 
         for can_problem in can_problems {
-            if let Some(diag) = can_problem.into_lsp_diagnostic(&fmt) {
+            if let Some(diag) = can_problem.clone().into_lsp_diagnostic(&fmt) {
+                for action in can_problem.to_lsp_code_actions(&diag, &fmt) {
+                    // Several problems can have the same fix, like an unused import of a builtin
+                    let same_fix = code_actions.iter_mut().find(|other: &&mut CodeAction| {
+                        other.title == action.title && other.edit == action.edit
+                    });
+
+                    match same_fix {
+                        Some(other) => other
+                            .diagnostics
+                            .get_or_insert_with(Vec::new)
+                            .extend(action.diagnostics.into_iter().flatten()),
+                        None => code_actions.push(action),
+                    }
+                }
                 all_problems.push(diag);
             }
         }
@@ -384,6 +420,6 @@ impl<'a> AnalyzedDocumentBuilder<'a> {
             }
         }
 
-        all_problems
+        (all_problems, code_actions)
     }
 }
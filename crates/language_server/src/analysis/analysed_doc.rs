@@ -5,11 +5,14 @@ use bumpalo::Bump;
 
 use roc_module::symbol::{ModuleId, Symbol};
 
-use roc_region::all::LineInfo;
+use roc_region::all::{LineInfo, Position as RocPosition, Region};
 
 use tower_lsp::lsp_types::{
-    CompletionItem, Diagnostic, GotoDefinitionResponse, Hover, HoverContents, LanguageString,
-    Location, MarkedString, Position, Range, SemanticTokens, SemanticTokensResult, TextEdit, Url,
+    CodeAction, CodeActionOrCommand, CompletionItem, Diagnostic, DocumentSymbol, Documentation,
+    GotoDefinitionResponse, Hover, HoverContents, InlayHint, InlayHintKind, InlayHintLabel,
+    LanguageString, Location, MarkedString, ParameterInformation, ParameterLabel, Position, Range,
+    SemanticTokens, SemanticTokensResult, SignatureHelp, SignatureInformation,
+    TextDocumentContentChangeEvent, TextEdit, Url, WorkspaceEdit,
 };
 
 use crate::{
@@ -18,8 +21,15 @@ use crate::{
 };
 
 use super::{
+    inlay_hints::{overlaps, unannotated_defs},
     parse_ast::Ast,
+    refactor::{add_type_annotation, extract_to_def},
+    references::{
+        find_symbol_regions, is_valid_rename, module_level_symbol, module_level_symbol_at,
+    },
     semantic_tokens::arrange_semantic_tokens,
+    signature_help::{call_at, defined_type, parameter_offsets},
+    symbols::document_symbols,
     utils::{format_var_type, is_roc_identifier_char},
     AnalysisResult, AnalyzedModule,
 };
@@ -64,6 +74,51 @@ impl DocInfo {
         );
     }
 
+    /// Applies a change sent by the editor, which replaces the text in its range,
+    /// or the whole document if it has no range.
+    pub fn apply_change(&self, change: TextDocumentContentChangeEvent, version: i32) -> DocInfo {
+        let source = match change.range {
+            Some(range) => {
+                let start = self.offset_at(range.start);
+                let end = self.offset_at(range.end).max(start);
+
+                let mut source = self.source.clone();
+                source.replace_range(start..end, &change.text);
+                source
+            }
+            None => change.text,
+        };
+
+        DocInfo::new(self.url.clone(), source, version)
+    }
+
+    /// The byte offset of an LSP position. Its `character` counts UTF-16 code units,
+    /// since that is the only position encoding every client supports.
+    fn offset_at(&self, position: Position) -> usize {
+        // Editors are allowed to send positions past the end of a line or the document,
+        // which mean the end of that line or document.
+        if position.line >= self.line_info.num_lines() {
+            return self.source.len();
+        }
+
+        let line_start = Position::new(position.line, 0).to_roc_position(&self.line_info);
+        let line_start = line_start.offset as usize;
+        let line = self.source[line_start..]
+            .split('\n')
+            .next()
+            .unwrap_or_default();
+
+        let mut utf16_len = 0;
+        for (index, char) in line.char_indices() {
+            if utf16_len >= position.character as usize {
+                return line_start + index;
+            }
+            utf16_len += char.len_utf16();
+        }
+
+        line_start + line.len()
+    }
+
     fn whole_document_range(&self) -> Range {
         let start = Position::new(0, 0);
         let end = Position::new(self.line_info.num_lines(), 0);
@@ -117,6 +172,18 @@ impl DocInfo {
 }
 
 impl AnalyzedDocument {
+    /// A document we know the contents of, but which hasn't been analysed yet.
+    pub(crate) fn unanalyzed(doc_info: DocInfo) -> Self {
+        Self {
+            doc_info,
+            analysis_result: AnalysisResult {
+                module: None,
+                diagnostics: vec![],
+                code_actions: vec![],
+            },
+        }
+    }
+
     pub fn url(&self) -> &Url {
         &self.doc_info.url
     }
@@ -144,6 +211,71 @@ impl AnalyzedDocument {
         self.analysis_result.diagnostics.clone()
     }
 
+    /// The quick fixes for diagnostics that overlap with `range`, followed by the refactorings
+    /// that apply to it.
+    pub fn code_actions(&self, range: Range) -> Vec<CodeActionOrCommand> {
+        let overlaps = |diagnostic: &Diagnostic| {
+            diagnostic.range.start <= range.end && range.start <= diagnostic.range.end
+        };
+
+        let quick_fixes = self
+            .analysis_result
+            .code_actions
+            .iter()
+            .filter(|action| action.diagnostics.iter().flatten().any(overlaps))
+            .cloned();
+
+        quick_fixes
+            .chain(self.refactors(range))
+            .map(CodeActionOrCommand::CodeAction)
+            .collect()
+    }
+
+    fn refactors(&self, range: Range) -> Vec<CodeAction> {
+        let Some(module) = self.module() else {
+            return vec![];
+        };
+
+        let source = &self.doc_info.source;
+        let region = self.region_of(range);
+
+        let refactors = [
+            add_type_annotation(module, source, region),
+            extract_to_def(&module.declarations, source, region),
+        ];
+
+        refactors
+            .into_iter()
+            .flatten()
+            .map(|refactor| {
+                let edits = refactor
+                    .edits
+                    .into_iter()
+                    .map(|(region, new_text)| {
+                        TextEdit::new(region.to_range(self.line_info()), new_text)
+                    })
+                    .collect();
+
+                CodeAction {
+                    title: refactor.title,
+                    kind: Some(refactor.kind),
+                    edit: Some(WorkspaceEdit::new(HashMap::from([(
+                        self.url().clone(),
+                        edits,
+                    )]))),
+                    ..CodeAction::default()
+                }
+            })
+            .collect()
+    }
+
+    /// Converts a range from the editor, which is allowed to go past the end of the document.
+    fn region_of(&self, range: Range) -> Region {
+        let offset_at = |position| RocPosition::new(self.doc_info.offset_at(position) as u32);
+
+        Region::new(offset_at(range.start), offset_at(range.end))
+    }
+
     pub fn symbol_at(&self, position: Position) -> Option<Symbol> {
         let line_info = self.line_info();
 
@@ -204,6 +336,118 @@ impl AnalyzedDocument {
         })
     }
 
+    /// The signature of the function whose arguments are being written at `position`.
+    pub fn signature_help(&self, position: Position) -> Option<SignatureHelp> {
+        let module = self.module()?;
+        let AnalyzedModule {
+            subs,
+            declarations,
+            module_id,
+            interns,
+            modules_info,
+            ..
+        } = module;
+
+        let pos = position.to_roc_position(self.line_info());
+        let call = call_at(&self.doc_info.source, pos, declarations)?;
+
+        let type_str = defined_type(module, call.symbol)
+            .unwrap_or_else(|| format_var_type(call.fn_var, &mut subs.clone(), module_id, interns));
+        let params = parameter_offsets(&type_str);
+        if params.is_empty() {
+            return None;
+        }
+
+        let name = call.symbol.as_str(interns);
+        let label = format!("{} : {}", name, type_str);
+        let type_start = (label.len() - type_str.len()) as u32;
+
+        let parameters = params
+            .into_iter()
+            .map(|[start, end]| ParameterInformation {
+                label: ParameterLabel::LabelOffsets([type_start + start, type_start + end]),
+                documentation: None,
+            })
+            .collect();
+
+        let documentation = modules_info
+            .get_docs(&call.symbol.module_id())
+            .and_then(|docs| docs.get_doc_for_symbol(&call.symbol))
+            .map(Documentation::String);
+
+        Some(SignatureHelp {
+            signatures: vec![SignatureInformation {
+                label,
+                documentation,
+                parameters: Some(parameters),
+                active_parameter: Some(call.active_argument),
+            }],
+            active_signature: Some(0),
+            active_parameter: Some(call.active_argument),
+        })
+    }
+
+    /// The inferred types of the definitions in `range` that don't have a type annotation.
+    pub fn inlay_hints(&self, range: Range) -> Vec<InlayHint> {
+        let Some(AnalyzedModule {
+            subs,
+            declarations,
+            module_id,
+            interns,
+            ..
+        }) = self.module()
+        else {
+            return vec![];
+        };
+
+        let region = self.region_of(range);
+        let line_info = self.line_info();
+
+        let mut subs = subs.clone();
+        unannotated_defs(region, declarations)
+            .into_iter()
+            .filter(|def| overlaps(region, def.name_region))
+            .map(|def| InlayHint {
+                position: def.name_region.to_range(line_info).end,
+                label: InlayHintLabel::String(format!(
+                    " : {}",
+                    format_var_type(def.var, &mut subs, module_id, interns)
+                )),
+                kind: Some(InlayHintKind::TYPE),
+                text_edits: None,
+                tooltip: None,
+                padding_left: None,
+                padding_right: None,
+                data: None,
+            })
+            .collect()
+    }
+
+    pub fn document_symbols(&self) -> Option<Vec<DocumentSymbol>> {
+        let AnalyzedModule {
+            subs,
+            declarations,
+            module_id,
+            interns,
+            ..
+        } = self.module()?;
+
+        Some(document_symbols(
+            &self.doc_info.source,
+            self.line_info(),
+            declarations,
+            subs,
+            module_id,
+            interns,
+        ))
+    }
+
+    pub(crate) fn module_name(&self) -> Option<String> {
+        let module = self.module()?;
+
+        Some(module.module_id.to_ident_str(&module.interns).to_string())
+    }
+
     pub fn definition(&self, symbol: Symbol) -> Option<GotoDefinitionResponse> {
         let AnalyzedModule { declarations, .. } = self.module()?;
 
@@ -218,6 +462,96 @@ impl AnalyzedDocument {
         self.module()?.module_id_to_url.get(&module_id).cloned()
     }
 
+    /// The URLs of the modules this document imports directly.
+    pub(crate) fn imported_urls(&self) -> Vec<Url> {
+        let Some(module) = self.module() else {
+            return vec![];
+        };
+
+        module
+            .imports_by_module
+            .keys()
+            .filter_map(|module_id| module.module_id_to_url.get(module_id).cloned())
+            .collect()
+    }
+
+    /// Like [Self::symbol_at], but also finds module-level names in type annotations and
+    /// import and export lists.
+    pub fn referenced_symbol_at(&self, position: Position) -> Option<Symbol> {
+        let module = self.module()?;
+        let roc_position = position.to_roc_position(self.line_info());
+
+        module_level_symbol_at(module, &self.doc_info.source, roc_position)
+            .or_else(|| self.symbol_at(position))
+    }
+
+    pub(crate) fn symbol_name(&self, symbol: Symbol) -> Option<&str> {
+        Some(symbol.as_str(&self.module()?.interns))
+    }
+
+    /// Whether the symbol can be mentioned outside of the body it's defined in, meaning we
+    /// need to search other modules for references to it.
+    pub(crate) fn is_module_level(&self, symbol: Symbol) -> bool {
+        let Some(module) = self.module() else {
+            return false;
+        };
+
+        symbol.module_id() != module.module_id
+            || module
+                .declarations
+                .symbols
+                .iter()
+                .any(|s| s.value == symbol)
+            || symbol
+                .as_str(&module.interns)
+                .starts_with(|c: char| c.is_uppercase())
+    }
+
+    /// Finds this document's symbol for a module-level `name` defined in the module at
+    /// `def_url`. Symbols can't be compared directly between documents because each analysis
+    /// numbers its modules separately.
+    pub(crate) fn translate_symbol(&self, def_url: &Url, name: &str) -> Option<Symbol> {
+        let module = self.module()?;
+        let (module_id, _) = module
+            .module_id_to_url
+            .iter()
+            .find(|(_, url)| *url == def_url)?;
+
+        module_level_symbol(module, *module_id, name)
+    }
+
+    pub fn references(&self, symbol: Symbol, include_declaration: bool) -> Vec<Range> {
+        let Some(module) = self.module() else {
+            return vec![];
+        };
+
+        find_symbol_regions(module, &self.doc_info.source, symbol, include_declaration)
+            .into_iter()
+            .map(|region| region.to_range(self.line_info()))
+            .collect()
+    }
+
+    /// Finds the range of the name to rename at `position`, if it can be renamed at all.
+    pub fn prepare_rename(&self, position: Position) -> Option<(Range, String)> {
+        let symbol = self.referenced_symbol_at(position)?;
+
+        if symbol.module_id().is_builtin() || self.module_url(symbol.module_id()).is_none() {
+            return None;
+        }
+
+        let range = self
+            .references(symbol, true)
+            .into_iter()
+            .find(|range| range.start <= position && position <= range.end)?;
+
+        Some((range, self.symbol_name(symbol)?.to_string()))
+    }
+
+    pub fn is_valid_rename(&self, symbol: Symbol, new_name: &str) -> bool {
+        self.symbol_name(symbol)
+            .is_some_and(|old_name| is_valid_rename(old_name, new_name))
+    }
+
     pub fn completion_items(
         &self,
         position: Position,
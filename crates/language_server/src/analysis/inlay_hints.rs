@@ -0,0 +1,91 @@
+//! Inferred types of definitions that don't have a type annotation.
+
+use roc_can::{
+    def::Def,
+    expr::Declarations,
+    pattern::Pattern,
+    traverse::{walk_decl, walk_def, DeclarationInfo, Visitor},
+};
+use roc_region::all::Region;
+use roc_types::subs::Variable;
+
+pub(super) fn overlaps(a: Region, b: Region) -> bool {
+    a.start() <= b.end() && b.start() <= a.end()
+}
+
+pub(super) struct UnannotatedDef {
+    pub name_region: Region,
+    pub def_region: Region,
+    pub var: Variable,
+}
+
+struct UnannotatedDefs {
+    range: Region,
+    found: Vec<UnannotatedDef>,
+}
+
+impl Visitor for UnannotatedDefs {
+    fn should_visit(&mut self, region: Region) -> bool {
+        overlaps(self.range, region)
+    }
+
+    fn visit_decl(&mut self, decl: DeclarationInfo<'_>) {
+        match &decl {
+            DeclarationInfo::Value {
+                loc_symbol,
+                expr_var,
+                annotation: None,
+                ..
+            }
+            | DeclarationInfo::Function {
+                loc_symbol,
+                expr_var,
+                annotation: None,
+                ..
+            } => {
+                self.found.push(UnannotatedDef {
+                    name_region: loc_symbol.region,
+                    def_region: decl.region(),
+                    var: *expr_var,
+                });
+            }
+            _ => {}
+        }
+
+        if self.should_visit(decl.region()) {
+            walk_decl(self, decl);
+        }
+    }
+
+    fn visit_def(&mut self, def: &Def) {
+        if !self.should_visit(def.region()) {
+            return;
+        }
+
+        if let (Pattern::Identifier(_), None) = (&def.loc_pattern.value, &def.annotation) {
+            self.found.push(UnannotatedDef {
+                name_region: def.loc_pattern.region,
+                def_region: def.region(),
+                var: def.expr_var,
+            });
+        }
+
+        walk_def(self, def);
+    }
+}
+
+/// Finds the unannotated top-level and `let` definitions that overlap with `range`,
+/// along with the variables of their types.
+pub(super) fn unannotated_defs(range: Region, declarations: &Declarations) -> Vec<UnannotatedDef> {
+    let mut visitor = UnannotatedDefs {
+        range,
+        found: vec![],
+    };
+    visitor.visit_decls(declarations);
+
+    visitor
+        .found
+        .into_iter()
+        .filter(|def| overlaps(range, def.def_region))
+        .collect()
+}
@@ -1,11 +1,12 @@
 use bumpalo::Bump;
 use roc_fmt::Buf;
 use roc_parse::{
-    ast::{Defs, Module},
+    ast::{Collection, Defs, Header, Module, Spaced, TypeDef, ValueDef},
+    header::{ExposedName, ImportsEntry},
     module::parse_module_defs,
     parser::SyntaxError,
 };
-use roc_region::all::Loc;
+use roc_region::all::{Loc, Region};
 
 use self::format::FormattedAst;
 
@@ -15,10 +16,32 @@ mod format;
 
 pub struct Ast<'a> {
     arena: &'a Bump,
+    src: &'a str,
     module: Module<'a>,
     defs: Defs<'a>,
 }
 
+/// Where a [ModuleLevelName] should be looked up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameScope<'a> {
+    /// The name is defined in this module.
+    Home,
+    /// The name is exposed by the module with this name.
+    Module(&'a str),
+}
+
+/// A name referring to a module-level value or type, found in the parse AST.
+///
+/// Canonicalization does not keep track of where types are mentioned, nor of the names in
+/// `exposes` and `import ... exposing` lists, so we find those by looking at the syntax.
+#[derive(Debug, Clone, Copy)]
+pub struct ModuleLevelName<'a> {
+    /// The region of the unqualified name, e.g. just `Dict` in `Dict.Dict`.
+    pub region: Region,
+    pub name: &'a str,
+    pub scope: NameScope<'a>,
+}
+
 impl<'a> Ast<'a> {
     pub fn parse(arena: &'a Bump, src: &'a str) -> Result<Ast<'a>, SyntaxError<'a>> {
         use roc_parse::{module::parse_header, state::State};
@@ -34,6 +57,7 @@ impl<'a> Ast<'a> {
             module,
             defs,
             arena,
+            src,
         })
     }
 
@@ -55,4 +79,145 @@ impl<'a> Ast<'a> {
 
         header_tokens.into_iter().chain(body_tokens)
     }
+
+    /// The top-level type aliases, opaque types and abilities, with their regions.
+    pub fn type_defs(&self) -> impl Iterator<Item = (&TypeDef<'a>, Region)> {
+        self.defs
+            .defs()
+            .zip(self.defs.regions.iter())
+            .filter_map(|(def, region)| def.ok().map(|type_def| (type_def, *region)))
+    }
+
+    /// Collects the names of module-level values and types mentioned in the header's exposes,
+    /// in imports, and in type positions in the body.
+    pub fn module_level_names(&self) -> Vec<ModuleLevelName<'a>> {
+        let mut names = Vec::new();
+
+        let header_exposes = match &self.module.header {
+            Header::Module(header) => Some(&header.exposes),
+            Header::App(header) => Some(&header.provides),
+            Header::Hosted(header) => Some(&header.exposes.item),
+            Header::Platform(header) => Some(&header.provides.item),
+            Header::Package(_) => None,
+        };
+
+        if let Some(exposes) = header_exposes {
+            names.extend(exposed_names(exposes, NameScope::Home));
+        }
+
+        // Maps the name a module is imported as to the name of the module
+        let mut imported_as: Vec<(&'a str, &'a str)> = Vec::new();
+        // Maps a name imported with `exposing` to the module it is imported from
+        let mut exposed_by: Vec<(&'a str, &'a str)> = Vec::new();
+
+        // Platforms and hosted modules still list their imports in the header
+        let header_imports = match &self.module.header {
+            Header::Platform(header) => header.imports.item.items,
+            Header::Hosted(header) => header.imports.item.items,
+            Header::Module(_) | Header::App(_) | Header::Package(_) => &[],
+        };
+
+        for entry in header_imports {
+            if let ImportsEntry::Module(module_name, exposed)
+            | ImportsEntry::Package(_, module_name, exposed) = entry.value.item()
+            {
+                let module_name: &'a str = (*module_name).into();
+                imported_as.push((module_name, module_name));
+
+                for name in exposed_names(exposed, NameScope::Module(module_name)) {
+                    exposed_by.push((name.name, module_name));
+                    names.push(name);
+                }
+            }
+        }
+
+        for value_def in self.defs.value_defs.iter() {
+            if let ValueDef::ModuleImport(import) = value_def {
+                let module_name: &'a str = import.name.value.name.into();
+
+                let import_name = match &import.alias {
+                    Some(alias) => self.text_at(alias.item.region),
+                    None => module_name,
+                };
+                imported_as.push((import_name, module_name));
+
+                if let Some(exposed) = &import.exposed {
+                    for name in exposed_names(&exposed.item, NameScope::Module(module_name)) {
+                        exposed_by.push((name.name, module_name));
+                        names.push(name);
+                    }
+                }
+            }
+        }
+
+        for Loc { region, value } in self.defs.iter_tokens(self.arena) {
+            if !matches!(value, Token::Type | Token::Ability) {
+                continue;
+            }
+
+            let text = self.text_at(region);
+
+            // Opaque references like `@Age` can only mention types from this module
+            if let Some(name) = text.strip_prefix('@') {
+                names.push(ModuleLevelName {
+                    region: Region::new(region.start().bump_column(1), region.end()),
+                    name,
+                    scope: NameScope::Home,
+                });
+                continue;
+            }
+
+            // Skip type variables, wildcards and inferred types
+            if !text.starts_with(|c: char| c.is_uppercase()) {
+                continue;
+            }
+
+            let name_module_level = match text.rsplit_once('.') {
+                Some((qualifier, name)) => {
+                    let module_name = lookup(&imported_as, qualifier).unwrap_or(qualifier);
+
+                    ModuleLevelName {
+                        region: Region::new(region.end().sub(name.len() as u32), region.end()),
+                        name,
+                        scope: NameScope::Module(module_name),
+                    }
+                }
+                None => ModuleLevelName {
+                    region,
+                    name: text,
+                    scope: lookup(&exposed_by, text)
+                        .map(NameScope::Module)
+                        .unwrap_or(NameScope::Home),
+                },
+            };
+
+            names.push(name_module_level);
+        }
+
+        names
+    }
+
+    fn text_at(&self, region: Region) -> &'a str {
+        &self.src[region.start().byte_offset()..region.end().byte_offset()]
+    }
+}
+
+fn exposed_names<'a>(
+    exposed: &Collection<'a, Loc<Spaced<'a, ExposedName<'a>>>>,
+    scope: NameScope<'a>,
+) -> impl Iterator<Item = ModuleLevelName<'a>> + 'a {
+    let items: &'a [_] = exposed.items;
+
+    items.iter().map(move |loc_name| ModuleLevelName {
+        region: loc_name.region,
+        name: (*loc_name.value.item()).into(),
+        scope,
+    })
+}
+
+fn lookup<'a>(pairs: &[(&'a str, &'a str)], key: &str) -> Option<&'a str> {
+    pairs
+        .iter()
+        .find(|(name, _)| *name == key)
+        .map(|(_, value)| *value)
 }
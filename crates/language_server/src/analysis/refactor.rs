@@ -0,0 +1,346 @@
+//! Refactorings offered as code actions: adding the inferred type annotation to a definition,
+//! and extracting an expression into its own definition.
+
+use roc_can::{
+    def::Def,
+    expr::{Declarations, Expr},
+    traverse::{walk_decl, walk_def, walk_expr, DeclarationInfo, Visitor},
+};
+use roc_region::all::{Loc, Position, Region};
+use roc_types::subs::Variable;
+use tower_lsp::lsp_types::CodeActionKind;
+
+use super::{inlay_hints::unannotated_defs, utils::format_var_annotation, AnalyzedModule};
+
+/// The name we give to extracted expressions, numbered if it's already taken.
+const EXTRACTED_NAME: &str = "extracted";
+
+pub(super) struct Refactor {
+    pub title: String,
+    pub kind: CodeActionKind,
+    pub edits: Vec<(Region, String)>,
+}
+
+fn text_at(source: &str, region: Region) -> &str {
+    source
+        .get(region.start().byte_offset()..region.end().byte_offset())
+        .unwrap_or_default()
+}
+
+fn line_start(source: &str, offset: usize) -> usize {
+    source[..offset].rfind('\n').map_or(0, |index| index + 1)
+}
+
+/// The whitespace before `position` on its line, if there is nothing else before it.
+fn indentation_before(source: &str, position: Position) -> Option<&str> {
+    let offset = position.byte_offset();
+    let before = &source[line_start(source, offset)..offset];
+
+    before.chars().all(|c| c == ' ').then_some(before)
+}
+
+/// Adds the inferred type annotation above the innermost unannotated definition at `range`.
+pub(super) fn add_type_annotation(
+    module: &AnalyzedModule,
+    source: &str,
+    range: Region,
+) -> Option<Refactor> {
+    let def = unannotated_defs(range, &module.declarations)
+        .into_iter()
+        .filter(|def| def.def_region.contains(&range))
+        .max_by_key(|def| def.def_region.start())?;
+
+    let name = text_at(source, def.name_region);
+    let indent = indentation_before(source, def.name_region.start())?;
+
+    let type_str = format_var_annotation(
+        def.var,
+        &mut module.subs.clone(),
+        &module.module_id,
+        &module.interns,
+    )?;
+
+    let line_start =
+        Position::new(line_start(source, def.name_region.start().byte_offset()) as u32);
+
+    Some(Refactor {
+        title: format!("Add type annotation to `{}`", name),
+        kind: CodeActionKind::REFACTOR_REWRITE,
+        edits: vec![(
+            Region::new(line_start, line_start),
+            format!("{}{} : {}\n", indent, name, type_str),
+        )],
+    })
+}
+
+/// An expression that definitions can be added to the start of, like the body of a function.
+struct Scope {
+    region: Region,
+    /// Whether the scope has names that aren't available outside of it, like arguments.
+    binds_names: bool,
+    /// Where each of the definitions and the final expression in the scope start.
+    statements: Vec<Position>,
+}
+
+fn statement_starts(expr: &Expr, region: Region) -> Vec<Position> {
+    match expr {
+        Expr::LetNonRec(def, body) => {
+            let mut starts = vec![def.region().start()];
+            starts.extend(statement_starts(&body.value, body.region));
+            starts
+        }
+        Expr::LetRec(defs, body, _) => {
+            let mut starts: Vec<_> = defs.iter().map(|def| def.region().start()).collect();
+            starts.extend(statement_starts(&body.value, body.region));
+            starts
+        }
+        Expr::Expect {
+            loc_continuation, ..
+        }
+        | Expr::ExpectFx {
+            loc_continuation, ..
+        }
+        | Expr::Dbg {
+            loc_continuation, ..
+        } => {
+            let mut starts = vec![region.start()];
+            starts.extend(statement_starts(
+                &loc_continuation.value,
+                loc_continuation.region,
+            ));
+            starts
+        }
+        _ => vec![region.start()],
+    }
+}
+
+struct ExtractionTargets {
+    selection: Region,
+    /// The selection without the parentheses around it, which canonical regions don't include.
+    expr_region: Region,
+    expressions: Vec<Region>,
+    scopes: Vec<Scope>,
+}
+
+impl ExtractionTargets {
+    fn add_scope(&mut self, body: &Loc<Expr>, binds_names: bool) {
+        if body.region.contains(&self.selection) {
+            self.scopes.push(Scope {
+                region: body.region,
+                binds_names,
+                statements: statement_starts(&body.value, body.region),
+            });
+        }
+    }
+}
+
+impl Visitor for ExtractionTargets {
+    fn should_visit(&mut self, region: Region) -> bool {
+        region.contains(&self.expr_region)
+    }
+
+    fn visit_decl(&mut self, decl: DeclarationInfo<'_>) {
+        if !self.should_visit(decl.region()) {
+            return;
+        }
+
+        match &decl {
+            DeclarationInfo::Value { loc_expr, .. }
+            | DeclarationInfo::Destructure { loc_expr, .. } => self.add_scope(loc_expr, false),
+            DeclarationInfo::Function { loc_body, .. } => self.add_scope(loc_body, true),
+            DeclarationInfo::Expectation { loc_condition } => self.add_scope(loc_condition, false),
+        }
+
+        walk_decl(self, decl);
+    }
+
+    fn visit_def(&mut self, def: &Def) {
+        if !self.should_visit(def.region()) {
+            return;
+        }
+
+        self.add_scope(&def.loc_expr, false);
+        walk_def(self, def);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr, region: Region, var: Variable) {
+        if !self.should_visit(region) {
+            return;
+        }
+
+        self.expressions.push(region);
+
+        match expr {
+            Expr::Closure(closure) => self.add_scope(&closure.loc_body, true),
+            Expr::When { branches, .. } => {
+                for branch in branches {
+                    self.add_scope(&branch.value, true);
+                }
+            }
+            Expr::If {
+                branches,
+                final_else,
+                ..
+            } => {
+                for (_, body) in branches {
+                    self.add_scope(body, false);
+                }
+                self.add_scope(final_else, false);
+            }
+            _ => {}
+        }
+
+        walk_expr(self, expr, var);
+    }
+}
+
+/// Removes whitespace around the selection, and the parentheses around the expression in it.
+fn trim_selection(source: &str, range: Region) -> (Region, Region) {
+    let text = text_at(source, range);
+    let start = range.start().byte_offset() + (text.len() - text.trim_start().len());
+    let end = start + text.trim().len();
+    let trimmed = Region::new(Position::new(start as u32), Position::new(end as u32));
+
+    let text = text_at(source, trimmed);
+    let inner = match text
+        .strip_prefix('(')
+        .and_then(|text| text.strip_suffix(')'))
+    {
+        Some(inner) if is_balanced(inner) => {
+            let start = start + 1 + (inner.len() - inner.trim_start().len());
+            let end = start + inner.trim().len();
+            Region::new(Position::new(start as u32), Position::new(end as u32))
+        }
+        _ => trimmed,
+    };
+
+    (trimmed, inner)
+}
+
+fn is_balanced(text: &str) -> bool {
+    let mut depth = 0;
+    for c in text.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return false,
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+
+    depth == 0
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// A name for the new definition that isn't used anywhere in the source.
+fn fresh_name(source: &str) -> String {
+    let is_used = |name: &str| {
+        source.match_indices(name).any(|(index, _)| {
+            let before = source[..index].chars().next_back();
+            let after = source[index + name.len()..].chars().next();
+
+            !before.is_some_and(is_ident_char) && !after.is_some_and(is_ident_char)
+        })
+    };
+
+    std::iter::once(EXTRACTED_NAME.to_string())
+        .chain((2..).map(|n| format!("{}{}", EXTRACTED_NAME, n)))
+        .find(|name| !is_used(name))
+        .unwrap()
+}
+
+/// Moves the selected expression into a new definition at the start of the statement it's in,
+/// in the innermost scope around it.
+pub(super) fn extract_to_def(
+    declarations: &Declarations,
+    source: &str,
+    range: Region,
+) -> Option<Refactor> {
+    if range.is_empty() {
+        return None;
+    }
+
+    let (selection, expr_region) = trim_selection(source, range);
+    if selection.is_empty() {
+        return None;
+    }
+
+    let mut targets = ExtractionTargets {
+        selection,
+        expr_region,
+        expressions: vec![],
+        scopes: vec![],
+    };
+    targets.visit_decls(declarations);
+
+    if !targets
+        .expressions
+        .iter()
+        .any(|region| *region == selection || *region == expr_region)
+    {
+        return None;
+    }
+
+    // Scopes are found from the outside in. We prefer adding the definition next to the
+    // surrounding ones, but can't move it out of a scope with names it might use.
+    let is_own_lines = |scope: &Scope| indentation_before(source, scope.region.start()).is_some();
+    let scope = targets
+        .scopes
+        .iter()
+        .rev()
+        .find(|scope| scope.binds_names || is_own_lines(scope))
+        .or(targets.scopes.last())?;
+
+    let name = fresh_name(source);
+    let expr_text = text_at(source, expr_region);
+
+    let edits = match indentation_before(source, scope.region.start()) {
+        // The scope is on lines of its own, so the definition can go above the statement
+        Some(_) => {
+            let statement = scope
+                .statements
+                .iter()
+                .rev()
+                .find(|start| **start <= selection.start())?;
+            let indent = indentation_before(source, *statement)?;
+            let line_start = Position::new(line_start(source, statement.byte_offset()) as u32);
+
+            vec![
+                (
+                    Region::new(line_start, line_start),
+                    format!("{}{} = {}\n", indent, name, expr_text),
+                ),
+                (selection, name.clone()),
+            ]
+        }
+        // The scope starts after something else on its line, like `main = ` or `\x -> `,
+        // so it has to be moved to a new line first
+        None => {
+            let scope_start = scope.region.start().byte_offset();
+            let line = &source[line_start(source, scope_start)..];
+            let indent = format!("{}    ", &line[..line.len() - line.trim_start().len()]);
+
+            let before = text_at(source, Region::new(scope.region.start(), selection.start()));
+            let after = text_at(source, Region::new(selection.end(), scope.region.end()));
+            let body = format!("{}{}{}", before, name, after).replace('\n', "\n    ");
+
+            // Replace the space between `=` or `->` and the scope too
+            let replace_start = source[..scope_start].trim_end_matches(' ').len();
+            let replaced = Region::new(Position::new(replace_start as u32), scope.region.end());
+
+            vec![(
+                replaced,
+                format!("\n{}{} = {}\n{}{}", indent, name, expr_text, indent, body),
+            )]
+        }
+    };
+
+    Some(Refactor {
+        title: format!("Extract to `{}`", name),
+        kind: CodeActionKind::REFACTOR_EXTRACT,
+        edits,
+    })
+}
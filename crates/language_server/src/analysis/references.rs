@@ -0,0 +1,162 @@
+//! Finding every mention of a symbol, used for find-references and rename.
+
+use bumpalo::Bump;
+use roc_can::traverse::{find_references, FoundReference};
+use roc_module::symbol::{ModuleId, Symbol};
+use roc_parse::{
+    ident::{lowercase_ident, uppercase_ident},
+    parser::Parser,
+    state::State,
+};
+use roc_region::all::{Position, Region};
+
+use super::{
+    parse_ast::{Ast, ModuleLevelName, NameScope},
+    AnalyzedModule,
+};
+
+/// Finds the regions of every mention of `symbol` in the module, narrowed down to just the
+/// identifier so they can be used for renaming.
+pub(super) fn find_symbol_regions(
+    module: &AnalyzedModule,
+    source: &str,
+    symbol: Symbol,
+    include_declaration: bool,
+) -> Vec<Region> {
+    let name = symbol.as_str(&module.interns);
+
+    let mut regions: Vec<Region> = find_references(symbol, &module.declarations)
+        .into_iter()
+        .filter_map(|found| match found {
+            FoundReference::Definition(region) => {
+                include_declaration.then(|| narrow_to_ident(source, region, name))
+            }
+            FoundReference::Lookup(region) => Some(narrow_to_ident(source, region, name)),
+            FoundReference::Annotation(signature) => include_declaration
+                .then(|| annotated_name_region(source, signature, name))
+                .flatten(),
+        })
+        .collect();
+
+    let arena = Bump::new();
+    if let Ok(ast) = Ast::parse(&arena, source) {
+        regions.extend(
+            ast.module_level_names()
+                .iter()
+                .filter(|name| resolve_name(module, name) == Some(symbol))
+                .map(|name| name.region),
+        );
+    }
+
+    regions.sort_by_key(|region| region.start());
+    regions.dedup();
+    regions
+}
+
+/// Finds the module-level value or type whose name is mentioned at `position`, in places
+/// canonicalization doesn't keep track of, like type annotations and import lists.
+pub(super) fn module_level_symbol_at(
+    module: &AnalyzedModule,
+    source: &str,
+    position: Position,
+) -> Option<Symbol> {
+    let arena = Bump::new();
+    let ast = Ast::parse(&arena, source).ok()?;
+
+    ast.module_level_names()
+        .iter()
+        .find(|name| name.region.contains_pos(position))
+        .and_then(|name| resolve_name(module, name))
+}
+
+/// Finds the symbol for a module-level `name` defined in `module_id`.
+pub(super) fn module_level_symbol(
+    module: &AnalyzedModule,
+    module_id: ModuleId,
+    name: &str,
+) -> Option<Symbol> {
+    let ident_id = module.interns.all_ident_ids.get(&module_id)?.get_id(name)?;
+
+    Some(Symbol::new(module_id, ident_id))
+}
+
+fn resolve_name(module: &AnalyzedModule, name: &ModuleLevelName) -> Option<Symbol> {
+    let module_id = match name.scope {
+        NameScope::Home => module.module_id,
+        NameScope::Module(module_name) => *module
+            .module_id_to_url
+            .keys()
+            .find(|id| id.to_ident_str(&module.interns).as_str() == module_name)?,
+    };
+
+    module_level_symbol(module, module_id, name.name)
+}
+
+/// Whether `new_name` can replace `old_name`, which means it has to be a valid identifier of
+/// the same kind, i.e. a value can't be renamed to something that starts with a capital.
+pub(super) fn is_valid_rename(old_name: &str, new_name: &str) -> bool {
+    let arena = Bump::new();
+    let state = State::new(new_name.as_bytes());
+
+    let parsed = if old_name.starts_with(|c: char| c.is_uppercase()) {
+        uppercase_ident().parse(&arena, state, 0)
+    } else {
+        lowercase_ident().parse(&arena, state, 0)
+    };
+
+    matches!(parsed, Ok((_, ident, _)) if ident.len() == new_name.len())
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Canonical regions can span more than the identifier itself, e.g. `Foo.bar` for a qualified
+/// lookup or `x as y` for an `as` pattern, so this looks for the identifier within the region.
+fn narrow_to_ident(source: &str, region: Region, name: &str) -> Region {
+    let start = region.start().byte_offset();
+    let Some(text) = source.get(start..region.end().byte_offset()) else {
+        return region;
+    };
+
+    if text == name {
+        return region;
+    }
+
+    let mut occurrences = text.match_indices(name).filter(|(index, _)| {
+        let before = text[..*index].chars().next_back();
+        let after = text[index + name.len()..].chars().next();
+
+        !before.is_some_and(is_ident_char) && !after.is_some_and(is_ident_char)
+    });
+
+    let occurrence = if text.ends_with(name) {
+        occurrences.last()
+    } else {
+        occurrences.next()
+    };
+
+    match occurrence {
+        Some((index, _)) => {
+            let start = region.start().bump_column(index as u32);
+            Region::new(start, start.bump_column(name.len() as u32))
+        }
+        None => region,
+    }
+}
+
+/// Annotations only store the region of their signature, so this finds the `name` in
+/// `name : Signature` by walking backwards over the colon.
+fn annotated_name_region(source: &str, signature: Region, name: &str) -> Option<Region> {
+    let before_signature = source.get(..signature.start().byte_offset())?;
+    let before_colon = before_signature.trim_end().strip_suffix(':')?.trim_end();
+    let before_name = before_colon.strip_suffix(name)?;
+
+    if before_name.ends_with(is_ident_char) {
+        return None;
+    }
+
+    let start = Position::new(before_name.len() as u32);
+
+    Some(Region::new(start, start.bump_column(name.len() as u32)))
+}
@@ -0,0 +1,186 @@
+//! Signatures of the functions being called, shown while typing their arguments.
+
+use roc_can::{
+    expr::{Declarations, Expr},
+    traverse::{walk_expr, Visitor},
+};
+use roc_module::symbol::Symbol;
+use roc_region::all::{Position, Region};
+use roc_types::subs::Variable;
+
+use super::{utils::format_var_type, AnalyzedModule};
+
+/// A function being called at the position we are looking at.
+pub(super) struct FoundCall {
+    pub symbol: Symbol,
+    pub fn_var: Variable,
+    /// The index of the argument being written.
+    pub active_argument: u32,
+}
+
+struct Candidate {
+    region: Region,
+    symbol: Symbol,
+    fn_var: Variable,
+    args: Option<Vec<Region>>,
+}
+
+struct CallsAt<'a> {
+    source: &'a str,
+    position: Position,
+    found: Vec<Candidate>,
+}
+
+impl CallsAt<'_> {
+    /// Whether the position is within `region`, or after it with only spaces in between,
+    /// which is where the next argument would be written.
+    fn is_at(&self, region: Region) -> bool {
+        if region.contains_pos(self.position) {
+            return true;
+        }
+
+        region.end() <= self.position
+            && self
+                .source
+                .get(region.end().byte_offset()..self.position.byte_offset())
+                .is_some_and(|between| between.chars().all(|c| c == ' ' || c == '\t'))
+    }
+}
+
+impl Visitor for CallsAt<'_> {
+    fn should_visit(&mut self, region: Region) -> bool {
+        self.is_at(region)
+    }
+
+    fn visit_expr(&mut self, expr: &Expr, region: Region, var: Variable) {
+        if !self.is_at(region) {
+            return;
+        }
+
+        match expr {
+            Expr::Call(f, args, _) => {
+                let (fn_var, loc_fn, _, _) = &**f;
+
+                if let Expr::Var(symbol, _) = loc_fn.value {
+                    self.found.push(Candidate {
+                        region,
+                        symbol,
+                        fn_var: *fn_var,
+                        args: Some(args.iter().map(|(_, arg)| arg.region).collect()),
+                    });
+                }
+            }
+            Expr::Var(symbol, _) => self.found.push(Candidate {
+                region,
+                symbol: *symbol,
+                fn_var: var,
+                args: None,
+            }),
+            _ => {}
+        }
+
+        walk_expr(self, expr, var);
+    }
+}
+
+/// Finds the innermost function call whose arguments are being written at `position`.
+///
+/// A function that is mentioned on its own counts too, since that is what a call looks like
+/// before its first argument has been written.
+pub(super) fn call_at(
+    source: &str,
+    position: Position,
+    declarations: &Declarations,
+) -> Option<FoundCall> {
+    let mut visitor = CallsAt {
+        source,
+        position,
+        found: vec![],
+    };
+    visitor.visit_decls(declarations);
+
+    let calls = &visitor.found;
+    let is_part_of_call = |region: Region| {
+        calls.iter().any(|call| {
+            call.args
+                .as_ref()
+                .is_some_and(|args| region.start() == call.region.start() || args.contains(&region))
+        })
+    };
+
+    let candidate = calls
+        .iter()
+        .filter(|candidate| candidate.args.is_some() || !is_part_of_call(candidate.region))
+        .max_by_key(|candidate| candidate.region.start())?;
+
+    let active_argument = match &candidate.args {
+        Some(args) => args.iter().filter(|arg| arg.end() < position).count(),
+        None => 0,
+    };
+
+    Some(FoundCall {
+        symbol: candidate.symbol,
+        fn_var: candidate.fn_var,
+        active_argument: active_argument as u32,
+    })
+}
+
+/// The type `symbol` was defined with. At the call site its type has been unified with the
+/// arguments, which leaves a type mismatch when there are fewer of them than parameters.
+pub(super) fn defined_type(module: &AnalyzedModule, symbol: Symbol) -> Option<String> {
+    let module_id = symbol.module_id();
+
+    if module_id == module.module_id {
+        let declarations = &module.declarations;
+        let index = declarations
+            .symbols
+            .iter()
+            .position(|loc_symbol| loc_symbol.value == symbol)?;
+        let var = declarations.variables[index];
+
+        return Some(format_var_type(
+            var,
+            &mut module.subs.clone(),
+            &module_id,
+            &module.interns,
+        ));
+    }
+
+    let (_, var) = module
+        .modules_info
+        .exposed_by_module
+        .get(&module_id)?
+        .iter()
+        .find(|(exposed, _)| *exposed == symbol)?;
+
+    module.modules_info.with_subs(&module_id, |subs| {
+        format_var_type(*var, subs, &module_id, &module.interns)
+    })
+}
+
+/// Finds the byte offsets of the parameters in a printed function type like
+/// `Str, List a -> Str`, ignoring commas and arrows nested in other types.
+pub(super) fn parameter_offsets(type_str: &str) -> Vec<[u32; 2]> {
+    let mut depth = 0;
+    let mut params = vec![];
+    let mut start = 0;
+
+    for (index, c) in type_str.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                params.push([start as u32, index as u32]);
+                start = index + 2;
+            }
+            '-' if depth == 0 && type_str[index..].starts_with("-> ") => {
+                params.push([start as u32, index.saturating_sub(1) as u32]);
+                return params;
+            }
+            _ => {}
+        }
+    }
+
+    // Not a function type
+    vec![]
+}
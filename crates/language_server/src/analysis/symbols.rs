@@ -0,0 +1,121 @@
+//! Outlines of the definitions in a module, for document and workspace symbols.
+
+use bumpalo::Bump;
+use roc_can::{
+    expr::Declarations,
+    traverse::{DeclarationInfo, Visitor},
+};
+use roc_module::symbol::{Interns, ModuleId};
+use roc_parse::ast::TypeDef;
+use roc_region::all::{LineInfo, Region};
+use roc_types::subs::{Subs, Variable};
+use tower_lsp::lsp_types::{DocumentSymbol, SymbolKind};
+
+use crate::convert::ToRange;
+
+use super::{parse_ast::Ast, utils::format_var_type};
+
+struct TopLevelValue {
+    name: String,
+    kind: SymbolKind,
+    region: Region,
+    name_region: Region,
+    var: Variable,
+}
+
+struct TopLevelValues<'a> {
+    interns: &'a Interns,
+    found: Vec<TopLevelValue>,
+}
+
+impl Visitor for TopLevelValues<'_> {
+    fn visit_decl(&mut self, decl: DeclarationInfo<'_>) {
+        let (loc_symbol, var, kind) = match &decl {
+            DeclarationInfo::Value {
+                loc_symbol,
+                expr_var,
+                ..
+            } => (loc_symbol, *expr_var, SymbolKind::CONSTANT),
+            DeclarationInfo::Function {
+                loc_symbol,
+                expr_var,
+                ..
+            } => (loc_symbol, *expr_var, SymbolKind::FUNCTION),
+            DeclarationInfo::Expectation { .. } | DeclarationInfo::Destructure { .. } => return,
+        };
+
+        self.found.push(TopLevelValue {
+            name: loc_symbol.value.as_str(self.interns).to_string(),
+            kind,
+            region: decl.region(),
+            name_region: loc_symbol.region,
+            var,
+        });
+    }
+}
+
+/// The top-level values and types defined in a module, in the order they are defined.
+#[allow(deprecated)] // `DocumentSymbol::deprecated` has to be set even though it is deprecated
+pub(super) fn document_symbols(
+    source: &str,
+    line_info: &LineInfo,
+    declarations: &Declarations,
+    subs: &Subs,
+    module_id: &ModuleId,
+    interns: &Interns,
+) -> Vec<DocumentSymbol> {
+    let mut values = TopLevelValues {
+        interns,
+        found: vec![],
+    };
+    values.visit_decls(declarations);
+
+    let mut subs = subs.clone();
+    let mut symbols: Vec<(Region, DocumentSymbol)> = values
+        .found
+        .into_iter()
+        .map(|value| {
+            let detail = format_var_type(value.var, &mut subs, module_id, interns);
+
+            let symbol = DocumentSymbol {
+                name: value.name,
+                detail: Some(detail),
+                kind: value.kind,
+                tags: None,
+                deprecated: None,
+                range: value.region.to_range(line_info),
+                selection_range: value.name_region.to_range(line_info),
+                children: None,
+            };
+
+            (value.region, symbol)
+        })
+        .collect();
+
+    let arena = Bump::new();
+    if let Ok(ast) = Ast::parse(&arena, source) {
+        symbols.extend(ast.type_defs().map(|(type_def, region)| {
+            let (header, kind) = match type_def {
+                TypeDef::Alias { header, .. } => (header, SymbolKind::STRUCT),
+                TypeDef::Opaque { header, .. } => (header, SymbolKind::CLASS),
+                TypeDef::Ability { header, .. } => (header, SymbolKind::INTERFACE),
+            };
+
+            let symbol = DocumentSymbol {
+                name: header.name.value.to_string(),
+                detail: None,
+                kind,
+                tags: None,
+                deprecated: None,
+                range: region.to_range(line_info),
+                selection_range: header.name.region.to_range(line_info),
+                children: None,
+            };
+
+            (region, symbol)
+        }));
+    }
+
+    symbols.sort_by_key(|(region, _)| region.start());
+    symbols.into_iter().map(|(_, symbol)| symbol).collect()
+}
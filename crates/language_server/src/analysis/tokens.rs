@@ -403,7 +403,23 @@ impl IterTokens for Loc<TypeAnnotation<'_>> {
             TypeAnnotation::Function(params, ret) => (params.iter_tokens(arena).into_iter())
                 .chain(ret.iter_tokens(arena))
                 .collect_in(arena),
-            TypeAnnotation::Apply(_mod, _type, args) => args.iter_tokens(arena),
+            TypeAnnotation::Apply(module, name, args) => {
+                // The applied name is always at the start of the annotation, e.g. `Dict.Dict k v`
+                let qualifier_len = if module.is_empty() {
+                    0
+                } else {
+                    module.len() + 1
+                };
+                let name_len = (qualifier_len + name.len()) as u32;
+                let name_region = Region::new(
+                    self.region.start(),
+                    self.region.start().bump_column(name_len),
+                );
+
+                (onetoken(Token::Type, name_region, arena).into_iter())
+                    .chain(args.iter_tokens(arena))
+                    .collect_in(arena)
+            }
             TypeAnnotation::BoundVariable(_) => onetoken(Token::Type, self.region, arena),
             TypeAnnotation::As(ty, _, as_ty) => (ty.iter_tokens(arena).into_iter())
                 .chain(as_ty.iter_tokens(arena))
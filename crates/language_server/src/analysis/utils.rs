@@ -1,5 +1,9 @@
 use roc_module::symbol::{Interns, ModuleId};
-use roc_types::subs::{Subs, Variable};
+use roc_reporting::{error::r#type::error_type_to_doc, report::RocDocAllocator};
+use roc_types::{
+    subs::{Subs, Variable},
+    types::Polarity,
+};
 
 pub(super) fn format_var_type(
     var: Variable,
@@ -19,6 +23,30 @@ pub(super) fn format_var_type(
     type_str
 }
 
+/// Prints the type of `var` the way error messages do, which is valid annotation syntax.
+/// Returns `None` if the type contains an error.
+pub(super) fn format_var_annotation(
+    var: Variable,
+    subs: &mut Subs,
+    module_id: &ModuleId,
+    interns: &Interns,
+) -> Option<String> {
+    let snapshot = subs.snapshot();
+    let error_type = subs.var_to_error_type(var, Polarity::OF_VALUE);
+    subs.rollback_to(snapshot);
+
+    // Wide enough that the type is printed on one line
+    let alloc = RocDocAllocator::new(&[], *module_id, interns);
+    let mut type_str = String::new();
+    error_type_to_doc(&alloc, error_type)
+        .1
+        .render_fmt(10_000, &mut type_str)
+        .ok()?;
+
+    // Errors in the type are printed as `?`
+    (!type_str.contains('?')).then_some(type_str)
+}
+
 pub(super) fn is_roc_identifier_char(char: &char) -> bool {
     matches!(char,'a'..='z'|'A'..='Z'|'0'..='9'|'.')
 }
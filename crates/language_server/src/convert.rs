@@ -1,6 +1,8 @@
 use roc_region::all::{LineColumn, LineColumnRegion, LineInfo, Region};
 use tower_lsp::lsp_types::{Position, Range};
 
+mod quick_fix;
+
 pub(crate) trait ToRange {
     type Feed;
 
@@ -69,7 +71,7 @@ impl ToRocPosition for tower_lsp::lsp_types::Position {
 }
 
 pub(crate) mod diag {
-    use std::path::Path;
+    use std::{collections::HashMap, path::Path};
 
     use roc_load::LoadingProblem;
     use roc_region::all::{LineInfo, Region};
@@ -77,9 +79,12 @@ pub(crate) mod diag {
 
     use roc_problem::Severity;
     use roc_reporting::report::RocDocAllocator;
-    use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range};
+    use tower_lsp::lsp_types::{
+        CodeAction, CodeActionKind, Diagnostic, DiagnosticSeverity, Position, Range, TextEdit, Url,
+        WorkspaceEdit,
+    };
 
-    use super::ToRange;
+    use super::{quick_fix::can_problem_fixes, ToRange};
 
     pub trait IntoLspSeverity {
         fn into_lsp_severity(self) -> DiagnosticSeverity;
@@ -186,6 +191,54 @@ pub(crate) mod diag {
         pub alloc: &'a RocDocAllocator<'a>,
         pub line_info: &'a LineInfo,
         pub path: &'a Path,
+        pub url: &'a Url,
+        pub source: &'a str,
+    }
+
+    pub trait ToLspCodeActions<'a> {
+        type Feed;
+
+        /// The quick fixes for this problem, attached to the diagnostic it was reported as.
+        fn to_lsp_code_actions(
+            &self,
+            diagnostic: &Diagnostic,
+            feed: &'a Self::Feed,
+        ) -> Vec<CodeAction>;
+    }
+
+    impl<'a> ToLspCodeActions<'a> for roc_problem::can::Problem {
+        type Feed = ProblemFmt<'a>;
+
+        fn to_lsp_code_actions(
+            &self,
+            diagnostic: &Diagnostic,
+            fmt: &'a ProblemFmt<'a>,
+        ) -> Vec<CodeAction> {
+            can_problem_fixes(self, fmt.source, fmt.alloc.interns)
+                .into_iter()
+                .map(|fix| {
+                    let edits = fix
+                        .edits
+                        .into_iter()
+                        .map(|(region, new_text)| {
+                            TextEdit::new(region.to_range(fmt.line_info), new_text)
+                        })
+                        .collect();
+
+                    CodeAction {
+                        title: fix.title,
+                        kind: Some(CodeActionKind::QUICKFIX),
+                        diagnostics: Some(vec![diagnostic.clone()]),
+                        edit: Some(WorkspaceEdit::new(HashMap::from([(
+                            fmt.url.clone(),
+                            edits,
+                        )]))),
+                        is_preferred: Some(fix.preferred),
+                        ..CodeAction::default()
+                    }
+                })
+                .collect()
+        }
     }
 
     impl<'a> IntoLspDiagnostic<'a> for roc_problem::can::Problem {
@@ -0,0 +1,382 @@
+//! Edits that resolve problems found by the compiler, offered as quick fixes.
+
+use bumpalo::Bump;
+use roc_module::symbol::{Interns, Symbol};
+use roc_parse::{
+    ast::{Defs, ValueDef},
+    module::{parse_header, parse_module_defs},
+    state::State,
+};
+use roc_problem::can::{Problem, RuntimeError};
+use roc_region::all::{Position, Region};
+use roc_reporting::error::r#type::suggest;
+
+/// How many alternative names we suggest, matching the reports.
+const MAX_SUGGESTIONS: usize = 4;
+
+pub(crate) struct QuickFix {
+    pub title: String,
+    pub edits: Vec<(Region, String)>,
+    /// Whether this is the obvious fix for the problem, which editors may apply automatically.
+    pub preferred: bool,
+}
+
+impl QuickFix {
+    fn new(title: String, region: Region, new_text: impl Into<String>) -> Self {
+        Self {
+            title,
+            edits: vec![(region, new_text.into())],
+            preferred: true,
+        }
+    }
+
+    fn alternatives(fixes: impl Iterator<Item = QuickFix>) -> Vec<QuickFix> {
+        fixes
+            .enumerate()
+            .map(|(index, fix)| QuickFix {
+                preferred: index == 0,
+                ..fix
+            })
+            .collect()
+    }
+}
+
+pub(crate) fn can_problem_fixes(
+    problem: &Problem,
+    source: &str,
+    interns: &Interns,
+) -> Vec<QuickFix> {
+    let name = |symbol: &Symbol| symbol.as_str(interns).to_string();
+
+    match problem {
+        Problem::UnusedImport(symbol, region) => vec![QuickFix::new(
+            format!("Remove unused import `{}`", name(symbol)),
+            remove_list_item(source, *region),
+            "",
+        )],
+        Problem::UnusedModuleImport(module_id, region)
+        | Problem::ExplicitBuiltinImport(module_id, region) => vec![QuickFix::new(
+            format!("Remove import of `{}`", module_id.to_ident_str(interns)),
+            remove_import(source, *region),
+            "",
+        )],
+        Problem::UnusedArgument(_, _, argument, region) => {
+            let argument = name(argument);
+
+            if can_prefix_with_underscore(source, *region, &argument) {
+                vec![QuickFix::new(
+                    format!("Prefix `{}` with an underscore", argument),
+                    Region::new(region.start(), region.start()),
+                    "_",
+                )]
+            } else {
+                vec![]
+            }
+        }
+        Problem::UnusedDef(symbol, region) => {
+            let def = name(symbol);
+
+            match remove_def(source, *region, &def) {
+                Some(def_region) => vec![QuickFix::new(
+                    format!("Remove unused definition `{}`", def),
+                    def_region,
+                    "",
+                )],
+                None => vec![],
+            }
+        }
+        Problem::UnnecessaryOutputWildcard { region } => vec![QuickFix::new(
+            "Remove unnecessary wildcard".to_string(),
+            *region,
+            "",
+        )],
+        Problem::RuntimeError(runtime_error) => runtime_error_fixes(runtime_error, source),
+        _ => vec![],
+    }
+}
+
+fn runtime_error_fixes(runtime_error: &RuntimeError, source: &str) -> Vec<QuickFix> {
+    match runtime_error {
+        RuntimeError::LookupNotInScope {
+            loc_name,
+            suggestion_options,
+            underscored_suggestion_region,
+        } => {
+            let name = loc_name.value.as_str();
+
+            let remove_underscore = underscored_suggestion_region
+                .filter(|region| text_at(source, *region).starts_with('_'))
+                .map(|region| {
+                    QuickFix::new(
+                        format!("Remove the leading underscore from `_{}`", name),
+                        Region::new(region.start(), region.start().bump_column(1)),
+                        "",
+                    )
+                });
+
+            // Only suggest names of the same kind, e.g. not types for a value
+            let is_uppercase = |name: &str| name.starts_with(char::is_uppercase);
+            let mut suggestions = suggest::sort(
+                name,
+                suggestion_options
+                    .iter()
+                    .map(|v| v.as_ref())
+                    .filter(|option| is_uppercase(option) == is_uppercase(name))
+                    .collect(),
+            );
+            suggestions.truncate(MAX_SUGGESTIONS);
+
+            let change_name = suggestions.into_iter().map(|suggestion| {
+                QuickFix::new(
+                    format!("Change to `{}`", suggestion),
+                    loc_name.region,
+                    suggestion,
+                )
+            });
+
+            QuickFix::alternatives(remove_underscore.into_iter().chain(change_name))
+        }
+        RuntimeError::ValueNotExposed {
+            ident,
+            region,
+            exposed_values,
+            ..
+        } => {
+            let ident = ident.as_str();
+            let Some(ident_region) = region_of_suffix(source, *region, ident) else {
+                return vec![];
+            };
+
+            let mut suggestions = suggest::sort(ident, exposed_values.iter().collect());
+            suggestions.truncate(MAX_SUGGESTIONS);
+
+            QuickFix::alternatives(suggestions.into_iter().map(|suggestion| {
+                QuickFix::new(
+                    format!("Change to `{}`", suggestion),
+                    ident_region,
+                    suggestion.as_str(),
+                )
+            }))
+        }
+        RuntimeError::ModuleNotImported {
+            module_name,
+            imported_modules,
+            region,
+            module_exists,
+        } => {
+            let module_name = module_name.as_str();
+
+            if *module_exists {
+                return match import_insertion_point(source) {
+                    Some(position) => vec![QuickFix::new(
+                        format!("Add `import {}`", module_name),
+                        Region::new(position, position),
+                        format!("\nimport {}", module_name),
+                    )],
+                    None => vec![],
+                };
+            }
+
+            if !text_at(source, *region).starts_with(module_name) {
+                return vec![];
+            }
+            let module_region = Region::new(
+                region.start(),
+                region.start().bump_column(module_name.len() as u32),
+            );
+
+            let mut suggestions = suggest::sort(
+                module_name,
+                imported_modules.iter().map(|v| v.as_ref()).collect(),
+            );
+            suggestions.truncate(MAX_SUGGESTIONS);
+
+            QuickFix::alternatives(suggestions.into_iter().map(|suggestion| {
+                QuickFix::new(
+                    format!("Change to `{}`", suggestion),
+                    module_region,
+                    suggestion,
+                )
+            }))
+        }
+        _ => vec![],
+    }
+}
+
+fn text_at(source: &str, region: Region) -> &str {
+    source
+        .get(region.start().byte_offset()..region.end().byte_offset())
+        .unwrap_or_default()
+}
+
+fn region_from_offsets(start: usize, end: usize) -> Region {
+    Region::new(Position::new(start as u32), Position::new(end as u32))
+}
+
+/// The region of `suffix` at the end of `region`, e.g. `bar` in `Foo.bar`.
+fn region_of_suffix(source: &str, region: Region, suffix: &str) -> Option<Region> {
+    if !text_at(source, region).ends_with(suffix) {
+        return None;
+    }
+
+    let end = region.end().byte_offset();
+    Some(region_from_offsets(end - suffix.len(), end))
+}
+
+fn line_start(source: &str, offset: usize) -> usize {
+    source[..offset].rfind('\n').map_or(0, |index| index + 1)
+}
+
+fn next_line_start(source: &str, offset: usize) -> usize {
+    source[offset..]
+        .find('\n')
+        .map_or(source.len(), |index| offset + index + 1)
+}
+
+/// Removes an item from a comma-separated list like `exposing [a, b]`, along with the comma
+/// that separates it from its neighbour.
+fn remove_list_item(source: &str, region: Region) -> Region {
+    let start = region.start().byte_offset();
+    let end = region.end().byte_offset();
+
+    let after = &source[end..];
+    let after_item = after.trim_start();
+    if let Some(after_comma) = after_item.strip_prefix(',') {
+        let next_item = source.len() - after_comma.trim_start().len();
+        return region_from_offsets(start, next_item);
+    }
+
+    let before_item = source[..start].trim_end();
+    if before_item.ends_with(',') {
+        return region_from_offsets(before_item.len() - 1, end);
+    }
+
+    region
+}
+
+/// Removes an `import` statement and the lines it's on, or an entry from a header's `imports`.
+fn remove_import(source: &str, region: Region) -> Region {
+    let start = region.start().byte_offset();
+
+    if text_at(source, region).starts_with("import") && is_line_start(source, start) {
+        region_from_offsets(
+            line_start(source, start),
+            next_line_start(source, region.end().byte_offset()),
+        )
+    } else {
+        remove_list_item(source, region)
+    }
+}
+
+fn is_line_start(source: &str, offset: usize) -> bool {
+    source[line_start(source, offset)..offset].trim().is_empty()
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Finds the lines of the definition of `name` starting at `region`, including its annotation.
+///
+/// Canonicalization only gives us the region of the name, so this relies on the body being
+/// indented further than the name, which is how the formatter lays out definitions.
+fn remove_def(source: &str, region: Region, name: &str) -> Option<Region> {
+    let start = region.start().byte_offset();
+    if text_at(source, region) != name || !is_line_start(source, start) {
+        return None;
+    }
+
+    let rest_of_line = &source[region.end().byte_offset()..next_line_start(source, start)];
+    if !rest_of_line.trim_start().starts_with('=') {
+        return None;
+    }
+
+    let def_line_start = line_start(source, start);
+    let indent = start - def_line_start;
+
+    // Include the annotation if it's on the line just above the definition
+    let mut remove_start = def_line_start;
+    if def_line_start > 0 {
+        let annotation_line_start = line_start(source, def_line_start - 1);
+        let annotation_line = &source[annotation_line_start..def_line_start];
+
+        if indentation(annotation_line) == indent
+            && annotation_line
+                .trim_start()
+                .strip_prefix(name)
+                .is_some_and(|rest| rest.trim_start().starts_with(':'))
+        {
+            remove_start = annotation_line_start;
+        }
+    }
+
+    // The definition goes on until the next line that isn't indented more than it
+    let mut remove_end = next_line_start(source, start);
+    let mut offset = remove_end;
+    while offset < source.len() {
+        let next = next_line_start(source, offset);
+        let line = &source[offset..next];
+
+        if !line.trim().is_empty() {
+            if indentation(line) <= indent {
+                break;
+            }
+            remove_end = next;
+        }
+
+        offset = next;
+    }
+
+    Some(region_from_offsets(remove_start, remove_end))
+}
+
+/// Record fields can't be renamed in a destructure, so we only prefix arguments that aren't
+/// part of one.
+fn can_prefix_with_underscore(source: &str, region: Region, name: &str) -> bool {
+    if text_at(source, region) != name {
+        return false;
+    }
+
+    let before = &source[..region.start().byte_offset()];
+
+    // The argument a record field is destructured into, like `x` in `{ a: x }`
+    if before.trim_end().ends_with(':') {
+        return true;
+    }
+
+    let mut depth = 0;
+    for c in before.chars().rev() {
+        match c {
+            ')' | ']' | '}' => depth += 1,
+            '(' | '[' if depth == 0 => return true,
+            '{' if depth == 0 => return false,
+            '(' | '[' | '{' => depth -= 1,
+            '\\' | '\n' if depth == 0 => return true,
+            _ => {}
+        }
+    }
+
+    true
+}
+
+/// Finds where to add an `import`: after the last `import` statement, or after the header.
+fn import_insertion_point(source: &str) -> Option<Position> {
+    let arena = Bump::new();
+    let (_, state) = parse_header(&arena, State::new(source.as_bytes())).ok()?;
+
+    let header_end = source[..state.pos().byte_offset()].trim_end().len();
+
+    let last_import_end = parse_module_defs(&arena, state, Defs::default())
+        .ok()
+        .and_then(|defs| {
+            defs.defs()
+                .zip(defs.regions.iter())
+                .filter(|(def, _)| matches!(def, Err(ValueDef::ModuleImport(_))))
+                .map(|(_, region)| region.end().byte_offset())
+                .max()
+        });
+
+    Some(Position::new(
+        last_import_end.unwrap_or(header_end).max(header_end) as u32,
+    ))
+}
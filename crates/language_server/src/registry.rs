@@ -1,7 +1,7 @@
 use log::{debug, info, trace};
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     sync::{Arc, OnceLock},
     time::Duration,
 };
@@ -9,8 +9,9 @@ use std::{
 use tokio::sync::{Mutex, MutexGuard};
 
 use tower_lsp::lsp_types::{
-    CompletionResponse, Diagnostic, GotoDefinitionResponse, Hover, Position, SemanticTokensResult,
-    TextEdit, Url,
+    CodeActionResponse, CompletionResponse, Diagnostic, DocumentSymbol, GotoDefinitionResponse,
+    Hover, InlayHint, Location, Position, PrepareRenameResponse, Range, SemanticTokensResult,
+    SignatureHelp, SymbolInformation, TextDocumentContentChangeEvent, TextEdit, Url, WorkspaceEdit,
 };
 
 use crate::analysis::{AnalyzedDocument, DocInfo};
@@ -33,6 +34,30 @@ impl DocumentPair {
             last_good_document,
         }
     }
+
+    /// Starts tracking a new version of the document's contents. The last analysis is kept
+    /// if the contents didn't change, so we don't have to analyse it again.
+    fn set_info(&mut self, info: DocInfo) {
+        let latest_document = match self.latest_document.get() {
+            Some(document) if document.doc_info.source == info.source => {
+                OnceLock::from(document.clone())
+            }
+            _ => OnceLock::new(),
+        };
+
+        *self = DocumentPair {
+            info,
+            latest_document,
+            last_good_document: self.last_good_document.clone(),
+        };
+    }
+
+    /// Whether we have already analysed this exact source for the document.
+    fn is_analysed(&self, source: &str) -> bool {
+        self.latest_document
+            .get()
+            .is_some_and(|document| document.doc_info.source == source)
+    }
 }
 
 #[derive(Debug)]
@@ -114,12 +139,66 @@ impl Registry {
             updating_url.to_string()
         );
 
+        let replaced = Registry::analyses_to_replace(&documents, &analysed_docs, &updating_url);
+
         for document in analysed_docs {
+            if !replaced.contains(document.url()) {
+                trace!(
+                    "Keeping previous analysis for {:?}",
+                    document.url().as_str()
+                );
+                continue;
+            }
+
             let document = Arc::new(document);
             Registry::update_document(&mut documents, document, &updating_url);
         }
     }
 
+    /// Finds the modules whose new analysis could differ from the one we already have: the module
+    /// we analysed, any module whose source changed, and every module that imports one of those.
+    /// The other modules keep their previous analysis (and the documents already handed out for it).
+    ///
+    /// This mirrors the modules `global_analysis` takes from its `AnalysisCache` rather than
+    /// checking them again.
+    fn analyses_to_replace(
+        documents: &HashMap<Url, DocumentPair>,
+        analysed_docs: &[AnalyzedDocument],
+        updating_url: &Url,
+    ) -> HashSet<Url> {
+        let mut replaced: HashSet<Url> = analysed_docs
+            .iter()
+            .filter(|document| {
+                document.url() == updating_url
+                    || !documents
+                        .get(document.url())
+                        .is_some_and(|pair| pair.is_analysed(&document.doc_info.source))
+            })
+            .map(|document| document.url().clone())
+            .collect();
+
+        let imports: Vec<_> = analysed_docs
+            .iter()
+            .map(|document| (document.url(), document.imported_urls()))
+            .collect();
+
+        loop {
+            let dependents: Vec<_> = imports
+                .iter()
+                .filter(|(url, imported)| {
+                    !replaced.contains(*url) && imported.iter().any(|url| replaced.contains(url))
+                })
+                .map(|(url, _)| (*url).clone())
+                .collect();
+
+            if dependents.is_empty() {
+                return replaced;
+            }
+
+            replaced.extend(dependents);
+        }
+    }
+
     pub async fn apply_doc_info_changes(&self, url: Url, info: DocInfo) {
         let mut documents_lock = self.documents.lock().await;
         let doc = documents_lock.get_mut(&url);
@@ -130,14 +209,117 @@ impl Registry {
                     url.as_str(),
                     info.version
                 );
-                *a = DocumentPair {
-                    info,
-                    last_good_document: a.last_good_document.clone(),
-                    latest_document: OnceLock::new(),
-                };
+                a.set_info(info);
+            }
+            None => {
+                debug!("Added docInfo for {:?}", url.as_str());
+                // Nothing can use the document until it is analysed, but we need to know its
+                // contents so later incremental changes can be applied to them.
+                let document = Arc::new(AnalyzedDocument::unanalyzed(info.clone()));
+                documents_lock.insert(
+                    url,
+                    DocumentPair {
+                        info,
+                        latest_document: OnceLock::new(),
+                        last_good_document: document,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Applies the changes sent by the editor to the latest contents of the document.
+    /// Returns `None` if we don't know the document yet.
+    pub async fn apply_text_changes(
+        &self,
+        url: &Url,
+        changes: Vec<TextDocumentContentChangeEvent>,
+        version: i32,
+    ) -> Option<DocInfo> {
+        let mut documents = self.documents.lock().await;
+        let pair = documents.get_mut(url)?;
+
+        let info = changes.into_iter().fold(pair.info.clone(), |info, change| {
+            info.apply_change(change, version)
+        });
+        debug!(
+            "Applied changes to {:?}, now at version:{:?}",
+            url.as_str(),
+            version
+        );
+
+        pair.set_info(info.clone());
+        Some(info)
+    }
+
+    /// Whether the latest contents of the document have already been analysed.
+    pub async fn is_analysed(&self, url: &Url) -> bool {
+        self.documents
+            .lock()
+            .await
+            .get(url)
+            .is_some_and(|pair| pair.is_analysed(&pair.info.source))
+    }
+
+    /// Throws away the latest analysis of the document, so it will be analysed again.
+    /// Returns the latest contents of the document.
+    pub async fn invalidate(&self, url: &Url) -> Option<DocInfo> {
+        let mut documents = self.documents.lock().await;
+        let pair = documents.get_mut(url)?;
+
+        pair.latest_document = OnceLock::new();
+        Some(pair.info.clone())
+    }
+
+    /// Finds the documents that have to be analysed again to pick up changes to `url`: the
+    /// ones that import it, directly or not, and aren't themselves imported by another one.
+    /// Analysing those will also reanalyse all of the dependents in between.
+    pub async fn dependents_to_reanalyse(&self, url: &Url) -> Vec<Url> {
+        let documents = self.documents.lock().await;
+
+        let imports: HashMap<&Url, Vec<Url>> = documents
+            .iter()
+            .map(|(url, pair)| {
+                let document = pair
+                    .latest_document
+                    .get()
+                    .filter(|document| document.type_checked())
+                    .unwrap_or(&pair.last_good_document);
+                (url, document.imported_urls())
+            })
+            .collect();
+
+        let mut dependents: HashSet<&Url> = HashSet::new();
+        loop {
+            let new_dependents: Vec<_> = imports
+                .iter()
+                .filter(|(importer, imported)| {
+                    !dependents.contains(*importer)
+                        && imported
+                            .iter()
+                            .any(|imported| imported == url || dependents.contains(imported))
+                })
+                .map(|(importer, _)| *importer)
+                .collect();
+
+            if new_dependents.is_empty() {
+                break;
             }
-            None => debug!("So existing docinfo for {:?} ", url.as_str()),
+
+            dependents.extend(new_dependents);
         }
+
+        dependents
+            .iter()
+            .filter(|dependent| {
+                !dependents.iter().any(|importer| {
+                    imports
+                        .get(importer)
+                        .is_some_and(|imported| imported.contains(dependent))
+                })
+            })
+            .map(|dependent| (*dependent).clone())
+            .collect()
     }
 
     async fn document_info_by_url(&self, url: &Url) -> Option<DocInfo> {
@@ -187,6 +369,206 @@ impl Registry {
         def_document.definition(symbol)
     }
 
+    /// Finds every mention of the symbol at `position`, across all of the documents we know
+    /// about if it can be referred to from other modules.
+    async fn symbol_occurrences(
+        &self,
+        url: &Url,
+        position: Position,
+        include_declaration: bool,
+    ) -> Option<HashMap<Url, Vec<Range>>> {
+        let document = self.latest_document_by_url(url).await?;
+        let symbol = document.referenced_symbol_at(position)?;
+
+        let mut occurrences = HashMap::new();
+        occurrences.insert(
+            url.clone(),
+            document.references(symbol, include_declaration),
+        );
+
+        if document.is_module_level(symbol) {
+            // Builtin symbols are the same in every analysis, everything else has to be
+            // looked up by the module it's defined in and its name.
+            let def_url = document.module_url(symbol.module_id());
+            let name = document.symbol_name(symbol)?;
+
+            let other_documents: Vec<_> = self
+                .documents
+                .lock()
+                .await
+                .iter()
+                .filter(|(other_url, _)| *other_url != url)
+                .map(|(_, pair)| {
+                    pair.latest_document
+                        .get()
+                        .filter(|doc| doc.type_checked())
+                        .unwrap_or(&pair.last_good_document)
+                        .clone()
+                })
+                .collect();
+
+            for other in other_documents {
+                let other_symbol = if symbol.module_id().is_builtin() {
+                    Some(symbol)
+                } else {
+                    def_url
+                        .as_ref()
+                        .and_then(|def_url| other.translate_symbol(def_url, name))
+                };
+
+                if let Some(other_symbol) = other_symbol {
+                    let ranges = other.references(other_symbol, include_declaration);
+                    if !ranges.is_empty() {
+                        occurrences.insert(other.url().clone(), ranges);
+                    }
+                }
+            }
+        }
+
+        Some(occurrences)
+    }
+
+    pub async fn references(
+        &self,
+        url: &Url,
+        position: Position,
+        include_declaration: bool,
+    ) -> Option<Vec<Location>> {
+        let occurrences = self
+            .symbol_occurrences(url, position, include_declaration)
+            .await?;
+
+        let mut locations: Vec<_> = occurrences
+            .into_iter()
+            .flat_map(|(uri, ranges)| {
+                ranges
+                    .into_iter()
+                    .map(move |range| Location::new(uri.clone(), range))
+            })
+            .collect();
+        locations
+            .sort_by(|a, b| (a.uri.as_str(), a.range.start).cmp(&(b.uri.as_str(), b.range.start)));
+
+        Some(locations)
+    }
+
+    pub async fn prepare_rename(
+        &self,
+        url: &Url,
+        position: Position,
+    ) -> Option<PrepareRenameResponse> {
+        let document = self.latest_document_by_url(url).await?;
+        let (range, placeholder) = document.prepare_rename(position)?;
+
+        Some(PrepareRenameResponse::RangeWithPlaceholder { range, placeholder })
+    }
+
+    /// Renames the symbol at `position` everywhere it's mentioned.
+    /// Fails if `new_name` isn't a valid name for that kind of symbol.
+    pub async fn rename(
+        &self,
+        url: &Url,
+        position: Position,
+        new_name: &str,
+    ) -> Result<Option<WorkspaceEdit>, String> {
+        let Some(document) = self.latest_document_by_url(url).await else {
+            return Ok(None);
+        };
+        let Some(symbol) = document.referenced_symbol_at(position) else {
+            return Ok(None);
+        };
+        if document.prepare_rename(position).is_none() {
+            return Ok(None);
+        }
+        if !document.is_valid_rename(symbol, new_name) {
+            return Err(format!("`{}` is not a valid name here", new_name));
+        }
+
+        let Some(occurrences) = self.symbol_occurrences(url, position, true).await else {
+            return Ok(None);
+        };
+
+        let changes = occurrences
+            .into_iter()
+            .filter(|(_, ranges)| !ranges.is_empty())
+            .map(|(uri, ranges)| {
+                let edits = ranges
+                    .into_iter()
+                    .map(|range| TextEdit::new(range, new_name.to_string()))
+                    .collect();
+                (uri, edits)
+            })
+            .collect();
+
+        Ok(Some(WorkspaceEdit::new(changes)))
+    }
+
+    pub async fn code_actions(&self, url: &Url, range: Range) -> Option<CodeActionResponse> {
+        let document = self.latest_document_by_url(url).await?;
+
+        Some(document.code_actions(range))
+    }
+
+    pub async fn signature_help(&self, url: &Url, position: Position) -> Option<SignatureHelp> {
+        self.latest_document_by_url(url)
+            .await?
+            .signature_help(position)
+    }
+
+    pub async fn inlay_hints(&self, url: &Url, range: Range) -> Option<Vec<InlayHint>> {
+        Some(self.latest_document_by_url(url).await?.inlay_hints(range))
+    }
+
+    pub async fn document_symbols(&self, url: &Url) -> Option<Vec<DocumentSymbol>> {
+        self.latest_document_by_url(url).await?.document_symbols()
+    }
+
+    /// Finds the top-level definitions in every document we know about whose names contain
+    /// `query`, ignoring case.
+    #[allow(deprecated)] // `SymbolInformation::deprecated` has to be set even though it is deprecated
+    pub async fn workspace_symbols(&self, query: &str) -> Vec<SymbolInformation> {
+        let documents: Vec<_> = self
+            .documents
+            .lock()
+            .await
+            .values()
+            .map(|pair| {
+                pair.latest_document
+                    .get()
+                    .filter(|doc| doc.type_checked())
+                    .unwrap_or(&pair.last_good_document)
+                    .clone()
+            })
+            .collect();
+
+        let query = query.to_lowercase();
+        let mut symbols = vec![];
+
+        for document in documents {
+            let module_name = document.module_name();
+
+            for symbol in document.document_symbols().into_iter().flatten() {
+                if symbol.name.to_lowercase().contains(&query) {
+                    symbols.push(SymbolInformation {
+                        name: symbol.name,
+                        kind: symbol.kind,
+                        tags: None,
+                        deprecated: None,
+                        location: Location::new(document.url().clone(), symbol.range),
+                        container_name: module_name.clone(),
+                    });
+                }
+            }
+        }
+
+        symbols.sort_by(|a, b| {
+            (a.location.uri.as_str(), a.location.range.start)
+                .cmp(&(b.location.uri.as_str(), b.location.range.start))
+        });
+
+        symbols
+    }
+
     pub async fn formatting(&self, url: &Url) -> Option<Vec<TextEdit>> {
         let document = self.document_info_by_url(url).await?;
         document.format()
@@ -1,15 +1,19 @@
 use analysis::HIGHLIGHT_TOKENS_LEGEND;
 
 use log::{debug, trace};
+use parking_lot::Mutex;
 use registry::{Registry, RegistryConfig};
 use std::future::Future;
 use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Arc;
 use std::time::Duration;
 
 use tower_lsp::jsonrpc::{self, Result};
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer, LspService, Server};
 
+use roc_load::AnalysisCache;
+
 use crate::analysis::{global_analysis, DocInfo};
 
 mod analysis;
@@ -37,6 +41,7 @@ impl Default for RocServerConfig {
 struct RocServerState {
     registry: Registry,
     config: RocServerConfig,
+    analysis_cache: Arc<Mutex<AnalysisCache>>,
 }
 
 impl std::panic::RefUnwindSafe for RocServer {}
@@ -64,14 +69,12 @@ impl RocServer {
     }
 
     pub fn capabilities() -> ServerCapabilities {
-        let text_document_sync = TextDocumentSyncCapability::Options(
-            // TODO: later on make this incremental
-            TextDocumentSyncOptions {
-                open_close: Some(true),
-                change: Some(TextDocumentSyncKind::FULL),
-                ..TextDocumentSyncOptions::default()
-            },
-        );
+        let text_document_sync = TextDocumentSyncCapability::Options(TextDocumentSyncOptions {
+            open_close: Some(true),
+            change: Some(TextDocumentSyncKind::INCREMENTAL),
+            save: Some(TextDocumentSyncSaveOptions::Supported(true)),
+            ..TextDocumentSyncOptions::default()
+        });
         let hover_provider = HoverProviderCapability::Simple(true);
         let definition_provider = DefinitionOptions {
             work_done_progress_options: WorkDoneProgressOptions {
@@ -103,6 +106,30 @@ impl RocServer {
                 work_done_progress: None,
             },
         };
+        let rename_provider = RenameOptions {
+            prepare_provider: Some(true),
+            work_done_progress_options: WorkDoneProgressOptions {
+                work_done_progress: None,
+            },
+        };
+        let code_action_provider = CodeActionOptions {
+            code_action_kinds: Some(vec![
+                CodeActionKind::QUICKFIX,
+                CodeActionKind::REFACTOR_EXTRACT,
+                CodeActionKind::REFACTOR_REWRITE,
+            ]),
+            work_done_progress_options: WorkDoneProgressOptions {
+                work_done_progress: None,
+            },
+            resolve_provider: None,
+        };
+        let signature_help_provider = SignatureHelpOptions {
+            trigger_characters: Some(vec![" ".to_string()]),
+            retrigger_characters: None,
+            work_done_progress_options: WorkDoneProgressOptions {
+                work_done_progress: None,
+            },
+        };
         ServerCapabilities {
             text_document_sync: Some(text_document_sync),
             hover_provider: Some(hover_provider),
@@ -110,6 +137,13 @@ impl RocServer {
             document_formatting_provider: Some(OneOf::Right(document_formatting_provider)),
             semantic_tokens_provider: Some(semantic_tokens_provider),
             completion_provider: Some(completion_provider),
+            references_provider: Some(OneOf::Left(true)),
+            rename_provider: Some(OneOf::Right(rename_provider)),
+            code_action_provider: Some(CodeActionProviderCapability::Options(code_action_provider)),
+            signature_help_provider: Some(signature_help_provider),
+            inlay_hint_provider: Some(OneOf::Left(true)),
+            document_symbol_provider: Some(OneOf::Left(true)),
+            workspace_symbol_provider: Some(OneOf::Left(true)),
             ..ServerCapabilities::default()
         }
     }
@@ -118,6 +152,30 @@ impl RocServer {
     async fn change(&self, fi: Url, text: String, version: i32) {
         let updating_result = self.state.change(&fi, text, version).await;
 
+        self.publish_diagnostics(fi, version, updating_result).await;
+    }
+
+    /// Records incremental changes to a document's content.
+    async fn edit(&self, fi: Url, changes: Vec<TextDocumentContentChangeEvent>, version: i32) {
+        let updating_result = self.state.edit(&fi, changes, version).await;
+
+        self.publish_diagnostics(fi, version, updating_result).await;
+    }
+
+    /// Analyses a document again, without any changes to its content.
+    async fn reanalyse(&self, fi: Url) {
+        match self.state.reanalyse(&fi).await {
+            Ok(version) => self.publish_diagnostics(fi, version, Ok(())).await,
+            Err(e) => debug!("Didn't reanalyse {:?}. Reason:{:?}", fi.as_str(), e),
+        }
+    }
+
+    async fn publish_diagnostics(
+        &self,
+        fi: Url,
+        version: i32,
+        updating_result: std::result::Result<(), String>,
+    ) {
         //The analysis task can be cancelled by another change coming in which will update the watched variable
         if let Err(e) = updating_result {
             debug!("Cancelled change. Reason:{:?}", e);
@@ -136,7 +194,11 @@ impl RocServer {
 
 impl RocServerState {
     pub fn new(config: RocServerConfig, registry: Registry) -> RocServerState {
-        Self { config, registry }
+        Self {
+            config,
+            registry,
+            analysis_cache: Default::default(),
+        }
     }
 
     async fn close(&self, _fi: Url) {}
@@ -154,6 +216,39 @@ impl RocServerState {
             .apply_doc_info_changes(fi.clone(), doc_info.clone())
             .await;
 
+        self.analyse(fi, doc_info).await
+    }
+
+    pub async fn edit(
+        &self,
+        fi: &Url,
+        changes: Vec<TextDocumentContentChangeEvent>,
+        version: i32,
+    ) -> std::result::Result<(), String> {
+        debug!("V{:?}:starting edit", version);
+        let doc_info = self
+            .registry
+            .apply_text_changes(fi, changes, version)
+            .await
+            .ok_or_else(|| format!("No content to apply changes to for {:?}", fi.as_str()))?;
+
+        self.analyse(fi, doc_info).await
+    }
+
+    /// Analyses the latest contents of a document again, returning their version.
+    pub async fn reanalyse(&self, fi: &Url) -> std::result::Result<i32, String> {
+        let doc_info = self
+            .registry
+            .invalidate(fi)
+            .await
+            .ok_or_else(|| format!("No content to analyse for {:?}", fi.as_str()))?;
+        let version = doc_info.version;
+
+        self.analyse(fi, doc_info).await.map(|()| version)
+    }
+
+    async fn analyse(&self, fi: &Url, doc_info: DocInfo) -> std::result::Result<(), String> {
+        let version = doc_info.version;
         debug!(
             "V{:?}:finished updating docinfo, starting analysis ",
             version
@@ -173,9 +268,21 @@ impl RocServerState {
                 return Err("Not latest version skipping analysis".to_string());
             }
 
+            if inner_ref.registry.is_analysed(fi).await {
+                debug!("V{:?}:content is unchanged, reusing analysis", version);
+                return Ok(());
+            }
+
+            let analysis_cache = inner_ref.analysis_cache.clone();
             let results = match tokio::time::timeout(
                 Duration::from_secs(60),
-                tokio::task::spawn_blocking(|| catch_unwind(|| global_analysis(doc_info))),
+                tokio::task::spawn_blocking(move || {
+                    // A panic can't leave the cache half updated, as a load only writes to it once
+                    // it has typechecked every module.
+                    catch_unwind(AssertUnwindSafe(|| {
+                        global_analysis(doc_info, &mut analysis_cache.lock())
+                    }))
+                }),
             )
             .await
             {
@@ -243,15 +350,17 @@ impl LanguageServer for RocServer {
     async fn did_change(&self, params: DidChangeTextDocumentParams) {
         let VersionedTextDocumentIdentifier { uri, version, .. } = params.text_document;
 
-        // NOTE: We specify that we expect full-content syncs in the server capabilities,
-        // so here we assume the only change passed is a change of the entire document's content.
-        let TextDocumentContentChangeEvent { text, .. } = params
-            .content_changes
-            .into_iter()
-            .last()
-            .expect("textDocument change event had no changes ");
+        self.edit(uri, params.content_changes, version).await;
+    }
 
-        self.change(uri, text, version).await;
+    async fn did_save(&self, params: DidSaveTextDocumentParams) {
+        let TextDocumentIdentifier { uri } = params.text_document;
+
+        // Imported modules are loaded from disk, so the modules importing this one only see
+        // its changes once it's saved.
+        for dependent in self.state.registry.dependents_to_reanalyse(&uri).await {
+            self.reanalyse(dependent).await;
+        }
     }
 
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
@@ -332,6 +441,135 @@ impl LanguageServer for RocServer {
         )
         .await
     }
+
+    async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
+        let CodeActionParams {
+            text_document,
+            range,
+            context: _,
+            work_done_progress_params: _,
+            partial_result_params: _,
+        } = params;
+
+        unwind_async(self.state.registry.code_actions(&text_document.uri, range)).await
+    }
+
+    async fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>> {
+        let ReferenceParams {
+            text_document_position:
+                TextDocumentPositionParams {
+                    text_document,
+                    position,
+                },
+            context,
+            work_done_progress_params: _,
+            partial_result_params: _,
+        } = params;
+
+        unwind_async(self.state.registry.references(
+            &text_document.uri,
+            position,
+            context.include_declaration,
+        ))
+        .await
+    }
+
+    async fn prepare_rename(
+        &self,
+        params: TextDocumentPositionParams,
+    ) -> Result<Option<PrepareRenameResponse>> {
+        let TextDocumentPositionParams {
+            text_document,
+            position,
+        } = params;
+
+        unwind_async(
+            self.state
+                .registry
+                .prepare_rename(&text_document.uri, position),
+        )
+        .await
+    }
+
+    async fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
+        let RenameParams {
+            text_document_position:
+                TextDocumentPositionParams {
+                    text_document,
+                    position,
+                },
+            new_name,
+            work_done_progress_params: _,
+        } = params;
+
+        unwind_async(
+            self.state
+                .registry
+                .rename(&text_document.uri, position, &new_name),
+        )
+        .await?
+        .map_err(jsonrpc::Error::invalid_params)
+    }
+
+    async fn signature_help(&self, params: SignatureHelpParams) -> Result<Option<SignatureHelp>> {
+        let SignatureHelpParams {
+            context: _,
+            text_document_position_params:
+                TextDocumentPositionParams {
+                    text_document,
+                    position,
+                },
+            work_done_progress_params: _,
+        } = params;
+
+        unwind_async(
+            self.state
+                .registry
+                .signature_help(&text_document.uri, position),
+        )
+        .await
+    }
+
+    async fn inlay_hint(&self, params: InlayHintParams) -> Result<Option<Vec<InlayHint>>> {
+        let InlayHintParams {
+            text_document,
+            range,
+            work_done_progress_params: _,
+        } = params;
+
+        unwind_async(self.state.registry.inlay_hints(&text_document.uri, range)).await
+    }
+
+    async fn document_symbol(
+        &self,
+        params: DocumentSymbolParams,
+    ) -> Result<Option<DocumentSymbolResponse>> {
+        let DocumentSymbolParams {
+            text_document,
+            work_done_progress_params: _,
+            partial_result_params: _,
+        } = params;
+
+        let symbols =
+            unwind_async(self.state.registry.document_symbols(&text_document.uri)).await?;
+
+        Ok(symbols.map(DocumentSymbolResponse::Nested))
+    }
+
+    async fn symbol(
+        &self,
+        params: WorkspaceSymbolParams,
+    ) -> Result<Option<Vec<SymbolInformation>>> {
+        let WorkspaceSymbolParams {
+            query,
+            work_done_progress_params: _,
+            partial_result_params: _,
+        } = params;
+
+        let symbols = unwind_async(self.state.registry.workspace_symbols(&query)).await?;
+
+        Ok(Some(symbols))
+    }
 }
 
 async fn unwind_async<Fut, T>(future: Fut) -> tower_lsp::jsonrpc::Result<T>
@@ -612,4 +850,593 @@ mod tests {
         "#]]
         .assert_debug_eq(&actual);
     }
+
+    /// Tests that incremental changes are applied on top of the latest contents,
+    /// and that unchanged contents reuse the previous analysis.
+    #[tokio::test]
+    async fn test_incremental_edits() {
+        let doc = DOC_LIT.to_string()
+            + indoc! {r#"
+            main =
+              when a is
+                inn as outer -> 
+                  "#};
+
+        let (inner, url) = test_setup(doc.clone()).await;
+        let reg = &inner.registry;
+
+        let insert = |text: &str, line, character| TextDocumentContentChangeEvent {
+            range: Some(Range::new(
+                Position::new(line, character),
+                Position::new(line, character),
+            )),
+            range_length: None,
+            text: text.to_string(),
+        };
+
+        inner
+            .edit(&url, vec![insert("x", 6, 6), insert("o", 6, 7)], 1)
+            .await
+            .unwrap();
+        inner
+            .edit(
+                &url,
+                vec![TextDocumentContentChangeEvent {
+                    range: Some(Range::new(Position::new(6, 6), Position::new(6, 7))),
+                    range_length: None,
+                    text: String::new(),
+                }],
+                2,
+            )
+            .await
+            .unwrap();
+        let comp1 = comp_labels(get_basic_completion_info(reg, &url, Position::new(6, 7)).await);
+
+        // Replacing text with the same text leaves nothing new to analyse
+        let same = TextDocumentContentChangeEvent {
+            range: Some(Range::new(Position::new(6, 6), Position::new(6, 7))),
+            range_length: None,
+            text: "o".to_string(),
+        };
+        reg.apply_text_changes(&url, vec![same], 3).await.unwrap();
+
+        assert!(reg.is_analysed(&url).await);
+        assert_eq!(reg.get_latest_version(&url).await, Some(3));
+
+        expect![[r#"
+            Some(
+                [
+                    "outer",
+                ],
+            )
+        "#]]
+        .assert_debug_eq(&comp1);
+    }
+
+    #[tokio::test]
+    async fn test_quick_fixes() {
+        let doc = indoc! {r#"
+            interface Test
+              exposes [main]
+              imports []
+
+            import Dict
+            import Str exposing [concat, isEmpty]
+
+            helper = 1
+
+            main = \unused, _ -> concat "a" mian
+            "#};
+
+        let (inner, url) = test_setup(doc.to_string()).await;
+        let reg = &inner.registry;
+
+        let whole_document = Range::new(Position::new(0, 0), Position::new(10, 0));
+        let actions = reg.code_actions(&url, whole_document).await.unwrap();
+
+        let actual: Vec<_> = actions
+            .into_iter()
+            .filter_map(|action| match action {
+                CodeActionOrCommand::CodeAction(action) => Some(action),
+                CodeActionOrCommand::Command(_) => None,
+            })
+            .map(|action| {
+                let edits = action.edit.unwrap().changes.unwrap().remove(&url).unwrap();
+                let edits: Vec<_> = edits
+                    .into_iter()
+                    .map(|edit| {
+                        let Range { start, end } = edit.range;
+                        format!(
+                            "{}:{}-{}:{} {:?}",
+                            start.line, start.character, end.line, end.character, edit.new_text
+                        )
+                    })
+                    .collect();
+                (action.title, action.is_preferred.unwrap_or(false), edits)
+            })
+            .collect();
+
+        expect![[r#"
+            [
+                (
+                    "Remove import of `Dict`",
+                    true,
+                    [
+                        "4:0-5:0 \"\"",
+                    ],
+                ),
+                (
+                    "Change to `main`",
+                    true,
+                    [
+                        "9:32-9:36 \"main\"",
+                    ],
+                ),
+                (
+                    "Change to `concat`",
+                    false,
+                    [
+                        "9:32-9:36 \"concat\"",
+                    ],
+                ),
+                (
+                    "Change to `helper`",
+                    false,
+                    [
+                        "9:32-9:36 \"helper\"",
+                    ],
+                ),
+                (
+                    "Change to `isEmpty`",
+                    false,
+                    [
+                        "9:32-9:36 \"isEmpty\"",
+                    ],
+                ),
+                (
+                    "Prefix `unused` with an underscore",
+                    true,
+                    [
+                        "9:8-9:8 \"_\"",
+                    ],
+                ),
+                (
+                    "Remove unused definition `helper`",
+                    true,
+                    [
+                        "7:0-8:0 \"\"",
+                    ],
+                ),
+                (
+                    "Remove unused import `isEmpty`",
+                    true,
+                    [
+                        "5:27-5:36 \"\"",
+                    ],
+                ),
+            ]
+        "#]]
+        .assert_debug_eq(&actual);
+    }
+
+    /// Tests that positions in edits count UTF-16 code units rather than bytes.
+    #[tokio::test]
+    async fn test_incremental_edit_after_multi_byte_chars() {
+        let doc = DOC_LIT.to_string() + "\n# é😀 x\n";
+
+        let (inner, url) = test_setup(doc.clone()).await;
+
+        // "# é😀 " is 6 UTF-16 code units, but 9 bytes
+        let change = TextDocumentContentChangeEvent {
+            range: Some(Range::new(Position::new(4, 6), Position::new(4, 7))),
+            range_length: None,
+            text: "y".to_string(),
+        };
+        let info = inner
+            .registry
+            .apply_text_changes(&url, vec![change], 1)
+            .await
+            .unwrap();
+
+        assert_eq!(info.source, DOC_LIT.to_string() + "\n# é😀 y\n");
+    }
+
+    fn ranges_to_tuples(ranges: impl IntoIterator<Item = Range>) -> Vec<(u32, u32, u32)> {
+        ranges
+            .into_iter()
+            .map(|range| (range.start.line, range.start.character, range.end.character))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_references_include_annotation_and_exposes() {
+        let doc = indoc! {r#"
+            interface Test
+              exposes [helper]
+              imports []
+
+            helper : Str -> Str
+            helper = \str -> str
+
+            main = helper "hi"
+            "#};
+
+        let (inner, url) = test_setup(doc.to_string()).await;
+        let reg = &inner.registry;
+
+        let with_decl = reg.references(&url, Position::new(7, 9), true).await;
+        let without_decl = reg.references(&url, Position::new(7, 9), false).await;
+
+        let actual = [with_decl, without_decl].map(|locations| {
+            locations.map(|locations| ranges_to_tuples(locations.into_iter().map(|l| l.range)))
+        });
+
+        expect![[r#"
+            [
+                Some(
+                    [
+                        (
+                            1,
+                            11,
+                            17,
+                        ),
+                        (
+                            4,
+                            0,
+                            6,
+                        ),
+                        (
+                            5,
+                            0,
+                            6,
+                        ),
+                        (
+                            7,
+                            7,
+                            13,
+                        ),
+                    ],
+                ),
+                Some(
+                    [
+                        (
+                            1,
+                            11,
+                            17,
+                        ),
+                        (
+                            7,
+                            7,
+                            13,
+                        ),
+                    ],
+                ),
+            ]
+        "#]]
+        .assert_debug_eq(&actual);
+    }
+
+    #[tokio::test]
+    async fn test_rename_opaque_type() {
+        let doc = DOC_LIT.to_string()
+            + indoc! {r#"
+            Age := U32
+
+            make : U32 -> Age
+            make = \n -> @Age n
+            "#};
+
+        let (inner, url) = test_setup(doc).await;
+        let reg = &inner.registry;
+
+        let prepared = reg.prepare_rename(&url, Position::new(5, 15)).await;
+        let invalid = reg.rename(&url, Position::new(5, 15), "years").await;
+        let edit = reg
+            .rename(&url, Position::new(5, 15), "Years")
+            .await
+            .unwrap()
+            .and_then(|edit| edit.changes)
+            .and_then(|mut changes| changes.remove(&url))
+            .map(|edits| {
+                ranges_to_tuples(edits.iter().map(|edit| edit.range))
+                    .into_iter()
+                    .zip(edits.into_iter().map(|edit| edit.new_text))
+                    .collect::<Vec<_>>()
+            });
+
+        assert!(invalid.is_err());
+        expect![[r#"
+            (
+                Some(
+                    RangeWithPlaceholder {
+                        range: Range {
+                            start: Position {
+                                line: 5,
+                                character: 14,
+                            },
+                            end: Position {
+                                line: 5,
+                                character: 17,
+                            },
+                        },
+                        placeholder: "Age",
+                    },
+                ),
+                Some(
+                    [
+                        (
+                            (
+                                3,
+                                0,
+                                3,
+                            ),
+                            "Years",
+                        ),
+                        (
+                            (
+                                5,
+                                14,
+                                17,
+                            ),
+                            "Years",
+                        ),
+                        (
+                            (
+                                6,
+                                14,
+                                17,
+                            ),
+                            "Years",
+                        ),
+                    ],
+                ),
+            )
+        "#]]
+        .assert_debug_eq(&(prepared, edit));
+    }
+
+    /// The titles and edits of the code actions for a range on one line.
+    async fn code_action_edits(
+        reg: &Registry,
+        url: &Url,
+        line: u32,
+        start: u32,
+        end: u32,
+    ) -> Vec<(String, Vec<String>)> {
+        let range = Range::new(Position::new(line, start), Position::new(line, end));
+        let actions = reg.code_actions(url, range).await.unwrap();
+
+        actions
+            .into_iter()
+            .filter_map(|action| match action {
+                CodeActionOrCommand::CodeAction(action) => Some(action),
+                CodeActionOrCommand::Command(_) => None,
+            })
+            .map(|action| {
+                let edits = action.edit.unwrap().changes.unwrap().remove(url).unwrap();
+                let edits = edits
+                    .into_iter()
+                    .map(|edit| {
+                        let Range { start, end } = edit.range;
+                        format!(
+                            "{}:{}-{}:{} {:?}",
+                            start.line, start.character, end.line, end.character, edit.new_text
+                        )
+                    })
+                    .collect();
+                (action.title, edits)
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_refactors() {
+        let doc = indoc! {r#"
+            interface Test
+              exposes [main, double]
+              imports []
+
+            double = \x -> x * 2
+
+            main =
+                total = List.sum [1, 2]
+                Num.toStr (double (total + 1))
+            "#};
+
+        let (inner, url) = test_setup(doc.to_string()).await;
+        let reg = &inner.registry;
+
+        let annotate = code_action_edits(reg, &url, 4, 0, 0).await;
+        let extract_argument = code_action_edits(reg, &url, 8, 22, 33).await;
+        let extract_body = code_action_edits(reg, &url, 4, 15, 20).await;
+
+        expect![[r#"
+            (
+                [
+                    (
+                        "Add type annotation to `double`",
+                        [
+                            "4:0-4:0 \"double : Num a -> Num a\\n\"",
+                        ],
+                    ),
+                ],
+                [
+                    (
+                        "Add type annotation to `main`",
+                        [
+                            "6:0-6:0 \"main : Str\\n\"",
+                        ],
+                    ),
+                    (
+                        "Extract to `extracted`",
+                        [
+                            "8:0-8:0 \"    extracted = total + 1\\n\"",
+                            "8:22-8:33 \"extracted\"",
+                        ],
+                    ),
+                ],
+                [
+                    (
+                        "Add type annotation to `double`",
+                        [
+                            "4:0-4:0 \"double : Num a -> Num a\\n\"",
+                        ],
+                    ),
+                    (
+                        "Extract to `extracted`",
+                        [
+                            "4:14-4:20 \"\\n    extracted = x * 2\\n    extracted\"",
+                        ],
+                    ),
+                ],
+            )
+        "#]]
+        .assert_debug_eq(&(annotate, extract_argument, extract_body));
+    }
+
+    #[tokio::test]
+    async fn test_symbols_and_inlay_hints() {
+        let doc = indoc! {r#"
+            interface Test
+              exposes [main, Age]
+              imports []
+
+            Age : U32
+
+            double = \x ->
+                twice = x * 2
+                twice
+
+            main : Str
+            main = Num.toStr (double 21)
+            "#};
+
+        let (inner, url) = test_setup(doc.to_string()).await;
+        let reg = &inner.registry;
+
+        let symbols: Vec<_> = reg
+            .document_symbols(&url)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|symbol| (symbol.name, symbol.kind, symbol.detail))
+            .collect();
+
+        let whole_document = Range::new(Position::new(0, 0), Position::new(20, 0));
+        let hints: Vec<_> = reg
+            .inlay_hints(&url, whole_document)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|hint| match hint.label {
+                InlayHintLabel::String(label) => {
+                    (hint.position.line, hint.position.character, label)
+                }
+                InlayHintLabel::LabelParts(_) => unreachable!(),
+            })
+            .collect();
+
+        let workspace_symbols: Vec<_> = reg
+            .workspace_symbols("DOUB")
+            .await
+            .into_iter()
+            .map(|symbol| (symbol.name, symbol.container_name))
+            .collect();
+
+        expect![[r#"
+            (
+                [
+                    (
+                        "Age",
+                        Struct,
+                        None,
+                    ),
+                    (
+                        "double",
+                        Function,
+                        Some(
+                            "Num a -> Num a",
+                        ),
+                    ),
+                    (
+                        "main",
+                        Constant,
+                        Some(
+                            "Str",
+                        ),
+                    ),
+                ],
+                [
+                    (
+                        6,
+                        6,
+                        " : Num a -> Num a",
+                    ),
+                    (
+                        7,
+                        9,
+                        " : Num *",
+                    ),
+                ],
+                [
+                    (
+                        "double",
+                        Some(
+                            "Test",
+                        ),
+                    ),
+                ],
+            )
+        "#]]
+        .assert_debug_eq(&(symbols, hints, workspace_symbols));
+    }
+
+    #[tokio::test]
+    async fn test_signature_help() {
+        let doc = indoc! {r#"
+            interface Test
+              exposes [main]
+              imports []
+
+            greet : Str, U8 -> Str
+            greet = \name, times -> Str.repeat name (Num.toU64 times)
+
+            main = greet "world" 
+            "#};
+
+        let (inner, url) = test_setup(doc.to_string()).await;
+        let reg = &inner.registry;
+
+        let first = reg.signature_help(&url, Position::new(7, 14)).await;
+        let second = reg.signature_help(&url, Position::new(7, 21)).await;
+
+        let summary = |help: Option<SignatureHelp>| {
+            help.map(|help| {
+                let signature = &help.signatures[0];
+                (signature.label.clone(), help.active_parameter)
+            })
+        };
+
+        expect![[r#"
+            (
+                Some(
+                    (
+                        "greet : Str, U8 -> Str",
+                        Some(
+                            0,
+                        ),
+                    ),
+                ),
+                Some(
+                    (
+                        "greet : Str, U8 -> Str",
+                        Some(
+                            1,
+                        ),
+                    ),
+                ),
+            )
+        "#]]
+        .assert_debug_eq(&(summary(first), summary(second)));
+    }
 }
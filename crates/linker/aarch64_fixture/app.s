@@ -0,0 +1,13 @@
+// The app that is surgically linked into `host`.
+    .text
+    .globl roc_magic1
+    .type roc_magic1, %function
+roc_magic1:
+    adrp x1, X
+    add x1, x1, :lo12:X
+    add x0, x1, x0
+    ret
+
+    .section .rodata
+X:
+    .asciz "foo"
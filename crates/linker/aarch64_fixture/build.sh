@@ -0,0 +1,25 @@
+#!/usr/bin/env bash
+
+# Rebuilds the prebuilt aarch64 `host` and `app.o` used by the `aarch64_host_app` test in `src/elf.rs`,
+# so that the test needs neither zig nor an aarch64 C toolchain.
+# Needs `llvm-mc` and `ld.lld` (e.g. the `rust-lld` that ships with rustup: `LLD="rust-lld -flavor gnu"`).
+
+set -euxo pipefail
+
+cd "$(dirname "$0")"
+
+LLVM_MC="${LLVM_MC:-llvm-mc}"
+LLD="${LLD:-ld.lld}"
+TMP="$(mktemp -d)"
+
+"$LLVM_MC" -triple=aarch64-linux-gnu -filetype=obj app.s -o app.o
+"$LLVM_MC" -triple=aarch64-linux-gnu -filetype=obj host.s -o "$TMP/host.o"
+
+# The host only needs some `libapp.so` that defines `roc_magic1` to link against.
+printf '.text\n.globl roc_magic1\n.type roc_magic1, %%function\nroc_magic1:\nret\n' > "$TMP/stub.s"
+"$LLVM_MC" -triple=aarch64-linux-gnu -filetype=obj "$TMP/stub.s" -o "$TMP/stub.o"
+$LLD -shared -soname libapp.so "$TMP/stub.o" -o "$TMP/libapp.so"
+
+$LLD -pie --dynamic-linker /lib/ld-linux-aarch64.so.1 "$TMP/host.o" "$TMP/libapp.so" -o host
+
+rm -r "$TMP"
@@ -0,0 +1,21 @@
+// A minimal aarch64 host without libc: it calls `roc_magic1` through the plt,
+// and also calls it through a pointer stored in its data.
+    .text
+    .globl _start
+    .type _start, %function
+_start:
+    mov x0, #0
+    bl roc_magic1
+    adrp x1, magic_ptr
+    ldr x1, [x1, :lo12:magic_ptr]
+    mov x0, #0
+    blr x1
+    mov x0, #0
+    mov x8, #93 // exit
+    svc #0
+
+    .data
+    .p2align 3
+    .globl magic_ptr
+magic_ptr:
+    .xword roc_magic1
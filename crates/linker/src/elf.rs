@@ -4,8 +4,8 @@ use memmap2::MmapMut;
 use object::{elf, endian};
 use object::{
     CompressedFileRange, CompressionFormat, LittleEndian as LE, Object, ObjectSection,
-    ObjectSymbol, RelocationKind, RelocationTarget, Section, SectionIndex, SectionKind, Symbol,
-    SymbolIndex, SymbolSection,
+    ObjectSymbol, RelocationEncoding, RelocationKind, RelocationTarget, Section, SectionIndex,
+    SectionKind, Symbol, SymbolIndex, SymbolKind, SymbolSection,
 };
 use roc_collections::all::MutMap;
 use roc_error_macros::{internal_error, user_error};
@@ -18,9 +18,11 @@ use std::{
     time::{Duration, Instant},
 };
 
+use crate::inspect::{HostSlot, HostSymbol};
 use crate::{
     align_by_constraint, align_to_offset_by_constraint, load_struct_inplace,
-    load_struct_inplace_mut, load_structs_inplace_mut, open_mmap, open_mmap_mut,
+    load_struct_inplace_mut, load_structs_inplace, load_structs_inplace_mut, open_mmap,
+    open_mmap_mut,
 };
 
 const MIN_SECTION_ALIGNMENT: usize = 0x40;
@@ -28,12 +30,147 @@ const MIN_SECTION_ALIGNMENT: usize = 0x40;
 // TODO: Analyze if this offset is always correct.
 const PLT_ADDRESS_OFFSET: u64 = 0x10;
 
+// A `jmp rel32` instruction.
+const JMP_INST_LEN: u64 = 5;
+
+// Every aarch64 instruction is 4 bytes.
+const AARCH64_INST_LEN: u64 = 4;
+const AARCH64_NOP: u32 = 0xd503201f;
+
+/// The instruction sets the surgical linker can patch.
+/// They differ in how calls are encoded and in the numbering of their dynamic relocations.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum ElfArch {
+    X86_64,
+    Aarch64,
+}
+
+impl ElfArch {
+    fn from_exec_data(exec_data: &[u8]) -> Self {
+        let exec_header = load_struct_inplace::<elf::FileHeader64<LE>>(exec_data, 0);
+        match exec_header.e_machine.get(LE) {
+            elf::EM_X86_64 => ElfArch::X86_64,
+            elf::EM_AARCH64 => ElfArch::Aarch64,
+            other => {
+                internal_error!("Surgical linking does not support elf machine type {other}");
+            }
+        }
+    }
+
+    fn r_jump_slot(self) -> u32 {
+        match self {
+            ElfArch::X86_64 => elf::R_X86_64_JUMP_SLOT,
+            ElfArch::Aarch64 => elf::R_AARCH64_JUMP_SLOT,
+        }
+    }
+
+    fn r_glob_dat(self) -> u32 {
+        match self {
+            ElfArch::X86_64 => elf::R_X86_64_GLOB_DAT,
+            ElfArch::Aarch64 => elf::R_AARCH64_GLOB_DAT,
+        }
+    }
+
+    fn r_abs64(self) -> u32 {
+        match self {
+            ElfArch::X86_64 => elf::R_X86_64_64,
+            ElfArch::Aarch64 => elf::R_AARCH64_ABS64,
+        }
+    }
+
+    fn r_relative(self) -> u32 {
+        match self {
+            ElfArch::X86_64 => elf::R_X86_64_RELATIVE,
+            ElfArch::Aarch64 => elf::R_AARCH64_RELATIVE,
+        }
+    }
+
+    fn r_irelative(self) -> u32 {
+        match self {
+            ElfArch::X86_64 => elf::R_X86_64_IRELATIVE,
+            ElfArch::Aarch64 => elf::R_AARCH64_IRELATIVE,
+        }
+    }
+
+    /// The size of the first `.plt` entry, which calls the dynamic linker instead of a function.
+    fn plt_header_size(self) -> u64 {
+        match self {
+            ElfArch::X86_64 => PLT_ADDRESS_OFFSET,
+            ElfArch::Aarch64 => 0x20,
+        }
+    }
+
+    /// The shift for the extra program headers must keep `adrp` page offsets in the host valid.
+    fn shift_alignment(self) -> u64 {
+        match self {
+            ElfArch::X86_64 => MIN_SECTION_ALIGNMENT as u64,
+            ElfArch::Aarch64 => 0x1000,
+        }
+    }
+}
+
+/// Decodes the target of an aarch64 `b` or `bl` instruction at `address`.
+fn aarch64_branch_target(inst: u32, address: u64) -> Option<u64> {
+    match inst & 0xfc00_0000 {
+        0x1400_0000 | 0x9400_0000 => {
+            // Sign extend the word offset in the low 26 bits.
+            let offset = (((inst << 6) as i32) >> 4) as i64;
+            Some(address.wrapping_add_signed(offset))
+        }
+        _ => None,
+    }
+}
+
+/// Replaces the offset of an aarch64 `b` or `bl` instruction at `address` to branch to `target`.
+fn aarch64_set_branch_target(inst: u32, address: i64, target: i64) -> u32 {
+    let offset = target - address;
+    if offset % 4 != 0 || !(-(1 << 27)..(1 << 27)).contains(&offset) {
+        internal_error!("Branch from {address:+x} to {target:+x} is out of range for aarch64");
+    }
+
+    (inst & 0xfc00_0000) | ((offset >> 2) as u32 & 0x03ff_ffff)
+}
+
+/// Encodes the target of an aarch64 app relocation into the instruction it applies to.
+fn aarch64_relocate(inst: u32, r_type: u32, address: i64, target: i64) -> u32 {
+    let set_imm12 = |imm12: i64| (inst & !(0xfff << 10)) | (((imm12 & 0xfff) as u32) << 10);
+
+    match r_type {
+        elf::R_AARCH64_CALL26 | elf::R_AARCH64_JUMP26 => {
+            aarch64_set_branch_target(inst, address, target)
+        }
+        // The app has no GOT, so its GOT loads are relaxed to reference the symbol directly.
+        elf::R_AARCH64_ADR_PREL_PG_HI21 | elf::R_AARCH64_ADR_GOT_PAGE => {
+            let pages = (target >> 12) - (address >> 12);
+            if !(-(1 << 20)..(1 << 20)).contains(&pages) {
+                internal_error!("Page from {address:+x} to {target:+x} is out of range for adrp");
+            }
+            let immlo = (pages as u32 & 0x3) << 29;
+            let immhi = ((pages >> 2) as u32 & 0x7ffff) << 5;
+            (inst & !((0x3 << 29) | (0x7ffff << 5))) | immlo | immhi
+        }
+        elf::R_AARCH64_ADD_ABS_LO12_NC => set_imm12(target),
+        elf::R_AARCH64_LDST8_ABS_LO12_NC => set_imm12(target & 0xfff),
+        elf::R_AARCH64_LDST16_ABS_LO12_NC => set_imm12((target & 0xfff) >> 1),
+        elf::R_AARCH64_LDST32_ABS_LO12_NC => set_imm12((target & 0xfff) >> 2),
+        elf::R_AARCH64_LDST64_ABS_LO12_NC => set_imm12((target & 0xfff) >> 3),
+        elf::R_AARCH64_LDST128_ABS_LO12_NC => set_imm12((target & 0xfff) >> 4),
+        elf::R_AARCH64_LD64_GOT_LO12_NC => {
+            // `ldr xd, [xn, :got_lo12:sym]` becomes `add xd, xn, :lo12:sym`.
+            0x9100_0000 | (((target & 0xfff) as u32) << 10) | (inst & 0x3ff)
+        }
+        other => {
+            internal_error!("aarch64 relocation type not yet supported: {other}");
+        }
+    }
+}
+
 struct ElfDynamicDeps {
     got_app_syms: Vec<(String, usize)>,
     got_sections: Vec<(usize, usize)>,
     app_sym_indices: Vec<usize>,
     dynamic_lib_count: usize,
-    shared_lib_index: usize,
+    shared_lib_index: Option<usize>,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
@@ -59,6 +196,8 @@ struct Metadata {
     app_functions: Vec<String>,
     // offset followed by address.
     plt_addresses: MutMap<String, (u64, u64)>,
+    // offset followed by address of the stub definitions in a static-pie host.
+    stub_addresses: MutMap<String, (u64, u64)>,
     surgeries: MutMap<String, Vec<SurgeryEntry>>,
     dynamic_symbol_indices: MutMap<String, u64>,
     static_symbol_indices: MutMap<String, u64>,
@@ -123,6 +262,75 @@ fn is_roc_undefined(sym: &object::Symbol) -> bool {
     sym.is_undefined() && is_roc_symbol(sym)
 }
 
+/// Preprocessing shifts all of the host's code, so the host must not contain any absolute addresses.
+/// Both PIE and static-pie executables are `ET_DYN`; a non-PIE host is `ET_EXEC`.
+fn is_position_independent(exec_data: &[u8]) -> bool {
+    let exec_header = load_struct_inplace::<elf::FileHeader64<LE>>(exec_data, 0);
+    exec_header.e_type.get(LE) == elf::ET_DYN
+}
+
+/// A static-pie host is position independent, but has no interpreter to load `libapp.so`.
+/// It applies its own relative relocations at startup, and is linked against stub definitions
+/// of the app functions instead.
+fn is_static_pie(exec_data: &[u8]) -> bool {
+    let exec_header = load_struct_inplace::<elf::FileHeader64<LE>>(exec_data, 0);
+    if exec_header.e_type.get(LE) != elf::ET_DYN {
+        return false;
+    }
+
+    let program_headers = load_structs_inplace::<elf::ProgramHeader64<LE>>(
+        exec_data,
+        exec_header.e_phoff.get(LE) as usize,
+        exec_header.e_phnum.get(LE) as usize,
+    );
+
+    !program_headers
+        .iter()
+        .any(|ph| ph.p_type.get(LE) == elf::PT_INTERP)
+}
+
+/// The virtual address where the program headers end, and where shifting for extra headers starts.
+fn program_headers_end(exec_data: &[u8]) -> u64 {
+    let exec_header = load_struct_inplace::<elf::FileHeader64<LE>>(exec_data, 0);
+    let ph_offset = exec_header.e_phoff.get(LE);
+    let ph_num = exec_header.e_phnum.get(LE);
+    let ph_end = ph_offset + ph_num as u64 * exec_header.e_phentsize.get(LE) as u64;
+
+    let program_headers = load_structs_inplace::<elf::ProgramHeader64<LE>>(
+        exec_data,
+        ph_offset as usize,
+        ph_num as usize,
+    );
+
+    program_headers
+        .iter()
+        .find(|ph| ph.p_type.get(LE) == elf::PT_LOAD && ph.p_offset.get(LE) == 0)
+        .map_or(0, |ph| ph.p_vaddr.get(LE) + ph_end)
+}
+
+/// Finds the address and file offset of the first PLT entry.
+/// Hosts built with `-fcf-protection` call through the `.plt.sec` section, which has no header entry.
+fn first_plt_entry(exec_obj: &object::File, arch: ElfArch) -> Option<(u64, u64)> {
+    let (section, header_size) = match exec_obj.section_by_name(".plt.sec") {
+        Some(section) => (section, 0),
+        None => (exec_obj.section_by_name(".plt")?, arch.plt_header_size()),
+    };
+
+    let file_offset = match section.compressed_file_range() {
+        Ok(
+            range @ CompressedFileRange {
+                format: CompressionFormat::None,
+                ..
+            },
+        ) => range.offset,
+        _ => {
+            internal_error!("Surgical linking does not work with compressed plt section");
+        }
+    };
+
+    Some((section.address() + header_size, file_offset + header_size))
+}
+
 fn collect_roc_definitions<'a>(object: &object::File<'a, &'a [u8]>) -> MutMap<String, u64> {
     let mut vaddresses = MutMap::default();
 
@@ -162,13 +370,23 @@ fn collect_roc_definitions<'a>(object: &object::File<'a, &'a [u8]>) -> MutMap<St
 }
 
 struct Surgeries<'a> {
+    arch: ElfArch,
     surgeries: MutMap<String, Vec<SurgeryEntry>>,
     app_func_addresses: MutMap<u64, &'a str>,
+    // Everything after the program headers will be shifted, but rip-relative references
+    // into the headers (like `__ehdr_start` in a static-pie) must keep pointing at them.
+    header_end: u64,
+    header_references: Vec<u64>,
     indirect_warning_given: bool,
 }
 
 impl<'a> Surgeries<'a> {
-    fn new(application_symbols: &[Symbol], app_func_addresses: MutMap<u64, &'a str>) -> Self {
+    fn new(
+        arch: ElfArch,
+        application_symbols: &[Symbol],
+        app_func_addresses: MutMap<u64, &'a str>,
+        header_end: u64,
+    ) -> Self {
         let mut surgeries = MutMap::default();
 
         // for each symbol that the host expects from the application
@@ -179,8 +397,11 @@ impl<'a> Surgeries<'a> {
         }
 
         Self {
+            arch,
             surgeries,
             app_func_addresses,
+            header_end,
+            header_references: vec![],
             indirect_warning_given: false,
         }
     }
@@ -212,7 +433,59 @@ impl<'a> Surgeries<'a> {
         }
 
         for text_section in text_sections {
-            self.append_text_section(object_bytes, &text_section, verbose)
+            match self.arch {
+                ElfArch::X86_64 => self.append_text_section(object_bytes, &text_section, verbose),
+                ElfArch::Aarch64 => self.append_aarch64_text_section(&text_section, verbose),
+            }
+        }
+    }
+
+    fn append_aarch64_text_section(&mut self, sec: &Section, verbose: bool) {
+        let file_offset = match sec.compressed_file_range() {
+            Ok(CompressedFileRange {
+                format: CompressionFormat::None,
+                offset,
+                ..
+            }) => offset,
+            _ => {
+                internal_error!(
+                    "Surgical linking does not work with compressed text sections: {:+x?}",
+                    sec
+                );
+            }
+        };
+
+        let data = match sec.data() {
+            Ok(data) => data,
+            Err(err) => {
+                internal_error!("Failed to load text section, {:+x?}: {}", sec, err);
+            }
+        };
+
+        // Every instruction has a fixed size, so only direct branches need to be decoded.
+        // Addresses loaded with `adrp` are left alone, and keep going through the plt.
+        for (i, word) in data.chunks_exact(AARCH64_INST_LEN as usize).enumerate() {
+            let inst = u32::from_le_bytes(word.try_into().unwrap());
+            let inst_offset = i as u64 * AARCH64_INST_LEN;
+            let inst_address = sec.address() + inst_offset;
+
+            let target = aarch64_branch_target(inst, inst_address);
+            if let Some((target, func_name)) =
+                target.and_then(|target| Some((target, self.app_func_addresses.get(&target)?)))
+            {
+                if verbose {
+                    println!("Found branch from {inst_address:+x} to {target:+x}({func_name})");
+                }
+
+                self.surgeries
+                    .get_mut(*func_name)
+                    .unwrap()
+                    .push(SurgeryEntry {
+                        file_offset: file_offset + inst_offset,
+                        virtual_offset: VirtualOffset::Relative(inst_address),
+                        size: AARCH64_INST_LEN as u8,
+                    });
+            }
         }
     }
 
@@ -245,6 +518,27 @@ impl<'a> Surgeries<'a> {
         while decoder.can_decode() {
             decoder.decode_out(&mut inst);
 
+            if inst.is_ip_rel_memory_operand() && inst.ip_rel_memory_address() < self.header_end {
+                if compressed {
+                    internal_error!(
+                        "Surgical linking does not work with compressed text sections: {:+x?}",
+                        sec
+                    );
+                }
+
+                let offsets = decoder.get_constant_offsets(&inst);
+                let offset =
+                    inst.ip() - sec.address() + file_offset + offsets.displacement_offset() as u64;
+                if verbose {
+                    println!(
+                        "Found reference from {:+x} to the headers at {:+x}",
+                        inst.ip(),
+                        inst.ip_rel_memory_address()
+                    );
+                }
+                self.header_references.push(offset);
+            }
+
             // Note: This gets really complex fast if we want to support more than basic calls/jumps.
             // A lot of them have to load addresses into registers/memory so we would have to discover that value.
             // Would probably require some static code analysis and would be impossible in some cases.
@@ -337,6 +631,7 @@ pub(crate) fn preprocess_elf_le(
     metadata_path: &Path,
     preprocessed_path: &Path,
     shared_lib: &Path,
+    stub_dll_symbols: &[String],
     verbose: bool,
     time: bool,
 ) {
@@ -350,6 +645,10 @@ pub(crate) fn preprocess_elf_le(
         }
     };
 
+    if !is_position_independent(exec_data) {
+        user_error!("The host executable is not position independent.\nBuild it as a PIE (the default for gcc and clang) or a static-pie, or use `--linker=legacy`.");
+    }
+
     let mut md = Metadata {
         roc_symbol_vaddresses: collect_roc_definitions(&exec_obj),
         ..Default::default()
@@ -387,70 +686,121 @@ pub(crate) fn preprocess_elf_le(
     // procedures/functions whose address isn't known in the time of linking, and is left
     // to be resolved by the dynamic linker at run time.
     let symbol_and_plt_processing_start = Instant::now();
-    let plt_section_name = ".plt";
-    let (plt_address, plt_offset) = match exec_obj.section_by_name(plt_section_name) {
-        Some(section) => {
-            let file_offset = match section.compressed_file_range() {
-                Ok(
-                    range @ CompressedFileRange {
-                        format: CompressionFormat::None,
-                        ..
-                    },
-                ) => range.offset,
-                _ => {
-                    internal_error!("Surgical linking does not work with compressed plt section");
-                }
-            };
-            (section.address(), file_offset)
-        }
-        None => {
-            internal_error!("Failed to find PLT section. Probably an malformed executable.");
-        }
-    };
+    let arch = ElfArch::from_exec_data(exec_data);
+    let static_pie = is_static_pie(exec_data);
     if verbose {
-        println!("PLT Address: {plt_address:+x}");
-        println!("PLT File Offset: {plt_offset:+x}");
+        println!("Architecture: {arch:?}");
+        println!("Static PIE: {static_pie}");
     }
 
-    let app_syms: Vec<_> = exec_obj
-        .dynamic_symbols()
-        .filter(is_roc_undefined)
-        .collect();
+    // The startup code of an aarch64 static-pie finds its headers with `adrp`, which we do not patch.
+    if static_pie && arch == ElfArch::Aarch64 {
+        user_error!("Surgical linking does not support static-pie hosts on aarch64.\nBuild the host as a dynamically linked PIE, or use `--linker=legacy`.");
+    }
 
     let mut app_func_addresses: MutMap<u64, &str> = MutMap::default();
 
-    let plt_relocs = (match exec_obj.dynamic_relocations() {
-                Some(relocs) => relocs,
+    let app_syms: Vec<_> = if static_pie {
+        // The stub definitions will be replaced by the app, so they are not host symbols.
+        md.roc_symbol_vaddresses
+            .retain(|name, _| !stub_dll_symbols.contains(name));
+
+        let app_syms: Vec<_> = exec_obj
+            .symbols()
+            .filter(|sym| {
+                sym.is_definition()
+                    && sym.kind() == SymbolKind::Text
+                    && stub_dll_symbols.iter().any(|name| sym.name() == Ok(name))
+            })
+            .collect();
+
+        for symbol in app_syms.iter() {
+            let name = symbol.name().unwrap();
+            app_func_addresses.insert(symbol.address(), name);
+
+            // Code that takes the address of a stub may have been relaxed to a `lea`, which we cannot find.
+            // As a backup, the stub itself becomes a jump to the app function, just like a plt entry.
+            let file_offset = symbol
+                .section_index()
+                .and_then(|index| exec_obj.section_by_index(index).ok())
+                .and_then(|sec| Some(sec.file_range()?.0 + symbol.address() - sec.address()));
+            match file_offset {
+                Some(file_offset) if symbol.size() >= JMP_INST_LEN => {
+                    md.stub_addresses
+                        .insert(name.to_string(), (file_offset, symbol.address()));
+                }
+                _ => {
+                    if verbose {
+                        println!("Stub for {name} is too small to be replaced by a jump");
+                    }
+                }
+            }
+        }
+
+        app_syms
+    } else {
+        let app_syms: Vec<_> = exec_obj
+            .dynamic_symbols()
+            .filter(is_roc_undefined)
+            .collect();
+
+        // A host that binds all of its app functions through the GOT may not have any PLT entries.
+        let plt_relocs: Vec<_> = (match exec_obj.dynamic_relocations() {
+            Some(relocs) => relocs,
+            None => {
+                internal_error!("Executable does not have any dynamic relocations. No work to do. Probably an invalid input.");
+            }
+        })
+        .filter_map(|(_, reloc)| {
+            if reloc.kind() == RelocationKind::Elf(arch.r_jump_slot()) {
+                Some(reloc)
+            } else {
+                None
+            }
+        })
+        .collect();
+
+        if !plt_relocs.is_empty() {
+            let (plt_address, plt_offset) = match first_plt_entry(&exec_obj, arch) {
+                Some(entry) => entry,
                 None => {
-                    internal_error!("Executable does not have any dynamic relocations. No work to do. Probably an invalid input.");
+                    internal_error!(
+                        "Failed to find PLT section. Probably an malformed executable."
+                    );
                 }
-            })
-            .filter_map(|(_, reloc)| {
-                if let RelocationKind::Elf(7) = reloc.kind() {
-                    Some(reloc)
-                } else {
-                    None
+            };
+            if verbose {
+                println!("PLT Address: {plt_address:+x}");
+                println!("PLT File Offset: {plt_offset:+x}");
+            }
+
+            for (i, reloc) in plt_relocs.iter().enumerate() {
+                for symbol in app_syms.iter() {
+                    if reloc.target() == RelocationTarget::Symbol(symbol.index()) {
+                        let func_address = i as u64 * PLT_ADDRESS_OFFSET + plt_address;
+                        let func_offset = i as u64 * PLT_ADDRESS_OFFSET + plt_offset;
+                        app_func_addresses.insert(func_address, symbol.name().unwrap());
+                        md.plt_addresses.insert(
+                            symbol.name().unwrap().to_string(),
+                            (func_offset, func_address),
+                        );
+                        break;
+                    }
                 }
-            });
-    for (i, reloc) in plt_relocs.enumerate() {
-        for symbol in app_syms.iter() {
-            if reloc.target() == RelocationTarget::Symbol(symbol.index()) {
-                let func_address = (i as u64 + 1) * PLT_ADDRESS_OFFSET + plt_address;
-                let func_offset = (i as u64 + 1) * PLT_ADDRESS_OFFSET + plt_offset;
-                app_func_addresses.insert(func_address, symbol.name().unwrap());
-                md.plt_addresses.insert(
-                    symbol.name().unwrap().to_string(),
-                    (func_offset, func_address),
-                );
-                break;
             }
         }
-    }
+
+        app_syms
+    };
 
     for sym in app_syms.iter() {
         let name = sym.name().unwrap().to_string();
         md.app_functions.push(name.clone());
-        md.dynamic_symbol_indices.insert(name, sym.index().0 as u64);
+        if static_pie {
+            md.static_symbol_indices.insert(name, sym.index().0 as u64);
+        } else {
+            md.dynamic_symbol_indices.insert(name, sym.index().0 as u64);
+        }
     }
     for sym in exec_obj.symbols().filter(is_roc_undefined) {
         let name = sym.name().unwrap().to_string();
@@ -472,9 +822,16 @@ pub(crate) fn preprocess_elf_le(
     // look at the text (i.e. code) sections and see collect work needs to be done
     let text_disassembly_start = Instant::now();
 
-    let mut surgeries = Surgeries::new(&app_syms, app_func_addresses);
+    let mut surgeries = Surgeries::new(
+        arch,
+        &app_syms,
+        app_func_addresses,
+        program_headers_end(exec_data),
+    );
     surgeries.append_text_sections(exec_data, &exec_obj, verbose);
     md.surgeries = surgeries.surgeries;
+    let app_func_addresses = surgeries.app_func_addresses;
+    let header_references = surgeries.header_references;
 
     let text_disassembly_duration = text_disassembly_start.elapsed();
 
@@ -487,7 +844,7 @@ pub(crate) fn preprocess_elf_le(
         dynamic_lib_count,
         shared_lib_index,
     } = scan_elf_dynamic_deps(
-        &exec_obj, &mut md, &app_syms, shared_lib, exec_data, verbose,
+        &exec_obj, arch, &mut md, &app_syms, shared_lib, static_pie, exec_data, verbose,
     );
 
     let scanning_dynamic_deps_duration = scanning_dynamic_deps_start.elapsed();
@@ -496,11 +853,14 @@ pub(crate) fn preprocess_elf_le(
 
     let out_mmap = gen_elf_le(
         exec_data,
+        arch,
         &mut md,
         preprocessed_path,
         &got_app_syms,
         &got_sections,
         &app_sym_indices,
+        &app_func_addresses,
+        &header_references,
         dynamic_lib_count,
         shared_lib_index,
         verbose,
@@ -558,13 +918,16 @@ pub(crate) fn preprocess_elf_le(
 #[allow(clippy::too_many_arguments)]
 fn gen_elf_le(
     exec_data: &[u8],
+    arch: ElfArch,
     md: &mut Metadata,
     preprocessed_path: &Path,
     got_app_syms: &[(String, usize)],
     got_sections: &[(usize, usize)],
     app_sym_indices: &[usize],
+    app_func_addresses: &MutMap<u64, &str>,
+    header_references: &[u64],
     dynamic_lib_count: usize,
-    shared_lib_index: usize,
+    shared_lib_index: Option<usize>,
     verbose: bool,
 ) -> MmapMut {
     let exec_header = load_struct_inplace::<elf::FileHeader64<LE>>(exec_data, 0);
@@ -587,9 +950,10 @@ fn gen_elf_le(
 
     // Copy header and shift everything to enable more program sections.
     let added_header_count = 3;
+    let shift_alignment = arch.shift_alignment();
     md.added_byte_count = ph_ent_size as u64 * added_header_count;
-    md.added_byte_count = md.added_byte_count
-        + (MIN_SECTION_ALIGNMENT as u64 - md.added_byte_count % MIN_SECTION_ALIGNMENT as u64);
+    md.added_byte_count =
+        md.added_byte_count + (shift_alignment - md.added_byte_count % shift_alignment);
     let ph_end = ph_offset as usize + ph_num as usize * ph_ent_size as usize;
     let physical_shift_start = ph_end as u64;
 
@@ -659,6 +1023,14 @@ fn gen_elf_le(
     out_mmap[physical_shift_start as usize + md.added_byte_count as usize..]
         .copy_from_slice(&exec_data[physical_shift_start as usize..]);
 
+    // Code moves with the shift, so references into the headers need a shorter displacement.
+    for offset in header_references {
+        let offset = (*offset + md.added_byte_count) as usize;
+        let displacement = i32::from_le_bytes(out_mmap[offset..][..4].try_into().unwrap());
+        let displacement = displacement - md.added_byte_count as i32;
+        out_mmap[offset..][..4].copy_from_slice(&displacement.to_le_bytes());
+    }
+
     // Update all sections for shift for extra program headers.
     let section_headers = load_structs_inplace_mut::<elf::SectionHeader64<LE>>(
         &mut out_mmap,
@@ -667,8 +1039,8 @@ fn gen_elf_le(
     );
 
     let mut rel_sections: Vec<(u64, u64)> = vec![];
-    let mut rela_sections: Vec<(usize, u64, u64)> = vec![];
-    for (i, sh) in section_headers.iter_mut().enumerate() {
+    let mut rela_sections: Vec<(u64, u64)> = vec![];
+    for sh in section_headers.iter_mut() {
         let sh_offset = sh.sh_offset.get(LE);
         let sh_addr = sh.sh_addr.get(LE);
         if physical_shift_start <= sh_offset {
@@ -683,7 +1055,7 @@ fn gen_elf_le(
         if sh_type == elf::SHT_REL {
             rel_sections.push((sh_offset, sh.sh_size.get(LE)));
         } else if sh_type == elf::SHT_RELA {
-            rela_sections.push((i, sh_offset, sh.sh_size.get(LE)));
+            rela_sections.push((sh_offset, sh.sh_size.get(LE)));
         }
     }
 
@@ -715,7 +1087,7 @@ fn gen_elf_le(
     }
 
     let dyn_offset = md.dynamic_section_offset + md.added_byte_count;
-    for (sec_index, sec_offset, sec_size) in rela_sections {
+    for (sec_offset, sec_size) in rela_sections {
         let relocations = load_structs_inplace_mut::<elf::Rela64<LE>>(
             &mut out_mmap,
             sec_offset as usize + md.added_byte_count as usize,
@@ -723,26 +1095,33 @@ fn gen_elf_le(
         );
         for (i, rel) in relocations.iter_mut().enumerate() {
             let r_offset = rel.r_offset.get(LE);
+            let r_addend = rel.r_addend.get(LE);
             if virtual_shift_start <= r_offset {
                 rel.r_offset.set(LE, r_offset + md.added_byte_count);
                 // Deal with potential adjusts to absolute jumps.
+                // Static-pie hosts also resolve their ifuncs through IRELATIVE relocations.
                 // TODO: Verify other relocation types.
-                if rel.r_type(LE, false) == elf::R_X86_64_RELATIVE {
-                    let r_addend = rel.r_addend.get(LE);
+                let r_type = rel.r_type(LE, false);
+                if r_type == arch.r_relative() || r_type == arch.r_irelative() {
                     rel.r_addend.set(LE, r_addend + md.added_byte_count as i64);
                 }
             }
+            let addend_addr = sec_offset as usize
+                + i * mem::size_of::<elf::Rela64<LE>>()
+                // This 16 skips the first 2 fields and gets to the addend field.
+                + 16;
             // If the relocation goes to a roc function, we need to surgically link it and change it to relative.
             let r_type = rel.r_type(LE, false);
-            if r_type == elf::R_X86_64_GLOB_DAT {
+            if r_type == arch.r_glob_dat() || r_type == arch.r_abs64() {
                 let r_sym = rel.r_sym(LE, false);
                 for (name, index) in got_app_syms.iter() {
                     if *index as u32 == r_sym {
-                        rel.set_r_info(LE, false, 0, elf::R_X86_64_RELATIVE);
-                        let addend_addr = sec_offset as usize
-                            + i * mem::size_of::<elf::Rela64<LE>>()
-                            // This 16 skips the first 2 fields and gets to the addend field.
-                            + 16;
+                        if r_addend != 0 {
+                            internal_error!(
+                                "Surgical linking does not support pointers into the middle of an app function: {name}{r_addend:+}"
+                            );
+                        }
+                        rel.set_r_info(LE, false, 0, arch.r_relative());
                         md.surgeries.get_mut(name).unwrap().push(SurgeryEntry {
                             file_offset: addend_addr as u64,
                             virtual_offset: VirtualOffset::Absolute,
@@ -750,88 +1129,26 @@ fn gen_elf_le(
                         });
                     }
                 }
-            }
-        }
-        // To correctly remove the JUMP_SLOT relocations for Roc functions we:
-        //     1. collect the indicies of all of them.
-        //     2. move them all to the end of the relocation sections.
-        //     3. shrink the relocation section to ignore them.
-        //     4. update the dynamic section to reflect the shrink as well.
-        let mut to_remove = relocations
-            .iter()
-            .enumerate()
-            .filter_map(|(i, rel)| {
-                let r_type = rel.r_type(LE, false);
-                let r_sym = rel.r_sym(LE, false);
-                if r_type == elf::R_X86_64_JUMP_SLOT && app_sym_indices.contains(&(r_sym as usize))
-                {
-                    Some(i)
-                } else {
-                    None
+            } else if r_type == arch.r_relative() {
+                // A position-independent host stores pointers to its own plt entries or stubs as relative addresses.
+                if let Some(name) = app_func_addresses.get(&(r_addend as u64)) {
+                    md.surgeries.get_mut(*name).unwrap().push(SurgeryEntry {
+                        file_offset: addend_addr as u64,
+                        virtual_offset: VirtualOffset::Absolute,
+                        size: 8,
+                    });
                 }
-            })
-            .collect::<Vec<_>>();
-
-        // We must remove in descending order to avoid swapping an element more than once and messing up the removal.
-        to_remove.sort();
-        to_remove.reverse();
-
-        let mut j = relocations.len() - 1;
-        for i in to_remove.iter() {
-            relocations.swap(*i, j);
-            let r_sym = relocations[j].r_sym(LE, false);
-            relocations[j].set_r_info(LE, false, r_sym, elf::R_X86_64_NONE);
-            j -= 1;
+            }
         }
-
-        let section_headers = load_structs_inplace_mut::<elf::SectionHeader64<LE>>(
-            &mut out_mmap,
-            sh_offset as usize + md.added_byte_count as usize,
-            sh_num as usize,
-        );
-
-        let old_size = section_headers[sec_index].sh_size.get(LE);
-        let removed_count = to_remove.len();
-        let removed_size = removed_count * std::mem::size_of::<elf::Rela64<LE>>();
-        section_headers[sec_index]
-            .sh_size
-            .set(LE, old_size - removed_size as u64);
-
-        let dyns = load_structs_inplace_mut::<elf::Dyn64<LE>>(
-            &mut out_mmap,
-            dyn_offset as usize,
-            dynamic_lib_count,
-        );
-        let is_rela_dyn = dyns
-            .iter()
-            .filter(|d| {
-                let tag = d.d_tag.get(LE) as u32;
-                tag == elf::DT_RELA
-            })
-            .any(|d| d.d_val.get(LE) == sec_offset);
-        let is_rela_plt = dyns
-            .iter()
-            .filter(|d| {
-                let tag = d.d_tag.get(LE) as u32;
-                tag == elf::DT_JMPREL
-            })
-            .any(|d| d.d_val.get(LE) == sec_offset);
-
-        for d in dyns.iter_mut() {
-            match d.d_tag.get(LE) as u32 {
-                elf::DT_RELACOUNT if is_rela_dyn => {
-                    let old_count = d.d_val.get(LE);
-                    d.d_val.set(LE, old_count - removed_count as u64);
-                }
-                elf::DT_RELASZ if is_rela_dyn => {
-                    let old_size = d.d_val.get(LE);
-                    d.d_val.set(LE, old_size - removed_size as u64);
-                }
-                elf::DT_PLTRELSZ if is_rela_plt => {
-                    let old_size = d.d_val.get(LE);
-                    d.d_val.set(LE, old_size - removed_size as u64);
-                }
-                _ => {}
+        // The plt entries of Roc functions will jump straight to the app, so their JUMP_SLOT relocations
+        // must no longer reference the symbols in `libapp.so`. We keep them in place, because lazy binding
+        // finds the relocation of every other plt entry by its index. A JUMP_SLOT relocation of the null
+        // symbol only writes into a GOT entry that nothing reads anymore.
+        for rel in relocations.iter_mut() {
+            let r_type = rel.r_type(LE, false);
+            let r_sym = rel.r_sym(LE, false);
+            if r_type == arch.r_jump_slot() && app_sym_indices.contains(&(r_sym as usize)) {
+                rel.set_r_info(LE, false, 0, arch.r_jump_slot());
             }
         }
     }
@@ -901,6 +1218,7 @@ fn gen_elf_le(
     }
 
     // Update all data in the global offset table.
+    // A static-pie host also resolves its TLS offsets into the GOT, and those must not be shifted.
     for (offset, size) in got_sections {
         let global_offsets = load_structs_inplace_mut::<endian::U64<LE>>(
             &mut out_mmap,
@@ -909,7 +1227,7 @@ fn gen_elf_le(
         );
         for go in global_offsets.iter_mut() {
             let go_addr = go.get(LE);
-            if physical_shift_start <= go_addr {
+            if (virtual_shift_start..last_segment_vaddr).contains(&go_addr) {
                 go.set(LE, go_addr + md.added_byte_count);
             }
         }
@@ -918,13 +1236,15 @@ fn gen_elf_le(
     // TODO: look into shifting all of the debug info and eh_frames.
 
     // Delete shared library from the dynamic table.
-    let out_ptr = out_mmap.as_mut_ptr();
-    unsafe {
-        std::ptr::copy(
-            out_ptr.add(dyn_offset as usize + 16 * (shared_lib_index + 1)),
-            out_ptr.add(dyn_offset as usize + 16 * shared_lib_index),
-            16 * (dynamic_lib_count - shared_lib_index),
-        );
+    if let Some(shared_lib_index) = shared_lib_index {
+        let out_ptr = out_mmap.as_mut_ptr();
+        unsafe {
+            std::ptr::copy(
+                out_ptr.add(dyn_offset as usize + 16 * (shared_lib_index + 1)),
+                out_ptr.add(dyn_offset as usize + 16 * shared_lib_index),
+                16 * (dynamic_lib_count - shared_lib_index),
+            );
+        }
     }
 
     // Update main elf header for extra data.
@@ -943,11 +1263,14 @@ fn gen_elf_le(
     out_mmap
 }
 
+#[allow(clippy::too_many_arguments)]
 fn scan_elf_dynamic_deps(
     exec_obj: &object::File,
+    arch: ElfArch,
     md: &mut Metadata,
     app_syms: &[Symbol],
     shared_lib: &Path,
+    static_pie: bool,
     exec_data: &[u8],
     verbose: bool,
 ) -> ElfDynamicDeps {
@@ -1018,10 +1341,10 @@ fn scan_elf_dynamic_deps(
     }
     let dynamic_lib_count = dyn_lib_index;
 
-    if shared_lib_index.is_none() {
+    // A static-pie host has its app functions defined by a stub instead.
+    if shared_lib_index.is_none() && !static_pie {
         panic!("Shared lib not found as a dependency of the executable");
     }
-    let shared_lib_index = shared_lib_index.unwrap();
 
     let symtab_sec = match exec_obj.section_by_name(".symtab") {
         Some(sec) => sec,
@@ -1088,7 +1411,11 @@ fn scan_elf_dynamic_deps(
         }
     })
     .filter_map(|(_, reloc)| {
-        if let RelocationKind::Elf(elf::R_X86_64_GLOB_DAT) = reloc.kind() {
+        // Pointers to app functions in the data of a position-independent host are bound with
+        // an absolute relocation like `R_X86_64_64`.
+        if reloc.kind() == RelocationKind::Elf(arch.r_glob_dat())
+            || reloc.kind() == RelocationKind::Absolute
+        {
             for symbol in app_syms.iter() {
                 if reloc.target() == RelocationTarget::Symbol(symbol.index()) {
                     return Some((symbol.name().unwrap().to_string(), symbol.index().0));
@@ -1107,7 +1434,7 @@ fn scan_elf_dynamic_deps(
         }
     })
     .filter_map(|(_, reloc)| {
-        if let RelocationKind::Elf(elf::R_X86_64_JUMP_SLOT) = reloc.kind() {
+        if reloc.kind() == RelocationKind::Elf(arch.r_jump_slot()) {
             for symbol in app_syms.iter() {
                 if reloc.target() == RelocationTarget::Symbol(symbol.index()) {
                     return Some(symbol.index().0);
@@ -1227,6 +1554,7 @@ fn surgery_elf_help(
     if !elf64 || !litte_endian {
         internal_error!("Only 64bit little endian elf currently supported for surgery");
     }
+    let arch = ElfArch::from_exec_data(exec_mmap);
     let exec_header = load_struct_inplace::<elf::FileHeader64<LE>>(exec_mmap, 0);
 
     let ph_offset = exec_header.e_phoff.get(LE);
@@ -1437,7 +1765,31 @@ fn surgery_elf_help(
                             })
                     };
 
-                    if let Some(target_offset) = target_offset {
+                    let aarch64_r_type = match rel.1.kind() {
+                        _ if arch != ElfArch::Aarch64 => None,
+                        RelocationKind::PltRelative
+                            if rel.1.encoding() == RelocationEncoding::AArch64Call =>
+                        {
+                            Some(elf::R_AARCH64_CALL26)
+                        }
+                        RelocationKind::Elf(r_type) => Some(r_type),
+                        _ => None,
+                    };
+
+                    if let (Some(target_offset), Some(r_type)) = (target_offset, aarch64_r_type) {
+                        let virt_base = section_virtual_offset + rel.0 as usize;
+                        let base = section_offset + rel.0 as usize;
+                        let target = target_offset + rel.1.addend();
+                        let inst = u32::from_le_bytes(exec_mmap[base..][..4].try_into().unwrap());
+                        let inst = aarch64_relocate(inst, r_type, virt_base as i64, target);
+                        if verbose {
+                            println!(
+                                "\t\tRelocation base location: {base:+x} (virt: {virt_base:+x})",
+                            );
+                            println!("\t\tFinal relocation target: {target:+x}");
+                        }
+                        exec_mmap[base..][..4].copy_from_slice(&inst.to_le_bytes());
+                    } else if let Some(target_offset) = target_offset {
                         let virt_base = section_virtual_offset + rel.0 as usize;
                         let base = section_offset + rel.0 as usize;
                         let target: i64 = match rel.1.kind() {
@@ -1640,6 +1992,16 @@ fn surgery_elf_help(
                 VirtualOffset::Absolute => 0,
             };
             match s.size {
+                4 if arch == ElfArch::Aarch64 => {
+                    let offset = (s.file_offset + md.added_byte_count) as usize;
+                    let inst = u32::from_le_bytes(exec_mmap[offset..][..4].try_into().unwrap());
+                    let inst = aarch64_set_branch_target(
+                        inst,
+                        surgery_virt_offset,
+                        func_virt_offset as i64,
+                    );
+                    exec_mmap[offset..][..4].copy_from_slice(&inst.to_le_bytes());
+                }
                 4 => {
                     let target = (func_virt_offset as i64 - surgery_virt_offset) as i32;
                     if verbose {
@@ -1669,21 +2031,60 @@ fn surgery_elf_help(
         if let Some((plt_off, plt_vaddr)) = md.plt_addresses.get(func_name) {
             let plt_off = (*plt_off + md.added_byte_count) as usize;
             let plt_vaddr = *plt_vaddr + md.added_byte_count;
-            let jmp_inst_len = 5;
-            let target =
-                (func_virt_offset as i64 - (plt_vaddr as i64 + jmp_inst_len as i64)) as i32;
             if verbose {
                 println!("\tPLT: {plt_off:+x}, {plt_vaddr:+x}");
-                println!("\tTarget Jump: {target:+x}");
             }
-            let data = target.to_le_bytes();
-            exec_mmap[plt_off] = 0xE9;
-            exec_mmap[plt_off + 1..plt_off + jmp_inst_len].copy_from_slice(&data);
-            for i in jmp_inst_len..PLT_ADDRESS_OFFSET as usize {
-                exec_mmap[plt_off + i] = 0x90;
+            match arch {
+                ElfArch::X86_64 => {
+                    let jmp_inst_len = JMP_INST_LEN as usize;
+                    let target =
+                        (func_virt_offset as i64 - (plt_vaddr as i64 + jmp_inst_len as i64)) as i32;
+                    if verbose {
+                        println!("\tTarget Jump: {target:+x}");
+                    }
+                    let data = target.to_le_bytes();
+                    exec_mmap[plt_off] = 0xE9;
+                    exec_mmap[plt_off + 1..plt_off + jmp_inst_len].copy_from_slice(&data);
+                    for i in jmp_inst_len..PLT_ADDRESS_OFFSET as usize {
+                        exec_mmap[plt_off + i] = 0x90;
+                    }
+                }
+                ElfArch::Aarch64 => {
+                    // A `b` to the app, followed by `nop`s for the rest of the entry.
+                    let b = aarch64_set_branch_target(
+                        0x1400_0000,
+                        plt_vaddr as i64,
+                        func_virt_offset as i64,
+                    );
+                    if verbose {
+                        println!("\tBranch: {b:08x}");
+                    }
+                    let plt_entry = &mut exec_mmap[plt_off..][..PLT_ADDRESS_OFFSET as usize];
+                    for (i, word) in plt_entry
+                        .chunks_exact_mut(AARCH64_INST_LEN as usize)
+                        .enumerate()
+                    {
+                        let inst = if i == 0 { b } else { AARCH64_NOP };
+                        word.copy_from_slice(&inst.to_le_bytes());
+                    }
+                }
             }
         }
 
+        // Likewise, replace the stub definition in a static-pie host with a jump.
+        if let Some((stub_off, stub_vaddr)) = md.stub_addresses.get(func_name) {
+            let stub_off = (*stub_off + md.added_byte_count) as usize;
+            let stub_vaddr = *stub_vaddr + md.added_byte_count;
+            let target = (func_virt_offset as i64 - (stub_vaddr + JMP_INST_LEN) as i64) as i32;
+            if verbose {
+                println!("\tStub: {stub_off:+x}, {stub_vaddr:+x}");
+                println!("\tTarget Jump: {target:+x}");
+            }
+            exec_mmap[stub_off] = 0xE9;
+            exec_mmap[stub_off + 1..stub_off + JMP_INST_LEN as usize]
+                .copy_from_slice(&target.to_le_bytes());
+        }
+
         if let Some(i) = md.dynamic_symbol_indices.get(func_name) {
             let sym = load_struct_inplace_mut::<elf::Sym64<LE>>(
                 exec_mmap,
@@ -1722,6 +2123,93 @@ fn surgery_elf_help(
     *offset_ref = offset;
 }
 
+/// Finds the Roc symbols an ELF host expects, along with the PLT and GOT slots it uses for them.
+/// A preprocessed host no longer binds them dynamically, so its slots come from the metadata instead.
+pub(crate) fn inspect_elf(
+    exec_data: &[u8],
+    metadata_path: Option<&Path>,
+) -> (Vec<HostSymbol>, Vec<String>) {
+    use object::ObjectSymbolTable;
+
+    let exec_obj = match object::File::parse(exec_data) {
+        Ok(obj) => obj,
+        Err(err) => {
+            internal_error!("Failed to parse executable file: {}", err);
+        }
+    };
+
+    let mut expected: MutMap<String, Vec<HostSlot>> = exec_obj
+        .dynamic_symbols()
+        .filter(is_roc_undefined)
+        .map(|sym| (sym.name().unwrap().to_string(), vec![]))
+        .collect();
+
+    let known_arch = matches!(
+        exec_obj.architecture(),
+        object::Architecture::X86_64 | object::Architecture::Aarch64
+    );
+    if let (true, Some(relocs)) = (known_arch, exec_obj.dynamic_relocations()) {
+        let arch = ElfArch::from_exec_data(exec_data);
+        let first_plt = first_plt_entry(&exec_obj, arch);
+        let mut plt_index = 0;
+
+        for (offset, reloc) in relocs {
+            let is_jump_slot = reloc.kind() == RelocationKind::Elf(arch.r_jump_slot());
+            let is_pointer = reloc.kind() == RelocationKind::Elf(arch.r_glob_dat())
+                || reloc.kind() == RelocationKind::Absolute;
+
+            let name = match reloc.target() {
+                RelocationTarget::Symbol(index) => exec_obj
+                    .dynamic_symbol_table()
+                    .and_then(|table| table.symbol_by_index(index).ok())
+                    .and_then(|sym| sym.name().ok()),
+                _ => None,
+            };
+
+            if let Some(slots) = name.and_then(|name| expected.get_mut(name)) {
+                if is_jump_slot {
+                    if let Some((plt_address, _)) = first_plt {
+                        slots.push(HostSlot::Plt(plt_index * PLT_ADDRESS_OFFSET + plt_address));
+                    }
+                    slots.push(HostSlot::Got(offset));
+                } else if is_pointer {
+                    slots.push(HostSlot::Got(offset));
+                }
+            }
+
+            if is_jump_slot {
+                plt_index += 1;
+            }
+        }
+    }
+
+    if let Some(md) = metadata_path.map(Metadata::read_from_file) {
+        for name in md.app_functions.iter() {
+            let slots = expected.entry(name.clone()).or_default();
+            if let Some((_, address)) = md.plt_addresses.get(name) {
+                slots.push(HostSlot::Plt(address + md.added_byte_count));
+            }
+            if let Some(surgeries) = md.surgeries.get(name) {
+                slots.push(HostSlot::Surgeries(surgeries.len()));
+            }
+        }
+    }
+
+    let mut expected: Vec<_> = expected
+        .into_iter()
+        .map(|(name, slots)| HostSymbol { name, slots })
+        .collect();
+    expected.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut provided: Vec<_> = collect_roc_definitions(&exec_obj)
+        .into_keys()
+        .filter(|name| name.starts_with("roc_"))
+        .collect();
+    provided.sort();
+
+    (expected, provided)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1731,6 +2219,8 @@ mod tests {
     use roc_target::Target;
 
     const ELF64_DYNHOST: &[u8] = include_bytes!("../dynhost_benchmarks_elf64") as &[_];
+    const AARCH64_HOST: &[u8] = include_bytes!("../aarch64_fixture/host") as &[_];
+    const AARCH64_APP: &[u8] = include_bytes!("../aarch64_fixture/app.o") as &[_];
 
     #[test]
     fn collect_definitions() {
@@ -1781,6 +2271,39 @@ mod tests {
         )
     }
 
+    /// Cross compiles when the target is not the host machine.
+    fn zig_target_args(target: Target) -> &'static [&'static str] {
+        match target {
+            Target::LinuxArm64 => &["-target", "aarch64-linux-gnu"],
+            _ => &[],
+        }
+    }
+
+    #[test]
+    fn inspect_dynhost() {
+        let (expected, provided) = inspect_elf(ELF64_DYNHOST, None);
+
+        let names: Vec<_> = expected.iter().map(|sym| sym.name.as_str()).collect();
+        assert_eq!(
+            [
+                "roc__mainForHost_1__Fx_caller",
+                "roc__mainForHost_1__Fx_result_size",
+                "roc__mainForHost_1_exposed_generic",
+                "roc__mainForHost_size"
+            ],
+            names.as_slice()
+        );
+
+        // `roc__mainForHost_size@plt` jumps through the GOT entry of its JUMP_SLOT relocation.
+        assert_eq!(
+            expected[3].slots,
+            [HostSlot::Plt(0x531a0), HostSlot::Got(0x5b8e0)]
+        );
+
+        assert!(provided.contains(&"roc_alloc".to_string()));
+        assert!(!provided.contains(&"memset".to_string()));
+    }
+
     #[allow(dead_code)]
     fn zig_host_app_help(dir: &Path, target: Target) {
         let host_zig = indoc!(
@@ -1815,6 +2338,7 @@ mod tests {
         let output = std::process::Command::new(&zig)
             .current_dir(dir)
             .args(["build-obj", "app.zig", "-fPIC", "-OReleaseFast"])
+            .args(zig_target_args(target))
             .output()
             .unwrap();
 
@@ -1841,7 +2365,7 @@ mod tests {
                 .collect()
         };
 
-        let dylib_bytes = crate::generate_dylib::create_dylib_elf64(&names).unwrap();
+        let dylib_bytes = crate::generate_dylib::create_dylib_elf64(&names, target).unwrap();
         std::fs::write(dir.join("libapp.so"), dylib_bytes).unwrap();
 
         // now we can compile the host (it uses libapp.so, hence the order here)
@@ -1855,6 +2379,7 @@ mod tests {
                 "-lc",
                 "-OReleaseFast",
             ])
+            .args(zig_target_args(target))
             .output()
             .unwrap();
 
@@ -1874,6 +2399,7 @@ mod tests {
             &dir.join("metadata"),
             &preprocessed_host_filename,
             &dir.join("libapp.so"),
+            &names,
             false,
             false,
         );
@@ -1915,4 +2441,345 @@ mod tests {
 
         assert_eq!("Hello foo\n", output);
     }
+
+    /// Checks that a linked aarch64 executable branches straight to the app function,
+    /// both from the host's code and from its plt entry, and no longer needs `libapp.so`.
+    fn check_aarch64_app_branches(final_bytes: &[u8], func_name: &str) {
+        let object = object::File::parse(final_bytes).unwrap();
+        assert_eq!(object.architecture(), object::Architecture::Aarch64);
+
+        let func_address = object
+            .dynamic_symbols()
+            .find(|sym| sym.name() == Ok(func_name))
+            .unwrap()
+            .address();
+
+        let branches_to_app = |section_name: &str| {
+            let section = object.section_by_name(section_name).unwrap();
+            section
+                .data()
+                .unwrap()
+                .chunks_exact(4)
+                .enumerate()
+                .filter(|(i, word)| {
+                    let inst = u32::from_le_bytes((*word).try_into().unwrap());
+                    let address = section.address() + *i as u64 * 4;
+                    aarch64_branch_target(inst, address) == Some(func_address)
+                })
+                .count()
+        };
+
+        assert!(branches_to_app(".text") > 0, "no call to {func_name}");
+        assert_eq!(branches_to_app(".plt"), 1, "plt entry is not patched");
+
+        use object::read::elf::{Dyn, FileHeader};
+
+        let header = elf::FileHeader64::<LE>::parse(final_bytes).unwrap();
+        let sections = header.sections(LE, final_bytes).unwrap();
+        let (dyns, link) = sections.dynamic(LE, final_bytes).unwrap().unwrap();
+        let strings = sections.strings(LE, final_bytes, link).unwrap();
+        let needs_libapp = dyns
+            .iter()
+            .take_while(|d| d.d_tag.get(LE) != elf::DT_NULL as u64)
+            .filter(|d| d.d_tag.get(LE) == elf::DT_NEEDED as u64)
+            .any(|d| d.string(LE, strings) == Ok(b"libapp.so".as_slice()));
+        assert!(!needs_libapp, "libapp.so is still needed");
+    }
+
+    #[test]
+    fn zig_aarch64_host_app() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+
+        zig_host_app_help(dir, Target::LinuxArm64);
+
+        let final_bytes = std::fs::read(dir.join("final")).unwrap();
+        check_aarch64_app_branches(&final_bytes, "roc_magic1");
+    }
+
+    /// Like `zig_aarch64_host_app`, but with a prebuilt host and app (see `aarch64_fixture/build.sh`),
+    /// so that it runs without zig or an aarch64 toolchain.
+    #[test]
+    fn aarch64_host_app() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+
+        let target = Target::LinuxArm64;
+        let names = vec!["roc_magic1".to_string()];
+
+        std::fs::write(dir.join("host"), AARCH64_HOST).unwrap();
+
+        let dylib_bytes = crate::generate_dylib::create_dylib_elf64(&names, target).unwrap();
+        std::fs::write(dir.join("libapp.so"), dylib_bytes).unwrap();
+
+        let preprocessed_host_filename = dir.join(preprocessed_host_filename(target));
+
+        preprocess_elf_le(
+            &dir.join("host"),
+            &dir.join("metadata"),
+            &preprocessed_host_filename,
+            &dir.join("libapp.so"),
+            &names,
+            false,
+            false,
+        );
+
+        std::fs::copy(&preprocessed_host_filename, dir.join("final")).unwrap();
+
+        surgery_elf(
+            AARCH64_APP,
+            &dir.join("metadata"),
+            &dir.join("final"),
+            false,
+            false,
+        );
+
+        let final_bytes = std::fs::read(dir.join("final")).unwrap();
+        check_aarch64_app_branches(&final_bytes, "roc_magic1");
+
+        // `magic_ptr` was an absolute relocation against `roc_magic1`, and now points at the app.
+        let object = object::File::parse(final_bytes.as_slice()).unwrap();
+        let func_address = object
+            .dynamic_symbols()
+            .find(|sym| sym.name() == Ok("roc_magic1"))
+            .unwrap()
+            .address();
+        let (offset, size) = object
+            .section_by_name(".rela.dyn")
+            .unwrap()
+            .file_range()
+            .unwrap();
+        let relocations = load_structs_inplace::<elf::Rela64<LE>>(
+            &final_bytes,
+            offset as usize,
+            size as usize / mem::size_of::<elf::Rela64<LE>>(),
+        );
+        assert!(relocations.iter().any(|rel| {
+            rel.r_type(LE, false) == elf::R_AARCH64_RELATIVE
+                && rel.r_addend.get(LE) as u64 == func_address
+        }));
+    }
+
+    fn run_cc(dir: &Path, args: &[&str]) {
+        let cc = std::env::var("CC").unwrap_or_else(|_| "cc".into());
+
+        let output = std::process::Command::new(cc)
+            .current_dir(dir)
+            .args(args)
+            .output()
+            .unwrap();
+
+        if !output.status.success() {
+            use std::io::Write;
+
+            std::io::stdout().write_all(&output.stdout).unwrap();
+            std::io::stderr().write_all(&output.stderr).unwrap();
+
+            panic!("cc {args:?} failed");
+        }
+    }
+
+    #[allow(dead_code)]
+    enum HostKind {
+        /// The compiler's default
+        Pie,
+        NoPie,
+        StaticPie,
+    }
+
+    /// Builds `app.o`, `libapp.so` and a C host of the given kind.
+    /// The host both calls the app function and stores a pointer to it.
+    #[allow(dead_code)]
+    fn c_build_host(dir: &Path, host_kind: HostKind) {
+        let host_c = indoc!(
+            r#"
+            #include <stdio.h>
+
+            extern const char *roc_magic1(unsigned long);
+
+            const char *(*magic_ptr)(unsigned long) = roc_magic1;
+
+            int main() {
+                printf("Hello %.3s %.3s\n", roc_magic1(0), magic_ptr(0));
+                return 0;
+            }
+            "#
+        );
+
+        let app_c = indoc!(
+            r#"
+            static const char X[] = "foo";
+
+            const char *roc_magic1(unsigned long index) {
+                return X + index;
+            }
+            "#
+        );
+
+        let stub_c = indoc!(
+            r#"
+            const char *roc_magic1(unsigned long index) {
+                return "bar" + index;
+            }
+            "#
+        );
+
+        std::fs::write(dir.join("host.c"), host_c.as_bytes()).unwrap();
+        std::fs::write(dir.join("app.c"), app_c.as_bytes()).unwrap();
+        std::fs::write(dir.join("stub.c"), stub_c.as_bytes()).unwrap();
+
+        run_cc(
+            dir,
+            &[
+                "-c",
+                "app.c",
+                "-fPIC",
+                "-O2",
+                "-fno-asynchronous-unwind-tables",
+            ],
+        );
+
+        let names = vec!["roc_magic1".to_string()];
+
+        let dylib_bytes =
+            crate::generate_dylib::create_dylib_elf64(&names, Target::LinuxX64).unwrap();
+        std::fs::write(dir.join("libapp.so"), dylib_bytes).unwrap();
+
+        match host_kind {
+            HostKind::Pie => run_cc(dir, &["-O0", "host.c", "libapp.so", "-o", "host"]),
+            HostKind::NoPie => run_cc(
+                dir,
+                &["-no-pie", "-O0", "host.c", "libapp.so", "-o", "host"],
+            ),
+            HostKind::StaticPie => run_cc(
+                dir,
+                &["-static-pie", "-O0", "host.c", "stub.c", "-o", "host"],
+            ),
+        }
+    }
+
+    /// Preprocesses the host built by `c_build_host` and links the app into it as `final`.
+    #[allow(dead_code)]
+    fn c_host_app_help(dir: &Path, target: Target, host_kind: HostKind) {
+        c_build_host(dir, host_kind);
+
+        let file = std::fs::File::open(dir.join("app.o")).unwrap();
+        let roc_app = unsafe { memmap2::Mmap::map(&file) }.unwrap();
+
+        let names = vec!["roc_magic1".to_string()];
+
+        let preprocessed_host_filename = dir.join(preprocessed_host_filename(target));
+
+        preprocess_elf_le(
+            &dir.join("host"),
+            &dir.join("metadata"),
+            &preprocessed_host_filename,
+            &dir.join("libapp.so"),
+            &names,
+            false,
+            false,
+        );
+
+        std::fs::copy(&preprocessed_host_filename, dir.join("final")).unwrap();
+
+        surgery_elf(
+            &roc_app,
+            &dir.join("metadata"),
+            &dir.join("final"),
+            false,
+            false,
+        );
+    }
+
+    /// Runs `final`, with either lazy binding or `LD_BIND_NOW`.
+    #[allow(dead_code)]
+    fn run_c_host_app(dir: &Path, bind_now: bool) -> String {
+        let mut command = std::process::Command::new(dir.join("final"));
+        command.current_dir(dir).env_remove("LD_BIND_NOW");
+
+        if bind_now {
+            command.env("LD_BIND_NOW", "1");
+        }
+
+        let output = command.output().unwrap();
+
+        if !output.status.success() {
+            use std::io::Write;
+
+            std::io::stdout().write_all(&output.stdout).unwrap();
+            std::io::stderr().write_all(&output.stderr).unwrap();
+
+            panic!("app.exe failed");
+        }
+
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    }
+
+    /// The symbol indices of the JUMP_SLOT relocations in `.rela.plt`.
+    #[allow(dead_code)]
+    fn jump_slot_symbols(path: &Path) -> Vec<u32> {
+        let data = std::fs::read(path).unwrap();
+        let obj = object::File::parse(data.as_slice()).unwrap();
+
+        let Some(rela_plt) = obj.section_by_name(".rela.plt") else {
+            return Vec::new();
+        };
+        let (offset, size) = rela_plt.file_range().unwrap();
+        let relocations = load_structs_inplace::<elf::Rela64<LE>>(
+            &data,
+            offset as usize,
+            size as usize / mem::size_of::<elf::Rela64<LE>>(),
+        );
+
+        relocations
+            .iter()
+            .filter(|rel| rel.r_type(LE, false) == elf::R_X86_64_JUMP_SLOT)
+            .map(|rel| rel.r_sym(LE, false))
+            .collect()
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn c_pie_host_app() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+
+        c_host_app_help(dir, Target::LinuxX64, HostKind::Pie);
+
+        // The JUMP_SLOT relocation of `roc_magic1` is kept, but now points at the null symbol.
+        // The loader has to accept it both lazily and when binding everything at startup.
+        assert!(jump_slot_symbols(&dir.join("final")).contains(&0));
+
+        assert_eq!("Hello foo foo\n", run_c_host_app(dir, false));
+        assert_eq!("Hello foo foo\n", run_c_host_app(dir, true));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn c_static_pie_host_app() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+
+        c_host_app_help(dir, Target::LinuxX64, HostKind::StaticPie);
+
+        // A static-pie host calls its stub definitions directly, so there is no JUMP_SLOT relocation
+        // to retarget, and its startup code only applies relative relocations.
+        assert!(jump_slot_symbols(&dir.join("final")).is_empty());
+
+        assert_eq!("Hello foo foo\n", run_c_host_app(dir, false));
+        assert_eq!("Hello foo foo\n", run_c_host_app(dir, true));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn c_no_pie_host_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+
+        c_build_host(dir, HostKind::NoPie);
+
+        // `preprocess_elf_le` exits with a user error for this host.
+        let exec_data = std::fs::read(dir.join("host")).unwrap();
+        assert!(!is_position_independent(&exec_data));
+    }
 }
@@ -14,7 +14,7 @@ pub(crate) use pe::APP_DLL;
 
 pub fn generate(target: Target, custom_names: &[String]) -> object::read::Result<Vec<u8>> {
     match target.operating_system() {
-        OperatingSystem::Linux => elf64::create_dylib_elf64(custom_names),
+        OperatingSystem::Linux => elf64::create_dylib_elf64(custom_names, target),
         OperatingSystem::Mac => macho::create_dylib_macho(custom_names, target),
         OperatingSystem::Windows => Ok(pe::synthetic_dll(custom_names)),
         other => unimplemented!("dylib creation for {:?}", other),
@@ -57,6 +57,20 @@ mod tests {
         check_exports(&target);
     }
 
+    #[test]
+    fn check_exports_elf64_aarch64() {
+        let target = target_lexicon::Triple {
+            architecture: target_lexicon::Architecture::Aarch64(
+                target_lexicon::Aarch64Architecture::Aarch64,
+            ),
+            operating_system: target_lexicon::OperatingSystem::Linux,
+            binary_format: target_lexicon::BinaryFormat::Elf,
+            ..target_lexicon::Triple::host()
+        };
+
+        check_exports(&target);
+    }
+
     #[test]
     fn check_exports_coff() {
         // NOTE: this does not work
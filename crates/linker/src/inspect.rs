@@ -0,0 +1,250 @@
+//! A report of how a host binary refers to the Roc app, for diagnosing the surgical linker.
+use std::fmt;
+
+use crate::ExposedSymbols;
+
+/// A place in the host that refers to a Roc function, and that the surgical linker must patch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostSlot {
+    /// A procedure linkage table entry that the host calls through.
+    Plt(u64),
+    /// A global offset table entry or data pointer that the loader fills in.
+    Got(u64),
+    /// An import address table entry of the dummy app dll.
+    Import(u64),
+    /// The number of call sites and pointers the preprocessor found.
+    Surgeries(usize),
+}
+
+impl fmt::Display for HostSlot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HostSlot::Plt(address) => write!(f, "PLT entry at {address:#x}"),
+            HostSlot::Got(address) => write!(f, "GOT entry at {address:#x}"),
+            HostSlot::Import(address) => write!(f, "import address at {address:#x}"),
+            HostSlot::Surgeries(1) => write!(f, "1 location to patch"),
+            HostSlot::Surgeries(count) => write!(f, "{count} locations to patch"),
+        }
+    }
+}
+
+/// A Roc symbol that the host expects the app to define.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostSymbol {
+    pub name: String,
+    pub slots: Vec<HostSlot>,
+}
+
+#[derive(Debug)]
+pub struct HostReport {
+    pub format: object::BinaryFormat,
+    pub architecture: object::Architecture,
+    /// Whether this is a `.rh` host, with the app's dynamic library already removed.
+    pub preprocessed: bool,
+    /// The symbols the host expects from the app, sorted by name.
+    pub expected: Vec<HostSymbol>,
+    /// The `roc_*` functions the host defines for the app, like `roc_alloc`.
+    pub provided: Vec<String>,
+}
+
+/// The differences between the symbols a host expects and those an app exposes to it.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Mismatches {
+    /// Linking fails, because the host expects these but the app does not define them.
+    pub missing_from_app: Vec<String>,
+    /// The host never refers to these, so the app defines them for nothing.
+    pub unused_by_host: Vec<String>,
+}
+
+impl Mismatches {
+    pub fn is_empty(&self) -> bool {
+        self.missing_from_app.is_empty() && self.unused_by_host.is_empty()
+    }
+}
+
+impl HostReport {
+    pub fn mismatches(&self, exposed: &ExposedSymbols) -> Mismatches {
+        let app_symbols = exposed.stub_dll_symbols();
+
+        let missing_from_app = self
+            .expected
+            .iter()
+            .filter(|symbol| !app_symbols.contains(&symbol.name))
+            .map(|symbol| symbol.name.clone())
+            .collect();
+
+        let unused_by_host = app_symbols
+            .into_iter()
+            .filter(|name| !self.expected.iter().any(|symbol| &symbol.name == name))
+            .collect();
+
+        Mismatches {
+            missing_from_app,
+            unused_by_host,
+        }
+    }
+}
+
+/// Explains what the app provides through one of its exposed symbols.
+pub fn describe_exposed_symbol(name: &str) -> String {
+    let rest = match name.strip_prefix("roc__") {
+        Some(rest) => rest,
+        None => return "not a Roc symbol".to_string(),
+    };
+
+    // Closure types are named like `roc__mainForHost_1__Fx_caller`.
+    if let Some((value, closure)) = rest.split_once("_1_") {
+        match closure {
+            "exposed" => return format!("calls `{value}`"),
+            "exposed_generic" => {
+                return format!("calls `{value}`, returning through a pointer argument")
+            }
+            "exposed_size" => return format!("the size of the return value of `{value}`"),
+            _ => {}
+        }
+
+        if let Some(closure) = closure.strip_suffix("_result_size") {
+            return format!("the size of the result of the `{closure}` closure of `{value}`");
+        }
+        if let Some(closure) = closure.strip_suffix("_caller") {
+            return format!("calls the `{closure}` closure of `{value}`");
+        }
+        if let Some(closure) = closure.strip_suffix("_size") {
+            return format!("the size of the `{closure}` closure of `{value}`");
+        }
+    }
+
+    if let Some(value) = rest.strip_suffix("_size") {
+        return format!("the size of the return value of `{value}`");
+    }
+
+    "an unknown kind of Roc symbol".to_string()
+}
+
+impl fmt::Display for HostReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = if self.preprocessed {
+            "preprocessed host"
+        } else {
+            "host"
+        };
+        writeln!(f, "{:?} {kind} for {:?}", self.format, self.architecture)?;
+
+        writeln!(f)?;
+        writeln!(f, "Expects {} symbols from the app:", self.expected.len())?;
+        for symbol in self.expected.iter() {
+            writeln!(
+                f,
+                "    {} ({})",
+                symbol.name,
+                describe_exposed_symbol(&symbol.name)
+            )?;
+
+            if symbol.slots.is_empty() {
+                writeln!(f, "        no references found")?;
+            }
+            for slot in symbol.slots.iter() {
+                writeln!(f, "        {slot}")?;
+            }
+        }
+
+        writeln!(f)?;
+        writeln!(f, "Provides {} functions to the app:", self.provided.len())?;
+        for name in self.provided.iter() {
+            writeln!(f, "    {name}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Mismatches {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "The host and the app agree on all exposed symbols.");
+        }
+
+        if !self.missing_from_app.is_empty() {
+            writeln!(
+                f,
+                "The host expects these symbols, but the app does not expose them:"
+            )?;
+            for name in self.missing_from_app.iter() {
+                writeln!(f, "    {name} ({})", describe_exposed_symbol(name))?;
+            }
+        }
+
+        if !self.unused_by_host.is_empty() {
+            writeln!(
+                f,
+                "The app exposes these symbols, but the host never uses them:"
+            )?;
+            for name in self.unused_by_host.iter() {
+                writeln!(f, "    {name} ({})", describe_exposed_symbol(name))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describe_symbols() {
+        assert_eq!(
+            describe_exposed_symbol("roc__mainForHost_1_exposed_generic"),
+            "calls `mainForHost`, returning through a pointer argument"
+        );
+        assert_eq!(
+            describe_exposed_symbol("roc__mainForHost_1__Fx_caller"),
+            "calls the `_Fx` closure of `mainForHost`"
+        );
+        assert_eq!(
+            describe_exposed_symbol("roc__mainForHost_1__Fx_result_size"),
+            "the size of the result of the `_Fx` closure of `mainForHost`"
+        );
+        assert_eq!(
+            describe_exposed_symbol("roc__mainForHost_size"),
+            "the size of the return value of `mainForHost`"
+        );
+    }
+
+    #[test]
+    fn mismatches() {
+        let report = HostReport {
+            format: object::BinaryFormat::Elf,
+            architecture: object::Architecture::X86_64,
+            preprocessed: false,
+            expected: vec![
+                HostSymbol {
+                    name: "roc__mainForHost_1_exposed_generic".to_string(),
+                    slots: vec![],
+                },
+                HostSymbol {
+                    name: "roc__mainForHost_1__Fx_caller".to_string(),
+                    slots: vec![],
+                },
+            ],
+            provided: vec![],
+        };
+
+        let exposed = ExposedSymbols {
+            top_level_values: vec!["mainForHost".to_string()],
+            exported_closure_types: vec![],
+        };
+
+        assert_eq!(
+            report.mismatches(&exposed),
+            Mismatches {
+                missing_from_app: vec!["roc__mainForHost_1__Fx_caller".to_string()],
+                unused_by_host: vec![
+                    "roc__mainForHost_1_exposed".to_string(),
+                    "roc__mainForHost_size".to_string(),
+                ],
+            }
+        );
+    }
+}
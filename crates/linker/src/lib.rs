@@ -5,7 +5,7 @@
 //! practical to use a regular linker.
 use memmap2::{Mmap, MmapMut};
 use object::Object;
-use roc_error_macros::internal_error;
+use roc_error_macros::{internal_error, user_error};
 use roc_load::{EntryPoint, ExecutionMode, ExposedToHost, LoadConfig, Threading};
 use roc_module::symbol::Interns;
 use roc_packaging::cache::RocCacheDir;
@@ -15,13 +15,17 @@ use roc_target::{Architecture, OperatingSystem, Target};
 use std::cmp::Ordering;
 use std::mem;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 mod elf;
+mod inspect;
 mod macho;
 mod pe;
 
 mod generate_dylib;
 
+pub use inspect::{describe_exposed_symbol, HostReport, HostSlot, HostSymbol, Mismatches};
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum LinkType {
     // These numbers correspond to the --lib and --no-link flags
@@ -34,6 +38,7 @@ pub fn supported(link_type: LinkType, target: Target) -> bool {
     if let LinkType::Executable = link_type {
         match target {
             Target::LinuxX64 => true,
+            Target::LinuxArm64 => true,
             Target::WinX64 => true,
             // macho support is incomplete
             Target::MacX64 => false,
@@ -71,6 +76,27 @@ pub fn generate_stub_lib(
     target: Target,
     function_kind: FunctionKind,
 ) -> (PathBuf, PathBuf, Vec<String>) {
+    let (platform_path, exposed_symbols) =
+        load_exposed_symbols(input_path, roc_cache_dir, target, function_kind);
+
+    let stub_lib = if target.operating_system() == OperatingSystem::Windows {
+        platform_path.with_file_name("libapp.obj")
+    } else {
+        platform_path.with_file_name("libapp.so")
+    };
+
+    let stub_dll_symbols = exposed_symbols.stub_dll_symbols();
+    generate_dynamic_lib(target, &stub_dll_symbols, &stub_lib);
+    (platform_path, stub_lib, stub_dll_symbols)
+}
+
+/// Loads an app, and returns the path of its platform along with the symbols the app exposes to it.
+pub fn load_exposed_symbols(
+    input_path: &Path,
+    roc_cache_dir: RocCacheDir<'_>,
+    target: Target,
+    function_kind: FunctionKind,
+) -> (PathBuf, ExposedSymbols) {
     // Note: this should theoretically just be able to load the host, I think.
     // Instead, I am loading an entire app because that was simpler and had example code.
     // If this was expected to stay around for the the long term, we should change it.
@@ -118,15 +144,7 @@ pub fn generate_stub_lib(
     };
 
     if let EntryPoint::Executable { platform_path, .. } = &loaded.entry_point {
-        let stub_lib = if target.operating_system() == OperatingSystem::Windows {
-            platform_path.with_file_name("libapp.obj")
-        } else {
-            platform_path.with_file_name("libapp.so")
-        };
-
-        let stub_dll_symbols = exposed_symbols.stub_dll_symbols();
-        generate_dynamic_lib(target, &stub_dll_symbols, &stub_lib);
-        (platform_path.into(), stub_lib, stub_dll_symbols)
+        (platform_path.into(), exposed_symbols)
     } else {
         unreachable!();
     }
@@ -363,6 +381,41 @@ fn stub_lib_is_up_to_date(target: Target, stub_lib_path: &Path, custom_names: &[
     it1.eq(it2)
 }
 
+/// Loads a host executable, or a preprocessed `.rh` host along with its metadata,
+/// and reports the Roc symbols it expects from the app.
+pub fn inspect_host(host_path: &Path) -> HostReport {
+    let data = open_mmap(host_path);
+    let object = object::File::parse(&*data).unwrap_or_else(|err| {
+        user_error!("{} is not a host executable: {err}", host_path.display())
+    });
+
+    let preprocessed = host_path.extension() == Some(PRECOMPILED_HOST_EXT.as_ref());
+
+    // Like the preprocessed host, the metadata is named after the target.
+    let metadata_path = host_path
+        .file_stem()
+        .and_then(|stem| Target::from_str(stem.to_str()?).ok())
+        .map(|target| host_path.with_file_name(metadata_file_name(target)))
+        .filter(|path| preprocessed && path.exists());
+
+    let (expected, provided) = match object.format() {
+        object::BinaryFormat::Elf => crate::elf::inspect_elf(&data, metadata_path.as_deref()),
+        object::BinaryFormat::MachO => crate::macho::inspect_macho(&data, metadata_path.as_deref()),
+        object::BinaryFormat::Pe => crate::pe::inspect_pe(&data, metadata_path.as_deref()),
+        other => {
+            user_error!("The surgical linker does not support {other:?} hosts")
+        }
+    };
+
+    HostReport {
+        format: object.format(),
+        architecture: object.architecture(),
+        preprocessed,
+        expected,
+        provided,
+    }
+}
+
 pub fn preprocess_host(
     target: Target,
     platform_main_roc: &Path,
@@ -412,6 +465,7 @@ fn preprocess(
                 metadata_path,
                 preprocessed_path,
                 shared_lib,
+                stub_dll_symbols,
                 verbose,
                 time,
             );
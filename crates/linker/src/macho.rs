@@ -18,6 +18,7 @@ use std::{
     time::{Duration, Instant},
 };
 
+use crate::inspect::{HostSlot, HostSymbol};
 use crate::{
     align_by_constraint, align_to_offset_by_constraint, load_struct_inplace,
     load_struct_inplace_mut, load_structs_inplace, load_structs_inplace_mut, open_mmap,
@@ -104,6 +105,59 @@ impl Metadata {
     }
 }
 
+/// Finds the Roc symbols a Mach-O host expects. Their stubs are only known after preprocessing,
+/// so those come from the metadata of a preprocessed host.
+pub(crate) fn inspect_macho(
+    exec_data: &[u8],
+    metadata_path: Option<&Path>,
+) -> (Vec<HostSymbol>, Vec<String>) {
+    let exec_obj = match object::File::parse(exec_data) {
+        Ok(obj) => obj,
+        Err(err) => {
+            internal_error!("Failed to parse executable file: {}", err);
+        }
+    };
+
+    let mut expected: MutMap<String, Vec<HostSlot>> = exec_obj
+        .symbols()
+        .filter(is_roc_undefined)
+        .map(|sym| {
+            (
+                sym.name().unwrap().trim_start_matches('_').to_string(),
+                vec![],
+            )
+        })
+        .collect();
+
+    if let Some(md) = metadata_path.map(Metadata::read_from_file) {
+        for name in md.app_functions.iter() {
+            let slots = expected
+                .entry(name.trim_start_matches('_').to_string())
+                .or_default();
+            if let Some((_, address)) = md.plt_addresses.get(name) {
+                slots.push(HostSlot::Plt(address + md.added_byte_count));
+            }
+            if let Some(surgeries) = md.surgeries.get(name) {
+                slots.push(HostSlot::Surgeries(surgeries.len()));
+            }
+        }
+    }
+
+    let mut expected: Vec<_> = expected
+        .into_iter()
+        .map(|(name, slots)| HostSymbol { name, slots })
+        .collect();
+    expected.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut provided: Vec<_> = collect_roc_definitions(&exec_obj)
+        .into_keys()
+        .filter(|name| name.starts_with("roc_"))
+        .collect();
+    provided.sort();
+
+    (expected, provided)
+}
+
 fn report_timing(label: &str, duration: Duration) {
     println!("\t{:9.3} ms   {}", duration.as_secs_f64() * 1000.0, label,);
 }
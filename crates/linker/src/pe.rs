@@ -19,8 +19,10 @@ use roc_collections::{MutMap, VecMap};
 use roc_error_macros::internal_error;
 
 use crate::{
-    generate_dylib::APP_DLL, load_struct_inplace, load_struct_inplace_mut,
-    load_structs_inplace_mut, open_mmap, open_mmap_mut,
+    generate_dylib::APP_DLL,
+    inspect::{HostSlot, HostSymbol},
+    load_struct_inplace, load_struct_inplace_mut, load_structs_inplace_mut, open_mmap,
+    open_mmap_mut,
 };
 
 /// The metadata stores information about/from the host .exe because
@@ -219,6 +221,51 @@ pub(crate) fn preprocess_windows(
     Ok(())
 }
 
+/// Finds the Roc functions a host .exe imports from our dummy .dll, with their import addresses.
+/// A preprocessed host no longer imports the dummy .dll, so those come from the metadata instead.
+pub(crate) fn inspect_pe(
+    data: &[u8],
+    metadata_path: Option<&Path>,
+) -> (Vec<HostSymbol>, Vec<String>) {
+    let dynhost_obj = object::read::pe::PeFile64::parse(data)
+        .unwrap_or_else(|err| internal_error!("Failed to parse executable file: {}", err));
+    let image_base = dynhost_obj.nt_headers().optional_header.image_base.get(LE);
+
+    let imports_dummy_dll = dynhost_obj.imports().is_ok_and(|imports| {
+        imports
+            .iter()
+            .any(|import| import.library() == APP_DLL.as_bytes())
+    });
+
+    let dynamic_relocations = if imports_dummy_dll {
+        Some(DynamicRelocationsPe::new(data))
+    } else {
+        metadata_path.map(|path| PeMetadata::read_from_file(path).dynamic_relocations)
+    };
+
+    let mut expected: Vec<_> = dynamic_relocations
+        .map(|relocations| relocations.address_and_offset)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, (virtual_address, _))| HostSymbol {
+            name,
+            slots: vec![HostSlot::Import(image_base + virtual_address as u64)],
+        })
+        .collect();
+    expected.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut provided: Vec<_> = dynhost_obj
+        .exports()
+        .unwrap_or_default()
+        .iter()
+        .map(|export| String::from_utf8_lossy(export.name()).into_owned())
+        .filter(|name| name.starts_with("roc_"))
+        .collect();
+    provided.sort();
+
+    (expected, provided)
+}
+
 fn remove_dummy_dll_import_table_entry(executable: &mut [u8], md: &PeMetadata) {
     const W: usize = std::mem::size_of::<ImageImportDescriptor>();
 
@@ -1555,6 +1602,27 @@ mod test {
         assert_eq!(addresses_api, addresses_file);
     }
 
+    #[test]
+    fn inspect_dynhost() {
+        let (expected, provided) = inspect_pe(PE_DYNHOST, None);
+
+        let names: Vec<_> = expected.iter().map(|sym| sym.name.as_str()).collect();
+        assert_eq!(
+            [
+                "roc__mainForHost_1__Fx_caller",
+                "roc__mainForHost_1__Fx_result_size",
+                "roc__mainForHost_1_exposed_generic",
+                "roc__mainForHost_size"
+            ],
+            names.as_slice()
+        );
+
+        assert!(expected
+            .iter()
+            .all(|sym| matches!(sym.slots.as_slice(), [HostSlot::Import(_)])));
+        assert!(provided.contains(&"roc_alloc".to_string()));
+    }
+
     #[test]
     fn collect_undefined_symbols_pe() {
         let object = object::File::parse(PE_DYNHOST).unwrap();
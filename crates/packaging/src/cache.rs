@@ -1,11 +1,16 @@
 #[cfg(not(target_family = "wasm"))]
 use {
     crate::https::{self, PackageMetadata, Problem},
+    crate::lockfile::PackageLock,
+    crate::{tarball, vendor},
     roc_error_macros::internal_error,
+    std::collections::HashSet,
     std::fs,
+    std::io::{self, ErrorKind},
+    std::time::{Duration, SystemTime},
 };
 #[cfg(not(target_family = "wasm"))]
-const MAX_DOWNLOAD_BYTES: u64 = 32 * 1_000_000_000; // GB
+pub(crate) const MAX_DOWNLOAD_BYTES: u64 = 32 * 1_000_000_000; // GB
 
 use std::path::{Path, PathBuf};
 
@@ -13,6 +18,10 @@ use std::path::{Path, PathBuf};
 pub enum RocCacheDir<'a> {
     /// Normal scenario: reading from the user's cache dir on disk
     Persistent(&'a Path),
+    /// Like Persistent, but every package that gets installed is recorded for the lockfile,
+    /// and with `--locked`, packages which aren't in the lockfile are refused.
+    #[cfg(not(target_family = "wasm"))]
+    WithLock(&'a Path, &'a PackageLock),
     /// For build.rs and tests where we never want to be downloading anything - yell loudly if we try!
     Disallowed,
     /// For tests only; we don't want to write to the real cache during a test!
@@ -24,6 +33,8 @@ impl RocCacheDir<'_> {
     pub fn as_persistent_path(&self) -> Option<&Path> {
         match self {
             RocCacheDir::Persistent(path) => Some(path),
+            #[cfg(not(target_family = "wasm"))]
+            RocCacheDir::WithLock(path, _) => Some(path),
             RocCacheDir::Disallowed => None,
             #[cfg(test)]
             RocCacheDir::Temp(_) => None,
@@ -71,18 +82,20 @@ fn nixos_error_if_dynamic(url: &str, dest_dir: &Path) {
 /// have an entry for the given URL. If we do, return its info. If we don't already have it, then:
 ///
 /// - Download and decompress the compressed tarball from the given URL
+///   (or from the mirror in the ROC_PACKAGE_MIRROR environment variable, if it's set)
 /// - Verify its bytes against the hash in the URL
 /// - Extract the tarball's contents into the appropriate cache directory
 ///
 /// Returns the path to the installed package (which will be in the cache dir somewhere), as well
 /// as the requested root module filename (optionally specified via the URL fragment).
+///
+/// `required_by` is the module whose header has the package, for the lockfile.
 #[cfg(not(target_family = "wasm"))]
 pub fn install_package<'a>(
     roc_cache_dir: RocCacheDir<'_>,
     url: &'a str,
+    required_by: &Path,
 ) -> Result<(PathBuf, Option<&'a str>), Problem> {
-    use std::io::ErrorKind;
-
     let PackageMetadata {
         cache_subdir,
         content_hash,
@@ -105,17 +118,31 @@ pub fn install_package<'a>(
                     nixos_error_if_dynamic(url, &dest_dir);
                 }
 
+                // This is only used by `roc cache prune`, so it's no reason to fail a build
+                // (e.g. if the cache is read-only).
+                let _ = record_use(&dest_dir, url);
+
                 Ok((dest_dir, root_module_filename))
             } else {
                 // Download into a tempdir; only move it to dest_dir if hash verification passes.
-                println!(
-                    "Downloading \u{001b}[36m{url}\u{001b}[0m\n    into {}\n",
-                    cache_dir.display()
-                );
+                let mirror = vendor::mirror();
+                let source_url = vendor::source_url(url, mirror.as_deref());
+
+                if mirror.is_some() {
+                    println!(
+                        "Downloading \u{001b}[36m{url}\u{001b}[0m\n    from {source_url}\n    into {}\n",
+                        cache_dir.display()
+                    );
+                } else {
+                    println!(
+                        "Downloading \u{001b}[36m{url}\u{001b}[0m\n    into {}\n",
+                        cache_dir.display()
+                    );
+                }
                 let tempdir = tempfile::tempdir().map_err(Problem::IoErr)?;
                 let tempdir_path = tempdir.path();
                 let downloaded_hash =
-                    https::download_and_hash(url, tempdir_path, MAX_DOWNLOAD_BYTES)?;
+                    https::download_and_hash(&source_url, tempdir_path, MAX_DOWNLOAD_BYTES)?;
 
                 // Download the tarball into memory and verify it.
                 // The tarball name is the hash of its contents.
@@ -176,6 +203,8 @@ pub fn install_package<'a>(
                         nixos_error_if_dynamic(url, &dest_dir);
                     }
 
+                    record_install(&dest_dir, url).map_err(Problem::IoErr)?;
+
                     // The package's files are now in the cache. We're done!
                     Ok((dest_dir, root_module_filename))
                 } else {
@@ -186,6 +215,17 @@ pub fn install_package<'a>(
                 }
             }
         }
+        RocCacheDir::WithLock(cache_dir, lock) => {
+            // Check before installing, so nothing that isn't locked gets downloaded.
+            lock.check(url)?;
+
+            let (package_dir, root_module_filename) =
+                install_package(RocCacheDir::Persistent(cache_dir), url, required_by)?;
+
+            lock.record(url, &package_dir, required_by);
+
+            Ok((package_dir, root_module_filename))
+        }
         RocCacheDir::Disallowed => {
             internal_error!(
                 "Tried to download a package ({:?}) via RocCacheDir::Disallowed - which was explicitly used in order to disallow downloading packages in the current context!",
@@ -197,6 +237,261 @@ pub fn install_package<'a>(
     }
 }
 
+/// Each package dir in the cache has a metadata file next to it with this extension, e.g.
+/// `jDRlAFAA3738vu3-vMpLUoyxtA86Z7CaZneoOKrihbE.meta`, which records the URL the package was
+/// installed from. The file's modification time is when the package was last used.
+#[cfg(not(target_family = "wasm"))]
+const METADATA_EXT: &str = "meta";
+
+/// The metadata key for the URL the package was installed from.
+#[cfg(not(target_family = "wasm"))]
+const URL_KEY: &str = "url";
+
+/// The metadata key for the hash of the package's files as they were extracted, in case the
+/// package's tarball was not bundled reproducibly (so the files don't hash to the package's
+/// hash by themselves). See `tarball::dir_hash`.
+#[cfg(not(target_family = "wasm"))]
+const CONTENTS_KEY: &str = "contents";
+
+/// Write the metadata for a package that was just downloaded and verified.
+#[cfg(not(target_family = "wasm"))]
+fn record_install(dest_dir: &Path, url: &str) -> io::Result<()> {
+    let contents_hash = tarball::dir_hash(dest_dir)?;
+
+    fs::write(
+        dest_dir.with_extension(METADATA_EXT),
+        format!("{URL_KEY} = {url}\n{CONTENTS_KEY} = {contents_hash}\n"),
+    )
+}
+
+/// Record that an installed package was used just now.
+#[cfg(not(target_family = "wasm"))]
+fn record_use(dest_dir: &Path, url: &str) -> io::Result<()> {
+    let metadata_path = dest_dir.with_extension(METADATA_EXT);
+
+    match fs::File::options().write(true).open(&metadata_path) {
+        Ok(file) => file.set_modified(SystemTime::now()),
+        // Packages installed by older versions of roc don't have metadata yet. We can't vouch
+        // for their contents anymore, so only record the URL.
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            fs::write(metadata_path, format!("{URL_KEY} = {url}\n"))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+#[derive(Debug)]
+pub struct CachedPackage {
+    /// e.g. ~/.cache/roc/example.com/roc-packages/jDRlAFAA3738vu3-vMpLUoyxtA86Z7CaZneoOKrihbE
+    pub dir: PathBuf,
+    /// The package's hash, which is also the name of its dir.
+    pub content_hash: String,
+    /// The URL the package was installed from, if it was installed by a version of roc
+    /// that recorded it.
+    pub url: Option<String>,
+    /// The total size of the package's files, in bytes.
+    pub size: u64,
+    pub last_used: SystemTime,
+    contents_hash: Option<String>,
+}
+
+#[cfg(not(target_family = "wasm"))]
+#[derive(Debug, PartialEq, Eq)]
+pub enum Verification {
+    /// The package's files are the ones its hash was verified against when it was downloaded.
+    Verified,
+    /// The package's files have been changed since it was downloaded.
+    Modified,
+    /// The package was installed by a version of roc that didn't record what its files hashed
+    /// to, and they don't hash to the package's hash by themselves.
+    Unverifiable,
+}
+
+/// Every package in the given cache dir, in no particular order.
+#[cfg(not(target_family = "wasm"))]
+pub fn cached_packages(cache_dir: &Path) -> io::Result<Vec<CachedPackage>> {
+    let mut packages = Vec::new();
+
+    if cache_dir.is_dir() {
+        add_cached_packages(cache_dir, &mut packages)?;
+    }
+
+    Ok(packages)
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn add_cached_packages(dir: &Path, packages: &mut Vec<CachedPackage>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+
+        let metadata_path = path.with_extension(METADATA_EXT);
+        let name = entry.file_name().to_string_lossy().into_owned();
+
+        // The cache subdirs come from package URLs, so they could be named anything, but a
+        // package dir is always named after its hash, which base64url-encodes 32 bytes.
+        let is_package_dir = metadata_path.is_file()
+            || (name.len() == 43
+                && name
+                    .chars()
+                    .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_'));
+
+        if !is_package_dir {
+            add_cached_packages(&path, packages)?;
+            continue;
+        }
+
+        let mut url = None;
+        let mut contents_hash = None;
+        let last_used = match fs::read_to_string(&metadata_path) {
+            Ok(metadata) => {
+                for line in metadata.lines() {
+                    match line.split_once(" = ") {
+                        Some((URL_KEY, value)) => url = Some(value.to_string()),
+                        Some((CONTENTS_KEY, value)) => contents_hash = Some(value.to_string()),
+                        _ => {}
+                    }
+                }
+
+                fs::metadata(&metadata_path)?.modified()?
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => entry.metadata()?.modified()?,
+            Err(err) => return Err(err),
+        };
+
+        packages.push(CachedPackage {
+            size: dir_size(&path)?,
+            dir: path,
+            content_hash: name,
+            url,
+            last_used,
+            contents_hash,
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn dir_size(dir: &Path) -> io::Result<u64> {
+    let mut size = 0;
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+
+        if metadata.is_dir() {
+            size += dir_size(&entry.path())?;
+        } else {
+            size += metadata.len();
+        }
+    }
+
+    Ok(size)
+}
+
+/// Re-hash a cached package's files to check that they haven't changed since it was downloaded.
+#[cfg(not(target_family = "wasm"))]
+pub fn verify(package: &CachedPackage) -> io::Result<Verification> {
+    let actual = tarball::dir_hash(&package.dir)?;
+
+    if actual == package.content_hash || Some(&actual) == package.contents_hash.as_ref() {
+        Ok(Verification::Verified)
+    } else if package.contents_hash.is_none() {
+        Ok(Verification::Unverifiable)
+    } else {
+        Ok(Verification::Modified)
+    }
+}
+
+/// Remove a package from the cache, so that it gets downloaded again the next time it's used.
+#[cfg(not(target_family = "wasm"))]
+pub fn remove(package: &CachedPackage) -> io::Result<()> {
+    fs::remove_dir_all(&package.dir)?;
+
+    match fs::remove_file(package.dir.with_extension(METADATA_EXT)) {
+        Err(err) if err.kind() != ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+/// The dirs of the cached packages which the given module uses, directly or through other
+/// packages, found by following package headers the same way the loader does. Packages which
+/// aren't in the cache yet are left out (along with the packages only they use).
+#[cfg(not(target_family = "wasm"))]
+pub fn packages_used_by(root_module: &Path, cache_dir: &Path) -> io::Result<HashSet<PathBuf>> {
+    let mut used = HashSet::new();
+    let mut visited_modules = HashSet::new();
+    let mut pending = vec![root_module.to_path_buf()];
+
+    while let Some(module_path) = pending.pop() {
+        if !visited_modules.insert(module_path.clone()) {
+            continue;
+        }
+
+        for package in vendor::package_names(&module_path)? {
+            if !package.starts_with("https://") {
+                // Local packages are relative to the module that depends on them.
+                pending.push(module_path.parent().unwrap().join(package));
+            } else if let Ok(metadata) = PackageMetadata::try_from(package.as_str()) {
+                let package_dir = cache_dir
+                    .join(metadata.cache_subdir)
+                    .join(metadata.content_hash);
+                let package_root =
+                    package_dir.join(metadata.root_module_filename.unwrap_or("main.roc"));
+
+                if package_root.is_file() {
+                    pending.push(package_root);
+                    used.insert(package_dir);
+                }
+            }
+        }
+    }
+
+    Ok(used)
+}
+
+/// Remove the cached packages which haven't been used for at least `older_than` (if given),
+/// and which none of the `used_by` modules use (if any are given). Returns the removed packages.
+#[cfg(not(target_family = "wasm"))]
+pub fn prune(
+    cache_dir: &Path,
+    older_than: Option<Duration>,
+    used_by: &[PathBuf],
+) -> io::Result<Vec<CachedPackage>> {
+    let cutoff = older_than.map(|duration| {
+        SystemTime::now()
+            .checked_sub(duration)
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+    });
+    let mut used = HashSet::new();
+
+    for root_module in used_by {
+        used.extend(packages_used_by(root_module, cache_dir)?);
+    }
+
+    let mut removed = Vec::new();
+
+    for package in cached_packages(cache_dir)? {
+        let is_old = match cutoff {
+            Some(cutoff) => package.last_used < cutoff,
+            None => true,
+        };
+
+        if is_old && !used.contains(&package.dir) {
+            remove(&package)?;
+            removed.push(package);
+        }
+    }
+
+    Ok(removed)
+}
+
 #[cfg(windows)]
 // e.g. the "Roc" in %APPDATA%\\Roc
 const ROC_CACHE_DIR_NAME: &str = "Roc";
@@ -271,3 +566,109 @@ pub fn roc_cache_dir() -> PathBuf {
 pub fn roc_cache_dir() -> PathBuf {
     PathBuf::from(".cache").join(ROC_CACHE_DIR_NAME)
 }
+
+/// Install a package the way install_package would, but from a tarball on disk.
+#[cfg(test)]
+fn install_test_package(cache_dir: &Path, package_dir: &Path, module: &str) -> (String, PathBuf) {
+    use crate::tarball::Compression;
+
+    fs::create_dir_all(package_dir).unwrap();
+    fs::write(package_dir.join("main.roc"), "package [Foo] {}\n").unwrap();
+    fs::write(package_dir.join("Foo.roc"), module).unwrap();
+
+    let filename = tarball::build(&package_dir.join("main.roc"), Compression::Gzip).unwrap();
+    let url = format!("https://example.com/packages/{filename}");
+    let PackageMetadata {
+        cache_subdir,
+        content_hash,
+        ..
+    } = PackageMetadata::try_from(url.as_str()).unwrap();
+    let dest_dir = cache_dir.join(cache_subdir).join(content_hash);
+
+    fs::create_dir_all(&dest_dir).unwrap();
+    https::download_and_hash(
+        &vendor::file_url(&package_dir.join(&filename)).unwrap(),
+        &dest_dir,
+        MAX_DOWNLOAD_BYTES,
+    )
+    .unwrap();
+    record_install(&dest_dir, &url).unwrap();
+
+    (url, dest_dir)
+}
+
+#[test]
+fn list_and_verify_cached_packages() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache_dir = dir.path().join("cache");
+    let (url, dest_dir) = install_test_package(
+        &cache_dir,
+        &dir.path().join("package"),
+        "module [foo]\n\nfoo = 1\n",
+    );
+
+    let packages = cached_packages(&cache_dir).unwrap();
+
+    assert_eq!(packages.len(), 1);
+    assert_eq!(packages[0].dir, dest_dir);
+    assert_eq!(packages[0].url.as_deref(), Some(url.as_str()));
+    assert_eq!(
+        packages[0].size,
+        fs::metadata(dest_dir.join("main.roc")).unwrap().len()
+            + fs::metadata(dest_dir.join("Foo.roc")).unwrap().len()
+    );
+    assert_eq!(verify(&packages[0]).unwrap(), Verification::Verified);
+
+    fs::write(dest_dir.join("Foo.roc"), "module [foo]\n\nfoo = 2\n").unwrap();
+
+    assert_eq!(verify(&packages[0]).unwrap(), Verification::Modified);
+}
+
+#[test]
+fn prune_cached_packages() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache_dir = dir.path().join("cache");
+    let (used_url, used_dir) = install_test_package(
+        &cache_dir,
+        &dir.path().join("used"),
+        "module [foo]\n\nfoo = 1\n",
+    );
+    let (_, unused_dir) = install_test_package(
+        &cache_dir,
+        &dir.path().join("unused"),
+        "module [foo]\n\nfoo = 2\n",
+    );
+    let app_path = dir.path().join("main.roc");
+
+    fs::write(
+        &app_path,
+        format!("app [main] {{ pkg: \"{used_url}\" }}\n\nmain = 1\n"),
+    )
+    .unwrap();
+
+    // Both packages were just used, so neither is old enough to prune.
+    let day = Duration::from_secs(24 * 60 * 60);
+
+    assert!(prune(&cache_dir, Some(day), &[]).unwrap().is_empty());
+
+    let removed = prune(&cache_dir, None, std::slice::from_ref(&app_path)).unwrap();
+
+    assert_eq!(removed.len(), 1);
+    assert_eq!(removed[0].dir, unused_dir);
+    assert!(!unused_dir.exists());
+    assert!(!unused_dir.with_extension(METADATA_EXT).exists());
+
+    // Once it's old enough, the used package gets pruned too, unless it's still used.
+    fs::File::options()
+        .write(true)
+        .open(used_dir.with_extension(METADATA_EXT))
+        .unwrap()
+        .set_modified(SystemTime::now() - 2 * day)
+        .unwrap();
+
+    assert!(prune(&cache_dir, Some(day), &[app_path])
+        .unwrap()
+        .is_empty());
+    assert_eq!(prune(&cache_dir, Some(day), &[]).unwrap().len(), 1);
+    assert!(cached_packages(&cache_dir).unwrap().is_empty());
+}
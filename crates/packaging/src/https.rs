@@ -1,6 +1,6 @@
 use std::{
     io::{self, Read, Write},
-    path::Path,
+    path::{Path, PathBuf},
 };
 
 use crate::tarball::Compression;
@@ -241,13 +241,63 @@ pub enum Problem {
     /// The Content-Length header of the response exceeded max_download_bytes
     DownloadTooBig(u64),
     NotFound,
+    /// With `--locked`, the package isn't in this lockfile, so it may not be installed.
+    NotInLockfile(PathBuf),
 }
 
+/// Download and decompress the tarball at the given URL into the given directory,
+/// and return the BLAKE3 hash of its (uncompressed) contents.
+///
+/// Besides https:// URLs, this accepts file:// URLs (for example, to a local mirror of packages)
+/// and http:// URLs (for example, to a mirror server on the local network); since the hash gets
+/// verified either way, these are only as trustworthy as the URL the hash came from.
 pub fn download_and_hash(
     url: &str,
     dest_dir: &Path,
     max_download_bytes: u64,
 ) -> Result<String, Problem> {
+    let (reader, encoding) = open(url, max_download_bytes)?;
+
+    decompress_into(dest_dir, encoding, reader)
+}
+
+/// Download the tarball at the given URL, and return its decompressed bytes.
+/// Like download_and_hash, this accepts file:// and http:// URLs too.
+pub fn download_tarball(url: &str, max_download_bytes: u64) -> Result<Vec<u8>, Problem> {
+    let (reader, encoding) = open(url, max_download_bytes)?;
+    let mut tarball_bytes = Vec::new();
+
+    decompress(encoding, reader)
+        .read_to_end(&mut tarball_bytes)
+        .map_err(Problem::IoErr)?;
+
+    Ok(tarball_bytes)
+}
+
+/// Open the (compressed) tarball at the given URL, and determine how it's compressed.
+fn open(url: &str, max_download_bytes: u64) -> Result<(Box<dyn Read>, Encoding), Problem> {
+    if url.starts_with("file://") {
+        // Url handles things like Windows drive letters (e.g. file:///C:/roc-mirror/...)
+        let path = url::Url::parse(url)
+            .ok()
+            .and_then(|file_url| file_url.to_file_path().ok())
+            .ok_or_else(|| {
+                Problem::IoErr(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{url} is not a valid file:// URL"),
+                ))
+            })?;
+        let file = std::fs::File::open(path).map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => Problem::NotFound,
+            _ => Problem::IoErr(err),
+        })?;
+
+        // Files have no Content-Encoding, so this infers the encoding from the extension.
+        let encoding = Encoding::new("", url)?;
+
+        return Ok((Box::new(file.take(max_download_bytes)), encoding));
+    }
+
     // TODO apparently it really improves performance to construct a Client once and then reuse it,
     // instead of making a new Client for every request.
     // Per https://github.com/seanmonstar/reqwest/issues/1454#issuecomment-1026076701
@@ -285,7 +335,8 @@ pub fn download_and_hash(
     // Use .take to prevent a malicious server from sending back bytes
     // until system resources are exhausted!
     let resp = ProgressReporter::new(resp.take(max_download_bytes), content_length);
-    decompress_into(dest_dir, encoding, resp)
+
+    Ok((Box::new(resp), encoding))
 }
 
 /// The content encodings we support
@@ -371,17 +422,18 @@ fn decompress_into(
     encoding: Encoding,
     reader: impl Read,
 ) -> Result<String, Problem> {
+    hash_and_unpack(dest_dir, decompress(encoding, reader))
+}
+
+fn decompress<'a>(encoding: Encoding, reader: impl Read + 'a) -> Box<dyn Read + 'a> {
     match encoding {
-        Encoding::Brotli => hash_and_unpack(
-            dest_dir,
-            brotli::Decompressor::new(reader, BROTLI_BUFFER_BYTES),
-        ),
+        Encoding::Brotli => Box::new(brotli::Decompressor::new(reader, BROTLI_BUFFER_BYTES)),
         Encoding::Gzip => {
             // Note: GzDecoder::new immediately parses the gzip header (so, calls read())
-            hash_and_unpack(dest_dir, flate2::read::GzDecoder::new(reader))
+            Box::new(flate2::read::GzDecoder::new(reader))
         }
-        Encoding::Deflate => hash_and_unpack(dest_dir, flate2::read::DeflateDecoder::new(reader)),
-        Encoding::Uncompressed => hash_and_unpack(dest_dir, reader),
+        Encoding::Deflate => Box::new(flate2::read::DeflateDecoder::new(reader)),
+        Encoding::Uncompressed => Box::new(reader),
     }
 }
 
@@ -0,0 +1,383 @@
+//! The `roc.lock` file, which records every package an app (or platform, or package) depends on,
+//! directly or through other packages, so the full set of dependencies can be audited and pinned.
+
+use crate::https::{PackageMetadata, Problem};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+pub const LOCKFILE_NAME: &str = "roc.lock";
+
+/// The lockfile for the given root module, which goes next to it.
+pub fn lockfile_path(root_module: &Path) -> PathBuf {
+    root_module.with_file_name(LOCKFILE_NAME)
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Lockfile {
+    /// Each package URL, along with the modules (for local files) or package URLs (for modules
+    /// inside downloaded packages) which depend on it.
+    packages: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Lockfile {
+    pub fn is_empty(&self) -> bool {
+        self.packages.is_empty()
+    }
+
+    pub fn urls(&self) -> impl Iterator<Item = &str> {
+        self.packages.keys().map(String::as_str)
+    }
+}
+
+/// Goes along with the cache dir (see `RocCacheDir::WithLock`) while loading, so that every
+/// package the loader installs gets recorded, and so that with `--locked`, packages which
+/// aren't in the lockfile get refused before anything is downloaded.
+#[derive(Debug, Default)]
+pub struct PackageLock {
+    /// With `--locked`, the lockfile's path and URLs, which are the only packages that may be installed.
+    locked: Option<(PathBuf, BTreeSet<String>)>,
+    resolved: Mutex<Vec<ResolvedPackage>>,
+}
+
+#[derive(Debug, Clone)]
+struct ResolvedPackage {
+    url: String,
+    /// Where the package is in the cache
+    package_dir: PathBuf,
+    /// The module whose header depends on the package
+    required_by: PathBuf,
+}
+
+impl PackageLock {
+    /// Record the packages that get installed, without restricting which ones may be.
+    pub fn unlocked() -> Self {
+        Self::default()
+    }
+
+    /// For `--locked`: only the packages in the root module's lockfile may be installed.
+    pub fn locked(root_module: &Path) -> Result<Self, LockfileProblem> {
+        let lockfile_path = lockfile_path(root_module);
+
+        match read_lockfile(&lockfile_path)? {
+            Some(text) => {
+                let urls = locked_urls(&text).into_iter().map(String::from).collect();
+
+                Ok(Self {
+                    locked: Some((lockfile_path, urls)),
+                    resolved: Mutex::default(),
+                })
+            }
+            None => Err(LockfileProblem::Missing(lockfile_path)),
+        }
+    }
+
+    /// Before a package gets installed: with `--locked`, refuse it if the lockfile doesn't have it.
+    pub(crate) fn check(&self, url: &str) -> Result<(), Problem> {
+        match &self.locked {
+            Some((lockfile_path, urls)) if !urls.contains(url) => {
+                Err(Problem::NotInLockfile(lockfile_path.clone()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// After a package got installed into (or found in) package_dir, for the module required_by.
+    pub(crate) fn record(&self, url: &str, package_dir: &Path, required_by: &Path) {
+        self.resolved.lock().unwrap().push(ResolvedPackage {
+            url: url.to_string(),
+            package_dir: package_dir.to_path_buf(),
+            required_by: required_by.to_path_buf(),
+        });
+    }
+
+    /// The lockfile for every package that was installed, whose modules are relative to root_module.
+    pub fn lockfile(&self, root_module: &Path) -> Lockfile {
+        // The loader may have been given these paths in another form than the CLI was.
+        let canonical = |path: &Path| fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let resolved = self.resolved.lock().unwrap();
+        let root_dir = canonical(root_module.parent().unwrap_or_else(|| Path::new("")));
+        let package_dirs: Vec<(PathBuf, &str)> = resolved
+            .iter()
+            .map(|package| (canonical(&package.package_dir), package.url.as_str()))
+            .collect();
+        let mut packages: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+
+        for package in resolved.iter() {
+            let module_path = canonical(&package.required_by);
+            // Modules inside a downloaded package (even a local package inside it) are required
+            // by that package's URL, and other modules by their path.
+            let required_by = match package_dirs
+                .iter()
+                .find(|(package_dir, _)| module_path.starts_with(package_dir))
+            {
+                Some((_, url)) => url.to_string(),
+                None => module_path
+                    .strip_prefix(&root_dir)
+                    .unwrap_or(&module_path)
+                    .to_string_lossy()
+                    .replace('\\', "/"),
+            };
+
+            packages
+                .entry(package.url.clone())
+                .or_default()
+                .insert(required_by);
+        }
+
+        Lockfile { packages }
+    }
+}
+
+impl fmt::Display for Lockfile {
+    /// The lockfile is TOML, with the packages in sorted order so it doesn't change unless
+    /// the dependencies do.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "# This file is generated by `roc check` and `roc build`. Don't edit it by hand!"
+        )?;
+        writeln!(
+            f,
+            "# It lists every package this app depends on, directly or through other packages."
+        )?;
+
+        for (url, required_by) in self.packages.iter() {
+            let hash = PackageMetadata::try_from(url.as_str())
+                .map(|metadata| metadata.content_hash)
+                .unwrap_or_default();
+            let required_by: Vec<String> = required_by.iter().map(|s| quoted(s)).collect();
+
+            writeln!(f)?;
+            writeln!(f, "[[package]]")?;
+            writeln!(f, "url = {}", quoted(url))?;
+            writeln!(f, "hash = {}", quoted(hash))?;
+            writeln!(f, "required-by = [{}]", required_by.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn quoted(string: &str) -> String {
+    format!("\"{}\"", string.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// The URLs in the text of a lockfile. Other lines are ignored, since the lockfile's text
+/// is compared as a whole.
+fn locked_urls(lockfile_text: &str) -> BTreeSet<&str> {
+    lockfile_text
+        .lines()
+        .filter_map(|line| line.strip_prefix("url = \""))
+        .filter_map(|rest| rest.strip_suffix('"'))
+        .collect()
+}
+
+#[derive(Debug)]
+pub enum LockfileProblem {
+    /// `--locked` was given, but there's no lockfile.
+    Missing(PathBuf),
+    /// The packages which were resolved don't match the lockfile.
+    Changed {
+        lockfile_path: PathBuf,
+        /// Packages which were resolved, but aren't in the lockfile.
+        added: Vec<String>,
+        /// Packages in the lockfile which weren't resolved.
+        removed: Vec<String>,
+    },
+    IoErr(PathBuf, io::Error),
+}
+
+impl fmt::Display for LockfileProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockfileProblem::Missing(path) => write!(
+                f,
+                "--locked was given, but {} does not exist. Run without --locked to create it.",
+                path.display()
+            ),
+            LockfileProblem::Changed {
+                lockfile_path,
+                added,
+                removed,
+            } => {
+                writeln!(
+                    f,
+                    "--locked was given, but the packages being used don't match {}:",
+                    lockfile_path.display()
+                )?;
+                writeln!(f)?;
+
+                for url in added {
+                    writeln!(f, "    + {url}")?;
+                }
+
+                for url in removed {
+                    writeln!(f, "    - {url}")?;
+                }
+
+                if added.is_empty() && removed.is_empty() {
+                    writeln!(f, "    (Different modules or packages depend on them now.)")?;
+                }
+
+                writeln!(f)?;
+                write!(f, "Run without --locked to update it.")
+            }
+            LockfileProblem::IoErr(path, err) => {
+                write!(f, "Unable to update {}: {err}", path.display())
+            }
+        }
+    }
+}
+
+fn read_lockfile(lockfile_path: &Path) -> Result<Option<String>, LockfileProblem> {
+    match fs::read_to_string(lockfile_path) {
+        Ok(text) => Ok(Some(text)),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(LockfileProblem::IoErr(lockfile_path.to_path_buf(), err)),
+    }
+}
+
+/// After loading, when every package has been installed: write the root module's lockfile,
+/// or with `--locked`, check that it wouldn't change. A lockfile is only created if there are
+/// packages to lock, but an existing one is always kept up to date.
+pub fn update(root_module: &Path, lock: &PackageLock, locked: bool) -> Result<(), LockfileProblem> {
+    let lockfile_path = lockfile_path(root_module);
+    let existing = read_lockfile(&lockfile_path)?;
+    let resolved = lock.lockfile(root_module);
+    let text = resolved.to_string();
+
+    match existing {
+        // Compare the text, so that `--locked` also notices hand edits.
+        Some(existing) if existing.replace("\r\n", "\n") == text => Ok(()),
+        Some(existing) if locked => {
+            let locked = locked_urls(&existing);
+
+            Err(LockfileProblem::Changed {
+                added: resolved
+                    .urls()
+                    .filter(|url| !locked.contains(url))
+                    .map(String::from)
+                    .collect(),
+                removed: locked
+                    .iter()
+                    .filter(|url| !resolved.packages.contains_key(**url))
+                    .map(|url| url.to_string())
+                    .collect(),
+                lockfile_path,
+            })
+        }
+        None if locked => Err(LockfileProblem::Missing(lockfile_path)),
+        None if resolved.is_empty() => Ok(()),
+        _ => fs::write(&lockfile_path, text)
+            .map_err(|err| LockfileProblem::IoErr(lockfile_path, err)),
+    }
+}
+
+#[cfg(test)]
+fn write_test_module(path: &Path, header: &str) -> PathBuf {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, header).unwrap();
+
+    path.to_path_buf()
+}
+
+#[test]
+fn lockfile_of_transitive_packages() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache_dir = dir.path().join("cache");
+    let platform_url =
+        "https://example.com/platform/jDRlAFAA3738vu3-vMpLUoyxtA86Z7CaZneoOKrihbE.tar.br";
+    let json_url = "https://example.com/json/1bmtVBrsrSZBxNUrtJiT2uIkHPLGsIOTCrcX6bPQ6qY.tar.br";
+    let parser_url =
+        "https://example.com/parser/F8xZFTEm1fA7RF6OA1jl6V_ef_roDHfwGsBva29RxEg.tar.br";
+    let platform_dir = cache_dir.join("platform");
+    let json_dir = cache_dir.join("json");
+    let parser_dir = cache_dir.join("parser");
+
+    let app = write_test_module(&dir.path().join("main.roc"), "app");
+    let local = write_test_module(&dir.path().join("local").join("main.roc"), "package");
+    let platform = write_test_module(&platform_dir.join("main.roc"), "platform");
+    let json = write_test_module(&json_dir.join("main.roc"), "package");
+    write_test_module(&parser_dir.join("main.roc"), "package");
+
+    // The order the loader happens to install them in doesn't matter.
+    let lock = PackageLock::unlocked();
+    lock.record(platform_url, &platform_dir, &app);
+    lock.record(json_url, &json_dir, &local);
+    lock.record(json_url, &json_dir, &platform);
+    lock.record(parser_url, &parser_dir, &json);
+
+    let expected = format!(
+        "# This file is generated by `roc check` and `roc build`. Don't edit it by hand!
+# It lists every package this app depends on, directly or through other packages.
+
+[[package]]
+url = \"{json_url}\"
+hash = \"1bmtVBrsrSZBxNUrtJiT2uIkHPLGsIOTCrcX6bPQ6qY\"
+required-by = [\"{platform_url}\", \"local/main.roc\"]
+
+[[package]]
+url = \"{parser_url}\"
+hash = \"F8xZFTEm1fA7RF6OA1jl6V_ef_roDHfwGsBva29RxEg\"
+required-by = [\"{json_url}\"]
+
+[[package]]
+url = \"{platform_url}\"
+hash = \"jDRlAFAA3738vu3-vMpLUoyxtA86Z7CaZneoOKrihbE\"
+required-by = [\"main.roc\"]
+"
+    );
+
+    assert_eq!(lock.lockfile(&app).to_string(), expected);
+}
+
+#[test]
+fn locked_mode_fails_on_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache_dir = dir.path().join("cache");
+    let old_url = "https://example.com/json/1bmtVBrsrSZBxNUrtJiT2uIkHPLGsIOTCrcX6bPQ6qY.tar.br";
+    let new_url = "https://example.com/json/F8xZFTEm1fA7RF6OA1jl6V_ef_roDHfwGsBva29RxEg.tar.br";
+    let app_path = write_test_module(&dir.path().join("main.roc"), "app");
+    let resolved = |url: &str| {
+        let lock = PackageLock::unlocked();
+        lock.record(url, &cache_dir.join("json"), &app_path);
+        lock
+    };
+
+    assert!(matches!(
+        PackageLock::locked(&app_path),
+        Err(LockfileProblem::Missing(_))
+    ));
+
+    update(&app_path, &resolved(old_url), false).unwrap();
+    update(&app_path, &resolved(old_url), true).unwrap();
+
+    // A package that isn't in the lockfile is refused before the loader can download it.
+    let lock = PackageLock::locked(&app_path).unwrap();
+
+    match crate::cache::install_package(
+        crate::cache::RocCacheDir::WithLock(&cache_dir, &lock),
+        new_url,
+        &app_path,
+    ) {
+        Err(Problem::NotInLockfile(path)) => assert_eq!(path, lockfile_path(&app_path)),
+        other => panic!("expected the package to be refused, but got {other:?}"),
+    }
+    assert!(!cache_dir.exists());
+
+    match update(&app_path, &resolved(new_url), true) {
+        Err(LockfileProblem::Changed { added, removed, .. }) => {
+            assert_eq!(added, [new_url]);
+            assert_eq!(removed, [old_url]);
+        }
+        other => panic!("expected the lockfile to have changed, but got {other:?}"),
+    }
+
+    // Without --locked, the lockfile gets updated instead.
+    update(&app_path, &resolved(new_url), false).unwrap();
+    update(&app_path, &resolved(new_url), true).unwrap();
+}
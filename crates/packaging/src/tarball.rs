@@ -1,18 +1,21 @@
 use brotli::enc::BrotliEncoderParams;
 use bumpalo::Bump;
-use flate2::write::GzEncoder;
+use flate2::GzBuilder;
+use roc_module::ident::QualifiedModuleName;
 use roc_parse::ast::{
-    Header, IngestedFileImport, Module, RecursiveValueDefIter, StrLiteral, ValueDef,
+    Defs, Header, IngestedFileImport, ModuleImport, RecursiveValueDefIter, StrLiteral, ValueDef,
+};
+use roc_parse::header::{
+    HostedHeader, ImportsCollection, ImportsEntry, PackageHeader, PlatformHeader,
 };
-use roc_parse::header::PlatformHeader;
 use roc_parse::module::{parse_header, parse_module_defs};
 use roc_parse::state::State;
+use std::collections::BTreeSet;
 use std::ffi::OsStr;
 use std::fs::File;
-use std::io::{self, Read, Write};
-use std::path::{Path, PathBuf};
+use std::io::{self, ErrorKind, Write};
+use std::path::{Component, Path, PathBuf};
 use tar;
-use walkdir::WalkDir;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Compression {
@@ -63,17 +66,15 @@ impl<'a> TryFrom<&'a str> for Compression {
 /// the name of that filename (including the .tar extension),
 /// so the caller can obtain the path to the file by calling
 /// Path::with_file_name(returned_string) on the Path argument it provided.
+///
+/// Building the same sources always gives the same bytes, and therefore the same hash,
+/// regardless of which machine they're built on.
 pub fn build(path_to_main: &Path, compression: Compression) -> io::Result<String> {
     let mut archive_bytes = Vec::new();
 
     write_archive(path_to_main, &mut archive_bytes)?;
 
-    // Now that we have our compressed archive, get its BLAKE3 hash
-    // and base64url encode it. Use base64url encoding because:
-    // - It's more concise than hex encoding, so the URL can be shorter
-    // - Unlike base64 encoding, it's URL-frienly (e.g. won't include slashes)
-    let hash = base64_url::encode(blake3::hash(&archive_bytes).as_bytes());
-    let mut filename = hash;
+    let mut filename = content_hash(&archive_bytes);
 
     filename.push_str(compression.file_ext());
 
@@ -88,232 +89,613 @@ pub fn build(path_to_main: &Path, compression: Compression) -> io::Result<String
             );
         });
 
-        match compression {
-            Compression::Brotli => {
-                brotli::BrotliCompress(
-                    &mut archive_bytes.as_slice(),
-                    &mut file,
-                    &BrotliEncoderParams {
-                        quality: 11,
-                        use_dictionary: true,
-                        ..Default::default()
-                    },
-                )?;
-            }
-            Compression::Gzip => {
-                let mut encoder = GzEncoder::new(&mut file, flate2::Compression::fast());
-                encoder.write_all(&archive_bytes)?;
-                encoder.finish()?;
-            }
-            Compression::Uncompressed => file.write_all(&archive_bytes)?,
-        };
+        compress(&archive_bytes, compression, &mut file)?;
     }
 
     Ok(filename)
 }
 
+/// Rebuild the archive for the given .roc file without writing it to disk, and return its hash.
+/// This is the hash that a URL to a bundle of these sources would have.
+pub fn rebuilt_hash(path_to_main: &Path) -> io::Result<String> {
+    let mut archive_bytes = Vec::new();
+
+    write_archive(path_to_main, &mut archive_bytes)?;
+
+    Ok(content_hash(&archive_bytes))
+}
+
+/// The hash of an archive of every file in the given directory, with each file's metadata
+/// normalized the same way as in a bundle. For a directory that a bundle was extracted into,
+/// this is the bundle's hash.
+pub(crate) fn dir_hash(dir: &Path) -> io::Result<String> {
+    let mut files = BTreeSet::new();
+
+    add_dir_files(dir, Path::new(""), &mut files)?;
+
+    let mut builder = tar::Builder::new(Vec::new());
+
+    for relative_path in files {
+        append_file(&mut builder, dir, &relative_path)?;
+    }
+
+    Ok(content_hash(&builder.into_inner()?))
+}
+
+fn add_dir_files(
+    root_dir: &Path,
+    relative_dir: &Path,
+    files: &mut BTreeSet<PathBuf>,
+) -> io::Result<()> {
+    for entry in std::fs::read_dir(root_dir.join(relative_dir))? {
+        let entry = entry?;
+        let relative_path = relative_dir.join(entry.file_name());
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            add_dir_files(root_dir, &relative_path, files)?;
+        } else if file_type.is_file() {
+            files.insert(relative_path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Given a package URL (e.g. `https://example.com/path/HASH.tar.br#main.roc`), a bundle's
+/// filename (e.g. `HASH.tar.br`), or just a hash, return the hash.
+pub fn published_hash(url_or_hash: &str) -> &str {
+    let without_fragment = url_or_hash
+        .split_once('#')
+        .map_or(url_or_hash, |(before, _)| before);
+    let filename = without_fragment
+        .rsplit_once('/')
+        .map_or(without_fragment, |(_, filename)| filename);
+
+    // Hashes are base64url-encoded, so they never contain a `.`
+    filename.split_once('.').map_or(filename, |(hash, _)| hash)
+}
+
+pub(crate) fn content_hash(archive_bytes: &[u8]) -> String {
+    // Now that we have our archive, get its BLAKE3 hash
+    // and base64url encode it. Use base64url encoding because:
+    // - It's more concise than hex encoding, so the URL can be shorter
+    // - Unlike base64 encoding, it's URL-frienly (e.g. won't include slashes)
+    base64_url::encode(blake3::hash(archive_bytes).as_bytes())
+}
+
+/// Compress the archive with fixed settings for each kind of compression, so that compressing
+/// the same archive always gives the same bytes.
+pub(crate) fn compress<W: Write>(
+    archive_bytes: &[u8],
+    compression: Compression,
+    mut writer: W,
+) -> io::Result<()> {
+    match compression {
+        Compression::Brotli => {
+            brotli::BrotliCompress(
+                &mut &*archive_bytes,
+                &mut writer,
+                &BrotliEncoderParams {
+                    quality: 11,
+                    lgwin: 22,
+                    use_dictionary: true,
+                    ..Default::default()
+                },
+            )?;
+        }
+        Compression::Gzip => {
+            // By default, gzip headers record when and on which OS compression happened.
+            let mut encoder = GzBuilder::new()
+                .mtime(0)
+                .operating_system(GZIP_UNKNOWN_OS)
+                .write(&mut writer, flate2::Compression::fast());
+            encoder.write_all(archive_bytes)?;
+            encoder.finish()?;
+        }
+        Compression::Uncompressed => writer.write_all(archive_bytes)?,
+    };
+
+    Ok(())
+}
+
+/// The value gzip uses for an unknown operating system
+const GZIP_UNKNOWN_OS: u8 = 255;
+
 /// Write an uncompressed tar archive to the given writer.
+///
+/// Starting from the root module, this follows the module graph and bundles only the
+/// modules that are reachable from it, plus the files they ingest. Any other .roc files
+/// in the same directory (scratch files, tests, and so on) are left out.
 fn write_archive<W: Write>(path: &Path, writer: W) -> io::Result<()> {
-    let root_dir = if let Some(parent) = path.parent() {
-        parent
-    } else {
-        eprintln!(
-            "{} is a directory, not a .roc file. Please specify a .roc file!",
-            path.to_string_lossy()
-        );
-        std::process::exit(1);
-    };
+    if path.is_dir() {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "{} is a directory, not a .roc file. Please specify a .roc file!",
+                path.display()
+            ),
+        ));
+    }
+
+    let root_dir = path.parent().unwrap_or_else(|| Path::new(""));
     let mut builder = tar::Builder::new(writer);
     let arena = Bump::new();
-    let mut buf = Vec::new();
-
-    // TODO use this when finding .roc files by discovering them from the root module.
-    // let other_modules: &[Module<'_>] =
-    match read_header(&arena, &mut buf, path)?.0.header {
-        Header::Module(_) => {
-            todo!();
-            // TODO report error
+    let (header, defs) = parse_module(&arena, path)?;
+
+    // The paths of everything to bundle, relative to root_dir.
+    let mut bundled = BTreeSet::new();
+    let mut pending = Vec::new();
+
+    match header {
+        Header::Package(PackageHeader { exposes, .. }) => {
+            for exposed in exposes.iter() {
+                pending.push((
+                    module_path(exposed.value.item().as_str()),
+                    path.to_path_buf(),
+                ));
+            }
         }
-        Header::App(_) => {
-            todo!();
-            // TODO report error
+        Header::Platform(PlatformHeader {
+            exposes, imports, ..
+        }) => {
+            add_host_files(root_dir, &mut bundled)?;
+
+            for exposed in exposes.item.iter() {
+                pending.push((
+                    module_path(exposed.value.item().as_str()),
+                    path.to_path_buf(),
+                ));
+            }
+
+            add_header_imports(root_dir, path, imports.item, &mut bundled, &mut pending)?;
         }
-        Header::Hosted(_) => {
-            todo!();
-            // TODO report error
+        Header::App(_) => return Err(invalid_root(path, "an app module")),
+        Header::Module(_) => return Err(invalid_root(path, "a module")),
+        Header::Hosted(_) => return Err(invalid_root(path, "a hosted module")),
+    };
+
+    bundled.insert(path.strip_prefix(root_dir).unwrap().to_path_buf());
+    add_imports(root_dir, path, &defs, &mut bundled, &mut pending)?;
+
+    // We could do this all in parallel, but a simple stack seems fast enough for this use case.
+    while let Some((relative_path, imported_by)) = pending.pop() {
+        if bundled.contains(&relative_path) {
+            continue;
         }
-        Header::Package(_) => {
-            add_source_files(&arena, root_dir, &mut builder)?;
+
+        let module_path = root_dir.join(&relative_path);
+
+        if !module_path.is_file() {
+            return Err(io::Error::new(
+                ErrorKind::NotFound,
+                format!(
+                    "{} imports a module from {}, but that file does not exist.",
+                    imported_by.display(),
+                    module_path.display()
+                ),
+            ));
         }
-        Header::Platform(PlatformHeader { imports: _, .. }) => {
-            // Add all the prebuilt host files to the archive.
-            // These should all be in the same directory as the platform module.
-            for entry in std::fs::read_dir(root_dir)? {
-                let path = entry?.path();
-
-                if [
-                    // surgical linker format
-                    Some("rh"),
-                    // metadata file
-                    Some("rm"),
-                    // legacy linker formats
-                    Some("o"),
-                    Some("a"),
-                    Some("obj"),
-                    Some("wasm"),
-                    // optimized wasm builds compile to .zig for now,
-                    // because zig can't emit .bc for wasm yet.
-                    Some("zig"),
-                ]
-                .contains(&path.extension().and_then(OsStr::to_str))
-                {
-                    builder.append_path_with_name(
-                        &path,
-                        // Store it without the root path, so that (for example) we don't store
-                        // `examples/cli/main.roc` and therefore end up with the root of the tarball
-                        // being an `examples/cli/` dir instead of having `main.roc` in the root.
-                        path.strip_prefix(root_dir).unwrap(),
-                    )?;
-                }
-            }
 
-            add_source_files(&arena, root_dir, &mut builder)?;
+        let (header, defs) = parse_module(&arena, &module_path)?;
+
+        // Hosted modules don't have their header imports converted to `import` defs.
+        if let Header::Hosted(HostedHeader { imports, .. }) = header {
+            add_header_imports(
+                root_dir,
+                &module_path,
+                imports.item,
+                &mut bundled,
+                &mut pending,
+            )?;
         }
-    };
 
-    // TODO: This will be necessary when bundling packages (not platforms, since platforms just
-    // slurp up the whole directory at the moment) and also platforms in a future where they
-    // have precompiled hosts, and we only need to grab the .roc files and the precompiled hostfiles!
-    // {
-    //     // Repeat this process on each of the root module's imports.
-    //     let mut stack = Vec::from_iter_in(other_modules, &arena);
-    //     let mut visited_paths = HashSet::from_iter([path]);
-
-    //     // We could do this all in parallel, but a simple stack seems fast enough for this use case.
-    //     while let Some(path) = stack.pop() {
-    //         let other_modules = match read_header(&arena, &mut buf, path) {
-    //             Module::Interface { .. } => {
-    //                 // TODO use header.imports
-    //                 builder.append_path(path)?;
-    //             }
-    //             Module::App { .. } => {
-    //                 // TODO report error
-    //             }
-    //             Module::Hosted { header } => {
-    //                 // TODO report error
-    //             }
-    //             Module::Platform { header } => {
-    //                 // TODO report error
-    //             }
-    //         };
-    //         let other_paths = todo!("infer from other_modules");
-
-    //         // Recurse on the other paths in the header
-    //         for other_path in other_paths {
-    //             if !visited_paths.contains(other_path) {
-    //                 stack.push(other_path);
-    //             }
-    //         }
-
-    //         paths_visited.insert(path);
-    //     }
-    // }
+        add_imports(root_dir, &module_path, &defs, &mut bundled, &mut pending)?;
+        bundled.insert(relative_path);
+    }
+
+    // Since this is a BTreeSet, the entries are always added in the same order.
+    for relative_path in bundled {
+        append_file(&mut builder, root_dir, &relative_path)?;
+    }
 
     builder.finish()
 }
 
-fn add_source_files<W: Write>(
-    arena: &Bump,
-    root_dir: &Path,
+/// Add a file to the archive with all of its metadata normalized, so that the archive
+/// doesn't depend on when, where, or by whom the file was written.
+fn append_file<W: Write>(
     builder: &mut tar::Builder<W>,
-) -> Result<(), io::Error> {
-    for entry in WalkDir::new(root_dir).into_iter().filter_entry(|entry| {
-        let path = entry.path();
+    root_dir: &Path,
+    relative_path: &Path,
+) -> io::Result<()> {
+    let bytes = std::fs::read(root_dir.join(relative_path))?;
+    let mut header = tar::Header::new_gnu();
+
+    header.set_entry_type(tar::EntryType::Regular);
+    header.set_size(bytes.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(0);
+    header.set_uid(0);
+    header.set_gid(0);
+
+    // Store it without the root path, so that (for example) we don't store
+    // `examples/cli/main.roc` and therefore end up with the root of the tarball
+    // being an `examples/cli/` dir instead of having `main.roc` in the root.
+    builder.append_data(&mut header, relative_path, bytes.as_slice())
+}
+
+fn invalid_root(path: &Path, kind: &str) -> io::Error {
+    io::Error::new(
+        ErrorKind::InvalidInput,
+        format!(
+            "{} is {kind}, but only packages and platforms can be bundled. Please specify the package or platform's main .roc file!",
+            path.display()
+        ),
+    )
+}
 
-        // Ignore everything except directories and .roc files
-        path.is_dir() || path.extension().and_then(OsStr::to_str) == Some("roc")
-    }) {
+/// Record all the prebuilt host files to bundle.
+/// These should all be in the same directory as the platform module.
+fn add_host_files(root_dir: &Path, bundled: &mut BTreeSet<PathBuf>) -> io::Result<()> {
+    // The root module's path might be just a filename, like `main.roc`
+    let dir = if root_dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        root_dir
+    };
+
+    for entry in std::fs::read_dir(dir)? {
         let entry = entry?;
         let path = entry.path();
 
-        // Only include files, not directories or symlinks.
-        // Symlinks may not work on Windows, and directories will get automatically
-        // added based on the paths of the files inside anyway. (In fact, if we don't
-        // filter out directories in this step, then empty ones can sometimes be added!)
-        if path.is_file() {
-            add_ingested_files(arena, root_dir, path, builder)?;
-
-            builder.append_path_with_name(
-                path,
-                // Store it without the root path, so that (for example) we don't store
-                // `examples/cli/main.roc` and therefore end up with the root of the tarball
-                // being an `examples/cli/` dir instead of having `main.roc` in the root.
-                path.strip_prefix(root_dir).unwrap(),
-            )?;
+        if [
+            // surgical linker format
+            Some("rh"),
+            // metadata file
+            Some("rm"),
+            // legacy linker formats
+            Some("o"),
+            Some("a"),
+            Some("obj"),
+            Some("wasm"),
+            // optimized wasm builds compile to .zig for now,
+            // because zig can't emit .bc for wasm yet.
+            Some("zig"),
+        ]
+        .contains(&path.extension().and_then(OsStr::to_str))
+        {
+            bundled.insert(PathBuf::from(entry.file_name()));
         }
     }
 
     Ok(())
 }
 
-fn read_header<'a>(
-    arena: &'a Bump,
-    buf: &'a mut Vec<u8>,
-    path: &'a Path,
-) -> io::Result<(Module<'a>, State<'a>)> {
-    // Read all the bytes into the buffer.
-    {
-        let mut file = File::open(path)?;
-        buf.clear();
-        file.read_to_end(buf)?;
-    }
+/// The path of a module in the root module's package, relative to the root module's directory.
+fn module_path(module_name: &str) -> PathBuf {
+    // Convert dots in module name to directories
+    let mut path: PathBuf = module_name.split('.').collect();
+
+    path.set_extension("roc");
 
-    // TODO avoid copying the contents of the file into a Bumpalo arena by doing multiple
-    // https://doc.rust-lang.org/std/io/trait.Read.html#tymethod.read calls instead of
-    // using the much more convenient file.read_to_end - which requires a std::vec::Vec.
-    // (We can't use that for the parser state and still return Module<'a> unfortunately.)
-    let arena_buf = bumpalo::collections::Vec::from_iter_in(buf.iter().copied(), arena);
-    let parse_state = State::new(arena_buf.into_bump_slice());
-    parse_header(arena, parse_state).map_err(|_err| {
-        todo!(); // TODO report a nice error and exit 1 - or maybe just return Err, for better testability?
-    })
+    path
 }
 
-fn add_ingested_files<W: Write>(
-    arena: &Bump,
+/// Whether this import refers to a module in the same package, rather than to
+/// a builtin or to a module in a dependency (which gets bundled separately).
+fn is_local_module(module_name: QualifiedModuleName) -> bool {
+    module_name.opt_package.is_none() && !module_name.is_builtin()
+}
+
+/// Record the modules and files imported by the defs of the module at `path`.
+fn add_imports(
     root_dir: &Path,
-    dot_roc_path: &Path,
-    builder: &mut tar::Builder<W>,
+    path: &Path,
+    defs: &Defs,
+    bundled: &mut BTreeSet<PathBuf>,
+    pending: &mut Vec<(PathBuf, PathBuf)>,
 ) -> io::Result<()> {
-    let mut buf = Vec::new();
-    let (module, state) = read_header(arena, &mut buf, dot_roc_path)?;
-    let (_, defs) = module.upgrade_header_imports(arena);
+    for (def, _) in RecursiveValueDefIter::new(defs) {
+        match def {
+            ValueDef::ModuleImport(ModuleImport { name, .. })
+                if is_local_module(name.value.into()) =>
+            {
+                pending.push((module_path(name.value.name.as_str()), path.to_path_buf()));
+            }
+            ValueDef::IngestedFileImport(IngestedFileImport {
+                path: ingested_path,
+                ..
+            }) => {
+                bundled.insert(ingested_file_path(root_dir, path, &ingested_path.value)?);
+            }
+            _ => {}
+        }
+    }
 
-    let defs = parse_module_defs(arena, state, defs).unwrap_or_else(|err| {
-        panic!("{} failed to parse: {:?}", dot_roc_path.display(), err);
-    });
+    Ok(())
+}
 
-    RecursiveValueDefIter::new(&defs).try_for_each(|(def, _)| {
-        if let ValueDef::IngestedFileImport(IngestedFileImport { path, .. }) = def {
-            if let StrLiteral::PlainLine(relative_path_str) = path.value {
-                let relative_path: PathBuf = relative_path_str.into();
-
-                if relative_path_str.contains("..") {
-                    panic!(
-                        "Cannot bundle {} (imported in {}) since it contains a relative `..` which would access files outside {}.",
-                        &relative_path.display(),
-                        dot_roc_path.display(),
-                        root_dir.display()
-                    );
+/// Record the modules and files imported by the header of a platform or hosted module,
+/// since those don't get converted to `import` defs.
+fn add_header_imports(
+    root_dir: &Path,
+    path: &Path,
+    imports: ImportsCollection,
+    bundled: &mut BTreeSet<PathBuf>,
+    pending: &mut Vec<(PathBuf, PathBuf)>,
+) -> io::Result<()> {
+    for entry in imports.iter() {
+        match entry.value.item() {
+            ImportsEntry::Module(module_name, _) => {
+                let qualified_name = QualifiedModuleName {
+                    opt_package: None,
+                    module: (*module_name).into(),
+                };
+
+                if is_local_module(qualified_name) {
+                    pending.push((module_path(module_name.as_str()), path.to_path_buf()));
                 }
+            }
+            ImportsEntry::Package(..) => {
+                // Modules in other packages get bundled with those packages.
+            }
+            ImportsEntry::IngestedFile(ingested_path, _) => {
+                bundled.insert(ingested_file_path(root_dir, path, ingested_path)?);
+            }
+        }
+    }
 
-                builder.append_path_with_name(root_dir.join(&relative_path), relative_path.display().to_string())
+    Ok(())
+}
 
-            } else {
-                unreachable!()
-            }
-        } else {
-            Ok(())
+/// The path of a file ingested by the module at `path`, relative to root_dir.
+/// Ingested paths are relative to the directory of the module that ingests them.
+fn ingested_file_path(
+    root_dir: &Path,
+    path: &Path,
+    ingested_path: &StrLiteral,
+) -> io::Result<PathBuf> {
+    let relative_path_str = match ingested_path {
+        StrLiteral::PlainLine(relative_path_str) => relative_path_str,
+        _ => {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "{} ingests a file using a string with escapes or interpolation, but only plain strings are supported.",
+                    path.display()
+                ),
+            ));
         }
-    })
+    };
+    let relative_path = Path::new(relative_path_str);
+
+    if relative_path
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Cannot bundle {} (imported in {}) since it is not inside {}.",
+                relative_path.display(),
+                path.display(),
+                root_dir.display()
+            ),
+        ));
+    }
+
+    let module_dir = path.strip_prefix(root_dir).unwrap().parent().unwrap();
+    let bundled_path = module_dir.join(relative_path);
+
+    if !root_dir.join(&bundled_path).is_file() {
+        return Err(io::Error::new(
+            ErrorKind::NotFound,
+            format!(
+                "{} imports {}, but that file does not exist.",
+                path.display(),
+                root_dir.join(&bundled_path).display()
+            ),
+        ));
+    }
+
+    Ok(bundled_path)
+}
+
+fn parse_module<'a>(arena: &'a Bump, path: &Path) -> io::Result<(Header<'a>, Defs<'a>)> {
+    let src = arena.alloc_slice_copy(&std::fs::read(path)?);
+    let syntax_error = |problem: String| {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            format!("{} failed to parse: {problem}", path.display()),
+        )
+    };
+
+    let (module, state) = parse_header(arena, State::new(src))
+        .map_err(|err| syntax_error(format!("{:?}", err.problem)))?;
+    let (module, header_defs) = module.upgrade_header_imports(arena);
+    let defs = parse_module_defs(arena, state, header_defs)
+        .map_err(|err| syntax_error(format!("{err:?}")))?;
+
+    Ok((module.header, defs))
+}
+
+#[cfg(test)]
+fn archive_paths(root_module: &Path) -> io::Result<Vec<String>> {
+    let mut archive_bytes = Vec::new();
+
+    write_archive(root_module, &mut archive_bytes)?;
+
+    tar::Archive::new(archive_bytes.as_slice())
+        .entries()?
+        .map(|entry| Ok(entry?.path()?.to_string_lossy().into_owned()))
+        .collect()
+}
+
+#[cfg(test)]
+fn write_files(dir: &Path, files: &[(&str, &str)]) {
+    for (path, contents) in files {
+        let path = dir.join(path);
+
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+}
+
+#[test]
+fn bundle_only_reachable_files() {
+    let dir = tempfile::tempdir().unwrap();
+
+    write_files(
+        dir.path(),
+        &[
+            ("main.roc", "package [Foo, Sub.Bar] {}\n"),
+            (
+                "Foo.roc",
+                "module [foo]\n\nimport Baz\nimport Str\nimport \"data.txt\" as data : Str\n\nfoo = Baz.baz\n",
+            ),
+            ("Baz.roc", "module [baz]\n\nbaz = 1\n"),
+            (
+                "Sub/Bar.roc",
+                "module [bar]\n\nimport \"bar.txt\" as barTxt : Str\n\nbar = 2\n",
+            ),
+            ("Sub/bar.txt", "bar"),
+            ("data.txt", "data"),
+            ("Scratch.roc", "module [scratch]\n\nscratch = 3\n"),
+            ("notes.txt", "not imported"),
+        ],
+    );
+
+    assert_eq!(
+        archive_paths(&dir.path().join("main.roc")).unwrap(),
+        [
+            "Baz.roc",
+            "Foo.roc",
+            "Sub/Bar.roc",
+            "Sub/bar.txt",
+            "data.txt",
+            "main.roc"
+        ]
+    );
+}
+
+#[test]
+fn bundle_app_is_an_error() {
+    let dir = tempfile::tempdir().unwrap();
+
+    write_files(
+        dir.path(),
+        &[(
+            "main.roc",
+            "app [main] { pf: platform \"platform/main.roc\" }\n\nmain = \"\"\n",
+        )],
+    );
+
+    let err = archive_paths(&dir.path().join("main.roc")).unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn bundle_missing_module_is_an_error() {
+    let dir = tempfile::tempdir().unwrap();
+
+    write_files(
+        dir.path(),
+        &[
+            ("main.roc", "package [Foo] {}\n"),
+            ("Foo.roc", "module [foo]\n\nimport Missing\n\nfoo = 1\n"),
+        ],
+    );
+
+    let err = archive_paths(&dir.path().join("main.roc")).unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+#[test]
+fn bundle_ingested_file_outside_root_is_an_error() {
+    let dir = tempfile::tempdir().unwrap();
+
+    write_files(
+        dir.path(),
+        &[
+            ("main.roc", "package [Foo] {}\n"),
+            (
+                "Foo.roc",
+                "module [foo]\n\nimport \"../secret.txt\" as secret : Str\n\nfoo = 1\n",
+            ),
+        ],
+    );
+
+    let err = archive_paths(&dir.path().join("main.roc")).unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn bundles_are_reproducible() {
+    let first_dir = tempfile::tempdir().unwrap();
+    let second_dir = tempfile::tempdir().unwrap();
+    let files = [
+        ("main.roc", "package [Foo, Bar] {}\n"),
+        ("Foo.roc", "module [foo]\n\nfoo = 1\n"),
+        ("Bar.roc", "module [bar]\n\nbar = 2\n"),
+    ];
+
+    // Write the same files in a different order, and at a different time.
+    write_files(first_dir.path(), &files);
+    files.iter().rev().for_each(|file| {
+        write_files(second_dir.path(), std::slice::from_ref(file));
+    });
+    File::options()
+        .write(true)
+        .open(second_dir.path().join("Foo.roc"))
+        .unwrap()
+        .set_modified(std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1))
+        .unwrap();
+
+    let archive = |dir: &Path| {
+        let mut archive_bytes = Vec::new();
+
+        write_archive(&dir.join("main.roc"), &mut archive_bytes).unwrap();
+
+        archive_bytes
+    };
+    let first_archive = archive(first_dir.path());
+
+    assert_eq!(first_archive, archive(second_dir.path()));
+
+    for compression in [
+        Compression::Brotli,
+        Compression::Gzip,
+        Compression::Uncompressed,
+    ] {
+        let compressed = || {
+            let mut compressed_bytes = Vec::new();
+
+            compress(&first_archive, compression, &mut compressed_bytes).unwrap();
+
+            compressed_bytes
+        };
+
+        assert_eq!(compressed(), compressed());
+    }
+
+    assert_eq!(
+        rebuilt_hash(&first_dir.path().join("main.roc")).unwrap(),
+        rebuilt_hash(&second_dir.path().join("main.roc")).unwrap()
+    );
+}
+
+#[test]
+fn published_hash_from_url_filename_or_hash() {
+    for published in [
+        "https://example.com/path/hash.tar.br#main.roc",
+        "https://example.com/path/hash.tar.gz",
+        "hash.tar",
+        "hash",
+    ] {
+        assert_eq!(published_hash(published), "hash");
+    }
 }
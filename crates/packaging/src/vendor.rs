@@ -0,0 +1,319 @@
+//! Fetching packages without access to their original URLs: from a mirror of their tarballs,
+//! or from a project-local directory of them made by `roc vendor`.
+
+use crate::cache::MAX_DOWNLOAD_BYTES;
+use crate::https::{self, PackageMetadata, Problem};
+use crate::tarball::{self, Compression};
+use bumpalo::Bump;
+use roc_parse::ast::Header;
+use roc_parse::module::parse_header;
+use roc_parse::state::State;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::Path;
+
+/// The environment variable which, if set, gives a mirror to fetch packages' tarballs from
+/// instead of their URLs: either a file:// URL of a directory, or the http:// or https:// URL
+/// of a server. For a package URL like `https://example.com/path/HASH.tar.br`, the tarball
+/// is fetched from `$ROC_PACKAGE_MIRROR/HASH.tar.br`, and its hash is verified as usual.
+pub const ROC_PACKAGE_MIRROR: &str = "ROC_PACKAGE_MIRROR";
+
+/// The mirror set in the ROC_PACKAGE_MIRROR environment variable, if any.
+pub fn mirror() -> Option<String> {
+    std::env::var(ROC_PACKAGE_MIRROR)
+        .ok()
+        .filter(|mirror| !mirror.is_empty())
+}
+
+/// Where to fetch the tarball for the given package URL from.
+pub fn source_url<'a>(url: &'a str, mirror: Option<&str>) -> Cow<'a, str> {
+    match mirror {
+        Some(mirror) => Cow::Owned(format!(
+            "{}/{}",
+            mirror.trim_end_matches('/'),
+            tarball_filename(url)
+        )),
+        None => Cow::Borrowed(url),
+    }
+}
+
+/// The filename of a package URL's tarball, e.g. `HASH.tar.br` for
+/// `https://example.com/path/HASH.tar.br#main.roc`
+fn tarball_filename(url: &str) -> &str {
+    let without_fragment = url.split_once('#').map_or(url, |(before, _)| before);
+
+    without_fragment
+        .rsplit_once('/')
+        .map_or(without_fragment, |(_, filename)| filename)
+}
+
+/// Copy the tarball of every package that the given module depends on (directly or through
+/// other packages) into vendor_dir, verifying each one's hash along the way. Afterwards,
+/// setting ROC_PACKAGE_MIRROR to the file:// URL of vendor_dir lets builds find all of them
+/// without network access.
+///
+/// Tarballs already in vendor_dir are verified rather than fetched again. Returns the URLs
+/// of the vendored packages, or else the URL (or path) that had a problem.
+pub fn vendor(
+    root_module: &Path,
+    vendor_dir: &Path,
+    mirror: Option<&str>,
+) -> Result<Vec<String>, (String, Problem)> {
+    let path_problem =
+        |path: &Path, err: io::Error| (path.display().to_string(), Problem::IoErr(err));
+
+    fs::create_dir_all(vendor_dir).map_err(|err| path_problem(vendor_dir, err))?;
+
+    // Packages get unpacked in here, so we can find the packages they depend on in turn.
+    let unpacked_dir = tempfile::tempdir().map_err(|err| path_problem(vendor_dir, err))?;
+    let mut vendored = Vec::new();
+    let mut visited_modules = HashSet::new();
+    let mut pending = vec![root_module.to_path_buf()];
+
+    while let Some(module_path) = pending.pop() {
+        if !visited_modules.insert(module_path.clone()) {
+            continue;
+        }
+
+        let packages =
+            package_names(&module_path).map_err(|err| path_problem(&module_path, err))?;
+
+        for package in packages {
+            if !package.starts_with("https://") {
+                // Local packages are relative to the module that depends on them.
+                pending.push(module_path.parent().unwrap().join(package));
+            } else if !vendored.contains(&package) {
+                let PackageMetadata {
+                    content_hash,
+                    root_module_filename,
+                    ..
+                } = PackageMetadata::try_from(package.as_str())
+                    .map_err(|problem| (package.clone(), Problem::InvalidUrl(problem)))?;
+
+                let tarball_bytes = vendor_package(&package, content_hash, vendor_dir, mirror)
+                    .map_err(|problem| (package.clone(), problem))?;
+                let package_dir = unpacked_dir.path().join(content_hash);
+
+                tar::Archive::new(tarball_bytes.as_slice())
+                    .unpack(&package_dir)
+                    .map_err(|err| (package.clone(), Problem::IoErr(err)))?;
+
+                pending.push(package_dir.join(root_module_filename.unwrap_or("main.roc")));
+                vendored.push(package);
+            }
+        }
+    }
+
+    Ok(vendored)
+}
+
+/// Make sure vendor_dir has the verified tarball for the given package URL,
+/// and return the tarball's decompressed bytes.
+fn vendor_package(
+    url: &str,
+    content_hash: &str,
+    vendor_dir: &Path,
+    mirror: Option<&str>,
+) -> Result<Vec<u8>, Problem> {
+    let filename = tarball_filename(url);
+    let dest_path = vendor_dir.join(filename);
+    let already_vendored = dest_path.is_file();
+
+    let tarball_bytes = if already_vendored {
+        https::download_tarball(&file_url(&dest_path)?, MAX_DOWNLOAD_BYTES)?
+    } else {
+        println!(
+            "Downloading \u{001b}[36m{url}\u{001b}[0m\n    into {}\n",
+            vendor_dir.display()
+        );
+
+        https::download_tarball(&source_url(url, mirror), MAX_DOWNLOAD_BYTES)?
+    };
+
+    let actual = tarball::content_hash(&tarball_bytes);
+
+    if actual != content_hash {
+        return Err(Problem::InvalidContentHash {
+            expected: content_hash.to_string(),
+            actual,
+        });
+    }
+
+    if !already_vendored {
+        // The hash is of the decompressed tarball, so recompressing it doesn't change the hash.
+        let compression = Compression::try_from(filename).unwrap_or(Compression::Uncompressed);
+        let mut file = tempfile::NamedTempFile::new_in(vendor_dir).map_err(Problem::IoErr)?;
+
+        tarball::compress(&tarball_bytes, compression, &mut file).map_err(Problem::IoErr)?;
+        file.persist(&dest_path)
+            .map_err(|err| Problem::IoErr(err.error))?;
+    }
+
+    Ok(tarball_bytes)
+}
+
+/// The file:// URL for a path (e.g. for setting ROC_PACKAGE_MIRROR to a directory).
+pub fn file_url(path: &Path) -> Result<String, Problem> {
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir().map_err(Problem::IoErr)?.join(path)
+    };
+
+    url::Url::from_file_path(&path)
+        .map(String::from)
+        .map_err(|()| {
+            Problem::IoErr(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("{} can't be made into a file:// URL", path.display()),
+            ))
+        })
+}
+
+/// The names of the packages in the header of the given module, which are either URLs
+/// or paths relative to the module.
+pub(crate) fn package_names(module_path: &Path) -> io::Result<Vec<String>> {
+    let src = fs::read(module_path)?;
+    let arena = Bump::new();
+    let (module, _) = parse_header(&arena, State::new(&src)).map_err(|err| {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "{} failed to parse: {:?}",
+                module_path.display(),
+                err.problem
+            ),
+        )
+    })?;
+
+    let entries = match module.header {
+        Header::App(header) => header.packages.value.items,
+        Header::Package(header) => header.packages.value.items,
+        Header::Platform(header) => header.packages.item.items,
+        Header::Module(_) | Header::Hosted(_) => &[],
+    };
+
+    Ok(entries
+        .iter()
+        .map(|entry| entry.value.item().package_name.value.to_str().to_string())
+        .collect())
+}
+
+#[cfg(test)]
+fn package_dirs() -> (tempfile::TempDir, std::path::PathBuf, String) {
+    // A package to depend on, bundled into its own directory the same way it would be published.
+    let dir = tempfile::tempdir().unwrap();
+    let package_dir = dir.path().join("package");
+    let app_dir = dir.path().join("app");
+
+    fs::create_dir_all(&package_dir).unwrap();
+    fs::create_dir_all(&app_dir).unwrap();
+    fs::write(package_dir.join("main.roc"), "package [Foo] {}\n").unwrap();
+    fs::write(package_dir.join("Foo.roc"), "module [foo]\n\nfoo = 1\n").unwrap();
+
+    let filename = tarball::build(&package_dir.join("main.roc"), Compression::Gzip).unwrap();
+
+    (dir, package_dir, filename)
+}
+
+#[cfg(test)]
+fn write_app(app_dir: &Path, url: &str) -> std::path::PathBuf {
+    let app_path = app_dir.join("main.roc");
+
+    fs::write(
+        &app_path,
+        format!("app [main] {{ pkg: \"{url}\" }}\n\nmain = 1\n"),
+    )
+    .unwrap();
+
+    app_path
+}
+
+#[test]
+fn source_url_with_mirror() {
+    let url = "https://example.com/path/hash.tar.br#main.roc";
+
+    assert_eq!(source_url(url, None), url);
+    assert_eq!(
+        source_url(url, Some("file:///srv/roc-mirror/")),
+        "file:///srv/roc-mirror/hash.tar.br"
+    );
+    assert_eq!(
+        source_url(url, Some("http://localhost:8000")),
+        "http://localhost:8000/hash.tar.br"
+    );
+}
+
+#[test]
+fn file_url_round_trips() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("roc mirror");
+    let url = file_url(&path).unwrap();
+
+    assert!(url.starts_with("file://"));
+    assert!(!url.contains(' '));
+    assert_eq!(url::Url::parse(&url).unwrap().to_file_path(), Ok(path));
+}
+
+#[test]
+fn vendor_from_mirror() {
+    let (dir, package_dir, filename) = package_dirs();
+    let url = format!("https://example.com/packages/{filename}");
+    let app_path = write_app(&dir.path().join("app"), &url);
+    let vendor_dir = dir.path().join("app").join("vendor");
+    let mirror = file_url(&package_dir).unwrap();
+
+    assert_eq!(
+        vendor(&app_path, &vendor_dir, Some(&mirror)).unwrap(),
+        vec![url.clone()]
+    );
+    assert!(vendor_dir.join(&filename).is_file());
+
+    // Once vendored, the vendor dir works as a mirror by itself.
+    fs::remove_file(package_dir.join(&filename)).unwrap();
+
+    assert_eq!(
+        vendor(&app_path, &vendor_dir, None).unwrap(),
+        vec![url.clone()]
+    );
+
+    let tempdir = tempfile::tempdir().unwrap();
+    let downloaded_hash = https::download_and_hash(
+        &source_url(&url, Some(&file_url(&vendor_dir).unwrap())),
+        tempdir.path(),
+        MAX_DOWNLOAD_BYTES,
+    )
+    .unwrap();
+
+    assert_eq!(format!("{downloaded_hash}.tar.gz"), filename);
+}
+
+#[test]
+fn vendor_verifies_mirrored_hash() {
+    let (dir, package_dir, filename) = package_dirs();
+
+    // Publish the package's tarball under a different hash than its contents have.
+    let wrong_filename = format!("wrong{filename}");
+    fs::rename(
+        package_dir.join(&filename),
+        package_dir.join(&wrong_filename),
+    )
+    .unwrap();
+
+    let url = format!("https://example.com/packages/{wrong_filename}");
+    let app_path = write_app(&dir.path().join("app"), &url);
+    let vendor_dir = dir.path().join("app").join("vendor");
+
+    let (problem_url, problem) = vendor(
+        &app_path,
+        &vendor_dir,
+        Some(&file_url(&package_dir).unwrap()),
+    )
+    .unwrap_err();
+
+    assert_eq!(problem_url, url);
+    assert!(matches!(problem, Problem::InvalidContentHash { .. }));
+    assert!(!vendor_dir.join(&wrong_filename).exists());
+}
@@ -308,7 +308,9 @@ fn mono_module_to_dylib_asm<'a>(
         module_id,
         exposed_to_host: exposed_to_host.top_level_values.keys().copied().collect(),
         lazy_literals,
+        fold_constants: true,
         mode: roc_gen_dev::AssemblyBackendMode::Repl,
+        source_info: None,
     };
 
     let module_object =
@@ -21,8 +21,8 @@ use roc_gen_llvm::{
 use roc_load::{Expectations, MonomorphizedModule};
 use roc_module::symbol::{Interns, ModuleId, Symbol};
 use roc_mono::{
-    ir::OptLevel,
-    layout::{GlobalLayoutInterner, STLayoutInterner},
+    ir::{OptLevel, ProcLayout},
+    layout::{GlobalLayoutInterner, Layout, LayoutIds, Niche, STLayoutInterner},
 };
 use roc_region::all::Region;
 use roc_reporting::{error::expect::Renderer, report::RenderTarget};
@@ -39,8 +39,9 @@ pub struct ExpectMemory<'a> {
 impl<'a> ExpectMemory<'a> {
     const SHM_SIZE: usize = 1024;
 
-    #[cfg(test)]
-    pub(crate) fn from_slice(slice: &mut [u8]) -> Self {
+    /// Uses `slice` as the shared memory. Since no other process can see it, this only works
+    /// for pure expects.
+    pub fn from_slice(slice: &mut [u8]) -> Self {
         Self {
             ptr: slice.as_mut_ptr(),
             length: slice.len(),
@@ -179,7 +180,7 @@ pub fn run_toplevel_expects<'a, W: std::io::Write>(
 }
 
 #[allow(clippy::too_many_arguments)]
-pub(crate) fn run_expects_with_memory<'a, W: std::io::Write>(
+pub fn run_expects_with_memory<'a, W: std::io::Write>(
     writer: &mut W,
     render_target: RenderTarget,
     arena: &'a Bump,
@@ -753,3 +754,103 @@ pub fn expect_mono_module_to_dylib<'a>(
     llvm_module_to_dylib(env.module, target, opt_level)
         .map(|dy_lib| (dy_lib, modules_expects, layout_interner))
 }
+
+/// Like `expect_mono_module_to_dylib`, but generates the code with the dev backend,
+/// which `roc test --dev` uses.
+pub fn expect_mono_module_to_dylib_asm<'a>(
+    arena: &'a Bump,
+    target: Target,
+    loaded: MonomorphizedModule<'a>,
+) -> Result<
+    (
+        libloading::Library,
+        MutMap<ModuleId, ExpectFunctions<'a>>,
+        STLayoutInterner<'a>,
+    ),
+    libloading::Error,
+> {
+    let dir = tempfile::tempdir().unwrap();
+    let app_o_file = dir.path().join("app.o");
+
+    let MonomorphizedModule {
+        module_id,
+        toplevel_expects,
+        procedures,
+        mut interns,
+        mut layout_interner,
+        ..
+    } = loaded;
+
+    let exposed_to_host = toplevel_expects
+        .values()
+        .flat_map(|expects| expects.pure.keys().chain(expects.fx.keys()))
+        .copied()
+        .collect();
+
+    let env = roc_gen_dev::Env {
+        arena,
+        module_id,
+        exposed_to_host,
+        lazy_literals: true,
+        fold_constants: true,
+        mode: roc_gen_dev::AssemblyBackendMode::CliTest,
+        source_info: None,
+    };
+
+    let module_object =
+        roc_gen_dev::build_module(&env, &mut interns, &mut layout_interner, target, procedures);
+
+    let module_out = module_object
+        .write()
+        .expect("failed to build output object");
+    std::fs::write(&app_o_file, module_out).expect("failed to write object to file");
+
+    let builtins_host_tempfile =
+        roc_bitcode::host_tempfile().expect("failed to write host builtins object to tempfile");
+
+    let (mut child, dylib_path) = roc_build::link::link(
+        target,
+        app_o_file.clone(),
+        &[
+            app_o_file.to_str().unwrap(),
+            builtins_host_tempfile.path().to_str().unwrap(),
+        ],
+        roc_build::link::LinkType::Dylib,
+    )
+    .expect("failed to link dynamic library");
+
+    child.wait().unwrap();
+
+    let lib = unsafe { libloading::Library::new(dylib_path) }?;
+
+    // every top-level expect takes no arguments and returns unit
+    let top_level = ProcLayout {
+        arguments: &[],
+        result: Layout::UNIT,
+        niche: Niche::NONE,
+    };
+
+    let mut layout_ids = LayoutIds::default();
+    let mut expect_function = |(symbol, region): (Symbol, Region)| {
+        let name = roc_gen_dev::expect_exposed_name(&mut layout_ids, &interns, symbol, &top_level);
+
+        ToplevelExpect {
+            name: arena.alloc_str(&name),
+            symbol,
+            region,
+        }
+    };
+
+    let mut modules_expects: MutMap<ModuleId, ExpectFunctions> = MutMap::default();
+
+    for (module_id, expects) in toplevel_expects.into_iter() {
+        let expect_funs = ExpectFunctions {
+            pure: BumpVec::from_iter_in(expects.pure.into_iter().map(&mut expect_function), arena),
+            fx: BumpVec::from_iter_in(expects.fx.into_iter().map(&mut expect_function), arena),
+        };
+
+        modules_expects.insert(module_id, expect_funs);
+    }
+
+    Ok((lib, modules_expects, layout_interner))
+}
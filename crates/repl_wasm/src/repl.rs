@@ -16,7 +16,7 @@ use roc_repl_ui::{
     repl_state::{ReplAction, ReplState},
     TIPS,
 };
-use roc_target::Target;
+use roc_target::{Target, WasmFeatures};
 use roc_types::pretty_print::{name_and_print_var, DebugPrint};
 
 use crate::{js_create_app, js_get_result_and_memory, js_run_app};
@@ -259,6 +259,8 @@ async fn eval_wasm<'a>(
                 .keys()
                 .copied()
                 .collect::<MutSet<_>>(),
+            source_info: None,
+            features: WasmFeatures::default(),
         };
 
         let (mut module, mut called_fns, main_fn_index) = {
@@ -1606,6 +1606,38 @@ pub fn to_https_problem_report<'b>(
                 severity: Severity::Fatal,
             }
         }
+        Problem::NotInLockfile(lockfile_path) => {
+            let doc = alloc.stack([
+                alloc.reflow(r"I need to load this package:"),
+                alloc
+                    .string((&url).to_string())
+                    .annotate(Annotation::Url)
+                    .indent(4),
+                alloc.concat([
+                    alloc.reflow(r"But "),
+                    alloc.keyword(r"--locked"),
+                    alloc.reflow(r" was given, and the package is not in the lockfile:"),
+                ]),
+                alloc
+                    .string(lockfile_path.display().to_string())
+                    .annotate(Annotation::PlainText)
+                    .indent(4),
+                alloc.reflow(r"So I will not download or use it."),
+                alloc.concat([
+                    alloc.tip(),
+                    alloc.reflow(r"If the package should be used, run without "),
+                    alloc.keyword(r"--locked"),
+                    alloc.reflow(r" to add it to the lockfile."),
+                ]),
+            ]);
+
+            Report {
+                filename,
+                doc,
+                title: "PACKAGE NOT LOCKED".to_string(),
+                severity: Severity::Fatal,
+            }
+        }
         Problem::DownloadTooBig(content_len) => {
             let nice_bytes = Byte::from_bytes(content_len.into())
                 .get_appropriate_unit(false)
@@ -0,0 +1,366 @@
+use roc_wasm_module::opcodes::OpCode;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::iter::once;
+
+use crate::{ImportDispatcher, Instance};
+
+const HELP: &str = "\
+Commands:
+  s, step             Run the next instruction, stepping into calls
+  n, next             Run the next instruction, stepping over calls
+  o, out, finish      Run until the current function returns
+  c, continue         Run until the next breakpoint
+  b, break [FN]       Pause whenever FN is called. FN is a function name or index. No FN lists breakpoints.
+  d, delete [FN]      Remove the breakpoint for FN. No FN removes all breakpoints.
+  bt, backtrace       Show the call stack
+  l, locals           Show the arguments and locals of the current function
+  stack               Show the value stack of the current function
+  g, globals          Show the global variables
+  x, memory ADDR [N]  Hex dump N bytes of memory from ADDR (default 64). Numbers can be hex (0x..) or decimal.
+  q, quit             Stop the program
+  h, help             Show this message
+An empty line repeats the last command.";
+
+enum RunMode {
+    /// Pause before the next instruction
+    Step,
+    /// Pause at the next instruction where the call depth is at most this
+    UntilDepth(usize),
+    /// Only pause at breakpoints
+    Continue,
+}
+
+enum Resume {
+    Run,
+    Quit,
+}
+
+/// Interactive debugger for an [Instance].
+/// It pauses at the first instruction, and then whenever the user asks it to.
+/// While paused, it reads commands from `input` and writes results to `output`.
+pub struct Debugger<'a> {
+    input: Box<dyn BufRead + 'a>,
+    output: Box<dyn Write + 'a>,
+    /// Function indices to pause at when they're called
+    breakpoints: Vec<usize>,
+    mode: RunMode,
+    last_command: String,
+    /// Call depth at the previous instruction, so we can see when a function is entered
+    prev_depth: Option<usize>,
+}
+
+impl fmt::Debug for Debugger<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Debugger")
+            .field("breakpoints", &self.breakpoints)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a> Debugger<'a> {
+    pub fn new(input: impl BufRead + 'a, output: impl Write + 'a) -> Self {
+        Debugger {
+            input: Box::new(input),
+            output: Box::new(output),
+            breakpoints: Vec::new(),
+            mode: RunMode::Step,
+            last_command: String::new(),
+            prev_depth: None,
+        }
+    }
+
+    pub(crate) fn start_call(&mut self) {
+        self.prev_depth = None;
+    }
+
+    pub(crate) fn before_instruction<I: ImportDispatcher>(
+        &mut self,
+        inst: &Instance<'a, I>,
+    ) -> Result<(), String> {
+        let depth = inst.previous_frames.len();
+        let fn_index = inst.current_frame.fn_index;
+        let is_fn_entry = !matches!(self.prev_depth, Some(prev) if depth <= prev);
+        self.prev_depth = Some(depth);
+
+        let is_breakpoint = is_fn_entry && self.breakpoints.contains(&fn_index);
+        let should_pause = is_breakpoint
+            || match self.mode {
+                RunMode::Step => true,
+                RunMode::UntilDepth(max_depth) => depth <= max_depth,
+                RunMode::Continue => false,
+            };
+        if !should_pause {
+            return Ok(());
+        }
+
+        let result = (|| {
+            if is_breakpoint {
+                write!(self.output, "Breakpoint: ")?;
+            }
+            self.show_location(inst)?;
+            self.prompt(inst)
+        })();
+        match result {
+            Ok(Resume::Run) => Ok(()),
+            Ok(Resume::Quit) => Err("Program stopped from the debugger".to_string()),
+            Err(e) => Err(format!("Debugger I/O error: {e}")),
+        }
+    }
+
+    /// Let the user look around after the program fails. It can't continue from here.
+    pub(crate) fn on_error<I: ImportDispatcher>(
+        &mut self,
+        inst: &Instance<'a, I>,
+        message: &str,
+    ) -> Result<(), String> {
+        let result = (|| {
+            writeln!(self.output, "{message}")?;
+            writeln!(
+                self.output,
+                "The program can't continue, but you can still inspect its state."
+            )?;
+            self.prompt(inst)
+        })();
+        result
+            .map(|_| ())
+            .map_err(|e| format!("Debugger I/O error: {e}"))
+    }
+
+    fn prompt<I: ImportDispatcher>(&mut self, inst: &Instance<'a, I>) -> io::Result<Resume> {
+        let depth = inst.previous_frames.len();
+        loop {
+            write!(self.output, "(wasm) ")?;
+            self.output.flush()?;
+
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 {
+                return Ok(Resume::Quit);
+            }
+            if !line.trim().is_empty() {
+                self.last_command = line.trim().to_string();
+            }
+            let command = self.last_command.clone();
+            let mut words = command.split_whitespace();
+            let Some(name) = words.next() else {
+                continue;
+            };
+            let arg = words.next();
+
+            match name {
+                "s" | "step" => {
+                    self.mode = RunMode::Step;
+                    return Ok(Resume::Run);
+                }
+                "n" | "next" => {
+                    self.mode = RunMode::UntilDepth(depth);
+                    return Ok(Resume::Run);
+                }
+                "o" | "out" | "finish" => {
+                    self.mode = match depth.checked_sub(1) {
+                        Some(caller_depth) => RunMode::UntilDepth(caller_depth),
+                        None => RunMode::Continue,
+                    };
+                    return Ok(Resume::Run);
+                }
+                "c" | "continue" => {
+                    self.mode = RunMode::Continue;
+                    return Ok(Resume::Run);
+                }
+                "b" | "break" => match arg {
+                    Some(arg) => match look_up_function(inst, arg) {
+                        Some(fn_index) => {
+                            if !self.breakpoints.contains(&fn_index) {
+                                self.breakpoints.push(fn_index);
+                            }
+                            let fn_name = inst.debug_fn_name(fn_index);
+                            writeln!(self.output, "Breakpoint at func[{fn_index}] {fn_name}")?;
+                        }
+                        None => writeln!(self.output, "I couldn't find a function `{arg}`")?,
+                    },
+                    None => {
+                        if self.breakpoints.is_empty() {
+                            writeln!(self.output, "No breakpoints")?;
+                        }
+                        for fn_index in self.breakpoints.iter() {
+                            let fn_name = inst.debug_fn_name(*fn_index);
+                            writeln!(self.output, "func[{fn_index}] {fn_name}")?;
+                        }
+                    }
+                },
+                "d" | "delete" => match arg {
+                    Some(arg) => match look_up_function(inst, arg) {
+                        Some(fn_index) => self.breakpoints.retain(|b| *b != fn_index),
+                        None => writeln!(self.output, "I couldn't find a function `{arg}`")?,
+                    },
+                    None => self.breakpoints.clear(),
+                },
+                "bt" | "backtrace" => self.show_backtrace(inst)?,
+                "l" | "locals" => self.show_locals(inst)?,
+                "stack" => self.show_stack(inst)?,
+                "g" | "globals" => {
+                    for (i, value) in inst.globals.iter().enumerate() {
+                        writeln!(self.output, "{i:3} {value:?}")?;
+                    }
+                }
+                "x" | "memory" => {
+                    let addr = arg.and_then(parse_number);
+                    let len = words.next().map_or(Some(64), parse_number);
+                    match (addr, len) {
+                        (Some(addr), Some(len)) => self.show_memory(inst, addr, len)?,
+                        _ => writeln!(self.output, "Usage: memory ADDR [N]")?,
+                    }
+                }
+                "q" | "quit" => return Ok(Resume::Quit),
+                "h" | "help" => writeln!(self.output, "{HELP}")?,
+                _ => writeln!(
+                    self.output,
+                    "Unknown command `{name}`. Type `help` for a list of commands."
+                )?,
+            }
+        }
+    }
+
+    fn show_location<I: ImportDispatcher>(&mut self, inst: &Instance<'a, I>) -> io::Result<()> {
+        let fn_index = inst.current_frame.fn_index;
+        let fn_name = inst.debug_fn_name(fn_index);
+        let file_offset = inst.program_counter + inst.module.code.section_offset as usize;
+        let op_code = OpCode::from(inst.module.code.bytes[inst.program_counter]);
+        writeln!(
+            self.output,
+            "func[{fn_index}] {fn_name}  {file_offset:06x} {op_code:?}"
+        )
+    }
+
+    /// Innermost frame first, with the address of the call instruction for callers
+    fn show_backtrace<I: ImportDispatcher>(&mut self, inst: &Instance<'a, I>) -> io::Result<()> {
+        let frames = inst.previous_frames.iter().chain(once(&inst.current_frame));
+        let call_addrs = frames
+            .clone()
+            .skip(1)
+            .map(|f| inst.debug_return_addr_to_call_addr(f.return_addr));
+        let addrs = call_addrs.chain(once(inst.program_counter));
+        let section_offset = inst.module.code.section_offset as usize;
+
+        let frames_and_addrs: Vec<_> = frames.zip(addrs).collect();
+        for (depth, (frame, addr)) in frames_and_addrs.iter().rev().enumerate() {
+            let fn_name = inst.debug_fn_name(frame.fn_index);
+            writeln!(
+                self.output,
+                "#{depth} func[{}] {fn_name}  {:06x}",
+                frame.fn_index,
+                addr + section_offset
+            )?;
+        }
+        Ok(())
+    }
+
+    fn show_locals<I: ImportDispatcher>(&mut self, inst: &Instance<'a, I>) -> io::Result<()> {
+        let frame = &inst.current_frame;
+        let arg_count = inst.debug_arg_count(frame.fn_index);
+        for local_index in 0..frame.locals_count {
+            let value = inst
+                .value_store
+                .get(frame.locals_start + local_index)
+                .unwrap();
+            let kind = if local_index < arg_count {
+                "arg"
+            } else {
+                "local"
+            };
+            writeln!(self.output, "{kind:5} {local_index:3} {value:?}")?;
+        }
+        Ok(())
+    }
+
+    fn show_stack<I: ImportDispatcher>(&mut self, inst: &Instance<'a, I>) -> io::Result<()> {
+        let frame = &inst.current_frame;
+        let stack_start = frame.locals_start + frame.locals_count;
+        if stack_start == inst.value_store.depth() {
+            writeln!(self.output, "Empty stack")?;
+        }
+        // Top of the stack first
+        for i in (stack_start..inst.value_store.depth()).rev() {
+            let value = inst.value_store.get(i).unwrap();
+            writeln!(self.output, "{:3} {value:?}", i - stack_start)?;
+        }
+        Ok(())
+    }
+
+    fn show_memory<I: ImportDispatcher>(
+        &mut self,
+        inst: &Instance<'a, I>,
+        addr: usize,
+        len: usize,
+    ) -> io::Result<()> {
+        let memory_size = inst.memory.len();
+        if addr >= memory_size {
+            return writeln!(
+                self.output,
+                "Address {addr:#x} is out of bounds. Memory size is {memory_size:#x}"
+            );
+        }
+        let end = memory_size.min(addr.saturating_add(len));
+        for (i, row) in inst.memory[addr..end].chunks(16).enumerate() {
+            write!(self.output, "{:08x} ", addr + 16 * i)?;
+            for col in 0..16 {
+                if col == 8 {
+                    write!(self.output, " ")?;
+                }
+                match row.get(col) {
+                    Some(byte) => write!(self.output, " {byte:02x}")?,
+                    None => write!(self.output, "   ")?,
+                }
+            }
+            let ascii: String = row
+                .iter()
+                .map(|b| {
+                    if b.is_ascii_graphic() || *b == b' ' {
+                        *b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            writeln!(self.output, "  |{ascii}|")?;
+        }
+        Ok(())
+    }
+}
+
+/// Find a function by its name in the name section or exports, or by its index
+fn look_up_function<I: ImportDispatcher>(
+    inst: &Instance<'_, I>,
+    name_or_index: &str,
+) -> Option<usize> {
+    let module = inst.module;
+    let from_names = module
+        .names
+        .function_names
+        .iter()
+        .find(|(_, name)| *name == name_or_index)
+        .map(|(index, _)| *index as usize);
+    let from_exports = || {
+        module
+            .export
+            .exports
+            .iter()
+            .find(|ex| ex.ty == roc_wasm_module::ExportType::Func && ex.name == name_or_index)
+            .map(|ex| ex.index as usize)
+    };
+    let fn_count = inst.import_count + module.code.function_count as usize;
+    let from_index = || {
+        name_or_index
+            .parse::<usize>()
+            .ok()
+            .filter(|index| *index < fn_count)
+    };
+    from_names.or_else(from_exports).or_else(from_index)
+}
+
+fn parse_number(s: &str) -> Option<usize> {
+    match s.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
@@ -4,11 +4,13 @@ use std::iter::{self, once, Iterator};
 
 use roc_wasm_module::opcodes::{MemoryInstruction, OpCode};
 use roc_wasm_module::parse::{Parse, SkipBytes};
-use roc_wasm_module::sections::{ImportDesc, MemorySection, SignatureParamsIter};
+use roc_wasm_module::sections::{Import, ImportDesc, MemorySection, SignatureTypesIter};
 use roc_wasm_module::{ExportType, WasmModule};
 use roc_wasm_module::{Value, ValueType};
 
+use crate::debugger::Debugger;
 use crate::frame::Frame;
+use crate::profiler::Profiler;
 use crate::value_store::ValueStore;
 use crate::{Error, ImportDispatcher};
 
@@ -47,7 +49,7 @@ pub struct Instance<'a, I: ImportDispatcher> {
     /// The current call frame
     pub(crate) current_frame: Frame,
     /// Previous call frames
-    previous_frames: Vec<'a, Frame>,
+    pub(crate) previous_frames: Vec<'a, Frame>,
     /// The WebAssembly stack machine's stack of values
     pub(crate) value_store: ValueStore<'a>,
     /// Values of any global variables
@@ -59,13 +61,17 @@ pub struct Instance<'a, I: ImportDispatcher> {
     /// Cache for branching instructions, split into buckets for each function.
     branch_cache: Vec<'a, Vec<'a, BranchCacheEntry>>,
     /// Number of imports in the module
-    import_count: usize,
+    pub(crate) import_count: usize,
     /// Import dispatcher from user code
     pub import_dispatcher: I,
     /// Temporary storage for import arguments
     import_arguments: Vec<'a, Value>,
     /// temporary storage for output using the --debug option
     debug_string: Option<String>,
+    /// Interactive debugger, enabled with the --debugger option
+    debugger: Option<Debugger<'a>>,
+    /// Instruction and call counts, enabled with the --profile option
+    profiler: Option<Profiler>,
 }
 
 impl<'a, I: ImportDispatcher> Instance<'a, I> {
@@ -95,6 +101,8 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
             import_dispatcher,
             import_arguments: Vec::new_in(arena),
             debug_string: Some(String::new()),
+            debugger: None,
+            profiler: None,
         }
     }
 
@@ -163,14 +171,30 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
             import_dispatcher,
             import_arguments: Vec::new_in(arena),
             debug_string,
+            debugger: None,
+            profiler: None,
         })
     }
 
+    /// Pause before the first instruction of each call, and let the user control execution interactively
+    pub fn set_debugger(&mut self, debugger: Debugger<'a>) {
+        self.debugger = Some(debugger);
+    }
+
+    /// Count the instructions and calls executed in each function, for every call stack
+    pub fn set_profiler(&mut self, profiler: Profiler) {
+        self.profiler = Some(profiler);
+    }
+
+    pub fn profiler(&self) -> Option<&Profiler> {
+        self.profiler.as_ref()
+    }
+
     pub fn call_export<A>(&mut self, fn_name: &str, arg_values: A) -> Result<Option<Value>, String>
     where
         A: IntoIterator<Item = Value>,
     {
-        let (fn_index, param_type_iter, ret_type) =
+        let (fn_index, param_type_iter, return_count) =
             self.call_export_help_before_arg_load(self.module, fn_name)?;
         let n_args = param_type_iter.len();
 
@@ -184,7 +208,7 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
             self.value_store.push(value);
         }
 
-        self.call_export_help_after_arg_load(self.module, fn_index, n_args, ret_type)
+        self.call_export_help_after_arg_load(self.module, fn_index, n_args, return_count)
     }
 
     pub fn call_export_from_cli(
@@ -206,7 +230,7 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
 
         // Implement the "basic numbers" CLI
         // Check if the called Wasm function takes numeric arguments, and if so, try to parse them from the CLI.
-        let (fn_index, param_type_iter, ret_type) =
+        let (fn_index, param_type_iter, return_count) =
             self.call_export_help_before_arg_load(module, fn_name)?;
         let n_args = param_type_iter.len();
         for (value_bytes, value_type) in arg_strings
@@ -225,14 +249,14 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
             self.value_store.push(value);
         }
 
-        self.call_export_help_after_arg_load(module, fn_index, n_args, ret_type)
+        self.call_export_help_after_arg_load(module, fn_index, n_args, return_count)
     }
 
     fn call_export_help_before_arg_load<'m>(
         &mut self,
         module: &'m WasmModule<'a>,
         fn_name: &str,
-    ) -> Result<(usize, SignatureParamsIter<'m>, Option<ValueType>), String> {
+    ) -> Result<(usize, SignatureTypesIter<'m>, usize), String> {
         let fn_index = {
             let mut export_iter = module.export.exports.iter();
             export_iter
@@ -272,7 +296,7 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
             cursor
         };
 
-        let (param_type_iter, return_type) = {
+        let (param_type_iter, return_type_iter) = {
             let signature_index = module.function.signatures[internal_fn_index];
             module.types.look_up(signature_index)
         };
@@ -286,7 +310,7 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
             );
         }
 
-        Ok((fn_index, param_type_iter, return_type))
+        Ok((fn_index, param_type_iter, return_type_iter.len()))
     }
 
     fn call_export_help_after_arg_load(
@@ -294,7 +318,7 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
         module: &WasmModule<'a>,
         fn_index: usize,
         n_args: usize,
-        return_type: Option<ValueType>,
+        return_count: usize,
     ) -> Result<Option<Value>, String> {
         self.previous_frames.clear();
         self.blocks.clear();
@@ -307,7 +331,7 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
             0, // return_addr
             self.blocks.len(),
             n_args,
-            return_type,
+            return_count,
             &module.code.bytes,
             &mut self.value_store,
             &mut self.program_counter,
@@ -317,7 +341,21 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
             vstack: self.value_store.depth(),
         });
 
+        if let Some(debugger) = self.debugger.as_mut() {
+            debugger.start_call();
+        }
+        if let Some(profiler) = self.profiler.as_mut() {
+            profiler.start_call();
+            profiler.enter(fn_index);
+        }
+
         loop {
+            if self.debugger.is_some() {
+                self.debugger_before_instruction()?;
+            }
+            if let Some(profiler) = self.profiler.as_mut() {
+                profiler.count_instruction();
+            }
             match self.execute_next_instruction(module) {
                 Ok(Action::Continue) => {}
                 Ok(Action::Break) => {
@@ -327,6 +365,9 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
                     let file_offset = self.program_counter + module.code.section_offset as usize;
                     let mut message = e.to_string_at(file_offset);
                     self.debug_stack_trace(&mut message).unwrap();
+                    if self.debugger.is_some() {
+                        self.debugger_on_error(&message)?;
+                    }
                     return Err(message);
                 }
             };
@@ -341,6 +382,21 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
         Ok(return_value)
     }
 
+    fn debugger_before_instruction(&mut self) -> Result<(), String> {
+        // Take the debugger out, so that it can look at the rest of the Instance
+        let mut debugger = self.debugger.take().unwrap();
+        let result = debugger.before_instruction(self);
+        self.debugger = Some(debugger);
+        result
+    }
+
+    fn debugger_on_error(&mut self, message: &str) -> Result<(), String> {
+        let mut debugger = self.debugger.take().unwrap();
+        let result = debugger.on_error(self, message);
+        self.debugger = Some(debugger);
+        result
+    }
+
     fn fetch_immediate_u32(&mut self, module: &WasmModule<'a>) -> u32 {
         let x = u32::parse((), &module.code.bytes, &mut self.program_counter).unwrap();
         if let Some(debug_string) = self.debug_string.as_mut() {
@@ -355,21 +411,14 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
         let Frame {
             return_addr,
             body_block_index,
-            return_type,
+            return_count,
             ..
         } = self.current_frame;
 
-        // Throw away all locals and values except the return value
+        // Throw away all locals and values except the return values
         let locals_block_index = body_block_index - 1;
-        let locals_block = &self.blocks[locals_block_index];
-        let new_stack_depth = if return_type.is_some() {
-            self.value_store
-                .set(locals_block.vstack, self.value_store.peek());
-            locals_block.vstack + 1
-        } else {
-            locals_block.vstack
-        };
-        self.value_store.truncate(new_stack_depth);
+        let locals_start = self.blocks[locals_block_index].vstack;
+        self.move_values_down(return_count, locals_start);
 
         // Resume executing at the next instruction in the caller function
         let new_block_len = locals_block_index; // don't need a -1 because one is a length and the other is an index!
@@ -378,6 +427,10 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
 
         // self.debug_values_and_blocks("end do_return");
 
+        if let Some(profiler) = self.profiler.as_mut() {
+            profiler.exit();
+        }
+
         if let Some(caller_frame) = self.previous_frames.pop() {
             self.current_frame = caller_frame;
             Action::Continue
@@ -387,6 +440,16 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
         }
     }
 
+    /// Move the top `count` values down the stack to `new_start`, discarding everything in between
+    fn move_values_down(&mut self, count: usize, new_start: usize) {
+        let old_start = self.value_store.depth() - count;
+        for i in 0..count {
+            let value = *self.value_store.get(old_start + i).unwrap();
+            self.value_store.set(new_start + i, value);
+        }
+        self.value_store.truncate(new_start + count);
+    }
+
     fn get_load_address(&mut self, module: &WasmModule<'a>) -> Result<u32, Error> {
         // Alignment is not used in the execution steps from the spec! Maybe it's just an optimization hint?
         // https://webassembly.github.io/spec/core/exec/instructions.html#memory-instructions
@@ -491,19 +554,7 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
     ) -> Result<(), Error> {
         // self.debug_values_and_blocks(&format!("start do_call {}", fn_index));
 
-        let (signature_index, opt_import) = if fn_index < self.import_count {
-            // Imported non-Wasm function
-            let import = &module.import.imports[fn_index];
-            let sig = match import.description {
-                ImportDesc::Func { signature_index } => signature_index,
-                _ => unreachable!(),
-            };
-            (sig, Some(import))
-        } else {
-            // Wasm function
-            let sig = module.function.signatures[fn_index - self.import_count];
-            (sig, None)
-        };
+        let (signature_index, opt_import) = self.callee_signature(fn_index, module);
 
         if let Some(expected) = expected_signature {
             assert_eq!(
@@ -512,10 +563,18 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
             );
         }
 
-        let (arg_type_iter, ret_type) = module.types.look_up(signature_index);
+        let (arg_type_iter, ret_type_iter) = module.types.look_up(signature_index);
         let n_args = arg_type_iter.len();
         if self.debug_string.is_some() {
-            self.debug_call(n_args, ret_type);
+            self.debug_call(n_args, ret_type_iter.clone());
+        }
+
+        if let Some(profiler) = self.profiler.as_mut() {
+            if opt_import.is_some() {
+                profiler.call_import(fn_index);
+            } else {
+                profiler.enter(fn_index);
+            }
         }
 
         if let Some(import) = opt_import {
@@ -545,42 +604,159 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
             }
         } else {
             let return_addr = self.program_counter;
-            // set PC to start of function bytes
-            let internal_fn_index = fn_index - self.import_count;
-            self.program_counter = module.code.function_offsets[internal_fn_index] as usize;
-            // advance PC to the start of the local variable declarations
-            u32::parse((), &module.code.bytes, &mut self.program_counter).unwrap();
-
-            self.blocks.push(Block {
-                ty: BlockType::Locals(fn_index),
-                vstack: self.value_store.depth() - n_args,
-            });
-            let body_block_index = self.blocks.len();
-
-            let mut swap_frame = Frame::enter(
+            let callee_frame = self.enter_wasm_function(
                 fn_index,
                 return_addr,
-                body_block_index,
                 n_args,
-                ret_type,
-                &module.code.bytes,
-                &mut self.value_store,
-                &mut self.program_counter,
+                ret_type_iter.len(),
+                module,
             );
-            std::mem::swap(&mut swap_frame, &mut self.current_frame);
-            self.previous_frames.push(swap_frame);
-
-            self.blocks.push(Block {
-                ty: BlockType::FunctionBody(fn_index),
-                vstack: self.value_store.depth(),
-            });
+            let caller_frame = std::mem::replace(&mut self.current_frame, callee_frame);
+            self.previous_frames.push(caller_frame);
         }
         // self.debug_values_and_blocks("end do_call");
 
         Ok(())
     }
 
-    fn debug_call(&mut self, n_args: usize, return_type: Option<ValueType>) {
+    /// Tail call (`return_call` or `return_call_indirect`).
+    /// The callee replaces the current frame, and returns directly to our caller.
+    fn do_return_call(
+        &mut self,
+        expected_signature: Option<u32>,
+        fn_index: usize,
+        module: &WasmModule<'a>,
+    ) -> Result<Action, Error> {
+        let (signature_index, opt_import) = self.callee_signature(fn_index, module);
+        if opt_import.is_some() {
+            // Imports run to completion immediately, so this is just a call followed by a return
+            self.do_call(expected_signature, fn_index, module)?;
+            return Ok(self.do_return());
+        }
+
+        if let Some(expected) = expected_signature {
+            assert_eq!(
+                expected, signature_index,
+                "Indirect function call failed. Expected signature {expected} but found {signature_index}",
+            );
+        }
+
+        let (arg_type_iter, ret_type_iter) = module.types.look_up(signature_index);
+        let n_args = arg_type_iter.len();
+        if self.debug_string.is_some() {
+            self.debug_call(n_args, ret_type_iter.clone());
+        }
+
+        // Throw away all locals and values of the current frame, except the callee's arguments
+        let Frame {
+            return_addr,
+            body_block_index,
+            ..
+        } = self.current_frame;
+        let locals_block_index = body_block_index - 1;
+        let locals_start = self.blocks[locals_block_index].vstack;
+        self.move_values_down(n_args, locals_start);
+        self.blocks.truncate(locals_block_index);
+
+        if let Some(profiler) = self.profiler.as_mut() {
+            profiler.exit();
+            profiler.enter(fn_index);
+        }
+
+        self.current_frame =
+            self.enter_wasm_function(fn_index, return_addr, n_args, ret_type_iter.len(), module);
+
+        Ok(Action::Continue)
+    }
+
+    /// Look up a function's signature index, and its import if it's not a Wasm function
+    fn callee_signature<'m>(
+        &self,
+        fn_index: usize,
+        module: &'m WasmModule<'a>,
+    ) -> (u32, Option<&'m Import<'a>>) {
+        if fn_index < self.import_count {
+            // Imported non-Wasm function
+            let import = &module.import.imports[fn_index];
+            let sig = match import.description {
+                ImportDesc::Func { signature_index } => signature_index,
+                _ => unreachable!(),
+            };
+            (sig, Some(import))
+        } else {
+            // Wasm function
+            let sig = module.function.signatures[fn_index - self.import_count];
+            (sig, None)
+        }
+    }
+
+    /// Jump to the start of a Wasm function and create its frame. The arguments are already on the stack.
+    fn enter_wasm_function(
+        &mut self,
+        fn_index: usize,
+        return_addr: usize,
+        n_args: usize,
+        return_count: usize,
+        module: &WasmModule<'a>,
+    ) -> Frame {
+        // set PC to start of function bytes
+        let internal_fn_index = fn_index - self.import_count;
+        self.program_counter = module.code.function_offsets[internal_fn_index] as usize;
+        // advance PC to the start of the local variable declarations
+        u32::parse((), &module.code.bytes, &mut self.program_counter).unwrap();
+
+        self.blocks.push(Block {
+            ty: BlockType::Locals(fn_index),
+            vstack: self.value_store.depth() - n_args,
+        });
+        let body_block_index = self.blocks.len();
+
+        let frame = Frame::enter(
+            fn_index,
+            return_addr,
+            body_block_index,
+            n_args,
+            return_count,
+            &module.code.bytes,
+            &mut self.value_store,
+            &mut self.program_counter,
+        );
+
+        self.blocks.push(Block {
+            ty: BlockType::FunctionBody(fn_index),
+            vstack: self.value_store.depth(),
+        });
+
+        frame
+    }
+
+    /// Read the immediates of `call_indirect` or `return_call_indirect`, and find the callee
+    fn fetch_indirect_callee(
+        &mut self,
+        module: &WasmModule<'a>,
+        file_offset: u32,
+    ) -> Result<(u32, usize), Error> {
+        let expected_signature = self.fetch_immediate_u32(module);
+        let table_index = self.fetch_immediate_u32(module);
+        let element_index = self.value_store.pop_u32()?;
+
+        // So far, all compilers seem to be emitting MVP-compatible code. (Rust, Zig, Roc...)
+        assert_eq!(
+            table_index, 0,
+            "Table index {table_index} not supported at file offset {file_offset:#x}. This interpreter only supports Wasm MVP."
+        );
+
+        // Dereference the function pointer (look up the element index in the function table)
+        let fn_index = module.element.lookup(element_index).unwrap_or_else(|| {
+            panic!(
+                "Indirect function call failed. There is no function with element index {element_index}"
+            )
+        });
+
+        Ok((expected_signature, fn_index as usize))
+    }
+
+    fn debug_call(&mut self, n_args: usize, return_types: SignatureTypesIter) {
         if let Some(debug_string) = self.debug_string.as_mut() {
             write!(debug_string, "         args=[").unwrap();
             let arg_iter = self
@@ -596,7 +772,8 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
                 }
                 write!(debug_string, "{arg:x?}").unwrap();
             }
-            writeln!(debug_string, "] return_type={return_type:?}").unwrap();
+            let return_types: std::vec::Vec<ValueType> = return_types.collect();
+            writeln!(debug_string, "] return_types={return_types:?}").unwrap();
         }
     }
 
@@ -735,24 +912,18 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
                 self.do_call(None, fn_index, module)?;
             }
             CALLINDIRECT => {
-                let expected_signature = self.fetch_immediate_u32(module);
-                let table_index = self.fetch_immediate_u32(module);
-                let element_index = self.value_store.pop_u32()?;
-
-                // So far, all compilers seem to be emitting MVP-compatible code. (Rust, Zig, Roc...)
-                assert_eq!(
-                    table_index, 0,
-                    "Table index {table_index} not supported at file offset {file_offset:#x}. This interpreter only supports Wasm MVP."
-                );
-
-                // Dereference the function pointer (look up the element index in the function table)
-                let fn_index = module.element.lookup(element_index).unwrap_or_else(|| {
-                    panic!(
-                        "Indirect function call failed. There is no function with element index {element_index}"
-                    )
-                });
-
-                self.do_call(Some(expected_signature), fn_index as usize, module)?;
+                let (expected_signature, fn_index) =
+                    self.fetch_indirect_callee(module, file_offset)?;
+                self.do_call(Some(expected_signature), fn_index, module)?;
+            }
+            RETURNCALL => {
+                let fn_index = self.fetch_immediate_u32(module) as usize;
+                action = self.do_return_call(None, fn_index, module)?;
+            }
+            RETURNCALLINDIRECT => {
+                let (expected_signature, fn_index) =
+                    self.fetch_indirect_callee(module, file_offset)?;
+                action = self.do_return_call(Some(expected_signature), fn_index, module)?;
             }
             DROP => {
                 self.value_store.pop();
@@ -1841,26 +2012,8 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
                 ..
             } = frame;
 
-            let arg_count = {
-                let signature_index = if *fn_index < self.import_count {
-                    match self.module.import.imports[*fn_index].description {
-                        ImportDesc::Func { signature_index } => signature_index,
-                        _ => unreachable!(),
-                    }
-                } else {
-                    self.module.function.signatures[fn_index - self.import_count]
-                };
-                self.module.types.look_up(signature_index).0.len()
-            };
-
-            let fn_name = self
-                .module
-                .names
-                .function_names
-                .iter()
-                .find(|(idx, _)| *idx == *fn_index as u32)
-                .map(|(_, name)| *name)
-                .unwrap_or("");
+            let arg_count = self.debug_arg_count(*fn_index);
+            let fn_name = self.debug_fn_name(*fn_index);
 
             // Function and address match wasm-objdump formatting, for easy copy & find
             writeln!(buffer, "func[{fn_index}]  {fn_name}")?;
@@ -1896,8 +2049,30 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
         Ok(())
     }
 
+    pub(crate) fn debug_arg_count(&self, fn_index: usize) -> usize {
+        let signature_index = if fn_index < self.import_count {
+            match self.module.import.imports[fn_index].description {
+                ImportDesc::Func { signature_index } => signature_index,
+                _ => unreachable!(),
+            }
+        } else {
+            self.module.function.signatures[fn_index - self.import_count]
+        };
+        self.module.types.look_up(signature_index).0.len()
+    }
+
+    pub(crate) fn debug_fn_name(&self, fn_index: usize) -> &'a str {
+        self.module
+            .names
+            .function_names
+            .iter()
+            .find(|(idx, _)| *idx == fn_index as u32)
+            .map(|(_, name)| *name)
+            .unwrap_or("")
+    }
+
     // Call address is more intuitive than the return address in the stack trace. Search backward for it.
-    fn debug_return_addr_to_call_addr(&self, return_addr: usize) -> usize {
+    pub(crate) fn debug_return_addr_to_call_addr(&self, return_addr: usize) -> usize {
         // return_addr is pointing at the next instruction after the CALL/CALLINDIRECT.
         // Just before that is the LEB-128 function index or type index.
         // The last LEB-128 byte is <128, but the others are >=128 so we can't mistake them for CALL/CALLINDIRECT
@@ -1,5 +1,7 @@
+mod debugger;
 mod frame;
 mod instance;
+mod profiler;
 #[cfg(test)]
 mod tests;
 
@@ -7,7 +9,9 @@ mod value_store;
 pub mod wasi;
 
 // Main external interface
+pub use debugger::Debugger;
 pub use instance::Instance;
+pub use profiler::Profiler;
 pub use wasi::{WasiDispatcher, WasiFile};
 
 pub use roc_wasm_module::Value;
@@ -6,12 +6,15 @@ use std::io;
 use std::iter::once;
 use std::process;
 
-use roc_wasm_interp::{DefaultImportDispatcher, Instance};
+use roc_wasm_interp::{Debugger, DefaultImportDispatcher, Instance, Profiler};
 use roc_wasm_module::WasmModule;
 
 pub const FLAG_FUNCTION: &str = "function";
 pub const FLAG_DEBUG: &str = "debug";
+pub const FLAG_DEBUGGER: &str = "debugger";
 pub const FLAG_HEX: &str = "hex";
+pub const FLAG_DIR: &str = "dir";
+pub const FLAG_PROFILE: &str = "profile";
 pub const WASM_FILE: &str = "WASM_FILE";
 pub const ARGS_FOR_APP: &str = "ARGS_FOR_APP";
 
@@ -32,12 +35,31 @@ fn main() -> io::Result<()> {
         .action(ArgAction::SetTrue)
         .required(false);
 
+    let flag_debugger = Arg::new(FLAG_DEBUGGER)
+        .long(FLAG_DEBUGGER)
+        .help("Run in an interactive debugger, with breakpoints and stepping. Type `help` at the prompt for a list of commands.")
+        .action(ArgAction::SetTrue)
+        .required(false);
+
     let flag_hex = Arg::new(FLAG_HEX)
         .long(FLAG_HEX)
         .help("If the called function returns a value, print it in hexadecimal format.")
         .action(ArgAction::SetTrue)
         .required(false);
 
+    let flag_dir = Arg::new(FLAG_DIR)
+        .long(FLAG_DIR)
+        .help("Give the app access to a host directory.\nUse HOST_DIR::GUEST_DIR to make it appear at a different path inside the app.")
+        .value_name("HOST_DIR")
+        .action(ArgAction::Append)
+        .required(false);
+
+    let flag_profile = Arg::new(FLAG_PROFILE)
+        .long(FLAG_PROFILE)
+        .help("Count the instructions executed in each function, and write them to FILE in the folded stacks format used by flamegraph tools.\nA summary table and call graph are written to FILE.summary.txt")
+        .value_name("FILE")
+        .required(false);
+
     let wasm_file_to_run = Arg::new(WASM_FILE)
         .help("The .wasm file to run")
         .required(true);
@@ -50,7 +72,10 @@ fn main() -> io::Result<()> {
         .about("Run the given .wasm file")
         .arg(flag_function)
         .arg(flag_debug)
+        .arg(flag_debugger)
         .arg(flag_hex)
+        .arg(flag_dir)
+        .arg(flag_profile)
         .arg(wasm_file_to_run)
         .arg(args_for_app);
 
@@ -59,7 +84,9 @@ fn main() -> io::Result<()> {
     let matches = app.get_matches();
     let start_fn_name = matches.get_one::<String>(FLAG_FUNCTION).unwrap();
     let is_debug_mode = matches.get_flag(FLAG_DEBUG);
+    let is_debugger_mode = matches.get_flag(FLAG_DEBUGGER);
     let is_hex_format = matches.get_flag(FLAG_HEX);
+    let profile_path = matches.get_one::<String>(FLAG_PROFILE);
     let start_arg_strings = matches.get_many::<String>(ARGS_FOR_APP).unwrap_or_default();
     let wasm_path = matches.get_one::<String>(WASM_FILE).unwrap();
     // WASI expects the .wasm file to be argv[0]
@@ -87,17 +114,43 @@ fn main() -> io::Result<()> {
 
     // Create an execution instance
 
-    let dispatcher = DefaultImportDispatcher::new(&wasi_argv);
+    let mut dispatcher = DefaultImportDispatcher::new(&wasi_argv);
+    for dir in matches.get_many::<String>(FLAG_DIR).unwrap_or_default() {
+        let (host_dir, guest_dir) = dir.split_once("::").unwrap_or((dir, dir));
+        if let Err(e) = dispatcher.wasi.preopen_dir(guest_dir, host_dir) {
+            eprintln!("I couldn't give the app access to the directory {host_dir}: {e}");
+            process::exit(1);
+        }
+    }
     let mut inst =
         Instance::for_module(&arena, &module, dispatcher, is_debug_mode).unwrap_or_else(|e| {
             eprintln!("{e}");
             process::exit(2);
         });
 
+    if is_debugger_mode {
+        println!("Type `help` for a list of debugger commands.");
+        inst.set_debugger(Debugger::new(io::stdin().lock(), io::stdout()));
+    }
+
+    if profile_path.is_some() {
+        inst.set_profiler(Profiler::new());
+    }
+
     // Run
 
     let result = inst.call_export_from_cli(&module, start_fn_name, &wasi_argv);
 
+    if let (Some(path), Some(profiler)) = (profile_path, inst.profiler()) {
+        let mut folded_stacks = std::vec::Vec::new();
+        profiler.write_folded_stacks(&module, &mut folded_stacks)?;
+        fs::write(path, folded_stacks)?;
+
+        let mut summary = std::vec::Vec::new();
+        profiler.write_summary(&module, &mut summary)?;
+        fs::write(format!("{path}.summary.txt"), summary)?;
+    }
+
     // Print out return value, if any
 
     match result {
@@ -0,0 +1,202 @@
+use roc_wasm_module::WasmModule;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+const ROOT: usize = 0;
+
+/// A node in the calling context tree. There's one for each distinct call stack.
+#[derive(Debug)]
+struct Node {
+    fn_index: u32,
+    parent: usize,
+    children: Vec<(u32, usize)>,
+    calls: u64,
+    /// Instructions executed in this function itself, not counting its callees
+    instructions: u64,
+}
+
+/// Counts the instructions and calls executed by an [Instance](crate::Instance), for each call stack.
+/// Counting instructions rather than time means the same program always gets the same profile,
+/// so profiles of different builds of the same app can be compared directly.
+#[derive(Debug)]
+pub struct Profiler {
+    nodes: Vec<Node>,
+    current: usize,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Profiler {
+            nodes: vec![Node {
+                fn_index: u32::MAX,
+                parent: ROOT,
+                children: vec![],
+                calls: 0,
+                instructions: 0,
+            }],
+            current: ROOT,
+        }
+    }
+
+    pub(crate) fn start_call(&mut self) {
+        self.current = ROOT;
+    }
+
+    pub(crate) fn enter(&mut self, fn_index: usize) {
+        self.current = self.call(fn_index);
+    }
+
+    /// Imported functions don't execute any Wasm instructions, so we only count the call
+    pub(crate) fn call_import(&mut self, fn_index: usize) {
+        self.call(fn_index);
+    }
+
+    pub(crate) fn exit(&mut self) {
+        self.current = self.nodes[self.current].parent;
+    }
+
+    pub(crate) fn count_instruction(&mut self) {
+        self.nodes[self.current].instructions += 1;
+    }
+
+    fn call(&mut self, fn_index: usize) -> usize {
+        let fn_index = fn_index as u32;
+        let existing = self.nodes[self.current]
+            .children
+            .iter()
+            .find(|(f, _)| *f == fn_index)
+            .map(|(_, node)| *node);
+        let node = existing.unwrap_or_else(|| {
+            let node = self.nodes.len();
+            self.nodes.push(Node {
+                fn_index,
+                parent: self.current,
+                children: vec![],
+                calls: 0,
+                instructions: 0,
+            });
+            self.nodes[self.current].children.push((fn_index, node));
+            node
+        });
+        self.nodes[node].calls += 1;
+        node
+    }
+
+    /// Write one line per call stack, with the number of instructions executed at the top of that stack.
+    /// This is the "folded stacks" format used by flamegraph tools like `inferno` and `flamegraph.pl`.
+    pub fn write_folded_stacks(&self, module: &WasmModule, out: &mut impl Write) -> io::Result<()> {
+        let mut stacks = BTreeMap::<String, u64>::new();
+        for (index, node) in self.nodes.iter().enumerate().skip(1) {
+            if node.instructions == 0 {
+                continue;
+            }
+            let mut names = vec![];
+            let mut ancestor = index;
+            while ancestor != ROOT {
+                names.push(fn_name(module, self.nodes[ancestor].fn_index));
+                ancestor = self.nodes[ancestor].parent;
+            }
+            names.reverse();
+            *stacks.entry(names.join(";")).or_default() += node.instructions;
+        }
+
+        for (stack, instructions) in stacks {
+            writeln!(out, "{stack} {instructions}")?;
+        }
+        Ok(())
+    }
+
+    /// Write a table of instruction and call counts per function, followed by the call graph
+    pub fn write_summary(&self, module: &WasmModule, out: &mut impl Write) -> io::Result<()> {
+        // Instructions executed in each node and everything it called
+        let mut node_totals: Vec<u64> = self.nodes.iter().map(|n| n.instructions).collect();
+        // Children are always created after their parents
+        for index in (1..self.nodes.len()).rev() {
+            let parent = self.nodes[index].parent;
+            node_totals[parent] += node_totals[index];
+        }
+
+        #[derive(Default)]
+        struct FunctionCost {
+            total: u64,
+            own: u64,
+            calls: u64,
+        }
+        let mut functions = BTreeMap::<u32, FunctionCost>::new();
+        let mut call_graph = BTreeMap::<(u32, u32), u64>::new();
+        for (index, node) in self.nodes.iter().enumerate().skip(1) {
+            let cost = functions.entry(node.fn_index).or_default();
+            cost.own += node.instructions;
+            cost.calls += node.calls;
+            // For recursive functions, the outermost call already includes the inner ones
+            if !self.has_ancestor(index, node.fn_index) {
+                cost.total += node_totals[index];
+            }
+            if node.parent != ROOT {
+                let caller = self.nodes[node.parent].fn_index;
+                *call_graph.entry((caller, node.fn_index)).or_default() += node.calls;
+            }
+        }
+
+        let mut rows: Vec<_> = functions.into_iter().collect();
+        rows.sort_by(|(a_index, a), (b_index, b)| b.total.cmp(&a.total).then(a_index.cmp(b_index)));
+
+        writeln!(out, "Total instructions: {}", node_totals[ROOT])?;
+        writeln!(out)?;
+        writeln!(
+            out,
+            "{:>14} {:>14} {:>10}  function",
+            "total", "self", "calls"
+        )?;
+        for (fn_index, cost) in rows {
+            writeln!(
+                out,
+                "{:>14} {:>14} {:>10}  {}",
+                cost.total,
+                cost.own,
+                cost.calls,
+                fn_name(module, fn_index)
+            )?;
+        }
+
+        writeln!(out)?;
+        writeln!(out, "Call graph:")?;
+        writeln!(out, "{:>10}  caller -> callee", "calls")?;
+        for ((caller, callee), calls) in call_graph {
+            writeln!(
+                out,
+                "{calls:>10}  {} -> {}",
+                fn_name(module, caller),
+                fn_name(module, callee)
+            )?;
+        }
+        Ok(())
+    }
+
+    fn has_ancestor(&self, index: usize, fn_index: u32) -> bool {
+        let mut ancestor = self.nodes[index].parent;
+        while ancestor != ROOT {
+            if self.nodes[ancestor].fn_index == fn_index {
+                return true;
+            }
+            ancestor = self.nodes[ancestor].parent;
+        }
+        false
+    }
+}
+
+fn fn_name(module: &WasmModule, fn_index: u32) -> String {
+    module
+        .names
+        .function_names
+        .iter()
+        .find(|(index, _)| *index == fn_index)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| format!("func[{fn_index}]"))
+}
@@ -2,11 +2,14 @@
 
 mod test_basics;
 mod test_convert;
+mod test_debugger;
 mod test_f32;
 mod test_f64;
 mod test_i32;
 mod test_i64;
 mod test_mem;
+mod test_profiler;
+mod test_wasi;
 
 use crate::{DefaultImportDispatcher, Instance};
 use bumpalo::{collections::Vec, Bump};
@@ -74,7 +77,7 @@ where
         module.code.function_offsets.push(0);
         module.add_function_signature(Signature {
             param_types: Vec::new_in(&arena),
-            ret_type: Some(ValueType::from(expected)),
+            ret_types: bumpalo::vec![in &arena; ValueType::from(expected)],
         });
         module.export.append(Export {
             name: "test",
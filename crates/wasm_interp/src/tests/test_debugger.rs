@@ -0,0 +1,126 @@
+use super::create_exported_function_no_locals;
+use crate::{Debugger, DefaultImportDispatcher, Instance};
+use bumpalo::Bump;
+use roc_wasm_module::{
+    opcodes::OpCode, sections::MemorySection, Signature, Value, ValueType, WasmModule,
+};
+
+/// func[0] two_plus_two calls func[1] add
+fn two_plus_two_module(arena: &Bump) -> WasmModule<'_> {
+    let mut module = WasmModule::new(arena);
+    module.memory = MemorySection::new(arena, MemorySection::PAGE_SIZE);
+
+    let signature0 = Signature {
+        param_types: bumpalo::vec![in arena],
+        ret_types: bumpalo::vec![in arena; ValueType::I32],
+    };
+    create_exported_function_no_locals(&mut module, "two_plus_two", signature0, |buf| {
+        buf.push(OpCode::I32CONST as u8);
+        buf.push(2);
+        buf.push(OpCode::I32CONST as u8);
+        buf.push(2);
+        buf.push(OpCode::CALL as u8);
+        buf.push(1);
+        buf.push(OpCode::END as u8);
+    });
+
+    let signature1 = Signature {
+        param_types: bumpalo::vec![in arena; ValueType::I32, ValueType::I32],
+        ret_types: bumpalo::vec![in arena; ValueType::I32],
+    };
+    create_exported_function_no_locals(&mut module, "add_export", signature1, |buf| {
+        buf.push(OpCode::GETLOCAL as u8);
+        buf.push(0);
+        buf.push(OpCode::GETLOCAL as u8);
+        buf.push(1);
+        buf.push(OpCode::I32ADD as u8);
+        buf.push(OpCode::END as u8);
+    });
+
+    module.names.append_function(0, "two_plus_two");
+    module.names.append_function(1, "add");
+    module
+}
+
+fn run_with_debugger(
+    commands: &str,
+    memory_init: &[u8],
+) -> (Result<Option<Value>, String>, String) {
+    let arena = Bump::new();
+    let module = two_plus_two_module(&arena);
+    let mut output = vec![];
+
+    let result = {
+        let mut inst =
+            Instance::for_module(&arena, &module, DefaultImportDispatcher::default(), false)
+                .unwrap();
+        inst.memory[..memory_init.len()].copy_from_slice(memory_init);
+        inst.set_debugger(Debugger::new(commands.as_bytes(), &mut output));
+        inst.call_export("two_plus_two", [])
+    };
+
+    (result, String::from_utf8(output).unwrap())
+}
+
+#[test]
+fn test_debugger_breakpoint_by_name() {
+    let commands = "break add\ncontinue\nlocals\nbacktrace\nstack\ncontinue\n";
+    let (result, output) = run_with_debugger(commands, &[]);
+
+    assert_eq!(result, Ok(Some(Value::I32(4))));
+    assert!(output.contains("Breakpoint at func[1] add"), "{output}");
+    assert!(output.contains("Breakpoint: func[1] add"), "{output}");
+    assert!(output.contains("arg     0 I32(2)"), "{output}");
+    assert!(output.contains("arg     1 I32(2)"), "{output}");
+    assert!(output.contains("#0 func[1] add"), "{output}");
+    assert!(output.contains("#1 func[0] two_plus_two"), "{output}");
+    assert!(output.contains("Empty stack"), "{output}");
+}
+
+#[test]
+fn test_debugger_breakpoint_by_index_and_export() {
+    for name in ["1", "add_export"] {
+        let commands = format!("b {name}\nc\nc\n");
+        let (result, output) = run_with_debugger(&commands, &[]);
+        assert_eq!(result, Ok(Some(Value::I32(4))));
+        assert!(output.contains("Breakpoint: func[1] add"), "{output}");
+    }
+}
+
+#[test]
+fn test_debugger_step_over() {
+    // The empty lines repeat `next`
+    let (result, output) = run_with_debugger("next\n\n\nnext\n", &[]);
+
+    assert_eq!(result, Ok(Some(Value::I32(4))));
+    assert!(output.contains("CALL"), "{output}");
+    assert!(output.contains("END"), "{output}");
+    assert!(!output.contains("func[1]"), "{output}");
+}
+
+#[test]
+fn test_debugger_step_into_and_out() {
+    let (result, output) = run_with_debugger("s\ns\ns\nstack\nout\nstack\nc\n", &[]);
+
+    assert_eq!(result, Ok(Some(Value::I32(4))));
+    assert!(output.contains("func[1] add"), "{output}");
+    assert!(output.contains("GETLOCAL"), "{output}");
+    // After stepping out, the return value is on the caller's stack
+    assert!(output.contains("  0 I32(4)"), "{output}");
+}
+
+#[test]
+fn test_debugger_memory_and_quit() {
+    let mut memory_init = [0; 21];
+    memory_init[16..].copy_from_slice(b"Hello");
+    let (result, output) = run_with_debugger("x 0x10 20\nquit\n", &memory_init);
+
+    assert_eq!(result, Err("Program stopped from the debugger".to_string()));
+    assert!(
+        output.contains(
+            "00000010  48 65 6c 6c 6f 00 00 00  00 00 00 00 00 00 00 00  |Hello...........|"
+        ),
+        "{output}"
+    );
+    assert!(output.contains("00000020  00 00 00 00"), "{output}");
+}
@@ -0,0 +1,115 @@
+use super::create_exported_function_no_locals;
+use crate::{DefaultImportDispatcher, Instance, Profiler};
+use bumpalo::Bump;
+use roc_wasm_module::{opcodes::OpCode, Signature, Value, ValueType, WasmModule};
+
+/// func[0] main calls func[1] add twice, and each call to add calls func[2] double once
+fn profiled_module(arena: &Bump) -> WasmModule<'_> {
+    let mut module = WasmModule::new(arena);
+
+    let signature0 = Signature {
+        param_types: bumpalo::vec![in arena],
+        ret_types: bumpalo::vec![in arena; ValueType::I32],
+    };
+    create_exported_function_no_locals(&mut module, "main", signature0, |buf| {
+        buf.push(OpCode::I32CONST as u8);
+        buf.push(1);
+        buf.push(OpCode::I32CONST as u8);
+        buf.push(2);
+        buf.push(OpCode::CALL as u8);
+        buf.push(1);
+        buf.push(OpCode::I32CONST as u8);
+        buf.push(3);
+        buf.push(OpCode::CALL as u8);
+        buf.push(1);
+        buf.push(OpCode::END as u8);
+    });
+
+    let signature1 = Signature {
+        param_types: bumpalo::vec![in arena; ValueType::I32, ValueType::I32],
+        ret_types: bumpalo::vec![in arena; ValueType::I32],
+    };
+    create_exported_function_no_locals(&mut module, "add", signature1, |buf| {
+        buf.push(OpCode::GETLOCAL as u8);
+        buf.push(0);
+        buf.push(OpCode::GETLOCAL as u8);
+        buf.push(1);
+        buf.push(OpCode::CALL as u8);
+        buf.push(2);
+        buf.push(OpCode::I32ADD as u8);
+        buf.push(OpCode::END as u8);
+    });
+
+    let signature2 = Signature {
+        param_types: bumpalo::vec![in arena; ValueType::I32],
+        ret_types: bumpalo::vec![in arena; ValueType::I32],
+    };
+    create_exported_function_no_locals(&mut module, "double", signature2, |buf| {
+        buf.push(OpCode::GETLOCAL as u8);
+        buf.push(0);
+        buf.push(OpCode::GETLOCAL as u8);
+        buf.push(0);
+        buf.push(OpCode::I32ADD as u8);
+        buf.push(OpCode::END as u8);
+    });
+
+    module.names.append_function(0, "main");
+    module.names.append_function(1, "add");
+    // func[2] has no name, so it should be shown by index
+    module
+}
+
+/// Returns the folded stacks and the summary
+fn run_profiled() -> (String, String) {
+    let arena = Bump::new();
+    let module = profiled_module(&arena);
+    let mut inst =
+        Instance::for_module(&arena, &module, DefaultImportDispatcher::default(), false).unwrap();
+    inst.set_profiler(Profiler::new());
+
+    let result = inst.call_export("main", []).unwrap();
+    // add(a, b) = a + double(b), so add(add(1, 2), 3) = 11
+    assert_eq!(result, Some(Value::I32(11)));
+
+    let profiler = inst.profiler().unwrap();
+    let mut folded_stacks = vec![];
+    profiler
+        .write_folded_stacks(&module, &mut folded_stacks)
+        .unwrap();
+    let mut summary = vec![];
+    profiler.write_summary(&module, &mut summary).unwrap();
+
+    (
+        String::from_utf8(folded_stacks).unwrap(),
+        String::from_utf8(summary).unwrap(),
+    )
+}
+
+#[test]
+fn test_profiler_folded_stacks() {
+    let (folded_stacks, _) = run_profiled();
+
+    let expected = "main 6\nmain;add 10\nmain;add;func[2] 8\n";
+    assert_eq!(folded_stacks, expected);
+}
+
+#[test]
+fn test_profiler_summary() {
+    let (_, summary) = run_profiled();
+    let expected = [
+        "Total instructions: 24",
+        "",
+        "         total           self      calls  function",
+        "            24              6          1  main",
+        "            18             10          2  add",
+        "             8              8          2  func[2]",
+        "",
+        "Call graph:",
+        "     calls  caller -> callee",
+        "         2  main -> add",
+        "         2  add -> func[2]",
+        "",
+    ]
+    .join("\n");
+    assert_eq!(summary, expected);
+}
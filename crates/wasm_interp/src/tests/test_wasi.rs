@@ -0,0 +1,328 @@
+use crate::wasi::{Errno, WasiDispatcher};
+use roc_wasm_module::Value;
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const PREOPEN_FD: i32 = 3;
+const PATH_ADDR: usize = 0x100;
+const OUT_ADDR: usize = 0x200;
+const IOV_ADDR: usize = 0x300;
+const BUF_ADDR: usize = 0x400;
+
+const OFLAGS_CREAT: i32 = 1;
+const RIGHTS_READ_WRITE: i64 = (1 << 1) | (1 << 6);
+
+fn call(wasi: &mut WasiDispatcher, function_name: &str, args: &[Value], memory: &mut [u8]) -> i32 {
+    wasi.dispatch(function_name, args, memory)
+        .unwrap()
+        .expect_i32()
+        .unwrap()
+}
+
+fn read_u32(memory: &[u8], addr: usize) -> u32 {
+    u32::from_le_bytes(memory[addr..][..4].try_into().unwrap())
+}
+
+fn read_u64(memory: &[u8], addr: usize) -> u64 {
+    u64::from_le_bytes(memory[addr..][..8].try_into().unwrap())
+}
+
+/// Write a path into memory and return the (pointer, length) arguments for it
+fn path_args(memory: &mut [u8], path: &str) -> [Value; 2] {
+    memory[PATH_ADDR..][..path.len()].copy_from_slice(path.as_bytes());
+    [Value::I32(PATH_ADDR as i32), Value::I32(path.len() as i32)]
+}
+
+fn path_open(wasi: &mut WasiDispatcher, memory: &mut [u8], path: &str, oflags: i32) -> i32 {
+    let [ptr, len] = path_args(memory, path);
+    let args = [
+        Value::I32(PREOPEN_FD),
+        Value::I32(0),
+        ptr,
+        len,
+        Value::I32(oflags),
+        Value::I64(RIGHTS_READ_WRITE),
+        Value::I64(0),
+        Value::I32(0),
+        Value::I32(OUT_ADDR as i32),
+    ];
+    call(wasi, "path_open", &args, memory)
+}
+
+/// Set up a single iovec pointing at the buffer area
+fn iov_args(memory: &mut [u8], fd: i32, len: usize) -> [Value; 4] {
+    memory[IOV_ADDR..][..4].copy_from_slice(&(BUF_ADDR as u32).to_le_bytes());
+    memory[IOV_ADDR + 4..][..4].copy_from_slice(&(len as u32).to_le_bytes());
+    [
+        Value::I32(fd),
+        Value::I32(IOV_ADDR as i32),
+        Value::I32(1),
+        Value::I32(OUT_ADDR as i32),
+    ]
+}
+
+#[test]
+fn test_preopened_dir_name() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut wasi = WasiDispatcher::default();
+    wasi.preopen_dir("/data", dir.path()).unwrap();
+    let mut memory = vec![0; 0x1000];
+
+    let prestat_args = [Value::I32(PREOPEN_FD), Value::I32(OUT_ADDR as i32)];
+    let result = call(&mut wasi, "fd_prestat_get", &prestat_args, &mut memory);
+    assert_eq!(result, Errno::Success as i32);
+    assert_eq!(read_u32(&memory, OUT_ADDR + 4), 5);
+
+    let name_args = [
+        Value::I32(PREOPEN_FD),
+        Value::I32(BUF_ADDR as i32),
+        Value::I32(5),
+    ];
+    let result = call(&mut wasi, "fd_prestat_dir_name", &name_args, &mut memory);
+    assert_eq!(result, Errno::Success as i32);
+    assert_eq!(&memory[BUF_ADDR..][..5], b"/data");
+
+    let next_args = [Value::I32(PREOPEN_FD + 1), Value::I32(OUT_ADDR as i32)];
+    let result = call(&mut wasi, "fd_prestat_get", &next_args, &mut memory);
+    assert_eq!(result, Errno::Badf as i32);
+}
+
+#[test]
+fn test_file_write_seek_read() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut wasi = WasiDispatcher::default();
+    wasi.preopen_dir(".", dir.path()).unwrap();
+    let mut memory = vec![0; 0x1000];
+
+    let result = path_open(&mut wasi, &mut memory, "hello.txt", OFLAGS_CREAT);
+    assert_eq!(result, Errno::Success as i32);
+    let fd = read_u32(&memory, OUT_ADDR) as i32;
+    assert_eq!(fd, PREOPEN_FD + 1);
+
+    let text = b"Hello, World!";
+    memory[BUF_ADDR..][..text.len()].copy_from_slice(text);
+    let write_args = iov_args(&mut memory, fd, text.len());
+    let result = call(&mut wasi, "fd_write", &write_args, &mut memory);
+    assert_eq!(result, Errno::Success as i32);
+    assert_eq!(read_u32(&memory, OUT_ADDR), text.len() as u32);
+
+    let seek_args = [
+        Value::I32(fd),
+        Value::I64(7),
+        Value::I32(0),
+        Value::I32(OUT_ADDR as i32),
+    ];
+    let result = call(&mut wasi, "fd_seek", &seek_args, &mut memory);
+    assert_eq!(result, Errno::Success as i32);
+    assert_eq!(read_u64(&memory, OUT_ADDR), 7);
+
+    memory[BUF_ADDR..][..text.len()].fill(0);
+    let read_args = iov_args(&mut memory, fd, 100);
+    let result = call(&mut wasi, "fd_read", &read_args, &mut memory);
+    assert_eq!(result, Errno::Success as i32);
+    assert_eq!(read_u32(&memory, OUT_ADDR), 6);
+    assert_eq!(&memory[BUF_ADDR..][..6], b"World!");
+
+    let filestat_args = [Value::I32(fd), Value::I32(OUT_ADDR as i32)];
+    let result = call(&mut wasi, "fd_filestat_get", &filestat_args, &mut memory);
+    assert_eq!(result, Errno::Success as i32);
+    assert_eq!(read_u64(&memory, OUT_ADDR + 32), text.len() as u64);
+
+    let result = call(&mut wasi, "fd_close", &[Value::I32(fd)], &mut memory);
+    assert_eq!(result, Errno::Success as i32);
+    let result = call(&mut wasi, "fd_close", &[Value::I32(fd)], &mut memory);
+    assert_eq!(result, Errno::Badf as i32);
+
+    let on_host = fs::read(dir.path().join("hello.txt")).unwrap();
+    assert_eq!(on_host, text);
+}
+
+#[test]
+fn test_directories() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("a.txt"), "a").unwrap();
+    let mut wasi = WasiDispatcher::default();
+    wasi.preopen_dir(".", dir.path()).unwrap();
+    let mut memory = vec![0; 0x1000];
+
+    let [ptr, len] = path_args(&mut memory, "sub");
+    let args = [Value::I32(PREOPEN_FD), ptr, len];
+    let result = call(&mut wasi, "path_create_directory", &args, &mut memory);
+    assert_eq!(result, Errno::Success as i32);
+    assert!(dir.path().join("sub").is_dir());
+
+    let readdir_args = [
+        Value::I32(PREOPEN_FD),
+        Value::I32(BUF_ADDR as i32),
+        Value::I32(0x400),
+        Value::I64(0),
+        Value::I32(OUT_ADDR as i32),
+    ];
+    let result = call(&mut wasi, "fd_readdir", &readdir_args, &mut memory);
+    assert_eq!(result, Errno::Success as i32);
+    let bufused = read_u32(&memory, OUT_ADDR) as usize;
+
+    let mut names = vec![];
+    let mut offset = 0;
+    while offset < bufused {
+        let dirent = BUF_ADDR + offset;
+        let name_len = read_u32(&memory, dirent + 16) as usize;
+        let name = &memory[dirent + 24..][..name_len];
+        names.push((
+            String::from_utf8(name.to_vec()).unwrap(),
+            memory[dirent + 20],
+        ));
+        offset += 24 + name_len;
+    }
+    let expected = [(".", 3), ("..", 3), ("a.txt", 4), ("sub", 3)];
+    let expected = expected.map(|(name, filetype)| (name.to_string(), filetype));
+    assert_eq!(names, expected);
+
+    let [ptr, len] = path_args(&mut memory, "sub");
+    let args = [Value::I32(PREOPEN_FD), ptr, len];
+    let result = call(&mut wasi, "path_unlink_file", &args, &mut memory);
+    assert_eq!(result, Errno::Isdir as i32);
+
+    let [ptr, len] = path_args(&mut memory, "sub/../a.txt");
+    let args = [Value::I32(PREOPEN_FD), ptr, len];
+    let result = call(&mut wasi, "path_unlink_file", &args, &mut memory);
+    assert_eq!(result, Errno::Success as i32);
+    assert!(!dir.path().join("a.txt").exists());
+}
+
+#[test]
+fn test_sandbox() {
+    let outer = tempfile::tempdir().unwrap();
+    let sandbox = outer.path().join("sandbox");
+    fs::create_dir(&sandbox).unwrap();
+    fs::write(outer.path().join("secret.txt"), "secret").unwrap();
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(outer.path(), sandbox.join("escape")).unwrap();
+
+    let mut wasi = WasiDispatcher::default();
+    wasi.preopen_dir(".", &sandbox).unwrap();
+    let mut memory = vec![0; 0x1000];
+
+    let mut escapes = vec!["../secret.txt", "a/../../secret.txt", "/secret.txt"];
+    if cfg!(unix) {
+        escapes.push("escape/secret.txt");
+    }
+    for path in escapes {
+        let result = path_open(&mut wasi, &mut memory, path, 0);
+        assert_eq!(result, Errno::Notcapable as i32, "{path}");
+    }
+
+    let result = path_open(&mut wasi, &mut memory, "missing.txt", 0);
+    assert_eq!(result, Errno::Noent as i32);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_sandbox_symlinks() {
+    use std::os::unix::fs::symlink;
+
+    let outer = tempfile::tempdir().unwrap();
+    let sandbox = outer.path().join("sandbox");
+    fs::create_dir_all(sandbox.join("a/b")).unwrap();
+    fs::write(outer.path().join("secret.txt"), "secret").unwrap();
+    fs::write(sandbox.join("inside.txt"), "inside").unwrap();
+
+    // Creating a file through this would create it outside the sandbox
+    symlink(outer.path().join("new.txt"), sandbox.join("dangling")).unwrap();
+    // Only escapes once `a/b/top` has been followed, which a purely lexical `..` would miss
+    symlink(&sandbox, sandbox.join("a/b/top")).unwrap();
+    symlink("../secret.txt", sandbox.join("up")).unwrap();
+    // Symlinks that stay in the sandbox are fine
+    symlink("a/b/top/inside.txt", sandbox.join("ok")).unwrap();
+    symlink("cycle", sandbox.join("cycle")).unwrap();
+
+    let mut wasi = WasiDispatcher::default();
+    wasi.preopen_dir(".", &sandbox).unwrap();
+    let mut memory = vec![0; 0x1000];
+
+    for path in ["dangling", "a/b/top/up", "a/b/top/dangling"] {
+        let result = path_open(&mut wasi, &mut memory, path, OFLAGS_CREAT);
+        assert_eq!(result, Errno::Notcapable as i32, "{path}");
+    }
+    assert!(!outer.path().join("new.txt").exists());
+
+    let result = path_open(&mut wasi, &mut memory, "cycle", 0);
+    assert_eq!(result, Errno::Loop as i32);
+
+    let result = path_open(&mut wasi, &mut memory, "ok", 0);
+    assert_eq!(result, Errno::Success as i32);
+    let fd = read_u32(&memory, OUT_ADDR) as i32;
+    let read_args = iov_args(&mut memory, fd, 100);
+    let result = call(&mut wasi, "fd_read", &read_args, &mut memory);
+    assert_eq!(result, Errno::Success as i32);
+    assert_eq!(&memory[BUF_ADDR..][..6], b"inside");
+}
+
+#[test]
+fn test_pread_pwrite_renumber() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("file.txt"), "Hello, World!").unwrap();
+    let mut wasi = WasiDispatcher::default();
+    wasi.preopen_dir(".", dir.path()).unwrap();
+    let mut memory = vec![0; 0x1000];
+
+    let result = path_open(&mut wasi, &mut memory, "file.txt", 0);
+    assert_eq!(result, Errno::Success as i32);
+    let fd = read_u32(&memory, OUT_ADDR) as i32;
+
+    memory[BUF_ADDR..][..5].copy_from_slice(b"Earth");
+    let [fd_arg, iovs, iovs_len, nwritten] = iov_args(&mut memory, fd, 5);
+    let pwrite_args = [fd_arg, iovs, iovs_len, Value::I64(7), nwritten];
+    let result = call(&mut wasi, "fd_pwrite", &pwrite_args, &mut memory);
+    assert_eq!(result, Errno::Success as i32);
+    assert_eq!(read_u32(&memory, OUT_ADDR), 5);
+
+    // Reading from an offset doesn't move the file's own offset, which is still at the start
+    let [fd_arg, iovs, iovs_len, nread] = iov_args(&mut memory, fd, 6);
+    let pread_args = [fd_arg, iovs, iovs_len, Value::I64(7), nread];
+    let result = call(&mut wasi, "fd_pread", &pread_args, &mut memory);
+    assert_eq!(result, Errno::Success as i32);
+    assert_eq!(read_u32(&memory, OUT_ADDR), 6);
+    assert_eq!(&memory[BUF_ADDR..][..6], b"Earth!");
+
+    let new_fd = PREOPEN_FD;
+    let renumber_args = [Value::I32(fd), Value::I32(new_fd)];
+    let result = call(&mut wasi, "fd_renumber", &renumber_args, &mut memory);
+    assert_eq!(result, Errno::Success as i32);
+
+    let read_args = iov_args(&mut memory, new_fd, 100);
+    let result = call(&mut wasi, "fd_read", &read_args, &mut memory);
+    assert_eq!(result, Errno::Success as i32);
+    assert_eq!(&memory[BUF_ADDR..][..13], b"Hello, Earth!");
+
+    let result = call(&mut wasi, "fd_close", &[Value::I32(fd)], &mut memory);
+    assert_eq!(result, Errno::Badf as i32);
+
+    let result = call(&mut wasi, "proc_raise", &[Value::I32(9)], &mut memory);
+    assert_eq!(result, Errno::Nosys as i32);
+}
+
+#[test]
+fn test_clock_time_get() {
+    let mut wasi = WasiDispatcher::default();
+    let mut memory = vec![0; 0x1000];
+
+    let before = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    let args = [Value::I32(0), Value::I64(1), Value::I32(OUT_ADDR as i32)];
+    let result = call(&mut wasi, "clock_time_get", &args, &mut memory);
+    assert_eq!(result, Errno::Success as i32);
+    let after = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    let time = read_u64(&memory, OUT_ADDR) as u128;
+    assert!(before.as_nanos() <= time && time <= after.as_nanos());
+
+    let args = [Value::I32(1), Value::I64(1), Value::I32(OUT_ADDR as i32)];
+    call(&mut wasi, "clock_time_get", &args, &mut memory);
+    let first = read_u64(&memory, OUT_ADDR);
+    call(&mut wasi, "clock_time_get", &args, &mut memory);
+    let second = read_u64(&memory, OUT_ADDR);
+    assert!(first <= second);
+
+    let args = [Value::I32(4), Value::I64(1), Value::I32(OUT_ADDR as i32)];
+    let result = call(&mut wasi, "clock_time_get", &args, &mut memory);
+    assert_eq!(result, Errno::Inval as i32);
+}
@@ -1,14 +1,52 @@
 use rand::prelude::*;
 use roc_wasm_module::Value;
-use std::io::{self, Read, StderrLock, StdoutLock, Write};
+use std::ffi::OsString;
+use std::fs::{self, File, FileTimes, Metadata, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, StderrLock, StdoutLock, Write};
+use std::path::{Component, Path, PathBuf};
 use std::process::exit;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 pub const MODULE_NAME: &str = "wasi_snapshot_preview1";
 
+const CLOCK_REALTIME: i32 = 0;
+const CLOCK_MONOTONIC: i32 = 1;
+const CLOCK_PROCESS_CPUTIME_ID: i32 = 2;
+const CLOCK_THREAD_CPUTIME_ID: i32 = 3;
+
+const FILETYPE_UNKNOWN: u8 = 0;
+const FILETYPE_CHARACTER_DEVICE: u8 = 2;
+const FILETYPE_DIRECTORY: u8 = 3;
+const FILETYPE_REGULAR_FILE: u8 = 4;
+const FILETYPE_SYMBOLIC_LINK: u8 = 7;
+
+const FDFLAGS_APPEND: u16 = 1;
+const LOOKUPFLAGS_SYMLINK_FOLLOW: i32 = 1;
+
+const OFLAGS_CREAT: i32 = 1;
+const OFLAGS_DIRECTORY: i32 = 2;
+const OFLAGS_EXCL: i32 = 4;
+const OFLAGS_TRUNC: i32 = 8;
+
+const RIGHTS_FD_READ: u64 = 1 << 1;
+const RIGHTS_FD_WRITE: u64 = 1 << 6;
+const RIGHTS_ALL: u64 = (1 << 29) - 1;
+
+const FSTFLAGS_ATIM: i32 = 1;
+const FSTFLAGS_ATIM_NOW: i32 = 2;
+const FSTFLAGS_MTIM: i32 = 4;
+const FSTFLAGS_MTIM_NOW: i32 = 8;
+
+const FILESTAT_SIZE: usize = 64;
+
+/// Same limit as Linux, so that symlink cycles can't keep `resolve_path` busy forever
+const MAX_SYMLINKS_FOLLOWED: usize = 40;
+
 pub struct WasiDispatcher<'a> {
     pub args: &'a [&'a [u8]],
     pub rng: ThreadRng,
     pub files: Vec<WasiFile>,
+    start_time: Instant,
 }
 
 impl Default for WasiDispatcher<'_> {
@@ -22,12 +60,33 @@ pub enum WasiFile {
     WriteOnly(Vec<u8>),
     ReadWrite(Vec<u8>),
     HostSystemFile,
+    /// A host directory that the app can see at `guest_path`.
+    /// The app can only access the host file system through one of these.
+    PreopenedDir {
+        guest_path: String,
+        host_path: PathBuf,
+    },
+    /// A directory opened by the app, inside the preopened directory `sandbox_root`
+    HostDir {
+        host_path: PathBuf,
+        sandbox_root: PathBuf,
+    },
+    /// A regular file opened by the app
+    HostFile {
+        file: File,
+        readable: bool,
+        writable: bool,
+        append: bool,
+    },
+    /// A file descriptor that has been closed. Its slot can be reused by `path_open`.
+    Closed,
 }
 
 enum WriteLock<'a> {
     StdOut(StdoutLock<'a>),
     Stderr(StderrLock<'a>),
     RegularFile(&'a mut Vec<u8>),
+    HostFile(&'a mut File),
 }
 
 /// Implementation of WASI syscalls
@@ -44,9 +103,27 @@ impl<'a> WasiDispatcher<'a> {
                 WasiFile::HostSystemFile,
                 WasiFile::HostSystemFile,
             ],
+            start_time: Instant::now(),
         }
     }
 
+    /// Give the app access to a host directory, which it will see at `guest_path`.
+    /// Preopened directories get file descriptors in the order they are added.
+    pub fn preopen_dir(&mut self, guest_path: &str, host_path: impl AsRef<Path>) -> io::Result<()> {
+        let host_path = fs::canonicalize(host_path)?;
+        if !host_path.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a directory", host_path.display()),
+            ));
+        }
+        self.files.push(WasiFile::PreopenedDir {
+            guest_path: guest_path.to_string(),
+            host_path,
+        });
+        Ok(())
+    }
+
     pub fn dispatch(
         &mut self,
         function_name: &str,
@@ -107,13 +184,80 @@ impl<'a> WasiDispatcher<'a> {
 
                 success_code
             }
-            "clock_res_get" => success_code, // this dummy implementation seems to be good enough for some functions
-            "clock_time_get" => success_code,
-            "fd_advise" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_allocate" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_close" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_datasync" => todo!("WASI {}({:?})", function_name, arguments),
+            "clock_res_get" => {
+                let clock_id = arguments[0].expect_i32().unwrap();
+                // Out param: clock resolution in nanoseconds
+                let ptr_resolution = arguments[1].expect_i32().unwrap() as usize;
+
+                if !(CLOCK_REALTIME..=CLOCK_THREAD_CPUTIME_ID).contains(&clock_id) {
+                    return Some(Value::I32(Errno::Inval as i32));
+                }
+                // Rust gives us nanoseconds. The host clock may be coarser, but there's no portable way to find out.
+                write_u64(memory, ptr_resolution, 1);
+
+                success_code
+            }
+            "clock_time_get" => {
+                let clock_id = arguments[0].expect_i32().unwrap();
+                // arguments[1] is the maximum lag the app will accept. We always give the most precise time we have.
+                // Out param: time in nanoseconds
+                let ptr_time = arguments[2].expect_i32().unwrap() as usize;
+
+                let nanos = match clock_id {
+                    CLOCK_REALTIME => SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_nanos(),
+                    // We don't measure CPU time, so the CPU time clocks are the same as the monotonic one
+                    CLOCK_MONOTONIC | CLOCK_PROCESS_CPUTIME_ID | CLOCK_THREAD_CPUTIME_ID => {
+                        self.start_time.elapsed().as_nanos()
+                    }
+                    _ => return Some(Value::I32(Errno::Inval as i32)),
+                };
+                write_u64(memory, ptr_time, nanos as u64);
+
+                success_code
+            }
+            "fd_advise" => {
+                // Advice about access patterns is optional, so we can ignore it
+                success_code
+            }
+            "fd_allocate" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                let offset = arguments[1].expect_i64().unwrap() as u64;
+                let len = arguments[2].expect_i64().unwrap() as u64;
+
+                let result = self.host_file(fd).and_then(|file| {
+                    // Only ever grows the file, like posix_fallocate
+                    let end = offset.checked_add(len).ok_or(Errno::Fbig)?;
+                    if file.metadata()?.len() < end {
+                        file.set_len(end)?;
+                    }
+                    Ok(())
+                });
+                errno_value(result)
+            }
+            "fd_close" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+
+                match self.files.get_mut(fd) {
+                    Some(WasiFile::Closed) | None => Some(Value::I32(Errno::Badf as i32)),
+                    Some(file) => {
+                        *file = WasiFile::Closed;
+                        success_code
+                    }
+                }
+            }
+            "fd_datasync" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                errno_value(
+                    self.host_file(fd)
+                        .and_then(|file| file.sync_data().map_err(Errno::from)),
+                )
+            }
             "fd_fdstat_get" => {
+                use WasiFile::*;
+
                 // (i32, i32) -> i32
 
                 // file descriptor
@@ -121,9 +265,29 @@ impl<'a> WasiDispatcher<'a> {
                 // ptr to a wasi_fdstat_t
                 let stat_mut_ptr = arguments[1].expect_i32().unwrap() as usize;
 
-                match fd {
-                    1 => {
-                        // Tell WASI that stdout is a tty (no seek or tell)
+                let (filetype, flags, rights_base, rights_inheriting) = match self.files.get(fd) {
+                    Some(PreopenedDir { .. } | HostDir { .. }) => {
+                        (FILETYPE_DIRECTORY, 0, RIGHTS_ALL, RIGHTS_ALL)
+                    }
+                    Some(HostFile {
+                        readable,
+                        writable,
+                        append,
+                        ..
+                    }) => {
+                        let mut rights = RIGHTS_ALL;
+                        if !readable {
+                            rights &= !RIGHTS_FD_READ;
+                        }
+                        if !writable {
+                            rights &= !RIGHTS_FD_WRITE;
+                        }
+                        let flags = if *append { FDFLAGS_APPEND } else { 0 };
+                        (FILETYPE_REGULAR_FILE, flags, rights, 0)
+                    }
+                    Some(Closed) | None => return Some(Value::I32(Errno::Badf as i32)),
+                    Some(ReadOnly(_) | WriteOnly(_) | ReadWrite(_) | HostSystemFile) => {
+                        // Tell WASI that stdio is a tty (no seek or tell)
                         // https://github.com/WebAssembly/wasi-libc/blob/659ff414560721b1660a19685110e484a081c3d4/libc-bottom-half/sources/isatty.c
                         // *Not* a tty if:
                         //     (statbuf.fs_filetype != __WASI_FILETYPE_CHARACTER_DEVICE ||
@@ -131,25 +295,111 @@ impl<'a> WasiDispatcher<'a> {
                         // So it's sufficient to set:
                         //     .fs_filetype = __WASI_FILETYPE_CHARACTER_DEVICE
                         //     .fs_rights_base = 0
+                        (FILETYPE_CHARACTER_DEVICE, 0, 0, 0)
+                    }
+                };
 
-                        const WASI_FILETYPE_CHARACTER_DEVICE: u8 = 2;
-                        memory[stat_mut_ptr] = WASI_FILETYPE_CHARACTER_DEVICE;
+                memory[stat_mut_ptr..][..8].fill(0);
+                memory[stat_mut_ptr] = filetype;
+                memory[stat_mut_ptr + 2..][..2].copy_from_slice(&flags.to_le_bytes());
+                write_u64(memory, stat_mut_ptr + 8, rights_base);
+                write_u64(memory, stat_mut_ptr + 16, rights_inheriting);
 
-                        for b in memory[stat_mut_ptr + 1..stat_mut_ptr + 24].iter_mut() {
-                            *b = 0;
+                success_code
+            }
+            "fd_fdstat_set_flags" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                let fdflags = arguments[1].expect_i32().unwrap() as u16;
+
+                // The host file was opened with its flags already, so they can't change afterwards
+                let result = match self.files.get(fd) {
+                    Some(WasiFile::HostFile { append, .. }) => {
+                        let current = if *append { FDFLAGS_APPEND } else { 0 };
+                        if fdflags == current {
+                            Ok(())
+                        } else {
+                            Err(Errno::Notsup)
                         }
                     }
-                    _ => todo!("WASI {}({:?})", function_name, arguments),
-                }
+                    Some(WasiFile::Closed) | None => Err(Errno::Badf),
+                    Some(_) if fdflags == 0 => Ok(()),
+                    Some(_) => Err(Errno::Notsup),
+                };
+                errno_value(result)
+            }
+            "fd_fdstat_set_rights" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                let rights = arguments[1].expect_i64().unwrap() as u64;
+                // arguments[2] is the rights for files opened from this one. We don't restrict those.
 
-                success_code
+                // Rights can only be taken away. Reading and writing are the only ones we keep track of.
+                let result = match self.files.get_mut(fd) {
+                    Some(WasiFile::HostFile {
+                        readable, writable, ..
+                    }) => {
+                        let wants_read = rights & RIGHTS_FD_READ != 0;
+                        let wants_write = rights & RIGHTS_FD_WRITE != 0;
+                        if (wants_read && !*readable) || (wants_write && !*writable) {
+                            Err(Errno::Notcapable)
+                        } else {
+                            *readable = wants_read;
+                            *writable = wants_write;
+                            Ok(())
+                        }
+                    }
+                    Some(WasiFile::Closed) | None => Err(Errno::Badf),
+                    Some(_) => Ok(()),
+                };
+                errno_value(result)
+            }
+            "fd_filestat_get" => errno_value(self.fd_filestat_get(arguments, memory)),
+            "fd_filestat_set_size" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                let size = arguments[1].expect_i64().unwrap() as u64;
+                errno_value(
+                    self.host_file(fd)
+                        .and_then(|file| file.set_len(size).map_err(Errno::from)),
+                )
+            }
+            "fd_filestat_set_times" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                let times = file_times(&arguments[1..]);
+                errno_value(
+                    self.host_file(fd)
+                        .and_then(|file| Ok(file.set_times(times?)?)),
+                )
+            }
+            "fd_pread" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                // Array of IO vectors
+                let ptr_iovs = arguments[1].expect_i32().unwrap() as usize;
+                // Length of array
+                let iovs_len = arguments[2].expect_i32().unwrap() as usize;
+                // Where in the file to read from. The file's own offset stays as it was.
+                let offset = arguments[3].expect_i64().unwrap() as u64;
+                // Out param: number of bytes read
+                let ptr_nread = arguments[4].expect_i32().unwrap() as usize;
+
+                let result = self.positioned_file(fd, false).and_then(|file| {
+                    let n_read = at_offset(file, offset, |file| {
+                        let mut n_read = 0;
+                        for i in 0..iovs_len {
+                            let ptr_iov = ptr_iovs + 8 * i;
+                            let iov_base = read_u32(memory, ptr_iov) as usize;
+                            let iov_len = read_u32(memory, ptr_iov + 4) as usize;
+                            let n = file.read(&mut memory[iov_base..][..iov_len])?;
+                            n_read += n;
+                            if n < iov_len {
+                                break;
+                            }
+                        }
+                        Ok(n_read)
+                    })?;
+                    write_u32(memory, ptr_nread, n_read as u32);
+                    Ok(())
+                });
+                errno_value(result)
             }
-            "fd_fdstat_set_flags" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_fdstat_set_rights" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_filestat_get" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_filestat_set_size" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_filestat_set_times" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_pread" => todo!("WASI {}({:?})", function_name, arguments),
             "fd_prestat_get" => {
                 // The preopened file descriptor to query
                 let fd = arguments[0].expect_i32().unwrap() as usize;
@@ -157,19 +407,60 @@ impl<'a> WasiDispatcher<'a> {
                 //  preopen type: 4 bytes, where 0=dir is the only one supported, it seems
                 //  preopen name length: 4 bytes
                 let ptr_buf = arguments[1].expect_i32().unwrap() as usize;
-                memory[ptr_buf..][..8].copy_from_slice(&0u64.to_le_bytes());
-                if fd < self.files.len() {
-                    success_code
-                } else {
-                    println!("WASI warning: file descriptor {fd} does not exist");
-                    Some(Value::I32(Errno::Badf as i32))
+
+                // WASI libc looks for preopened directories by calling this on every fd from 3 up, until it gets `Badf`
+                match self.files.get(fd) {
+                    Some(WasiFile::PreopenedDir { guest_path, .. }) => {
+                        write_u32(memory, ptr_buf, 0);
+                        write_u32(memory, ptr_buf + 4, guest_path.len() as u32);
+                        success_code
+                    }
+                    _ => Some(Value::I32(Errno::Badf as i32)),
                 }
             }
             "fd_prestat_dir_name" => {
-                // We're not giving names to any of our files so just return success
-                success_code
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                // Where to write the name. The app gets the length from `fd_prestat_get`.
+                let ptr_path = arguments[1].expect_i32().unwrap() as usize;
+                let path_len = arguments[2].expect_i32().unwrap() as usize;
+
+                match self.files.get(fd) {
+                    Some(WasiFile::PreopenedDir { guest_path, .. }) => {
+                        let len = path_len.min(guest_path.len());
+                        memory[ptr_path..][..len].copy_from_slice(&guest_path.as_bytes()[..len]);
+                        success_code
+                    }
+                    _ => Some(Value::I32(Errno::Badf as i32)),
+                }
+            }
+            "fd_pwrite" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                // Array of IO vectors
+                let ptr_iovs = arguments[1].expect_i32().unwrap() as usize;
+                // Length of array
+                let iovs_len = arguments[2].expect_i32().unwrap() as usize;
+                // Where in the file to write to. The file's own offset stays as it was.
+                let offset = arguments[3].expect_i64().unwrap() as u64;
+                // Out param: number of bytes written
+                let ptr_nwritten = arguments[4].expect_i32().unwrap() as usize;
+
+                let result = self.positioned_file(fd, true).and_then(|file| {
+                    let n_written = at_offset(file, offset, |file| {
+                        let mut n_written = 0;
+                        for i in 0..iovs_len {
+                            let ptr_iov = ptr_iovs + 8 * i;
+                            let iov_base = read_u32(memory, ptr_iov) as usize;
+                            let iov_len = read_u32(memory, ptr_iov + 4) as usize;
+                            file.write_all(&memory[iov_base..][..iov_len])?;
+                            n_written += iov_len;
+                        }
+                        Ok(n_written)
+                    })?;
+                    write_u32(memory, ptr_nwritten, n_written as u32);
+                    Ok(())
+                });
+                errno_value(result)
             }
-            "fd_pwrite" => todo!("WASI {}({:?})", function_name, arguments),
             "fd_read" => {
                 use WasiFile::*;
 
@@ -189,11 +480,12 @@ impl<'a> WasiDispatcher<'a> {
                 // };
 
                 let mut n_read: usize = 0;
-                match self.files.get(fd) {
+                match self.files.get_mut(fd) {
                     Some(ReadOnly(content) | ReadWrite(content)) => {
-                        for _ in 0..iovs_len {
-                            let iov_base = read_u32(memory, ptr_iovs) as usize;
-                            let iov_len = read_i32(memory, ptr_iovs + 4) as usize;
+                        for i in 0..iovs_len {
+                            let ptr_iov = ptr_iovs + (8 * i as usize);
+                            let iov_base = read_u32(memory, ptr_iov) as usize;
+                            let iov_len = read_i32(memory, ptr_iov + 4) as usize;
                             let remaining = content.len() - n_read;
                             let len = remaining.min(iov_len);
                             if len == 0 {
@@ -205,9 +497,10 @@ impl<'a> WasiDispatcher<'a> {
                     }
                     Some(HostSystemFile) if fd == 0 => {
                         let mut stdin = io::stdin();
-                        for _ in 0..iovs_len {
-                            let iov_base = read_u32(memory, ptr_iovs) as usize;
-                            let iov_len = read_i32(memory, ptr_iovs + 4) as usize;
+                        for i in 0..iovs_len {
+                            let ptr_iov = ptr_iovs + (8 * i as usize);
+                            let iov_base = read_u32(memory, ptr_iov) as usize;
+                            let iov_len = read_i32(memory, ptr_iov + 4) as usize;
                             match stdin.read(&mut memory[iov_base..][..iov_len]) {
                                 Ok(n) => {
                                     n_read += n;
@@ -218,17 +511,71 @@ impl<'a> WasiDispatcher<'a> {
                             }
                         }
                     }
+                    Some(HostFile {
+                        file,
+                        readable: true,
+                        ..
+                    }) => {
+                        for i in 0..iovs_len {
+                            let ptr_iov = ptr_iovs + (8 * i as usize);
+                            let iov_base = read_u32(memory, ptr_iov) as usize;
+                            let iov_len = read_u32(memory, ptr_iov + 4) as usize;
+                            match file.read(&mut memory[iov_base..][..iov_len]) {
+                                Ok(n) => {
+                                    n_read += n;
+                                    if n < iov_len {
+                                        break;
+                                    }
+                                }
+                                Err(e) => return Some(Value::I32(Errno::from(e) as i32)),
+                            }
+                        }
+                    }
+                    Some(PreopenedDir { .. } | HostDir { .. }) => {
+                        return Some(Value::I32(Errno::Isdir as i32))
+                    }
                     _ => return Some(Value::I32(Errno::Badf as i32)),
                 };
 
                 memory[ptr_nread..][..4].copy_from_slice(&(n_read as u32).to_le_bytes());
                 success_code
             }
-            "fd_readdir" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_renumber" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_seek" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_sync" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_tell" => todo!("WASI {}({:?})", function_name, arguments),
+            "fd_readdir" => errno_value(self.fd_readdir(arguments, memory)),
+            "fd_renumber" => {
+                let from = arguments[0].expect_i32().unwrap() as usize;
+                let to = arguments[1].expect_i32().unwrap() as usize;
+
+                // Like dup2, this closes whatever was open at `to`, but both have to be open already
+                let is_open =
+                    |fd: usize| !matches!(self.files.get(fd), Some(WasiFile::Closed) | None);
+                if !is_open(from) || !is_open(to) {
+                    return Some(Value::I32(Errno::Badf as i32));
+                }
+                if from != to {
+                    self.files[to] = std::mem::replace(&mut self.files[from], WasiFile::Closed);
+                }
+                success_code
+            }
+            "fd_seek" => errno_value(self.fd_seek(arguments, memory)),
+            "fd_sync" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                errno_value(
+                    self.host_file(fd)
+                        .and_then(|file| file.sync_all().map_err(Errno::from)),
+                )
+            }
+            "fd_tell" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                // Out param: the current offset
+                let ptr_offset = arguments[1].expect_i32().unwrap() as usize;
+
+                let result = self.seekable_file(fd).and_then(|file| {
+                    let offset = file.stream_position()?;
+                    write_u64(memory, ptr_offset, offset);
+                    Ok(())
+                });
+                errno_value(result)
+            }
             "fd_write" => {
                 use WasiFile::*;
 
@@ -252,6 +599,14 @@ impl<'a> WasiDispatcher<'a> {
                     Some(WriteOnly(content) | ReadWrite(content)) => {
                         WriteLock::RegularFile(content)
                     }
+                    Some(HostFile {
+                        file,
+                        writable: true,
+                        ..
+                    }) => WriteLock::HostFile(file),
+                    Some(PreopenedDir { .. } | HostDir { .. }) => {
+                        return Some(Value::I32(Errno::Isdir as i32))
+                    }
                     _ => return Some(Value::I32(Errno::Badf as i32)),
                 };
 
@@ -280,6 +635,7 @@ impl<'a> WasiDispatcher<'a> {
                         WriteLock::StdOut(stdout) => stdout.write_all(bytes),
                         WriteLock::Stderr(stderr) => stderr.write_all(bytes),
                         WriteLock::RegularFile(content) => content.write_all(bytes),
+                        WriteLock::HostFile(file) => file.write_all(bytes),
                     };
                     if write_result.is_err() {
                         break;
@@ -300,23 +656,103 @@ impl<'a> WasiDispatcher<'a> {
                     Err(_) => Some(Value::I32(Errno::Io as i32)),
                 }
             }
-            "path_create_directory" => todo!("WASI {}({:?})", function_name, arguments),
-            "path_filestat_get" => todo!("WASI {}({:?})", function_name, arguments),
-            "path_filestat_set_times" => todo!("WASI {}({:?})", function_name, arguments),
-            "path_link" => todo!("WASI {}({:?})", function_name, arguments),
-            "path_open" => todo!("WASI {}({:?})", function_name, arguments),
-            "path_readlink" => todo!("WASI {}({:?})", function_name, arguments),
-            "path_remove_directory" => todo!("WASI {}({:?})", function_name, arguments),
-            "path_rename" => todo!("WASI {}({:?})", function_name, arguments),
-            "path_symlink" => todo!("WASI {}({:?})", function_name, arguments),
-            "path_unlink_file" => todo!("WASI {}({:?})", function_name, arguments),
-            "poll_oneoff" => todo!("WASI {}({:?})", function_name, arguments),
+            "path_create_directory" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                let ptr_path = arguments[1].expect_i32().unwrap() as usize;
+                let path_len = arguments[2].expect_i32().unwrap() as usize;
+
+                let result = self
+                    .resolve_path(fd, &memory[ptr_path..][..path_len])
+                    .and_then(|(host_path, _)| Ok(fs::create_dir(host_path)?));
+                errno_value(result)
+            }
+            "path_filestat_get" => errno_value(self.path_filestat_get(arguments, memory)),
+            "path_filestat_set_times" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                // arguments[1] is the lookup flags. Like `path_open`, we always follow symlinks.
+                let ptr_path = arguments[2].expect_i32().unwrap() as usize;
+                let path_len = arguments[3].expect_i32().unwrap() as usize;
+                let times = file_times(&arguments[4..]);
+
+                let result = self
+                    .resolve_path(fd, &memory[ptr_path..][..path_len])
+                    .and_then(|(host_path, _)| {
+                        let times = times?;
+                        let file = File::open(host_path)?;
+                        Ok(file.set_times(times)?)
+                    });
+                errno_value(result)
+            }
+            // Links could point outside of the preopened directories, so we don't support them
+            "path_link" | "path_readlink" | "path_symlink" => {
+                Some(Value::I32(Errno::Notsup as i32))
+            }
+            "path_open" => errno_value(self.path_open(arguments, memory)),
+            "path_remove_directory" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                let ptr_path = arguments[1].expect_i32().unwrap() as usize;
+                let path_len = arguments[2].expect_i32().unwrap() as usize;
+
+                let result = self
+                    .resolve_path(fd, &memory[ptr_path..][..path_len])
+                    .and_then(|(host_path, sandbox_root)| {
+                        if host_path == sandbox_root {
+                            return Err(Errno::Notcapable);
+                        }
+                        if !fs::symlink_metadata(&host_path)?.is_dir() {
+                            return Err(Errno::Notdir);
+                        }
+                        if fs::read_dir(&host_path)?.next().is_some() {
+                            return Err(Errno::Notempty);
+                        }
+                        Ok(fs::remove_dir(host_path)?)
+                    });
+                errno_value(result)
+            }
+            "path_rename" => {
+                let old_fd = arguments[0].expect_i32().unwrap() as usize;
+                let ptr_old_path = arguments[1].expect_i32().unwrap() as usize;
+                let old_path_len = arguments[2].expect_i32().unwrap() as usize;
+                let new_fd = arguments[3].expect_i32().unwrap() as usize;
+                let ptr_new_path = arguments[4].expect_i32().unwrap() as usize;
+                let new_path_len = arguments[5].expect_i32().unwrap() as usize;
+
+                let result = self
+                    .resolve_path(old_fd, &memory[ptr_old_path..][..old_path_len])
+                    .and_then(|(old_path, _)| {
+                        let (new_path, _) =
+                            self.resolve_path(new_fd, &memory[ptr_new_path..][..new_path_len])?;
+                        Ok(fs::rename(old_path, new_path)?)
+                    });
+                errno_value(result)
+            }
+            "path_unlink_file" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                let ptr_path = arguments[1].expect_i32().unwrap() as usize;
+                let path_len = arguments[2].expect_i32().unwrap() as usize;
+
+                let result = self
+                    .resolve_path(fd, &memory[ptr_path..][..path_len])
+                    .and_then(|(host_path, _)| {
+                        if fs::symlink_metadata(&host_path)?.is_dir() {
+                            return Err(Errno::Isdir);
+                        }
+                        Ok(fs::remove_file(host_path)?)
+                    });
+                errno_value(result)
+            }
+            // We have no event loop to wait on, and Roc apps don't need one
+            "poll_oneoff" => Some(Value::I32(Errno::Nosys as i32)),
             "proc_exit" => {
                 let exit_code = arguments[0].expect_i32().unwrap();
                 exit(exit_code);
             }
-            "proc_raise" => todo!("WASI {}({:?})", function_name, arguments),
-            "sched_yield" => todo!("WASI {}({:?})", function_name, arguments),
+            // Signals are not part of WASI anymore, and the host's are not the app's business
+            "proc_raise" => Some(Value::I32(Errno::Nosys as i32)),
+            "sched_yield" => {
+                std::thread::yield_now();
+                success_code
+            }
             "random_get" => {
                 // A pointer to a buffer where the random bytes will be written
                 let ptr_buf = arguments[0].expect_i32().unwrap() as usize;
@@ -327,12 +763,413 @@ impl<'a> WasiDispatcher<'a> {
                 }
                 success_code
             }
-            "sock_recv" => todo!("WASI {}({:?})", function_name, arguments),
-            "sock_send" => todo!("WASI {}({:?})", function_name, arguments),
-            "sock_shutdown" => todo!("WASI {}({:?})", function_name, arguments),
+            // There's no way to open a socket, so there are none to use
+            "sock_recv" | "sock_send" | "sock_shutdown" => Some(Value::I32(Errno::Nosys as i32)),
             _ => panic!("Unknown WASI function {function_name}({arguments:?})"),
         }
     }
+
+    fn fd_filestat_get(&mut self, arguments: &[Value], memory: &mut [u8]) -> Result<(), Errno> {
+        use WasiFile::*;
+
+        let fd = arguments[0].expect_i32().unwrap() as usize;
+        // Out param: a filestat struct
+        let ptr_filestat = arguments[1].expect_i32().unwrap() as usize;
+
+        let metadata = match self.files.get(fd) {
+            Some(HostFile { file, .. }) => file.metadata()?,
+            Some(PreopenedDir { host_path, .. } | HostDir { host_path, .. }) => {
+                fs::metadata(host_path)?
+            }
+            Some(Closed) | None => return Err(Errno::Badf),
+            Some(ReadOnly(_) | WriteOnly(_) | ReadWrite(_) | HostSystemFile) => {
+                // Same as `fd_fdstat_get`: these look like a tty, with nothing else to say about them
+                memory[ptr_filestat..][..FILESTAT_SIZE].fill(0);
+                memory[ptr_filestat + 16] = FILETYPE_CHARACTER_DEVICE;
+                return Ok(());
+            }
+        };
+        write_filestat(memory, ptr_filestat, &metadata);
+        Ok(())
+    }
+
+    fn path_filestat_get(&mut self, arguments: &[Value], memory: &mut [u8]) -> Result<(), Errno> {
+        let fd = arguments[0].expect_i32().unwrap() as usize;
+        let lookup_flags = arguments[1].expect_i32().unwrap();
+        let ptr_path = arguments[2].expect_i32().unwrap() as usize;
+        let path_len = arguments[3].expect_i32().unwrap() as usize;
+        // Out param: a filestat struct
+        let ptr_filestat = arguments[4].expect_i32().unwrap() as usize;
+
+        let (host_path, _) = self.resolve_path(fd, &memory[ptr_path..][..path_len])?;
+        let metadata = if lookup_flags & LOOKUPFLAGS_SYMLINK_FOLLOW != 0 {
+            fs::metadata(host_path)?
+        } else {
+            fs::symlink_metadata(host_path)?
+        };
+        write_filestat(memory, ptr_filestat, &metadata);
+        Ok(())
+    }
+
+    fn path_open(&mut self, arguments: &[Value], memory: &mut [u8]) -> Result<(), Errno> {
+        // The directory that the path is relative to
+        let fd = arguments[0].expect_i32().unwrap() as usize;
+        // arguments[1] is the lookup flags. We always follow symlinks, as long as they stay in the sandbox.
+        let ptr_path = arguments[2].expect_i32().unwrap() as usize;
+        let path_len = arguments[3].expect_i32().unwrap() as usize;
+        let oflags = arguments[4].expect_i32().unwrap();
+        let rights = arguments[5].expect_i64().unwrap() as u64;
+        // arguments[6] is the rights for files opened from this one. We don't restrict those.
+        let fdflags = arguments[7].expect_i32().unwrap() as u16;
+        // Out param: the new file descriptor
+        let ptr_fd = arguments[8].expect_i32().unwrap() as usize;
+
+        let (host_path, sandbox_root) = self.resolve_path(fd, &memory[ptr_path..][..path_len])?;
+        let is_dir = host_path.is_dir();
+        let wasi_file = if oflags & OFLAGS_DIRECTORY != 0 || is_dir {
+            if !is_dir {
+                return Err(if host_path.exists() {
+                    Errno::Notdir
+                } else {
+                    Errno::Noent
+                });
+            }
+            if oflags & OFLAGS_CREAT != 0 && oflags & OFLAGS_EXCL != 0 {
+                return Err(Errno::Exist);
+            }
+            if oflags & OFLAGS_TRUNC != 0 || rights & RIGHTS_FD_WRITE != 0 {
+                return Err(Errno::Isdir);
+            }
+            WasiFile::HostDir {
+                host_path: fs::canonicalize(host_path)?,
+                sandbox_root,
+            }
+        } else {
+            let writable = rights & RIGHTS_FD_WRITE != 0;
+            let readable = rights & RIGHTS_FD_READ != 0 || !writable;
+            let append = writable && fdflags & FDFLAGS_APPEND != 0;
+            let file = OpenOptions::new()
+                .read(readable)
+                .write(writable)
+                .append(append)
+                .create(oflags & OFLAGS_CREAT != 0)
+                .create_new(oflags & OFLAGS_CREAT != 0 && oflags & OFLAGS_EXCL != 0)
+                .truncate(oflags & OFLAGS_TRUNC != 0)
+                .open(host_path)?;
+            WasiFile::HostFile {
+                file,
+                readable,
+                writable,
+                append,
+            }
+        };
+
+        let new_fd = self.add_file(wasi_file);
+        write_u32(memory, ptr_fd, new_fd);
+        Ok(())
+    }
+
+    fn fd_readdir(&mut self, arguments: &[Value], memory: &mut [u8]) -> Result<(), Errno> {
+        let fd = arguments[0].expect_i32().unwrap() as usize;
+        // Buffer to fill with dirent structs, each followed by its name
+        let ptr_buf = arguments[1].expect_i32().unwrap() as usize;
+        let buf_len = arguments[2].expect_i32().unwrap() as usize;
+        // Index of the first entry to return. We give each entry the next one's index as its `d_next`.
+        let cookie = arguments[3].expect_i64().unwrap() as usize;
+        // Out param: number of bytes written to the buffer
+        let ptr_bufused = arguments[4].expect_i32().unwrap() as usize;
+
+        let (host_path, _) = self.dir_paths(fd)?;
+
+        let dir_metadata = fs::metadata(host_path)?;
+        let mut entries = vec![
+            (".".to_string(), dir_metadata.clone()),
+            ("..".to_string(), dir_metadata),
+        ];
+        let mut children = vec![];
+        for entry in fs::read_dir(host_path)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            children.push((name, entry.metadata()?));
+        }
+        // Sort so that the cookies stay valid between calls
+        children.sort_by(|(a, _), (b, _)| a.cmp(b));
+        entries.extend(children);
+
+        // If the last entry doesn't fit, the app can tell because we filled the whole buffer.
+        // It will call again with that entry's cookie and a bigger buffer.
+        let mut bytes = vec![];
+        for (index, (name, metadata)) in entries.iter().enumerate().skip(cookie) {
+            if bytes.len() >= buf_len {
+                break;
+            }
+            let (_, ino, _) = host_ids(metadata);
+            bytes.extend_from_slice(&(index as u64 + 1).to_le_bytes());
+            bytes.extend_from_slice(&ino.to_le_bytes());
+            bytes.extend_from_slice(&(name.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&[filetype(metadata), 0, 0, 0]);
+            bytes.extend_from_slice(name.as_bytes());
+        }
+
+        let n_written = bytes.len().min(buf_len);
+        memory[ptr_buf..][..n_written].copy_from_slice(&bytes[..n_written]);
+        write_u32(memory, ptr_bufused, n_written as u32);
+        Ok(())
+    }
+
+    fn fd_seek(&mut self, arguments: &[Value], memory: &mut [u8]) -> Result<(), Errno> {
+        let fd = arguments[0].expect_i32().unwrap() as usize;
+        let offset = arguments[1].expect_i64().unwrap();
+        let whence = arguments[2].expect_i32().unwrap();
+        // Out param: the new offset, relative to the start of the file
+        let ptr_new_offset = arguments[3].expect_i32().unwrap() as usize;
+
+        let seek_from = match whence {
+            0 => SeekFrom::Start(u64::try_from(offset).map_err(|_| Errno::Inval)?),
+            1 => SeekFrom::Current(offset),
+            2 => SeekFrom::End(offset),
+            _ => return Err(Errno::Inval),
+        };
+        let new_offset = self.seekable_file(fd)?.seek(seek_from)?;
+        write_u64(memory, ptr_new_offset, new_offset);
+        Ok(())
+    }
+
+    /// Put a newly opened file in the lowest free file descriptor
+    fn add_file(&mut self, wasi_file: WasiFile) -> u32 {
+        let first_free = self
+            .files
+            .iter()
+            .enumerate()
+            .skip(3)
+            .find(|(_, f)| matches!(f, WasiFile::Closed));
+        match first_free {
+            Some((fd, _)) => {
+                self.files[fd] = wasi_file;
+                fd as u32
+            }
+            None => {
+                self.files.push(wasi_file);
+                self.files.len() as u32 - 1
+            }
+        }
+    }
+
+    fn host_file(&mut self, fd: usize) -> Result<&mut File, Errno> {
+        match self.files.get_mut(fd) {
+            Some(WasiFile::HostFile { file, .. }) => Ok(file),
+            Some(WasiFile::PreopenedDir { .. } | WasiFile::HostDir { .. }) => Err(Errno::Isdir),
+            Some(WasiFile::Closed) | None => Err(Errno::Badf),
+            Some(_) => Err(Errno::Inval),
+        }
+    }
+
+    /// A host file for `fd_pread` (`for_writing` false) or `fd_pwrite` (`for_writing` true)
+    fn positioned_file(&mut self, fd: usize, for_writing: bool) -> Result<&mut File, Errno> {
+        match self.files.get_mut(fd) {
+            Some(WasiFile::HostFile {
+                file,
+                readable,
+                writable,
+                ..
+            }) => {
+                if (for_writing && *writable) || (!for_writing && *readable) {
+                    Ok(file)
+                } else {
+                    Err(Errno::Badf)
+                }
+            }
+            Some(WasiFile::PreopenedDir { .. } | WasiFile::HostDir { .. }) => Err(Errno::Isdir),
+            Some(WasiFile::Closed) | None => Err(Errno::Badf),
+            // stdio and in-memory files are streams
+            Some(_) => Err(Errno::Spipe),
+        }
+    }
+
+    fn seekable_file(&mut self, fd: usize) -> Result<&mut File, Errno> {
+        match self.host_file(fd) {
+            // stdio and in-memory files are streams
+            Err(Errno::Inval) => Err(Errno::Spipe),
+            result => result,
+        }
+    }
+
+    /// The host path of a directory file descriptor, and the preopened directory it's inside
+    fn dir_paths(&self, fd: usize) -> Result<(&Path, &Path), Errno> {
+        match self.files.get(fd) {
+            Some(WasiFile::PreopenedDir { host_path, .. }) => Ok((host_path, host_path)),
+            Some(WasiFile::HostDir {
+                host_path,
+                sandbox_root,
+            }) => Ok((host_path, sandbox_root)),
+            Some(WasiFile::Closed) | None => Err(Errno::Badf),
+            Some(_) => Err(Errno::Notdir),
+        }
+    }
+
+    /// Find the host path for a path from the app, relative to one of its directory file descriptors.
+    /// Returns the host path and the preopened directory it's inside.
+    /// Any path that leads out of the preopened directory is rejected, whether by `..` or by a symlink,
+    /// including a symlink that doesn't lead anywhere yet (since creating a file would follow it).
+    fn resolve_path(&self, dir_fd: usize, guest_path: &[u8]) -> Result<(PathBuf, PathBuf), Errno> {
+        let guest_path = std::str::from_utf8(guest_path).map_err(|_| Errno::Ilseq)?;
+        let (dir, sandbox_root) = self.dir_paths(dir_fd)?;
+
+        let mut host_path = dir.to_path_buf();
+        for component in Path::new(guest_path).components() {
+            match component {
+                Component::Normal(name) => host_path.push(name),
+                Component::CurDir => {}
+                Component::ParentDir if host_path != sandbox_root => {
+                    host_path.pop();
+                }
+                Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                    return Err(Errno::Notcapable);
+                }
+            }
+        }
+
+        // The path may not exist yet, so `fs::canonicalize` can't tell us where it really leads.
+        // Follow its symlinks by hand instead, checking that every one of them stays in the sandbox.
+        let relative = host_path
+            .strip_prefix(sandbox_root)
+            .map_err(|_| Errno::Notcapable)?;
+        let mut real_path = sandbox_root.to_path_buf();
+        let mut pending: Vec<OsString> = relative
+            .iter()
+            .rev()
+            .map(|name| name.to_os_string())
+            .collect();
+        let mut symlinks_followed = 0;
+
+        while let Some(name) = pending.pop() {
+            if name == ".." {
+                if real_path == sandbox_root {
+                    return Err(Errno::Notcapable);
+                }
+                real_path.pop();
+                continue;
+            } else if name == "." {
+                continue;
+            }
+
+            real_path.push(&name);
+
+            // Everything before `name` is already resolved, so this is the symlink itself (if it is one)
+            if let Ok(target) = fs::read_link(&real_path) {
+                symlinks_followed += 1;
+                if symlinks_followed > MAX_SYMLINKS_FOLLOWED {
+                    return Err(Errno::Loop);
+                }
+
+                real_path.pop();
+                let target = if target.is_absolute() {
+                    real_path = sandbox_root.to_path_buf();
+                    target
+                        .strip_prefix(sandbox_root)
+                        .map_err(|_| Errno::Notcapable)?
+                        .to_path_buf()
+                } else {
+                    target
+                };
+                pending.extend(target.iter().rev().map(|name| name.to_os_string()));
+            }
+        }
+
+        Ok((host_path, sandbox_root.to_path_buf()))
+    }
+}
+
+/// Run `f` on the file at the given offset, then put the file's own offset back where it was
+fn at_offset<T>(
+    file: &mut File,
+    offset: u64,
+    f: impl FnOnce(&mut File) -> io::Result<T>,
+) -> Result<T, Errno> {
+    let original_offset = file.stream_position()?;
+    file.seek(SeekFrom::Start(offset))?;
+    let result = f(file);
+    file.seek(SeekFrom::Start(original_offset))?;
+    Ok(result?)
+}
+
+/// The times to set from the (atim, mtim, fst_flags) arguments of the `*_filestat_set_times` functions
+fn file_times(arguments: &[Value]) -> Result<FileTimes, Errno> {
+    let atim = arguments[0].expect_i64().unwrap() as u64;
+    let mtim = arguments[1].expect_i64().unwrap() as u64;
+    let fst_flags = arguments[2].expect_i32().unwrap();
+
+    let time = |nanos: u64, flag: i32, now_flag: i32| match (fst_flags & flag, fst_flags & now_flag)
+    {
+        (0, 0) => Ok(None),
+        (_, 0) => Ok(Some(UNIX_EPOCH + std::time::Duration::from_nanos(nanos))),
+        (0, _) => Ok(Some(SystemTime::now())),
+        _ => Err(Errno::Inval),
+    };
+
+    let mut times = FileTimes::new();
+    if let Some(accessed) = time(atim, FSTFLAGS_ATIM, FSTFLAGS_ATIM_NOW)? {
+        times = times.set_accessed(accessed);
+    }
+    if let Some(modified) = time(mtim, FSTFLAGS_MTIM, FSTFLAGS_MTIM_NOW)? {
+        times = times.set_modified(modified);
+    }
+    Ok(times)
+}
+
+fn errno_value(result: Result<(), Errno>) -> Option<Value> {
+    let errno = match result {
+        Ok(()) => Errno::Success,
+        Err(errno) => errno,
+    };
+    Some(Value::I32(errno as i32))
+}
+
+fn filetype(metadata: &Metadata) -> u8 {
+    let file_type = metadata.file_type();
+    if file_type.is_dir() {
+        FILETYPE_DIRECTORY
+    } else if file_type.is_file() {
+        FILETYPE_REGULAR_FILE
+    } else if file_type.is_symlink() {
+        FILETYPE_SYMBOLIC_LINK
+    } else {
+        FILETYPE_UNKNOWN
+    }
+}
+
+/// Device ID, inode and number of hard links
+#[cfg(unix)]
+fn host_ids(metadata: &Metadata) -> (u64, u64, u64) {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.dev(), metadata.ino(), metadata.nlink())
+}
+
+/// Device ID, inode and number of hard links
+#[cfg(not(unix))]
+fn host_ids(_metadata: &Metadata) -> (u64, u64, u64) {
+    (0, 0, 1)
+}
+
+fn timestamp(time: io::Result<SystemTime>) -> u64 {
+    time.ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_nanos() as u64)
+}
+
+fn write_filestat(memory: &mut [u8], addr: usize, metadata: &Metadata) {
+    let (dev, ino, nlink) = host_ids(metadata);
+    let modified = timestamp(metadata.modified());
+    write_u64(memory, addr, dev);
+    write_u64(memory, addr + 8, ino);
+    write_u64(memory, addr + 16, 0);
+    memory[addr + 16] = filetype(metadata);
+    write_u64(memory, addr + 24, nlink);
+    write_u64(memory, addr + 32, metadata.len());
+    write_u64(memory, addr + 40, timestamp(metadata.accessed()));
+    write_u64(memory, addr + 48, modified);
+    // Status change time isn't available on all hosts, and it's not much different from modification time
+    write_u64(memory, addr + 56, modified);
 }
 
 fn read_u32(memory: &[u8], addr: usize) -> u32 {
@@ -355,12 +1192,16 @@ fn write_i32(memory: &mut [u8], addr: usize, value: i32) {
     memory[addr..][..4].copy_from_slice(&value.to_le_bytes());
 }
 
+fn write_u64(memory: &mut [u8], addr: usize, value: u64) {
+    memory[addr..][..8].copy_from_slice(&value.to_le_bytes());
+}
+
 /// Error codes returned by functions.
 /// Not all of these error codes are returned by the functions provided by this
 /// API; some are used in higher-level library layers, and others are provided
 /// merely for alignment with POSIX.
 #[repr(u8)]
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Errno {
     /// No error occurred. System call completed successfully.
     Success,
@@ -517,3 +1358,19 @@ pub enum Errno {
     /// Extension: Capabilities insufficient.
     Notcapable,
 }
+
+impl From<io::Error> for Errno {
+    fn from(error: io::Error) -> Self {
+        use io::ErrorKind::*;
+        match error.kind() {
+            NotFound => Errno::Noent,
+            PermissionDenied => Errno::Access,
+            AlreadyExists => Errno::Exist,
+            InvalidInput => Errno::Inval,
+            WouldBlock => Errno::Again,
+            Interrupted => Errno::Intr,
+            Unsupported => Errno::Notsup,
+            _ => Errno::Io,
+        }
+    }
+}
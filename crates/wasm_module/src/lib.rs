@@ -3,6 +3,7 @@ pub mod opcodes;
 pub mod parse;
 pub mod sections;
 pub mod serialize;
+pub mod validate;
 
 use std::iter::repeat;
 
@@ -22,6 +23,7 @@ use self::sections::{
     TableSection, TypeSection,
 };
 pub use self::serialize::{SerialBuffer, Serialize};
+pub use self::validate::ValidationError;
 
 pub const STACK_POINTER_GLOBAL_ID: u32 = 0;
 pub const FRAME_ALIGNMENT_BYTES: i32 = 16;
@@ -392,6 +394,13 @@ impl<'a> WasmModule<'a> {
         self.code.bytes = buffer;
     }
 
+    /// Type-check every function body against its signature, and check that all the
+    /// blocks are balanced and all the indices they use are in bounds.
+    /// Useful for catching code generation and linking bugs in debug builds and tests.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        validate::validate_module(self)
+    }
+
     fn trace_live_functions<I: Iterator<Item = u32>>(
         &self,
         arena: &'a Bump,
@@ -198,7 +198,8 @@ pub fn update_section_size<T: SerialBuffer>(buffer: &mut T, header_indices: Sect
 #[derive(PartialEq, Eq, Debug)]
 pub struct Signature<'a> {
     pub param_types: Vec<'a, ValueType>,
-    pub ret_type: Option<ValueType>,
+    /// More than one return type requires the multi-value proposal
+    pub ret_types: Vec<'a, ValueType>,
 }
 
 impl Signature<'_> {
@@ -209,18 +210,19 @@ impl<'a> Serialize for Signature<'a> {
     fn serialize<T: SerialBuffer>(&self, buffer: &mut T) {
         buffer.append_u8(Self::SEPARATOR);
         self.param_types.serialize(buffer);
-        self.ret_type.serialize(buffer);
+        self.ret_types.serialize(buffer);
     }
 }
 
-#[derive(Debug)]
-pub struct SignatureParamsIter<'a> {
+/// The parameter or return types of a signature in the TypeSection
+#[derive(Debug, Clone)]
+pub struct SignatureTypesIter<'a> {
     bytes: &'a [u8],
     index: usize,
     end: usize,
 }
 
-impl<'a> Iterator for SignatureParamsIter<'a> {
+impl<'a> Iterator for SignatureTypesIter<'a> {
     type Item = ValueType;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -240,9 +242,9 @@ impl<'a> Iterator for SignatureParamsIter<'a> {
     }
 }
 
-impl<'a> ExactSizeIterator for SignatureParamsIter<'a> {}
+impl<'a> ExactSizeIterator for SignatureTypesIter<'a> {}
 
-impl<'a> DoubleEndedIterator for SignatureParamsIter<'a> {
+impl<'a> DoubleEndedIterator for SignatureTypesIter<'a> {
     fn next_back(&mut self) -> Option<Self::Item> {
         if self.end == 0 {
             None
@@ -272,7 +274,10 @@ impl<'a> TypeSection<'a> {
 
     /// Find a matching signature or insert a new one. Return the index.
     pub fn insert(&mut self, signature: Signature<'a>) -> u32 {
-        let mut sig_bytes = Vec::with_capacity_in(signature.param_types.len() + 4, self.arena);
+        let mut sig_bytes = Vec::with_capacity_in(
+            signature.param_types.len() + signature.ret_types.len() + 4,
+            self.arena,
+        );
         signature.serialize(&mut sig_bytes);
 
         let sig_len = sig_bytes.len();
@@ -299,23 +304,28 @@ impl<'a> TypeSection<'a> {
         self.bytes.is_empty()
     }
 
-    pub fn look_up(&'a self, sig_index: u32) -> (SignatureParamsIter<'a>, Option<ValueType>) {
+    /// Number of signatures in the section
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub fn look_up(&'a self, sig_index: u32) -> (SignatureTypesIter<'a>, SignatureTypesIter<'a>) {
         let mut offset = self.offsets[sig_index as usize];
         offset += 1; // separator
-        let param_count = u32::parse((), &self.bytes, &mut offset).unwrap() as usize;
-        let params_iter = SignatureParamsIter {
-            bytes: &self.bytes[offset..][..param_count],
-            index: 0,
-            end: param_count,
-        };
-        offset += param_count;
+        let params_iter = self.types_iter(&mut offset);
+        let results_iter = self.types_iter(&mut offset);
+        (params_iter, results_iter)
+    }
 
-        let return_type = if self.bytes[offset] == 0 {
-            None
-        } else {
-            Some(ValueType::from(self.bytes[offset + 1]))
+    fn types_iter(&'a self, offset: &mut usize) -> SignatureTypesIter<'a> {
+        let count = u32::parse((), &self.bytes, offset).unwrap() as usize;
+        let iter = SignatureTypesIter {
+            bytes: &self.bytes[*offset..][..count],
+            index: 0,
+            end: count,
         };
-        (params_iter, return_type)
+        *offset += count;
+        iter
     }
 }
 
@@ -350,8 +360,8 @@ impl<'a> Parse<&'a Bump> for TypeSection<'a> {
             let n_params = u32::parse((), &bytes, &mut i).unwrap();
             i += n_params as usize; // skip over one byte per param type
 
-            let n_return_values = bytes[i];
-            i += 1 + n_return_values as usize;
+            let n_return_values = u32::parse((), &bytes, &mut i).unwrap();
+            i += n_return_values as usize;
         }
 
         Ok(TypeSection {
@@ -1898,15 +1908,19 @@ mod tests {
         let signatures = [
             Signature {
                 param_types: bumpalo::vec![in arena],
-                ret_type: None,
+                ret_types: bumpalo::vec![in arena],
             },
             Signature {
                 param_types: bumpalo::vec![in arena; I32, I64, F32, F64],
-                ret_type: None,
+                ret_types: bumpalo::vec![in arena],
             },
             Signature {
                 param_types: bumpalo::vec![in arena; I32, I32, I32],
-                ret_type: Some(I32),
+                ret_types: bumpalo::vec![in arena; I32],
+            },
+            Signature {
+                param_types: bumpalo::vec![in arena; I64],
+                ret_types: bumpalo::vec![in arena; I32, I32, F64],
             },
         ];
         let capacity = signatures.len();
@@ -1920,5 +1934,9 @@ mod tests {
             section.insert(sig);
         }
         test_assert_types_preload(arena, &section);
+
+        let (params, results) = section.look_up(3);
+        assert_eq!(params.collect::<std::vec::Vec<_>>(), [I64]);
+        assert_eq!(results.collect::<std::vec::Vec<_>>(), [I32, I32, F64]);
     }
 }
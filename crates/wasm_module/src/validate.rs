@@ -0,0 +1,953 @@
+use std::fmt;
+
+use crate::opcodes::{MemoryInstruction, OpCode, LOOKUP_TABLE};
+use crate::parse::{Parse, ParseError, SkipBytes};
+use crate::sections::{ConstExpr, GlobalType, ImportDesc};
+use crate::{ValueType, WasmModule};
+
+/// An invalid instruction in a function body, found by [WasmModule::validate]
+#[derive(Debug, PartialEq, Eq)]
+pub struct ValidationError {
+    pub fn_index: u32,
+    pub fn_name: Option<String>,
+    /// Byte offset of the instruction in the code section
+    pub offset: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid Wasm in function {}", self.fn_index)?;
+        if let Some(name) = &self.fn_name {
+            write!(f, " `{name}`")?;
+        }
+        write!(
+            f,
+            " at code section offset 0x{:x}: {}",
+            self.offset, self.message
+        )
+    }
+}
+
+/// Everything we need to know about the rest of the module to check a function body
+struct ModuleInfo {
+    signatures: Vec<(Vec<ValueType>, Vec<ValueType>)>,
+    fn_signatures: Vec<u32>,
+    globals: Vec<GlobalType>,
+    has_table: bool,
+    has_memory: bool,
+}
+
+impl ModuleInfo {
+    fn new(module: &WasmModule<'_>) -> Result<Self, String> {
+        let signatures = (0..module.types.len() as u32)
+            .map(|sig_index| {
+                let (params, results) = module.types.look_up(sig_index);
+                (params.collect(), results.collect())
+            })
+            .collect();
+
+        let mut fn_signatures = vec![];
+        let mut globals = vec![];
+        let mut has_table = !module.element.is_empty();
+        let mut has_memory = module.memory.count > 0;
+        for import in module.import.imports.iter() {
+            match import.description {
+                ImportDesc::Func { signature_index } => fn_signatures.push(signature_index),
+                ImportDesc::Table { .. } => has_table = true,
+                ImportDesc::Mem { .. } => has_memory = true,
+                ImportDesc::Global { ty } => globals.push(ty),
+            }
+        }
+        fn_signatures.extend(module.function.signatures.iter().copied());
+
+        let mut cursor = 0;
+        for _ in 0..module.global.count {
+            let ty = GlobalType::parse((), &module.global.bytes, &mut cursor)
+                .and_then(|ty| ConstExpr::skip_bytes(&module.global.bytes, &mut cursor).map(|_| ty))
+                .map_err(|e| format!("Invalid global section: {}", e.message))?;
+            globals.push(ty);
+        }
+
+        Ok(ModuleInfo {
+            signatures,
+            fn_signatures,
+            globals,
+            has_table,
+            has_memory,
+        })
+    }
+}
+
+/// Check every function body in the code section, following the validation algorithm from the
+/// appendix of the Wasm spec. Returns the first error found.
+pub(crate) fn validate_module(module: &WasmModule<'_>) -> Result<(), ValidationError> {
+    let code = &module.code;
+    let first_fn_index = module.import.function_count() as u32 + code.dead_import_dummy_count;
+    let fn_error = |fn_index: u32, offset: usize, message: String| ValidationError {
+        fn_index,
+        fn_name: module
+            .names
+            .function_names
+            .iter()
+            .find(|(i, _)| *i == fn_index)
+            .map(|(_, name)| name.to_string()),
+        offset,
+        message,
+    };
+
+    let info = ModuleInfo::new(module).map_err(|message| fn_error(first_fn_index, 0, message))?;
+
+    // Dead code elimination rewrites the code bytes without updating the other offsets,
+    // but the first function never moves. Serialization relies on that too.
+    let Some(&first_offset) = code.function_offsets.first() else {
+        return Ok(());
+    };
+    let mut cursor = first_offset as usize;
+    for fn_index in first_fn_index..first_fn_index + code.function_count {
+        let body_start = cursor;
+        let body_len = u32::parse((), &code.bytes, &mut cursor)
+            .map_err(|e| fn_error(fn_index, body_start, e.message))?;
+        let body_end = cursor + body_len as usize;
+        if body_end > code.bytes.len() {
+            let message = format!("Function body of {body_len} bytes runs past the code section");
+            return Err(fn_error(fn_index, body_start, message));
+        }
+
+        let mut validator = FunctionValidator {
+            info: &info,
+            bytes: &code.bytes[..body_end],
+            cursor,
+            op_offset: cursor,
+            locals: vec![],
+            operands: vec![],
+            frames: vec![],
+            ret_types: vec![],
+        };
+        validator
+            .validate(fn_index)
+            .map_err(|message| fn_error(fn_index, validator.op_offset, message))?;
+
+        cursor = body_end;
+    }
+
+    Ok(())
+}
+
+struct ControlFrame {
+    /// BLOCK, LOOP, IF or ELSE. The function body itself counts as a BLOCK.
+    opcode: OpCode,
+    results: Vec<ValueType>,
+    /// Operand stack height when the block started
+    height: usize,
+    /// The rest of the block is unreachable, so the operand stack is polymorphic
+    unreachable: bool,
+}
+
+struct FunctionValidator<'v> {
+    info: &'v ModuleInfo,
+    /// Code section bytes, ending where this function ends
+    bytes: &'v [u8],
+    cursor: usize,
+    /// Start of the current instruction, for error messages
+    op_offset: usize,
+    locals: Vec<ValueType>,
+    /// Types on the operand stack. `None` is a value of unknown type, in unreachable code.
+    operands: Vec<Option<ValueType>>,
+    frames: Vec<ControlFrame>,
+    ret_types: Vec<ValueType>,
+}
+
+impl FunctionValidator<'_> {
+    fn validate(&mut self, fn_index: u32) -> Result<(), String> {
+        let Some(&sig_index) = self.info.fn_signatures.get(fn_index as usize) else {
+            return Err("There's no function section entry for this function".into());
+        };
+        let (params, ret_types) = self.signature(sig_index)?;
+        self.locals.extend(params);
+        self.ret_types = ret_types.clone();
+
+        let local_group_count = self.read_u32()?;
+        for _ in 0..local_group_count {
+            let count = self.read_u32()?;
+            let ty = self.read_value_type()?;
+            self.locals.resize(self.locals.len() + count as usize, ty);
+        }
+
+        self.push_frame(OpCode::BLOCK, ret_types);
+        while !self.frames.is_empty() {
+            if self.cursor >= self.bytes.len() {
+                return Err("Function body ends without a final END instruction".into());
+            }
+            self.op_offset = self.cursor;
+            let byte = self.bytes[self.cursor];
+            let Some(op) = LOOKUP_TABLE[byte as usize] else {
+                return Err(format!("Unknown instruction byte 0x{byte:02x}"));
+            };
+            self.cursor += 1;
+            self.instruction(op)?;
+        }
+
+        if self.cursor != self.bytes.len() {
+            self.op_offset = self.cursor;
+            return Err("Found more code after the final END instruction".into());
+        }
+        Ok(())
+    }
+
+    fn instruction(&mut self, op: OpCode) -> Result<(), String> {
+        use OpCode::*;
+        use ValueType::*;
+
+        match op {
+            UNREACHABLE => self.set_unreachable(),
+            NOP => {}
+            BLOCK | LOOP => {
+                let results = self.read_block_type()?;
+                self.push_frame(op, results);
+            }
+            IF => {
+                let results = self.read_block_type()?;
+                self.pop_expect(I32)?;
+                self.push_frame(op, results);
+            }
+            ELSE => {
+                let frame = self.pop_frame()?;
+                if frame.opcode != IF {
+                    return Err(format!("ELSE without IF, in a {:?}", frame.opcode));
+                }
+                self.push_frame(ELSE, frame.results);
+            }
+            END => {
+                let frame = self.pop_frame()?;
+                if frame.opcode == IF && !frame.results.is_empty() {
+                    return Err("IF without ELSE can't return a value".into());
+                }
+                self.push_results(&frame.results);
+            }
+            BR => {
+                let depth = self.read_u32()?;
+                let label = self.label_types(depth)?;
+                self.pop_results(&label)?;
+                self.set_unreachable();
+            }
+            BRIF => {
+                let depth = self.read_u32()?;
+                self.pop_expect(I32)?;
+                let label = self.label_types(depth)?;
+                self.pop_results(&label)?;
+                self.push_results(&label);
+            }
+            BRTABLE => {
+                let count = self.read_u32()?;
+                let mut labels = vec![];
+                for _ in 0..count {
+                    let depth = self.read_u32()?;
+                    labels.push(self.label_types(depth)?);
+                }
+                let default_depth = self.read_u32()?;
+                let default_label = self.label_types(default_depth)?;
+                if let Some(label) = labels.iter().find(|l| **l != default_label) {
+                    return Err(format!(
+                        "BRTABLE targets have different types, {label:?} and {default_label:?}"
+                    ));
+                }
+                self.pop_expect(I32)?;
+                self.pop_results(&default_label)?;
+                self.set_unreachable();
+            }
+            RETURN => {
+                self.pop_results(&self.ret_types.clone())?;
+                self.set_unreachable();
+            }
+            CALL | RETURNCALL => {
+                let fn_index = self.read_u32()?;
+                let fn_count = self.info.fn_signatures.len();
+                let Some(&sig_index) = self.info.fn_signatures.get(fn_index as usize) else {
+                    return Err(format!(
+                        "Called function {fn_index} but there are only {fn_count} functions"
+                    ));
+                };
+                self.call(op, sig_index)?;
+            }
+            CALLINDIRECT | RETURNCALLINDIRECT => {
+                let sig_index = self.read_u32()?;
+                let table_index = self.read_u32()?;
+                if table_index != 0 || !self.info.has_table {
+                    return Err(format!(
+                        "{op:?} uses table {table_index}, which doesn't exist"
+                    ));
+                }
+                self.pop_expect(I32)?;
+                self.call(op, sig_index)?;
+            }
+            DROP => {
+                self.pop()?;
+            }
+            SELECT => {
+                self.pop_expect(I32)?;
+                let second = self.pop()?;
+                let first = self.pop()?;
+                match (first, second) {
+                    (Some(a), Some(b)) if a != b => {
+                        return Err(format!("SELECT between different types {a:?} and {b:?}"))
+                    }
+                    _ => self.operands.push(first.or(second)),
+                }
+            }
+            GETLOCAL => {
+                let ty = self.local(op)?;
+                self.push(ty);
+            }
+            SETLOCAL => {
+                let ty = self.local(op)?;
+                self.pop_expect(ty)?;
+            }
+            TEELOCAL => {
+                let ty = self.local(op)?;
+                self.pop_expect(ty)?;
+                self.push(ty);
+            }
+            GETGLOBAL => {
+                let global = self.global(op)?;
+                self.push(global.value_type);
+            }
+            SETGLOBAL => {
+                let global = self.global(op)?;
+                if !global.is_mutable {
+                    return Err("SETGLOBAL on an immutable global".into());
+                }
+                self.pop_expect(global.value_type)?;
+            }
+
+            I32LOAD => self.load(2, I32)?,
+            I64LOAD => self.load(3, I64)?,
+            F32LOAD => self.load(2, F32)?,
+            F64LOAD => self.load(3, F64)?,
+            I32LOAD8S | I32LOAD8U => self.load(0, I32)?,
+            I32LOAD16S | I32LOAD16U => self.load(1, I32)?,
+            I64LOAD8S | I64LOAD8U => self.load(0, I64)?,
+            I64LOAD16S | I64LOAD16U => self.load(1, I64)?,
+            I64LOAD32S | I64LOAD32U => self.load(2, I64)?,
+            I32STORE => self.store(2, I32)?,
+            I64STORE => self.store(3, I64)?,
+            F32STORE => self.store(2, F32)?,
+            F64STORE => self.store(3, F64)?,
+            I32STORE8 => self.store(0, I32)?,
+            I32STORE16 => self.store(1, I32)?,
+            I64STORE8 => self.store(0, I64)?,
+            I64STORE16 => self.store(1, I64)?,
+            I64STORE32 => self.store(2, I64)?,
+
+            CURRENTMEMORY => {
+                self.memory_index()?;
+                self.push(I32);
+            }
+            GROWMEMORY => {
+                self.memory_index()?;
+                self.pop_expect(I32)?;
+                self.push(I32);
+            }
+            MEMORY => {
+                let sub_op = self.read_u32()?;
+                match MemoryInstruction::try_from(sub_op as u8) {
+                    Ok(MemoryInstruction::MemoryCopy) => {
+                        self.memory_index()?;
+                        self.memory_index()?;
+                    }
+                    Ok(MemoryInstruction::MemoryFill) => {
+                        self.memory_index()?;
+                    }
+                    _ => return Err(format!("Unsupported memory instruction {sub_op}")),
+                }
+                self.pop_expect(I32)?;
+                self.pop_expect(I32)?;
+                self.pop_expect(I32)?;
+            }
+
+            I32CONST => {
+                self.read(i32::parse)?;
+                self.push(I32);
+            }
+            I64CONST => {
+                self.read(i64::parse)?;
+                self.push(I64);
+            }
+            F32CONST => {
+                self.skip(4)?;
+                self.push(F32);
+            }
+            F64CONST => {
+                self.skip(8)?;
+                self.push(F64);
+            }
+
+            _ => {
+                let (params, result) = numeric_signature(op)
+                    .ok_or_else(|| format!("Validation of {op:?} is not implemented"))?;
+                for param in params.iter().rev() {
+                    self.pop_expect(*param)?;
+                }
+                self.push(result);
+            }
+        }
+        Ok(())
+    }
+
+    fn signature(&self, sig_index: u32) -> Result<(Vec<ValueType>, Vec<ValueType>), String> {
+        match self.info.signatures.get(sig_index as usize) {
+            Some((params, results)) => Ok((params.clone(), results.clone())),
+            None => Err(format!(
+                "Signature {sig_index} is out of bounds. The type section has {} signatures.",
+                self.info.signatures.len()
+            )),
+        }
+    }
+
+    fn call(&mut self, op: OpCode, sig_index: u32) -> Result<(), String> {
+        let (params, results) = self.signature(sig_index)?;
+        for param in params.iter().rev() {
+            self.pop_expect(*param)?;
+        }
+        if matches!(op, OpCode::RETURNCALL | OpCode::RETURNCALLINDIRECT) {
+            // The callee's results are returned directly to our caller
+            if results != self.ret_types {
+                return Err(format!(
+                    "{op:?} to a function returning {results:?}, from a function returning {:?}",
+                    self.ret_types
+                ));
+            }
+            self.set_unreachable();
+        } else {
+            self.push_results(&results);
+        }
+        Ok(())
+    }
+
+    fn local(&mut self, op: OpCode) -> Result<ValueType, String> {
+        let index = self.read_u32()?;
+        self.locals.get(index as usize).copied().ok_or_else(|| {
+            format!(
+                "{op:?} uses local {index} but there are only {} locals",
+                self.locals.len()
+            )
+        })
+    }
+
+    fn global(&mut self, op: OpCode) -> Result<GlobalType, String> {
+        let index = self.read_u32()?;
+        self.info
+            .globals
+            .get(index as usize)
+            .copied()
+            .ok_or_else(|| {
+                format!(
+                    "{op:?} uses global {index} but there are only {} globals",
+                    self.info.globals.len()
+                )
+            })
+    }
+
+    fn memory_index(&mut self) -> Result<(), String> {
+        let index = self.read_u8()?;
+        if index != 0 || !self.info.has_memory {
+            return Err(format!("Memory {index} doesn't exist"));
+        }
+        Ok(())
+    }
+
+    /// Check the alignment & offset immediates of a load or store
+    fn mem_arg(&mut self, natural_align: u32) -> Result<(), String> {
+        let align = self.read_u32()?;
+        self.read_u32()?;
+        if !self.info.has_memory {
+            return Err("Memory access in a module without memory".into());
+        }
+        if align > natural_align {
+            return Err(format!(
+                "Alignment 2^{align} is larger than the natural alignment 2^{natural_align}"
+            ));
+        }
+        Ok(())
+    }
+
+    fn load(&mut self, natural_align: u32, ty: ValueType) -> Result<(), String> {
+        self.mem_arg(natural_align)?;
+        self.pop_expect(ValueType::I32)?;
+        self.push(ty);
+        Ok(())
+    }
+
+    fn store(&mut self, natural_align: u32, ty: ValueType) -> Result<(), String> {
+        self.mem_arg(natural_align)?;
+        self.pop_expect(ty)?;
+        self.pop_expect(ValueType::I32)?;
+        Ok(())
+    }
+
+    /*******************************************************************
+     * Operand stack & control frames
+     *******************************************************************/
+
+    fn push(&mut self, ty: ValueType) {
+        self.operands.push(Some(ty));
+    }
+
+    fn push_results(&mut self, results: &[ValueType]) {
+        for ty in results {
+            self.push(*ty);
+        }
+    }
+
+    fn pop(&mut self) -> Result<Option<ValueType>, String> {
+        let frame = self.frames.last().unwrap();
+        if self.operands.len() == frame.height {
+            if frame.unreachable {
+                Ok(None)
+            } else {
+                Err("Expected a value but the stack is empty".into())
+            }
+        } else {
+            Ok(self.operands.pop().unwrap())
+        }
+    }
+
+    fn pop_expect(&mut self, expected: ValueType) -> Result<(), String> {
+        match self.pop() {
+            Ok(Some(actual)) if actual != expected => Err(format!(
+                "Expected {expected:?} on the stack but found {actual:?}"
+            )),
+            Ok(_) => Ok(()),
+            Err(_) => Err(format!("Expected {expected:?} but the stack is empty")),
+        }
+    }
+
+    fn pop_results(&mut self, results: &[ValueType]) -> Result<(), String> {
+        for ty in results.iter().rev() {
+            self.pop_expect(*ty)?;
+        }
+        Ok(())
+    }
+
+    fn push_frame(&mut self, opcode: OpCode, results: Vec<ValueType>) {
+        self.frames.push(ControlFrame {
+            opcode,
+            results,
+            height: self.operands.len(),
+            unreachable: false,
+        });
+    }
+
+    fn pop_frame(&mut self) -> Result<ControlFrame, String> {
+        let results = self.frames.last().unwrap().results.clone();
+        self.pop_results(&results)?;
+        let frame = self.frames.pop().unwrap();
+        let extra = self.operands.len() - frame.height;
+        if extra > 0 {
+            return Err(format!(
+                "{:?} ends with {extra} extra value(s) on the stack",
+                frame.opcode
+            ));
+        }
+        Ok(frame)
+    }
+
+    /// The rest of the current block is unreachable, so it can pop values of any type
+    fn set_unreachable(&mut self) {
+        let frame = self.frames.last_mut().unwrap();
+        self.operands.truncate(frame.height);
+        frame.unreachable = true;
+    }
+
+    /// The types a branch to this label must provide
+    fn label_types(&self, depth: u32) -> Result<Vec<ValueType>, String> {
+        let frame_count = self.frames.len();
+        if depth as usize >= frame_count {
+            return Err(format!(
+                "Branch to depth {depth} but there are only {frame_count} enclosing blocks"
+            ));
+        }
+        let frame = &self.frames[frame_count - 1 - depth as usize];
+        if frame.opcode == OpCode::LOOP {
+            Ok(vec![])
+        } else {
+            Ok(frame.results.clone())
+        }
+    }
+
+    /*******************************************************************
+     * Reading immediates
+     *******************************************************************/
+
+    fn read<T>(
+        &mut self,
+        parse: fn((), &[u8], &mut usize) -> Result<T, ParseError>,
+    ) -> Result<T, String> {
+        if self.cursor >= self.bytes.len() {
+            return Err("Unexpected end of function body".into());
+        }
+        parse((), self.bytes, &mut self.cursor).map_err(|e| e.message)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, String> {
+        self.read(u8::parse)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, String> {
+        self.read(u32::parse)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), String> {
+        if self.cursor + len > self.bytes.len() {
+            return Err("Unexpected end of function body".into());
+        }
+        self.cursor += len;
+        Ok(())
+    }
+
+    fn read_value_type(&mut self) -> Result<ValueType, String> {
+        let byte = self.read_u8()?;
+        value_type(byte).ok_or_else(|| format!("Invalid value type 0x{byte:02x}"))
+    }
+
+    /// Block types can also be signature indices, with multi-value. We don't generate those.
+    fn read_block_type(&mut self) -> Result<Vec<ValueType>, String> {
+        let byte = self.read_u8()?;
+        if byte == ValueType::VOID {
+            Ok(vec![])
+        } else {
+            value_type(byte)
+                .map(|ty| vec![ty])
+                .ok_or_else(|| format!("Unsupported block type 0x{byte:02x}"))
+        }
+    }
+}
+
+fn value_type(byte: u8) -> Option<ValueType> {
+    match byte {
+        0x7f => Some(ValueType::I32),
+        0x7e => Some(ValueType::I64),
+        0x7d => Some(ValueType::F32),
+        0x7c => Some(ValueType::F64),
+        _ => None,
+    }
+}
+
+/// Parameter and result types for numeric instructions, which have no immediates
+fn numeric_signature(op: OpCode) -> Option<(&'static [ValueType], ValueType)> {
+    use OpCode::*;
+    use ValueType::*;
+
+    let signature: (&'static [ValueType], ValueType) = match op {
+        I32EQZ => (&[I32], I32),
+        I32EQ | I32NE | I32LTS | I32LTU | I32GTS | I32GTU | I32LES | I32LEU | I32GES | I32GEU => {
+            (&[I32, I32], I32)
+        }
+        I64EQZ => (&[I64], I32),
+        I64EQ | I64NE | I64LTS | I64LTU | I64GTS | I64GTU | I64LES | I64LEU | I64GES | I64GEU => {
+            (&[I64, I64], I32)
+        }
+        F32EQ | F32NE | F32LT | F32GT | F32LE | F32GE => (&[F32, F32], I32),
+        F64EQ | F64NE | F64LT | F64GT | F64LE | F64GE => (&[F64, F64], I32),
+
+        I32CLZ | I32CTZ | I32POPCNT | I32EXTEND8S | I32EXTEND16S => (&[I32], I32),
+        I32ADD | I32SUB | I32MUL | I32DIVS | I32DIVU | I32REMS | I32REMU | I32AND | I32OR
+        | I32XOR | I32SHL | I32SHRS | I32SHRU | I32ROTL | I32ROTR => (&[I32, I32], I32),
+        I64CLZ | I64CTZ | I64POPCNT | I64EXTEND8S | I64EXTEND16S | I64EXTEND32S => (&[I64], I64),
+        I64ADD | I64SUB | I64MUL | I64DIVS | I64DIVU | I64REMS | I64REMU | I64AND | I64OR
+        | I64XOR | I64SHL | I64SHRS | I64SHRU | I64ROTL | I64ROTR => (&[I64, I64], I64),
+        F32ABS | F32NEG | F32CEIL | F32FLOOR | F32TRUNC | F32NEAREST | F32SQRT => (&[F32], F32),
+        F32ADD | F32SUB | F32MUL | F32DIV | F32MIN | F32MAX | F32COPYSIGN => (&[F32, F32], F32),
+        F64ABS | F64NEG | F64CEIL | F64FLOOR | F64TRUNC | F64NEAREST | F64SQRT => (&[F64], F64),
+        F64ADD | F64SUB | F64MUL | F64DIV | F64MIN | F64MAX | F64COPYSIGN => (&[F64, F64], F64),
+
+        I32WRAPI64 => (&[I64], I32),
+        I32TRUNCSF32 | I32TRUNCUF32 | I32REINTERPRETF32 => (&[F32], I32),
+        I32TRUNCSF64 | I32TRUNCUF64 => (&[F64], I32),
+        I64EXTENDSI32 | I64EXTENDUI32 => (&[I32], I64),
+        I64TRUNCSF32 | I64TRUNCUF32 => (&[F32], I64),
+        I64TRUNCSF64 | I64TRUNCUF64 | I64REINTERPRETF64 => (&[F64], I64),
+        F32CONVERTSI32 | F32CONVERTUI32 | F32REINTERPRETI32 => (&[I32], F32),
+        F32CONVERTSI64 | F32CONVERTUI64 => (&[I64], F32),
+        F32DEMOTEF64 => (&[F64], F32),
+        F64CONVERTSI32 | F64CONVERTUI32 => (&[I32], F64),
+        F64CONVERTSI64 | F64CONVERTUI64 | F64REINTERPRETI64 => (&[I64], F64),
+        F64PROMOTEF32 => (&[F32], F64),
+
+        _ => return None,
+    };
+    Some(signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SerialBuffer, Signature};
+    use bumpalo::Bump;
+    use OpCode::*;
+
+    /// Build a module containing one function, named `test_fn`
+    fn module_with_function<'a>(
+        arena: &'a Bump,
+        params: &[ValueType],
+        ret_types: &[ValueType],
+        locals: &[(u32, ValueType)],
+        code: &[u8],
+    ) -> WasmModule<'a> {
+        let mut module = WasmModule::new(arena);
+        module.add_function_signature(Signature {
+            param_types: bumpalo::collections::Vec::from_iter_in(params.iter().copied(), arena),
+            ret_types: bumpalo::collections::Vec::from_iter_in(ret_types.iter().copied(), arena),
+        });
+
+        let buf = &mut module.code.bytes;
+        let len_index = buf.encode_padded_u32(0);
+        let start = buf.len();
+        buf.encode_u32(locals.len() as u32);
+        for (count, ty) in locals {
+            buf.encode_u32(*count);
+            buf.append_u8(*ty as u8);
+        }
+        buf.append_slice(code);
+        buf.overwrite_padded_u32(len_index, (buf.len() - start) as u32);
+        module.code.function_count = 1;
+        module.code.function_offsets.push(0);
+        module.names.append_function(0, "test_fn");
+        module
+    }
+
+    fn validate(
+        params: &[ValueType],
+        ret_types: &[ValueType],
+        locals: &[(u32, ValueType)],
+        code: &[u8],
+    ) -> Result<(), ValidationError> {
+        let arena = Bump::new();
+        let module = module_with_function(&arena, params, ret_types, locals, code);
+        module.validate()
+    }
+
+    fn expect_error(result: Result<(), ValidationError>, offset: usize, message: &str) {
+        let error = result.unwrap_err();
+        assert_eq!(error.fn_index, 0);
+        assert_eq!(error.fn_name.as_deref(), Some("test_fn"));
+        // The function starts with 5 bytes of padded length and 1 or 2 bytes of local declarations
+        assert_eq!(error.offset, offset, "{error}");
+        assert_eq!(error.message, message);
+    }
+
+    #[test]
+    fn test_valid_function() {
+        use ValueType::*;
+        #[rustfmt::skip]
+        let code = [
+            GETLOCAL as u8, 0,
+            IF as u8, I32 as u8,
+                I64CONST as u8, 42,
+                I32WRAPI64 as u8,
+            ELSE as u8,
+                GETLOCAL as u8, 1,
+            END as u8,
+            TEELOCAL as u8, 1,
+            BLOCK as u8, ValueType::VOID,
+                LOOP as u8, ValueType::VOID,
+                    GETLOCAL as u8, 1,
+                    BRIF as u8, 1,
+                    BR as u8, 0,
+                END as u8,
+            END as u8,
+            END as u8,
+        ];
+        assert_eq!(validate(&[I32], &[I32], &[(1, I32)], &code), Ok(()));
+    }
+
+    #[test]
+    fn test_unreachable_code_can_pop_anything() {
+        use ValueType::*;
+        let code = [UNREACHABLE as u8, F64ADD as u8, DROP as u8, END as u8];
+        assert_eq!(validate(&[], &[I32], &[], &code), Ok(()));
+
+        let code = [
+            GETLOCAL as u8,
+            0,
+            RETURN as u8,
+            I32ADD as u8,
+            DROP as u8,
+            END as u8,
+        ];
+        assert_eq!(validate(&[I64], &[I64], &[], &code), Ok(()));
+    }
+
+    #[test]
+    fn test_type_mismatch() {
+        use ValueType::*;
+        let code = [
+            I32CONST as u8,
+            1,
+            I64CONST as u8,
+            2,
+            I32ADD as u8,
+            END as u8,
+        ];
+        let result = validate(&[], &[I32], &[], &code);
+        expect_error(result, 10, "Expected I32 on the stack but found I64");
+    }
+
+    #[test]
+    fn test_wrong_return_type() {
+        use ValueType::*;
+        let code = [F32CONST as u8, 0, 0, 0, 0, END as u8];
+        let result = validate(&[], &[I32], &[], &code);
+        expect_error(result, 11, "Expected I32 on the stack but found F32");
+    }
+
+    #[test]
+    fn test_unbalanced_block() {
+        use ValueType::*;
+        let code = [
+            BLOCK as u8,
+            ValueType::VOID,
+            I32CONST as u8,
+            1,
+            END as u8,
+            END as u8,
+        ];
+        let result = validate(&[], &[], &[], &code);
+        expect_error(result, 10, "BLOCK ends with 1 extra value(s) on the stack");
+
+        let code = [
+            I32CONST as u8,
+            1,
+            IF as u8,
+            I32 as u8,
+            I32CONST as u8,
+            2,
+            END as u8,
+            END as u8,
+        ];
+        let result = validate(&[], &[I32], &[], &code);
+        expect_error(result, 12, "IF without ELSE can't return a value");
+
+        let code = [BLOCK as u8, ValueType::VOID, END as u8];
+        let result = validate(&[], &[], &[], &code);
+        expect_error(
+            result,
+            8,
+            "Function body ends without a final END instruction",
+        );
+    }
+
+    #[test]
+    fn test_index_bounds() {
+        use ValueType::*;
+        let code = [GETLOCAL as u8, 2, DROP as u8, END as u8];
+        let result = validate(&[I32], &[], &[(1, F64)], &code);
+        expect_error(
+            result,
+            8,
+            "GETLOCAL uses local 2 but there are only 2 locals",
+        );
+
+        let code = [GETGLOBAL as u8, 0, DROP as u8, END as u8];
+        let result = validate(&[], &[], &[], &code);
+        expect_error(
+            result,
+            6,
+            "GETGLOBAL uses global 0 but there are only 0 globals",
+        );
+
+        let code = [CALL as u8, 1, END as u8];
+        let result = validate(&[], &[], &[], &code);
+        expect_error(
+            result,
+            6,
+            "Called function 1 but there are only 1 functions",
+        );
+
+        let code = [I32CONST as u8, 0, CALLINDIRECT as u8, 0, 0, END as u8];
+        let result = validate(&[], &[], &[], &code);
+        expect_error(result, 8, "CALLINDIRECT uses table 0, which doesn't exist");
+
+        let code = [BR as u8, 1, END as u8];
+        let result = validate(&[], &[], &[], &code);
+        expect_error(
+            result,
+            6,
+            "Branch to depth 1 but there are only 1 enclosing blocks",
+        );
+    }
+
+    #[test]
+    fn test_multi_value() {
+        use ValueType::*;
+        #[rustfmt::skip]
+        let code = [
+            GETLOCAL as u8, 0,
+            I64CONST as u8, 1,
+            CALL as u8, 0,
+            RETURN as u8,
+            END as u8,
+        ];
+        assert_eq!(validate(&[I32, I64], &[I32, I64], &[], &code), Ok(()));
+
+        let code = [GETLOCAL as u8, 0, END as u8];
+        let result = validate(&[I32, I64], &[I32, I64], &[], &code);
+        expect_error(result, 8, "Expected I64 on the stack but found I32");
+    }
+
+    #[test]
+    fn test_tail_calls() {
+        use ValueType::*;
+        #[rustfmt::skip]
+        let code = [
+            GETLOCAL as u8, 0,
+            RETURNCALL as u8, 0,
+            I32ADD as u8, // unreachable, so it can pop anything
+            END as u8,
+        ];
+        assert_eq!(validate(&[I32], &[I32], &[], &code), Ok(()));
+
+        // The callee must return the same types as the caller
+        let arena = Bump::new();
+        let code = [I64CONST as u8, 0, RETURNCALL as u8, 0, END as u8];
+        let mut module = module_with_function(&arena, &[], &[I64], &[], &code);
+        let import_sig = module.types.insert(Signature {
+            param_types: bumpalo::vec![in &arena; I64],
+            ret_types: bumpalo::vec![in &arena; I32],
+        });
+        module.import.imports.push(crate::sections::Import {
+            module: "env",
+            name: "f",
+            description: ImportDesc::Func {
+                signature_index: import_sig,
+            },
+        });
+        let error = module.validate().unwrap_err();
+        assert_eq!(error.fn_index, 1);
+        assert_eq!(
+            error.message,
+            "RETURNCALL to a function returning [I32], from a function returning [I64]"
+        );
+    }
+
+    #[test]
+    fn test_dead_code_elimination_keeps_module_valid() {
+        use ValueType::*;
+        let arena = Bump::new();
+        let code = [I32CONST as u8, 1, END as u8];
+        let mut module = module_with_function(&arena, &[], &[I32], &[], &code);
+
+        // The code section of a parsed module starts with the function count
+        let mut bytes = bumpalo::vec![in &arena];
+        bytes.encode_u32(1);
+        bytes.extend_from_slice(&module.code.bytes);
+        module.code.bytes = bytes;
+        module.code.function_offsets[0] = 1;
+        module.validate().unwrap();
+
+        // No functions are called, so DCE replaces this one with a dummy
+        let called_fns = bitvec::bitvec![0; 1];
+        module.eliminate_dead_code(&arena, called_fns);
+        module.validate().unwrap();
+    }
+}